@@ -36,14 +36,22 @@ void myst_dump_argv(int argc, const char* argv[]);
 
 static void _create_itimer_thread(void);
 
+static void _create_worker_thread(void);
+
 long myst_syscall(long n, long params[6])
 {
     static pthread_once_t _once = PTHREAD_ONCE_INIT;
+    static pthread_once_t _worker_once = PTHREAD_ONCE_INIT;
 
-    /* create the itimer thread on demand (only if needed) */
-    if (n == SYS_setitimer)
+    /* create the itimer thread (which runs all the timers) on demand */
+    if (n == SYS_setitimer || n == SYS_timer_create ||
+        n == SYS_timerfd_settime)
         pthread_once(&_once, _create_itimer_thread);
 
+    /* and the worker thread (which refreshes the quote cache) */
+    if (n == SYS_myst_gen_creds)
+        pthread_once(&_worker_once, _create_worker_thread);
+
     return (*_syscall_callback)(n, params);
 }
 
@@ -195,3 +203,33 @@ static void _create_itimer_thread(void)
         abort();
     }
 }
+
+static void* _worker_thread(void* arg)
+{
+    (void)arg;
+
+    /* Run the slow background work of the kernel, if there is any (or else
+     * return at once, ending the thread) */
+    long params[6] = {0};
+    myst_syscall(SYS_myst_run_work, params);
+
+    return NULL;
+}
+
+// Create the worker thread as the itimer thread above. The quote cache does
+// without it (a request that finds the credentials expired generates them),
+// so failing to create it (when the enclave has no thread to spare) is not
+// an error.
+static void _create_worker_thread(void)
+{
+    pthread_attr_t attr;
+    pthread_t thread;
+
+    if (pthread_attr_init(&attr) != 0)
+        return;
+
+    if (pthread_attr_setdetachstate(&attr, PTHREAD_CREATE_DETACHED) == 0)
+        pthread_create(&thread, &attr, _worker_thread, NULL);
+
+    pthread_attr_destroy(&attr);
+}
@@ -13,7 +13,10 @@
 #include <myst/eraise.h>
 #include <myst/ext2.h>
 #include <myst/hex.h>
+#include <myst/ioctl.h>
 #include <myst/paths.h>
+#include <myst/perms.h>
+#include <myst/realpath.h>
 #include <myst/round.h>
 #include <myst/strings.h>
 #include "ext2common.h"
@@ -1106,6 +1109,95 @@ typedef enum follow
     FOLLOW = 1,
 } follow_t;
 
+/* find the directory that is the root of the calling process (see chroot)
+ * within this file system, or the root of the file system when the process
+ * is not confined to it. The root has no symbolic links so its components
+ * are not followed.
+ */
+static int _get_process_root(
+    ext2_t* ext2,
+    ext2_ino_t* ino_out,
+    char realpath[PATH_MAX])
+{
+    int ret = 0;
+    myst_path_t* root = NULL;
+    ext2_ino_t ino = EXT2_ROOT_INO;
+    char* p;
+    char* save;
+
+    *ino_out = 0;
+
+    if (!(root = malloc(sizeof(myst_path_t))))
+        ERAISE(-ENOMEM);
+
+    ECHECK(myst_realpath_fs_root(ext2->target, root));
+
+    if (realpath)
+    {
+        *realpath = '\0';
+
+        if (strcmp(root->buf, "/") != 0 &&
+            myst_strlcpy(realpath, root->buf, PATH_MAX) >= PATH_MAX)
+        {
+            ERAISE_QUIET(-ENAMETOOLONG);
+        }
+    }
+
+    p = strtok_r(root->buf, "/", &save);
+
+    for (; p; p = strtok_r(NULL, "/", &save))
+    {
+        ext2_dirent_t ent;
+
+        ECHECK_QUIET(_load_dirent(ext2, ino, p, &ent));
+
+        if (ent.file_type != EXT2_FT_DIR)
+            ERAISE_QUIET(-ENOTDIR);
+
+        ino = ent.inode;
+    }
+
+    *ino_out = ino;
+
+done:
+
+    if (root)
+        free(root);
+
+    return ret;
+}
+
+/* whether dir_ino is the root directory of the calling process, so that ".."
+ * in a symbolic link stays there instead of leaving the root.
+ */
+static int _is_process_root(ext2_t* ext2, ext2_ino_t dir_ino, bool* result)
+{
+    int ret = 0;
+    myst_path_t* root = NULL;
+    ext2_ino_t ino;
+
+    *result = false;
+
+    if (!(root = malloc(sizeof(myst_path_t))))
+        ERAISE(-ENOMEM);
+
+    ECHECK(myst_realpath_fs_root(ext2->target, root));
+
+    /* not confined to this file system */
+    if (*root->buf == '\0')
+        goto done;
+
+    if (_get_process_root(ext2, &ino, NULL) == 0)
+        *result = (ino == dir_ino);
+
+done:
+
+    if (root)
+        free(root);
+
+    return ret;
+}
+
 static int _path_to_ino_recursive(
     ext2_t* ext2,
     const char* path,
@@ -1154,6 +1246,17 @@ static int _path_to_ino_recursive(
     {
         ext2_dirent_t ent;
         ext2_ino_t ino;
+        bool clamp = false;
+
+        /* ".." does not climb above the root of the process */
+        if (strcmp(toks[i], "..") == 0)
+            ECHECK(_is_process_root(ext2, current_ino, &clamp));
+
+        if (clamp)
+        {
+            previous_ino = current_ino;
+            continue;
+        }
 
         ECHECK(_load_dirent(ext2, current_ino, toks[i], &ent));
         assert(ent.inode != 0);
@@ -1166,6 +1269,7 @@ static int _path_to_ino_recursive(
             if (i + 1 != ntoks || follow == FOLLOW)
             {
                 char target[EXT2_PATH_MAX];
+                const char* next = target;
 
                 /* load the target from the symlink */
                 ECHECK((_load_file_by_ino(ext2, ino, &data, &size)));
@@ -1200,14 +1304,15 @@ static int _path_to_ino_recursive(
                     }
                     else
                     {
-                        *realpath = '\0';
-                        current_ino = EXT2_ROOT_INO;
+                        /* resolve against the root of the process */
+                        ECHECK(_get_process_root(ext2, &current_ino, realpath));
+                        next = target + strspn(target, "/");
                     }
                 }
 
                 ECHECK(_path_to_ino_recursive(
                     ext2,
-                    target,
+                    next,
                     current_ino,
                     FOLLOW,
                     &current_ino,
@@ -3097,6 +3202,11 @@ int ext2_open(
         _dirent_init(&ent, ino, EXT2_FT_REG_FILE, filename);
         ECHECK(_add_dirent(ext2, dino, &dinode, filename, &ent));
     }
+    else
+    {
+        const int want = myst_perms_open_access(flags);
+        ECHECK(myst_perms_check(inode.i_mode, inode.i_uid, inode.i_gid, want));
+    }
 
     if (S_ISLNK(inode.i_mode) && (flags & O_NOFOLLOW))
         ERAISE(-ELOOP);
@@ -3442,14 +3552,7 @@ int ext2_access(myst_fs_t* fs, const char* pathname, int mode)
     if (mode == F_OK)
         goto done;
 
-    if ((mode & R_OK) && !(inode.i_mode & S_IRUSR))
-        ERAISE(-EACCES);
-
-    if ((mode & W_OK) && !(inode.i_mode & S_IWUSR))
-        ERAISE(-EACCES);
-
-    if ((mode & X_OK) && !(inode.i_mode & S_IXUSR))
-        ERAISE(-EACCES);
+    ECHECK(myst_perms_check(inode.i_mode, inode.i_uid, inode.i_gid, mode));
 
 done:
     return ret;
@@ -4495,15 +4598,13 @@ static int _ext2_ioctl(
     ext2_t* ext2 = (ext2_t*)fs;
     int ret = 0;
 
+    (void)request;
     (void)arg;
 
     if (!_ext2_valid(ext2) || !_file_valid(file))
         ERAISE(-EBADF);
 
-    if (request == TIOCGWINSZ)
-        ERAISE(-EINVAL);
-
-    ERAISE(-ENOTSUP);
+    ERAISE(-ENOTTY);
 
 done:
 
@@ -4705,6 +4806,166 @@ done:
     return ret;
 }
 
+/* no requests: each fails with ENOTTY */
+static const myst_ioctl_table_t _ioctls = MYST_IOCTL_TABLE_NONE("ext2");
+
+static int _chmod(
+    ext2_t* ext2,
+    ext2_ino_t ino,
+    ext2_inode_t* inode,
+    mode_t mode)
+{
+    int ret = 0;
+
+    ECHECK(myst_perms_check_chmod(inode->i_uid));
+
+    inode->i_mode = (uint16_t)((inode->i_mode & S_IFMT) | (mode & 07777));
+    _update_timestamps(inode, CHANGE);
+    ECHECK(_write_inode(ext2, ino, inode));
+
+done:
+    return ret;
+}
+
+static int _chown(
+    ext2_t* ext2,
+    ext2_ino_t ino,
+    ext2_inode_t* inode,
+    uid_t owner,
+    gid_t group)
+{
+    int ret = 0;
+
+    /* the inode only has room for the low 16 bits of the IDs */
+    if ((owner != (uid_t)-1 && owner > UINT16_MAX) ||
+        (group != (gid_t)-1 && group > UINT16_MAX))
+    {
+        ERAISE(-EINVAL);
+    }
+
+    ECHECK(myst_perms_check_chown(inode->i_uid, inode->i_gid, owner, group));
+
+    if (owner != (uid_t)-1)
+        inode->i_uid = (uint16_t)owner;
+
+    if (group != (gid_t)-1)
+        inode->i_gid = (uint16_t)group;
+
+    if (!S_ISLNK(inode->i_mode))
+        inode->i_mode = (uint16_t)myst_perms_chown_mode(inode->i_mode);
+
+    _update_timestamps(inode, CHANGE);
+    ECHECK(_write_inode(ext2, ino, inode));
+
+done:
+    return ret;
+}
+
+static int _ext2_chmod(myst_fs_t* fs, const char* pathname, mode_t mode)
+{
+    int ret = 0;
+    ext2_t* ext2 = (ext2_t*)fs;
+    ext2_ino_t ino;
+    ext2_inode_t inode;
+    char suffix[PATH_MAX];
+    myst_fs_t* tfs = NULL;
+
+    if (!_ext2_valid(ext2) || !pathname)
+        ERAISE(-EINVAL);
+
+    ECHECK(_path_to_inode(
+        ext2, pathname, FOLLOW, NULL, &ino, NULL, &inode, suffix, &tfs));
+    if (tfs)
+    {
+        /* delegate operation to target filesystem */
+        if (!tfs->fs_chmod)
+            ERAISE(-EPERM);
+
+        ECHECK(tfs->fs_chmod(tfs, suffix, mode));
+        goto done;
+    }
+
+    ECHECK(_chmod(ext2, ino, &inode, mode));
+
+done:
+    return ret;
+}
+
+static int _ext2_fchmod(myst_fs_t* fs, myst_file_t* file, mode_t mode)
+{
+    int ret = 0;
+    ext2_t* ext2 = (ext2_t*)fs;
+
+    if (!_ext2_valid(ext2) || !_file_valid(file))
+        ERAISE(-EINVAL);
+
+    ECHECK(_chmod(ext2, file->ino, &file->inode, mode));
+
+done:
+    return ret;
+}
+
+static int _ext2_chown(
+    myst_fs_t* fs,
+    const char* pathname,
+    bool follow,
+    uid_t owner,
+    gid_t group)
+{
+    int ret = 0;
+    ext2_t* ext2 = (ext2_t*)fs;
+    ext2_ino_t ino;
+    ext2_inode_t inode;
+    char suffix[PATH_MAX];
+    myst_fs_t* tfs = NULL;
+
+    if (!_ext2_valid(ext2) || !pathname)
+        ERAISE(-EINVAL);
+
+    ECHECK(_path_to_inode(
+        ext2,
+        pathname,
+        follow ? FOLLOW : NOFOLLOW,
+        NULL,
+        &ino,
+        NULL,
+        &inode,
+        suffix,
+        &tfs));
+    if (tfs)
+    {
+        /* delegate operation to target filesystem */
+        if (!tfs->fs_chown)
+            ERAISE(-EPERM);
+
+        ECHECK(tfs->fs_chown(tfs, suffix, follow, owner, group));
+        goto done;
+    }
+
+    ECHECK(_chown(ext2, ino, &inode, owner, group));
+
+done:
+    return ret;
+}
+
+static int _ext2_fchown(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    uid_t owner,
+    gid_t group)
+{
+    int ret = 0;
+    ext2_t* ext2 = (ext2_t*)fs;
+
+    if (!_ext2_valid(ext2) || !_file_valid(file))
+        ERAISE(-EINVAL);
+
+    ECHECK(_chown(ext2, file->ino, &file->inode, owner, group));
+
+done:
+    return ret;
+}
+
 static myst_fs_t _base = {
     {
         .fd_read = (void*)ext2_read,
@@ -4714,6 +4975,7 @@ static myst_fs_t _base = {
         .fd_fstat = (void*)ext2_fstat,
         .fd_fcntl = (void*)_ext2_fcntl,
         .fd_ioctl = (void*)_ext2_ioctl,
+        .fd_ioctls = &_ioctls,
         .fd_dup = (void*)_ext2_dup,
         .fd_close = (void*)ext2_close,
         .fd_target_fd = (void*)_ext2_target_fd,
@@ -4754,6 +5016,10 @@ static myst_fs_t _base = {
     .fs_statfs = _ext2_statfs,
     .fs_fstatfs = _ext2_fstatfs,
     .fs_futimens = _ext2_futimens,
+    .fs_chmod = _ext2_chmod,
+    .fs_fchmod = _ext2_fchmod,
+    .fs_chown = _ext2_chown,
+    .fs_fchown = _ext2_fchown,
 };
 
 int ext2_create(
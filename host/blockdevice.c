@@ -5,6 +5,7 @@
 #include <fcntl.h>
 #include <stdbool.h>
 #include <string.h>
+#include <sys/stat.h>
 #include <unistd.h>
 
 #include <myst/blockdevice.h>
@@ -143,3 +144,37 @@ int myst_read_block_device(
 done:
     return ret;
 }
+
+int myst_get_block_device_size(int blkdev, uint64_t* size)
+{
+    int ret = 0;
+    struct stat st;
+
+    if (blkdev < 0 || !size)
+        ERAISE(-EINVAL);
+
+    if (fstat(blkdev, &st) != 0)
+        ERAISE(-errno);
+
+    if (!S_ISREG(st.st_mode) || st.st_size < 0)
+        ERAISE(-EINVAL);
+
+    *size = (uint64_t)st.st_size;
+
+done:
+    return ret;
+}
+
+int myst_sync_block_device(int blkdev)
+{
+    int ret = 0;
+
+    if (blkdev < 0)
+        ERAISE(-EINVAL);
+
+    if (fsync(blkdev) != 0)
+        ERAISE(-errno);
+
+done:
+    return ret;
+}
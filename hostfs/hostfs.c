@@ -14,8 +14,12 @@
 #include <myst/eraise.h>
 #include <myst/fdtable.h>
 #include <myst/fs.h>
+#include <myst/ioctl.h>
 #include <myst/iov.h>
+#include <myst/manifest.h>
+#include <myst/printf.h>
 #include <myst/realpath.h>
+#include <myst/sha256.h>
 #include <myst/strings.h>
 #include <myst/syscall.h>
 #include <myst/tcall.h>
@@ -38,6 +42,7 @@ typedef struct hostfs
     uint64_t magic;
     char source[PATH_MAX]; /* source argument to myst_mount() */
     char target[PATH_MAX]; /* target argument to myst_mount() */
+    myst_manifest_t* manifest; /* if set, files are verified on open */
 } hostfs_t;
 
 static bool _hostfs_valid(const hostfs_t* hostfs)
@@ -97,6 +102,13 @@ struct myst_file
     uint64_t magic;
     char realpath[PATH_MAX];
     int fd;
+
+    /* the contents of a file that matched the manifest (reads are served
+     * from this copy so that the host cannot change the file afterwards) */
+    bool verified;
+    uint8_t* data;
+    size_t size;
+    off_t offset;
 };
 
 static bool _file_valid(const myst_file_t* file)
@@ -104,6 +116,156 @@ static bool _file_valid(const myst_file_t* file)
     return file && file->magic == FILE_MAGIC;
 }
 
+static void _free_file(myst_file_t* file)
+{
+    if (file->data)
+        free(file->data);
+
+    memset(file, 0xdd, sizeof(myst_file_t));
+    free(file);
+}
+
+/*
+**==============================================================================
+**
+** manifest verification:
+**
+** When the mount has a manifest, the mount is read-only and only files listed
+** in the manifest may be opened (directories may be opened to list them). On
+** open, the whole file is read into enclave memory and its SHA-256 hash is
+** compared with the manifest.
+**
+**==============================================================================
+*/
+
+#define VERIFY_CHUNK_SIZE (64 * 1024)
+
+static int _read_host_file(int fd, uint8_t** data_out, size_t* size_out)
+{
+    int ret = 0;
+    uint8_t* data = NULL;
+    size_t size = 0;
+    size_t capacity = 0;
+
+    for (;;)
+    {
+        long tret;
+
+        if (size == capacity)
+        {
+            uint8_t* p;
+
+            if (capacity > SIZE_MAX - VERIFY_CHUNK_SIZE)
+                ERAISE(-EFBIG);
+
+            capacity += VERIFY_CHUNK_SIZE;
+
+            if (!(p = realloc(data, capacity)))
+                ERAISE(-ENOMEM);
+
+            data = p;
+        }
+
+        long params[6] = {fd, (long)(data + size), capacity - size, size};
+        ECHECK((tret = myst_tcall(SYS_pread64, params)));
+
+        if (tret == 0)
+            break;
+
+        /* do not trust the host to honor the length */
+        if ((size_t)tret > capacity - size)
+            ERAISE(-EIO);
+
+        size += tret;
+    }
+
+    *data_out = data;
+    data = NULL;
+    *size_out = size;
+
+done:
+
+    if (data)
+        free(data);
+
+    return ret;
+}
+
+static int _verify_file(hostfs_t* hostfs, myst_file_t* file)
+{
+    int ret = 0;
+    myst_sha256_t expected;
+    myst_sha256_t actual;
+    struct stat statbuf;
+    int r;
+
+    if ((r = myst_manifest_find(hostfs->manifest, file->realpath, &expected)))
+    {
+        if (r != -ENOENT)
+            ERAISE(r);
+
+        /* directory listings are left to the host */
+        long params[6] = {file->fd, (long)&statbuf};
+        ECHECK(myst_tcall(SYS_fstat, params));
+
+        if (!S_ISDIR(statbuf.st_mode))
+            ERAISE(-EACCES);
+
+        goto done;
+    }
+
+    ECHECK(_read_host_file(file->fd, &file->data, &file->size));
+    ECHECK(myst_sha256(&actual, file->data, file->size));
+
+    if (memcmp(&actual, &expected, sizeof(expected)) != 0)
+    {
+        myst_eprintf(
+            "kernel: hostfs: manifest mismatch: %s\n", file->realpath);
+        ERAISE(-EIO);
+    }
+
+    file->verified = true;
+
+done:
+    return ret;
+}
+
+/* copy from the verified contents */
+static size_t _copy_verified(
+    const myst_file_t* file,
+    void* buf,
+    size_t count,
+    off_t offset)
+{
+    size_t n;
+
+    if ((size_t)offset >= file->size)
+        return 0;
+
+    n = file->size - (size_t)offset;
+
+    if (n > count)
+        n = count;
+
+    memcpy(buf, file->data + offset, n);
+
+    return n;
+}
+
+int myst_hostfs_set_manifest(myst_fs_t* fs, myst_manifest_t* manifest)
+{
+    int ret = 0;
+    hostfs_t* hostfs = (hostfs_t*)fs;
+
+    if (!_hostfs_valid(hostfs) || !manifest || hostfs->manifest)
+        ERAISE(-EINVAL);
+
+    hostfs->manifest = manifest;
+
+done:
+    return ret;
+}
+
 /*
 **==============================================================================
 **
@@ -120,6 +282,8 @@ static int _fs_release(myst_fs_t* fs)
     if (!_hostfs_valid(hostfs))
         ERAISE(-EINVAL);
 
+    myst_manifest_free(hostfs->manifest);
+
     memset(hostfs, 0xdd, sizeof(hostfs_t));
     free(hostfs);
 
@@ -162,9 +326,17 @@ static int _fs_open(
     if (!_hostfs_valid(hostfs) || !pathname || !file_out)
         ERAISE(-EINVAL);
 
+    if (hostfs->manifest)
+    {
+        if ((flags & O_ACCMODE) != O_RDONLY || (flags & (O_CREAT | O_TRUNC)))
+            ERAISE(-EROFS);
+    }
+
     if (!(file = calloc(1, sizeof(myst_file_t))))
         ERAISE(-ENOMEM);
 
+    file->fd = -1;
+
     ECHECK(myst_realpath(pathname, (myst_path_t*)file->realpath));
 
     ECHECK(_to_host_path(hostfs, path, sizeof(path), pathname));
@@ -178,6 +350,9 @@ static int _fs_open(
     file->magic = FILE_MAGIC;
     file->fd = (int)tret;
 
+    if (hostfs->manifest)
+        ECHECK(_verify_file(hostfs, file));
+
     *file_out = file;
     file = NULL;
     /* hostfs does not delegate the open operation */
@@ -186,7 +361,15 @@ static int _fs_open(
 done:
 
     if (file)
-        free(file);
+    {
+        if (file->fd >= 0)
+        {
+            long params[6] = {file->fd};
+            myst_tcall(SYS_close, params);
+        }
+
+        _free_file(file);
+    }
 
     return ret;
 }
@@ -215,6 +398,33 @@ static off_t _fs_lseek(
     if (!_hostfs_valid(hostfs) || !_file_valid(file))
         ERAISE(-EINVAL);
 
+    if (file->verified)
+    {
+        off_t new_offset;
+
+        switch (whence)
+        {
+            case SEEK_SET:
+                new_offset = offset;
+                break;
+            case SEEK_CUR:
+                new_offset = file->offset + offset;
+                break;
+            case SEEK_END:
+                new_offset = (off_t)file->size + offset;
+                break;
+            default:
+                ERAISE(-EINVAL);
+        }
+
+        if (new_offset < 0)
+            ERAISE(-EINVAL);
+
+        file->offset = new_offset;
+        ret = new_offset;
+        goto done;
+    }
+
     long params[6] = {file->fd, offset, whence};
     ECHECK((tret = myst_tcall(SYS_lseek, params)));
 
@@ -240,6 +450,13 @@ static ssize_t _fs_read(
     if (!buf && count)
         ERAISE(-EINVAL);
 
+    if (file->verified)
+    {
+        ret = _copy_verified(file, buf, count, file->offset);
+        file->offset += ret;
+        goto done;
+    }
+
     long params[6] = {file->fd, (long)buf, count};
     ECHECK((tret = myst_tcall(SYS_read, params)));
 
@@ -265,6 +482,10 @@ static ssize_t _fs_write(
     if (!buf && count)
         ERAISE(-EINVAL);
 
+    /* files on a mount with a manifest are only opened for reading */
+    if (hostfs->manifest)
+        ERAISE(-EBADF);
+
     long params[6] = {file->fd, (long)buf, count};
     ECHECK((tret = myst_tcall(SYS_write, params)));
 
@@ -291,6 +512,15 @@ static ssize_t _fs_pread(
     if (!buf && count)
         ERAISE(-EINVAL);
 
+    if (offset < 0)
+        ERAISE(-EINVAL);
+
+    if (file->verified)
+    {
+        ret = _copy_verified(file, buf, count, offset);
+        goto done;
+    }
+
     long params[6] = {file->fd, (long)buf, count, offset};
     ECHECK((tret = myst_tcall(SYS_pread64, params)));
 
@@ -317,6 +547,9 @@ static ssize_t _fs_pwrite(
     if (!buf && count)
         ERAISE(-EINVAL);
 
+    if (hostfs->manifest)
+        ERAISE(-EBADF);
+
     long params[6] = {file->fd, (long)buf, count, offset};
     ECHECK((tret = myst_tcall(SYS_pwrite64, params)));
 
@@ -379,8 +612,7 @@ static int _fs_close(myst_fs_t* fs, myst_file_t* file)
     if (tret != 0)
         ERAISE(-EINVAL);
 
-    memset(file, 0xdd, sizeof(myst_file_t));
-    free(file);
+    _free_file(file);
 
     ret = tret;
 
@@ -479,6 +711,13 @@ static int _fs_fstat(myst_fs_t* fs, myst_file_t* file, struct stat* statbuf)
     if (tret != 0)
         ERAISE(-EINVAL);
 
+    /* report the size of what will be read rather than the host's size */
+    if (file->verified)
+    {
+        statbuf->st_size = file->size;
+        statbuf->st_blocks = (file->size + 511) / 512;
+    }
+
     ret = tret;
 
 done:
@@ -496,6 +735,9 @@ static int _fs_link(myst_fs_t* fs, const char* oldpath, const char* newpath)
     if (!_hostfs_valid(hostfs) || !oldpath || !newpath)
         ERAISE(-EINVAL);
 
+    if (hostfs->manifest)
+        ERAISE(-EROFS);
+
     ECHECK(_to_host_path(hostfs, opath, sizeof(opath), oldpath));
     ECHECK(_to_host_path(hostfs, npath, sizeof(npath), newpath));
 
@@ -521,6 +763,9 @@ static int _fs_unlink(myst_fs_t* fs, const char* pathname)
     if (!_hostfs_valid(hostfs) || !pathname)
         ERAISE(-EINVAL);
 
+    if (hostfs->manifest)
+        ERAISE(-EROFS);
+
     ECHECK(_to_host_path(hostfs, path, sizeof(path), pathname));
 
     long params[6] = {(long)path};
@@ -546,6 +791,9 @@ static int _fs_rename(myst_fs_t* fs, const char* oldpath, const char* newpath)
     if (!_hostfs_valid(hostfs) || !oldpath || !newpath)
         ERAISE(-EINVAL);
 
+    if (hostfs->manifest)
+        ERAISE(-EROFS);
+
     ECHECK(_to_host_path(hostfs, opath, sizeof(opath), oldpath));
     ECHECK(_to_host_path(hostfs, npath, sizeof(npath), newpath));
 
@@ -571,6 +819,9 @@ static int _fs_truncate(myst_fs_t* fs, const char* path, off_t length)
     if (!_hostfs_valid(hostfs) || !path || length < 0)
         ERAISE(-EINVAL);
 
+    if (hostfs->manifest)
+        ERAISE(-EROFS);
+
     ECHECK(_to_host_path(hostfs, hpath, sizeof(hpath), path));
 
     long params[6] = {(long)hpath, length};
@@ -594,6 +845,9 @@ static int _fs_ftruncate(myst_fs_t* fs, myst_file_t* file, off_t length)
     if (!_hostfs_valid(hostfs) || !_file_valid(file) || length < 0)
         ERAISE(-EINVAL);
 
+    if (hostfs->manifest)
+        ERAISE(-EINVAL);
+
     long params[6] = {file->fd, length};
     ECHECK((tret = myst_tcall(SYS_ftruncate, params)));
 
@@ -616,6 +870,9 @@ static int _fs_mkdir(myst_fs_t* fs, const char* pathname, mode_t mode)
     if (!_hostfs_valid(hostfs) || !pathname)
         ERAISE(-EINVAL);
 
+    if (hostfs->manifest)
+        ERAISE(-EROFS);
+
     ECHECK(_to_host_path(hostfs, path, sizeof(path), pathname));
 
     long params[6] = {(long)path, mode};
@@ -640,6 +897,9 @@ static int _fs_rmdir(myst_fs_t* fs, const char* pathname)
     if (!_hostfs_valid(hostfs) || !pathname)
         ERAISE(-EINVAL);
 
+    if (hostfs->manifest)
+        ERAISE(-EROFS);
+
     ECHECK(_to_host_path(hostfs, path, sizeof(path), pathname));
 
     long params[6] = {(long)path};
@@ -727,6 +987,9 @@ static int _fs_symlink(myst_fs_t* fs, const char* target, const char* linkpath)
     if (!_hostfs_valid(hostfs) || !target || !linkpath)
         ERAISE(-EINVAL);
 
+    if (hostfs->manifest)
+        ERAISE(-EROFS);
+
     /* Note: store target as-is (it may refer to a different file system) */
 
     ECHECK(_to_host_path(hostfs, host_linkpath, PATH_MAX, linkpath));
@@ -803,15 +1066,13 @@ static int _fs_ioctl(
     hostfs_t* hostfs = (hostfs_t*)fs;
     int ret = 0;
 
+    (void)request;
     (void)arg;
 
     if (!_hostfs_valid(hostfs) || !_file_valid(file))
         ERAISE(-EBADF);
 
-    if (request == TIOCGWINSZ)
-        ERAISE(-EINVAL);
-
-    ERAISE(-ENOTSUP);
+    ERAISE(-ENOTTY);
 
 done:
 
@@ -835,6 +1096,16 @@ static int _fs_dup(
         ERAISE(-ENOMEM);
 
     *new_file = *file;
+    new_file->data = NULL;
+    new_file->fd = -1;
+
+    if (file->verified)
+    {
+        if (!(new_file->data = malloc(file->size ? file->size : 1)))
+            ERAISE(-ENOMEM);
+
+        memcpy(new_file->data, file->data, file->size);
+    }
 
     long params[6] = {file->fd};
     ECHECK((tret = myst_tcall(SYS_dup, params)));
@@ -848,10 +1119,7 @@ static int _fs_dup(
 done:
 
     if (new_file)
-    {
-        memset(new_file, 0xdd, sizeof(myst_file_t));
-        free(new_file);
-    }
+        _free_file(new_file);
 
     return ret;
 }
@@ -941,6 +1209,9 @@ static int _fs_futimens(
     if (!_hostfs_valid(hostfs) || !_file_valid(file))
         ERAISE(-EINVAL);
 
+    if (hostfs->manifest)
+        ERAISE(-EROFS);
+
     long params[6] = {(long)file->fd, (long)NULL, (long)times, 0};
     ECHECK((tret = myst_tcall(SYS_utimensat, params)));
     ret = tret;
@@ -949,6 +1220,69 @@ done:
     return ret;
 }
 
+static int _fs_fchmod(myst_fs_t* fs, myst_file_t* file, mode_t mode)
+{
+    int ret = 0;
+    hostfs_t* hostfs = (hostfs_t*)fs;
+    long tret;
+
+    if (!_hostfs_valid(hostfs) || !_file_valid(file))
+        ERAISE(-EINVAL);
+
+    if (hostfs->manifest)
+        ERAISE(-EROFS);
+
+    /* the host decides (hostfs keeps no modes or owners of its own) */
+    long params[6] = {(long)file->fd, (long)mode};
+    ECHECK((tret = myst_tcall(SYS_fchmod, params)));
+    ret = tret;
+
+done:
+    return ret;
+}
+
+static int _fs_chmod(myst_fs_t* fs, const char* pathname, mode_t mode)
+{
+    int ret = 0;
+    hostfs_t* hostfs = (hostfs_t*)fs;
+    char path[PATH_MAX];
+    long fd = -1;
+    long tret;
+
+    if (!_hostfs_valid(hostfs) || !pathname)
+        ERAISE(-EINVAL);
+
+    if (hostfs->manifest)
+        ERAISE(-EROFS);
+
+    ECHECK(_to_host_path(hostfs, path, sizeof(path), pathname));
+
+    /* the host only does fchmod(), so open the file for it */
+    {
+        long params[6] = {(long)path, O_RDONLY};
+        ECHECK((fd = myst_tcall(SYS_open, params)));
+    }
+
+    {
+        long params[6] = {fd, (long)mode};
+        ECHECK((tret = myst_tcall(SYS_fchmod, params)));
+        ret = tret;
+    }
+
+done:
+
+    if (fd >= 0)
+    {
+        long params[6] = {fd};
+        myst_tcall(SYS_close, params);
+    }
+
+    return ret;
+}
+
+/* no requests: each fails with ENOTTY */
+static const myst_ioctl_table_t _ioctls = MYST_IOCTL_TABLE_NONE("hostfs");
+
 int myst_init_hostfs(myst_fs_t** fs_out)
 {
     int ret = 0;
@@ -964,6 +1298,7 @@ int myst_init_hostfs(myst_fs_t** fs_out)
             .fd_fstat = (void*)_fs_fstat,
             .fd_fcntl = (void*)_fs_fcntl,
             .fd_ioctl = (void*)_fs_ioctl,
+            .fd_ioctls = &_ioctls,
             .fd_dup = (void*)_fs_dup,
             .fd_close = (void*)_fs_close,
             .fd_target_fd = (void*)_fs_target_fd,
@@ -1004,6 +1339,8 @@ int myst_init_hostfs(myst_fs_t** fs_out)
         .fs_statfs = _fs_statfs,
         .fs_fstatfs = _fs_fstatfs,
         .fs_futimens = _fs_futimens,
+        .fs_chmod = _fs_chmod,
+        .fs_fchmod = _fs_fchmod,
     };
     // clang-format on
 
@@ -0,0 +1,60 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_AUDIT_H
+#define _MYST_AUDIT_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <sys/socket.h>
+
+#include <myst/buf.h>
+#include <myst/defs.h>
+#include <myst/sha256.h>
+
+/* the number of records kept in memory (older records are only exported) */
+#define MYST_AUDIT_MAX_RECORDS 512
+
+/* the maximum length of a record (excluding the hash) */
+#define MYST_AUDIT_RECORD_SIZE 512
+
+typedef enum myst_audit_type
+{
+    MYST_AUDIT_EXEC,
+    MYST_AUDIT_MOUNT,
+    MYST_AUDIT_CONNECT,
+    MYST_AUDIT_ATTESTATION,
+} myst_audit_type_t;
+
+/* enable auditing and optionally stream the records to a host file */
+int myst_audit_setup(bool enabled, const char* export_path);
+
+bool myst_audit_enabled(void);
+
+MYST_PRINTF_FORMAT(2, 3)
+void myst_audit_record(myst_audit_type_t type, const char* format, ...);
+
+void myst_audit_exec(const char* path, size_t argc);
+
+void myst_audit_mount(
+    const char* source,
+    const char* target,
+    const char* fstype,
+    unsigned long flags,
+    long ret);
+
+void myst_audit_connect(
+    int sockfd,
+    const struct sockaddr* addr,
+    socklen_t addrlen,
+    long ret);
+
+void myst_audit_attestation(const char* request, long ret);
+
+/* get the sequence number and hash of the most recent record */
+void myst_audit_head(uint64_t* seq, myst_sha256_t* hash);
+
+/* write the retained records in the export format (see doc/audit-log.md) */
+int myst_audit_format(myst_buf_t* buf);
+
+#endif /* _MYST_AUDIT_H */
@@ -29,4 +29,10 @@ int myst_read_block_device(
     struct myst_block* blocks,
     size_t num_blocks);
 
+/* the size of the block device in bytes */
+int myst_get_block_device_size(int blkdev, uint64_t* size);
+
+/* flush what has been written to the block device to stable storage */
+int myst_sync_block_device(int blkdev);
+
 #endif /* _MYST_RAWBLKDEV_H */
@@ -0,0 +1,49 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_BUDGET_H
+#define _MYST_BUDGET_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <sys/resource.h>
+
+/* the CPU and wall-clock budget of a process */
+typedef struct myst_budget
+{
+    /* RLIMIT_CPU in seconds (RLIM_INFINITY if none) */
+    rlim_t cpu_soft;
+    rlim_t cpu_hard;
+
+    /* the CPU time (in nanoseconds) at which SIGXCPU is sent next */
+    long next_sigxcpu;
+
+    /* when the process is killed (monotonic nanoseconds, 0 if never) */
+    long wall_deadline;
+
+    /* when the budget is checked next (monotonic nanoseconds) */
+    long next_check;
+
+    /* whether the process has been killed for exceeding its budget */
+    bool killed;
+} myst_budget_t;
+
+struct myst_thread;
+
+/* set the budget of the processes from config.json (0 for no limit): the
+ * soft and hard RLIMIT_CPU and the wall-clock limit, all in seconds */
+void myst_budget_setup(size_t cpu_soft, size_t cpu_hard, size_t wall);
+
+/* start the budget of a new process, which inherits RLIMIT_CPU from its
+ * parent process (NULL for the first process) */
+void myst_budget_init(struct myst_thread* process, struct myst_thread* parent);
+
+/* enforce the budget of the process of a thread that entered the kernel */
+void myst_budget_check(struct myst_thread* thread);
+
+/* get and set RLIMIT_CPU of the calling process */
+long myst_budget_prlimit(
+    const struct rlimit* new_rlim,
+    struct rlimit* old_rlim);
+
+#endif /* _MYST_BUDGET_H */
@@ -4,18 +4,92 @@
 #ifndef _MYST_CLOCK_H
 #define _MYST_CLOCK_H
 
+#include <stdbool.h>
+#include <stddef.h>
+
+#include <myst/shmheader.h>
+
 #define NANO_IN_SECOND 1000000000
 #define MICRO_IN_SECOND 1000000
 
+/* the host clocks that may feed the enclave realtime clock (as selected by
+ * "HostClockSource" in config.json) */
+typedef enum myst_clock_source
+{
+    /* CLOCK_REALTIME when the enclave starts */
+    MYST_CLOCK_SOURCE_REALTIME = 0,
+
+    /* CLOCK_TAI less the TAI offset of the host kernel when the enclave
+     * starts (so the host must know the TAI offset) */
+    MYST_CLOCK_SOURCE_TAI = 1,
+
+    /* CLOCK_REALTIME, followed (at a bounded rate) as NTP disciplines it */
+    MYST_CLOCK_SOURCE_NTP = 2,
+} myst_clock_source_t;
+
+/* how the enclave keeps time (from config.json) */
+typedef struct myst_clock_config
+{
+    /* the host clock that feeds the realtime clock ("HostClockSource") */
+    int source;
+
+    /* TAI minus UTC in seconds, or 0 to take it from the host ("TaiOffset") */
+    long tai_offset;
+
+    /* spread leap seconds over the 24 hours around them rather than holding
+     * the realtime clock for an inserted second ("LeapSecondSmear") */
+    bool leap_smear;
+} myst_clock_config_t;
+
+/* the maximum rate at which the enclave follows the host realtime clock
+ * with MYST_CLOCK_SOURCE_NTP: 500 parts per million, as for NTP itself */
+#define MYST_CLOCK_MAX_SLEW_DIVISOR 2000
+
+/* the header of struct clock_ctrl ("MCLK") */
+#define MYST_CLOCK_CTRL_MAGIC 0x4b4c434d
+
+/* Version 1 ends with the source field; version 2 adds the status of the
+ * host clock after it. The enclave accepts both (see myst_setup_clock). */
+#define MYST_CLOCK_CTRL_VERSION 2
+#define MYST_CLOCK_CTRL_V1_SIZE offsetof(struct clock_ctrl, seq)
+
 struct clock_ctrl
 {
+    myst_shm_header_t header;
+
     long realtime0;
     long monotime0;
     volatile long now;
     unsigned long interval;
     volatile int done;
+
+    /* the host clock that realtime0 came from (myst_clock_source_t) */
+    int source;
+
+    /* What the host kernel reports about its realtime clock (version 2).
+     * The host makes seq odd while it updates the fields below and even
+     * when it is done. */
+    volatile unsigned long seq;
+
+    /* host realtime minus the realtime extrapolated from realtime0 at the
+     * same monotonic time, in nanoseconds (MYST_CLOCK_SOURCE_NTP only) */
+    volatile long realtime_offset;
+
+    /* the maximum and estimated errors of the host clock (nanoseconds) */
+    volatile long maxerror;
+    volatile long esterror;
+
+    /* whether NTP has synchronized the host clock */
+    volatile int synced;
+
+    /* TAI minus UTC in seconds, as the host kernel knows it (0 if not) */
+    volatile long tai_offset;
+
+    /* a leap second at the end of the current UTC day: 1 if one is
+     * inserted, -1 if one is deleted and 0 if there is none */
+    volatile int leap;
 };
 
-int myst_setup_clock(struct clock_ctrl*);
+int myst_setup_clock(struct clock_ctrl*, const myst_clock_config_t* config);
 
 #endif /* _MYST_CLOCK_H */
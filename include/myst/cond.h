@@ -7,6 +7,7 @@
 #ifndef _MYST_COND_H
 #define _MYST_COND_H
 
+#include <myst/deadline.h>
 #include <myst/mutex.h>
 #include <myst/spinlock.h>
 #include <time.h>
@@ -28,6 +29,12 @@ int myst_cond_timedwait(
     myst_mutex_t* mutex,
     const struct timespec* timeout);
 
+/* wait until signaled or until the deadline passes (ETIMEDOUT) */
+int myst_cond_wait_deadline(
+    myst_cond_t* c,
+    myst_mutex_t* mutex,
+    const myst_deadline_t* deadline);
+
 int myst_cond_signal(myst_cond_t* c);
 
 /* Wake up n waiters */
@@ -0,0 +1,44 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_CONSOLE_H
+#define _MYST_CONSOLE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <sys/types.h>
+
+/* the maximum number of rotated log files kept per stream */
+#define MYST_CONSOLE_MAX_LOG_FILES 99
+
+typedef struct myst_console_config
+{
+    /* host files that receive a copy of stdout and stderr (or null) */
+    const char* stdout_log;
+    const char* stderr_log;
+
+    /* prefix each logged line with the UTC time */
+    bool timestamps;
+
+    /* rotate a log when it would grow beyond this many bytes (zero: never) */
+    size_t max_size;
+
+    /* the number of rotated files to keep (path.1 is the most recent) */
+    size_t max_files;
+
+    /* do not write stdout and stderr to the host terminal */
+    bool log_only;
+} myst_console_config_t;
+
+int myst_console_setup(const myst_console_config_t* config);
+
+/* write to the console (STDOUT_FILENO or STDERR_FILENO) */
+long myst_console_write(int fd, const void* buf, size_t count);
+
+/* write to the host terminal, through the console ring if there is one */
+long myst_console_write_host(int fd, const void* buf, size_t count);
+
+/* write any partial lines to the log files */
+void myst_console_flush(void);
+
+#endif /* _MYST_CONSOLE_H */
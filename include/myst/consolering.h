@@ -0,0 +1,69 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_CONSOLERING_H
+#define _MYST_CONSOLERING_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include <myst/shmheader.h>
+
+/*
+** The console ring: a single-producer single-consumer ring buffer in host
+** memory that carries writes to stdout and stderr out of the enclave, so
+** that a write to the console costs a copy rather than a call to the host.
+** The kernel appends records (under a lock, so it is the one producer) and
+** a host thread writes them to the host descriptors in order. Each record
+** is a myst_console_record_t followed by its bytes, padded to 8 bytes, and
+** may wrap around the end of the data.
+**
+** The kernel keeps its own copy of head, so the host can only make it wait
+** (or lose the output) by lying about tail.
+*/
+
+/* the header of struct myst_console_ring ("MCON") */
+#define MYST_CONSOLE_RING_MAGIC 0x4e4f434d
+#define MYST_CONSOLE_RING_VERSION 1
+
+/* the bytes of data in the ring (a power of 2) */
+#define MYST_CONSOLE_RING_CAPACITY (64 * 1024)
+
+typedef struct myst_console_record
+{
+    uint32_t fd;
+    uint32_t size;
+} myst_console_record_t;
+
+typedef struct myst_console_ring
+{
+    myst_shm_header_t header;
+
+    /* the bytes written by the kernel and read by the host (these only
+     * grow: the offset in data is modulo the capacity) */
+    volatile uint64_t head;
+    volatile uint64_t tail;
+
+    /* set by the host once the enclave has returned */
+    volatile int done;
+
+    uint8_t data[MYST_CONSOLE_RING_CAPACITY];
+} myst_console_ring_t;
+
+/* the bytes that a record takes up in the ring */
+static __inline__ uint64_t myst_console_record_size(uint32_t size)
+{
+    return (sizeof(myst_console_record_t) + size + 7) & ~(uint64_t)7;
+}
+
+/* use the ring for the console (or the host calls if ring is null) */
+int myst_console_ring_setup(myst_console_ring_t* ring);
+
+/* write to STDOUT_FILENO or STDERR_FILENO through the ring, or return
+ * -ENOTSUP if there is no ring */
+long myst_console_ring_write(int fd, const void* buf, size_t count);
+
+/* wait (for a while) for the host to write out what is in the ring */
+void myst_console_ring_flush(void);
+
+#endif /* _MYST_CONSOLERING_H */
@@ -0,0 +1,81 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_COREDUMP_H
+#define _MYST_COREDUMP_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/* the maximum core dump size if "CoreDump.MaxSize" is not given */
+#define MYST_COREDUMP_DEFAULT_MAX_SIZE (64 * 1024 * 1024)
+
+/* the magic number at the start of an encrypted core dump */
+#define MYST_COREDUMP_MAGIC "MYSTCORE"
+
+typedef struct myst_coredump_config
+{
+    /* the host directory that receives core.<pid>; disabled if null */
+    const char* path;
+
+    /* the most bytes to write (the memory included is truncated to fit) */
+    size_t max_size;
+
+    /* PEM file in the root file system with the operator's RSA public key;
+     * if given, the core dump is encrypted to this key */
+    const char* encryption_key;
+} myst_coredump_config_t;
+
+/* the registers of the crashing thread (in the order of user_regs_struct) */
+typedef struct myst_coredump_regs
+{
+    uint64_t r15;
+    uint64_t r14;
+    uint64_t r13;
+    uint64_t r12;
+    uint64_t rbp;
+    uint64_t rbx;
+    uint64_t r11;
+    uint64_t r10;
+    uint64_t r9;
+    uint64_t r8;
+    uint64_t rax;
+    uint64_t rcx;
+    uint64_t rdx;
+    uint64_t rsi;
+    uint64_t rdi;
+    uint64_t orig_rax;
+    uint64_t rip;
+    uint64_t cs;
+    uint64_t eflags;
+    uint64_t rsp;
+    uint64_t ss;
+    uint64_t fs_base;
+    uint64_t gs_base;
+    uint64_t ds;
+    uint64_t es;
+    uint64_t fs;
+    uint64_t gs;
+} myst_coredump_regs_t;
+
+/* called by the target when an exception it cannot handle kills a thread */
+typedef void (*myst_crash_handler_t)(
+    int signum,
+    const myst_coredump_regs_t* regs);
+
+/* fails if core dumps are enabled without an encryption key in an enclave
+ * that is not in debug mode */
+int myst_coredump_setup(
+    const myst_coredump_config_t* config,
+    bool tee_debug_mode);
+
+/* write a core dump for the calling thread's process, which a fatal signal
+ * is about to kill; regs is null if the kernel sent the signal, in which
+ * case the registers are those of the kernel at that point */
+void myst_coredump(int signum, const myst_coredump_regs_t* regs);
+
+/* whether the default action of the signal dumps core (see signal(7)) */
+bool myst_coredump_signal(int signum);
+
+#endif /* _MYST_COREDUMP_H */
@@ -104,6 +104,23 @@ int myst_cpio_mem_unpack(
     const char* target,
     myst_cpio_create_file_function_t create_file);
 
+/* a whiteout in a layer: an empty file named ".wh.<name>" removes <name> */
+#define MYST_CPIO_WHITEOUT_PREFIX ".wh."
+
+/* Write the layer that turns the tree at base into the tree at update:
+ * what was added or changed, and a whiteout for what was removed. The
+ * modes of directories that are in both trees are not compared */
+int myst_cpio_make_layer(
+    const char* base,
+    const char* update,
+    const char* target);
+
+/* apply a layer (as written by myst_cpio_make_layer) to the tree at target */
+int myst_cpio_mem_apply_layer(
+    const void* cpio_data,
+    size_t cpio_size,
+    const char* target);
+
 /* Test for CPIO magic string: "070701"; return 0 or ENOTSUP */
 int myst_cpio_test(const char* path);
 
@@ -0,0 +1,56 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_DEADLINE_H
+#define _MYST_DEADLINE_H
+
+#include <limits.h>
+#include <stdbool.h>
+#include <sys/time.h>
+#include <time.h>
+
+/* the deadline of a wait without a timeout */
+#define MYST_DEADLINE_NEVER LONG_MAX
+
+/* The end of a timeout as an absolute CLOCK_MONOTONIC time. A wait that
+ * blocks several times (after spurious wakeups, say) takes what remains of
+ * the deadline each time rather than restarting the timeout, and setting
+ * the realtime clock does not move it. */
+typedef struct myst_deadline
+{
+    /* nanoseconds of CLOCK_MONOTONIC (or MYST_DEADLINE_NEVER) */
+    long nsec;
+} myst_deadline_t;
+
+/* the current CLOCK_MONOTONIC time in nanoseconds */
+long myst_deadline_now(void);
+
+/* start a deadline that is timeout from now (NULL for none); fails with
+ * -EINVAL if the timeout is negative or malformed */
+int myst_deadline_init(myst_deadline_t* deadline, const struct timespec* to);
+
+/* as above for a struct timeval (as used by select) */
+int myst_deadline_init_timeval(
+    myst_deadline_t* deadline,
+    const struct timeval* to);
+
+/* as above for milliseconds (as used by poll): negative waits forever */
+void myst_deadline_init_msec(myst_deadline_t* deadline, int msec);
+
+/* whether the deadline has passed */
+bool myst_deadline_expired(const myst_deadline_t* deadline);
+
+/* the nanoseconds left (0 once expired, MYST_DEADLINE_NEVER without one) */
+long myst_deadline_remaining(const myst_deadline_t* deadline);
+
+/* The time left as a relative timeout for a wait: NULL if there is no
+ * deadline, or else buf (which is zero once expired) */
+const struct timespec* myst_deadline_remaining_timespec(
+    const myst_deadline_t* deadline,
+    struct timespec* buf);
+
+/* the time left in milliseconds, rounded up so that a wait does not end
+ * early (-1 without a deadline and at most INT_MAX) */
+int myst_deadline_remaining_msec(const myst_deadline_t* deadline);
+
+#endif /* _MYST_DEADLINE_H */
@@ -0,0 +1,39 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_DEBUGMALLOC_H
+#define _MYST_DEBUGMALLOC_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#include <myst/kernel.h>
+
+/* the default number of freed bytes held back from reuse */
+#define MYST_DEBUG_MALLOC_QUARANTINE_SIZE (1024 * 1024)
+
+/* enable the debug allocator before the first kernel allocation */
+void myst_debug_malloc_setup(bool enable, size_t quarantine_size);
+
+bool myst_debug_malloc_enabled(void);
+
+void* myst_debug_malloc(size_t size);
+
+void* myst_debug_calloc(size_t nmemb, size_t size);
+
+void* myst_debug_realloc(void* ptr, size_t size);
+
+void* myst_debug_memalign(size_t alignment, size_t size);
+
+void myst_debug_free(void* ptr);
+
+/* return the quarantined blocks to the heap */
+void myst_debug_malloc_flush(void);
+
+int myst_debug_malloc_stats(myst_malloc_stats_t* stats);
+
+/* print the blocks that are still allocated, grouped by backtrace; returns
+ * the number of bytes still in use */
+size_t myst_debug_malloc_report(void);
+
+#endif /* _MYST_DEBUGMALLOC_H */
@@ -0,0 +1,37 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_DIRHASH_H
+#define _MYST_DIRHASH_H
+
+#include <stddef.h>
+#include <sys/types.h>
+
+#include <myst/sha256.h>
+
+/* The hash of a directory tree ("myst hashdir") is the SHA-256 of a record
+ * for each entry below the directory, visited depth first with the entries of
+ * each directory in myst_dirhash_sort() order. Paths are relative to the top
+ * directory. Only directories, regular files and symbolic links are allowed.
+ */
+
+/* add the record of a directory */
+int myst_dirhash_dir(myst_sha256_ctx_t* ctx, const char* path, mode_t mode);
+
+/* add the record of a regular file: the caller then adds its size bytes */
+int myst_dirhash_file(
+    myst_sha256_ctx_t* ctx,
+    const char* path,
+    mode_t mode,
+    size_t size);
+
+/* add the record of a symbolic link */
+int myst_dirhash_symlink(
+    myst_sha256_ctx_t* ctx,
+    const char* path,
+    const char* target);
+
+/* sort the names of the entries of a directory (by strcmp) */
+void myst_dirhash_sort(char** names, size_t count);
+
+#endif /* _MYST_DIRHASH_H */
@@ -0,0 +1,79 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_DISK_H
+#define _MYST_DISK_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <sys/types.h>
+
+#include <myst/blkdev.h>
+
+/* the most disks that an enclave can have (/dev/vda to /dev/vdz) */
+#define MYST_MAX_DISKS 26
+
+/* the block cache of a disk without a "CacheSize" */
+#define MYST_DISK_DEFAULT_CACHE_SIZE (1024 * 1024)
+
+/* the smallest block cache (in blocks of MYST_BLKSIZE) */
+#define MYST_DISK_MIN_CACHE_BLOCKS 16
+
+/* a disk as written in the "Disks" section of config.json */
+typedef struct myst_disk_config
+{
+    /* the raw image on the host */
+    const char* host_path;
+
+    /* a host file that the cached writes go through (NULL to write them
+     * through to the image) */
+    const char* journal_path;
+
+    /* the size of the block cache in bytes (0 for the default) */
+    size_t cache_size;
+
+    /* whether writes fail with EROFS */
+    bool read_only;
+} myst_disk_config_t;
+
+typedef struct myst_disks_config
+{
+    myst_disk_config_t* disks;
+    size_t num_disks;
+} myst_disks_config_t;
+
+/* open the configured disks as /dev/vda, /dev/vdb and so on, replaying what
+ * their journals committed */
+int myst_disks_setup(const myst_disks_config_t* config);
+
+/* the index of the disk with the given path (/dev/vd<letter>), or -ENOENT */
+int myst_disk_find(const char* path);
+
+/* the size of a disk in bytes */
+uint64_t myst_disk_size(int disk);
+
+/* whether writes to the disk fail with EROFS */
+bool myst_disk_read_only(int disk);
+
+/* read or write count bytes at the given offset, which need not be aligned
+ * to blocks (the count is cut short at the end of the disk) */
+ssize_t myst_disk_pread(int disk, void* buf, size_t count, uint64_t offset);
+
+ssize_t myst_disk_pwrite(
+    int disk,
+    const void* buf,
+    size_t count,
+    uint64_t offset);
+
+/* write the cached blocks of a disk to the image (through its journal) */
+int myst_disk_sync(int disk);
+
+/* myst_disk_sync() for every disk */
+int myst_disks_sync(void);
+
+/* a block device over the disk with the given path, for a file system
+ * (ext2) or for a crypt or verity layer; closing it syncs the disk */
+int myst_disk_open_blkdev(const char* path, myst_blkdev_t** blkdev);
+
+#endif /* _MYST_DISK_H */
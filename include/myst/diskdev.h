@@ -0,0 +1,53 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_DISKDEV_H
+#define _MYST_DISKDEV_H
+
+#include <sys/types.h>
+
+#include <myst/defs.h>
+#include <myst/fdops.h>
+
+typedef struct myst_diskdev myst_diskdev_t;
+
+typedef struct myst_disk_file myst_disk_file_t;
+
+struct myst_diskdev
+{
+    myst_fdops_t fdops;
+
+    /* open a disk (/dev/vd<letter>, see myst/disk.h) */
+    int (*dd_open)(
+        myst_diskdev_t* diskdev,
+        const char* path,
+        int flags,
+        myst_disk_file_t** file);
+
+    off_t (*dd_lseek)(
+        myst_diskdev_t* diskdev,
+        myst_disk_file_t* file,
+        off_t offset,
+        int whence);
+
+    ssize_t (*dd_pread)(
+        myst_diskdev_t* diskdev,
+        myst_disk_file_t* file,
+        void* buf,
+        size_t count,
+        off_t offset);
+
+    ssize_t (*dd_pwrite)(
+        myst_diskdev_t* diskdev,
+        myst_disk_file_t* file,
+        const void* buf,
+        size_t count,
+        off_t offset);
+
+    /* write the cached blocks of the disk to the host image */
+    int (*dd_fsync)(myst_diskdev_t* diskdev, myst_disk_file_t* file);
+};
+
+myst_diskdev_t* myst_diskdev_get(void);
+
+#endif /* _MYST_DISKDEV_H */
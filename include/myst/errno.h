@@ -8,4 +8,7 @@
 
 const char* myst_error_name(long errnum);
 
+/* find an error number by name (e.g., "EPERM"); returns 0 if not found */
+long myst_error_num(const char* name);
+
 #endif /* _MYST_ERRNO_H */
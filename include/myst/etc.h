@@ -0,0 +1,16 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_ETC_H
+#define _MYST_ETC_H
+
+#include <stddef.h>
+
+/* Create the /etc files that the root file system does not provide:
+ * hostname, hosts, resolv.conf (if name servers are given), passwd, group
+ * and nsswitch.conf */
+int myst_create_etc_files(
+    const char* const* dns_servers,
+    size_t num_dns_servers);
+
+#endif /* _MYST_ETC_H */
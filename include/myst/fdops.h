@@ -36,6 +36,9 @@ struct myst_fdops
     int (
         *fd_ioctl)(void* device, void* object, unsigned long request, long arg);
 
+    /* the requests that fd_ioctl() supports (see myst/ioctl.h) */
+    const struct myst_ioctl_table* fd_ioctls;
+
     int (*fd_dup)(void* device, void* object, void** object_out);
 
     int (*fd_close)(void* device, void* object);
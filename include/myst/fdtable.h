@@ -10,12 +10,16 @@
 #include <unistd.h>
 
 #include <myst/defs.h>
+#include <myst/diskdev.h>
 #include <myst/epolldev.h>
 #include <myst/fs.h>
 #include <myst/inotifydev.h>
+#include <myst/iouringdev.h>
 #include <myst/pipedev.h>
+#include <myst/ptydev.h>
 #include <myst/sockdev.h>
 #include <myst/spinlock.h>
+#include <myst/timerfddev.h>
 #include <myst/ttydev.h>
 
 #define MYST_FDTABLE_SIZE 1024
@@ -29,6 +33,10 @@ typedef enum myst_fdtable_type
     MYST_FDTABLE_TYPE_SOCK,
     MYST_FDTABLE_TYPE_EPOLL,
     MYST_FDTABLE_TYPE_INOTIFY,
+    MYST_FDTABLE_TYPE_IO_URING,
+    MYST_FDTABLE_TYPE_PTY,
+    MYST_FDTABLE_TYPE_DISK,
+    MYST_FDTABLE_TYPE_TIMERFD,
 } myst_fdtable_type_t;
 
 typedef struct myst_fdtable_entry
@@ -50,6 +58,9 @@ int myst_fdtable_cloexec(myst_fdtable_t* fdtable);
 
 int myst_fdtable_free(myst_fdtable_t* fdtable);
 
+/* returns the number of open file descriptors */
+size_t myst_fdtable_count(myst_fdtable_t* fdtable);
+
 /* returns a file descriptor */
 int myst_fdtable_assign(
     myst_fdtable_t* fdtable,
@@ -142,6 +153,16 @@ MYST_INLINE int myst_fdtable_get_inotify(
     return myst_fdtable_get(fdtable, fd, type, (void**)device, (void**)inotify);
 }
 
+MYST_INLINE int myst_fdtable_get_iouring(
+    myst_fdtable_t* fdtable,
+    int fd,
+    myst_iouringdev_t** device,
+    myst_iouring_t** iouring)
+{
+    const myst_fdtable_type_t type = MYST_FDTABLE_TYPE_IO_URING;
+    return myst_fdtable_get(fdtable, fd, type, (void**)device, (void**)iouring);
+}
+
 int myst_fdtable_get_any(
     myst_fdtable_t* fdtable,
     int fd,
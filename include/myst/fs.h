@@ -7,6 +7,7 @@
 #include <dirent.h>
 #include <fcntl.h>
 #include <limits.h>
+#include <stdbool.h>
 #include <sys/stat.h>
 #include <sys/statfs.h>
 #include <sys/types.h>
@@ -164,10 +165,41 @@ struct myst_fs
         myst_fs_t* fs,
         myst_file_t* file,
         const struct timespec times[2]);
+
+    /* get the creation time (optional: file systems that do not record it
+     * leave this null) */
+    int (*fs_btime)(
+        myst_fs_t* fs,
+        const char* pathname,
+        bool follow,
+        struct timespec* btime);
+
+    /* change the mode and owner (optional: with these null, chmod() and
+     * chown() fail with EPERM) */
+    int (*fs_chmod)(myst_fs_t* fs, const char* pathname, mode_t mode);
+
+    int (*fs_fchmod)(myst_fs_t* fs, myst_file_t* file, mode_t mode);
+
+    int (*fs_chown)(
+        myst_fs_t* fs,
+        const char* pathname,
+        bool follow,
+        uid_t owner,
+        gid_t group);
+
+    int (*fs_fchown)(
+        myst_fs_t* fs,
+        myst_file_t* file,
+        uid_t owner,
+        gid_t group);
 };
 
 int myst_remove_fd_link(int fd);
 
+/* get the path of an open file as the calling process knows it beneath its
+ * root directory (fs_realpath() gets the real path) */
+int myst_fs_path(myst_fs_t* fs, myst_file_t* file, char* buf, size_t size);
+
 int myst_load_fs(
     myst_mount_resolve_callback_t resolve_cb,
     const char* source,
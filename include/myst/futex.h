@@ -20,10 +20,44 @@
 #define FUTEX_WAIT_BITSET    9
 #define FUTEX_PRIVATE        128
 #define FUTEX_CLOCK_REALTIME 256
+
+#define FUTEX_WAITERS        0x80000000
+#define FUTEX_OWNER_DIED     0x40000000
+#define FUTEX_TID_MASK       0x3fffffff
+#define ROBUST_LIST_LIMIT    2048
 // clang-format on
 
-int myst_futex_wait(int* uaddr, int val, const struct timespec* to);
+/* the robust futex list ABI (see set_robust_list(2)) */
+struct robust_list
+{
+    struct robust_list* next;
+};
+
+struct robust_list_head
+{
+    struct robust_list list;
+    long futex_offset;
+    struct robust_list* list_op_pending;
+};
+
+/* a shared futex (one without FUTEX_PRIVATE) in a MAP_SHARED mapping of a
+ * file is the same futex in every process that maps it */
+int myst_futex_wait(
+    int* uaddr,
+    int val,
+    const struct timespec* to,
+    bool shared);
+
+int myst_futex_wake(int* uaddr, int val, bool shared);
+
+long myst_syscall_set_robust_list(struct robust_list_head* head, size_t len);
+
+long myst_syscall_get_robust_list(
+    int pid,
+    struct robust_list_head** head_ptr,
+    size_t* len_ptr);
 
-int myst_futex_wake(int* uaddr, int val);
+/* mark the robust futexes still held by an exiting thread as owner-died */
+void myst_futex_exit_robust_list(struct myst_thread* thread);
 
 #endif /* _MYST_FUTEX_H */
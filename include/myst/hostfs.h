@@ -5,7 +5,12 @@
 #define _MYST_HOSTFS_H
 
 #include <myst/fs.h>
+#include <myst/manifest.h>
 
 int myst_init_hostfs(myst_fs_t** fs_out);
 
+/* make the mount read-only and verify every opened file against the manifest
+ * (the file system takes ownership of the manifest) */
+int myst_hostfs_set_manifest(myst_fs_t* fs, myst_manifest_t* manifest);
+
 #endif /* _MYST_HOSTFS_H */
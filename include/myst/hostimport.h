@@ -0,0 +1,52 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_HOSTIMPORT_H
+#define _MYST_HOSTIMPORT_H
+
+#include <stddef.h>
+
+/* a host directory as written in the "HostDirectoryImports" section of
+ * config.json */
+typedef struct myst_host_import
+{
+    /* the directory on the host */
+    const char* host_path;
+
+    /* where its contents are copied in the root file system */
+    const char* target_path;
+
+    /* the expected hash of the directory in hex ("myst hashdir") */
+    const char* sha256;
+} myst_host_import_t;
+
+/* a host file as written in the "HostFiles" section of config.json */
+typedef struct myst_host_file
+{
+    /* the file on the host */
+    const char* host_path;
+
+    /* where it is copied in the root file system */
+    const char* target_path;
+} myst_host_file_t;
+
+/* the largest host file that is copied */
+#define MYST_HOST_FILE_MAX_SIZE (1024 * 1024)
+
+typedef struct myst_host_imports_config
+{
+    myst_host_import_t* imports;
+    size_t num_imports;
+    myst_host_file_t* files;
+    size_t num_files;
+} myst_host_imports_config_t;
+
+/* copy each host directory into the root file system, failing unless the
+ * copy has the expected hash */
+int myst_import_host_dirs(const myst_host_imports_config_t* config);
+
+/* copy each host file into the root file system (read-only and unchecked,
+ * as the host chooses the contents) */
+int myst_import_host_files(const myst_host_imports_config_t* config);
+
+#endif /* _MYST_HOSTIMPORT_H */
@@ -4,7 +4,21 @@
 #ifndef _MYST_ID_H
 #define _MYST_ID_H
 
+#include <sys/types.h>
+
 #define MYST_DEFAULT_UID (uid_t)0
 #define MYST_DEFAULT_GID (gid_t)0
 
+/* the effective IDs of the calling process (every process runs as root, as
+ * setuid() and setgid() only accept the defaults) */
+static inline uid_t myst_geteuid(void)
+{
+    return MYST_DEFAULT_UID;
+}
+
+static inline gid_t myst_getegid(void)
+{
+    return MYST_DEFAULT_GID;
+}
+
 #endif /* _MYST_ID_H */
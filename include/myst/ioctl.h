@@ -0,0 +1,68 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_IOCTL_H
+#define _MYST_IOCTL_H
+
+#include <stddef.h>
+
+/*
+** The ioctl() requests of a device. Each device declares the requests it
+** supports in a table, with what the argument is, and myst_syscall_ioctl()
+** checks a request against the table of the device before the device sees
+** it: a request that is not in the table fails with ENOTTY (as Linux does
+** for a request that the driver does not know), and is logged under
+** --trace-errors, and one whose argument is not user memory fails with
+** EFAULT. The handler of the device then only sees the requests in its
+** table, with valid arguments.
+*/
+
+/* what the argument of a request is */
+typedef enum myst_ioctl_dir
+{
+    /* a value (or nothing) rather than a pointer */
+    MYST_IOCTL_VALUE,
+
+    /* a pointer to size bytes that the device reads, writes or both */
+    MYST_IOCTL_IN,
+    MYST_IOCTL_OUT,
+    MYST_IOCTL_INOUT,
+} myst_ioctl_dir_t;
+
+typedef struct myst_ioctl
+{
+    unsigned long request;
+    const char* name;
+    myst_ioctl_dir_t dir;
+    size_t size;
+} myst_ioctl_t;
+
+typedef struct myst_ioctl_table
+{
+    /* the device, as logged */
+    const char* name;
+
+    const myst_ioctl_t* ioctls;
+    size_t num_ioctls;
+} myst_ioctl_table_t;
+
+/* a request whose argument points to a TYPE */
+#define MYST_IOCTL(REQUEST, DIR, TYPE) {REQUEST, #REQUEST, DIR, sizeof(TYPE)}
+
+/* a request whose argument is a value */
+#define MYST_IOCTL_ARG(REQUEST) {REQUEST, #REQUEST, MYST_IOCTL_VALUE, 0}
+
+#define MYST_IOCTL_TABLE(NAME, IOCTLS) \
+    {NAME, IOCTLS, sizeof(IOCTLS) / sizeof(IOCTLS[0])}
+
+/* the table of a device that supports no requests */
+#define MYST_IOCTL_TABLE_NONE(NAME) {NAME, NULL, 0}
+
+/* return 0 if the device supports the request and arg is valid for it, or
+ * else -ENOTTY or -EFAULT */
+long myst_ioctl_check(
+    const myst_ioctl_table_t* table,
+    unsigned long request,
+    long arg);
+
+#endif /* _MYST_IOCTL_H */
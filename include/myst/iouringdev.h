@@ -0,0 +1,269 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_IOURINGDEV_H
+#define _MYST_IOURINGDEV_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <sys/types.h>
+#include <sys/uio.h>
+
+#include <myst/fdops.h>
+
+/*
+**==============================================================================
+**
+** io_uring ABI (see include/uapi/linux/io_uring.h in the Linux sources)
+**
+**==============================================================================
+*/
+
+/* mmap() offsets of the rings and of the submission queue entries */
+#define MYST_IORING_OFF_SQ_RING 0ULL
+#define MYST_IORING_OFF_CQ_RING 0x8000000ULL
+#define MYST_IORING_OFF_SQES 0x10000000ULL
+
+/* io_uring_setup() flags */
+#define MYST_IORING_SETUP_IOPOLL (1U << 0)
+#define MYST_IORING_SETUP_SQPOLL (1U << 1)
+#define MYST_IORING_SETUP_SQ_AFF (1U << 2)
+#define MYST_IORING_SETUP_CQSIZE (1U << 3)
+#define MYST_IORING_SETUP_CLAMP (1U << 4)
+
+/* io_uring_params.features */
+#define MYST_IORING_FEAT_SINGLE_MMAP (1U << 0)
+#define MYST_IORING_FEAT_NODROP (1U << 1)
+#define MYST_IORING_FEAT_SUBMIT_STABLE (1U << 2)
+#define MYST_IORING_FEAT_RW_CUR_POS (1U << 3)
+
+/* io_uring_enter() flags */
+#define MYST_IORING_ENTER_GETEVENTS (1U << 0)
+#define MYST_IORING_ENTER_SQ_WAKEUP (1U << 1)
+
+/* io_uring_sqe.flags */
+#define MYST_IOSQE_FIXED_FILE (1U << 0)
+#define MYST_IOSQE_IO_DRAIN (1U << 1)
+#define MYST_IOSQE_IO_LINK (1U << 2)
+#define MYST_IOSQE_IO_HARDLINK (1U << 3)
+#define MYST_IOSQE_ASYNC (1U << 4)
+
+/* io_uring_sqe.fsync_flags */
+#define MYST_IORING_FSYNC_DATASYNC (1U << 0)
+
+/* io_uring_sqe.opcode (only the supported opcodes are listed) */
+#define MYST_IORING_OP_NOP 0
+#define MYST_IORING_OP_READV 1
+#define MYST_IORING_OP_WRITEV 2
+#define MYST_IORING_OP_FSYNC 3
+#define MYST_IORING_OP_ACCEPT 13
+#define MYST_IORING_OP_READ 22
+#define MYST_IORING_OP_WRITE 23
+#define MYST_IORING_OP_LAST 24
+
+/* io_uring_register() opcodes */
+#define MYST_IORING_REGISTER_PROBE 8
+
+/* io_uring_probe_op.flags */
+#define MYST_IO_URING_OP_SUPPORTED (1U << 0)
+
+/* the maximum number of submission queue entries */
+#define MYST_IORING_MAX_ENTRIES 4096
+
+typedef struct myst_io_sqring_offsets
+{
+    uint32_t head;
+    uint32_t tail;
+    uint32_t ring_mask;
+    uint32_t ring_entries;
+    uint32_t flags;
+    uint32_t dropped;
+    uint32_t array;
+    uint32_t resv1;
+    uint64_t resv2;
+} myst_io_sqring_offsets_t;
+
+typedef struct myst_io_cqring_offsets
+{
+    uint32_t head;
+    uint32_t tail;
+    uint32_t ring_mask;
+    uint32_t ring_entries;
+    uint32_t overflow;
+    uint32_t cqes;
+    uint32_t flags;
+    uint32_t resv1;
+    uint64_t resv2;
+} myst_io_cqring_offsets_t;
+
+typedef struct myst_io_uring_params
+{
+    uint32_t sq_entries;
+    uint32_t cq_entries;
+    uint32_t flags;
+    uint32_t sq_thread_cpu;
+    uint32_t sq_thread_idle;
+    uint32_t features;
+    uint32_t wq_fd;
+    uint32_t resv[3];
+    myst_io_sqring_offsets_t sq_off;
+    myst_io_cqring_offsets_t cq_off;
+} myst_io_uring_params_t;
+
+typedef struct myst_io_uring_sqe
+{
+    uint8_t opcode;
+    uint8_t flags;
+    uint16_t ioprio;
+    int32_t fd;
+    union {
+        uint64_t off;
+        uint64_t addr2; /* IORING_OP_ACCEPT: socklen_t* */
+    };
+    uint64_t addr;
+    uint32_t len;
+    union {
+        int32_t rw_flags;
+        uint32_t fsync_flags;
+        uint32_t accept_flags;
+    };
+    uint64_t user_data;
+    uint16_t buf_index;
+    uint16_t personality;
+    int32_t splice_fd_in;
+    uint64_t __pad2[2];
+} myst_io_uring_sqe_t;
+
+typedef struct myst_io_uring_cqe
+{
+    uint64_t user_data;
+    int32_t res;
+    uint32_t flags;
+} myst_io_uring_cqe_t;
+
+typedef struct myst_io_uring_probe_op
+{
+    uint8_t op;
+    uint8_t resv;
+    uint16_t flags;
+    uint32_t resv2;
+} myst_io_uring_probe_op_t;
+
+typedef struct myst_io_uring_probe
+{
+    uint8_t last_op;
+    uint8_t ops_len;
+    uint16_t resv;
+    uint32_t resv2[3];
+    myst_io_uring_probe_op_t ops[];
+} myst_io_uring_probe_t;
+
+/*
+**==============================================================================
+**
+** myst_iouringdev_t
+**
+**==============================================================================
+*/
+
+typedef struct myst_iouringdev myst_iouringdev_t;
+
+typedef struct myst_iouring myst_iouring_t;
+
+struct myst_iouringdev
+{
+    myst_fdops_t fdops;
+
+    int (*iu_setup)(
+        myst_iouringdev_t* dev,
+        uint32_t entries,
+        myst_io_uring_params_t* params,
+        myst_iouring_t** obj);
+
+    long (*iu_enter)(
+        myst_iouringdev_t* dev,
+        myst_iouring_t* obj,
+        uint32_t to_submit,
+        uint32_t min_complete,
+        uint32_t flags);
+
+    long (*iu_register)(
+        myst_iouringdev_t* dev,
+        myst_iouring_t* obj,
+        uint32_t opcode,
+        void* arg,
+        uint32_t nr_args);
+
+    /* get the address of the region at the given mmap() offset */
+    int (*iu_mmap)(
+        myst_iouringdev_t* dev,
+        myst_iouring_t* obj,
+        off_t offset,
+        size_t length,
+        void** addr);
+
+    ssize_t (*iu_read)(
+        myst_iouringdev_t* dev,
+        myst_iouring_t* obj,
+        void* buf,
+        size_t count);
+
+    ssize_t (*iu_write)(
+        myst_iouringdev_t* dev,
+        myst_iouring_t* obj,
+        const void* buf,
+        size_t count);
+
+    ssize_t (*iu_readv)(
+        myst_iouringdev_t* dev,
+        myst_iouring_t* obj,
+        const struct iovec* iov,
+        int iovcnt);
+
+    ssize_t (*iu_writev)(
+        myst_iouringdev_t* dev,
+        myst_iouring_t* obj,
+        const struct iovec* iov,
+        int iovcnt);
+
+    int (*iu_fstat)(
+        myst_iouringdev_t* dev,
+        myst_iouring_t* obj,
+        struct stat* statbuf);
+
+    int (*iu_fcntl)(
+        myst_iouringdev_t* dev,
+        myst_iouring_t* obj,
+        int cmd,
+        long arg);
+
+    int (*iu_ioctl)(
+        myst_iouringdev_t* dev,
+        myst_iouring_t* obj,
+        unsigned long request,
+        long arg);
+
+    int (*iu_dup)(
+        myst_iouringdev_t* dev,
+        const myst_iouring_t* obj,
+        myst_iouring_t** obj_out);
+
+    int (*iu_close)(myst_iouringdev_t* dev, myst_iouring_t* obj);
+
+    int (*iu_target_fd)(myst_iouringdev_t* dev, myst_iouring_t* obj);
+
+    int (*iu_get_events)(myst_iouringdev_t* dev, myst_iouring_t* obj);
+};
+
+myst_iouringdev_t* myst_iouringdev_get(void);
+
+/* release the mappings of io_uring rings in [addr, addr + length) that the
+ * calling process made, and return whether there were any (munmap() has
+ * nothing more to do then) */
+bool myst_iouring_munmap(void* addr, size_t length);
+
+/* release the mappings of io_uring rings of a process that exits */
+void myst_iouring_release_mappings(pid_t pid);
+
+#endif /* _MYST_IOURINGDEV_H */
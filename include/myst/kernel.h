@@ -4,8 +4,20 @@
 #ifndef _MYST_KERNEL_H
 #define _MYST_KERNEL_H
 
+#include <myst/consolering.h>
+#include <myst/coredump.h>
+#include <myst/disk.h>
+#include <myst/hostimport.h>
+#include <myst/keyrelease.h>
+#include <myst/mount.h>
+#include <myst/rootfslayers.h>
+#include <myst/secret.h>
+#include <myst/services.h>
+#include <myst/shutdown.h>
+#include <myst/syscallpolicy.h>
 #include <myst/tcall.h>
 #include <myst/types.h>
+#include <myst/wxpolicy.h>
 
 typedef struct myst_kernel_args
 {
@@ -51,10 +63,99 @@ typedef struct myst_kernel_args
     /* configure hostname in kernel */
     const char* hostname;
 
+    /* override the uname() release, version and machine strings */
+    const char* uname_release;
+    const char* uname_version;
+    const char* uname_machine;
+
+    /* name servers written to /etc/resolv.conf */
+    const char** dns_servers;
+    size_t num_dns_servers;
+
+    /* copy stdout and stderr to host log files */
+    const char* console_log_stdout;
+    const char* console_log_stderr;
+    bool console_log_timestamps;
+    size_t console_log_max_size;
+    size_t console_log_max_files;
+    bool console_log_only;
+
+    /* the ring that carries the console output to the host (or NULL) */
+    myst_console_ring_t* console_ring;
+
+    /* record security-relevant events (and stream them to a host file) */
+    bool audit_log;
+    const char* audit_log_path;
+
+    /* show a writable RAM file system over the root file system, which is
+     * then never written */
+    bool rootfs_overlay;
+
+    /* the layers applied to the root file system (see
+     * kernel/rootfslayers.c) */
+    myst_rootfs_layers_config_t rootfs_layers;
+
+    /* the syscall allow/deny rules */
+    myst_syscall_policy_t syscall_policy;
+
+    /* deny writable and executable mappings (see kernel/wxpolicy.c) */
+    myst_wx_config_t wx;
+
+    /* the seconds to cache the attested credentials for (see
+     * kernel/quotecache.c), or 0 to generate them for each request */
+    size_t quote_cache_lifetime;
+
+    /* what to do when memory runs out ("fail" or "kill") */
+    const char* oom_policy;
+    size_t mem_low_watermark;
+
+    /* check kernel allocations for corruption and report leaks at exit */
+    bool debug_malloc;
+    size_t debug_malloc_quarantine;
+
+    /* panic when a signal handler could reenter a held kernel mutex */
+    bool debug_signal_safety;
+
+    /* the initial RLIMIT_CPU and the wall-clock limit of the processes, in
+     * seconds (0 for none) */
+    size_t cpu_time_limit;
+    size_t cpu_time_hard_limit;
+    size_t wall_time_limit;
+
+    /* the keys released to the enclave before the application starts */
+    myst_key_release_config_t key_release;
+    myst_secrets_config_t secrets;
+
+    /* write a core file to the host when the application crashes */
+    myst_coredump_config_t coredump;
+
+    /* processes to run and supervise in place of the application */
+    myst_services_config_t services;
+
+    /* host directories copied into the root file system before the
+     * application starts */
+    myst_host_imports_config_t host_imports;
+
+    /* the raw host images seen as /dev/vda, /dev/vdb and so on */
+    myst_disks_config_t disks;
+
+    /* the file systems that the application may mount */
+    myst_mount_policy_t mount_policy;
+
+    /* how to stop the processes when the host asks or the application exits */
+    myst_shutdown_config_t shutdown;
+
     /* The read-write-execute memory management pages */
     void* mman_data;
     size_t mman_size;
 
+    /* The leading bytes of the mman pages that are committed on first
+     * access with SGX2 (see "CommittedMemorySize") */
+    size_t mman_pending_size;
+
+    /* The number of page faults that the TEE handled (NULL if none) */
+    const volatile uint64_t* page_faults;
+
     /* The CPIO root file system image */
     char rootfs[PATH_MAX];
     void* rootfs_data;
@@ -75,11 +176,15 @@ typedef struct myst_kernel_args
     /* The number of threads that can be created (including the main thread) */
     size_t max_threads;
 
+    /* The number of virtual CPUs reported to the application */
+    size_t num_cpus;
+
     /* Tracing options */
     bool trace_errors;
     bool trace_syscalls;
     bool have_syscall_instruction;
     bool export_ramfs;
+    bool export_coverage;
 
     /* The event object for the main thread */
     uint64_t event;
@@ -105,6 +210,9 @@ typedef struct myst_malloc_stats
 
 int myst_get_malloc_stats(myst_malloc_stats_t* stats);
 
+/* returns 1 if memory was released */
+int myst_malloc_trim(void);
+
 int myst_find_leaks(void);
 
 #endif /* _MYST_KERNEL_H */
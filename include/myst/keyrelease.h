@@ -0,0 +1,44 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_KEYRELEASE_H
+#define _MYST_KEYRELEASE_H
+
+#include <stddef.h>
+
+/* the largest key that can be released (a 4096-bit RSA key is about 2.4K) */
+#define MYST_KEY_RELEASE_MAX_KEY_SIZE 8192
+
+/* the "KeyRelease" section of config.json, which configures the
+ * "key-release" secret provider */
+typedef struct myst_key_release_config
+{
+    /* the attestation service (e.g., "https://<name>.<region>.attest.azure
+     * .net"); keys cannot be released if this is null */
+    const char* attestation_url;
+
+    /* PEM file in the root file system with the trusted CA certificates */
+    const char* ca_certificates;
+
+    /* where the access token for the key store comes from (the Azure
+     * instance metadata service by default) */
+    const char* identity_endpoint;
+    const char* client_id;
+    const char* resource;
+} myst_key_release_config_t;
+
+/* one key release, which the kernel passes to the target */
+typedef struct myst_key_release_request
+{
+    const char* attestation_url;
+    const char* key_url;
+    const char* identity_endpoint;
+    const char* client_id;
+    const char* resource;
+
+    /* the trusted CA certificates (PEM, zero-terminated) */
+    const char* ca_data;
+    size_t ca_size;
+} myst_key_release_request_t;
+
+#endif /* _MYST_KEYRELEASE_H */
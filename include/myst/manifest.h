@@ -0,0 +1,30 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_MANIFEST_H
+#define _MYST_MANIFEST_H
+
+#include <stddef.h>
+
+#include <myst/sha256.h>
+
+/* the SHA-256 hashes of the files under a hostfs mount */
+typedef struct myst_manifest myst_manifest_t;
+
+/* load "manifests/<name>" from the archive, which is part of the signed
+ * image; each line has the form produced by sha256sum: "<hash>  <path>" */
+int myst_manifest_load(
+    const void* archive_data,
+    size_t archive_size,
+    const char* name,
+    myst_manifest_t** manifest);
+
+/* get the hash of a path relative to the mount point */
+int myst_manifest_find(
+    const myst_manifest_t* manifest,
+    const char* path,
+    myst_sha256_t* hash);
+
+void myst_manifest_free(myst_manifest_t* manifest);
+
+#endif /* _MYST_MANIFEST_H */
@@ -0,0 +1,63 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_METRICS_H
+#define _MYST_METRICS_H
+
+#include <stdint.h>
+
+#include <myst/buf.h>
+
+/*
+** The metrics registry. Kernel subsystems count events in counters and time
+** them in histograms, which /proc/myst/metrics exposes in the Prometheus
+** text format (see doc/metrics.md). Updates are atomic and never block, so
+** they may be made from any context.
+*/
+
+/* the finite buckets of a histogram: 1us, 4us, 16us, ... 4s (plus +Inf) */
+#define MYST_HISTOGRAM_BUCKETS 12
+
+typedef struct myst_counter
+{
+    const char* name;
+    const char* help;
+    uint64_t value;
+    struct myst_counter* next;
+} myst_counter_t;
+
+/* a histogram of durations in nanoseconds (exposed in seconds) */
+typedef struct myst_histogram
+{
+    const char* name;
+    const char* help;
+    uint64_t buckets[MYST_HISTOGRAM_BUCKETS + 1];
+    uint64_t sum;
+    struct myst_histogram* next;
+} myst_histogram_t;
+
+#define MYST_COUNTER_INITIALIZER(NAME, HELP) {NAME, HELP, 0, NULL}
+
+#define MYST_HISTOGRAM_INITIALIZER(NAME, HELP) {NAME, HELP, {0}, 0, NULL}
+
+/* the metrics of the kernel */
+extern myst_counter_t myst_metric_tcalls;
+extern myst_histogram_t myst_metric_syscall_seconds;
+extern myst_histogram_t myst_metric_futex_wait_seconds;
+
+/* add metrics that are not the kernel's own (they must stay valid) */
+void myst_metrics_register_counter(myst_counter_t* counter);
+
+void myst_metrics_register_histogram(myst_histogram_t* histogram);
+
+static __inline__ void myst_counter_add(myst_counter_t* counter, uint64_t n)
+{
+    __atomic_fetch_add(&counter->value, n, __ATOMIC_RELAXED);
+}
+
+void myst_histogram_observe(myst_histogram_t* histogram, long nsec);
+
+/* write the metrics in the Prometheus text format */
+int myst_metrics_format(myst_buf_t* buf);
+
+#endif /* _MYST_METRICS_H */
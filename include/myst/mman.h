@@ -96,6 +96,15 @@ typedef struct myst_mman
     /* Whether to scrub memory when it is unmapped (fill with 0xDD) */
     bool scrub;
 
+    /* Lowest map value and highest break value so far: the memory between
+     * them has never been handed out */
+    uintptr_t map_min;
+    uintptr_t brk_max;
+
+    /* End of the memory that is committed on first access (SGX2): it reads
+     * as zero until then, so it is not zero-filled when first mapped */
+    uintptr_t pending_end;
+
     /* Heap locking */
     myst_spinlock_t lock;
 
@@ -106,6 +115,9 @@ typedef struct myst_mman
 
 int myst_mman_init(myst_mman_t* heap, uintptr_t base, size_t size);
 
+/* memory in [start, end) is committed on first access and reads as zero */
+void myst_mman_set_pending(myst_mman_t* mman, uintptr_t end);
+
 int myst_mman_mmap(
     myst_mman_t* heap,
     void* addr,
@@ -5,9 +5,11 @@
 #define _MYST_MMANUTILS_H
 
 #include <myst/mman.h>
+#include <stdbool.h>
+#include <stdint.h>
 #include <sys/types.h>
 
-int myst_setup_mman(void* data, size_t size);
+int myst_setup_mman(void* data, size_t size, size_t pending_size);
 
 int myst_teardown_mman(void);
 
@@ -30,6 +32,9 @@ void* myst_mremap(
     int flags,
     void* new_address);
 
+/* whether [addr, addr + size) lies within the mman region */
+bool myst_is_mman_range(const void* addr, size_t size);
+
 int myst_get_total_ram(size_t* size);
 
 int myst_get_free_ram(size_t* size);
@@ -38,8 +43,25 @@ int myst_register_process_mapping(pid_t pid, void* addr, size_t size);
 
 int myst_release_process_mappings(pid_t pid);
 
+int myst_enumerate_process_mappings(
+    pid_t pid,
+    void (*callback)(void* addr, size_t size, void* arg),
+    void* arg);
+
 int myst_msync(void* addr, size_t length, int flags);
 
+/* the file range that a MAP_SHARED mapping maps at an address */
+typedef struct myst_mapping_key
+{
+    dev_t dev;
+    ino_t ino;
+    uint64_t offset;
+} myst_mapping_key_t;
+
+/* get the file range mapped at addr (-ENOENT unless it is in a MAP_SHARED
+ * mapping of a file) */
+int myst_get_shared_mapping_key(const void* addr, myst_mapping_key_t* key);
+
 void myst_mman_close_notify(int fd);
 
 #endif /* _MYST_MMANUTILS_H */
@@ -5,11 +5,50 @@
 #define _MYST_MOUNT_H
 
 #include <limits.h>
+#include <stdbool.h>
+#include <stddef.h>
+
 #include <myst/fs.h>
 
+/* a mount that the application may perform (the "AllowedMounts" section
+ * of config.json) */
+typedef struct myst_mount_rule
+{
+    /* the file system type (e.g., "tmpfs", "proc" or "ext2") */
+    const char* type;
+
+    /* the source that mount() must be given (any source when null) */
+    const char* source;
+
+    /* the absolute path to mount on (any path when null) */
+    const char* target;
+} myst_mount_rule_t;
+
+typedef struct myst_mount_policy
+{
+    myst_mount_rule_t* rules;
+    size_t num_rules;
+
+    /* set by a configuration, which allows only the mounts of its rules
+     * (and none without rules) */
+    bool enforced;
+} myst_mount_policy_t;
+
+/* Restrict the mount() and umount2() of the application to these rules
+ * (unless not enforced, when it may mount any supported file system) */
+int myst_mount_policy_setup(const myst_mount_policy_t* policy);
+
 /* Mount a file system onto a target path */
 int myst_mount(myst_fs_t* fs, const char* source, const char* target);
 
+/* Put fs in place of the file system mounted on target (which is returned
+ * in old_fs and not released) */
+int myst_mount_replace(
+    myst_fs_t* fs,
+    const char* source,
+    const char* target,
+    myst_fs_t** old_fs);
+
 /* Unmount the file system that is mounted on target */
 int myst_umount(const char* target);
 
@@ -0,0 +1,39 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_OOM_H
+#define _MYST_OOM_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#define MYST_OOM_MAX_CALLBACKS 16
+
+/* called under memory pressure to release memory (needed is a hint) */
+typedef void (*myst_oom_callback_t)(size_t needed, void* arg);
+
+/* policy is "fail" (the default) or "kill"; a nonzero low_watermark invokes
+ * the pressure callbacks when free memory drops below that many bytes */
+int myst_oom_setup(const char* policy, size_t low_watermark);
+
+int myst_oom_register_callback(myst_oom_callback_t callback, void* arg);
+
+size_t myst_oom_low_watermark(void);
+
+/* the number of processes killed by the "kill" policy */
+size_t myst_oom_kills(void);
+
+/* note that an allocation failed (exhausted) or that free memory may be low;
+ * safe to call with allocator locks held since it only sets a flag */
+void myst_oom_pressure(bool exhausted);
+
+/* run the callbacks for pressure noted by myst_oom_pressure(); called when
+ * the calling thread leaves the kernel and holds no allocator locks */
+void myst_oom_poll(void);
+
+/* called when an allocation of length bytes fails: returns zero if the
+ * callbacks released memory and the allocation should be retried; otherwise
+ * applies the policy and returns -ENOMEM */
+int myst_oom_handle(size_t length);
+
+#endif /* _MYST_OOM_H */
@@ -0,0 +1,20 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_OVERLAYFS_H
+#define _MYST_OVERLAYFS_H
+
+#include <myst/fs.h>
+
+/* Create a file system that shows the upper file system over the lower one.
+ * The lower file system is never written: files are copied up to the upper
+ * file system when first written and removals are recorded as whiteouts in
+ * the upper file system. The new file system takes ownership of both layers.
+ */
+int myst_init_overlayfs(
+    myst_mount_resolve_callback_t resolve_cb,
+    myst_fs_t* lower,
+    myst_fs_t* upper,
+    myst_fs_t** fs_out);
+
+#endif /* _MYST_OVERLAYFS_H */
@@ -0,0 +1,35 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_PERMS_H
+#define _MYST_PERMS_H
+
+#include <sys/types.h>
+
+/*
+** The ownership and permission rules that the file systems share (see
+** kernel/perms.c), so that ramfs, ext2 and the overlay agree on who may
+** open, chmod and chown a file.
+*/
+
+/* 0 if the calling process has the access in want (R_OK, W_OK, X_OK) to a
+ * file with this mode and owner, or else -EACCES */
+int myst_perms_check(mode_t mode, uid_t uid, gid_t gid, int want);
+
+/* the access that open() with these flags needs (for myst_perms_check()) */
+int myst_perms_open_access(int flags);
+
+/* the mode of a new file (mode without the umask of the calling process) */
+mode_t myst_perms_umask(mode_t mode);
+
+/* 0 if the calling process may chmod a file owned by uid, or else -EPERM */
+int myst_perms_check_chmod(uid_t uid);
+
+/* 0 if the calling process may change the owner and group of a file from
+ * uid and gid to owner and group (-1 leaves one as it is), or else -EPERM */
+int myst_perms_check_chown(uid_t uid, gid_t gid, uid_t owner, gid_t group);
+
+/* the mode of a file after it changed owner (without the set-ID bits) */
+mode_t myst_perms_chown_mode(mode_t mode);
+
+#endif /* _MYST_PERMS_H */
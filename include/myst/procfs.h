@@ -22,6 +22,9 @@ int procfs_setup();
 
 int procfs_teardown();
 
+/* Create /proc/[pid] entries */
+int procfs_pid_setup(pid_t pid);
+
 /* Cleanup /proc/[pid] entries */
 int procfs_pid_cleanup(pid_t pid);
 
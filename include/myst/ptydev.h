@@ -0,0 +1,37 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_PTYDEV_H
+#define _MYST_PTYDEV_H
+
+#include <stdbool.h>
+
+#include <myst/defs.h>
+#include <myst/fdops.h>
+
+/* the most pseudo-terminals that can be open at once */
+#define MYST_PTY_MAX 64
+
+typedef struct myst_ptydev myst_ptydev_t;
+
+typedef struct myst_pty myst_pty_t;
+
+struct myst_ptydev
+{
+    myst_fdops_t fdops;
+
+    /* open /dev/ptmx (the master of a new pair) or /dev/pts/<n> (the slave
+     * of pair n) */
+    int (*pd_open)(
+        myst_ptydev_t* ptydev,
+        const char* path,
+        int flags,
+        myst_pty_t** pty);
+};
+
+myst_ptydev_t* myst_ptydev_get(void);
+
+/* whether path is /dev/ptmx or under /dev/pts */
+bool myst_is_pty_path(const char* path);
+
+#endif /* _MYST_PTYDEV_H */
@@ -0,0 +1,37 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_QUOTECACHE_H
+#define _MYST_QUOTECACHE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/*
+** The attested credentials of the enclave (a key pair and a certificate
+** with a quote over its public key, as used for TTLS), cached for the
+** "QuoteCacheLifetime" of config.json so that a TLS handshake does not have
+** to wait for a quote to be generated (see kernel/quotecache.c). Without a
+** lifetime, the credentials are generated for each request.
+*/
+
+/* cache the credentials for the given seconds (0 to not cache them) */
+int myst_quote_cache_setup(size_t lifetime);
+
+/* Return a copy of the credentials, which the caller releases with
+ * myst_quote_cache_free_creds(). With the cache, this only waits for a
+ * quote when the cached one has expired (or there is none yet) */
+long myst_quote_cache_gen_creds(
+    uint8_t** cert,
+    size_t* cert_size,
+    uint8_t** pkey,
+    size_t* pkey_size);
+
+/* release credentials from myst_quote_cache_gen_creds() */
+long myst_quote_cache_free_creds(
+    uint8_t* cert,
+    size_t cert_size,
+    uint8_t* pkey,
+    size_t pkey_size);
+
+#endif /* _MYST_QUOTECACHE_H */
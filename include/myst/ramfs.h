@@ -22,7 +22,8 @@ int myst_create_virtual_file(
     myst_fs_t* fs,
     const char* pathname,
     mode_t mode,
-    int (*vcallback)(myst_buf_t* buf));
+    int (*vcallback)(myst_buf_t* buf, void* context),
+    void* context);
 
 int myst_release_tree(
     myst_fs_t* fs,
@@ -6,6 +6,23 @@
 
 #include <myst/types.h>
 
+/* Resolve path (relative to the current working directory) to a real path.
+ * Paths are resolved beneath the root directory of the calling process (see
+ * chroot) and ".." never climbs above it.
+ */
 int myst_realpath(const char* path, myst_path_t* resolved_path);
 
+/* Convert a real path (such as one returned by fs_realpath) to the path the
+ * calling process knows it by beneath its root directory. Real paths outside
+ * the root become "/".
+ */
+int myst_realpath_unroot(const char* realpath, myst_path_t* path);
+
+/* Get the root directory of the calling process as a path within the file
+ * system mounted on mntpath, where following a symbolic link must not climb
+ * above it with "..". The path is empty when the root is not in this file
+ * system (or is "/").
+ */
+int myst_realpath_fs_root(const char* mntpath, myst_path_t* root);
+
 #endif /* _MYST_REALPATH_H */
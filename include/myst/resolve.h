@@ -0,0 +1,55 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_RESOLVE_H
+#define _MYST_RESOLVE_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#include <myst/types.h>
+
+/* openat2() RESOLVE_* flags (see include/uapi/linux/openat2.h) */
+#define MYST_RESOLVE_NO_XDEV 0x01
+#define MYST_RESOLVE_NO_MAGICLINKS 0x02
+#define MYST_RESOLVE_NO_SYMLINKS 0x04
+#define MYST_RESOLVE_BENEATH 0x08
+#define MYST_RESOLVE_IN_ROOT 0x10
+#define MYST_RESOLVE_CACHED 0x20
+
+#define MYST_RESOLVE_ALL 0x3f
+
+/* the openat2() argument (struct open_how) */
+typedef struct myst_open_how
+{
+    uint64_t flags;
+    uint64_t mode;
+    uint64_t resolve;
+} myst_open_how_t;
+
+/* Resolve pathname relative to the directory dirpath (an absolute path as the
+ * calling process knows it) following each symbolic link as restricted by the
+ * RESOLVE_* flags. The result has no symbolic links except perhaps the final
+ * component when follow is false. Fails with EXDEV when RESOLVE_BENEATH or
+ * RESOLVE_NO_XDEV are violated and ELOOP for a link with RESOLVE_NO_SYMLINKS
+ * (or for a /proc/<pid>/fd, exe, cwd or root link with RESOLVE_NO_MAGICLINKS,
+ * RESOLVE_BENEATH or RESOLVE_IN_ROOT). RESOLVE_CACHED is the caller's to check.
+ */
+int myst_resolve_path(
+    const char* dirpath,
+    const char* pathname,
+    uint64_t resolve,
+    bool follow,
+    myst_path_t* path);
+
+/* Held by openat2() from the lookup of a path with RESOLVE_* restrictions
+ * until the file is open, by the dispatcher from the check of a syscall
+ * policy path rule until the file is open, and by the calls that can put a
+ * symbolic link or another directory into a path (symlink, link, rename,
+ * mount and umount), so that the file that is opened is the one that was
+ * checked. The host can still change the paths of hostfs file systems. */
+void myst_resolve_lock(void);
+
+void myst_resolve_unlock(void);
+
+#endif /* _MYST_RESOLVE_H */
@@ -0,0 +1,24 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_ROOTFSLAYERS_H
+#define _MYST_ROOTFSLAYERS_H
+
+#include <stddef.h>
+
+/* the "RootfsLayers" settings of config.json */
+typedef struct myst_rootfs_layers_config
+{
+    /* the host directory that holds the layers ("RootfsLayerStore") */
+    const char* store;
+
+    /* the SHA-256 of each layer in hex, from the bottom up */
+    const char** layers;
+    size_t num_layers;
+} myst_rootfs_layers_config_t;
+
+/* apply each layer to the root file system, failing unless the layer in
+ * the store has the expected hash */
+int myst_apply_rootfs_layers(const myst_rootfs_layers_config_t* config);
+
+#endif /* _MYST_ROOTFSLAYERS_H */
@@ -0,0 +1,55 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_RUSAGE_H
+#define _MYST_RUSAGE_H
+
+#include <stddef.h>
+#include <sys/resource.h>
+
+#include <myst/buf.h>
+
+struct myst_thread;
+
+/* the resource usage of a thread, a process or the children of a process */
+typedef struct myst_rusage
+{
+    /* time (in nanoseconds) spent in user space and in the kernel */
+    long utime;
+    long stime;
+
+    /* the number of voluntary and involuntary context switches */
+    long nvcsw;
+    long nivcsw;
+
+    /* the peak resident set size (in bytes) */
+    size_t maxrss;
+} myst_rusage_t;
+
+/* called before and after the calling thread blocks in the target: counts a
+ * voluntary context switch and keeps the time blocked out of system time */
+long myst_rusage_block_begin(void);
+
+void myst_rusage_block_end(long begin);
+
+/* adjust the resident set size of the calling process by delta bytes */
+void myst_rusage_add_rss(long delta);
+
+/* fold the usage of a reaped child process into its parent process and
+ * return the usage of the child (including its own reaped children) */
+void myst_rusage_reap(
+    struct myst_thread* parent,
+    struct myst_thread* child,
+    myst_rusage_t* usage);
+
+/* get the usage for who (RUSAGE_SELF, RUSAGE_CHILDREN or RUSAGE_THREAD) */
+int myst_rusage_get(struct myst_thread* thread, int who, myst_rusage_t* usage);
+
+void myst_rusage_to_rusage(const myst_rusage_t* usage, struct rusage* rusage);
+
+/* format /proc/[pid]/stat and /proc/[pid]/status for the process */
+int myst_rusage_format_stat(struct myst_thread* process, myst_buf_t* buf);
+
+int myst_rusage_format_status(struct myst_thread* process, myst_buf_t* buf);
+
+#endif /* _MYST_RUSAGE_H */
@@ -0,0 +1,59 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_SCHED_H
+#define _MYST_SCHED_H
+
+#include <sched.h>
+#include <sys/types.h>
+
+struct myst_thread;
+
+/* Inherit the scheduling attributes of the parent thread */
+void myst_sched_clone(
+    const struct myst_thread* parent,
+    struct myst_thread* child);
+
+/* Apply the scheduling attributes of the calling thread to its host thread */
+void myst_sched_apply(void);
+
+long myst_syscall_getpriority(int which, id_t who);
+
+long myst_syscall_setpriority(int which, id_t who, int prio);
+
+long myst_syscall_sched_setscheduler(
+    pid_t pid,
+    int policy,
+    const struct sched_param* param);
+
+long myst_syscall_sched_getscheduler(pid_t pid);
+
+long myst_syscall_sched_setparam(pid_t pid, const struct sched_param* param);
+
+long myst_syscall_sched_getparam(pid_t pid, struct sched_param* param);
+
+long myst_syscall_sched_get_priority_max(int policy);
+
+long myst_syscall_sched_get_priority_min(int policy);
+
+/* The number of virtual CPUs the application sees */
+size_t myst_get_num_cpus(void);
+
+/* Give a starting thread its virtual CPU and take it back at exit */
+void myst_sched_take_cpu(struct myst_thread* thread);
+
+void myst_sched_release_cpu(struct myst_thread* thread);
+
+long myst_syscall_getcpu(unsigned* cpu, unsigned* node);
+
+long myst_syscall_sched_getaffinity(
+    pid_t pid,
+    size_t cpusetsize,
+    cpu_set_t* mask);
+
+long myst_syscall_sched_setaffinity(
+    pid_t pid,
+    size_t cpusetsize,
+    const cpu_set_t* mask);
+
+#endif /* _MYST_SCHED_H */
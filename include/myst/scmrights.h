@@ -0,0 +1,37 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_SCMRIGHTS_H
+#define _MYST_SCMRIGHTS_H
+
+#include <stdbool.h>
+#include <sys/socket.h>
+
+#include <myst/fdtable.h>
+
+/* the most descriptors that one message can carry (as on Linux) */
+#define MYST_SCM_MAX_FD 253
+
+typedef struct myst_scm_rights myst_scm_rights_t;
+
+/* replace the SCM_RIGHTS messages of msg with a host carrier: msg_out is msg
+ * with a new control buffer, and rights is null if msg has no descriptors */
+int myst_scm_rights_send(
+    myst_fdtable_t* fdtable,
+    const struct msghdr* msg,
+    struct msghdr* msg_out,
+    myst_scm_rights_t** rights);
+
+/* called after the host sendmsg: keeps the descriptors in flight until the
+ * receiver takes them, or drops them if the message was not sent */
+void myst_scm_rights_sent(myst_scm_rights_t* rights, bool sent);
+
+/* called after the host recvmsg: replaces the host carriers in the control
+ * buffer (of the given size) with new descriptors in fdtable */
+void myst_scm_rights_recv(
+    myst_fdtable_t* fdtable,
+    struct msghdr* msg,
+    socklen_t size,
+    int flags);
+
+#endif /* _MYST_SCMRIGHTS_H */
@@ -0,0 +1,100 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_SECRET_H
+#define _MYST_SECRET_H
+
+#include <stddef.h>
+
+#include <myst/keyrelease.h>
+
+/* the largest secret that a provider may return */
+#define MYST_SECRET_MAX_SIZE MYST_KEY_RELEASE_MAX_KEY_SIZE
+
+/* a secret as written in the "Secrets" section of config.json */
+typedef struct myst_secret
+{
+    /* the name of the provider that fetches the secret */
+    const char* provider;
+
+    /* what to fetch; the meaning depends on the provider (a path, a URL) */
+    const char* id;
+
+    /* an environment variable that is set to the secret as hex */
+    const char* environment;
+
+    /* a file in the root file system that receives the secret */
+    const char* file;
+} myst_secret_t;
+
+typedef struct myst_secrets_config
+{
+    /* PEM file in the root file system with the CA certificates trusted by
+     * the "attested-https" provider */
+    const char* ca_certificates;
+
+    myst_secret_t* secrets;
+    size_t num_secrets;
+} myst_secrets_config_t;
+
+/* one fetch by the "attested-https" provider, which the kernel passes to
+ * the target */
+typedef struct myst_attested_https_request
+{
+    const char* url;
+
+    /* the trusted CA certificates (PEM, zero-terminated) */
+    const char* ca_data;
+} myst_attested_https_request_t;
+
+typedef struct myst_secret_provider myst_secret_provider_t;
+
+/* a source of secrets; providers are looked up by name */
+struct myst_secret_provider
+{
+    const char* name;
+
+    /* fetch one secret into buf; return zero or a negative errno */
+    int (*get)(
+        myst_secret_provider_t* provider,
+        const myst_secret_t* secret,
+        void* buf,
+        size_t size,
+        size_t* size_out);
+
+    /* optional: called at exit to release what the provider holds */
+    void (*release)(myst_secret_provider_t* provider);
+
+    /* private to the registry */
+    myst_secret_provider_t* next;
+};
+
+/* make a provider available to the "Secrets" section; fails with -EEXIST if
+ * the name is taken */
+int myst_register_secret_provider(myst_secret_provider_t* provider);
+
+myst_secret_provider_t* myst_find_secret_provider(const char* name);
+
+/* called before any secret is fetched, after the built-in providers are
+ * registered; a kernel built with additional providers defines this to
+ * register them */
+void myst_register_custom_secret_providers(void);
+
+/* fetch the configured secrets and store them before the application starts;
+ * key_release configures the "key-release" provider; on return, envp holds
+ * the environment with the secrets added */
+int myst_secrets_setup(
+    const myst_secrets_config_t* config,
+    const myst_key_release_config_t* key_release,
+    size_t* envc,
+    const char*** envp);
+
+/* built-in providers */
+int myst_register_key_release_provider(
+    const myst_key_release_config_t* config);
+
+int myst_register_sealed_file_provider(void);
+
+int myst_register_attested_https_provider(const char* ca_certificates);
+
+#endif /* _MYST_SECRET_H */
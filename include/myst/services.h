@@ -0,0 +1,55 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_SERVICES_H
+#define _MYST_SERVICES_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* how long to wait for "ReadyPath" if "ReadyTimeout" is not given */
+#define MYST_SERVICE_DEFAULT_READY_TIMEOUT 30
+
+/* a service as written in the "Services" section of config.json */
+typedef struct myst_service
+{
+    /* the name used in kernel messages */
+    const char* name;
+
+    /* the executable and its arguments (not including argv[0]) */
+    const char* path;
+    char** argv;
+    size_t argc;
+
+    /* NAME=VALUE pairs that are added to the application environment */
+    char** envp;
+    size_t envc;
+
+    /* when to start the service again: "never" (the default), "on-failure"
+     * (a non-zero exit status or a signal) or "always" */
+    const char* restart;
+
+    /* the most restarts (zero means no limit) */
+    size_t max_restarts;
+
+    /* a file the service creates when ready; the next service is started
+     * after it appears, or the launch fails after ready_timeout seconds */
+    const char* ready_path;
+    uint64_t ready_timeout;
+} myst_service_t;
+
+typedef struct myst_services_config
+{
+    myst_service_t* services;
+    size_t num_services;
+} myst_services_config_t;
+
+/* start the services in order and supervise them until the last one (the
+ * main service) exits for good; returns its exit status, or a negative errno
+ * if a service could not be started */
+int myst_run_services(
+    const myst_services_config_t* config,
+    size_t envc,
+    const char* envp[]);
+
+#endif /* _MYST_SERVICES_H */
@@ -4,16 +4,46 @@
 #ifndef _MYST_SHM_H
 #define _MYST_SHM_H
 
+#include <stddef.h>
+#include <stdint.h>
+
 #include <myst/clock.h>
+#include <myst/consolering.h>
 
 /* Note: members of this struct are copied by value into the enclave */
 struct myst_shm
 {
     /* clock related shared fields */
     struct clock_ctrl* clock;
+
+    /* set to non-zero when the host receives SIGTERM */
+    volatile int* shutdown;
+
+    /* the events the host poller has seen on each host descriptor (see
+     * tools/myst/host/poller.c), indexed by descriptor */
+    volatile uint32_t* poll_events;
+    size_t poll_nfds;
+
+    /* the ring that carries the console output (see myst/consolering.h) */
+    myst_console_ring_t* console;
 };
 
-int shm_create_clock(struct myst_shm* shm, unsigned long clock_tick);
+/* start the host clock thread, taking the realtime clock from the given
+ * myst_clock_source_t */
+int shm_create_clock(
+    struct myst_shm* shm,
+    unsigned long clock_tick,
+    int source);
 void shm_free_clock(struct myst_shm* shm);
 
+/* start the host thread that writes out the console ring (or NULL) */
+myst_console_ring_t* shm_create_console(void);
+
+/* stop the thread once it has written out the ring, and free the ring */
+void shm_free_console(myst_console_ring_t* ring);
+
+/* handle SIGTERM by setting the returned flag and waking the host event of
+ * the main thread; a second SIGTERM ends the host process at once */
+volatile int* shm_create_shutdown(uint64_t event);
+
 #endif /* _MYST_SHM_H */
\ No newline at end of file
@@ -0,0 +1,39 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_SHMHEADER_H
+#define _MYST_SHMHEADER_H
+
+#include <stdint.h>
+
+/*
+** The header of a control block that the host and the enclave share. The
+** host launcher and the enclave image are built separately, so a block
+** starts with a magic number that names it, the version of its layout and
+** its size as the host sees it. The enclave checks all three before it
+** reads anything else from the block and refuses a block that it does not
+** know, rather than misreading fields at the wrong offsets.
+**
+** A new version only appends fields to the previous one, so the enclave
+** can accept a block of the version before its own and treat the fields
+** that the host did not fill as absent.
+*/
+typedef struct myst_shm_header
+{
+    uint32_t magic;
+    uint32_t version;
+    uint64_t size;
+} myst_shm_header_t;
+
+static __inline__ void myst_shm_header_init(
+    myst_shm_header_t* header,
+    uint32_t magic,
+    uint32_t version,
+    uint64_t size)
+{
+    header->magic = magic;
+    header->version = version;
+    header->size = size;
+}
+
+#endif /* _MYST_SHMHEADER_H */
@@ -0,0 +1,48 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_SHUTDOWN_H
+#define _MYST_SHUTDOWN_H
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <time.h>
+
+/* the seconds between SIGTERM and SIGKILL if "ShutdownGracePeriod" is not
+ * given */
+#define MYST_SHUTDOWN_DEFAULT_GRACE_PERIOD 10
+
+typedef struct myst_shutdown_config
+{
+    /* host memory that the launcher sets to non-zero on SIGTERM (or null) */
+    volatile const int* request;
+
+    /* the seconds that processes have to exit after SIGTERM */
+    uint64_t grace_period;
+} myst_shutdown_config_t;
+
+int myst_shutdown_setup(const myst_shutdown_config_t* config);
+
+/* called on each exit from the kernel: sends SIGTERM to the main process
+ * when the host asks for a shutdown, and SIGKILL once the grace period has
+ * passed */
+void myst_shutdown_poll(void);
+
+/* whether the calling thread, woken in an interruptible wait, should leave
+ * it (with EINTR) so that myst_shutdown_poll() can act on a shutdown */
+bool myst_shutdown_interrupted(void);
+
+/* the timeout for an interruptible wait of the calling thread: shortened
+ * (to buf) so that the main thread wakes when the grace period is over */
+const struct timespec* myst_shutdown_timeout(
+    const struct timespec* timeout,
+    struct timespec* buf);
+
+/* whether the host has asked for a shutdown */
+bool myst_shutdown_requested(void);
+
+/* called after the main process exits: sends SIGTERM to the remaining
+ * processes, waits up to the grace period, then kills any still running */
+void myst_shutdown_processes(void);
+
+#endif /* _MYST_SHUTDOWN_H */
@@ -24,11 +24,26 @@ long myst_signal_sigprocmask(int how, const sigset_t* set, sigset_t* oldset);
 
 long myst_signal_process(myst_thread_t* thread);
 
+/* block the signals in set until the current syscall returns */
+long myst_signal_set_syscall_mask(const sigset_t* set);
+
+/* deliver the pending signals and restore the mask saved by
+ * myst_signal_set_syscall_mask(), as the syscall returns */
+long myst_signal_process_syscall_exit(myst_thread_t* thread);
+
 long myst_signal_deliver(
     myst_thread_t* thread,
     unsigned signum,
     siginfo_t* siginfo);
 
+/* deliver a signal raised by a fault, which cannot be blocked or ignored */
+long myst_signal_force(
+    myst_thread_t* thread,
+    unsigned signum,
+    siginfo_t* siginfo);
+
+long myst_signal_sigaltstack(const stack_t* ss, stack_t* old_ss);
+
 long myst_signal_sigpending(sigset_t* set, unsigned size);
 
 long myst_signal_clone(myst_thread_t* parent, myst_thread_t* child);
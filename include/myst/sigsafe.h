@@ -0,0 +1,66 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_SIGSAFE_H
+#define _MYST_SIGSAFE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+/*
+** The signal-safety audit (DebugSignalSafety). A signal handler runs on the
+** thread that it interrupts, from the kernel: at the end of a syscall or
+** while the thread waits for the host (myst_tcall_wait()). If the thread
+** holds a kernel mutex at that point, a handler that makes a syscall which
+** needs the same mutex either deadlocks or (as the mutexes are recursive)
+** sees the half-updated state that the mutex protects. With the audit on,
+** the kernel tracks the mutexes that each thread holds and panics, naming
+** them, before calling a handler that could reenter them.
+**
+** The kernel code that runs while a signal is being delivered (around the
+** call of the handler) must stay within the async-signal-safe subset: it
+** may take spinlocks, use atomics and allocate and free memory, but it may
+** not block, so not lock a myst_mutex_t, wait on a myst_cond_t or wait for
+** the host. The functions outside the subset call myst_sigsafe_assert(),
+** which panics (with the audit on) when called during a delivery.
+*/
+
+/* the mutexes a thread holds that are recorded (the rest are counted) */
+#define MYST_SIGSAFE_MAX_LOCKS 8
+
+typedef struct myst_sigsafe
+{
+    /* the number of mutexes held */
+    size_t nlocks;
+    const void* locks[MYST_SIGSAFE_MAX_LOCKS];
+
+    /* where each of the recorded mutexes was locked */
+    void* callers[MYST_SIGSAFE_MAX_LOCKS];
+
+    /* whether a signal is being delivered (outside the handler) */
+    bool delivering;
+} myst_sigsafe_t;
+
+struct myst_thread;
+
+void myst_sigsafe_setup(bool enable);
+
+bool myst_sigsafe_enabled(void);
+
+/* record that the calling thread locked or unlocked a mutex */
+void myst_sigsafe_acquired(const void* lock, void* caller);
+
+void myst_sigsafe_released(const void* lock);
+
+/* mark the start and end of the delivery of a signal */
+void myst_sigsafe_begin_delivery(struct myst_thread* thread);
+
+void myst_sigsafe_end_delivery(struct myst_thread* thread);
+
+/* panic if the handler of signum would run with a kernel mutex held */
+void myst_sigsafe_check_handler(struct myst_thread* thread, unsigned signum);
+
+/* panic if func (which is not async-signal-safe) runs during a delivery */
+void myst_sigsafe_assert(const char* func);
+
+#endif /* _MYST_SIGSAFE_H */
@@ -0,0 +1,51 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_STACKGUARD_H
+#define _MYST_STACKGUARD_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+struct myst_thread;
+
+/* the pattern that fills the guard pages below the thread stacks */
+#define MYST_STACK_GUARD_BYTE 0x4f
+
+/* PROT_NONE ranges larger than this are reservations rather than guards */
+#define MYST_STACK_GUARD_MAX_SIZE (64 * 1024)
+
+/* called by the target when a page fault at addr is not its own: if it hit
+ * the inaccessible guard of the calling thread, sets rip and rsp so that the
+ * thread resumes in the kernel to take SIGSEGV, and returns true */
+typedef bool (*myst_fault_handler_t)(
+    uint64_t addr,
+    uint64_t* rip,
+    uint64_t* rsp);
+
+/* give the target the handler for faults in the inaccessible guards */
+int myst_stack_guard_setup(void);
+
+/* track the PROT_NONE mappings that the C runtime turns into stack guards */
+void myst_stack_guard_mmap(void* addr, size_t length, int prot, int flags);
+
+void myst_stack_guard_mprotect(void* addr, size_t length, int prot);
+
+void myst_stack_guard_munmap(void* addr, size_t length);
+
+/* give the guard below child_stack to the new thread (called by clone) */
+void myst_stack_guard_attach(struct myst_thread* thread, void* child_stack);
+
+/* set the guard of a stack that the kernel created (called by exec) */
+void myst_stack_guard_set(struct myst_thread* thread, void* addr, size_t len);
+
+/* release the guard of an exiting thread */
+void myst_stack_guard_detach(struct myst_thread* thread);
+
+/* check whether the calling thread has overrun its stack or faulted in its
+ * guard and, if it has, raise SIGSEGV (SEGV_ACCERR) with the highest address
+ * that was touched */
+void myst_stack_guard_check(struct myst_thread* thread);
+
+#endif /* _MYST_STACKGUARD_H */
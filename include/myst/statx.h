@@ -0,0 +1,74 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_STATX_H
+#define _MYST_STATX_H
+
+#include <stdint.h>
+
+/*
+**==============================================================================
+**
+** statx ABI (see include/uapi/linux/stat.h in the Linux sources)
+**
+**==============================================================================
+*/
+
+/* statx() mask bits (requested and returned) */
+#define MYST_STATX_TYPE 0x00000001U
+#define MYST_STATX_MODE 0x00000002U
+#define MYST_STATX_NLINK 0x00000004U
+#define MYST_STATX_UID 0x00000008U
+#define MYST_STATX_GID 0x00000010U
+#define MYST_STATX_ATIME 0x00000020U
+#define MYST_STATX_MTIME 0x00000040U
+#define MYST_STATX_CTIME 0x00000080U
+#define MYST_STATX_INO 0x00000100U
+#define MYST_STATX_SIZE 0x00000200U
+#define MYST_STATX_BLOCKS 0x00000400U
+#define MYST_STATX_BASIC_STATS 0x000007ffU
+#define MYST_STATX_BTIME 0x00000800U
+#define MYST_STATX_MNT_ID 0x00001000U
+#define MYST_STATX__RESERVED 0x80000000U
+
+/* statx() flags (besides AT_SYMLINK_NOFOLLOW and AT_EMPTY_PATH) */
+#define MYST_AT_NO_AUTOMOUNT 0x800
+#define MYST_AT_STATX_SYNC_TYPE 0x6000
+#define MYST_AT_STATX_FORCE_SYNC 0x2000
+#define MYST_AT_STATX_DONT_SYNC 0x4000
+
+typedef struct myst_statx_timestamp
+{
+    int64_t tv_sec;
+    uint32_t tv_nsec;
+    int32_t __reserved;
+} myst_statx_timestamp_t;
+
+typedef struct myst_statx
+{
+    uint32_t stx_mask;
+    uint32_t stx_blksize;
+    uint64_t stx_attributes;
+    uint32_t stx_nlink;
+    uint32_t stx_uid;
+    uint32_t stx_gid;
+    uint16_t stx_mode;
+    uint16_t __spare0[1];
+    uint64_t stx_ino;
+    uint64_t stx_size;
+    uint64_t stx_blocks;
+    uint64_t stx_attributes_mask;
+    myst_statx_timestamp_t stx_atime;
+    myst_statx_timestamp_t stx_btime;
+    myst_statx_timestamp_t stx_ctime;
+    myst_statx_timestamp_t stx_mtime;
+    uint32_t stx_rdev_major;
+    uint32_t stx_rdev_minor;
+    uint32_t stx_dev_major;
+    uint32_t stx_dev_minor;
+    uint64_t stx_mnt_id;
+    uint64_t __spare2;
+    uint64_t __spare3[12];
+} myst_statx_t;
+
+#endif /* _MYST_STATX_H */
@@ -7,14 +7,18 @@
 #include <dirent.h>
 #include <fcntl.h>
 #include <poll.h>
+#include <signal.h>
 #include <stdbool.h>
 #include <sys/select.h>
+#include <sys/socket.h>
 #include <sys/stat.h>
 #include <sys/syscall.h>
 #include <sys/time.h>
 #include <sys/uio.h>
+#include <sys/utsname.h>
 #include <time.h>
 
+#include <myst/deadline.h>
 #include <myst/defs.h>
 #include <myst/syscallext.h>
 
@@ -114,6 +118,9 @@ long myst_syscall(long n, long params[6]);
 
 const char* syscall_str(long n);
 
+/* find a syscall by name ("open" or "SYS_open"); returns -1 if not found */
+long myst_syscall_num(const char* name);
+
 void myst_set_rootfs(const char* path);
 
 long myst_syscall_ret(long r);
@@ -130,6 +137,14 @@ long myst_syscall_openat(
     int flags,
     mode_t mode);
 
+struct myst_open_how;
+
+long myst_syscall_openat2(
+    int dirfd,
+    const char* pathname,
+    struct myst_open_how* how,
+    size_t size);
+
 long myst_syscall_lseek(int fd, off_t offset, int whence);
 
 long myst_syscall_close(int fd);
@@ -152,6 +167,15 @@ long myst_syscall_lstat(const char* pathname, struct stat* statbuf);
 
 long myst_syscall_fstat(int fd, struct stat* statbuf);
 
+struct myst_statx;
+
+long myst_syscall_statx(
+    int dirfd,
+    const char* pathname,
+    int flags,
+    unsigned int mask,
+    struct myst_statx* statxbuf);
+
 long myst_syscall_mkdir(const char* pathname, mode_t mode);
 
 long myst_syscall_getdents64(int fd, struct dirent* dirp, size_t count);
@@ -164,6 +188,35 @@ long myst_syscall_unlink(const char* pathname);
 
 long myst_syscall_access(const char* pathname, int mode);
 
+long myst_syscall_faccessat(
+    int dirfd,
+    const char* pathname,
+    int mode,
+    int flags);
+
+long myst_syscall_chmod(const char* pathname, mode_t mode);
+
+long myst_syscall_fchmod(int fd, mode_t mode);
+
+long myst_syscall_fchmodat(
+    int dirfd,
+    const char* pathname,
+    mode_t mode,
+    int flags);
+
+long myst_syscall_chown(const char* pathname, uid_t owner, gid_t group);
+
+long myst_syscall_lchown(const char* pathname, uid_t owner, gid_t group);
+
+long myst_syscall_fchown(int fd, uid_t owner, gid_t group);
+
+long myst_syscall_fchownat(
+    int dirfd,
+    const char* pathname,
+    uid_t owner,
+    gid_t group,
+    int flags);
+
 long myst_syscall_rename(const char* oldpath, const char* newpath);
 
 long myst_syscall_truncate(const char* path, off_t length);
@@ -178,6 +231,10 @@ long myst_syscall_chdir(const char* path);
 
 long myst_syscall_getcwd(char* buf, size_t size);
 
+long myst_syscall_fchdir(int fd);
+
+long myst_syscall_chroot(const char* path);
+
 long myst_syscall_fcntl(int fd, int cmd, long arg);
 
 long myst_syscall_add_symbol_file(
@@ -195,6 +252,11 @@ long myst_syscall_clock_gettime(clockid_t clk_id, struct timespec* tp);
 
 long myst_syscall_clock_settime(clockid_t clk_id, struct timespec* tp);
 
+struct timex;
+
+/* report what the host says about the realtime clock (modes must be 0) */
+long myst_syscall_clock_adjtime(clockid_t clk_id, struct timex* tx);
+
 long myst_syscall_gettimeofday(struct timeval* tv, struct timezone* tz);
 
 long myst_syscall_time(time_t* tloc);
@@ -208,6 +270,11 @@ long myst_syscall_clone(
     void* newtls,
     pid_t* ctid);
 
+long myst_syscall_execve(
+    const char* filename,
+    char* const argv[],
+    char* const envp[]);
+
 long myst_syscall_futex(
     int* uaddr,
     int op,
@@ -228,6 +295,12 @@ long myst_syscall_wait4(
 
 long myst_syscall_poll(struct pollfd* fds, nfds_t nfds, int timeout);
 
+/* poll until there are events or the deadline passes */
+long myst_poll_deadline(
+    struct pollfd* fds,
+    nfds_t nfds,
+    const myst_deadline_t* deadline);
+
 long myst_syscall_select(
     int nfds,
     fd_set* readfds,
@@ -241,6 +314,8 @@ long myst_syscall_exit_group(int status);
 
 long myst_syscall_tgkill(int tgid, int tid, int sig);
 
+long myst_syscall_tkill(int tid, int sig);
+
 long myst_syscall_mount(
     const char* source,
     const char* target,
@@ -259,8 +334,24 @@ long myst_syscall_sendfile(int out_fd, int in_fd, off_t* offset, size_t count);
 
 long myst_syscall_sethostname(const char* hostname, size_t len);
 
+long myst_syscall_setdomainname(const char* name, size_t len);
+
+/* override the release, version and machine strings returned by uname() */
+int myst_set_uname(
+    const char* release,
+    const char* version,
+    const char* machine);
+
 long myst_syscall_umask(mode_t mask);
 
+long myst_syscall_prctl(int option, unsigned long arg2);
+
+long myst_syscall_arch_prctl(int code, unsigned long addr);
+
+long myst_syscall_personality(unsigned long persona);
+
+long myst_syscall_uname(struct utsname* buf);
+
 long myst_syscall_run_itimer(void);
 
 long myst_syscall_setitimer(
@@ -270,6 +361,70 @@ long myst_syscall_setitimer(
 
 int myst_syscall_getitimer(int which, struct itimerval* curr_value);
 
+long myst_syscall_timer_create(
+    clockid_t clockid,
+    const struct sigevent* sevp,
+    int* timerid);
+
+long myst_syscall_timer_settime(
+    int timerid,
+    int flags,
+    const struct itimerspec* new_value,
+    struct itimerspec* old_value);
+
+long myst_syscall_timer_gettime(int timerid, struct itimerspec* curr_value);
+
+long myst_syscall_timer_getoverrun(int timerid);
+
+long myst_syscall_timer_delete(int timerid);
+
+/* delete the POSIX timers of a process that is exiting */
+void myst_release_process_timers(pid_t pid);
+
+/* move the absolute CLOCK_REALTIME timers, as CLOCK_REALTIME was set forward
+ * (or back) by delta nanoseconds */
+void myst_timers_clock_was_set(long delta);
+
+/* move the timers likewise when the host steps CLOCK_REALTIME (checked from
+ * time to time on the work queue; see kernel/work.c) */
+void myst_clock_resync_start(void);
+
+long myst_syscall_timerfd_create(int clockid, int flags);
+
+long myst_syscall_timerfd_settime(
+    int fd,
+    int flags,
+    const struct itimerspec* new_value,
+    struct itimerspec* old_value);
+
+long myst_syscall_timerfd_gettime(int fd, struct itimerspec* curr_value);
+
 long myst_syscall_fsync(int fd);
 
+long myst_syscall_accept4(
+    int sockfd,
+    struct sockaddr* addr,
+    socklen_t* addrlen,
+    int flags);
+
+struct myst_io_uring_params;
+
+long myst_syscall_io_uring_setup(
+    uint32_t entries,
+    struct myst_io_uring_params* params);
+
+long myst_syscall_io_uring_enter(
+    int fd,
+    uint32_t to_submit,
+    uint32_t min_complete,
+    uint32_t flags,
+    const sigset_t* sig,
+    size_t sigsz);
+
+long myst_syscall_io_uring_register(
+    int fd,
+    uint32_t opcode,
+    void* arg,
+    uint32_t nr_args);
+
 #endif /* _MYST_SYSCALL_H */
@@ -7,6 +7,27 @@
 #include <myst/types.h>
 #include <sys/types.h>
 
+/* Linux syscalls that older C library headers may not define */
+#ifndef SYS_io_uring_setup
+#define SYS_io_uring_setup 425
+#endif
+
+#ifndef SYS_io_uring_enter
+#define SYS_io_uring_enter 426
+#endif
+
+#ifndef SYS_io_uring_register
+#define SYS_io_uring_register 427
+#endif
+
+#ifndef SYS_openat2
+#define SYS_openat2 437
+#endif
+
+#ifndef SYS_faccessat2
+#define SYS_faccessat2 439
+#endif
+
 /* myst-specific syscalls */
 enum
 {
@@ -46,6 +67,9 @@ enum
     SYS_myst_oe_free_attestation_certificate,
     SYS_myst_oe_verify_attestation_certificate,
     SYS_myst_oe_result_str,
+
+    /* after the Open Enclave extensions so as to keep their numbers */
+    SYS_myst_run_work,
 };
 
 #endif /* _MYST_SYSCALLEXT_H */
@@ -0,0 +1,77 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_SYSCALLPOLICY_H
+#define _MYST_SYSCALLPOLICY_H
+
+#include <limits.h>
+#include <stdbool.h>
+#include <stddef.h>
+
+#include <myst/resolve.h>
+
+/* a rule as written in the "SyscallPolicy" section of config.json */
+typedef struct myst_syscall_rule
+{
+    /* the syscall name (e.g., "open" or "SYS_open") */
+    const char* syscall;
+
+    /* "allow", "errno" or "kill" */
+    const char* action;
+
+    /* the error name returned by the "errno" action (default "EPERM") */
+    const char* error;
+
+    /* open(), openat(), openat2() and creat(): a pattern matched against the
+     * path */
+    const char* path;
+
+    /* connect(): an IPv4 address with an optional prefix length (e.g.,
+     * "10.0.0.0/8") or "unix:" followed by a pattern for the socket path */
+    const char* address;
+
+    /* connect(): the destination port (zero matches any port) */
+    long port;
+} myst_syscall_rule_t;
+
+typedef struct myst_syscall_policy
+{
+    /* the action taken when no rule matches (default "allow") */
+    const char* default_action;
+
+    /* the error name returned by the default "errno" action */
+    const char* default_error;
+
+    /* the rules (the first matching rule wins) */
+    myst_syscall_rule_t* rules;
+    size_t num_rules;
+} myst_syscall_policy_t;
+
+int myst_syscall_policy_setup(const myst_syscall_policy_t* policy);
+
+bool myst_syscall_policy_enabled(void);
+
+/* the arguments of an open call, copied from the application */
+typedef struct myst_syscall_policy_args
+{
+    char path[PATH_MAX];
+    myst_open_how_t how;
+} myst_syscall_policy_args_t;
+
+/* whether a path rule names syscall n */
+bool myst_syscall_policy_checks_path(long n);
+
+/* copy the path of an open call (and the struct open_how of openat2) into
+ * args and point params at the copies, so that the call opens the path that
+ * the rules were checked against: 0 or a negative error number */
+long myst_syscall_policy_copy_args(
+    long n,
+    long params[6],
+    myst_syscall_policy_args_t* args);
+
+/* return zero if the syscall is allowed or the negative error number that
+ * the syscall should fail with; the "kill" action terminates the process
+ * when the calling thread leaves the kernel */
+long myst_syscall_policy_check(long n, const long params[6]);
+
+#endif /* _MYST_SYSCALLPOLICY_H */
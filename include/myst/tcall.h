@@ -12,8 +12,12 @@
 #include <unistd.h>
 
 #include <myst/blockdevice.h>
+#include <myst/coredump.h>
 #include <myst/defs.h>
 #include <myst/fssig.h>
+#include <myst/keyrelease.h>
+#include <myst/secret.h>
+#include <myst/stackguard.h>
 
 typedef enum myst_tcall_number
 {
@@ -53,10 +57,28 @@ typedef enum myst_tcall_number
     MYST_TCALL_VERIFY_SIGNATURE = 2081,
     MYST_TCALL_LOAD_FSSIG = 2082,
     MYST_TCALL_CLOCK_GETRES = 2083,
+    MYST_TCALL_EXPORT_COVERAGE = 2084,
+    MYST_TCALL_WRITE_LOG = 2085,
+    MYST_TCALL_KEY_RELEASE = 2086,
+    MYST_TCALL_UNSEAL_SECRET = 2087,
+    MYST_TCALL_ATTESTED_HTTPS_GET = 2088,
+    MYST_TCALL_SET_CRASH_HANDLER = 2089,
+    MYST_TCALL_RSA_OAEP_ENCRYPT = 2090,
+    MYST_TCALL_AES_GCM_ENCRYPT = 2091,
+    MYST_TCALL_CLOCK_ADJTIME = 2092,
+    MYST_TCALL_GET_BLOCK_DEVICE_SIZE = 2093,
+    MYST_TCALL_SYNC_BLOCK_DEVICE = 2094,
+    MYST_TCALL_MPROTECT = 2095,
+    MYST_TCALL_SET_FAULT_HANDLER = 2096,
 } myst_tcall_number_t;
 
 long myst_tcall(long n, long params[6]);
 
+struct timex;
+
+/* the state of the realtime clock, as for adjtimex() with modes 0 */
+long myst_tcall_clock_adjtime(clockid_t clk_id, struct timex* tx);
+
 typedef long (*myst_tcall_t)(long n, long params[6]);
 
 long myst_tcall_random(void* data, size_t size);
@@ -73,6 +95,14 @@ long myst_tcall_read_console(int fd, void* buf, size_t count);
 
 long myst_tcall_write_console(int fd, const void* buf, size_t count);
 
+/* append to a host log file, rotating it when it exceeds max_size bytes */
+long myst_tcall_write_log(
+    const char* path,
+    const void* buf,
+    size_t count,
+    size_t max_size,
+    size_t max_files);
+
 long myst_tcall_create_thread(uint64_t cookie);
 
 long myst_tcall_wait(uint64_t event, const struct timespec* timeout);
@@ -86,6 +116,11 @@ long myst_tcall_wake_wait(
 
 long myst_tcall_export_file(const char* path, const void* data, size_t size);
 
+long myst_tcall_export_coverage(
+    const char* path,
+    const void* data,
+    size_t size);
+
 long myst_tcall_add_symbol_file(
     const void* file_data,
     size_t file_size,
@@ -122,6 +157,10 @@ long myst_tcall_poll_wake(void);
 
 long myst_tcall_poll(struct pollfd* fds, nfds_t nfds, int timeout);
 
+/* set the protection of kernel pages where the target can (-ENOTSUP where
+ * it cannot, as in SGX1 enclaves) */
+long myst_tcall_mprotect(void* addr, size_t length, int prot);
+
 int myst_tcall_open_block_device(const char* path, bool read_only);
 
 int myst_tcall_close_block_device(int blkdev);
@@ -138,6 +177,10 @@ int myst_tcall_read_block_device(
     struct myst_block* blocks,
     size_t num_blocks);
 
+int myst_tcall_get_block_device_size(int blkdev, uint64_t* size);
+
+int myst_tcall_sync_block_device(int blkdev);
+
 int myst_tcall_verify_signature(
     const char* pem_public_key,
     const uint8_t* hash,
@@ -149,4 +192,53 @@ int myst_tcall_verify_signature(
 
 int myst_tcall_load_fssig(const char* path, myst_fssig_t* fssig);
 
+/* attest the enclave and retrieve a key from the key store (SGX only) */
+long myst_tcall_key_release(
+    const myst_key_release_request_t* request,
+    void* key,
+    size_t key_size,
+    size_t* key_size_out);
+
+/* unseal a blob made by oe_seal() with this enclave's signer (SGX only) */
+long myst_tcall_unseal_secret(
+    const void* blob,
+    size_t blob_size,
+    void* buf,
+    size_t size,
+    size_t* size_out);
+
+/* attest the enclave to an HTTPS server with an attested client certificate
+ * and return the response body (SGX only) */
+long myst_tcall_attested_https_get(
+    const myst_attested_https_request_t* request,
+    void* buf,
+    size_t size,
+    size_t* size_out);
+
+/* called by the target when an exception it cannot handle kills a thread */
+long myst_tcall_set_crash_handler(myst_crash_handler_t handler);
+
+/* called by the target for page faults that it does not handle itself
+ * (-ENOTSUP on targets without an exception handler) */
+long myst_tcall_set_fault_handler(myst_fault_handler_t handler);
+
+/* encrypt to an RSA public key (PEM, zero-terminated) with RSA-OAEP and
+ * SHA-256 */
+long myst_tcall_rsa_oaep_encrypt(
+    const char* pem_public_key,
+    const void* in,
+    size_t in_size,
+    void* out,
+    size_t out_size,
+    size_t* out_size_out);
+
+/* AES-256-GCM with a 32-byte key and a 12-byte nonce; writes a 16-byte tag */
+long myst_tcall_aes_gcm_encrypt(
+    const uint8_t key[32],
+    const uint8_t nonce[12],
+    const void* in,
+    size_t size,
+    void* out,
+    uint8_t tag[16]);
+
 #endif /* _MYST_TCALL_H */
@@ -9,9 +9,12 @@
 #include <unistd.h>
 
 #include <myst/assume.h>
+#include <myst/budget.h>
 #include <myst/defs.h>
 #include <myst/fdtable.h>
+#include <myst/rusage.h>
 #include <myst/setjmp.h>
+#include <myst/sigsafe.h>
 #include <myst/spinlock.h>
 #include <myst/tcall.h>
 #include <myst/types.h>
@@ -92,6 +95,12 @@ struct myst_thread
     /* Timespec at when the thread last crossed over to userspace */
     struct timespec leave_kernel_ts;
 
+    /* The resource usage of this thread (see kernel/rusage.c) */
+    myst_rusage_t rusage;
+
+    /* Nanoseconds spent blocked in the target since entering the kernel */
+    long blocked_nsecs;
+
     /* the C-runtime thread descriptor */
     myst_td_t* crt_td;
 
@@ -127,6 +136,7 @@ struct myst_thread
     {
         /* the stack that was created by myst_exec() */
         void* exec_stack;
+        size_t exec_stack_size;
 
         /* the copy of the CRT data made by myst_exec() */
         void* exec_crt_data;
@@ -146,10 +156,35 @@ struct myst_thread
         char* cwd;
         myst_spinlock_t cwd_lock;
 
+        /* process root directory set by chroot() as a real path (NULL for
+         * "/"). The cwd above is relative to this root */
+        char* root;
+        myst_spinlock_t root_lock;
+
         /* The current umask this process */
         mode_t umask;
         myst_spinlock_t umask_lock;
 
+        /* The PR_SET_DUMPABLE attribute (whether to dump core on a crash) */
+        int dumpable;
+
+        /* The execution domain and flags set with personality() */
+        unsigned long personality;
+
+        /* The current and peak resident set size (in bytes) */
+        size_t rss;
+        size_t maxrss;
+
+        /* The usage of reaped children (see myst_rusage_reap()) */
+        myst_rusage_t children_rusage;
+        bool reaped;
+
+        /* The CPU and wall-clock budget (see kernel/budget.c) */
+        myst_budget_t budget;
+
+        /* Whether the program is exempt from WriteXorExecute */
+        bool wx_exempt;
+
     } main;
 
     volatile _Atomic enum myst_thread_status status;
@@ -167,6 +202,11 @@ struct myst_thread
         /* The mask of blocked signals */
         uint64_t mask;
 
+        /* The mask to restore when the syscall returns, after delivering the
+         * signals that the mask of the syscall let in (as for ppoll) */
+        uint64_t saved_mask;
+        bool restore_mask;
+
         /* The pending signals */
         _Atomic uint64_t pending;
 
@@ -175,8 +215,35 @@ struct myst_thread
 
         /* The list of siginfo_t for pending signals */
         siginfo_t* siginfos[NSIG - 1];
+
+        /* The alternate signal stack set by sigaltstack() */
+        stack_t altstack;
+
+        /* The handler call in progress on the alternate stack */
+        void* altstack_call;
+
+        /* The kernel mutexes held, for DebugSignalSafety (see sigsafe.h) */
+        myst_sigsafe_t sigsafe;
     } signal;
 
+    /* the guard below the stack of this thread (see kernel/stackguard.c) */
+    struct
+    {
+        /* the guard that is filled with a pattern and checked */
+        uint8_t* addr;
+        size_t length;
+
+        /* the guard that is inaccessible instead */
+        uint8_t* protected_addr;
+        size_t protected_length;
+
+        /* the stack pointer that the thread started with */
+        uint8_t* start_sp;
+
+        /* where the thread faulted in the inaccessible guard */
+        uint8_t* fault;
+    } stack_guard;
+
     /* the parameters passed to the munmap syscall by __unmapself() */
     void* unmapself_addr;
     size_t unmapself_length;
@@ -190,6 +257,24 @@ struct myst_thread
 
     /* thread name */
     char name[16];
+
+    /* scheduling attributes (see kernel/sched.c) */
+    struct
+    {
+        int nice;
+        int policy;
+        int priority;
+        int reset_on_fork;
+
+        /* the virtual CPU reported by getcpu() and whether the thread holds
+         * it exclusively */
+        int cpu;
+        int cpu_held;
+    } sched;
+
+    /* the list of robust futexes registered with SYS_set_robust_list */
+    struct robust_list_head* robust_list_head;
+    size_t robust_list_len;
 };
 
 MYST_INLINE bool myst_valid_thread(const myst_thread_t* thread)
@@ -264,6 +349,31 @@ MYST_INLINE bool myst_thread_queue_contains(
     return false;
 }
 
+/* take the thread out of the queue (if it is there) */
+MYST_INLINE void myst_thread_queue_remove(
+    myst_thread_queue_t* queue,
+    myst_thread_t* thread)
+{
+    myst_thread_t* prev = NULL;
+
+    for (myst_thread_t* p = queue->front; p; prev = p, p = p->qnext)
+    {
+        if (p == thread)
+        {
+            if (prev)
+                prev->qnext = p->qnext;
+            else
+                queue->front = p->qnext;
+
+            if (queue->back == p)
+                queue->back = prev;
+
+            p->qnext = NULL;
+            break;
+        }
+    }
+}
+
 MYST_INLINE bool myst_thread_queue_empty(myst_thread_queue_t* queue)
 {
     return queue->front ? false : true;
@@ -296,6 +406,12 @@ int myst_get_num_threads(void);
 
 myst_thread_t* myst_find_thread(int tid);
 
+/* find thread tid of process tgid (of any process if tgid is -1) */
+myst_thread_t* myst_find_thread_by_tid(pid_t tgid, pid_t tid);
+
+/* find the process thread for pid (caller holds myst_process_list_lock) */
+myst_thread_t* myst_find_process_by_pid(pid_t pid);
+
 size_t myst_kill_thread_group();
 
 MYST_INLINE char* myst_get_thread_name(myst_thread_t* thread)
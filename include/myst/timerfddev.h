@@ -0,0 +1,46 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_TIMERFDDEV_H
+#define _MYST_TIMERFDDEV_H
+
+#include <sys/types.h>
+#include <time.h>
+
+#include <myst/defs.h>
+#include <myst/fdops.h>
+
+typedef struct myst_timerfddev myst_timerfddev_t;
+
+typedef struct myst_timerfd myst_timerfd_t;
+
+struct myst_timerfddev
+{
+    myst_fdops_t fdops;
+
+    int (*td_create)(
+        myst_timerfddev_t* timerfddev,
+        clockid_t clockid,
+        int flags,
+        myst_timerfd_t** timerfd);
+
+    int (*td_settime)(
+        myst_timerfddev_t* timerfddev,
+        myst_timerfd_t* timerfd,
+        int flags,
+        const struct itimerspec* new_value,
+        struct itimerspec* old_value);
+
+    int (*td_gettime)(
+        myst_timerfddev_t* timerfddev,
+        myst_timerfd_t* timerfd,
+        struct itimerspec* curr_value);
+};
+
+myst_timerfddev_t* myst_timerfddev_get(void);
+
+/* CLOCK_REALTIME was set forward (or back) by delta nanoseconds: move the
+ * absolute realtime timers and cancel those set with TFD_TIMER_CANCEL_ON_SET */
+void myst_timerfd_clock_was_set(long delta);
+
+#endif /* _MYST_TIMERFDDEV_H */
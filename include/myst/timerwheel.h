@@ -0,0 +1,52 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_TIMERWHEEL_H
+#define _MYST_TIMERWHEEL_H
+
+#include <stdbool.h>
+
+/* the wheel runs timers to the millisecond */
+#define MYST_TIMER_TICK 1000000L
+
+typedef struct myst_timer myst_timer_t;
+
+/* Called (once the timer expires) on the timer thread with the wheel
+ * locked, so it must neither block nor call the functions below. Return
+ * the monotonic time to run again at, or 0 to leave the timer disarmed */
+typedef long (*myst_timer_callback_t)(myst_timer_t* timer, long now);
+
+struct myst_timer
+{
+    myst_timer_t* prev;
+    myst_timer_t* next;
+
+    /* CLOCK_MONOTONIC nanoseconds */
+    long expires;
+
+    /* the wheel slot that holds the timer (-1 when disarmed) */
+    int slot;
+
+    myst_timer_callback_t callback;
+    void* arg;
+};
+
+void myst_timer_init(
+    myst_timer_t* timer,
+    myst_timer_callback_t callback,
+    void* arg);
+
+/* arm (or rearm) the timer to expire at the given CLOCK_MONOTONIC time */
+void myst_timer_add(myst_timer_t* timer, long expires);
+
+/* disarm the timer: once this returns its callback is not running and does
+ * not run again; returns whether it was armed */
+bool myst_timer_cancel(myst_timer_t* timer);
+
+/* when the timer expires (0 if it is disarmed) */
+long myst_timer_expires(myst_timer_t* timer);
+
+/* run the expired timers and wait for the next ones (never returns) */
+long myst_timer_run(void);
+
+#endif /* _MYST_TIMERWHEEL_H */
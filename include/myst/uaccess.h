@@ -0,0 +1,61 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_UACCESS_H
+#define _MYST_UACCESS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+/*
+** Access to user memory at the syscall boundary. The kernel shares the
+** address space with the application, so it could dereference the pointers
+** that a syscall passes, but the application could then have the kernel
+** read or write memory that is not the application's: the kernel's own data
+** or memory outside the enclave. The syscalls instead copy their arguments
+** in and their results out with these functions, which fail with -EFAULT
+** unless the range lies within the mman region (where all the memory of the
+** application is) and, for objects, is aligned for the type.
+**
+** In MYST_DEBUG builds, the kernel buffer of a failed copy-in is poisoned
+** with MYST_UACCESS_POISON, so a caller that ignores the error reads a
+** pattern that stands out rather than stale kernel data.
+*/
+
+#define MYST_UACCESS_POISON 0xa5
+
+/* whether [addr, addr + size) is user memory */
+bool myst_is_user_range(const void* addr, size_t size);
+
+/* copy size bytes from or to user memory (0 or -EFAULT) */
+long myst_copy_from_user(void* dst, const void* src, size_t size);
+
+long myst_copy_to_user(void* dst, const void* src, size_t size);
+
+/* the same where the user pointer must be a multiple of align */
+long myst_copy_from_user_aligned(
+    void* dst,
+    const void* src,
+    size_t size,
+    size_t align);
+
+long myst_copy_to_user_aligned(
+    void* dst,
+    const void* src,
+    size_t size,
+    size_t align);
+
+/* copy a string of at most size bytes (with the null terminator) from user
+ * memory: returns its length, -EFAULT or -ENAMETOOLONG */
+long myst_strncpy_from_user(char* dst, const char* src, size_t size);
+
+/* copy an object in or out: the user pointer must be aligned for its type */
+#define MYST_COPY_FROM_USER(DST, SRC) \
+    myst_copy_from_user_aligned(      \
+        DST, SRC, sizeof(*(DST)), __alignof__(*(DST)))
+
+#define MYST_COPY_TO_USER(DST, SRC) \
+    myst_copy_to_user_aligned(      \
+        DST, SRC, sizeof(*(SRC)), __alignof__(*(SRC)))
+
+#endif /* _MYST_UACCESS_H */
@@ -0,0 +1,79 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_WORK_H
+#define _MYST_WORK_H
+
+#include <stdbool.h>
+
+/*
+** Background work for the kernel. The enclave has a fixed number of threads
+** (TCS), so a subsystem that needs to do something later or from time to
+** time (refresh a cache, poll the host) queues a work item instead of
+** creating a thread of its own. The items are run cooperatively by the
+** threads of the application as they enter the kernel (see kernel/work.c),
+** one thread at a time and a few items per kernel entry. The few that take
+** long are run by a single worker thread instead.
+*/
+
+typedef struct myst_work myst_work_t;
+
+/* Called on a thread that has just entered the kernel (or on the worker
+ * thread), without any kernel locks held. It may lock mutexes and call the
+ * host but should not take long, unless it is worker work, as the thread
+ * belongs to the application. Return the monotonic time to run again at, or
+ * 0 to leave the work unqueued */
+typedef long (*myst_work_callback_t)(myst_work_t* work, long now);
+
+struct myst_work
+{
+    myst_work_t* next;
+
+    /* CLOCK_MONOTONIC nanoseconds */
+    long when;
+
+    /* whether the work is in the queue, running or cancelled while it ran */
+    bool queued;
+    bool running;
+    bool cancelled;
+
+    /* whether only the worker thread runs the work */
+    bool worker;
+
+    const char* name;
+    myst_work_callback_t callback;
+    void* arg;
+};
+
+void myst_work_init(
+    myst_work_t* work,
+    const char* name,
+    myst_work_callback_t callback,
+    void* arg);
+
+/* as above for work that takes too long to run in a syscall of the
+ * application, which only the worker thread runs */
+void myst_work_init_worker(
+    myst_work_t* work,
+    const char* name,
+    myst_work_callback_t callback,
+    void* arg);
+
+/* queue (or requeue) the work to run at the given CLOCK_MONOTONIC time, or
+ * as soon as possible if that is 0 */
+void myst_work_queue(myst_work_t* work, long when);
+
+/* unqueue the work: once this returns its callback is not running (unless
+ * this is called from the callback) and does not run again; returns whether
+ * it was queued */
+bool myst_work_cancel(myst_work_t* work);
+
+/* run the work that is due (called at every kernel entry) */
+void myst_work_run(void);
+
+/* run the worker work as it comes due (on the worker thread, which the C
+ * runtime starts with SYS_myst_run_work); never returns, unless there is no
+ * worker work (-ENOENT) or another thread is the worker (-EEXIST) */
+long myst_work_run_worker(void);
+
+#endif /* _MYST_WORK_H */
@@ -0,0 +1,33 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_WXPOLICY_H
+#define _MYST_WXPOLICY_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+/* the "WriteXorExecute" settings of config.json */
+typedef struct myst_wx_config
+{
+    /* deny mappings that are both writable and executable */
+    bool enabled;
+
+    /* the programs (by absolute path) that may still have such mappings,
+     * such as JIT compilers ("WriteXorExecuteAllow") */
+    const char** allow;
+    size_t num_allow;
+} myst_wx_config_t;
+
+struct myst_thread;
+
+int myst_wx_setup(const myst_wx_config_t* config);
+
+/* record whether the process that is now running path is exempt */
+void myst_wx_exec(struct myst_thread* process, const char* path);
+
+/* return zero if the mmap() or mprotect() of the calling process may have
+ * the given protection, or else -EACCES (and the kernel logs it) */
+long myst_wx_check(long n, const void* addr, size_t length, int prot);
+
+#endif /* _MYST_WXPOLICY_H */
@@ -0,0 +1,498 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <netinet/in.h>
+#include <stdarg.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/un.h>
+#include <syscall.h>
+#include <time.h>
+
+#include <myst/atexit.h>
+#include <myst/audit.h>
+#include <myst/eraise.h>
+#include <myst/process.h>
+#include <myst/spinlock.h>
+#include <myst/syscall.h>
+#include <myst/syscallext.h>
+#include <myst/tcall.h>
+
+/*
+**==============================================================================
+**
+** Audit log:
+**
+** Security-relevant events are appended to a hash chain, where the hash of
+** each record covers the hash of the previous record:
+**
+**     hash[0] = 32 zero bytes
+**     hash[i] = SHA-256(hash[i-1] || text[i])
+**
+** The most recent records are kept in memory (see /proc/myst/audit) and every
+** record is streamed to the host file given by "AuditLogPath". At exit, the
+** kernel appends the head of the chain together with an enclave report whose
+** report data is the head hash, so a verifier can detect records that the host
+** removed, reordered or altered. See doc/audit-log.md for the format.
+**
+**==============================================================================
+*/
+
+/* OE_REPORT_FLAGS_REMOTE_ATTESTATION */
+#define REPORT_FLAGS_REMOTE_ATTESTATION 0x00000001
+
+#define HASH_HEX_SIZE (MYST_SHA256_SIZE * 2 + 1)
+
+/* "<hash> <text>\n" */
+#define LINE_SIZE (HASH_HEX_SIZE + MYST_AUDIT_RECORD_SIZE + 1)
+
+typedef struct record
+{
+    uint64_t seq;
+    myst_sha256_t hash;
+    char text[MYST_AUDIT_RECORD_SIZE];
+} record_t;
+
+static const char* _type_names[] = {
+    [MYST_AUDIT_EXEC] = "EXEC",
+    [MYST_AUDIT_MOUNT] = "MOUNT",
+    [MYST_AUDIT_CONNECT] = "CONNECT",
+    [MYST_AUDIT_ATTESTATION] = "ATTESTATION",
+};
+
+static bool _enabled;
+static const char* _export_path;
+static myst_spinlock_t _lock = MYST_SPINLOCK_INITIALIZER;
+
+/* ring of the most recent records (indexed by seq % MYST_AUDIT_MAX_RECORDS) */
+static record_t* _records;
+
+/* sequence number and hash of the most recent record */
+static uint64_t _seq;
+static myst_sha256_t _head;
+
+/* sequence number and hash of the record before the oldest retained one */
+static uint64_t _base_seq;
+static myst_sha256_t _base;
+
+/* formatting buffer for exported lines (protected by _lock) */
+static char _line[LINE_SIZE];
+
+static void _hex(char* out, const void* data, size_t size)
+{
+    const uint8_t* p = data;
+    static const char _digits[] = "0123456789abcdef";
+
+    for (size_t i = 0; i < size; i++)
+    {
+        *out++ = _digits[p[i] >> 4];
+        *out++ = _digits[p[i] & 0x0f];
+    }
+
+    *out = '\0';
+}
+
+/* escape bytes that would break the line-oriented format */
+static size_t _escape(char* out, size_t size, const char* in)
+{
+    size_t n = 0;
+
+    for (const uint8_t* p = (const uint8_t*)in; *p; p++)
+    {
+        if (*p < 0x20 || *p >= 0x7f || *p == '\\')
+        {
+            if (n + 4 >= size)
+                break;
+
+            snprintf(out + n, 5, "\\x%02x", *p);
+            n += 4;
+        }
+        else
+        {
+            if (n + 1 >= size)
+                break;
+
+            out[n++] = (char)*p;
+        }
+    }
+
+    out[n] = '\0';
+    return n;
+}
+
+/* send a line to the host file (the caller holds the lock) */
+static void _export(const char* line)
+{
+    if (_export_path)
+        myst_tcall_write_log(_export_path, line, strlen(line), 0, 0);
+}
+
+static void _export_start(void)
+{
+    char hex[HASH_HEX_SIZE];
+
+    _hex(hex, _head.data, sizeof(_head.data));
+    snprintf(_line, sizeof(_line), "start %lu %s\n", _seq, hex);
+    _export(_line);
+}
+
+static void _format_line(char* buf, size_t size, const record_t* r)
+{
+    char hex[HASH_HEX_SIZE];
+
+    _hex(hex, r->hash.data, sizeof(r->hash.data));
+    snprintf(buf, size, "%s %s\n", hex, r->text);
+}
+
+/* request a report whose report data is the head hash and append it */
+static void _atexit_export(void* arg)
+{
+    uint64_t seq;
+    myst_sha256_t hash;
+    char hex[HASH_HEX_SIZE];
+    uint8_t* report = NULL;
+    size_t report_size = 0;
+    char* line = NULL;
+    size_t line_size;
+    int n;
+
+    (void)arg;
+
+    myst_audit_head(&seq, &hash);
+
+    {
+        uint64_t args[] = {
+            REPORT_FLAGS_REMOTE_ATTESTATION,
+            (uint64_t)hash.data,
+            sizeof(hash.data),
+            0,
+            0,
+            (uint64_t)&report,
+            (uint64_t)&report_size,
+        };
+        long params[6] = {(long)args};
+
+        /* fails on targets that cannot produce reports (e.g., linux) */
+        if (myst_tcall(SYS_myst_oe_get_report_v2, params) != 0)
+        {
+            report = NULL;
+            report_size = 0;
+        }
+    }
+
+    line_size = sizeof("head  report=\n") + 20 + HASH_HEX_SIZE +
+                (report_size ? report_size * 2 : sizeof("none"));
+
+    if ((line = malloc(line_size)))
+    {
+        _hex(hex, hash.data, sizeof(hash.data));
+        n = snprintf(line, line_size, "head %lu %s report=", seq, hex);
+
+        if (report)
+        {
+            _hex(line + n, report, report_size);
+            n += (int)(report_size * 2);
+        }
+        else
+        {
+            strcpy(line + n, "none");
+            n += 4;
+        }
+
+        strcpy(line + n, "\n");
+
+        myst_spin_lock(&_lock);
+        _export(line);
+        myst_spin_unlock(&_lock);
+
+        free(line);
+    }
+
+    if (report)
+    {
+        uint64_t args[] = {(uint64_t)report};
+        long params[6] = {(long)args};
+        myst_tcall(SYS_myst_oe_free_report, params);
+    }
+}
+
+int myst_audit_setup(bool enabled, const char* export_path)
+{
+    int ret = 0;
+
+    if (export_path && !enabled)
+        ERAISE(-EINVAL);
+
+    if (!enabled)
+        goto done;
+
+    if (!(_records = calloc(MYST_AUDIT_MAX_RECORDS, sizeof(record_t))))
+        ERAISE(-ENOMEM);
+
+    _export_path = export_path;
+    _enabled = true;
+
+    if (_export_path)
+    {
+        myst_spin_lock(&_lock);
+        _export_start();
+        myst_spin_unlock(&_lock);
+
+        ECHECK(myst_atexit(_atexit_export, NULL));
+    }
+
+done:
+    return ret;
+}
+
+bool myst_audit_enabled(void)
+{
+    return _enabled;
+}
+
+void myst_audit_record(myst_audit_type_t type, const char* format, ...)
+{
+    struct timespec ts = {0};
+    char detail[MYST_AUDIT_RECORD_SIZE];
+    va_list ap;
+    record_t* r;
+    size_t n;
+    myst_sha256_ctx_t ctx;
+
+    if (!_enabled)
+        return;
+
+    va_start(ap, format);
+    vsnprintf(detail, sizeof(detail), format, ap);
+    va_end(ap);
+
+    myst_syscall_clock_gettime(CLOCK_REALTIME, &ts);
+
+    myst_spin_lock(&_lock);
+    {
+        const uint64_t seq = _seq + 1;
+
+        r = &_records[seq % MYST_AUDIT_MAX_RECORDS];
+
+        /* remember the hash of the record that is about to be dropped */
+        if (r->seq)
+        {
+            _base_seq = r->seq;
+            _base = r->hash;
+        }
+
+        r->seq = seq;
+        n = (size_t)snprintf(
+            r->text,
+            sizeof(r->text),
+            "%lu %ld.%09ld %s pid=%d ",
+            seq,
+            ts.tv_sec,
+            ts.tv_nsec,
+            _type_names[type],
+            myst_getpid());
+
+        if (n < sizeof(r->text))
+            _escape(r->text + n, sizeof(r->text) - n, detail);
+
+        myst_sha256_start(&ctx);
+        myst_sha256_update(&ctx, _head.data, sizeof(_head.data));
+        myst_sha256_update(&ctx, r->text, strlen(r->text));
+        myst_sha256_finish(&ctx, &r->hash);
+
+        _seq = seq;
+        _head = r->hash;
+
+        if (_export_path)
+        {
+            _format_line(_line, sizeof(_line), r);
+            _export(_line);
+        }
+    }
+    myst_spin_unlock(&_lock);
+}
+
+void myst_audit_exec(const char* path, size_t argc)
+{
+    myst_audit_record(MYST_AUDIT_EXEC, "path=%s argc=%zu", path, argc);
+}
+
+void myst_audit_mount(
+    const char* source,
+    const char* target,
+    const char* fstype,
+    unsigned long flags,
+    long ret)
+{
+    myst_audit_record(
+        MYST_AUDIT_MOUNT,
+        "source=%s target=%s type=%s flags=0x%lx ret=%ld",
+        source ? source : "(null)",
+        target ? target : "(null)",
+        fstype ? fstype : "(null)",
+        flags,
+        ret);
+}
+
+/* convert a port from network byte order */
+static unsigned int _port(uint16_t port)
+{
+    const uint8_t* p = (const uint8_t*)&port;
+    return (unsigned int)((p[0] << 8) | p[1]);
+}
+
+static void _format_addr(
+    char* buf,
+    size_t size,
+    const struct sockaddr* addr,
+    socklen_t addrlen)
+{
+    if (!addr || addrlen < sizeof(sa_family_t))
+    {
+        snprintf(buf, size, "none");
+    }
+    else if (
+        addr->sa_family == AF_INET && addrlen >= sizeof(struct sockaddr_in))
+    {
+        const struct sockaddr_in* in = (const struct sockaddr_in*)addr;
+        const uint8_t* p = (const uint8_t*)&in->sin_addr;
+
+        snprintf(
+            buf,
+            size,
+            "%u.%u.%u.%u:%u",
+            p[0],
+            p[1],
+            p[2],
+            p[3],
+            _port(in->sin_port));
+    }
+    else if (
+        addr->sa_family == AF_INET6 && addrlen >= sizeof(struct sockaddr_in6))
+    {
+        const struct sockaddr_in6* in6 = (const struct sockaddr_in6*)addr;
+        const uint8_t* p = in6->sin6_addr.s6_addr;
+        size_t n = 0;
+
+        n += (size_t)snprintf(buf + n, size - n, "[");
+
+        for (size_t i = 0; i < 16 && n < size; i += 2)
+        {
+            n += (size_t)snprintf(
+                buf + n,
+                size - n,
+                "%s%x",
+                i ? ":" : "",
+                (p[i] << 8) | p[i + 1]);
+        }
+
+        if (n < size)
+            snprintf(buf + n, size - n, "]:%u", _port(in6->sin6_port));
+    }
+    else if (addr->sa_family == AF_UNIX)
+    {
+        const struct sockaddr_un* un = (const struct sockaddr_un*)addr;
+        const char* path = un->sun_path;
+        const char* prefix = "unix:";
+        size_t n;
+        size_t max = addrlen - offsetof(struct sockaddr_un, sun_path);
+
+        if (max > sizeof(un->sun_path))
+            max = sizeof(un->sun_path);
+
+        /* abstract socket names start with a zero byte */
+        if (max && path[0] == '\0')
+        {
+            prefix = "unix:@";
+            path++;
+            max--;
+        }
+
+        for (n = 0; n < max && path[n]; n++)
+            ;
+
+        snprintf(buf, size, "%s%.*s", prefix, (int)n, path);
+    }
+    else
+    {
+        snprintf(buf, size, "family=%u", addr->sa_family);
+    }
+}
+
+void myst_audit_connect(
+    int sockfd,
+    const struct sockaddr* addr,
+    socklen_t addrlen,
+    long ret)
+{
+    char buf[256];
+
+    if (!_enabled)
+        return;
+
+    _format_addr(buf, sizeof(buf), addr, addrlen);
+    myst_audit_record(
+        MYST_AUDIT_CONNECT, "fd=%d addr=%s ret=%ld", sockfd, buf, ret);
+}
+
+void myst_audit_attestation(const char* request, long ret)
+{
+    myst_audit_record(
+        MYST_AUDIT_ATTESTATION, "request=%s ret=%ld", request, ret);
+}
+
+void myst_audit_head(uint64_t* seq, myst_sha256_t* hash)
+{
+    myst_spin_lock(&_lock);
+    *seq = _seq;
+    *hash = _head;
+    myst_spin_unlock(&_lock);
+}
+
+int myst_audit_format(myst_buf_t* buf)
+{
+    int ret = 0;
+    char line[LINE_SIZE];
+    char hex[HASH_HEX_SIZE];
+    uint64_t first;
+
+    if (!buf)
+        ERAISE(-EINVAL);
+
+    myst_buf_clear(buf);
+
+    if (!_enabled)
+        goto done;
+
+    myst_spin_lock(&_lock);
+    {
+        first = _base_seq + 1;
+
+        _hex(hex, _base.data, sizeof(_base.data));
+        snprintf(line, sizeof(line), "start %lu %s\n", _base_seq, hex);
+        ret = myst_buf_append(buf, line, strlen(line));
+
+        for (uint64_t seq = first; ret == 0 && seq <= _seq; seq++)
+        {
+            _format_line(
+                line, sizeof(line), &_records[seq % MYST_AUDIT_MAX_RECORDS]);
+            ret = myst_buf_append(buf, line, strlen(line));
+        }
+
+        if (ret == 0)
+        {
+            _hex(hex, _head.data, sizeof(_head.data));
+            snprintf(line, sizeof(line), "head %lu %s\n", _seq, hex);
+            ret = myst_buf_append(buf, line, strlen(line));
+        }
+    }
+    myst_spin_unlock(&_lock);
+
+    if (ret != 0)
+        ERAISE(-ENOMEM);
+
+done:
+    return ret;
+}
@@ -0,0 +1,220 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <limits.h>
+#include <signal.h>
+#include <string.h>
+
+#include <myst/budget.h>
+#include <myst/clock.h>
+#include <myst/deadline.h>
+#include <myst/printf.h>
+#include <myst/rusage.h>
+#include <myst/signal.h>
+#include <myst/syscall.h>
+#include <myst/thread.h>
+
+/*
+**==============================================================================
+**
+** CPU and wall-clock budgets:
+**
+** "CpuTimeLimit" and "CpuTimeHardLimit" in config.json set the initial soft
+** and hard RLIMIT_CPU of the processes, and "WallTimeLimit" how long each
+** process may run. As on Linux, a process that uses up its soft CPU limit
+** receives SIGXCPU, and another one for each further second of CPU time,
+** until it uses up its hard limit and is killed with SIGKILL. A process that
+** runs past its wall-clock limit is killed with SIGKILL. The kernel logs
+** every such event.
+**
+** The budget of a process is checked (at most every CHECK_INTERVAL) when one
+** of its threads enters the kernel: Mystikos cannot preempt a thread that
+** runs in user space, and the signals only take effect in the kernel anyway.
+**
+**==============================================================================
+*/
+
+#define CHECK_INTERVAL (10 * 1000000L)
+
+static rlim_t _cpu_soft = RLIM_INFINITY;
+static rlim_t _cpu_hard = RLIM_INFINITY;
+static long _wall;
+
+/* whether any process may have a limit (the checks are skipped if not) */
+static bool _enabled;
+
+static long _seconds_to_nsec(rlim_t seconds)
+{
+    if (seconds == RLIM_INFINITY || seconds > LONG_MAX / NANO_IN_SECOND)
+        return LONG_MAX;
+
+    return (long)seconds * NANO_IN_SECOND;
+}
+
+void myst_budget_setup(size_t cpu_soft, size_t cpu_hard, size_t wall)
+{
+    if (cpu_hard)
+        _cpu_hard = cpu_hard;
+
+    if (cpu_soft)
+        _cpu_soft = cpu_soft;
+
+    /* the soft limit may not exceed the hard limit */
+    if (_cpu_soft > _cpu_hard)
+        _cpu_soft = _cpu_hard;
+
+    if (wall)
+        _wall = _seconds_to_nsec(wall);
+
+    _enabled = _cpu_soft != RLIM_INFINITY || _wall;
+}
+
+void myst_budget_init(myst_thread_t* process, myst_thread_t* parent)
+{
+    myst_budget_t* b = &process->main.budget;
+
+    memset(b, 0, sizeof(myst_budget_t));
+
+    if (parent)
+    {
+        b->cpu_soft = parent->main.budget.cpu_soft;
+        b->cpu_hard = parent->main.budget.cpu_hard;
+    }
+    else
+    {
+        b->cpu_soft = _cpu_soft;
+        b->cpu_hard = _cpu_hard;
+    }
+
+    b->next_sigxcpu = _seconds_to_nsec(b->cpu_soft);
+
+    if (_wall)
+    {
+        long now = myst_deadline_now();
+
+        if (__builtin_saddl_overflow(now, _wall, &b->wall_deadline))
+            b->wall_deadline = LONG_MAX;
+    }
+}
+
+/* kill the process once the calling thread leaves the kernel */
+static void _kill(myst_thread_t* thread, myst_thread_t* process)
+{
+    process->main.budget.killed = true;
+
+    if (thread != process)
+        myst_syscall_kill(process->pid, SIGKILL);
+
+    myst_signal_deliver(thread, SIGKILL, NULL);
+}
+
+void myst_budget_check(myst_thread_t* thread)
+{
+    myst_thread_t* process;
+    myst_budget_t* b;
+    myst_rusage_t usage;
+    long now;
+    long next;
+    long cpu;
+
+    if (!_enabled || !(process = myst_find_process_thread(thread)))
+        return;
+
+    b = &process->main.budget;
+    now = myst_deadline_now();
+    next = __atomic_load_n(&b->next_check, __ATOMIC_ACQUIRE);
+
+    /* one of the threads checks at a time */
+    if (b->killed || now < next ||
+        !__atomic_compare_exchange_n(
+            &b->next_check,
+            &next,
+            now + CHECK_INTERVAL,
+            false,
+            __ATOMIC_ACQ_REL,
+            __ATOMIC_ACQUIRE))
+    {
+        return;
+    }
+
+    if (b->wall_deadline && now >= b->wall_deadline)
+    {
+        myst_eprintf(
+            "kernel: process %d exceeded its wall-clock limit (%ld seconds): "
+            "killed\n",
+            process->pid,
+            _wall / NANO_IN_SECOND);
+        _kill(thread, process);
+        return;
+    }
+
+    if (b->cpu_soft == RLIM_INFINITY ||
+        myst_rusage_get(process, RUSAGE_SELF, &usage) != 0)
+    {
+        return;
+    }
+
+    cpu = usage.utime + usage.stime;
+
+    if (cpu >= _seconds_to_nsec(b->cpu_hard))
+    {
+        myst_eprintf(
+            "kernel: process %d exceeded its hard CPU time limit "
+            "(%lu seconds): killed\n",
+            process->pid,
+            (unsigned long)b->cpu_hard);
+        _kill(thread, process);
+    }
+    else if (cpu >= b->next_sigxcpu)
+    {
+        myst_eprintf(
+            "kernel: process %d exceeded its soft CPU time limit "
+            "(%lu seconds): SIGXCPU\n",
+            process->pid,
+            (unsigned long)b->cpu_soft);
+
+        /* the next one after another second of CPU time */
+        b->next_sigxcpu = (cpu / NANO_IN_SECOND + 1) * NANO_IN_SECOND;
+        myst_syscall_kill(process->pid, SIGXCPU);
+    }
+}
+
+long myst_budget_prlimit(const struct rlimit* new_rlim, struct rlimit* old_rlim)
+{
+    myst_thread_t* process = myst_find_process_thread(myst_thread_self());
+    myst_budget_t* b;
+
+    if (!process)
+        return -ESRCH;
+
+    b = &process->main.budget;
+
+    if (new_rlim)
+    {
+        if (new_rlim->rlim_cur > new_rlim->rlim_max)
+            return -EINVAL;
+
+        /* like an unprivileged process, so that the config stays in force */
+        if (new_rlim->rlim_max > b->cpu_hard)
+            return -EPERM;
+    }
+
+    if (old_rlim)
+    {
+        old_rlim->rlim_cur = b->cpu_soft;
+        old_rlim->rlim_max = b->cpu_hard;
+    }
+
+    if (new_rlim)
+    {
+        b->cpu_soft = new_rlim->rlim_cur;
+        b->cpu_hard = new_rlim->rlim_max;
+        b->next_sigxcpu = _seconds_to_nsec(b->cpu_soft);
+
+        if (b->cpu_soft != RLIM_INFINITY)
+            _enabled = true;
+    }
+
+    return 0;
+}
@@ -10,6 +10,8 @@
 
 #include <myst/cond.h>
 #include <myst/mutex.h>
+#include <myst/shutdown.h>
+#include <myst/sigsafe.h>
 #include <myst/strings.h>
 #include <myst/tcall.h>
 
@@ -47,6 +49,20 @@ int myst_cond_timedwait(
     myst_cond_t* c,
     myst_mutex_t* mutex,
     const struct timespec* timeout)
+{
+    myst_deadline_t deadline;
+    int r;
+
+    if ((r = myst_deadline_init(&deadline, timeout)) != 0)
+        return -r;
+
+    return myst_cond_wait_deadline(c, mutex, &deadline);
+}
+
+int myst_cond_wait_deadline(
+    myst_cond_t* c,
+    myst_mutex_t* mutex,
+    const myst_deadline_t* deadline)
 {
     myst_thread_t* self = myst_thread_self();
     int ret = 0;
@@ -54,13 +70,19 @@ int myst_cond_timedwait(
     assert(self != NULL);
     assert(self->magic == MYST_THREAD_MAGIC);
 
-    if (!c || !mutex)
+    if (!c || !mutex || !deadline)
         return EINVAL;
 
+    myst_sigsafe_assert(__FUNCTION__);
+
     myst_spin_lock(&c->lock);
     {
         myst_thread_t* waiter = NULL;
 
+        /* a shutdown may end the waits that signals interrupt (see
+         * myst_shutdown_interrupted) */
+        const bool interruptible = (self->signal.cond_wait == c);
+
         /* Add the self thread to the end of the wait queue */
         myst_thread_queue_push_back(&c->queue, self);
 
@@ -73,8 +95,26 @@ int myst_cond_timedwait(
 
         for (;;)
         {
+            bool shortened = false;
+            bool interrupted;
+
             myst_spin_unlock(&c->lock);
             {
+                struct timespec buf;
+                struct timespec limit;
+                const struct timespec* timeout;
+
+                /* a wakeup that did not select self waits for the rest */
+                timeout = myst_deadline_remaining_timespec(deadline, &buf);
+
+                if (interruptible)
+                {
+                    const struct timespec* t = timeout;
+
+                    timeout = myst_shutdown_timeout(t, &limit);
+                    shortened = (timeout != t);
+                }
+
                 if (waiter)
                 {
                     ret = (int)myst_tcall_wake_wait(
@@ -86,6 +126,8 @@ int myst_cond_timedwait(
                 {
                     ret = (int)myst_tcall_wait(self->event, timeout);
                 }
+
+                interrupted = interruptible && myst_shutdown_interrupted();
             }
             myst_spin_lock(&c->lock);
 
@@ -93,8 +135,25 @@ int myst_cond_timedwait(
             if (!myst_thread_queue_contains(&c->queue, self))
                 break;
 
+            /* leave the kernel so that the shutdown signals reach self */
+            if (interrupted)
+            {
+                myst_thread_queue_remove(&c->queue, self);
+                ret = EINTR;
+                break;
+            }
+
+            /* the wait was only shortened for the shutdown */
+            if (shortened)
+                ret = 0;
+
+            /* a wait that timed out leaves the queue, or waiting on this
+             * condition again would link self into the queue twice */
             if (ret != 0)
+            {
+                myst_thread_queue_remove(&c->queue, self);
                 break;
+            }
         }
     }
     myst_spin_unlock(&c->lock);
@@ -0,0 +1,251 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+
+#include <myst/atexit.h>
+#include <myst/console.h>
+#include <myst/consolering.h>
+#include <myst/eraise.h>
+#include <myst/spinlock.h>
+#include <myst/syscall.h>
+#include <myst/tcall.h>
+
+/*
+**==============================================================================
+**
+** Console multiplexing:
+**
+** Writes to stdout and stderr go to the host terminal (unless "ConsoleLogOnly"
+** is set) and are also copied, one complete line at a time, to the host log
+** file configured for the stream. Lines are optionally prefixed with a UTC
+** timestamp. The host rotates a log file (path -> path.1 -> path.2 ...) when
+** a write would make it larger than the configured maximum size. Partial lines
+** are flushed when the kernel exits.
+**
+**==============================================================================
+*/
+
+#define LINE_SIZE 4096
+
+/* "YYYY-MM-DDTHH:MM:SS.uuuuuuZ " */
+#define TIMESTAMP_SIZE 32
+
+typedef struct stream
+{
+    const char* path;
+    myst_spinlock_t lock;
+    char line[LINE_SIZE];
+    size_t len;
+    char out[TIMESTAMP_SIZE + LINE_SIZE + 1];
+} stream_t;
+
+static stream_t _streams[STDERR_FILENO + 1];
+
+static myst_console_config_t _config;
+
+/* format the time (see civil_from_days() in Howard Hinnant's date algorithms) */
+static size_t _format_timestamp(char buf[TIMESTAMP_SIZE])
+{
+    struct timespec ts = {0};
+    int64_t days;
+    int64_t secs;
+    int64_t era;
+    int64_t doe;
+    int64_t yoe;
+    int64_t doy;
+    int64_t mp;
+    int64_t year;
+    int64_t month;
+    int64_t day;
+    int n;
+
+    myst_syscall_clock_gettime(CLOCK_REALTIME, &ts);
+
+    days = ts.tv_sec / 86400;
+    secs = ts.tv_sec % 86400;
+
+    days += 719468;
+    era = (days >= 0 ? days : days - 146096) / 146097;
+    doe = days - era * 146097;
+    yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    mp = (5 * doy + 2) / 153;
+    day = doy - (153 * mp + 2) / 5 + 1;
+    month = mp < 10 ? mp + 3 : mp - 9;
+    year = yoe + era * 400 + (month <= 2);
+
+    n = snprintf(
+        buf,
+        TIMESTAMP_SIZE,
+        "%04ld-%02ld-%02ldT%02ld:%02ld:%02ld.%06ldZ ",
+        year,
+        month,
+        day,
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60,
+        ts.tv_nsec / 1000);
+
+    return (n > 0 && n < TIMESTAMP_SIZE) ? (size_t)n : 0;
+}
+
+/* send the buffered line to the host (the caller holds the lock) */
+static long _flush_line(stream_t* s, bool add_newline)
+{
+    long ret = 0;
+    size_t n = 0;
+
+    if (_config.timestamps)
+        n = _format_timestamp(s->out);
+
+    memcpy(s->out + n, s->line, s->len);
+    n += s->len;
+
+    if (add_newline)
+        s->out[n++] = '\n';
+
+    s->len = 0;
+
+    ECHECK(myst_tcall_write_log(
+        s->path, s->out, n, _config.max_size, _config.max_files));
+
+done:
+    return ret;
+}
+
+static long _log(stream_t* s, const char* buf, size_t count)
+{
+    long ret = 0;
+
+    myst_spin_lock(&s->lock);
+
+    while (count)
+    {
+        const char* nl = memchr(buf, '\n', count);
+        size_t n = nl ? (size_t)(nl - buf) + 1 : count;
+
+        if (n > LINE_SIZE - s->len)
+            n = LINE_SIZE - s->len;
+
+        memcpy(s->line + s->len, buf, n);
+        s->len += n;
+        buf += n;
+        count -= n;
+
+        /* flush complete lines and lines that fill the buffer */
+        if (s->line[s->len - 1] == '\n' || s->len == LINE_SIZE)
+        {
+            long r;
+
+            if ((r = _flush_line(s, false)) < 0 && ret == 0)
+                ret = r;
+        }
+    }
+
+    myst_spin_unlock(&s->lock);
+
+    return ret;
+}
+
+static void _atexit_flush(void* arg)
+{
+    (void)arg;
+    myst_console_flush();
+}
+
+int myst_console_setup(const myst_console_config_t* config)
+{
+    int ret = 0;
+
+    if (!config)
+        ERAISE(-EINVAL);
+
+    if (config->max_files > MYST_CONSOLE_MAX_LOG_FILES)
+        ERAISE(-EINVAL);
+
+    /* logging only makes sense if there is somewhere to log to */
+    if (config->log_only && !config->stdout_log && !config->stderr_log)
+        ERAISE(-EINVAL);
+
+    _config = *config;
+
+    /* keep at least one rotated file */
+    if (_config.max_files == 0)
+        _config.max_files = 1;
+
+    _streams[STDOUT_FILENO].path = config->stdout_log;
+    _streams[STDERR_FILENO].path = config->stderr_log;
+
+    if (config->stdout_log || config->stderr_log)
+        ECHECK(myst_atexit(_atexit_flush, NULL));
+
+done:
+    return ret;
+}
+
+long myst_console_write_host(int fd, const void* buf, size_t count)
+{
+    long ret = myst_console_ring_write(fd, buf, count);
+
+    if (ret == -ENOTSUP)
+        ret = myst_tcall_write_console(fd, buf, count);
+
+    return ret;
+}
+
+long myst_console_write(int fd, const void* buf, size_t count)
+{
+    long ret = 0;
+    stream_t* s;
+
+    if (fd != STDOUT_FILENO && fd != STDERR_FILENO)
+        ERAISE(myst_tcall_write_console(fd, buf, count));
+
+    s = &_streams[fd];
+
+    if (!_config.log_only || !s->path)
+    {
+        long n;
+        ECHECK(n = myst_console_write_host(fd, buf, count));
+        count = (size_t)n;
+    }
+
+    if (s->path)
+    {
+        long r = _log(s, buf, count);
+
+        /* log failures only matter when the log is the only output */
+        if (r < 0 && _config.log_only)
+            ERAISE(r);
+    }
+
+    ret = (long)count;
+
+done:
+    return ret;
+}
+
+void myst_console_flush(void)
+{
+    myst_console_ring_flush();
+
+    for (int fd = STDOUT_FILENO; fd <= STDERR_FILENO; fd++)
+    {
+        stream_t* s = &_streams[fd];
+
+        if (!s->path)
+            continue;
+
+        myst_spin_lock(&s->lock);
+
+        if (s->len)
+            _flush_line(s, true);
+
+        myst_spin_unlock(&s->lock);
+    }
+}
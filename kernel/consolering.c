@@ -0,0 +1,158 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <string.h>
+#include <unistd.h>
+
+#include <myst/consolering.h>
+#include <myst/printf.h>
+#include <myst/spinlock.h>
+#include <myst/tcall.h>
+#include <myst/time.h>
+
+/* larger writes are split, so that a record always fits in the ring */
+#define MAX_RECORD (MYST_CONSOLE_RING_CAPACITY / 4)
+
+/* how long to wait for the host to make room or to empty the ring */
+#define WAIT_MSEC 1000
+
+static myst_console_ring_t* _ring;
+static myst_spinlock_t _lock = MYST_SPINLOCK_INITIALIZER;
+
+/* the kernel's copy of the head of the ring */
+static uint64_t _head;
+
+/* the host stopped reading the ring: write to the console directly */
+static volatile bool _stalled;
+
+int myst_console_ring_setup(myst_console_ring_t* ring)
+{
+    if (!ring)
+        return 0;
+
+    if (ring->header.magic != MYST_CONSOLE_RING_MAGIC ||
+        ring->header.version != MYST_CONSOLE_RING_VERSION ||
+        ring->header.size != sizeof(myst_console_ring_t))
+    {
+        myst_eprintf(
+            "kernel: the console ring from the host is not version %u: the "
+            "host launcher does not match the enclave image\n",
+            MYST_CONSOLE_RING_VERSION);
+        return -EINVAL;
+    }
+
+    _head = ring->head;
+    _ring = ring;
+
+    return 0;
+}
+
+/* copy into the ring at the given position (which wraps) */
+static void _copy(uint64_t pos, const void* data, size_t size)
+{
+    size_t offset = pos & (MYST_CONSOLE_RING_CAPACITY - 1);
+    size_t n = MYST_CONSOLE_RING_CAPACITY - offset;
+
+    if (n > size)
+        n = size;
+
+    memcpy(&_ring->data[offset], data, n);
+    memcpy(&_ring->data[0], (const uint8_t*)data + n, size - n);
+}
+
+/* wait for there to be room for the given bytes (with _lock held) */
+static bool _wait_for_room(uint64_t size)
+{
+    for (size_t i = 0; i <= WAIT_MSEC; i++)
+    {
+        uint64_t tail = __atomic_load_n(&_ring->tail, __ATOMIC_ACQUIRE);
+
+        /* the host may say anything about tail: only believe it if it is
+         * behind head */
+        if (_head - tail <= MYST_CONSOLE_RING_CAPACITY - size)
+            return true;
+
+        myst_sleep_msec(1);
+    }
+
+    return false;
+}
+
+long myst_console_ring_write(int fd, const void* buf, size_t count)
+{
+    const uint8_t* p = buf;
+    size_t rem = count;
+    bool stalled = false;
+
+    if (!_ring || _stalled)
+        return -ENOTSUP;
+
+    if (fd != STDOUT_FILENO && fd != STDERR_FILENO)
+        return -EINVAL;
+
+    myst_spin_lock(&_lock);
+
+    while (rem && !_stalled)
+    {
+        myst_console_record_t record;
+        uint64_t size;
+
+        record.fd = (uint32_t)fd;
+        record.size = rem < MAX_RECORD ? rem : MAX_RECORD;
+        size = myst_console_record_size(record.size);
+
+        if (!_wait_for_room(size))
+        {
+            _stalled = stalled = true;
+            break;
+        }
+
+        _copy(_head, &record, sizeof(record));
+        _copy(_head + sizeof(record), p, record.size);
+
+        /* publish the record once it is all there */
+        _head += size;
+        __atomic_store_n(&_ring->head, _head, __ATOMIC_RELEASE);
+
+        p += record.size;
+        rem -= record.size;
+    }
+
+    myst_spin_unlock(&_lock);
+
+    if (stalled)
+    {
+        myst_eprintf(
+            "kernel: the host stopped reading the console ring: writing to "
+            "the console directly\n");
+    }
+
+    /* the rest goes to the host directly */
+    if (rem)
+    {
+        long r = myst_tcall_write_console(fd, p, rem);
+
+        if (r < 0)
+            return rem == count ? r : (long)(count - rem);
+    }
+
+    return (long)count;
+}
+
+void myst_console_ring_flush(void)
+{
+    /* without the lock, as this is called when the kernel panics */
+    if (!_ring || _stalled)
+        return;
+
+    for (size_t i = 0; i < WAIT_MSEC; i++)
+    {
+        uint64_t head = __atomic_load_n(&_head, __ATOMIC_ACQUIRE);
+
+        if (__atomic_load_n(&_ring->tail, __ATOMIC_ACQUIRE) == head)
+            break;
+
+        myst_sleep_msec(1);
+    }
+}
@@ -0,0 +1,617 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <elf.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/procfs.h>
+#include <syscall.h>
+
+#include <myst/coredump.h>
+#include <myst/defs.h>
+#include <myst/eraise.h>
+#include <myst/errno.h>
+#include <myst/file.h>
+#include <myst/fsgs.h>
+#include <myst/id.h>
+#include <myst/mmanutils.h>
+#include <myst/printf.h>
+#include <myst/round.h>
+#include <myst/strings.h>
+#include <myst/tcall.h>
+#include <myst/thread.h>
+
+/*
+**==============================================================================
+**
+** Core dumps:
+**
+** When "CoreDump.Path" is set in config.json, a signal whose default action
+** dumps core (SIGSEGV, SIGABRT, ...) and an exception that the target cannot
+** handle write a minimal ELF core file to core.<pid> in that host directory:
+**
+**     PT_NOTE    NT_PRSTATUS (the crashing thread) and NT_PRPSINFO
+**     PT_LOAD    the live part of the crashing thread's stack, the C runtime
+**                image and the process's mmap() regions, in that order,
+**                until "CoreDump.MaxSize" is reached
+**
+** The enclave's memory is exported to the host, so the operator may give
+** "CoreDump.EncryptionKey", an RSA public key in the root file system. The
+** file is then encrypted with a random AES-256-GCM key, which is wrapped to
+** the operator's key with RSA-OAEP (SHA-256):
+**
+**     "MYSTCORE"  uint32 version (1)  uint32 n  wrapped key[n]
+**     records:    uint32 size  ciphertext[size]  tag[16]
+**
+** Record i is encrypted with the 12-byte nonce holding i (little endian) in
+** its first 8 bytes. The last record is empty, so a truncated file cannot be
+** taken for a complete one.
+**
+**==============================================================================
+*/
+
+#define MAX_REGIONS 256
+#define CHUNK_SIZE (16 * 1024)
+#define TAG_SIZE 16
+#define NONCE_SIZE 12
+#define KEY_SIZE 32
+#define MAX_WRAPPED_KEY_SIZE 1024
+#define VERSION 1
+
+MYST_STATIC_ASSERT(sizeof(myst_coredump_regs_t) == sizeof(elf_gregset_t));
+
+typedef struct region
+{
+    uint64_t addr;
+    uint64_t size;
+} region_t;
+
+typedef struct writer
+{
+    int fd;
+    bool encrypt;
+    uint8_t key[KEY_SIZE];
+    uint64_t index;
+    size_t len;
+    int err;
+} writer_t;
+
+static myst_coredump_config_t _config;
+
+/* the operator's public key (PEM, zero-terminated) */
+static void* _key_data;
+static size_t _key_size;
+
+/* one dump at a time; the buffers are static so that a dump does not need
+ * the heap, which may be what the crash corrupted */
+static volatile int _dumping;
+static uint8_t _buf[CHUNK_SIZE];
+static uint8_t _out[sizeof(uint32_t) + CHUNK_SIZE + TAG_SIZE];
+static region_t _regions[MAX_REGIONS];
+static size_t _num_regions;
+
+static long _host_write(int fd, const void* data, size_t size)
+{
+    const uint8_t* p = data;
+
+    while (size)
+    {
+        long params[6] = {fd, (long)p, (long)size};
+        long n = myst_tcall(SYS_write, params);
+
+        if (n <= 0)
+            return n < 0 ? n : -EIO;
+
+        p += n;
+        size -= (size_t)n;
+    }
+
+    return 0;
+}
+
+static void _flush(writer_t* w, bool final)
+{
+    long r;
+
+    if (w->err || (w->len == 0 && !final))
+        return;
+
+    if (w->encrypt)
+    {
+        uint8_t nonce[NONCE_SIZE] = {0};
+        const uint32_t size = (uint32_t)w->len;
+
+        memcpy(nonce, &w->index, sizeof(w->index));
+        memcpy(_out, &size, sizeof(size));
+
+        if ((r = myst_tcall_aes_gcm_encrypt(
+                 w->key,
+                 nonce,
+                 _buf,
+                 w->len,
+                 _out + sizeof(size),
+                 _out + sizeof(size) + w->len)) != 0)
+        {
+            w->err = (int)r;
+            return;
+        }
+
+        r = _host_write(w->fd, _out, sizeof(size) + w->len + TAG_SIZE);
+        w->index++;
+    }
+    else
+    {
+        r = _host_write(w->fd, _buf, w->len);
+    }
+
+    w->len = 0;
+
+    if (r != 0)
+        w->err = (int)r;
+}
+
+static void _write(writer_t* w, const void* data, size_t size)
+{
+    const uint8_t* p = data;
+
+    while (size && !w->err)
+    {
+        size_t n = CHUNK_SIZE - w->len;
+
+        if (n > size)
+            n = size;
+
+        if (p)
+        {
+            memcpy(_buf + w->len, p, n);
+            p += n;
+        }
+        else
+        {
+            memset(_buf + w->len, 0, n);
+        }
+
+        w->len += n;
+        size -= n;
+
+        if (w->len == CHUNK_SIZE)
+            _flush(w, false);
+    }
+}
+
+static int _write_encryption_header(writer_t* w)
+{
+    int ret = 0;
+    uint8_t wrapped[MAX_WRAPPED_KEY_SIZE];
+    size_t wrapped_size;
+    const uint32_t version = VERSION;
+    uint32_t n;
+
+    ECHECK(myst_tcall_random(w->key, sizeof(w->key)));
+
+    ECHECK(myst_tcall_rsa_oaep_encrypt(
+        _key_data,
+        w->key,
+        sizeof(w->key),
+        wrapped,
+        sizeof(wrapped),
+        &wrapped_size));
+
+    n = (uint32_t)wrapped_size;
+
+    /* the header is not encrypted */
+    ECHECK(_host_write(w->fd, MYST_COREDUMP_MAGIC, 8));
+    ECHECK(_host_write(w->fd, &version, sizeof(version)));
+    ECHECK(_host_write(w->fd, &n, sizeof(n)));
+    ECHECK(_host_write(w->fd, wrapped, wrapped_size));
+
+    w->encrypt = true;
+
+done:
+    return ret;
+}
+
+static void _add_region(uint64_t addr, uint64_t size)
+{
+    uint64_t end;
+
+    if (!addr || !size || _num_regions == MAX_REGIONS)
+        return;
+
+    end = addr + size;
+    addr = myst_round_down_to_page_size(addr);
+
+    if (myst_round_up(end, PAGE_SIZE, &end) != 0)
+        return;
+
+    /* the mappings are not trimmed by munmap(), so they may overlap */
+    for (size_t i = 0; i < _num_regions; i++)
+    {
+        const region_t* r = &_regions[i];
+
+        if (addr < r->addr + r->size && r->addr < end)
+            return;
+    }
+
+    _regions[_num_regions].addr = addr;
+    _regions[_num_regions].size = end - addr;
+    _num_regions++;
+}
+
+static void _add_mapping(void* addr, size_t size, void* arg)
+{
+    (void)arg;
+    _add_region((uint64_t)addr, size);
+}
+
+/* the live part of the stack first (from a page below the stack pointer),
+ * then the C runtime image and the mappings */
+static void _find_regions(myst_thread_t* process, uint64_t sp)
+{
+    _num_regions = 0;
+
+    _add_region(
+        (uint64_t)process->main.exec_stack, process->main.exec_stack_size);
+    _add_region(
+        (uint64_t)process->main.exec_crt_data, process->main.exec_crt_size);
+    myst_enumerate_process_mappings(process->pid, _add_mapping, NULL);
+
+    for (size_t i = 0; i < _num_regions; i++)
+    {
+        region_t r = _regions[i];
+
+        if (sp >= r.addr && sp < r.addr + r.size)
+        {
+            const uint64_t start = myst_round_down_to_page_size(sp) - PAGE_SIZE;
+
+            if (start > r.addr)
+            {
+                r.size -= start - r.addr;
+                r.addr = start;
+            }
+
+            _regions[i] = _regions[0];
+            _regions[0] = r;
+            break;
+        }
+    }
+}
+
+static size_t _note_size(size_t desc_size)
+{
+    /* the name is "CORE" and both fields are padded to 4 bytes */
+    return sizeof(Elf64_Nhdr) + 8 + ((desc_size + 3) & ~(size_t)3);
+}
+
+static void _write_note(
+    writer_t* w,
+    uint32_t type,
+    const void* desc,
+    size_t desc_size)
+{
+    const char name[8] = "CORE";
+    Elf64_Nhdr nh;
+
+    nh.n_namesz = 5;
+    nh.n_descsz = (Elf64_Word)desc_size;
+    nh.n_type = type;
+
+    _write(w, &nh, sizeof(nh));
+    _write(w, name, sizeof(name));
+    _write(w, desc, desc_size);
+    _write(w, NULL, _note_size(desc_size) - sizeof(nh) - 8 - desc_size);
+}
+
+static void _get_prstatus(
+    struct elf_prstatus* ps,
+    myst_thread_t* thread,
+    int signum,
+    const myst_coredump_regs_t* regs)
+{
+    memset(ps, 0, sizeof(*ps));
+    ps->pr_info.si_signo = signum;
+    ps->pr_cursig = (short)signum;
+    ps->pr_sighold = thread->signal.mask;
+    ps->pr_sigpend = thread->signal.pending;
+    ps->pr_pid = thread->tid;
+    ps->pr_ppid = thread->ppid;
+    ps->pr_pgrp = thread->pid;
+    ps->pr_sid = thread->sid;
+    memcpy(&ps->pr_reg, regs, sizeof(ps->pr_reg));
+}
+
+static void _get_prpsinfo(struct elf_prpsinfo* pi, myst_thread_t* process)
+{
+    memset(pi, 0, sizeof(*pi));
+    pi->pr_state = 0;
+    pi->pr_sname = 'R';
+    pi->pr_uid = MYST_DEFAULT_UID;
+    pi->pr_gid = MYST_DEFAULT_GID;
+    pi->pr_pid = process->pid;
+    pi->pr_ppid = process->ppid;
+    pi->pr_pgrp = process->pid;
+    pi->pr_sid = process->sid;
+    MYST_STRLCPY(pi->pr_fname, process->name);
+    MYST_STRLCPY(pi->pr_psargs, process->name);
+}
+
+static int _write_core(
+    writer_t* w,
+    myst_thread_t* thread,
+    int signum,
+    const myst_coredump_regs_t* regs)
+{
+    int ret = 0;
+    myst_thread_t* process = myst_find_process_thread(thread);
+    struct elf_prstatus ps;
+    struct elf_prpsinfo pi;
+    const size_t notes_size =
+        _note_size(sizeof(ps)) + _note_size(sizeof(pi));
+    size_t num_phdrs;
+    uint64_t offset;
+    uint64_t budget;
+    Elf64_Ehdr eh;
+
+    _find_regions(process, regs->rsp);
+    num_phdrs = 1 + _num_regions;
+
+    /* the memory starts at the first page after the headers and notes */
+    offset = sizeof(eh) + num_phdrs * sizeof(Elf64_Phdr) + notes_size;
+    ECHECK(myst_round_up(offset, PAGE_SIZE, &offset));
+
+    if (offset >= _config.max_size)
+        ERAISE(-EFBIG);
+
+    /* drop what does not fit */
+    budget = _config.max_size - offset;
+
+    for (size_t i = 0; i < _num_regions; i++)
+    {
+        region_t* r = &_regions[i];
+
+        if (r->size > budget)
+            r->size = budget;
+
+        budget -= r->size;
+    }
+
+    memset(&eh, 0, sizeof(eh));
+    memcpy(eh.e_ident, ELFMAG, SELFMAG);
+    eh.e_ident[EI_CLASS] = ELFCLASS64;
+    eh.e_ident[EI_DATA] = ELFDATA2LSB;
+    eh.e_ident[EI_VERSION] = EV_CURRENT;
+    eh.e_ident[EI_OSABI] = ELFOSABI_NONE;
+    eh.e_type = ET_CORE;
+    eh.e_machine = EM_X86_64;
+    eh.e_version = EV_CURRENT;
+    eh.e_phoff = sizeof(eh);
+    eh.e_ehsize = sizeof(eh);
+    eh.e_phentsize = sizeof(Elf64_Phdr);
+    eh.e_phnum = (Elf64_Half)num_phdrs;
+    _write(w, &eh, sizeof(eh));
+
+    /* PT_NOTE */
+    {
+        Elf64_Phdr ph;
+
+        memset(&ph, 0, sizeof(ph));
+        ph.p_type = PT_NOTE;
+        ph.p_offset = sizeof(eh) + num_phdrs * sizeof(Elf64_Phdr);
+        ph.p_filesz = notes_size;
+        ph.p_align = 4;
+        _write(w, &ph, sizeof(ph));
+    }
+
+    /* PT_LOAD (empty segments are kept to keep the count) */
+    for (size_t i = 0, o = offset; i < _num_regions; i++)
+    {
+        const region_t* r = &_regions[i];
+        Elf64_Phdr ph;
+
+        memset(&ph, 0, sizeof(ph));
+        ph.p_type = PT_LOAD;
+        ph.p_flags = PF_R | PF_W;
+        ph.p_offset = o;
+        ph.p_vaddr = r->addr;
+        ph.p_filesz = r->size;
+        ph.p_memsz = r->size;
+        ph.p_align = PAGE_SIZE;
+        _write(w, &ph, sizeof(ph));
+        o += r->size;
+    }
+
+    _get_prstatus(&ps, thread, signum, regs);
+    _write_note(w, NT_PRSTATUS, &ps, sizeof(ps));
+    _get_prpsinfo(&pi, process);
+    _write_note(w, NT_PRPSINFO, &pi, sizeof(pi));
+
+    /* pad to the first page of memory */
+    _write(
+        w,
+        NULL,
+        offset - (sizeof(eh) + num_phdrs * sizeof(Elf64_Phdr) + notes_size));
+
+    for (size_t i = 0; i < _num_regions; i++)
+        _write(w, (const void*)_regions[i].addr, _regions[i].size);
+
+    _flush(w, false);
+
+    /* the empty record that ends an encrypted file */
+    if (w->encrypt)
+        _flush(w, true);
+
+    ret = w->err;
+
+done:
+    return ret;
+}
+
+/* the registers at this point, for signals that the kernel sends */
+__attribute__((noinline)) static void _get_regs(myst_coredump_regs_t* regs)
+{
+    memset(regs, 0, sizeof(*regs));
+    __asm__ volatile("mov %%rbx, %0" : "=m"(regs->rbx));
+    __asm__ volatile("mov %%r12, %0" : "=m"(regs->r12));
+    __asm__ volatile("mov %%r13, %0" : "=m"(regs->r13));
+    __asm__ volatile("mov %%r14, %0" : "=m"(regs->r14));
+    __asm__ volatile("mov %%r15, %0" : "=m"(regs->r15));
+    regs->rbp = (uint64_t)__builtin_frame_address(0);
+    regs->rsp = regs->rbp;
+    regs->rip = (uint64_t)__builtin_return_address(0);
+}
+
+static myst_thread_t* _thread_self(void)
+{
+    uint64_t value;
+
+    /* the exception may occur before the thread is set up */
+    if (myst_tcall_get_tsd(&value) != 0 ||
+        !myst_valid_thread((myst_thread_t*)value))
+    {
+        return NULL;
+    }
+
+    return (myst_thread_t*)value;
+}
+
+bool myst_coredump_signal(int signum)
+{
+    switch (signum)
+    {
+        case SIGQUIT:
+        case SIGILL:
+        case SIGTRAP:
+        case SIGABRT:
+        case SIGBUS:
+        case SIGFPE:
+        case SIGSEGV:
+        case SIGXCPU:
+        case SIGXFSZ:
+        case SIGSYS:
+            return true;
+        default:
+            return false;
+    }
+}
+
+void myst_coredump(int signum, const myst_coredump_regs_t* regs)
+{
+    myst_thread_t* thread;
+    myst_coredump_regs_t kregs;
+    char path[PATH_MAX];
+    writer_t w = {.fd = -1};
+    int r;
+
+    if (!_config.path || !(thread = _thread_self()))
+        return;
+
+    /* PR_SET_DUMPABLE */
+    if (!myst_find_process_thread(thread)->main.dumpable)
+        return;
+
+    /* another thread is dumping, or the dump itself crashed */
+    if (__sync_lock_test_and_set(&_dumping, 1))
+        return;
+
+    if (!regs)
+    {
+        _get_regs(&kregs);
+        kregs.fs_base = (uint64_t)myst_get_fsbase();
+        regs = &kregs;
+    }
+
+    if (snprintf(path, sizeof(path), "%s/core.%d", _config.path, thread->pid) >=
+        (int)sizeof(path))
+    {
+        r = -ENAMETOOLONG;
+        goto done;
+    }
+
+    {
+        const int flags = O_WRONLY | O_CREAT | O_TRUNC | O_CLOEXEC;
+        long params[6] = {(long)path, flags, 0600};
+
+        if ((r = (int)myst_tcall(SYS_open, params)) < 0)
+            goto done;
+
+        w.fd = r;
+    }
+
+    if (_key_data && (r = _write_encryption_header(&w)) != 0)
+        goto done;
+
+    r = _write_core(&w, thread, signum, regs);
+
+done:
+
+    if (w.fd >= 0)
+    {
+        long params[6] = {w.fd};
+        myst_tcall(SYS_close, params);
+    }
+
+    memset(w.key, 0, sizeof(w.key));
+
+    if (r == 0)
+        myst_eprintf("kernel: wrote core dump to %s\n", path);
+    else
+        myst_eprintf(
+            "kernel: failed to write core dump: %s\n", myst_error_name(-r));
+
+    __sync_lock_release(&_dumping);
+}
+
+static void _crash_handler(int signum, const myst_coredump_regs_t* regs)
+{
+    myst_coredump(signum, regs);
+}
+
+int myst_coredump_setup(
+    const myst_coredump_config_t* config,
+    bool tee_debug_mode)
+{
+    int ret = 0;
+
+    if (!config)
+        ERAISE(-EINVAL);
+
+    if (!config->path)
+        goto done;
+
+    /* a core file in the clear would give enclave memory to the host */
+    if (!config->encryption_key && !tee_debug_mode)
+    {
+        myst_eprintf(
+            "kernel: core dump: EncryptionKey is required unless "
+            "the enclave is in debug mode\n");
+        ERAISE(-EPERM);
+    }
+
+    _config = *config;
+
+    if (!_config.max_size)
+        _config.max_size = MYST_COREDUMP_DEFAULT_MAX_SIZE;
+
+    /* the key comes from the signed root file system */
+    if (config->encryption_key &&
+        myst_load_file(config->encryption_key, &_key_data, &_key_size) != 0)
+    {
+        myst_eprintf(
+            "kernel: core dump: cannot load %s\n", config->encryption_key);
+        ERAISE(-ENOENT);
+    }
+
+    /* targets without an exception handler do not support this */
+    if ((ret = (int)myst_tcall_set_crash_handler(_crash_handler)) == -ENOTSUP)
+        ret = 0;
+
+    ECHECK(ret);
+
+done:
+    return ret;
+}
@@ -0,0 +1,120 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stddef.h>
+
+#include <myst/clock.h>
+#include <myst/deadline.h>
+#include <myst/syscall.h>
+
+long myst_deadline_now(void)
+{
+    struct timespec ts;
+
+    if (myst_syscall_clock_gettime(CLOCK_MONOTONIC, &ts) != 0)
+        return 0;
+
+    return ts.tv_sec * NANO_IN_SECOND + ts.tv_nsec;
+}
+
+/* the deadline that is nsec from now (saturating at never) */
+static void _init(myst_deadline_t* deadline, long sec, long nsec)
+{
+    long timeout;
+
+    if (__builtin_smull_overflow(sec, NANO_IN_SECOND, &timeout) ||
+        __builtin_saddl_overflow(timeout, nsec, &timeout) ||
+        __builtin_saddl_overflow(myst_deadline_now(), timeout, &deadline->nsec))
+    {
+        deadline->nsec = MYST_DEADLINE_NEVER;
+    }
+}
+
+int myst_deadline_init(myst_deadline_t* deadline, const struct timespec* to)
+{
+    if (!deadline)
+        return -EINVAL;
+
+    if (!to)
+    {
+        deadline->nsec = MYST_DEADLINE_NEVER;
+        return 0;
+    }
+
+    if (to->tv_sec < 0 || to->tv_nsec < 0 || to->tv_nsec >= NANO_IN_SECOND)
+        return -EINVAL;
+
+    _init(deadline, to->tv_sec, to->tv_nsec);
+    return 0;
+}
+
+int myst_deadline_init_timeval(
+    myst_deadline_t* deadline,
+    const struct timeval* to)
+{
+    if (!deadline)
+        return -EINVAL;
+
+    if (!to)
+    {
+        deadline->nsec = MYST_DEADLINE_NEVER;
+        return 0;
+    }
+
+    if (to->tv_sec < 0 || to->tv_usec < 0 || to->tv_usec >= MICRO_IN_SECOND)
+        return -EINVAL;
+
+    _init(deadline, to->tv_sec, to->tv_usec * 1000);
+    return 0;
+}
+
+void myst_deadline_init_msec(myst_deadline_t* deadline, int msec)
+{
+    if (msec < 0)
+        deadline->nsec = MYST_DEADLINE_NEVER;
+    else
+        _init(deadline, msec / 1000, (msec % 1000) * 1000000L);
+}
+
+bool myst_deadline_expired(const myst_deadline_t* deadline)
+{
+    return myst_deadline_remaining(deadline) == 0;
+}
+
+long myst_deadline_remaining(const myst_deadline_t* deadline)
+{
+    long now;
+
+    if (deadline->nsec == MYST_DEADLINE_NEVER)
+        return MYST_DEADLINE_NEVER;
+
+    now = myst_deadline_now();
+    return deadline->nsec > now ? deadline->nsec - now : 0;
+}
+
+const struct timespec* myst_deadline_remaining_timespec(
+    const myst_deadline_t* deadline,
+    struct timespec* buf)
+{
+    long remaining = myst_deadline_remaining(deadline);
+
+    if (remaining == MYST_DEADLINE_NEVER)
+        return NULL;
+
+    buf->tv_sec = remaining / NANO_IN_SECOND;
+    buf->tv_nsec = remaining % NANO_IN_SECOND;
+    return buf;
+}
+
+int myst_deadline_remaining_msec(const myst_deadline_t* deadline)
+{
+    long remaining = myst_deadline_remaining(deadline);
+    long msec;
+
+    if (remaining == MYST_DEADLINE_NEVER)
+        return -1;
+
+    msec = remaining / 1000000 + (remaining % 1000000 != 0);
+    return msec > INT_MAX ? INT_MAX : (int)msec;
+}
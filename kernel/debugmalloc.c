@@ -0,0 +1,392 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdint.h>
+#include <string.h>
+
+#include <myst/backtrace.h>
+#include <myst/debugmalloc.h>
+#include <myst/defs.h>
+#include <myst/list.h>
+#include <myst/panic.h>
+#include <myst/printf.h>
+#include <myst/round.h>
+#include <myst/spinlock.h>
+
+/*
+**==============================================================================
+**
+** Debug allocator:
+**
+** When "DebugMalloc" is set in config.json, every kernel allocation carries a
+** header, with the allocation backtrace and a canary, and a trailing canary.
+** The canaries are checked when the block is freed, which catches writes past
+** either end of the block. A freed block is poisoned and quarantined rather
+** than returned to the heap; a second free of a quarantined block is reported
+** as a double free, and a block whose poison was overwritten when it leaves
+** the quarantine is reported as a use after free. At exit, the blocks that
+** are still allocated are reported, grouped by allocation backtrace.
+**
+**==============================================================================
+*/
+
+/* provided by dlmalloc (see malloc.c) */
+void* dlmemalign(size_t alignment, size_t size);
+void dlfree(void* ptr);
+
+#define MAX_BACKTRACE_ADDRS 16
+#define MAX_REPORTED_LEAKS 32
+
+#define ALLOCATED_MAGIC 0x9f3a2c5e71d4b806
+#define FREED_MAGIC 0x5b8e14c7a6d0f293
+#define CANARY_KEY 0xc3a5c85c97cb3127
+#define FREED_BYTE 0xdd
+#define MIN_ALIGNMENT 16
+
+typedef struct header
+{
+    myst_list_node_t base;
+
+    /* the address returned by dlmemalign() */
+    void* block;
+
+    /* the size requested by the caller */
+    size_t size;
+
+    void* addrs[MAX_BACKTRACE_ADDRS];
+    size_t num_addrs;
+
+    bool reported;
+    uint64_t magic;
+
+    /* must be the last field (immediately before the caller's memory) */
+    uint64_t canary;
+} header_t;
+
+static bool _enabled;
+static size_t _quarantine_size;
+
+static myst_list_t _allocated;
+static myst_list_t _quarantine;
+static size_t _quarantine_bytes;
+static myst_malloc_stats_t _stats;
+static myst_spinlock_t _lock = MYST_SPINLOCK_INITIALIZER;
+
+void myst_debug_malloc_setup(bool enable, size_t quarantine_size)
+{
+    _enabled = enable;
+
+    if (quarantine_size)
+        _quarantine_size = quarantine_size;
+    else
+        _quarantine_size = MYST_DEBUG_MALLOC_QUARANTINE_SIZE;
+}
+
+bool myst_debug_malloc_enabled(void)
+{
+    return _enabled;
+}
+
+static header_t* _header(void* ptr)
+{
+    return (header_t*)ptr - 1;
+}
+
+static uint64_t _canary(const void* ptr)
+{
+    return (uint64_t)ptr ^ CANARY_KEY;
+}
+
+static void _dump(const char* what, header_t* h, void* ptr)
+{
+    myst_eprintf(
+        "kernel: debug malloc: %s: ptr=%p size=%zu allocated at:\n",
+        what,
+        ptr,
+        h->size);
+    myst_dump_backtrace(h->addrs, h->num_addrs);
+    myst_panic("kernel heap corruption");
+}
+
+void* myst_debug_memalign(size_t alignment, size_t size)
+{
+    size_t offset;
+    uint8_t* block;
+    uint8_t* ptr;
+    header_t* h;
+    uint64_t tail;
+
+    if (alignment < MIN_ALIGNMENT)
+        alignment = MIN_ALIGNMENT;
+
+    if (alignment & (alignment - 1))
+        return NULL;
+
+    if (myst_round_up(sizeof(header_t), alignment, &offset) != 0)
+        return NULL;
+
+    if (size > SIZE_MAX - offset - sizeof(tail))
+        return NULL;
+
+    if (!(block = dlmemalign(alignment, offset + size + sizeof(tail))))
+        return NULL;
+
+    ptr = block + offset;
+    h = _header(ptr);
+    h->block = block;
+    h->size = size;
+    h->num_addrs = myst_backtrace(h->addrs, MYST_COUNTOF(h->addrs));
+    h->reported = false;
+    h->magic = ALLOCATED_MAGIC;
+    h->canary = _canary(ptr);
+
+    /* the tail canary may be unaligned */
+    tail = _canary(ptr + size);
+    memcpy(ptr + size, &tail, sizeof(tail));
+
+    myst_spin_lock(&_lock);
+    {
+        myst_list_append(&_allocated, &h->base);
+        _stats.usage += size;
+
+        if (_stats.usage > _stats.peak_usage)
+            _stats.peak_usage = _stats.usage;
+    }
+    myst_spin_unlock(&_lock);
+
+    return ptr;
+}
+
+void* myst_debug_malloc(size_t size)
+{
+    return myst_debug_memalign(MIN_ALIGNMENT, size);
+}
+
+void* myst_debug_calloc(size_t nmemb, size_t size)
+{
+    void* ptr;
+    size_t n;
+
+    if (__builtin_mul_overflow(nmemb, size, &n))
+        return NULL;
+
+    if ((ptr = myst_debug_malloc(n)))
+        memset(ptr, 0, n);
+
+    return ptr;
+}
+
+static bool _is_poisoned(const header_t* h)
+{
+    const uint8_t* p = (const uint8_t*)(h + 1);
+
+    for (size_t i = 0; i < h->size; i++)
+    {
+        if (p[i] != FREED_BYTE)
+            return false;
+    }
+
+    return true;
+}
+
+/* return the oldest quarantined block if the quarantine is full */
+static header_t* _evict_locked(void)
+{
+    header_t* h = (header_t*)_quarantine.head;
+
+    if (!h || _quarantine_bytes <= _quarantine_size)
+        return NULL;
+
+    myst_list_remove(&_quarantine, &h->base);
+    _quarantine_bytes -= h->size;
+
+    return h;
+}
+
+static void _release(header_t* h)
+{
+    if (!_is_poisoned(h))
+        _dump("use after free", h, h + 1);
+
+    h->magic = 0;
+    dlfree(h->block);
+}
+
+void myst_debug_free(void* ptr)
+{
+    header_t* h;
+    uint64_t tail;
+    header_t* evicted;
+
+    if (!ptr)
+        return;
+
+    h = _header(ptr);
+
+    if (h->magic == FREED_MAGIC)
+        _dump("double free", h, ptr);
+
+    if (h->magic != ALLOCATED_MAGIC)
+    {
+        myst_eprintf("kernel: debug malloc: bad pointer: ptr=%p\n", ptr);
+        myst_panic("kernel heap corruption");
+    }
+
+    if (h->canary != _canary(ptr))
+        _dump("buffer underflow", h, ptr);
+
+    memcpy(&tail, (uint8_t*)ptr + h->size, sizeof(tail));
+
+    if (tail != _canary((uint8_t*)ptr + h->size))
+        _dump("buffer overflow", h, ptr);
+
+    h->magic = FREED_MAGIC;
+    memset(ptr, FREED_BYTE, h->size);
+
+    myst_spin_lock(&_lock);
+    {
+        myst_list_remove(&_allocated, &h->base);
+        _stats.usage -= h->size;
+
+        myst_list_append(&_quarantine, &h->base);
+        _quarantine_bytes += h->size;
+    }
+    myst_spin_unlock(&_lock);
+
+    for (;;)
+    {
+        myst_spin_lock(&_lock);
+        evicted = _evict_locked();
+        myst_spin_unlock(&_lock);
+
+        if (!evicted)
+            break;
+
+        _release(evicted);
+    }
+}
+
+void* myst_debug_realloc(void* ptr, size_t size)
+{
+    void* p;
+    size_t n;
+
+    if (!ptr)
+        return myst_debug_malloc(size);
+
+    if (_header(ptr)->magic == FREED_MAGIC)
+        _dump("realloc of a freed block", _header(ptr), ptr);
+
+    if (!(p = myst_debug_malloc(size)))
+        return NULL;
+
+    n = _header(ptr)->size;
+    memcpy(p, ptr, size < n ? size : n);
+    myst_debug_free(ptr);
+
+    return p;
+}
+
+void myst_debug_malloc_flush(void)
+{
+    header_t* h;
+
+    for (;;)
+    {
+        myst_spin_lock(&_lock);
+        {
+            if ((h = (header_t*)_quarantine.head))
+            {
+                myst_list_remove(&_quarantine, &h->base);
+                _quarantine_bytes -= h->size;
+            }
+        }
+        myst_spin_unlock(&_lock);
+
+        if (!h)
+            break;
+
+        _release(h);
+    }
+}
+
+int myst_debug_malloc_stats(myst_malloc_stats_t* stats)
+{
+    if (!stats)
+        return -EINVAL;
+
+    myst_spin_lock(&_lock);
+    *stats = _stats;
+    myst_spin_unlock(&_lock);
+
+    return 0;
+}
+
+static bool _same_backtrace(const header_t* x, const header_t* y)
+{
+    if (x->num_addrs != y->num_addrs)
+        return false;
+
+    return memcmp(x->addrs, y->addrs, x->num_addrs * sizeof(void*)) == 0;
+}
+
+size_t myst_debug_malloc_report(void)
+{
+    size_t usage;
+    size_t groups = 0;
+
+    myst_spin_lock(&_lock);
+    {
+        usage = _stats.usage;
+
+        myst_eprintf(
+            "kernel: debug malloc: %zu bytes in %zu blocks still allocated "
+            "(peak %zu bytes)\n",
+            usage,
+            _allocated.size,
+            _stats.peak_usage);
+
+        for (myst_list_node_t* p = _allocated.head; p; p = p->next)
+        {
+            header_t* h = (header_t*)p;
+            size_t bytes = 0;
+            size_t blocks = 0;
+
+            if (h->reported)
+                continue;
+
+            for (myst_list_node_t* q = p; q; q = q->next)
+            {
+                header_t* t = (header_t*)q;
+
+                if (!t->reported && _same_backtrace(h, t))
+                {
+                    t->reported = true;
+                    bytes += t->size;
+                    blocks++;
+                }
+            }
+
+            if (++groups > MAX_REPORTED_LEAKS)
+                continue;
+
+            myst_eprintf(
+                "kernel: debug malloc: leaked %zu bytes in %zu blocks "
+                "allocated at:\n",
+                bytes,
+                blocks);
+            myst_dump_backtrace(h->addrs, h->num_addrs);
+        }
+
+        if (groups > MAX_REPORTED_LEAKS)
+        {
+            myst_eprintf(
+                "kernel: debug malloc: %zu more backtraces not shown\n",
+                groups - MAX_REPORTED_LEAKS);
+        }
+    }
+    myst_spin_unlock(&_lock);
+
+    return usage;
+}
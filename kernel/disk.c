@@ -0,0 +1,796 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <myst/atexit.h>
+#include <myst/blockdevice.h>
+#include <myst/defs.h>
+#include <myst/disk.h>
+#include <myst/eraise.h>
+#include <myst/errno.h>
+#include <myst/mutex.h>
+#include <myst/printf.h>
+
+/*
+**==============================================================================
+**
+** Disks:
+**
+** Each entry of "Disks" in config.json is a raw image on the host that the
+** enclave sees as /dev/vda, /dev/vdb and so on (see kernel/diskdev.c), and
+** that ext2 can be mounted from, with or without a LUKS layer under it (see
+** myst_load_fs()). Reads and writes go through a block cache of the disk,
+** which keeps the blocks most recently used and drops the least recently
+** used one when it is full.
+**
+** Without a "Journal", a write goes through to the image before it returns.
+** With one, written blocks stay in the cache until the disk is synced (by
+** fsync(), sync(), BLKFLSBUF, unmounting, the cache filling with them or the
+** kernel exiting), which writes them all to the journal, then a header that
+** commits them, then the blocks to their places in the image, then a header
+** that marks the journal empty. Should the enclave stop in the middle, the
+** next boot writes the committed blocks again before the disk is used, so
+** the image has either all the blocks of a sync or none of them.
+**
+** The host sees (and could change) what is on the image, so a disk that
+** holds anything secret wants a LUKS layer.
+**
+**==============================================================================
+*/
+
+#define JOURNAL_MAGIC 0x6c6e726a6b736964 /* "diskjrnl" */
+
+/* the block numbers in a block of the journal */
+#define BLKNOS_PER_BLOCK (MYST_BLKSIZE / sizeof(uint64_t))
+
+/* the first block of the journal */
+typedef struct journal_header
+{
+    uint64_t magic;
+
+    /* the blocks that were committed (0 if they are all in place) */
+    uint64_t count;
+
+    uint8_t padding[MYST_BLKSIZE - 2 * sizeof(uint64_t)];
+} journal_header_t;
+
+MYST_STATIC_ASSERT(sizeof(journal_header_t) == MYST_BLKSIZE);
+
+typedef struct block block_t;
+
+struct block
+{
+    /* the next block in the hash chain */
+    block_t* chain;
+
+    /* the neighbours in the list from most to least recently used */
+    block_t* prev;
+    block_t* next;
+
+    uint64_t blkno;
+    bool dirty;
+    uint8_t data[MYST_BLKSIZE];
+};
+
+typedef struct disk
+{
+    myst_mutex_t mutex;
+    int fd;
+    int journal_fd; /* -1 to write through */
+    bool read_only;
+    uint64_t num_blocks;
+
+    /* the block cache */
+    size_t max_cached;
+    size_t num_cached;
+    size_t num_dirty;
+    block_t** chains; /* max_cached of them */
+    block_t* head;    /* the most recently used block */
+    block_t* tail;    /* the least recently used block */
+} disk_t;
+
+static disk_t* _disks[MYST_MAX_DISKS];
+static size_t _num_disks;
+
+static int _read_block(int fd, uint64_t blkno, void* data)
+{
+    return myst_read_block_device(fd, blkno, (myst_block_t*)data, 1);
+}
+
+static int _write_block(int fd, uint64_t blkno, const void* data)
+{
+    return myst_write_block_device(fd, blkno, (const myst_block_t*)data, 1);
+}
+
+static void _unlink(disk_t* disk, block_t* block)
+{
+    if (block->prev)
+        block->prev->next = block->next;
+    else
+        disk->head = block->next;
+
+    if (block->next)
+        block->next->prev = block->prev;
+    else
+        disk->tail = block->prev;
+
+    block->prev = NULL;
+    block->next = NULL;
+}
+
+static void _push_front(disk_t* disk, block_t* block)
+{
+    block->prev = NULL;
+    block->next = disk->head;
+
+    if (disk->head)
+        disk->head->prev = block;
+    else
+        disk->tail = block;
+
+    disk->head = block;
+}
+
+static block_t* _lookup(disk_t* disk, uint64_t blkno)
+{
+    block_t* p;
+
+    for (p = disk->chains[blkno % disk->max_cached]; p; p = p->chain)
+    {
+        if (p->blkno == blkno)
+            return p;
+    }
+
+    return NULL;
+}
+
+static void _remove(disk_t* disk, block_t* block)
+{
+    block_t** pp = &disk->chains[block->blkno % disk->max_cached];
+
+    while (*pp != block)
+        pp = &(*pp)->chain;
+
+    *pp = block->chain;
+    block->chain = NULL;
+
+    _unlink(disk, block);
+    disk->num_cached--;
+
+    if (block->dirty)
+    {
+        block->dirty = false;
+        disk->num_dirty--;
+    }
+}
+
+/* write the dirty blocks to the journal and then in place (with the mutex
+ * held) */
+static int _flush(disk_t* disk)
+{
+    int ret = 0;
+    uint64_t blknos[BLKNOS_PER_BLOCK];
+    journal_header_t header;
+    const uint64_t count = disk->num_dirty;
+    const uint64_t num_lists =
+        (count + BLKNOS_PER_BLOCK - 1) / BLKNOS_PER_BLOCK;
+    uint64_t i = 0;
+    block_t* p;
+
+    if (count == 0)
+        goto done;
+
+    /* the block numbers (after the header) and then the blocks */
+    for (p = disk->head; p; p = p->next)
+    {
+        if (!p->dirty)
+            continue;
+
+        blknos[i % BLKNOS_PER_BLOCK] = p->blkno;
+        ECHECK(_write_block(disk->journal_fd, 1 + num_lists + i, p->data));
+        i++;
+
+        if (i % BLKNOS_PER_BLOCK == 0 || i == count)
+        {
+            const uint64_t list = (i - 1) / BLKNOS_PER_BLOCK;
+            uint8_t buf[MYST_BLKSIZE];
+            const size_t n = (i - 1) % BLKNOS_PER_BLOCK + 1;
+
+            memset(buf, 0, sizeof(buf));
+            memcpy(buf, blknos, n * sizeof(uint64_t));
+            ECHECK(_write_block(disk->journal_fd, 1 + list, buf));
+        }
+    }
+
+    ECHECK(myst_sync_block_device(disk->journal_fd));
+
+    /* commit */
+    memset(&header, 0, sizeof(header));
+    header.magic = JOURNAL_MAGIC;
+    header.count = count;
+    ECHECK(_write_block(disk->journal_fd, 0, &header));
+    ECHECK(myst_sync_block_device(disk->journal_fd));
+
+    for (p = disk->head; p; p = p->next)
+    {
+        if (!p->dirty)
+            continue;
+
+        ECHECK(_write_block(disk->fd, p->blkno, p->data));
+        p->dirty = false;
+        disk->num_dirty--;
+    }
+
+    ECHECK(myst_sync_block_device(disk->fd));
+
+    /* a boot that finds this header empty has nothing to replay, and one
+     * that finds it committed writes the same blocks again */
+    memset(&header, 0, sizeof(header));
+    header.magic = JOURNAL_MAGIC;
+    ECHECK(_write_block(disk->journal_fd, 0, &header));
+
+done:
+    return ret;
+}
+
+/* the cached block, reading it from the image if fill is true (with the
+ * mutex held) */
+static int _get_block(disk_t* disk, uint64_t blkno, bool fill, block_t** out)
+{
+    int ret = 0;
+    block_t* block;
+
+    if ((block = _lookup(disk, blkno)))
+    {
+        _unlink(disk, block);
+        _push_front(disk, block);
+        *out = block;
+        goto done;
+    }
+
+    if (disk->num_cached == disk->max_cached)
+    {
+        /* a dirty block cannot be dropped before it is on the image */
+        if (disk->tail->dirty)
+            ECHECK(_flush(disk));
+
+        block = disk->tail;
+        _remove(disk, block);
+    }
+    else if (!(block = calloc(1, sizeof(block_t))))
+    {
+        ERAISE(-ENOMEM);
+    }
+
+    block->blkno = blkno;
+
+    if (fill && (ret = _read_block(disk->fd, blkno, block->data)) != 0)
+    {
+        free(block);
+        ERAISE(ret);
+    }
+
+    block->chain = disk->chains[blkno % disk->max_cached];
+    disk->chains[blkno % disk->max_cached] = block;
+    _push_front(disk, block);
+    disk->num_cached++;
+
+    *out = block;
+
+done:
+    return ret;
+}
+
+/* write the blocks that the journal committed where they belong */
+static int _replay(disk_t* disk)
+{
+    int ret = 0;
+    uint64_t size;
+    journal_header_t header;
+    uint64_t blknos[BLKNOS_PER_BLOCK];
+    uint8_t buf[MYST_BLKSIZE];
+    uint64_t num_lists;
+
+    ECHECK(myst_get_block_device_size(disk->journal_fd, &size));
+
+    /* a new journal */
+    if (size < MYST_BLKSIZE)
+        goto done;
+
+    ECHECK(_read_block(disk->journal_fd, 0, &header));
+
+    if (header.magic != JOURNAL_MAGIC || header.count == 0)
+        goto done;
+
+    num_lists = (header.count + BLKNOS_PER_BLOCK - 1) / BLKNOS_PER_BLOCK;
+
+    /* the journal holds what its header says it does */
+    if (header.count > size / MYST_BLKSIZE ||
+        1 + num_lists + header.count > size / MYST_BLKSIZE)
+    {
+        ERAISE(-EINVAL);
+    }
+
+    for (uint64_t i = 0; i < header.count; i++)
+    {
+        if (i % BLKNOS_PER_BLOCK == 0)
+        {
+            const uint64_t list = i / BLKNOS_PER_BLOCK;
+            ECHECK(_read_block(disk->journal_fd, 1 + list, blknos));
+        }
+
+        if (blknos[i % BLKNOS_PER_BLOCK] >= disk->num_blocks)
+            ERAISE(-EINVAL);
+
+        ECHECK(_read_block(disk->journal_fd, 1 + num_lists + i, buf));
+        ECHECK(_write_block(disk->fd, blknos[i % BLKNOS_PER_BLOCK], buf));
+    }
+
+    ECHECK(myst_sync_block_device(disk->fd));
+
+    memset(&header, 0, sizeof(header));
+    header.magic = JOURNAL_MAGIC;
+    ECHECK(_write_block(disk->journal_fd, 0, &header));
+    ECHECK(myst_sync_block_device(disk->journal_fd));
+
+done:
+    return ret;
+}
+
+static void _close_disk(disk_t* disk)
+{
+    block_t* p;
+    block_t* next;
+
+    for (p = disk->head; p; p = next)
+    {
+        next = p->next;
+        free(p);
+    }
+
+    if (disk->journal_fd >= 0)
+        myst_close_block_device(disk->journal_fd);
+
+    if (disk->fd >= 0)
+        myst_close_block_device(disk->fd);
+
+    free(disk->chains);
+    free(disk);
+}
+
+static int _open_disk(const myst_disk_config_t* config, disk_t** disk_out)
+{
+    int ret = 0;
+    disk_t* disk = NULL;
+    size_t cache_size = config->cache_size;
+    uint64_t size;
+
+    if (!config->host_path)
+        ERAISE(-EINVAL);
+
+    /* a journal is written to */
+    if (config->read_only && config->journal_path)
+        ERAISE(-EINVAL);
+
+    if (!cache_size)
+        cache_size = MYST_DISK_DEFAULT_CACHE_SIZE;
+
+    if (cache_size / MYST_BLKSIZE < MYST_DISK_MIN_CACHE_BLOCKS)
+        ERAISE(-EINVAL);
+
+    if (!(disk = calloc(1, sizeof(disk_t))))
+        ERAISE(-ENOMEM);
+
+    disk->fd = -1;
+    disk->journal_fd = -1;
+    disk->read_only = config->read_only;
+    disk->max_cached = cache_size / MYST_BLKSIZE;
+
+    if (!(disk->chains = calloc(disk->max_cached, sizeof(block_t*))))
+        ERAISE(-ENOMEM);
+
+    ECHECK(
+        disk->fd =
+            myst_open_block_device(config->host_path, disk->read_only));
+    ECHECK(myst_get_block_device_size(disk->fd, &size));
+
+    /* the image is made of whole blocks */
+    if (size == 0 || size % MYST_BLKSIZE)
+        ERAISE(-EINVAL);
+
+    disk->num_blocks = size / MYST_BLKSIZE;
+
+    if (config->journal_path)
+    {
+        ECHECK(
+            disk->journal_fd =
+                myst_open_block_device(config->journal_path, false));
+        ECHECK(_replay(disk));
+    }
+
+    *disk_out = disk;
+    disk = NULL;
+
+done:
+
+    if (disk)
+        _close_disk(disk);
+
+    return ret;
+}
+
+static disk_t* _get_disk(int index)
+{
+    if (index < 0 || (size_t)index >= _num_disks)
+        return NULL;
+
+    return _disks[index];
+}
+
+static void _atexit(void* arg)
+{
+    (void)arg;
+
+    for (size_t i = 0; i < _num_disks; i++)
+    {
+        int r;
+
+        if ((r = myst_disk_sync((int)i)) != 0)
+        {
+            myst_eprintf(
+                "kernel: cannot sync /dev/vd%c: %s\n",
+                (char)('a' + i),
+                myst_error_name(-r));
+        }
+
+        _close_disk(_disks[i]);
+        _disks[i] = NULL;
+    }
+
+    _num_disks = 0;
+}
+
+int myst_disks_setup(const myst_disks_config_t* config)
+{
+    int ret = 0;
+
+    if (!config)
+        ERAISE(-EINVAL);
+
+    if (config->num_disks == 0)
+        goto done;
+
+    if (config->num_disks > MYST_MAX_DISKS)
+    {
+        myst_eprintf("kernel: at most %d disks\n", MYST_MAX_DISKS);
+        ERAISE(-EINVAL);
+    }
+
+    for (size_t i = 0; i < config->num_disks; i++)
+    {
+        const myst_disk_config_t* entry = &config->disks[i];
+        int r;
+
+        if ((r = _open_disk(entry, &_disks[i])) != 0)
+        {
+            myst_eprintf(
+                "kernel: cannot open disk %s: %s\n",
+                entry->host_path ? entry->host_path : "(null)",
+                myst_error_name(-r));
+            ERAISE(r);
+        }
+
+        _num_disks++;
+    }
+
+    ECHECK(myst_atexit(_atexit, NULL));
+
+done:
+    return ret;
+}
+
+int myst_disk_find(const char* path)
+{
+    const char prefix[] = "/dev/vd";
+    const size_t len = sizeof(prefix) - 1;
+    size_t index;
+
+    if (!path || strncmp(path, prefix, len) != 0)
+        return -ENOENT;
+
+    if (path[len] < 'a' || path[len] > 'z' || path[len + 1] != '\0')
+        return -ENOENT;
+
+    if ((index = (size_t)(path[len] - 'a')) >= _num_disks)
+        return -ENOENT;
+
+    return (int)index;
+}
+
+uint64_t myst_disk_size(int index)
+{
+    disk_t* disk = _get_disk(index);
+    return disk ? disk->num_blocks * MYST_BLKSIZE : 0;
+}
+
+bool myst_disk_read_only(int index)
+{
+    disk_t* disk = _get_disk(index);
+    return disk ? disk->read_only : true;
+}
+
+ssize_t myst_disk_pread(int index, void* buf, size_t count, uint64_t offset)
+{
+    ssize_t ret = 0;
+    disk_t* disk;
+    uint64_t size;
+    uint8_t* p = buf;
+    bool locked = false;
+
+    if (!(disk = _get_disk(index)))
+        ERAISE(-EBADF);
+
+    if (!buf && count)
+        ERAISE(-EFAULT);
+
+    size = disk->num_blocks * MYST_BLKSIZE;
+
+    if (offset >= size)
+        goto done;
+
+    if (count > size - offset)
+        count = size - offset;
+
+    myst_mutex_lock(&disk->mutex);
+    locked = true;
+
+    while ((size_t)ret < count)
+    {
+        const uint64_t blkno = offset / MYST_BLKSIZE;
+        const size_t off = offset % MYST_BLKSIZE;
+        size_t n = MYST_BLKSIZE - off;
+        block_t* block;
+
+        if (n > count - (size_t)ret)
+            n = count - (size_t)ret;
+
+        ECHECK(_get_block(disk, blkno, true, &block));
+        memcpy(p, block->data + off, n);
+
+        p += n;
+        offset += n;
+        ret += (ssize_t)n;
+    }
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&disk->mutex);
+
+    return ret;
+}
+
+ssize_t myst_disk_pwrite(
+    int index,
+    const void* buf,
+    size_t count,
+    uint64_t offset)
+{
+    ssize_t ret = 0;
+    disk_t* disk;
+    uint64_t size;
+    const uint8_t* p = buf;
+    bool locked = false;
+
+    if (!(disk = _get_disk(index)))
+        ERAISE(-EBADF);
+
+    if (!buf && count)
+        ERAISE(-EFAULT);
+
+    if (disk->read_only)
+        ERAISE(-EROFS);
+
+    size = disk->num_blocks * MYST_BLKSIZE;
+
+    if (count == 0)
+        goto done;
+
+    if (offset >= size)
+        ERAISE(-ENOSPC);
+
+    if (count > size - offset)
+        count = size - offset;
+
+    myst_mutex_lock(&disk->mutex);
+    locked = true;
+
+    while ((size_t)ret < count)
+    {
+        const uint64_t blkno = offset / MYST_BLKSIZE;
+        const size_t off = offset % MYST_BLKSIZE;
+        size_t n = MYST_BLKSIZE - off;
+        block_t* block;
+        int r;
+
+        if (n > count - (size_t)ret)
+            n = count - (size_t)ret;
+
+        /* a whole block need not be read first */
+        ECHECK(_get_block(disk, blkno, n != MYST_BLKSIZE, &block));
+        memcpy(block->data + off, p, n);
+
+        if (disk->journal_fd >= 0)
+        {
+            if (!block->dirty)
+            {
+                block->dirty = true;
+                disk->num_dirty++;
+            }
+        }
+        else if ((r = _write_block(disk->fd, blkno, block->data)) != 0)
+        {
+            /* the cache must not hold what the image does not */
+            _remove(disk, block);
+            free(block);
+            ERAISE(r);
+        }
+
+        p += n;
+        offset += n;
+        ret += (ssize_t)n;
+    }
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&disk->mutex);
+
+    return ret;
+}
+
+int myst_disk_sync(int index)
+{
+    int ret = 0;
+    disk_t* disk;
+
+    if (!(disk = _get_disk(index)))
+        ERAISE(-EBADF);
+
+    if (disk->read_only)
+        goto done;
+
+    myst_mutex_lock(&disk->mutex);
+
+    if (disk->journal_fd >= 0)
+        ret = _flush(disk);
+    else
+        ret = myst_sync_block_device(disk->fd);
+
+    myst_mutex_unlock(&disk->mutex);
+
+done:
+    return ret;
+}
+
+int myst_disks_sync(void)
+{
+    int ret = 0;
+
+    for (size_t i = 0; i < _num_disks; i++)
+    {
+        int r;
+
+        /* sync the others even if one fails */
+        if ((r = myst_disk_sync((int)i)) != 0 && ret == 0)
+            ret = r;
+    }
+
+    return ret;
+}
+
+/*
+**==============================================================================
+**
+** The block device of a disk (myst_blkdev_t)
+**
+**==============================================================================
+*/
+
+typedef struct blkdev
+{
+    myst_blkdev_t base;
+    int disk;
+} blkdev_t;
+
+static int _bd_close(myst_blkdev_t* dev)
+{
+    int ret = 0;
+    blkdev_t* impl = (blkdev_t*)dev;
+
+    if (!dev)
+        ERAISE(-EINVAL);
+
+    ret = myst_disk_sync(impl->disk);
+    free(impl);
+
+done:
+    return ret;
+}
+
+static int _bd_get(myst_blkdev_t* dev, uint64_t blkno, void* data)
+{
+    int ret = 0;
+    blkdev_t* impl = (blkdev_t*)dev;
+    ssize_t n;
+
+    if (!dev || !data)
+        ERAISE(-EINVAL);
+
+    if (blkno >= myst_disk_size(impl->disk) / MYST_BLKSIZE)
+        ERAISE(-EINVAL);
+
+    ECHECK(
+        n = myst_disk_pread(
+            impl->disk, data, MYST_BLKSIZE, blkno * MYST_BLKSIZE));
+
+    if (n != MYST_BLKSIZE)
+        ERAISE(-EIO);
+
+done:
+    return ret;
+}
+
+static int _bd_put(myst_blkdev_t* dev, uint64_t blkno, const void* data)
+{
+    int ret = 0;
+    blkdev_t* impl = (blkdev_t*)dev;
+    ssize_t n;
+
+    if (!dev || !data)
+        ERAISE(-EINVAL);
+
+    if (blkno >= myst_disk_size(impl->disk) / MYST_BLKSIZE)
+        ERAISE(-EINVAL);
+
+    ECHECK(
+        n = myst_disk_pwrite(
+            impl->disk, data, MYST_BLKSIZE, blkno * MYST_BLKSIZE));
+
+    if (n != MYST_BLKSIZE)
+        ERAISE(-EIO);
+
+done:
+    return ret;
+}
+
+int myst_disk_open_blkdev(const char* path, myst_blkdev_t** blkdev)
+{
+    int ret = 0;
+    blkdev_t* impl;
+    int disk;
+
+    if (blkdev)
+        *blkdev = NULL;
+
+    if (!path || !blkdev)
+        ERAISE(-EINVAL);
+
+    ECHECK(disk = myst_disk_find(path));
+
+    if (!(impl = calloc(1, sizeof(blkdev_t))))
+        ERAISE(-ENOMEM);
+
+    impl->base.close = _bd_close;
+    impl->base.get = _bd_get;
+    impl->base.put = _bd_put;
+    impl->disk = disk;
+
+    *blkdev = &impl->base;
+
+done:
+    return ret;
+}
@@ -0,0 +1,561 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/sysmacros.h>
+#include <unistd.h>
+
+#include <myst/disk.h>
+#include <myst/diskdev.h>
+#include <myst/eraise.h>
+#include <myst/id.h>
+#include <myst/ioctl.h>
+#include <myst/spinlock.h>
+
+/*
+**==============================================================================
+**
+** The /dev/vd<letter> devices:
+**
+** A descriptor of a disk (see kernel/disk.c) reads and writes its bytes at
+** the file offset, which dup() and fork() share as they do for a file. Like
+** a Linux block device, it has no size of its own (BLKGETSIZE64 gives that),
+** writing past its end fails with ENOSPC, and SEEK_END seeks from its end.
+**
+**==============================================================================
+*/
+
+#define MAGIC 0x8b7d13c4
+
+/* the major number that Linux usually gives virtio disks (the minor is
+ * 16 times the index of the disk) */
+#define DISK_MAJOR 254
+
+/* what the descriptors made by dup() share */
+typedef struct description
+{
+    myst_spinlock_t lock;
+    size_t refs;
+    int disk;
+    int flags; /* the access mode */
+    off_t offset;
+} description_t;
+
+struct myst_disk_file
+{
+    uint32_t magic;
+    int fdflags; /* FD_CLOEXEC */
+    description_t* desc;
+};
+
+MYST_INLINE bool _valid_file(const myst_disk_file_t* file)
+{
+    return file && file->magic == MAGIC && file->desc;
+}
+
+static int _dd_open(
+    myst_diskdev_t* diskdev,
+    const char* path,
+    int flags,
+    myst_disk_file_t** file_out)
+{
+    int ret = 0;
+    myst_disk_file_t* file = NULL;
+    description_t* desc = NULL;
+    int disk;
+
+    if (file_out)
+        *file_out = NULL;
+
+    if (!diskdev || !path || !file_out)
+        ERAISE(-EINVAL);
+
+    ECHECK(disk = myst_disk_find(path));
+
+    if ((flags & O_ACCMODE) != O_RDONLY && myst_disk_read_only(disk))
+        ERAISE(-EROFS);
+
+    if (flags & O_DIRECTORY)
+        ERAISE(-ENOTDIR);
+
+    if (!(file = calloc(1, sizeof(myst_disk_file_t))))
+        ERAISE(-ENOMEM);
+
+    if (!(desc = calloc(1, sizeof(description_t))))
+        ERAISE(-ENOMEM);
+
+    desc->refs = 1;
+    desc->disk = disk;
+    desc->flags = flags & O_ACCMODE;
+
+    file->magic = MAGIC;
+    file->desc = desc;
+    desc = NULL;
+
+    if (flags & O_CLOEXEC)
+        file->fdflags = FD_CLOEXEC;
+
+    *file_out = file;
+    file = NULL;
+
+done:
+
+    if (desc)
+        free(desc);
+
+    if (file)
+        free(file);
+
+    return ret;
+}
+
+static ssize_t _dd_pread(
+    myst_diskdev_t* diskdev,
+    myst_disk_file_t* file,
+    void* buf,
+    size_t count,
+    off_t offset)
+{
+    ssize_t ret = 0;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EBADF);
+
+    if (offset < 0)
+        ERAISE(-EINVAL);
+
+    if ((file->desc->flags & O_ACCMODE) == O_WRONLY)
+        ERAISE(-EBADF);
+
+    ret = myst_disk_pread(file->desc->disk, buf, count, (uint64_t)offset);
+
+done:
+    return ret;
+}
+
+static ssize_t _dd_pwrite(
+    myst_diskdev_t* diskdev,
+    myst_disk_file_t* file,
+    const void* buf,
+    size_t count,
+    off_t offset)
+{
+    ssize_t ret = 0;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EBADF);
+
+    if (offset < 0)
+        ERAISE(-EINVAL);
+
+    if ((file->desc->flags & O_ACCMODE) == O_RDONLY)
+        ERAISE(-EBADF);
+
+    ret = myst_disk_pwrite(file->desc->disk, buf, count, (uint64_t)offset);
+
+done:
+    return ret;
+}
+
+/* read or write at the file offset and advance it */
+static ssize_t _transfer(
+    myst_diskdev_t* diskdev,
+    myst_disk_file_t* file,
+    void* buf,
+    size_t count,
+    bool write)
+{
+    ssize_t ret = 0;
+    description_t* desc;
+    off_t offset;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EBADF);
+
+    desc = file->desc;
+
+    myst_spin_lock(&desc->lock);
+    offset = desc->offset;
+    myst_spin_unlock(&desc->lock);
+
+    if (write)
+        ECHECK(ret = _dd_pwrite(diskdev, file, buf, count, offset));
+    else
+        ECHECK(ret = _dd_pread(diskdev, file, buf, count, offset));
+
+    myst_spin_lock(&desc->lock);
+    desc->offset = offset + ret;
+    myst_spin_unlock(&desc->lock);
+
+done:
+    return ret;
+}
+
+static ssize_t _dd_read(
+    myst_diskdev_t* diskdev,
+    myst_disk_file_t* file,
+    void* buf,
+    size_t count)
+{
+    return _transfer(diskdev, file, buf, count, false);
+}
+
+static ssize_t _dd_write(
+    myst_diskdev_t* diskdev,
+    myst_disk_file_t* file,
+    const void* buf,
+    size_t count)
+{
+    return _transfer(diskdev, file, (void*)buf, count, true);
+}
+
+static ssize_t _dd_readv(
+    myst_diskdev_t* diskdev,
+    myst_disk_file_t* file,
+    const struct iovec* iov,
+    int iovcnt)
+{
+    ssize_t ret = 0;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EBADF);
+
+    ret = myst_fdops_readv(&diskdev->fdops, file, iov, iovcnt);
+    ECHECK(ret);
+
+done:
+    return ret;
+}
+
+static ssize_t _dd_writev(
+    myst_diskdev_t* diskdev,
+    myst_disk_file_t* file,
+    const struct iovec* iov,
+    int iovcnt)
+{
+    ssize_t ret = 0;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EBADF);
+
+    ret = myst_fdops_writev(&diskdev->fdops, file, iov, iovcnt);
+    ECHECK(ret);
+
+done:
+    return ret;
+}
+
+static off_t _dd_lseek(
+    myst_diskdev_t* diskdev,
+    myst_disk_file_t* file,
+    off_t offset,
+    int whence)
+{
+    off_t ret = 0;
+    description_t* desc;
+    off_t base;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EBADF);
+
+    desc = file->desc;
+    myst_spin_lock(&desc->lock);
+
+    switch (whence)
+    {
+        case SEEK_SET:
+            base = 0;
+            break;
+        case SEEK_CUR:
+            base = desc->offset;
+            break;
+        case SEEK_END:
+            base = (off_t)myst_disk_size(desc->disk);
+            break;
+        default:
+            base = -1;
+            break;
+    }
+
+    if (base < 0 || base + offset < 0)
+        ret = -EINVAL;
+    else
+        ret = desc->offset = base + offset;
+
+    myst_spin_unlock(&desc->lock);
+
+done:
+    return ret;
+}
+
+static int _dd_fsync(myst_diskdev_t* diskdev, myst_disk_file_t* file)
+{
+    int ret = 0;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EBADF);
+
+    ECHECK(myst_disk_sync(file->desc->disk));
+
+done:
+    return ret;
+}
+
+static int _dd_fstat(
+    myst_diskdev_t* diskdev,
+    myst_disk_file_t* file,
+    struct stat* statbuf)
+{
+    int ret = 0;
+    struct stat buf;
+
+    if (!diskdev || !_valid_file(file) || !statbuf)
+        ERAISE(-EINVAL);
+
+    memset(&buf, 0, sizeof(buf));
+    buf.st_dev = 5; /* devtmpfs */
+    buf.st_ino = (ino_t)file->desc->disk + 1;
+    buf.st_mode = S_IFBLK | S_IRUSR | S_IWUSR | S_IRGRP;
+    buf.st_nlink = 1;
+    buf.st_uid = MYST_DEFAULT_UID;
+    buf.st_gid = MYST_DEFAULT_GID;
+    buf.st_rdev = makedev(DISK_MAJOR, 16 * file->desc->disk);
+    buf.st_blksize = MYST_BLKSIZE;
+
+    *statbuf = buf;
+
+done:
+    return ret;
+}
+
+static int _dd_fcntl(
+    myst_diskdev_t* diskdev,
+    myst_disk_file_t* file,
+    int cmd,
+    long arg)
+{
+    int ret = 0;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EINVAL);
+
+    switch (cmd)
+    {
+        case F_SETFD:
+        {
+            if (arg != FD_CLOEXEC && arg != 0)
+                ERAISE(-EINVAL);
+
+            file->fdflags = arg;
+            goto done;
+        }
+        case F_GETFD:
+        {
+            ret = file->fdflags;
+            goto done;
+        }
+        case F_SETFL:
+        {
+            /* the status flags make no difference to a disk */
+            goto done;
+        }
+        case F_GETFL:
+        {
+            ret = file->desc->flags;
+            goto done;
+        }
+        default:
+        {
+            ERAISE(-ENOTSUP);
+        }
+    }
+
+done:
+    return ret;
+}
+
+static int _dd_ioctl(
+    myst_diskdev_t* diskdev,
+    myst_disk_file_t* file,
+    unsigned long request,
+    long arg)
+{
+    int ret = 0;
+    int disk;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EBADF);
+
+    disk = file->desc->disk;
+
+    switch (request)
+    {
+        case BLKGETSIZE64:
+        {
+            *(uint64_t*)arg = myst_disk_size(disk);
+            break;
+        }
+        case BLKGETSIZE:
+        {
+            /* in 512-byte sectors */
+            *(unsigned long*)arg = myst_disk_size(disk) / 512;
+            break;
+        }
+        case BLKSSZGET:
+        case BLKBSZGET:
+        {
+            *(int*)arg = MYST_BLKSIZE;
+            break;
+        }
+        case BLKROGET:
+        {
+            *(int*)arg = myst_disk_read_only(disk);
+            break;
+        }
+        case BLKFLSBUF:
+        {
+            ECHECK(myst_disk_sync(disk));
+            break;
+        }
+        default:
+        {
+            ERAISE(-ENOTTY);
+        }
+    }
+
+done:
+    return ret;
+}
+
+static int _dd_dup(
+    myst_diskdev_t* diskdev,
+    const myst_disk_file_t* file,
+    myst_disk_file_t** file_out)
+{
+    int ret = 0;
+    myst_disk_file_t* new_file;
+
+    if (file_out)
+        *file_out = NULL;
+
+    if (!diskdev || !_valid_file(file) || !file_out)
+        ERAISE(-EINVAL);
+
+    if (!(new_file = calloc(1, sizeof(myst_disk_file_t))))
+        ERAISE(-ENOMEM);
+
+    *new_file = *file;
+
+    /* file descriptor flags are not propagated */
+    new_file->fdflags = 0;
+
+    myst_spin_lock(&file->desc->lock);
+    file->desc->refs++;
+    myst_spin_unlock(&file->desc->lock);
+
+    *file_out = new_file;
+
+done:
+    return ret;
+}
+
+static int _dd_close(myst_diskdev_t* diskdev, myst_disk_file_t* file)
+{
+    int ret = 0;
+    description_t* desc;
+    bool release;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EBADF);
+
+    desc = file->desc;
+
+    myst_spin_lock(&desc->lock);
+    release = --desc->refs == 0;
+    myst_spin_unlock(&desc->lock);
+
+    if (release)
+    {
+        memset(desc, 0, sizeof(description_t));
+        free(desc);
+    }
+
+    memset(file, 0, sizeof(myst_disk_file_t));
+    free(file);
+
+done:
+    return ret;
+}
+
+static int _dd_target_fd(myst_diskdev_t* diskdev, myst_disk_file_t* file)
+{
+    int ret = 0;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EINVAL);
+
+    ret = -ENOTSUP;
+
+done:
+    return ret;
+}
+
+static int _dd_get_events(myst_diskdev_t* diskdev, myst_disk_file_t* file)
+{
+    int ret = 0;
+
+    if (!diskdev || !_valid_file(file))
+        ERAISE(-EINVAL);
+
+    /* a disk is always ready */
+    ret = POLLIN | POLLOUT;
+
+done:
+    return ret;
+}
+
+static const myst_ioctl_t _ioctl_list[] = {
+    MYST_IOCTL(BLKGETSIZE64, MYST_IOCTL_OUT, uint64_t),
+    MYST_IOCTL(BLKGETSIZE, MYST_IOCTL_OUT, unsigned long),
+    MYST_IOCTL(BLKSSZGET, MYST_IOCTL_OUT, int),
+    MYST_IOCTL(BLKBSZGET, MYST_IOCTL_OUT, int),
+    MYST_IOCTL(BLKROGET, MYST_IOCTL_OUT, int),
+    MYST_IOCTL_ARG(BLKFLSBUF),
+};
+
+static const myst_ioctl_table_t _ioctls =
+    MYST_IOCTL_TABLE("disk", _ioctl_list);
+
+extern myst_diskdev_t* myst_diskdev_get(void)
+{
+    // clang-format-off
+    static myst_diskdev_t _diskdev = {
+        {
+            .fd_read = (void*)_dd_read,
+            .fd_write = (void*)_dd_write,
+            .fd_readv = (void*)_dd_readv,
+            .fd_writev = (void*)_dd_writev,
+            .fd_fstat = (void*)_dd_fstat,
+            .fd_fcntl = (void*)_dd_fcntl,
+            .fd_ioctl = (void*)_dd_ioctl,
+            .fd_ioctls = &_ioctls,
+            .fd_dup = (void*)_dd_dup,
+            .fd_close = (void*)_dd_close,
+            .fd_target_fd = (void*)_dd_target_fd,
+            .fd_get_events = (void*)_dd_get_events,
+        },
+        .dd_open = _dd_open,
+        .dd_lseek = _dd_lseek,
+        .dd_pread = _dd_pread,
+        .dd_pwrite = _dd_pwrite,
+        .dd_fsync = _dd_fsync,
+    };
+    // clang-format-on
+
+    return &_diskdev;
+}
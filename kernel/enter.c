@@ -4,12 +4,21 @@
 #include <assert.h>
 #include <stdlib.h>
 #include <string.h>
+#include <sys/personality.h>
 
 #include <myst/atexit.h>
+#include <myst/audit.h>
+#include <myst/budget.h>
+#include <myst/console.h>
+#include <myst/consolering.h>
+#include <myst/coredump.h>
 #include <myst/cpio.h>
 #include <myst/crash.h>
+#include <myst/debugmalloc.h>
+#include <myst/disk.h>
 #include <myst/eraise.h>
 #include <myst/errno.h>
+#include <myst/etc.h>
 #include <myst/exec.h>
 #include <myst/fdtable.h>
 #include <myst/file.h>
@@ -17,20 +26,33 @@
 #include <myst/fsgs.h>
 #include <myst/hex.h>
 #include <myst/hostfs.h>
+#include <myst/hostimport.h>
 #include <myst/initfini.h>
 #include <myst/kernel.h>
+#include <myst/metrics.h>
 #include <myst/mmanutils.h>
 #include <myst/mount.h>
+#include <myst/oom.h>
 #include <myst/options.h>
+#include <myst/overlayfs.h>
 #include <myst/panic.h>
 #include <myst/printf.h>
 #include <myst/process.h>
 #include <myst/procfs.h>
 #include <myst/pubkey.h>
+#include <myst/quotecache.h>
+#include <myst/rootfslayers.h>
 #include <myst/ramfs.h>
+#include <myst/sched.h>
+#include <myst/secret.h>
+#include <myst/services.h>
+#include <myst/shutdown.h>
+#include <myst/sigsafe.h>
 #include <myst/signal.h>
+#include <myst/stackguard.h>
 #include <myst/strings.h>
 #include <myst/syscall.h>
+#include <myst/syscallpolicy.h>
 #include <myst/tee.h>
 #include <myst/thread.h>
 #include <myst/times.h>
@@ -51,6 +73,8 @@ long myst_tcall(long n, long params[6])
         myst_set_fsbase(myst_get_gsbase());
     }
 
+    myst_counter_add(&myst_metric_tcalls, 1);
+
     long ret = (__myst_kernel_args.tcall)(n, params);
 
     if (fs)
@@ -270,6 +294,50 @@ done:
 }
 #endif /* MYST_ENABLE_HOSTFS */
 
+/* show a new RAM file system over the root file system */
+static int _setup_overlay(void)
+{
+    int ret = 0;
+    myst_fs_t* upper = NULL;
+    myst_fs_t* overlay = NULL;
+    myst_fs_t* old_fs;
+
+    if (myst_init_ramfs(myst_mount_resolve, &upper) != 0)
+    {
+        myst_eprintf("kernel: cannot initialize the overlay upper layer\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* the overlay takes ownership of both layers */
+    if (myst_init_overlayfs(myst_mount_resolve, _fs, upper, &overlay) != 0)
+    {
+        myst_eprintf("kernel: cannot initialize the rootfs overlay\n");
+        ERAISE(-EINVAL);
+    }
+
+    upper = NULL;
+
+    if (myst_mount_replace(overlay, "/", "/", &old_fs) != 0 || old_fs != _fs)
+    {
+        myst_eprintf("kernel: cannot mount the rootfs overlay\n");
+        ERAISE(-EINVAL);
+    }
+
+    _fs = overlay;
+    overlay = NULL;
+
+done:
+
+    /* without releasing the layers, which the mount table still uses */
+    if (overlay)
+        free(overlay);
+
+    if (upper)
+        (*upper->fs_release)(upper);
+
+    return ret;
+}
+
 static const char* _getenv(const char** envp, const char* varname)
 {
     const char* ret = NULL;
@@ -317,9 +385,7 @@ static int _create_tls_credentials(myst_fs_t* fs)
     myst_file_t* file = NULL;
     int flags = O_CREAT | O_WRONLY;
 
-    long params[6] = {
-        (long)&cert, (long)&cert_size, (long)&pkey, (long)&pkey_size};
-    ECHECK(myst_tcall(MYST_TCALL_GEN_CREDS, params));
+    ECHECK(myst_quote_cache_gen_creds(&cert, &cert_size, &pkey, &pkey_size));
 
     // Save the certificate
     ECHECK((fs->fs_open)(fs, certificate_path, flags, 0444, NULL, &file));
@@ -337,11 +403,7 @@ static int _create_tls_credentials(myst_fs_t* fs)
 
 done:
     if (cert || pkey)
-    {
-        long params[6] = {
-            (long)cert, (long)cert_size, (long)pkey, (long)pkey_size};
-        myst_tcall(MYST_TCALL_FREE_CREDS, params);
-    }
+        myst_quote_cache_free_creds(cert, cert_size, pkey, pkey_size);
 
     if (file)
     {
@@ -430,6 +492,8 @@ static int _create_main_thread(uint64_t event, myst_thread_t** thread_out)
     thread->main.thread_group_lock = MYST_SPINLOCK_INITIALIZER;
     thread->thread_lock = &thread->main.thread_group_lock;
     strcpy(thread->name, "main");
+    thread->main.dumpable = 1;
+    thread->main.personality = PER_LINUX;
 
     // Initial process list is just us. All new processes will be inserted in
     // the list. Dont need to set these as they are already NULL, but being here
@@ -563,6 +627,14 @@ done:
     return ret;
 }
 
+/* OOM pressure callback: release unused kernel heap segments */
+static void _trim_heap(size_t needed, void* arg)
+{
+    (void)needed;
+    (void)arg;
+    myst_malloc_trim();
+}
+
 int myst_enter_kernel(myst_kernel_args_t* args)
 {
     int ret = 0;
@@ -577,10 +649,20 @@ int myst_enter_kernel(myst_kernel_args_t* args)
     /* Save the aguments */
     __myst_kernel_args = *args;
 
+    /* this must precede the first kernel allocation */
+    myst_debug_malloc_setup(args->debug_malloc, args->debug_malloc_quarantine);
+
+    /* the audit is for debugging: a production enclave does not panic */
+    myst_sigsafe_setup(args->debug_signal_safety && args->tee_debug_mode);
+
+    myst_budget_setup(
+        args->cpu_time_limit, args->cpu_time_hard_limit, args->wall_time_limit);
+
     /* ATTN: it seems __options can be eliminated */
     __options.trace_syscalls = args->trace_syscalls;
     __options.have_syscall_instruction = args->have_syscall_instruction;
     __options.export_ramfs = args->export_ramfs;
+    __options.export_coverage = args->export_coverage;
 
     /* enable error tracing if requested */
     if (args->trace_errors)
@@ -631,7 +713,8 @@ int myst_enter_kernel(myst_kernel_args_t* args)
     }
 
     /* Setup the memory manager */
-    if (myst_setup_mman(args->mman_data, args->mman_size) != 0)
+    if (myst_setup_mman(
+            args->mman_data, args->mman_size, args->mman_pending_size) != 0)
     {
         myst_eprintf("kernel: memory manager setup failed\n");
         ERAISE(-EINVAL);
@@ -647,6 +730,27 @@ int myst_enter_kernel(myst_kernel_args_t* args)
     /* Mount the root file system */
     ECHECK(_mount_rootfs(args, fstype));
 
+    /* Open the disks that the configuration names */
+    if (myst_disks_setup(&args->disks) != 0)
+    {
+        myst_eprintf("kernel: failed to open the disks\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* Restrict the mounts of the application */
+    if (myst_mount_policy_setup(&args->mount_policy) != 0)
+    {
+        myst_eprintf("kernel: failed to setup the mount policy\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* keep the attested credentials for the TLS handshakes */
+    if (myst_quote_cache_setup(args->quote_cache_lifetime) != 0)
+    {
+        myst_eprintf("kernel: failed to setup the quote cache\n");
+        ERAISE(-EINVAL);
+    }
+
     /* Generate TLS credentials if needed */
     want_tls_creds = _getenv(args->envp, WANT_TLS_CREDENTIAL);
     if (want_tls_creds != NULL)
@@ -673,11 +777,13 @@ int myst_enter_kernel(myst_kernel_args_t* args)
     __myst_main_thread = thread;
 
     thread->main.cwd_lock = MYST_SPINLOCK_INITIALIZER;
+    thread->main.root_lock = MYST_SPINLOCK_INITIALIZER;
     thread->main.cwd = strdup(args->cwd);
     if (thread->main.cwd == NULL)
         ERAISE(-ENOMEM);
 
     thread->main.umask = MYST_DEFAULT_UMASK;
+    myst_budget_init(thread, NULL);
 
     /* Setup virtual proc filesystem */
     procfs_setup();
@@ -686,6 +792,9 @@ int myst_enter_kernel(myst_kernel_args_t* args)
         ECHECK(
             myst_syscall_sethostname(args->hostname, strlen(args->hostname)));
 
+    ECHECK(myst_set_uname(
+        args->uname_release, args->uname_version, args->uname_machine));
+
     /* setup the TTY devices */
     if (_setup_tty() != 0)
     {
@@ -693,6 +802,57 @@ int myst_enter_kernel(myst_kernel_args_t* args)
         ERAISE(-EINVAL);
     }
 
+    /* write the console output through the ring, if the host made one */
+    if (myst_console_ring_setup(args->console_ring) != 0)
+        ERAISE(-EINVAL);
+
+    /* copy the console output to the host log files */
+    {
+        myst_console_config_t config;
+
+        config.stdout_log = args->console_log_stdout;
+        config.stderr_log = args->console_log_stderr;
+        config.timestamps = args->console_log_timestamps;
+        config.max_size = args->console_log_max_size;
+        config.max_files = args->console_log_max_files;
+        config.log_only = args->console_log_only;
+
+        if (myst_console_setup(&config) != 0)
+        {
+            myst_eprintf("kernel: failed to setup the console logs\n");
+            ERAISE(-EINVAL);
+        }
+    }
+
+    /* record security-relevant events in the audit log */
+    if (myst_audit_setup(args->audit_log, args->audit_log_path) != 0)
+    {
+        myst_eprintf("kernel: failed to setup the audit log\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* restrict the syscalls the application may make */
+    if (myst_syscall_policy_setup(&args->syscall_policy) != 0)
+    {
+        myst_eprintf("kernel: failed to setup the syscall policy\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* deny mappings that are both writable and executable */
+    if (myst_wx_setup(&args->wx) != 0)
+    {
+        myst_eprintf("kernel: failed to setup WriteXorExecute\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* handle memory pressure, trimming the kernel heap first */
+    if (myst_oom_setup(args->oom_policy, args->mem_low_watermark) != 0 ||
+        myst_oom_register_callback(_trim_heap, NULL) != 0)
+    {
+        myst_eprintf("kernel: failed to setup the OOM policy\n");
+        ERAISE(-EINVAL);
+    }
+
     /* Unpack the CPIO from memory */
     if (fstype == MYST_FSTYPE_RAMFS &&
         myst_cpio_mem_unpack(
@@ -702,6 +862,71 @@ int myst_enter_kernel(myst_kernel_args_t* args)
         ERAISE(-EINVAL);
     }
 
+    /* Create the /etc files the root file system does not provide */
+    if (fstype == MYST_FSTYPE_RAMFS &&
+        myst_create_etc_files(args->dns_servers, args->num_dns_servers) != 0)
+    {
+        myst_eprintf("kernel: failed to create /etc files\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* From here on, root file system changes go to a RAM file system */
+    if (args->rootfs_overlay)
+        ECHECK(_setup_overlay());
+
+    /* Apply the root file system layers, whose hashes are in the
+     * configuration (to the overlay rather than to an EXT2 image) */
+    if (args->rootfs_layers.num_layers && fstype != MYST_FSTYPE_RAMFS &&
+        !args->rootfs_overlay)
+    {
+        myst_eprintf("kernel: RootfsLayers requires RootfsOverlay\n");
+        ERAISE(-EINVAL);
+    }
+
+    if (myst_apply_rootfs_layers(&args->rootfs_layers) != 0)
+    {
+        myst_eprintf("kernel: failed to apply the root file system layers\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* Copy the host directories whose hashes are in the configuration */
+    if (myst_import_host_dirs(&args->host_imports) != 0)
+    {
+        myst_eprintf("kernel: failed to import the host directories\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* Copy the host files that the configuration names */
+    if (myst_import_host_files(&args->host_imports) != 0)
+    {
+        myst_eprintf("kernel: failed to copy the host files\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* Fetch the configured secrets into the root file system and environment */
+    if (myst_secrets_setup(
+            &args->secrets, &args->key_release, &args->envc, &args->envp) != 0)
+    {
+        myst_eprintf("kernel: failed to fetch the configured secrets\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* Write core dumps on crashes (the key is in the root file system) */
+    if (myst_coredump_setup(&args->coredump, args->tee_debug_mode) != 0)
+    {
+        myst_eprintf("kernel: failed to setup core dumps\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* Stop the processes when the host asks or the application exits */
+    ECHECK(myst_shutdown_setup(&args->shutdown));
+
+    /* Raise SIGSEGV when a thread faults in the guard below its stack */
+    ECHECK(myst_stack_guard_setup());
+
+    /* Follow the steps of the host's realtime clock */
+    myst_clock_resync_start();
+
     /* Create top-level proc entries */
     create_proc_root_entries();
 
@@ -714,10 +939,26 @@ int myst_enter_kernel(myst_kernel_args_t* args)
 #endif
 
     myst_times_start();
+    myst_sched_take_cpu(thread);
 
     /* Run the main program: wait for SYS_exit to perform longjmp() */
     if (myst_setjmp(&thread->jmpbuf) == 0)
     {
+        /* act as the init process for the configured services instead */
+        if (args->services.num_services)
+        {
+            thread->exit_status = myst_run_services(
+                &args->services, args->envc, args->envp);
+
+            if (thread->exit_status < 0)
+            {
+                myst_eprintf("kernel: failed to run the services\n");
+                thread->exit_status = 1;
+            }
+
+            myst_longjmp(&thread->jmpbuf, 1);
+        }
+
         /* enter the C-runtime on the target thread descriptor */
         if (myst_exec(
                 thread,
@@ -744,6 +985,9 @@ int myst_enter_kernel(myst_kernel_args_t* args)
         /* thread jumps here on SYS_exit syscall */
         exit_status = thread->exit_status;
 
+        /* stop the processes that outlived the main process */
+        myst_shutdown_processes();
+
         /* release the fdtable */
         if (thread->fdtable)
         {
@@ -773,6 +1017,10 @@ int myst_enter_kernel(myst_kernel_args_t* args)
         free(thread->main.cwd);
         thread->main.cwd = NULL;
 
+        /* Free the root directory */
+        free(thread->main.root);
+        thread->main.root = NULL;
+
         /* switch back to the target thread descriptor */
         myst_set_fsbase(thread->target_td);
     }
@@ -792,11 +1040,16 @@ int myst_enter_kernel(myst_kernel_args_t* args)
     _teardown_ramfs();
 
     /* Put the thread on the zombie list */
+    myst_sched_release_cpu(thread);
     myst_zombify_thread(thread);
 
     /* call functions installed with myst_atexit() */
     myst_call_atexit_functions();
 
+    /* report the kernel allocations that were never freed */
+    if (myst_debug_malloc_enabled())
+        myst_debug_malloc_report();
+
 #ifdef MYST_ENABLE_LEAK_CHECKER
     /* Check for memory leaks */
     if (myst_find_leaks() != 0)
@@ -16,6 +16,7 @@
 #include <myst/eraise.h>
 #include <myst/fdtable.h>
 #include <myst/id.h>
+#include <myst/ioctl.h>
 #include <myst/list.h>
 #include <myst/spinlock.h>
 #include <myst/syscall.h>
@@ -492,7 +493,7 @@ static int _ed_ioctl(
     if (!epolldev || !_valid_epoll(epoll))
         ERAISE(-EBADF);
 
-    ERAISE(-ENOTSUP);
+    ERAISE(-ENOTTY);
 
 done:
 
@@ -561,6 +562,9 @@ done:
     return ret;
 }
 
+/* no requests: each fails with ENOTTY */
+static const myst_ioctl_table_t _ioctls = MYST_IOCTL_TABLE_NONE("epoll");
+
 extern myst_epolldev_t* myst_epolldev_get(void)
 {
     // clang-format-off
@@ -573,6 +577,7 @@ extern myst_epolldev_t* myst_epolldev_get(void)
             .fd_fstat = (void*)_ed_fstat,
             .fd_fcntl = (void*)_ed_fcntl,
             .fd_ioctl = (void*)_ed_ioctl,
+            .fd_ioctls = &_ioctls,
             .fd_dup = (void*)_ed_dup,
             .fd_close = (void*)_ed_close,
             .fd_target_fd = (void*)_ed_target_fd,
@@ -0,0 +1,125 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/utsname.h>
+#include <unistd.h>
+
+#include <myst/buf.h>
+#include <myst/eraise.h>
+#include <myst/etc.h>
+#include <myst/file.h>
+#include <myst/id.h>
+#include <myst/printf.h>
+#include <myst/syscall.h>
+
+static int _append(myst_buf_t* buf, const char* str)
+{
+    return myst_buf_append(buf, str, strlen(str));
+}
+
+/* create the file unless the root file system already provides it */
+static int _create_file(const char* path, const myst_buf_t* buf)
+{
+    int ret = 0;
+    int fd = -1;
+
+    if (access(path, F_OK) == 0)
+        goto done;
+
+    /* the syscall rather than open(), to raise its error */
+    ECHECK(fd = myst_syscall_open(path, O_WRONLY | O_CREAT | O_EXCL, 0644));
+
+    if (myst_writen(fd, buf->data, buf->size) != 0)
+        ERAISE(-EIO);
+
+done:
+
+    if (fd >= 0)
+        myst_syscall_close(fd);
+
+    return ret;
+}
+
+int myst_create_etc_files(
+    const char* const* dns_servers,
+    size_t num_dns_servers)
+{
+    int ret = 0;
+    struct utsname uts;
+    myst_buf_t buf = MYST_BUF_INITIALIZER;
+    char line[256];
+
+    ECHECK(myst_syscall_uname(&uts));
+
+    if (myst_mkdirhier("/etc", 0755) != 0)
+        ERAISE(-EINVAL);
+
+    /* /etc/hostname */
+    {
+        ECHECK(_append(&buf, uts.nodename));
+        ECHECK(_append(&buf, "\n"));
+        ECHECK(_create_file("/etc/hostname", &buf));
+        myst_buf_clear(&buf);
+    }
+
+    /* /etc/hosts */
+    {
+        ECHECK(_append(&buf, "127.0.0.1\tlocalhost\n"));
+        ECHECK(_append(&buf, "::1\tlocalhost ip6-localhost ip6-loopback\n"));
+        snprintf(line, sizeof(line), "127.0.1.1\t%s\n", uts.nodename);
+        ECHECK(_append(&buf, line));
+        ECHECK(_create_file("/etc/hosts", &buf));
+        myst_buf_clear(&buf);
+    }
+
+    /* /etc/resolv.conf (the C library falls back to 127.0.0.1 without it) */
+    if (num_dns_servers)
+    {
+        for (size_t i = 0; i < num_dns_servers; i++)
+        {
+            snprintf(line, sizeof(line), "nameserver %s\n", dns_servers[i]);
+            ECHECK(_append(&buf, line));
+        }
+
+        ECHECK(_create_file("/etc/resolv.conf", &buf));
+        myst_buf_clear(&buf);
+    }
+
+    /* /etc/passwd: the user that all processes run as */
+    {
+        snprintf(
+            line,
+            sizeof(line),
+            "root:x:%u:%u:root:/root:/bin/sh\n",
+            MYST_DEFAULT_UID,
+            MYST_DEFAULT_GID);
+        ECHECK(_append(&buf, line));
+        ECHECK(_create_file("/etc/passwd", &buf));
+        myst_buf_clear(&buf);
+    }
+
+    /* /etc/group */
+    {
+        snprintf(line, sizeof(line), "root:x:%u:\n", MYST_DEFAULT_GID);
+        ECHECK(_append(&buf, line));
+        ECHECK(_create_file("/etc/group", &buf));
+        myst_buf_clear(&buf);
+    }
+
+    /* /etc/nsswitch.conf (for glibc-based applications) */
+    {
+        ECHECK(_append(&buf, "passwd:\tfiles\n"));
+        ECHECK(_append(&buf, "group:\tfiles\n"));
+        ECHECK(_append(&buf, "hosts:\tfiles dns\n"));
+        ECHECK(_create_file("/etc/nsswitch.conf", &buf));
+        myst_buf_clear(&buf);
+    }
+
+done:
+    myst_buf_release(&buf);
+    return ret;
+}
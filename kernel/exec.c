@@ -9,6 +9,7 @@
 #include <sys/mman.h>
 
 #include <myst/atexit.h>
+#include <myst/audit.h>
 #include <myst/cpio.h>
 #include <myst/eraise.h>
 #include <myst/exec.h>
@@ -22,14 +23,17 @@
 #include <myst/process.h>
 #include <myst/reloc.h>
 #include <myst/round.h>
+#include <myst/rusage.h>
 #include <myst/setjmp.h>
 #include <myst/spinlock.h>
+#include <myst/stackguard.h>
 #include <myst/strings.h>
 #include <myst/syscall.h>
 #include <myst/tcall.h>
 #include <myst/thread.h>
+#include <myst/wxpolicy.h>
 
-#define GUARD 0x4f
+#define GUARD MYST_STACK_GUARD_BYTE
 
 typedef struct _pair
 {
@@ -833,6 +837,9 @@ int myst_exec(
     if ((crt_size % PAGE_SIZE) != 0)
         ERAISE(-EINVAL);
 
+    myst_audit_exec(argv[0], argc);
+    myst_wx_exec(myst_find_process_thread(thread), argv[0]);
+
     /* allocate and zero-fill the new CRT image */
     {
         const int prot = PROT_READ | PROT_WRITE | PROT_EXEC;
@@ -948,10 +955,26 @@ int myst_exec(
     if (_setup_exe_link(argv[0]) != 0)
         ERAISE(-EIO);
 
+    /* like Linux, name the process after the basename of the executable */
+    {
+        const char* slash = strrchr(argv[0], '/');
+        ECHECK(myst_set_thread_name(thread, slash ? slash + 1 : argv[0]));
+    }
+
+    /* the new program registers its own robust futex list */
+    thread->robust_list_head = NULL;
+    thread->robust_list_len = 0;
+
     /* The thread is responsible for freeing the stack */
     thread->main.exec_stack = stack;
+    thread->main.exec_stack_size = stack_size;
     thread->main.exec_crt_data = crt_data;
     thread->main.exec_crt_size = crt_size;
+    myst_rusage_add_rss((long)(crt_size + stack_size));
+
+    /* the new program starts on the new stack without a signal stack */
+    myst_stack_guard_set(thread, stack, PAGE_SIZE);
+    memset(&thread->signal.altstack, 0, sizeof(stack_t));
 
     /* close file descriptors with FD_CLOEXEC flag */
     {
@@ -971,6 +994,7 @@ int myst_exec(
     /* unreachable */
 
     thread->main.exec_stack = NULL;
+    thread->main.exec_stack_size = 0;
     thread->main.exec_crt_data = NULL;
     thread->main.exec_crt_size = 0;
     ERAISE(-ENOEXEC);
@@ -176,6 +176,26 @@ done:
     return ret;
 }
 
+size_t myst_fdtable_count(myst_fdtable_t* fdtable)
+{
+    size_t count = 0;
+
+    if (!fdtable)
+        return 0;
+
+    myst_spin_lock(&fdtable->lock);
+    {
+        for (int i = 0; i < MYST_FDTABLE_SIZE; i++)
+        {
+            if (fdtable->entries[i].type != MYST_FDTABLE_TYPE_NONE)
+                count++;
+        }
+    }
+    myst_spin_unlock(&fdtable->lock);
+
+    return count;
+}
+
 int myst_fdtable_assign(
     myst_fdtable_t* fdtable,
     myst_fdtable_type_t type,
@@ -2,8 +2,10 @@
 // Licensed under the MIT License.
 
 #include <limits.h>
+#include <stdlib.h>
 
 #include <myst/blkdev.h>
+#include <myst/disk.h>
 #include <myst/eraise.h>
 #include <myst/ext2.h>
 #include <myst/fs.h>
@@ -13,7 +15,9 @@
 #include <myst/mount.h>
 #include <myst/process.h>
 #include <myst/pubkey.h>
+#include <myst/realpath.h>
 #include <myst/roothash.h>
+#include <myst/strings.h>
 #include <myst/syscall.h>
 #include <myst/tcall.h>
 #include <myst/thread.h>
@@ -51,6 +55,31 @@ done:
     return ret;
 }
 
+int myst_fs_path(myst_fs_t* fs, myst_file_t* file, char* buf, size_t size)
+{
+    int ret = 0;
+    myst_path_t* path = NULL;
+
+    if (!fs || !file || !buf)
+        ERAISE(-EINVAL);
+
+    if (!(path = malloc(sizeof(myst_path_t))))
+        ERAISE(-ENOMEM);
+
+    ECHECK((*fs->fs_realpath)(fs, file, buf, size));
+    ECHECK(myst_realpath_unroot(buf, path));
+
+    if (myst_strlcpy(buf, path->buf, size) >= size)
+        ERAISE(-ENAMETOOLONG);
+
+done:
+
+    if (path)
+        free(path);
+
+    return ret;
+}
+
 #ifdef MYST_ENABLE_EXT2FS
 int myst_load_fs(
     myst_mount_resolve_callback_t resolve_cb,
@@ -70,46 +99,54 @@ int myst_load_fs(
     if (!source)
         ERAISE(-EINVAL);
 
-    /* load the file-system signature structure */
-    if ((r = myst_tcall_load_fssig(source, &fssig)) != 0 && r != -ENOTSUP)
-        ERAISE(-r);
-
-    /* create the bottom device (verity or raw) */
-    if (fssig.magic == MYST_FSSIG_MAGIC)
+    if (myst_disk_find(source) >= 0)
+    {
+        /* a disk of config.json (which the host knows by another name) */
+        ECHECK(myst_disk_open_blkdev(source, &blkdev));
+    }
+    else
     {
-        if (fssig.signature_size)
+        /* load the file-system signature structure */
+        if ((r = myst_tcall_load_fssig(source, &fssig)) != 0 && r != -ENOTSUP)
+            ERAISE(-r);
+
+        /* create the bottom device (verity or raw) */
+        if (fssig.magic == MYST_FSSIG_MAGIC)
         {
-            ECHECK(myst_pubkey_verify(
-                __myst_kernel_args.archive_data,
-                __myst_kernel_args.archive_size,
+            if (fssig.signature_size)
+            {
+                ECHECK(myst_pubkey_verify(
+                    __myst_kernel_args.archive_data,
+                    __myst_kernel_args.archive_size,
+                    fssig.root_hash,
+                    sizeof(fssig.root_hash),
+                    fssig.signer,
+                    sizeof(fssig.signer),
+                    fssig.signature,
+                    fssig.signature_size));
+            }
+            else
+            {
+                ECHECK(myst_roothash_verify(
+                    __myst_kernel_args.archive_data,
+                    __myst_kernel_args.archive_size,
+                    fssig.root_hash,
+                    sizeof(fssig.root_hash)));
+            }
+
+            /* create the device stack */
+            ECHECK(myst_verityblkdev_open(
+                source,
+                fssig.hash_offset,
                 fssig.root_hash,
-                sizeof(fssig.root_hash),
-                fssig.signer,
-                sizeof(fssig.signer),
-                fssig.signature,
-                fssig.signature_size));
+                sizeof(myst_sha256_t),
+                &blkdev));
         }
         else
         {
-            ECHECK(myst_roothash_verify(
-                __myst_kernel_args.archive_data,
-                __myst_kernel_args.archive_size,
-                fssig.root_hash,
-                sizeof(fssig.root_hash)));
+            const bool ephemeral = true;
+            ECHECK(myst_rawblkdev_open(source, ephemeral, 0, &blkdev));
         }
-
-        /* create the device stack */
-        ECHECK(myst_verityblkdev_open(
-            source,
-            fssig.hash_offset,
-            fssig.root_hash,
-            sizeof(myst_sha256_t),
-            &blkdev));
-    }
-    else
-    {
-        const bool ephemeral = true;
-        ECHECK(myst_rawblkdev_open(source, ephemeral, 0, &blkdev));
     }
 
     if (key)
@@ -8,8 +8,11 @@
 #include <myst/cond.h>
 #include <myst/eraise.h>
 #include <myst/futex.h>
+#include <myst/metrics.h>
+#include <myst/mmanutils.h>
 #include <myst/strings.h>
 #include <myst/thread.h>
+#include <myst/uaccess.h>
 
 /*
 **==============================================================================
@@ -27,11 +30,22 @@
 
 typedef struct futex futex_t;
 
+/* What a futex is known by: its address for a private futex and, for a
+ * shared futex in a MAP_SHARED mapping of a file, the file and the offset
+ * in it, which are the same for every process that maps the futex (a
+ * shared futex that is not in such a mapping is known by its address) */
+typedef struct futex_key
+{
+    dev_t dev;
+    ino_t ino;
+    uint64_t offset;
+} futex_key_t;
+
 struct futex
 {
     futex_t* next;
     size_t refs;
-    volatile int* uaddr;
+    futex_key_t key;
     myst_cond_t cond;
     myst_mutex_t mutex;
 };
@@ -76,12 +90,44 @@ static void _free_futexes(void* arg)
     }
 }
 
-static futex_t* _get_futex(volatile int* uaddr)
+static void _get_key(const int* uaddr, bool shared, futex_key_t* key)
+{
+    myst_mapping_key_t mk;
+
+    if (shared && myst_get_shared_mapping_key(uaddr, &mk) == 0)
+    {
+        key->dev = mk.dev;
+        key->ino = mk.ino;
+        key->offset = mk.offset;
+    }
+    else
+    {
+        key->dev = 0;
+        key->ino = 0;
+        key->offset = (uint64_t)uaddr;
+    }
+}
+
+static bool _same_key(const futex_key_t* x, const futex_key_t* y)
+{
+    return x->dev == y->dev && x->ino == y->ino && x->offset == y->offset;
+}
+
+static uint64_t _index(const futex_key_t* key)
+{
+    return (key->dev ^ key->ino ^ (key->offset >> 4)) % NUM_CHAINS;
+}
+
+static futex_t* _get_futex(const int* uaddr, bool shared)
 {
     futex_t* ret = NULL;
-    uint64_t index = ((uint64_t)uaddr >> 4) % NUM_CHAINS;
+    futex_key_t key;
+    uint64_t index;
     futex_t* f;
 
+    _get_key(uaddr, shared, &key);
+    index = _index(&key);
+
     _lock();
 
     if (!_installed_free_futexes)
@@ -92,7 +138,7 @@ static futex_t* _get_futex(volatile int* uaddr)
 
     for (futex_t* p = _chains[index]; p; p = p->next)
     {
-        if (p->uaddr == uaddr)
+        if (_same_key(&p->key, &key))
         {
             p->refs++;
             ret = p;
@@ -104,7 +150,7 @@ static futex_t* _get_futex(volatile int* uaddr)
         goto done;
 
     f->refs = 1;
-    f->uaddr = uaddr;
+    f->key = key;
     f->next = _chains[index];
     _chains[index] = f;
 
@@ -117,18 +163,18 @@ done:
     return ret;
 }
 
-static int _put_futex(int* uaddr)
+static int _put_futex(futex_t* f)
 {
 #if 0
     int ret = -1;
-    uint64_t index = ((uint64_t)uaddr >> 2) % NUM_CHAINS;
+    uint64_t index = _index(&f->key);
     futex_t* prev = NULL;
 
     myst_spin_lock(&_lock);
 
     for (futex_t* p = _chains[index]; p; p = p->next)
     {
-        if (p->uaddr == uaddr)
+        if (p == f)
         {
             p->refs--;
 
@@ -154,16 +200,21 @@ done:
 
     return ret;
 #else
-    (void)uaddr;
+    (void)f;
     return 0;
 #endif
 }
 
-int myst_futex_wait(int* uaddr, int val, const struct timespec* to)
+int myst_futex_wait(
+    int* uaddr,
+    int val,
+    const struct timespec* to,
+    bool shared)
 {
     int ret = 0;
     futex_t* f = NULL;
     myst_thread_t* me = myst_thread_self();
+    myst_deadline_t deadline;
 
 #if defined(DEBUG_TRACE)
     printf("%s(): uaddr=%p\n", __FUNCTION__, uaddr);
@@ -175,7 +226,11 @@ int myst_futex_wait(int* uaddr, int val, const struct timespec* to)
         goto done;
     }
 
-    if (!(f = _get_futex(uaddr)))
+    /* the timeout runs from here, however long the futex takes to lock */
+    if ((ret = myst_deadline_init(&deadline, to)) != 0)
+        goto done;
+
+    if (!(f = _get_futex(uaddr, shared)))
     {
         ret = -ENOMEM;
         goto done;
@@ -184,6 +239,7 @@ int myst_futex_wait(int* uaddr, int val, const struct timespec* to)
     myst_mutex_lock(&f->mutex);
     {
         int retval;
+        long begin;
 
         if (*uaddr != val)
         {
@@ -195,7 +251,10 @@ int myst_futex_wait(int* uaddr, int val, const struct timespec* to)
         // Give termination signal handler a chance to wake up the thread.
         me->signal.cond_wait = &f->cond;
 
-        retval = myst_cond_timedwait(&f->cond, &f->mutex, to);
+        begin = myst_deadline_now();
+        retval = myst_cond_wait_deadline(&f->cond, &f->mutex, &deadline);
+        myst_histogram_observe(
+            &myst_metric_futex_wait_seconds, myst_deadline_now() - begin);
 
         me->signal.cond_wait = NULL;
 
@@ -207,12 +266,12 @@ int myst_futex_wait(int* uaddr, int val, const struct timespec* to)
 done:
 
     if (f)
-        _put_futex(uaddr);
+        _put_futex(f);
 
     return ret;
 }
 
-int myst_futex_wake(int* uaddr, int val)
+int myst_futex_wake(int* uaddr, int val, bool shared)
 {
     int ret = 0;
     futex_t* f = NULL;
@@ -228,7 +287,7 @@ int myst_futex_wake(int* uaddr, int val)
         goto done;
     }
 
-    if (!(f = _get_futex(uaddr)))
+    if (!(f = _get_futex(uaddr, shared)))
     {
         ret = -ENOMEM;
         goto done;
@@ -268,7 +327,7 @@ done:
         myst_mutex_unlock(&f->mutex);
 
     if (f)
-        _put_futex(uaddr);
+        _put_futex(f);
 
     return ret;
 }
@@ -280,6 +339,7 @@ static int _futex_requeue(int* uaddr, int op, int val, int val2, int* uaddr2)
     futex_t* f2 = NULL;
     bool locked = false;
     bool locked2 = false;
+    bool shared = !(op & FUTEX_PRIVATE);
 
 #if defined(DEBUG_TRACE)
     printf("%s(): uaddr=%p\n", __FUNCTION__, uaddr);
@@ -298,13 +358,13 @@ static int _futex_requeue(int* uaddr, int op, int val, int val2, int* uaddr2)
         goto done;
     }
 
-    if (!(f = _get_futex(uaddr)))
+    if (!(f = _get_futex(uaddr, shared)))
     {
         ret = -ENOMEM;
         goto done;
     }
 
-    if (!(f2 = _get_futex(uaddr2)))
+    if (!(f2 = _get_futex(uaddr2, shared)))
     {
         ret = -ENOMEM;
         goto done;
@@ -337,10 +397,10 @@ done:
         myst_mutex_unlock(&f2->mutex);
 
     if (f)
-        _put_futex(uaddr);
+        _put_futex(f);
 
     if (f2)
-        _put_futex(uaddr2);
+        _put_futex(f2);
 
     return ret;
 }
@@ -362,16 +422,18 @@ long myst_syscall_futex(
     int val3)
 {
     long ret = 0;
+    bool shared = !(op & FUTEX_PRIVATE);
 
     (void)val3;
 
     if (op == FUTEX_WAIT || op == (FUTEX_WAIT | FUTEX_PRIVATE))
     {
-        ECHECK(myst_futex_wait(uaddr, val, (const struct timespec*)arg));
+        const struct timespec* to = (const struct timespec*)arg;
+        ECHECK(myst_futex_wait(uaddr, val, to, shared));
     }
     else if (op == FUTEX_WAKE || op == (FUTEX_WAKE | FUTEX_PRIVATE))
     {
-        ECHECK(myst_futex_wake(uaddr, val));
+        ECHECK(myst_futex_wake(uaddr, val, shared));
     }
     else if (op == FUTEX_REQUEUE || op == (FUTEX_REQUEUE | FUTEX_PRIVATE))
     {
@@ -385,3 +447,139 @@ long myst_syscall_futex(
 done:
     return ret;
 }
+
+/*
+**==============================================================================
+**
+** robust futex lists:
+**
+** A thread registers the list of robust mutexes that it holds with
+** set_robust_list(). When the thread exits, the kernel walks that list and
+** marks every futex still owned by the thread with FUTEX_OWNER_DIED, waking
+** one waiter, so that the next pthread_mutex_lock() returns EOWNERDEAD
+** instead of blocking forever.
+**
+**==============================================================================
+*/
+
+long myst_syscall_set_robust_list(struct robust_list_head* head, size_t len)
+{
+    myst_thread_t* thread = myst_thread_self();
+
+    if (len != sizeof(struct robust_list_head))
+        return -EINVAL;
+
+    thread->robust_list_head = head;
+    thread->robust_list_len = len;
+
+    return 0;
+}
+
+long myst_syscall_get_robust_list(
+    int pid,
+    struct robust_list_head** head_ptr,
+    size_t* len_ptr)
+{
+    long ret = 0;
+    myst_thread_t* thread = myst_thread_self();
+
+    if (!head_ptr || !len_ptr)
+        ERAISE(-EFAULT);
+
+    if (pid && !(thread = myst_find_thread(pid)))
+        ERAISE(-ESRCH);
+
+    *head_ptr = thread->robust_list_head;
+    *len_ptr = thread->robust_list_len;
+
+done:
+    return ret;
+}
+
+/* release a futex owned by a thread that died (see handle_futex_death()) */
+static void _handle_futex_death(
+    struct robust_list* entry,
+    long futex_offset,
+    pid_t tid,
+    bool pending_op)
+{
+    volatile uint32_t* uaddr;
+    uint32_t uval;
+    uint32_t nval;
+
+    uaddr = (uint32_t*)((uintptr_t)entry + (uintptr_t)futex_offset);
+
+    /* futex words must be 4-byte aligned user memory */
+    if ((uintptr_t)uaddr % sizeof(uint32_t) ||
+        !myst_is_user_range((void*)uaddr, sizeof(uint32_t)))
+        return;
+
+    uval = *uaddr;
+
+    /* the thread died between clearing the word and removing the entry:
+     * wake a waiter that may have seen the word before it was cleared */
+    if (pending_op && uval == 0)
+    {
+        myst_futex_wake((int*)uaddr, 1, true);
+        return;
+    }
+
+    do
+    {
+        if ((uval & FUTEX_TID_MASK) != (uint32_t)tid)
+            return;
+
+        /* keep FUTEX_WAITERS so that the next unlock wakes the others */
+        nval = (uval & FUTEX_WAITERS) | FUTEX_OWNER_DIED;
+    } while (!__atomic_compare_exchange_n(
+        uaddr, &uval, nval, false, __ATOMIC_SEQ_CST, __ATOMIC_SEQ_CST));
+
+    if (nval & FUTEX_WAITERS)
+        myst_futex_wake((int*)uaddr, 1, true);
+}
+
+/* the entries may have bit 0 set to mark a priority-inheritance futex */
+static struct robust_list* _robust_entry(struct robust_list* entry)
+{
+    return (struct robust_list*)((uintptr_t)entry & ~(uintptr_t)1);
+}
+
+void myst_futex_exit_robust_list(struct myst_thread* thread)
+{
+    struct robust_list_head* uhead = thread->robust_list_head;
+    struct robust_list_head head;
+    struct robust_list* entry;
+    struct robust_list* pending;
+    size_t limit = ROBUST_LIST_LIMIT;
+
+    if (!uhead)
+        return;
+
+    thread->robust_list_head = NULL;
+    thread->robust_list_len = 0;
+
+    /* the list is in user memory, which the thread may have left corrupt */
+    if (MYST_COPY_FROM_USER(&head, uhead) != 0)
+        return;
+
+    entry = _robust_entry(head.list.next);
+    pending = _robust_entry(head.list_op_pending);
+
+    /* a corrupt or circular list is abandoned after ROBUST_LIST_LIMIT */
+    while (entry && entry != &uhead->list && limit--)
+    {
+        struct robust_list* next;
+
+        if (MYST_COPY_FROM_USER(&next, &entry->next) != 0)
+            break;
+
+        /* the pending entry is handled below */
+        if (entry != pending)
+            _handle_futex_death(entry, head.futex_offset, thread->tid, false);
+
+        entry = _robust_entry(next);
+    }
+
+    if (pending)
+        _handle_futex_death(pending, head.futex_offset, thread->tid, true);
+}
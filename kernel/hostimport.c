@@ -0,0 +1,538 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stddef.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+
+#include <myst/dirhash.h>
+#include <myst/eraise.h>
+#include <myst/errno.h>
+#include <myst/file.h>
+#include <myst/hex.h>
+#include <myst/hostimport.h>
+#include <myst/paths.h>
+#include <myst/printf.h>
+#include <myst/strings.h>
+#include <myst/syscall.h>
+#include <myst/tcall.h>
+
+/*
+**==============================================================================
+**
+** Host directory imports:
+**
+** Before the application starts, each directory listed in the
+** "HostDirectoryImports" section of config.json is read from the host and
+** copied into the root file system. The copy is hashed as it is made (from
+** enclave memory, so the host cannot change what was hashed) and the launch
+** fails unless the hash matches the one in the signed configuration.
+**
+** The files listed in "HostFiles" are copied as well, but not checked: they
+** carry parameters that the host chooses at launch (a region or an instance
+** name, say), as HostEnvironmentVariables does. Only the listed files are
+** read, and each is copied read-only and only up to MYST_HOST_FILE_MAX_SIZE.
+**
+**==============================================================================
+*/
+
+/* the deepest directory tree that is imported */
+#define MAX_DEPTH 64
+
+#define COPY_BUFSIZE (64 * 1024)
+
+typedef struct import
+{
+    myst_sha256_ctx_t ctx;
+    char* buf; /* COPY_BUFSIZE bytes */
+} import_t;
+
+/* the paths of an entry: on the host, in the root file system and in the
+ * hash records */
+typedef struct paths
+{
+    char host[PATH_MAX];
+    char target[PATH_MAX];
+    char rel[PATH_MAX];
+} paths_t;
+
+static long _host_open(const char* path, int flags)
+{
+    long params[6] = {(long)path, flags, 0};
+    return myst_tcall(SYS_open, params);
+}
+
+static void _host_close(int fd)
+{
+    long params[6] = {fd};
+    myst_tcall(SYS_close, params);
+}
+
+static int _host_lstat(const char* path, struct stat* st)
+{
+    long params[6] = {(long)path, (long)st};
+    return (int)myst_tcall(SYS_lstat, params);
+}
+
+static int _host_fstat(int fd, struct stat* st)
+{
+    long params[6] = {fd, (long)st};
+    return (int)myst_tcall(SYS_fstat, params);
+}
+
+static ssize_t _host_read(int fd, void* buf, size_t count)
+{
+    long params[6] = {fd, (long)buf, (long)count};
+    ssize_t n = (ssize_t)myst_tcall(SYS_read, params);
+
+    /* do not trust the host to stay within the buffer */
+    if (n > (ssize_t)count)
+        return -EIO;
+
+    return n;
+}
+
+static void _free_names(char** names, size_t count)
+{
+    for (size_t i = 0; i < count; i++)
+        free(names[i]);
+
+    free(names);
+}
+
+/* read the names in a host directory, checking each host record */
+static int _read_names(
+    import_t* import,
+    const char* path,
+    int flags,
+    char*** names_out,
+    size_t* count_out)
+{
+    int ret = 0;
+    int fd = -1;
+    char** names = NULL;
+    size_t count = 0;
+    size_t capacity = 0;
+    const size_t size = COPY_BUFSIZE;
+    long n;
+
+    ECHECK(fd = _host_open(path, O_RDONLY | O_DIRECTORY | flags));
+
+    for (;;)
+    {
+        long params[6] = {fd, (long)import->buf, (long)size};
+
+        ECHECK(n = myst_tcall(SYS_getdents64, params));
+
+        if (n == 0)
+            break;
+
+        if (n > (long)size)
+            ERAISE(-EIO);
+
+        for (long off = 0; off < n;)
+        {
+            const struct dirent* ent = (void*)(import->buf + off);
+            const size_t min = offsetof(struct dirent, d_name) + 1;
+            size_t reclen;
+            size_t maxlen;
+            const char* name;
+
+            if ((size_t)(n - off) < min)
+                ERAISE(-EIO);
+
+            reclen = ent->d_reclen;
+
+            if (reclen < min || reclen > (size_t)(n - off))
+                ERAISE(-EIO);
+
+            name = ent->d_name;
+            maxlen = reclen - offsetof(struct dirent, d_name);
+
+            if (!memchr(name, '\0', maxlen) || !*name || strchr(name, '/'))
+                ERAISE(-EIO);
+
+            off += (long)reclen;
+
+            if (strcmp(name, ".") == 0 || strcmp(name, "..") == 0)
+                continue;
+
+            if (count == capacity)
+            {
+                size_t m = capacity ? capacity * 2 : 16;
+                char** p;
+
+                if (!(p = realloc(names, m * sizeof(char*))))
+                    ERAISE(-ENOMEM);
+
+                names = p;
+                capacity = m;
+            }
+
+            if (!(names[count] = strdup(name)))
+                ERAISE(-ENOMEM);
+
+            count++;
+        }
+    }
+
+    myst_dirhash_sort(names, count);
+
+    *names_out = names;
+    *count_out = count;
+    names = NULL;
+    count = 0;
+
+done:
+
+    if (fd >= 0)
+        _host_close(fd);
+
+    if (names)
+        _free_names(names, count);
+
+    return ret;
+}
+
+static int _import_file(import_t* import, const paths_t* paths)
+{
+    int ret = 0;
+    int fd = -1;
+    int target_fd = -1;
+    struct stat st;
+    size_t rem;
+
+    ECHECK(fd = _host_open(paths->host, O_RDONLY | O_NOFOLLOW));
+    ECHECK(_host_fstat(fd, &st));
+
+    if (!S_ISREG(st.st_mode) || st.st_size < 0)
+        ERAISE(-EINVAL);
+
+    ECHECK(myst_dirhash_file(
+        &import->ctx, paths->rel, st.st_mode, (size_t)st.st_size));
+
+    ECHECK(
+        target_fd = myst_syscall_open(
+            paths->target,
+            O_WRONLY | O_CREAT | O_TRUNC,
+            st.st_mode & 07777));
+
+    /* copy exactly the size that was hashed */
+    for (rem = (size_t)st.st_size; rem > 0;)
+    {
+        size_t count = rem < COPY_BUFSIZE ? rem : COPY_BUFSIZE;
+        ssize_t n;
+
+        ECHECK(n = _host_read(fd, import->buf, count));
+
+        if (n == 0)
+            ERAISE(-EIO);
+
+        ECHECK(myst_sha256_update(&import->ctx, import->buf, (size_t)n));
+
+        if (myst_writen(target_fd, import->buf, (size_t)n) != 0)
+            ERAISE(-EIO);
+
+        rem -= (size_t)n;
+    }
+
+done:
+
+    if (target_fd >= 0)
+        myst_syscall_close(target_fd);
+
+    if (fd >= 0)
+        _host_close(fd);
+
+    return ret;
+}
+
+static int _import_symlink(import_t* import, const paths_t* paths)
+{
+    int ret = 0;
+    char* target = import->buf;
+    long n;
+
+    long params[6] = {(long)paths->host, (long)target, PATH_MAX - 1};
+    ECHECK(n = myst_tcall(SYS_readlink, params));
+
+    if (n > PATH_MAX - 1)
+        ERAISE(-EIO);
+
+    target[n] = '\0';
+
+    ECHECK(myst_dirhash_symlink(&import->ctx, paths->rel, target));
+    ECHECK(myst_syscall_symlink(target, paths->target));
+
+done:
+    return ret;
+}
+
+/* import the entries of a directory (whose own record was added) */
+static int _import_dir(import_t* import, const paths_t* dir, size_t depth)
+{
+    int ret = 0;
+    char** names = NULL;
+    size_t count = 0;
+    paths_t* paths = NULL;
+    int flags;
+
+    if (depth > MAX_DEPTH)
+        ERAISE(-ELOOP);
+
+    if (!(paths = malloc(sizeof(paths_t))))
+        ERAISE(-ENOMEM);
+
+    /* only the top directory may be a symbolic link */
+    flags = depth ? O_NOFOLLOW : 0;
+    ECHECK(_read_names(import, dir->host, flags, &names, &count));
+
+    for (size_t i = 0; i < count; i++)
+    {
+        const char* name = names[i];
+        struct stat st;
+
+        ECHECK(myst_make_path(paths->host, PATH_MAX, dir->host, name));
+        ECHECK(myst_make_path(paths->target, PATH_MAX, dir->target, name));
+
+        if (*dir->rel)
+            ECHECK(myst_make_path(paths->rel, PATH_MAX, dir->rel, name));
+        else if (myst_strlcpy(paths->rel, name, PATH_MAX) >= PATH_MAX)
+            ERAISE(-ENAMETOOLONG);
+
+        ECHECK(_host_lstat(paths->host, &st));
+
+        if (S_ISDIR(st.st_mode))
+        {
+            long r;
+
+            ECHECK(myst_dirhash_dir(&import->ctx, paths->rel, st.st_mode));
+
+            r = myst_syscall_mkdir(paths->target, st.st_mode & 07777);
+
+            if (r != 0 && r != -EEXIST)
+                ERAISE(r);
+
+            ECHECK(_import_dir(import, paths, depth + 1));
+        }
+        else if (S_ISREG(st.st_mode))
+        {
+            ECHECK(_import_file(import, paths));
+        }
+        else if (S_ISLNK(st.st_mode))
+        {
+            ECHECK(_import_symlink(import, paths));
+        }
+        else
+        {
+            myst_eprintf(
+                "kernel: cannot import %s: not a directory, regular file or "
+                "symbolic link\n",
+                paths->host);
+            ERAISE(-EINVAL);
+        }
+    }
+
+done:
+
+    if (names)
+        _free_names(names, count);
+
+    if (paths)
+        free(paths);
+
+    return ret;
+}
+
+static int _import(import_t* import, const myst_host_import_t* entry)
+{
+    int ret = 0;
+    paths_t* paths = NULL;
+    uint8_t expected[MYST_SHA256_SIZE];
+    myst_sha256_t sha256;
+
+    if (!entry->host_path || !entry->target_path || !entry->sha256 ||
+        entry->target_path[0] != '/')
+    {
+        ERAISE(-EINVAL);
+    }
+
+    if (myst_ascii_to_bin(entry->sha256, expected, sizeof(expected)) !=
+        sizeof(expected))
+    {
+        myst_eprintf(
+            "kernel: bad Sha256 for host directory %s\n", entry->host_path);
+        ERAISE(-EINVAL);
+    }
+
+    if (!(paths = calloc(1, sizeof(paths_t))))
+        ERAISE(-ENOMEM);
+
+    if (myst_strlcpy(paths->host, entry->host_path, PATH_MAX) >= PATH_MAX ||
+        myst_strlcpy(paths->target, entry->target_path, PATH_MAX) >= PATH_MAX)
+    {
+        ERAISE(-ENAMETOOLONG);
+    }
+
+    ECHECK(myst_mkdirhier(paths->target, 0755));
+    ECHECK(myst_sha256_start(&import->ctx));
+    ECHECK(_import_dir(import, paths, 0));
+    ECHECK(myst_sha256_finish(&import->ctx, &sha256));
+
+    if (memcmp(sha256.data, expected, sizeof(expected)) != 0)
+    {
+        myst_eprintf(
+            "kernel: host directory %s does not match its Sha256\n",
+            entry->host_path);
+        ERAISE(-EPERM);
+    }
+
+done:
+
+    if (paths)
+        free(paths);
+
+    return ret;
+}
+
+int myst_import_host_dirs(const myst_host_imports_config_t* config)
+{
+    int ret = 0;
+    import_t import = {0};
+
+    if (!config)
+        ERAISE(-EINVAL);
+
+    if (config->num_imports == 0)
+        goto done;
+
+    if (!(import.buf = malloc(COPY_BUFSIZE)))
+        ERAISE(-ENOMEM);
+
+    for (size_t i = 0; i < config->num_imports; i++)
+    {
+        const myst_host_import_t* entry = &config->imports[i];
+        int r;
+
+        if ((r = _import(&import, entry)) != 0)
+        {
+            myst_eprintf(
+                "kernel: failed to import host directory %s: %s\n",
+                entry->host_path ? entry->host_path : "(null)",
+                myst_error_name(-r));
+            ERAISE(r);
+        }
+    }
+
+done:
+
+    if (import.buf)
+        free(import.buf);
+
+    return ret;
+}
+
+static int _import_host_file(char* buf, const myst_host_file_t* entry)
+{
+    int ret = 0;
+    int fd = -1;
+    int target_fd = -1;
+    struct stat st;
+    size_t rem;
+
+    if (!entry->host_path || !entry->target_path ||
+        entry->target_path[0] != '/')
+    {
+        ERAISE(-EINVAL);
+    }
+
+    if (strlen(entry->target_path) >= PATH_MAX)
+        ERAISE(-ENAMETOOLONG);
+
+    ECHECK(fd = _host_open(entry->host_path, O_RDONLY));
+    ECHECK(_host_fstat(fd, &st));
+
+    if (!S_ISREG(st.st_mode) || st.st_size < 0)
+        ERAISE(-EINVAL);
+
+    if (st.st_size > MYST_HOST_FILE_MAX_SIZE)
+        ERAISE(-EFBIG);
+
+    /* the directory and the name (in buf, before it is used to copy) */
+    ECHECK(myst_split_path(
+        entry->target_path, buf, PATH_MAX, buf + PATH_MAX, PATH_MAX));
+    ECHECK(myst_mkdirhier(buf, 0755));
+
+    ECHECK(
+        target_fd = myst_syscall_open(
+            entry->target_path, O_WRONLY | O_CREAT | O_TRUNC, 0444));
+
+    /* copy no more than the size that was checked */
+    for (rem = (size_t)st.st_size; rem > 0;)
+    {
+        size_t count = rem < COPY_BUFSIZE ? rem : COPY_BUFSIZE;
+        ssize_t n;
+
+        ECHECK(n = _host_read(fd, buf, count));
+
+        if (n == 0)
+            break;
+
+        if (myst_writen(target_fd, buf, (size_t)n) != 0)
+            ERAISE(-EIO);
+
+        rem -= (size_t)n;
+    }
+
+done:
+
+    if (target_fd >= 0)
+        myst_syscall_close(target_fd);
+
+    if (fd >= 0)
+        _host_close(fd);
+
+    return ret;
+}
+
+int myst_import_host_files(const myst_host_imports_config_t* config)
+{
+    int ret = 0;
+    char* buf = NULL;
+
+    if (!config)
+        ERAISE(-EINVAL);
+
+    if (config->num_files == 0)
+        goto done;
+
+    if (!(buf = malloc(COPY_BUFSIZE)))
+        ERAISE(-ENOMEM);
+
+    for (size_t i = 0; i < config->num_files; i++)
+    {
+        const myst_host_file_t* entry = &config->files[i];
+        int r;
+
+        if ((r = _import_host_file(buf, entry)) != 0)
+        {
+            myst_eprintf(
+                "kernel: failed to copy host file %s: %s\n",
+                entry->host_path ? entry->host_path : "(null)",
+                myst_error_name(-r));
+            ERAISE(r);
+        }
+    }
+
+done:
+
+    if (buf)
+        free(buf);
+
+    return ret;
+}
@@ -10,6 +10,7 @@
 #include <myst/eraise.h>
 #include <myst/fdtable.h>
 #include <myst/inotifydev.h>
+#include <myst/ioctl.h>
 #include <myst/list.h>
 #include <myst/paths.h>
 #include <myst/spinlock.h>
@@ -222,15 +223,13 @@ static int _id_ioctl(
 {
     int ret = 0;
 
+    (void)request;
     (void)arg;
 
     if (!dev || !_valid_inotify(obj))
         ERAISE(-EBADF);
 
-    if (request == TIOCGWINSZ)
-        ERAISE(-EINVAL);
-
-    ERAISE(-ENOTSUP);
+    ERAISE(-ENOTTY);
 
 done:
 
@@ -386,6 +385,9 @@ done:
     return ret;
 }
 
+/* no requests: each fails with ENOTTY */
+static const myst_ioctl_table_t _ioctls = MYST_IOCTL_TABLE_NONE("inotify");
+
 myst_inotifydev_t* myst_inotifydev_get(void)
 {
     // clang-format off
@@ -399,6 +401,7 @@ myst_inotifydev_t* myst_inotifydev_get(void)
             .fd_fstat = (void*)_id_fstat,
             .fd_fcntl = (void*)_id_fcntl,
             .fd_ioctl = (void*)_id_ioctl,
+            .fd_ioctls = &_ioctls,
             .fd_dup = (void*)_id_dup,
             .fd_close = (void*)_id_close,
             .fd_target_fd = (void*)_id_target_fd,
@@ -0,0 +1,84 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+
+#include <myst/ioctl.h>
+#include <myst/printf.h>
+#include <myst/spinlock.h>
+#include <myst/trace.h>
+#include <myst/uaccess.h>
+
+/* the unknown requests that were logged (once each per device) */
+#define MAX_LOGGED 64
+
+typedef struct logged
+{
+    const myst_ioctl_table_t* table;
+    unsigned long request;
+} logged_t;
+
+static logged_t _logged[MAX_LOGGED];
+static size_t _num_logged;
+static myst_spinlock_t _lock = MYST_SPINLOCK_INITIALIZER;
+
+/* whether this unknown request is the first of its kind (to be logged) */
+static bool _first(const myst_ioctl_table_t* table, unsigned long request)
+{
+    bool first = false;
+
+    myst_spin_lock(&_lock);
+
+    for (size_t i = 0; i < _num_logged; i++)
+    {
+        if (_logged[i].table == table && _logged[i].request == request)
+            goto done;
+    }
+
+    /* once the array is full, the rest go unlogged */
+    if (_num_logged < MAX_LOGGED)
+    {
+        _logged[_num_logged].table = table;
+        _logged[_num_logged].request = request;
+        _num_logged++;
+        first = true;
+    }
+
+done:
+    myst_spin_unlock(&_lock);
+    return first;
+}
+
+long myst_ioctl_check(
+    const myst_ioctl_table_t* table,
+    unsigned long request,
+    long arg)
+{
+    for (size_t i = 0; i < table->num_ioctls; i++)
+    {
+        const myst_ioctl_t* ioctl = &table->ioctls[i];
+
+        if (ioctl->request != request)
+            continue;
+
+        if (ioctl->dir != MYST_IOCTL_VALUE &&
+            !myst_is_user_range((const void*)arg, ioctl->size))
+        {
+            return -EFAULT;
+        }
+
+        return 0;
+    }
+
+    /* isatty() asks every file for TIOCGWINSZ, so this is only logged with
+     * error tracing (and once) */
+    if (myst_get_trace() && _first(table, request))
+    {
+        myst_eprintf(
+            "kernel: ioctl: %s does not support request 0x%lx (ENOTTY)\n",
+            table->name,
+            request);
+    }
+
+    return -ENOTTY;
+}
@@ -0,0 +1,1254 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <malloc.h>
+#include <poll.h>
+#include <stdbool.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/user.h>
+
+#include <myst/defs.h>
+#include <myst/eraise.h>
+#include <myst/fdtable.h>
+#include <myst/ioctl.h>
+#include <myst/iouringdev.h>
+#include <myst/list.h>
+#include <myst/mutex.h>
+#include <myst/process.h>
+#include <myst/round.h>
+#include <myst/spinlock.h>
+#include <myst/syscall.h>
+
+/*
+**==============================================================================
+**
+** io_uring emulation:
+**
+** The rings live on the kernel heap and mmap() on the io_uring file descriptor
+** returns their address (enclave memory is shared by all processes, so no
+** mapping is needed). Each such mapping holds a reference to the rings, as
+** each file descriptor does, so the rings outlive the descriptors until the
+** process unmaps them or exits. There is no kernel submission thread:
+** io_uring_enter() consumes the submission queue and executes each operation
+** through the ordinary file-descriptor layer. An operation on a socket or a
+** pipe that would block is deferred and retried on later calls;
+** io_uring_enter() with IORING_ENTER_GETEVENTS polls the deferred file
+** descriptors (one batched host call) until enough completions are
+** available.
+**
+** A link (IOSQE_IO_LINK) is deferred as a unit: its operations execute in
+** submission order as each stops blocking, and a failure cancels the rest.
+** A drained operation (IOSQE_IO_DRAIN) is deferred until the operations
+** submitted before it complete, and the ones submitted after it wait for it.
+** Nothing blocks while the ring is locked other than through the poll of
+** io_uring_enter() with IORING_ENTER_GETEVENTS (which releases the lock).
+**
+**==============================================================================
+*/
+
+#define MAGIC 0x5c2e9a41
+
+/* the ring header (the SQ index array and the CQEs follow it) */
+typedef struct rings
+{
+    uint32_t sq_head;
+    uint32_t sq_tail;
+    uint32_t sq_ring_mask;
+    uint32_t sq_ring_entries;
+    uint32_t sq_flags;
+    uint32_t sq_dropped;
+    uint32_t cq_head;
+    uint32_t cq_tail;
+    uint32_t cq_ring_mask;
+    uint32_t cq_ring_entries;
+    uint32_t cq_overflow;
+    uint32_t cq_flags;
+} rings_t;
+
+/* a deferred operation (the next one of a deferred link) */
+typedef struct request
+{
+    struct request* prev;
+    struct request* next;
+
+    /* the operations linked after this one */
+    struct request* link;
+
+    /* this operation (and its link) had IOSQE_IO_DRAIN */
+    bool drain;
+
+    myst_io_uring_sqe_t sqe;
+} request_t;
+
+typedef struct iouring_impl
+{
+    size_t refs;
+    myst_mutex_t mutex;
+
+    /* the region mapped at IORING_OFF_SQ_RING and IORING_OFF_CQ_RING */
+    rings_t* rings;
+    size_t rings_size;
+    uint32_t* sq_array;
+    myst_io_uring_cqe_t* cqes;
+
+    /* the region mapped at IORING_OFF_SQES */
+    myst_io_uring_sqe_t* sqes;
+    size_t sqes_size;
+
+    uint32_t sq_entries;
+    uint32_t cq_entries;
+
+    /* deferred operations (and links) in submission order */
+    myst_list_t pending;
+
+    /* the number of deferred operations, counting the linked ones */
+    size_t npending;
+
+    /* the number of deferred operations with IOSQE_IO_DRAIN */
+    size_t ndrains;
+
+    /* the last operation of the link being submitted (the previous SQE had
+     * IOSQE_IO_LINK or IOSQE_IO_HARDLINK) */
+    request_t* link_tail;
+} iouring_impl_t;
+
+struct myst_iouring
+{
+    uint32_t magic;
+    int fdflags;
+    iouring_impl_t* impl;
+};
+
+MYST_INLINE bool _valid_iouring(const myst_iouring_t* obj)
+{
+    return obj && obj->magic == MAGIC && obj->impl;
+}
+
+/* a region of the rings that a process mapped with mmap() */
+typedef struct mapping
+{
+    struct mapping* next;
+    pid_t pid;
+    void* addr;
+    iouring_impl_t* impl;
+} mapping_t;
+
+static mapping_t* _mappings;
+static myst_spinlock_t _mappings_lock = MYST_SPINLOCK_INITIALIZER;
+
+static uint32_t _round_up_pow2(uint32_t x)
+{
+    uint32_t r = 1;
+
+    while (r < x)
+        r <<= 1;
+
+    return r;
+}
+
+/* free a deferred operation and the ones linked after it */
+static void _free_request(request_t* r)
+{
+    while (r)
+    {
+        request_t* link = r->link;
+        free(r);
+        r = link;
+    }
+}
+
+static void _free_impl(iouring_impl_t* p)
+{
+    for (request_t* r = (request_t*)p->pending.head; r;)
+    {
+        request_t* next = r->next;
+        _free_request(r);
+        r = next;
+    }
+
+    if (p->rings)
+        free(p->rings);
+
+    if (p->sqes)
+        free(p->sqes);
+
+    memset(p, 0, sizeof(iouring_impl_t));
+    free(p);
+}
+
+/* drop a reference of a file descriptor or a mapping */
+static void _release_impl(iouring_impl_t* p)
+{
+    if (__atomic_sub_fetch(&p->refs, 1, __ATOMIC_SEQ_CST) == 0)
+        _free_impl(p);
+}
+
+/*
+**==============================================================================
+**
+** completion queue
+**
+**==============================================================================
+*/
+
+/* the number of CQEs the application has not consumed yet */
+static uint32_t _cq_ready(iouring_impl_t* p)
+{
+    const uint32_t head = __atomic_load_n(&p->rings->cq_head, __ATOMIC_ACQUIRE);
+    return p->rings->cq_tail - head;
+}
+
+/* the number of CQEs that can be posted without overflowing */
+static uint32_t _cq_space(iouring_impl_t* p)
+{
+    const uint32_t used = _cq_ready(p) + (uint32_t)p->npending;
+    return used >= p->cq_entries ? 0 : p->cq_entries - used;
+}
+
+static void _post(iouring_impl_t* p, uint64_t user_data, long res)
+{
+    const uint32_t tail = p->rings->cq_tail;
+    myst_io_uring_cqe_t* cqe;
+
+    if (_cq_ready(p) >= p->cq_entries)
+    {
+        p->rings->cq_overflow++;
+        return;
+    }
+
+    cqe = &p->cqes[tail & p->rings->cq_ring_mask];
+    cqe->user_data = user_data;
+    cqe->res = (int32_t)res;
+    cqe->flags = 0;
+
+    /* publish the CQE before the new tail */
+    __atomic_store_n(&p->rings->cq_tail, tail + 1, __ATOMIC_RELEASE);
+}
+
+/*
+**==============================================================================
+**
+** operations
+**
+**==============================================================================
+*/
+
+static long _preadv(int fd, const struct iovec* iov, int iovcnt, off_t off)
+{
+    long total = 0;
+
+    for (int i = 0; i < iovcnt; i++)
+    {
+        long r = myst_syscall_pread(fd, iov[i].iov_base, iov[i].iov_len, off);
+
+        if (r < 0)
+            return total ? total : r;
+
+        total += r;
+        off += r;
+
+        if ((size_t)r < iov[i].iov_len)
+            break;
+    }
+
+    return total;
+}
+
+static long _pwritev(int fd, const struct iovec* iov, int iovcnt, off_t off)
+{
+    long total = 0;
+
+    for (int i = 0; i < iovcnt; i++)
+    {
+        long r = myst_syscall_pwrite(fd, iov[i].iov_base, iov[i].iov_len, off);
+
+        if (r < 0)
+            return total ? total : r;
+
+        total += r;
+        off += r;
+
+        if ((size_t)r < iov[i].iov_len)
+            break;
+    }
+
+    return total;
+}
+
+static long _execute(const myst_io_uring_sqe_t* sqe)
+{
+    const int fd = sqe->fd;
+    void* addr = (void*)sqe->addr;
+    const size_t len = sqe->len;
+    const int iovcnt = (int)sqe->len;
+
+    /* there are no registered files (IORING_REGISTER_FILES) */
+    if ((sqe->flags & MYST_IOSQE_FIXED_FILE))
+        return -EBADF;
+
+    /* an offset of -1 means the current file position (RW_CUR_POS) */
+    const bool cur_pos = (sqe->off == (uint64_t)-1);
+    const off_t off = (off_t)sqe->off;
+
+    switch (sqe->opcode)
+    {
+        case MYST_IORING_OP_NOP:
+            return 0;
+        case MYST_IORING_OP_READ:
+        {
+            if (cur_pos)
+                return myst_syscall_read(fd, addr, len);
+
+            return myst_syscall_pread(fd, addr, len, off);
+        }
+        case MYST_IORING_OP_WRITE:
+        {
+            if (cur_pos)
+                return myst_syscall_write(fd, addr, len);
+
+            return myst_syscall_pwrite(fd, addr, len, off);
+        }
+        case MYST_IORING_OP_READV:
+        {
+            if (cur_pos)
+                return myst_syscall_readv(fd, addr, iovcnt);
+
+            return _preadv(fd, addr, iovcnt, off);
+        }
+        case MYST_IORING_OP_WRITEV:
+        {
+            if (cur_pos)
+                return myst_syscall_writev(fd, addr, iovcnt);
+
+            return _pwritev(fd, addr, iovcnt, off);
+        }
+        case MYST_IORING_OP_FSYNC:
+        {
+            if ((sqe->fsync_flags & ~MYST_IORING_FSYNC_DATASYNC))
+                return -EINVAL;
+
+            /* fdatasync() and fsync() are the same here */
+            return myst_syscall_fsync(fd);
+        }
+        case MYST_IORING_OP_ACCEPT:
+        {
+            socklen_t* addrlen = (socklen_t*)sqe->addr2;
+            const int flags = (int)sqe->accept_flags;
+            return myst_syscall_accept4(fd, addr, addrlen, flags);
+        }
+        default:
+            return -EINVAL;
+    }
+}
+
+static short _poll_events(const myst_io_uring_sqe_t* sqe)
+{
+    switch (sqe->opcode)
+    {
+        case MYST_IORING_OP_READ:
+        case MYST_IORING_OP_READV:
+        case MYST_IORING_OP_ACCEPT:
+            return POLLIN;
+        case MYST_IORING_OP_WRITE:
+        case MYST_IORING_OP_WRITEV:
+            return POLLOUT;
+        default:
+            return 0;
+    }
+}
+
+/* whether the operation can be executed without blocking */
+static bool _ready(const myst_io_uring_sqe_t* sqe)
+{
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    const short events = _poll_events(sqe);
+    myst_fdtable_type_t type;
+    void* device;
+    void* object;
+    struct pollfd pfd;
+
+    if (!events || (sqe->flags & MYST_IOSQE_FIXED_FILE))
+        return true;
+
+    /* bad file descriptors fail right away */
+    if (myst_fdtable_get_any(fdtable, sqe->fd, &type, &device, &object) != 0)
+        return true;
+
+    /* only sockets and pipes block (regular files are always ready) */
+    if (type != MYST_FDTABLE_TYPE_SOCK && type != MYST_FDTABLE_TYPE_PIPE)
+        return true;
+
+    pfd.fd = sqe->fd;
+    pfd.events = events;
+    pfd.revents = 0;
+
+    return myst_syscall_poll(&pfd, 1, 0) != 0;
+}
+
+/* execute the first operation of a deferred link and return the request,
+ * which holds the next operation, or NULL once the link is complete */
+static request_t* _complete(iouring_impl_t* p, request_t* r)
+{
+    const long res = _execute(&r->sqe);
+    request_t* link;
+
+    _post(p, r->sqe.user_data, res);
+    p->npending--;
+
+    /* a failure cancels the rest of an IOSQE_IO_LINK chain */
+    if (res < 0 && (r->sqe.flags & MYST_IOSQE_IO_LINK))
+    {
+        while ((link = r->link))
+        {
+            r->link = link->link;
+            _post(p, link->sqe.user_data, -ECANCELED);
+            p->npending--;
+            free(link);
+        }
+    }
+
+    /* the next operation takes this one's place in the queue */
+    if ((link = r->link))
+    {
+        r->sqe = link->sqe;
+        r->link = link->link;
+        free(link);
+        return r;
+    }
+
+    myst_list_remove(&p->pending, (myst_list_node_t*)r);
+    p->ndrains -= r->drain;
+    free(r);
+
+    return NULL;
+}
+
+/* execute the deferred operations that no longer block */
+static void _complete_pending(iouring_impl_t* p)
+{
+    for (request_t* r = (request_t*)p->pending.head; r;)
+    {
+        request_t* next = r->next;
+
+        /* a drained operation waits for the ones before it */
+        if (r->drain && r != (request_t*)p->pending.head)
+            break;
+
+        /* run the link until an operation would block */
+        while (r && _ready(&r->sqe))
+            r = _complete(p, r);
+
+        /* and the ones after it wait for it */
+        if (r && r->drain)
+            break;
+
+        r = next;
+    }
+}
+
+/* wait (with the mutex released) until a deferred operation may proceed */
+static long _wait_pending(iouring_impl_t* p)
+{
+    long ret = 0;
+    struct pollfd* fds;
+    nfds_t nfds = 0;
+
+    if (!(fds = calloc(p->pending.size, sizeof(struct pollfd))))
+        ERAISE(-ENOMEM);
+
+    /* the operations that may start (see _complete_pending) */
+    for (request_t* r = (request_t*)p->pending.head; r; r = r->next)
+    {
+        if (r->drain && r != (request_t*)p->pending.head)
+            break;
+
+        fds[nfds].fd = r->sqe.fd;
+        fds[nfds].events = _poll_events(&r->sqe);
+        nfds++;
+
+        if (r->drain)
+            break;
+    }
+
+    myst_mutex_unlock(&p->mutex);
+    ret = myst_syscall_poll(fds, nfds, -1);
+    myst_mutex_lock(&p->mutex);
+
+    free(fds);
+
+done:
+    return ret;
+}
+
+static int _submit_one(iouring_impl_t* p, const myst_io_uring_sqe_t* sqe)
+{
+    int ret = 0;
+    const uint8_t link = MYST_IOSQE_IO_LINK | MYST_IOSQE_IO_HARDLINK;
+    const bool drain = (sqe->flags & MYST_IOSQE_IO_DRAIN);
+    request_t* r;
+
+    /* an operation that nothing holds back and that does not block */
+    if (!p->link_tail && !(sqe->flags & link) && !p->ndrains &&
+        !(drain && p->npending) && _ready(sqe))
+    {
+        _post(p, sqe->user_data, _execute(sqe));
+        goto done;
+    }
+
+    if (!(r = calloc(1, sizeof(request_t))))
+        ERAISE(-ENOMEM);
+
+    r->sqe = *sqe;
+    p->npending++;
+
+    if (p->link_tail)
+    {
+        /* the next operation of the link being submitted */
+        p->link_tail->link = r;
+    }
+    else
+    {
+        r->drain = drain;
+        p->ndrains += drain;
+        myst_list_append(&p->pending, (myst_list_node_t*)r);
+    }
+
+    p->link_tail = (sqe->flags & link) ? r : NULL;
+
+done:
+    return ret;
+}
+
+static long _submit(iouring_impl_t* p, uint32_t to_submit)
+{
+    long ret = 0;
+    rings_t* rings = p->rings;
+    uint32_t head = rings->sq_head; /* only the kernel updates the head */
+    const uint32_t tail = __atomic_load_n(&rings->sq_tail, __ATOMIC_ACQUIRE);
+    uint32_t n = 0;
+
+    while (n < to_submit && head != tail)
+    {
+        const uint32_t index = p->sq_array[head & rings->sq_ring_mask];
+        myst_io_uring_sqe_t sqe;
+        int r;
+
+        if (index >= p->sq_entries)
+        {
+            rings->sq_dropped++;
+            head++;
+            continue;
+        }
+
+        /* every submitted operation needs room for its completion */
+        if (_cq_space(p) == 0)
+            break;
+
+        /* copy the SQE so the application may reuse it (SUBMIT_STABLE) */
+        sqe = p->sqes[index];
+        head++;
+
+        if ((r = _submit_one(p, &sqe)) < 0)
+        {
+            if (n == 0)
+                ret = r;
+            break;
+        }
+
+        n++;
+    }
+
+    __atomic_store_n(&rings->sq_head, head, __ATOMIC_RELEASE);
+
+    /* a link ends with the submission, then it runs as far as it can */
+    p->link_tail = NULL;
+    _complete_pending(p);
+
+    if (ret == 0)
+    {
+        /* the completion queue is full */
+        if (n == 0 && head != tail && to_submit)
+            ERAISE(-EBUSY);
+
+        ret = n;
+    }
+
+done:
+    return ret;
+}
+
+/*
+**==============================================================================
+**
+** io_uring device
+**
+**==============================================================================
+*/
+
+static int _iu_setup(
+    myst_iouringdev_t* dev,
+    uint32_t entries,
+    myst_io_uring_params_t* params,
+    myst_iouring_t** obj_out)
+{
+    int ret = 0;
+    const uint32_t supported = MYST_IORING_SETUP_CQSIZE | MYST_IORING_SETUP_CLAMP;
+    myst_iouring_t* obj = NULL;
+    iouring_impl_t* p = NULL;
+    uint32_t sq_entries;
+    uint32_t cq_entries;
+    uint64_t array_off;
+    uint64_t cqes_off;
+    uint64_t rings_size;
+    uint64_t sqes_size;
+
+    if (obj_out)
+        *obj_out = NULL;
+
+    if (!dev || !params || !obj_out)
+        ERAISE(-EINVAL);
+
+    /* no kernel polling threads (SQPOLL, IOPOLL) */
+    if ((params->flags & ~supported))
+        ERAISE(-EINVAL);
+
+    for (size_t i = 0; i < MYST_COUNTOF(params->resv); i++)
+    {
+        if (params->resv[i])
+            ERAISE(-EINVAL);
+    }
+
+    if (entries == 0)
+        ERAISE(-EINVAL);
+
+    if (entries > MYST_IORING_MAX_ENTRIES)
+    {
+        if (!(params->flags & MYST_IORING_SETUP_CLAMP))
+            ERAISE(-EINVAL);
+
+        entries = MYST_IORING_MAX_ENTRIES;
+    }
+
+    sq_entries = _round_up_pow2(entries);
+
+    if ((params->flags & MYST_IORING_SETUP_CQSIZE))
+    {
+        cq_entries = params->cq_entries;
+
+        if (cq_entries == 0)
+            ERAISE(-EINVAL);
+
+        if (cq_entries > 2 * MYST_IORING_MAX_ENTRIES)
+        {
+            if (!(params->flags & MYST_IORING_SETUP_CLAMP))
+                ERAISE(-EINVAL);
+
+            cq_entries = 2 * MYST_IORING_MAX_ENTRIES;
+        }
+
+        cq_entries = _round_up_pow2(cq_entries);
+
+        if (cq_entries < sq_entries)
+            ERAISE(-EINVAL);
+    }
+    else
+    {
+        cq_entries = 2 * sq_entries;
+    }
+
+    /* lay out the rings: header, SQ index array, CQEs */
+    ECHECK(myst_round_up(sizeof(rings_t), 64, &array_off));
+    ECHECK(myst_round_up(array_off + sq_entries * sizeof(uint32_t), 64, &cqes_off));
+    rings_size = cqes_off + cq_entries * sizeof(myst_io_uring_cqe_t);
+    ECHECK(myst_round_up(rings_size, PAGE_SIZE, &rings_size));
+    sqes_size = sq_entries * sizeof(myst_io_uring_sqe_t);
+    ECHECK(myst_round_up(sqes_size, PAGE_SIZE, &sqes_size));
+
+    if (!(p = calloc(1, sizeof(iouring_impl_t))))
+        ERAISE(-ENOMEM);
+
+    if (!(p->rings = memalign(PAGE_SIZE, rings_size)))
+        ERAISE(-ENOMEM);
+
+    if (!(p->sqes = memalign(PAGE_SIZE, sqes_size)))
+        ERAISE(-ENOMEM);
+
+    memset(p->rings, 0, rings_size);
+    memset(p->sqes, 0, sqes_size);
+
+    p->refs = 1;
+    p->rings_size = rings_size;
+    p->sqes_size = sqes_size;
+    p->sq_array = (uint32_t*)((uint8_t*)p->rings + array_off);
+    p->cqes = (myst_io_uring_cqe_t*)((uint8_t*)p->rings + cqes_off);
+    p->sq_entries = sq_entries;
+    p->cq_entries = cq_entries;
+    p->rings->sq_ring_mask = sq_entries - 1;
+    p->rings->sq_ring_entries = sq_entries;
+    p->rings->cq_ring_mask = cq_entries - 1;
+    p->rings->cq_ring_entries = cq_entries;
+
+    if (!(obj = calloc(1, sizeof(myst_iouring_t))))
+        ERAISE(-ENOMEM);
+
+    obj->magic = MAGIC;
+    obj->fdflags = FD_CLOEXEC; /* io_uring descriptors are always O_CLOEXEC */
+    obj->impl = p;
+    p = NULL;
+
+    /* tell the application where everything is */
+    params->sq_entries = sq_entries;
+    params->cq_entries = cq_entries;
+    params->features = MYST_IORING_FEAT_SINGLE_MMAP |
+                       MYST_IORING_FEAT_SUBMIT_STABLE |
+                       MYST_IORING_FEAT_RW_CUR_POS;
+
+    memset(&params->sq_off, 0, sizeof(params->sq_off));
+    params->sq_off.head = offsetof(rings_t, sq_head);
+    params->sq_off.tail = offsetof(rings_t, sq_tail);
+    params->sq_off.ring_mask = offsetof(rings_t, sq_ring_mask);
+    params->sq_off.ring_entries = offsetof(rings_t, sq_ring_entries);
+    params->sq_off.flags = offsetof(rings_t, sq_flags);
+    params->sq_off.dropped = offsetof(rings_t, sq_dropped);
+    params->sq_off.array = (uint32_t)array_off;
+
+    memset(&params->cq_off, 0, sizeof(params->cq_off));
+    params->cq_off.head = offsetof(rings_t, cq_head);
+    params->cq_off.tail = offsetof(rings_t, cq_tail);
+    params->cq_off.ring_mask = offsetof(rings_t, cq_ring_mask);
+    params->cq_off.ring_entries = offsetof(rings_t, cq_ring_entries);
+    params->cq_off.overflow = offsetof(rings_t, cq_overflow);
+    params->cq_off.cqes = (uint32_t)cqes_off;
+    params->cq_off.flags = offsetof(rings_t, cq_flags);
+
+    *obj_out = obj;
+    obj = NULL;
+
+done:
+
+    if (p)
+        _free_impl(p);
+
+    if (obj)
+        free(obj);
+
+    return ret;
+}
+
+static long _iu_enter(
+    myst_iouringdev_t* dev,
+    myst_iouring_t* obj,
+    uint32_t to_submit,
+    uint32_t min_complete,
+    uint32_t flags)
+{
+    long ret = 0;
+    const uint32_t supported =
+        MYST_IORING_ENTER_GETEVENTS | MYST_IORING_ENTER_SQ_WAKEUP;
+    iouring_impl_t* p;
+    bool locked = false;
+    long submitted = 0;
+
+    if (!dev || !_valid_iouring(obj))
+        ERAISE(-EBADF);
+
+    if ((flags & ~supported))
+        ERAISE(-EINVAL);
+
+    p = obj->impl;
+    myst_mutex_lock(&p->mutex);
+    locked = true;
+
+    /* retire deferred operations that have become ready */
+    _complete_pending(p);
+
+    if (to_submit)
+        ECHECK(submitted = _submit(p, to_submit));
+
+    if ((flags & MYST_IORING_ENTER_GETEVENTS))
+    {
+        if (min_complete > p->cq_entries)
+            min_complete = p->cq_entries;
+
+        /* completions only come from deferred operations */
+        while (_cq_ready(p) < min_complete && p->pending.size)
+        {
+            long r;
+
+            if ((r = _wait_pending(p)) < 0)
+            {
+                /* report the submissions rather than the interruption */
+                if (submitted)
+                    break;
+
+                ERAISE(r);
+            }
+
+            _complete_pending(p);
+        }
+    }
+
+    ret = submitted;
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&p->mutex);
+
+    return ret;
+}
+
+static long _iu_register(
+    myst_iouringdev_t* dev,
+    myst_iouring_t* obj,
+    uint32_t opcode,
+    void* arg,
+    uint32_t nr_args)
+{
+    long ret = 0;
+
+    if (!dev || !_valid_iouring(obj))
+        ERAISE(-EBADF);
+
+    switch (opcode)
+    {
+        case MYST_IORING_REGISTER_PROBE:
+        {
+            myst_io_uring_probe_t* probe = arg;
+            const uint8_t supported[] = {
+                MYST_IORING_OP_NOP,
+                MYST_IORING_OP_READV,
+                MYST_IORING_OP_WRITEV,
+                MYST_IORING_OP_FSYNC,
+                MYST_IORING_OP_ACCEPT,
+                MYST_IORING_OP_READ,
+                MYST_IORING_OP_WRITE,
+            };
+            size_t size;
+
+            if (!probe)
+                ERAISE(-EFAULT);
+
+            if (nr_args > 256)
+                nr_args = 256;
+
+            size = sizeof(*probe) + nr_args * sizeof(probe->ops[0]);
+
+            /* the caller must pass a zero-filled probe */
+            for (size_t i = 0; i < size; i++)
+            {
+                if (((const uint8_t*)probe)[i])
+                    ERAISE(-EINVAL);
+            }
+
+            probe->last_op = MYST_IORING_OP_LAST - 1;
+            probe->ops_len = (uint8_t)(nr_args < MYST_IORING_OP_LAST
+                                           ? nr_args
+                                           : MYST_IORING_OP_LAST);
+
+            for (size_t i = 0; i < probe->ops_len; i++)
+                probe->ops[i].op = (uint8_t)i;
+
+            for (size_t i = 0; i < MYST_COUNTOF(supported); i++)
+            {
+                if (supported[i] < probe->ops_len)
+                    probe->ops[supported[i]].flags = MYST_IO_URING_OP_SUPPORTED;
+            }
+
+            break;
+        }
+        default:
+        {
+            ERAISE(-EINVAL);
+        }
+    }
+
+done:
+    return ret;
+}
+
+static int _iu_mmap(
+    myst_iouringdev_t* dev,
+    myst_iouring_t* obj,
+    off_t offset,
+    size_t length,
+    void** addr)
+{
+    int ret = 0;
+    iouring_impl_t* p;
+
+    if (addr)
+        *addr = NULL;
+
+    if (!dev || !_valid_iouring(obj) || !addr)
+        ERAISE(-EINVAL);
+
+    p = obj->impl;
+
+    switch ((uint64_t)offset)
+    {
+        case MYST_IORING_OFF_SQ_RING:
+        case MYST_IORING_OFF_CQ_RING:
+        {
+            /* the SQ and CQ rings share one region (SINGLE_MMAP) */
+            if (length > p->rings_size)
+                ERAISE(-EINVAL);
+
+            *addr = p->rings;
+            break;
+        }
+        case MYST_IORING_OFF_SQES:
+        {
+            if (length > p->sqes_size)
+                ERAISE(-EINVAL);
+
+            *addr = p->sqes;
+            break;
+        }
+        default:
+        {
+            ERAISE(-EINVAL);
+        }
+    }
+
+    /* the mapping keeps the rings until it is unmapped */
+    {
+        mapping_t* m;
+
+        if (!(m = calloc(1, sizeof(mapping_t))))
+        {
+            *addr = NULL;
+            ERAISE(-ENOMEM);
+        }
+
+        m->pid = myst_getpid();
+        m->addr = *addr;
+        m->impl = p;
+        __atomic_fetch_add(&p->refs, 1, __ATOMIC_SEQ_CST);
+
+        myst_spin_lock(&_mappings_lock);
+        m->next = _mappings;
+        _mappings = m;
+        myst_spin_unlock(&_mappings_lock);
+    }
+
+done:
+    return ret;
+}
+
+/* remove the mappings of the process that match (all if addr is null) */
+static bool _unmap(pid_t pid, const void* addr, size_t length)
+{
+    mapping_t* dead = NULL;
+    const uint8_t* start = addr;
+    const uint8_t* end = start + length;
+
+    myst_spin_lock(&_mappings_lock);
+    for (mapping_t** p = &_mappings; *p;)
+    {
+        mapping_t* m = *p;
+        const uint8_t* a = m->addr;
+
+        if (m->pid == pid && (!addr || (a >= start && a < end)))
+        {
+            *p = m->next;
+            m->next = dead;
+            dead = m;
+        }
+        else
+        {
+            p = &m->next;
+        }
+    }
+    myst_spin_unlock(&_mappings_lock);
+
+    if (!dead)
+        return false;
+
+    while (dead)
+    {
+        mapping_t* next = dead->next;
+        _release_impl(dead->impl);
+        free(dead);
+        dead = next;
+    }
+
+    return true;
+}
+
+bool myst_iouring_munmap(void* addr, size_t length)
+{
+    return addr && length && _unmap(myst_getpid(), addr, length);
+}
+
+void myst_iouring_release_mappings(pid_t pid)
+{
+    _unmap(pid, NULL, 0);
+}
+
+static ssize_t _iu_read(
+    myst_iouringdev_t* dev,
+    myst_iouring_t* obj,
+    void* buf,
+    size_t count)
+{
+    (void)dev;
+    (void)obj;
+    (void)buf;
+    (void)count;
+    return -EINVAL;
+}
+
+static ssize_t _iu_write(
+    myst_iouringdev_t* dev,
+    myst_iouring_t* obj,
+    const void* buf,
+    size_t count)
+{
+    (void)dev;
+    (void)obj;
+    (void)buf;
+    (void)count;
+    return -EINVAL;
+}
+
+static ssize_t _iu_readv(
+    myst_iouringdev_t* dev,
+    myst_iouring_t* obj,
+    const struct iovec* iov,
+    int iovcnt)
+{
+    (void)dev;
+    (void)obj;
+    (void)iov;
+    (void)iovcnt;
+    return -EINVAL;
+}
+
+static ssize_t _iu_writev(
+    myst_iouringdev_t* dev,
+    myst_iouring_t* obj,
+    const struct iovec* iov,
+    int iovcnt)
+{
+    (void)dev;
+    (void)obj;
+    (void)iov;
+    (void)iovcnt;
+    return -EINVAL;
+}
+
+static int _iu_fstat(
+    myst_iouringdev_t* dev,
+    myst_iouring_t* obj,
+    struct stat* statbuf)
+{
+    int ret = 0;
+
+    if (!dev || !_valid_iouring(obj) || !statbuf)
+        ERAISE(-EINVAL);
+
+    /* an anonymous inode */
+    memset(statbuf, 0, sizeof(struct stat));
+    statbuf->st_mode = S_IRUSR | S_IWUSR;
+    statbuf->st_nlink = 1;
+    statbuf->st_blksize = PAGE_SIZE;
+
+done:
+    return ret;
+}
+
+static int _iu_fcntl(
+    myst_iouringdev_t* dev,
+    myst_iouring_t* obj,
+    int cmd,
+    long arg)
+{
+    int ret = 0;
+
+    if (!dev || !_valid_iouring(obj))
+        ERAISE(-EBADF);
+
+    switch (cmd)
+    {
+        case F_SETFD:
+        {
+            if (arg != FD_CLOEXEC && arg != 0)
+                ERAISE(-EINVAL);
+
+            obj->fdflags = (int)arg;
+            break;
+        }
+        case F_GETFD:
+        {
+            ret = obj->fdflags;
+            break;
+        }
+        case F_GETFL:
+        {
+            ret = O_RDWR;
+            break;
+        }
+        case F_SETFL:
+        {
+            /* io_uring_enter() never blocks on the descriptor itself */
+            break;
+        }
+        default:
+        {
+            ERAISE(-EINVAL);
+        }
+    }
+
+done:
+    return ret;
+}
+
+static int _iu_ioctl(
+    myst_iouringdev_t* dev,
+    myst_iouring_t* obj,
+    unsigned long request,
+    long arg)
+{
+    int ret = 0;
+
+    (void)request;
+    (void)arg;
+
+    if (!dev || !_valid_iouring(obj))
+        ERAISE(-EBADF);
+
+    ERAISE(-ENOTTY);
+
+done:
+    return ret;
+}
+
+static int _iu_dup(
+    myst_iouringdev_t* dev,
+    const myst_iouring_t* obj,
+    myst_iouring_t** obj_out)
+{
+    int ret = 0;
+    myst_iouring_t* new_obj = NULL;
+
+    if (obj_out)
+        *obj_out = NULL;
+
+    if (!dev || !_valid_iouring(obj) || !obj_out)
+        ERAISE(-EINVAL);
+
+    if (!(new_obj = calloc(1, sizeof(myst_iouring_t))))
+        ERAISE(-ENOMEM);
+
+    /* the new descriptor shares the rings */
+    *new_obj = *obj;
+    __atomic_fetch_add(&obj->impl->refs, 1, __ATOMIC_SEQ_CST);
+
+    /* file descriptor flags are not propagated */
+    new_obj->fdflags = 0;
+
+    *obj_out = new_obj;
+    new_obj = NULL;
+
+done:
+
+    if (new_obj)
+        free(new_obj);
+
+    return ret;
+}
+
+static int _iu_close(myst_iouringdev_t* dev, myst_iouring_t* obj)
+{
+    int ret = 0;
+
+    if (!dev || !_valid_iouring(obj))
+        ERAISE(-EBADF);
+
+    /* the rings stay while the application maps them */
+    _release_impl(obj->impl);
+
+    memset(obj, 0, sizeof(myst_iouring_t));
+    free(obj);
+
+done:
+    return ret;
+}
+
+static int _iu_target_fd(myst_iouringdev_t* dev, myst_iouring_t* obj)
+{
+    int ret = 0;
+
+    if (!dev || !_valid_iouring(obj))
+        ERAISE(-EINVAL);
+
+    ret = -ENOTSUP;
+
+done:
+    return ret;
+}
+
+static int _iu_get_events(myst_iouringdev_t* dev, myst_iouring_t* obj)
+{
+    int ret = 0;
+
+    if (!dev || !_valid_iouring(obj))
+        ERAISE(-EINVAL);
+
+    /* readable when completions are waiting */
+    ret = POLLOUT;
+
+    if (_cq_ready(obj->impl))
+        ret |= POLLIN;
+
+done:
+    return ret;
+}
+
+/* no requests: each fails with ENOTTY */
+static const myst_ioctl_table_t _ioctls = MYST_IOCTL_TABLE_NONE("io_uring");
+
+myst_iouringdev_t* myst_iouringdev_get(void)
+{
+    // clang-format off
+    static myst_iouringdev_t _iouringdev =
+    {
+        {
+            .fd_read = (void*)_iu_read,
+            .fd_write = (void*)_iu_write,
+            .fd_readv = (void*)_iu_readv,
+            .fd_writev = (void*)_iu_writev,
+            .fd_fstat = (void*)_iu_fstat,
+            .fd_fcntl = (void*)_iu_fcntl,
+            .fd_ioctl = (void*)_iu_ioctl,
+            .fd_ioctls = &_ioctls,
+            .fd_dup = (void*)_iu_dup,
+            .fd_close = (void*)_iu_close,
+            .fd_target_fd = (void*)_iu_target_fd,
+            .fd_get_events = (void*)_iu_get_events,
+        },
+        .iu_setup = _iu_setup,
+        .iu_enter = _iu_enter,
+        .iu_register = _iu_register,
+        .iu_mmap = _iu_mmap,
+        .iu_read = _iu_read,
+        .iu_write = _iu_write,
+        .iu_readv = _iu_readv,
+        .iu_writev = _iu_writev,
+        .iu_fstat = _iu_fstat,
+        .iu_fcntl = _iu_fcntl,
+        .iu_ioctl = _iu_ioctl,
+        .iu_dup = _iu_dup,
+        .iu_close = _iu_close,
+        .iu_target_fd = _iu_target_fd,
+        .iu_get_events = _iu_get_events,
+    };
+    // clang-format on
+
+    return &_iouringdev;
+}
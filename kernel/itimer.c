@@ -7,117 +7,87 @@
 #include <stdio.h>
 #include <string.h>
 
-#include <myst/clock.h>
-#include <myst/cond.h>
+#include <myst/deadline.h>
 #include <myst/eraise.h>
 #include <myst/mutex.h>
 #include <myst/process.h>
 #include <myst/syscall.h>
-#include <myst/time.h>
+#include <myst/timerwheel.h>
 #include <myst/timeval.h>
 
 /* ATTN: currently the itimer is only for the single process case */
 typedef struct itimer
 {
-    myst_cond_t cond;
-    uint64_t real_interval; /* ITIMER_REAL interval */
-    uint64_t real_value;    /* ITIMER_REAL value */
+    myst_timer_t timer;  /* ITIMER_REAL */
+    long interval;       /* ITIMER_REAL interval (nanoseconds) */
+    pid_t pid;           /* the process that set the timer */
     myst_mutex_t mutex;
-    _Atomic(int) initialized;
 } itimer_t;
 
 static itimer_t _it;
 
-/* get time as microseconds since epoch: return 0 on failure */
-static uint64_t _get_current_time(void)
+/* convert microseconds to nanoseconds, saturating */
+static long _usec_to_nsec(uint64_t usec)
 {
-    uint64_t ret;
-    struct timespec ts;
-    struct timeval tv;
+    if (usec > LONG_MAX / 1000)
+        return LONG_MAX;
 
-    if (myst_syscall_clock_gettime(CLOCK_REALTIME, &ts) != 0)
-        return 0;
-
-    /* downgrade from nanosecond to microsecond granularity */
-    tv.tv_sec = ts.tv_sec;
-    tv.tv_usec = ts.tv_nsec / 1000;
-
-    /* convert timeval to uint64 */
-    if (myst_timeval_to_uint64(&tv, &ret) != 0)
-        return 0;
+    return (long)usec * 1000;
+}
 
-    return ret;
+static uint64_t _nsec_to_usec(long nsec)
+{
+    /* round up so that a pending timer does not look disarmed */
+    return (uint64_t)(nsec / 1000 + (nsec % 1000 != 0));
 }
 
-static void _update_and_check_expiration(uint64_t start, uint64_t end)
+/* runs on the timer thread when ITIMER_REAL expires */
+static long _expire(myst_timer_t* timer, long now)
 {
-    uint64_t elapsed = end - start;
+    itimer_t* it = timer->arg;
+    long next;
+
+    myst_syscall_kill(it->pid, SIGALRM);
 
-    /* update the timer */
-    if (elapsed < _it.real_value)
-        _it.real_value -= elapsed;
-    else
-        _it.real_value = 0;
+    if (it->interval == 0)
+        return 0;
 
-    /* if timer expired */
-    if (_it.real_value == 0)
+    /* skip the periods that were missed rather than firing for each */
+    if (__builtin_saddl_overflow(timer->expires, it->interval, &next) ||
+        next <= now)
     {
-        myst_syscall_kill(myst_getpid(), SIGALRM);
-        _it.real_value = _it.real_interval;
+        if (__builtin_saddl_overflow(now, it->interval, &next))
+            next = LONG_MAX;
     }
+
+    return next;
 }
 
 long myst_syscall_run_itimer(void)
+{
+    /* this thread runs every timer on the timer wheel */
+    return myst_timer_run();
+}
+
+static void _lock(void)
 {
     myst_mutex_lock(&_it.mutex);
-    {
-        _it.initialized = 1;
 
-        for (;;)
-        {
-            struct timespec buf;
-            struct timespec* to;
-
-            /* if ITIMER_REAL is non-zero */
-            if (_it.real_value == 0)
-            {
-                to = NULL;
-            }
-            else
-            {
-                size_t PULSE = MICRO_IN_SECOND / 1000; /* one millisecond */
-                size_t min = PULSE;
-
-                if (_it.real_value < min)
-                    min = _it.real_value;
-
-                to = &buf;
-                to->tv_sec = min / 1000000;
-                to->tv_nsec = (min * 1000) % NANO_IN_SECOND;
-            }
-
-            uint64_t start = _get_current_time();
-            int r = myst_cond_timedwait(&_it.cond, &_it.mutex, to);
-            uint64_t end = _get_current_time();
-
-            assert(start != 0);
-            assert(end != 0);
-            assert(end >= start);
-
-            /* if recieved a signal on condition */
-            if (r == 0)
-            {
-                /* no-op */
-            }
-            else if (r == ETIMEDOUT)
-            {
-                _update_and_check_expiration(start, end);
-            }
-        }
-    }
-    myst_mutex_unlock(&_it.mutex);
+    if (!_it.timer.callback)
+        myst_timer_init(&_it.timer, _expire, &_it);
+}
+
+/* get the current value of ITIMER_REAL (with _it.mutex held) */
+static void _get_value(long now, struct itimerval* value)
+{
+    long expires = myst_timer_expires(&_it.timer);
+    long remaining = 0;
+
+    if (expires)
+        remaining = expires > now ? expires - now : 1;
 
-    return 0;
+    myst_uint64_to_timeval(_nsec_to_usec(remaining), &value->it_value);
+    myst_uint64_to_timeval(_nsec_to_usec(_it.interval), &value->it_interval);
 }
 
 long myst_syscall_setitimer(
@@ -128,6 +98,7 @@ long myst_syscall_setitimer(
     long ret = 0;
     uint64_t interval;
     uint64_t value;
+    long now = myst_deadline_now();
 
     /* ATTN: only ITIMER_REAL is supported so far */
     if (which != ITIMER_REAL || !new_value)
@@ -137,29 +108,26 @@ long myst_syscall_setitimer(
     ECHECK(myst_timeval_to_uint64(&new_value->it_interval, &interval));
     ECHECK(myst_timeval_to_uint64(&new_value->it_value, &value));
 
-    /* wait for itimer thread to obtain the mutex for the first time */
-    while (_it.initialized == 0)
-    {
-        __asm__ __volatile__("pause" : : : "memory");
-    }
-
-    myst_mutex_lock(&_it.mutex);
+    _lock();
     {
         if (old_value)
-        {
-            myst_uint64_to_timeval(_it.real_interval, &old_value->it_interval);
-            myst_uint64_to_timeval(_it.real_value, &old_value->it_value);
-        }
+            _get_value(now, old_value);
+
+        /* the callback does not run again once the timer is cancelled */
+        myst_timer_cancel(&_it.timer);
 
         /* set the new value for the itimer */
-        _it.real_interval = interval;
-        _it.real_value = value;
+        _it.interval = _usec_to_nsec(interval);
+        _it.pid = myst_getpid();
 
-        /* signal the itimer thread */
-        if (myst_cond_signal(&_it.cond) != 0)
+        if (value)
         {
-            myst_mutex_unlock(&_it.mutex);
-            ERAISE(-ENOSYS);
+            long expires;
+
+            if (__builtin_saddl_overflow(now, _usec_to_nsec(value), &expires))
+                expires = LONG_MAX;
+
+            myst_timer_add(&_it.timer, expires);
         }
     }
     myst_mutex_unlock(&_it.mutex);
@@ -179,9 +147,8 @@ int myst_syscall_getitimer(int which, struct itimerval* curr_value)
     if (which != ITIMER_REAL || !curr_value)
         ERAISE(-EINVAL);
 
-    myst_mutex_lock(&_it.mutex);
-    myst_uint64_to_timeval(_it.real_value, &curr_value->it_value);
-    myst_uint64_to_timeval(_it.real_interval, &curr_value->it_interval);
+    _lock();
+    _get_value(myst_deadline_now(), curr_value);
     myst_mutex_unlock(&_it.mutex);
 
 done:
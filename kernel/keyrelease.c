@@ -0,0 +1,107 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <myst/eraise.h>
+#include <myst/file.h>
+#include <myst/keyrelease.h>
+#include <myst/printf.h>
+#include <myst/secret.h>
+#include <myst/tcall.h>
+
+/*
+**==============================================================================
+**
+** The "key-release" secret provider:
+**
+** The target attests the enclave and releases the key named by the secret's
+** id (a key URL) from the key store. The target generates a key pair in the
+** enclave, gets an attestation token for it from the attestation service and
+** sends the token with the release request, so the key store only returns
+** the key wrapped to the enclave's key pair. The "KeyRelease" section of
+** config.json configures the attestation service and the identity used.
+**
+**==============================================================================
+*/
+
+typedef struct key_release_provider
+{
+    myst_secret_provider_t base;
+    const myst_key_release_config_t* config;
+
+    /* the CA certificates, loaded on first use */
+    void* ca_data;
+    size_t ca_size;
+} key_release_provider_t;
+
+static int _get(
+    myst_secret_provider_t* provider,
+    const myst_secret_t* secret,
+    void* buf,
+    size_t size,
+    size_t* size_out)
+{
+    int ret = 0;
+    key_release_provider_t* p = (key_release_provider_t*)provider;
+    const myst_key_release_config_t* config = p->config;
+    myst_key_release_request_t request;
+
+    if (!config->attestation_url)
+    {
+        myst_eprintf("kernel: key release: no AttestationUrl\n");
+        ERAISE(-EINVAL);
+    }
+
+    if (!config->ca_certificates)
+    {
+        myst_eprintf("kernel: key release: no CaCertificates\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* the CA certificates come from the signed root file system */
+    if (!p->ca_data &&
+        myst_load_file(config->ca_certificates, &p->ca_data, &p->ca_size) != 0)
+    {
+        myst_eprintf(
+            "kernel: key release: cannot load %s\n", config->ca_certificates);
+        ERAISE(-ENOENT);
+    }
+
+    request.attestation_url = config->attestation_url;
+    request.key_url = secret->id;
+    request.identity_endpoint = config->identity_endpoint;
+    request.client_id = config->client_id;
+    request.resource = config->resource;
+    request.ca_data = p->ca_data;
+    request.ca_size = p->ca_size;
+
+    ECHECK(myst_tcall_key_release(&request, buf, size, size_out));
+
+done:
+    return ret;
+}
+
+static void _release(myst_secret_provider_t* provider)
+{
+    key_release_provider_t* p = (key_release_provider_t*)provider;
+
+    free(p->ca_data);
+    p->ca_data = NULL;
+}
+
+static key_release_provider_t _provider = {
+    .base = {"key-release", _get, _release},
+};
+
+int myst_register_key_release_provider(const myst_key_release_config_t* config)
+{
+    if (!config)
+        return -EINVAL;
+
+    _provider.config = config;
+
+    return myst_register_secret_provider(&_provider.base);
+}
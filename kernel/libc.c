@@ -179,6 +179,21 @@ int memcmp(const void* s1, const void* s2, size_t n)
     return 0;
 }
 
+void* memchr(const void* s, int c, size_t n)
+{
+    const unsigned char* p = (const unsigned char*)s;
+
+    while (n--)
+    {
+        if (*p == (unsigned char)c)
+            return (void*)p;
+
+        p++;
+    }
+
+    return NULL;
+}
+
 void* memmove(void* dest_, const void* src_, size_t n)
 {
     uint8_t* dest = (uint8_t*)dest_;
@@ -21,6 +21,7 @@
 
 #include <myst/backtrace.h>
 #include <myst/crash.h>
+#include <myst/debugmalloc.h>
 #include <myst/kernel.h>
 #include <myst/list.h>
 #include <myst/mmanutils.h>
@@ -197,6 +198,9 @@ void* malloc(size_t size)
 {
     void* p = NULL;
 
+    if (myst_debug_malloc_enabled())
+        return myst_debug_malloc(size);
+
     if (!(p = dlmalloc(size)))
         return NULL;
 
@@ -212,6 +216,9 @@ void* calloc(size_t nmemb, size_t size)
 {
     void* p = NULL;
 
+    if (myst_debug_malloc_enabled())
+        return myst_debug_calloc(nmemb, size);
+
     if (!(p = dlcalloc(nmemb, size)))
         return NULL;
 
@@ -228,6 +235,9 @@ void* realloc(void* ptr, size_t size)
 {
     void* p = NULL;
 
+    if (myst_debug_malloc_enabled())
+        return myst_debug_realloc(ptr, size);
+
 #ifdef MYST_ENABLE_LEAK_CHECKER
     if (ptr && _remove_node(ptr) != 0)
         myst_panic("unexpected");
@@ -248,6 +258,9 @@ void* memalign(size_t alignment, size_t size)
 {
     void* p = NULL;
 
+    if (myst_debug_malloc_enabled())
+        return myst_debug_memalign(alignment, size);
+
     if (!(p = dlmemalign(alignment, size)))
         return NULL;
 
@@ -261,6 +274,12 @@ void* memalign(size_t alignment, size_t size)
 
 void free(void* ptr)
 {
+    if (myst_debug_malloc_enabled())
+    {
+        myst_debug_free(ptr);
+        return;
+    }
+
     dlfree(ptr);
 
 #ifdef MYST_ENABLE_LEAK_CHECKER
@@ -298,8 +317,21 @@ int myst_find_leaks(void)
 #endif
 }
 
+int myst_malloc_trim(void)
+{
+    /* the quarantined blocks are the first memory to give back */
+    if (myst_debug_malloc_enabled())
+        myst_debug_malloc_flush();
+
+    /* release unused heap segments back to the enclave memory */
+    return dlmalloc_trim(0);
+}
+
 int myst_get_malloc_stats(myst_malloc_stats_t* stats)
 {
+    if (myst_debug_malloc_enabled())
+        return myst_debug_malloc_stats(stats);
+
 #ifdef MYST_ENABLE_LEAK_CHECKER
     {
         if (!stats)
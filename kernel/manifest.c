@@ -0,0 +1,283 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <limits.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+
+#include <myst/cpio.h>
+#include <myst/eraise.h>
+#include <myst/hex.h>
+#include <myst/manifest.h>
+#include <myst/realpath.h>
+#include <myst/strings.h>
+
+#define MANIFESTS_DIR "manifests/"
+#define HASH_CHARS (2 * sizeof(myst_sha256_t))
+
+typedef struct entry
+{
+    char* path;
+    myst_sha256_t hash;
+} entry_t;
+
+struct myst_manifest
+{
+    entry_t* entries;
+    size_t num_entries;
+};
+
+static bool _isspace(char c)
+{
+    switch (c)
+    {
+        case ' ':
+        case '\f':
+        case '\n':
+        case '\r':
+        case '\t':
+        case '\v':
+            return true;
+        default:
+            return false;
+    }
+}
+
+/* convert a manifest path to the form seen by the hostfs driver */
+static int _normalize_path(const char* path, myst_path_t* normalized)
+{
+    int ret = 0;
+    char buf[PATH_MAX];
+
+    while (strncmp(path, "./", 2) == 0)
+        path += 2;
+
+    if (*path == '\0')
+        ERAISE(-EINVAL);
+
+    if (path[0] != '/')
+    {
+        if (myst_strlcpy(buf, "/", sizeof(buf)) >= sizeof(buf))
+            ERAISE(-ENAMETOOLONG);
+    }
+    else
+    {
+        *buf = '\0';
+    }
+
+    if (myst_strlcat(buf, path, sizeof(buf)) >= sizeof(buf))
+        ERAISE(-ENAMETOOLONG);
+
+    ECHECK(myst_realpath(buf, normalized));
+
+done:
+    return ret;
+}
+
+/* parse one line of the form "<hash>  <path>" or "<hash> *<path>" */
+static int _parse_line(char* line, entry_t* entry)
+{
+    int ret = 0;
+    char hex[HASH_CHARS + 1];
+    char* p = line;
+    myst_path_t path;
+
+    for (size_t i = 0; i < HASH_CHARS; i++)
+    {
+        if (!p[i])
+            ERAISE(-EINVAL);
+    }
+
+    memcpy(hex, p, HASH_CHARS);
+    hex[HASH_CHARS] = '\0';
+    p += HASH_CHARS;
+
+    if (myst_ascii_to_bin(hex, entry->hash.data, sizeof(entry->hash)) !=
+        sizeof(entry->hash))
+    {
+        ERAISE(-EINVAL);
+    }
+
+    if (!_isspace(*p))
+        ERAISE(-EINVAL);
+
+    while (_isspace(*p))
+        p++;
+
+    /* binary mode marker written by sha256sum -b */
+    if (*p == '*')
+        p++;
+
+    ECHECK(_normalize_path(p, &path));
+
+    if (!(entry->path = strdup(path.buf)))
+        ERAISE(-ENOMEM);
+
+done:
+    return ret;
+}
+
+static int _parse(char* text, myst_manifest_t* manifest)
+{
+    int ret = 0;
+    size_t max_entries = 0;
+    char* line = text;
+
+    /* every entry takes a line */
+    for (const char* p = text; *p; p++)
+    {
+        if (*p == '\n')
+            max_entries++;
+    }
+
+    max_entries++;
+
+    if (!(manifest->entries = calloc(max_entries, sizeof(entry_t))))
+        ERAISE(-ENOMEM);
+
+    while (line)
+    {
+        char* next;
+        char* end;
+
+        if ((next = strchr(line, '\n')))
+            *next++ = '\0';
+
+        /* remove leading and trailing whitespace */
+        while (_isspace(*line))
+            line++;
+
+        end = line + strlen(line);
+
+        while (end != line && _isspace(end[-1]))
+            *--end = '\0';
+
+        if (*line && *line != '#')
+        {
+            entry_t* entry = &manifest->entries[manifest->num_entries];
+
+            ECHECK(_parse_line(line, entry));
+            manifest->num_entries++;
+        }
+
+        line = next;
+    }
+
+done:
+    return ret;
+}
+
+int myst_manifest_load(
+    const void* archive_data,
+    size_t archive_size,
+    const char* name,
+    myst_manifest_t** manifest_out)
+{
+    int ret = 0;
+    size_t pos = 0;
+    myst_manifest_t* manifest = NULL;
+    char* text = NULL;
+    bool found = false;
+
+    if (manifest_out)
+        *manifest_out = NULL;
+
+    if (!archive_data || !archive_size || !name || !*name || !manifest_out)
+        ERAISE(-EINVAL);
+
+    /* the manifest is named by the basename given to --manifest */
+    if (strchr(name, '/'))
+        ERAISE(-EINVAL);
+
+    while (!found)
+    {
+        myst_cpio_entry_t ent;
+        const void* file_data;
+        int r;
+
+        if ((r = myst_cpio_next_entry(
+                 archive_data, archive_size, &pos, &ent, &file_data)) == 0)
+        {
+            break;
+        }
+
+        if (r < 0)
+            ERAISE(-EINVAL);
+
+        if (!S_ISREG(ent.mode))
+            continue;
+
+        if (strncmp(ent.name, MANIFESTS_DIR, sizeof(MANIFESTS_DIR) - 1) != 0)
+            continue;
+
+        if (strcmp(ent.name + sizeof(MANIFESTS_DIR) - 1, name) != 0)
+            continue;
+
+        if (!(text = malloc(ent.size + 1)))
+            ERAISE(-ENOMEM);
+
+        memcpy(text, file_data, ent.size);
+        text[ent.size] = '\0';
+        found = true;
+    }
+
+    if (!found)
+        ERAISE(-ENOENT);
+
+    if (!(manifest = calloc(1, sizeof(myst_manifest_t))))
+        ERAISE(-ENOMEM);
+
+    ECHECK(_parse(text, manifest));
+
+    *manifest_out = manifest;
+    manifest = NULL;
+
+done:
+
+    if (text)
+        free(text);
+
+    if (manifest)
+        myst_manifest_free(manifest);
+
+    return ret;
+}
+
+int myst_manifest_find(
+    const myst_manifest_t* manifest,
+    const char* path,
+    myst_sha256_t* hash)
+{
+    int ret = 0;
+
+    if (!manifest || !path || !hash)
+        ERAISE(-EINVAL);
+
+    for (size_t i = 0; i < manifest->num_entries; i++)
+    {
+        if (strcmp(manifest->entries[i].path, path) == 0)
+        {
+            *hash = manifest->entries[i].hash;
+            goto done;
+        }
+    }
+
+    ERAISE(-ENOENT);
+
+done:
+    return ret;
+}
+
+void myst_manifest_free(myst_manifest_t* manifest)
+{
+    if (manifest)
+    {
+        for (size_t i = 0; i < manifest->num_entries; i++)
+            free(manifest->entries[i].path);
+
+        free(manifest->entries);
+        free(manifest);
+    }
+}
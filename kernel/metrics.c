@@ -0,0 +1,197 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <stdarg.h>
+#include <stdio.h>
+#include <string.h>
+
+#include <myst/clock.h>
+#include <myst/eraise.h>
+#include <myst/kernel.h>
+#include <myst/metrics.h>
+#include <myst/spinlock.h>
+
+myst_counter_t myst_metric_tcalls = MYST_COUNTER_INITIALIZER(
+    "myst_tcalls_total",
+    "Calls from the kernel to the target, most of which leave the TEE.");
+
+myst_histogram_t myst_metric_futex_wait_seconds = MYST_HISTOGRAM_INITIALIZER(
+    "myst_futex_wait_duration_seconds",
+    "Time spent by futex waits, whether woken, timed out or interrupted.");
+
+myst_histogram_t myst_metric_syscall_seconds = {
+    .name = "myst_syscall_duration_seconds",
+    .help = "Time spent in the kernel by syscalls.",
+    .next = &myst_metric_futex_wait_seconds,
+};
+
+/* the upper bounds of the finite buckets */
+static const long _bounds[MYST_HISTOGRAM_BUCKETS] = {
+    1000L,
+    4000L,
+    16000L,
+    64000L,
+    256000L,
+    1024000L,
+    4096000L,
+    16384000L,
+    65536000L,
+    262144000L,
+    1048576000L,
+    4194304000L,
+};
+
+static const char* _bound_names[MYST_HISTOGRAM_BUCKETS] = {
+    "1e-06",
+    "4e-06",
+    "1.6e-05",
+    "6.4e-05",
+    "0.000256",
+    "0.001024",
+    "0.004096",
+    "0.016384",
+    "0.065536",
+    "0.262144",
+    "1.048576",
+    "4.194304",
+};
+
+static myst_counter_t* _counters = &myst_metric_tcalls;
+static myst_histogram_t* _histograms = &myst_metric_syscall_seconds;
+static myst_spinlock_t _lock = MYST_SPINLOCK_INITIALIZER;
+
+void myst_metrics_register_counter(myst_counter_t* counter)
+{
+    myst_spin_lock(&_lock);
+    counter->next = _counters;
+    _counters = counter;
+    myst_spin_unlock(&_lock);
+}
+
+void myst_metrics_register_histogram(myst_histogram_t* histogram)
+{
+    myst_spin_lock(&_lock);
+    histogram->next = _histograms;
+    _histograms = histogram;
+    myst_spin_unlock(&_lock);
+}
+
+void myst_histogram_observe(myst_histogram_t* histogram, long nsec)
+{
+    size_t i = 0;
+
+    if (nsec < 0)
+        nsec = 0;
+
+    while (i < MYST_HISTOGRAM_BUCKETS && nsec > _bounds[i])
+        i++;
+
+    __atomic_fetch_add(&histogram->buckets[i], 1, __ATOMIC_RELAXED);
+    __atomic_fetch_add(&histogram->sum, (uint64_t)nsec, __ATOMIC_RELAXED);
+}
+
+MYST_PRINTF_FORMAT(2, 3)
+static int _append(myst_buf_t* buf, const char* format, ...)
+{
+    char line[256];
+    va_list ap;
+    int n;
+
+    va_start(ap, format);
+    n = vsnprintf(line, sizeof(line), format, ap);
+    va_end(ap);
+
+    if (n < 0 || (size_t)n >= sizeof(line))
+        return -EINVAL;
+
+    return myst_buf_append(buf, line, (size_t)n);
+}
+
+static int _format_header(
+    myst_buf_t* buf,
+    const char* name,
+    const char* help,
+    const char* type)
+{
+    int ret = 0;
+
+    ECHECK(_append(buf, "# HELP %s %s\n", name, help));
+    ECHECK(_append(buf, "# TYPE %s %s\n", name, type));
+
+done:
+    return ret;
+}
+
+static int _format_counter(myst_buf_t* buf, const myst_counter_t* c)
+{
+    int ret = 0;
+    uint64_t value = __atomic_load_n(&c->value, __ATOMIC_RELAXED);
+
+    ECHECK(_format_header(buf, c->name, c->help, "counter"));
+    ECHECK(_append(buf, "%s %lu\n", c->name, value));
+
+done:
+    return ret;
+}
+
+/* the buckets are cumulative in the text format */
+static int _format_histogram(myst_buf_t* buf, const myst_histogram_t* h)
+{
+    int ret = 0;
+    uint64_t count = 0;
+    uint64_t sum = __atomic_load_n(&h->sum, __ATOMIC_RELAXED);
+
+    ECHECK(_format_header(buf, h->name, h->help, "histogram"));
+
+    for (size_t i = 0; i <= MYST_HISTOGRAM_BUCKETS; i++)
+    {
+        const char* le =
+            i < MYST_HISTOGRAM_BUCKETS ? _bound_names[i] : "+Inf";
+
+        count += __atomic_load_n(&h->buckets[i], __ATOMIC_RELAXED);
+        ECHECK(_append(buf, "%s_bucket{le=\"%s\"} %lu\n", h->name, le, count));
+    }
+
+    ECHECK(_append(
+        buf,
+        "%s_sum %lu.%09lu\n",
+        h->name,
+        sum / NANO_IN_SECOND,
+        sum % NANO_IN_SECOND));
+    ECHECK(_append(buf, "%s_count %lu\n", h->name, count));
+
+done:
+    return ret;
+}
+
+int myst_metrics_format(myst_buf_t* buf)
+{
+    int ret = 0;
+    myst_counter_t page_faults = MYST_COUNTER_INITIALIZER(
+        "myst_page_faults_total",
+        "Page faults handled by the TEE (pages committed on first access).");
+
+    if (__myst_kernel_args.page_faults)
+        page_faults.value = *__myst_kernel_args.page_faults;
+
+    myst_buf_clear(buf);
+
+    myst_spin_lock(&_lock);
+
+    for (const myst_counter_t* c = _counters; c; c = c->next)
+    {
+        if ((ret = _format_counter(buf, c)) != 0)
+            break;
+    }
+
+    for (const myst_histogram_t* h = _histograms; h && !ret; h = h->next)
+        ret = _format_histogram(buf, h);
+
+    myst_spin_unlock(&_lock);
+    ECHECK(ret);
+
+    ECHECK(_format_counter(buf, &page_faults));
+
+done:
+    return ret;
+}
@@ -558,6 +558,31 @@ done:
     return ret;
 }
 
+/* Zero-fill [addr, addr + length), except for the part that has never been
+ * handed out and is still pending: that reads as zero, and writing to it
+ * would commit it before the application uses it */
+static void _mman_zero_fill(myst_mman_t* mman, uintptr_t addr, size_t length)
+{
+    const uintptr_t end = addr + length;
+    const uintptr_t lo = mman->brk_max;
+    uintptr_t hi = mman->map_min;
+
+    if (mman->pending_end < hi)
+        hi = mman->pending_end;
+
+    if (lo >= hi || end <= lo || addr >= hi)
+    {
+        memset((void*)addr, 0, length);
+        return;
+    }
+
+    if (addr < lo)
+        memset((void*)addr, 0, lo - addr);
+
+    if (end > hi)
+        memset((void*)hi, 0, end - hi);
+}
+
 static int _mmap(
     myst_mman_t* mman,
     void* addr,
@@ -748,7 +773,12 @@ done:
 
     /* Zero-fill mapped memory */
     if (ptr_out && *ptr_out)
-        memset(*ptr_out, 0, length);
+    {
+        _mman_zero_fill(mman, (uintptr_t)*ptr_out, length);
+
+        if ((uintptr_t)*ptr_out < mman->map_min)
+            mman->map_min = (uintptr_t)*ptr_out;
+    }
 
     return ret;
 }
@@ -840,6 +870,10 @@ int myst_mman_init(myst_mman_t* mman, uintptr_t base, size_t size)
     /* Set the top of the mapped memory (grows negativey) */
     mman->map = mman->end;
 
+    /* No memory has been handed out yet */
+    mman->brk_max = mman->brk;
+    mman->map_min = mman->map;
+
     /* Set pointer to the next available entry in the myst_vad_t array */
     mman->next_vad = (myst_vad_t*)base;
 
@@ -922,6 +956,9 @@ int myst_mman_sbrk(myst_mman_t* mman, ptrdiff_t increment, void** ptr_out)
         /* Increment the break value and return the old break value */
         ptr = (void*)mman->brk;
         mman->brk += (uintptr_t)increment;
+
+        if (mman->brk > mman->brk_max)
+            mman->brk_max = mman->brk;
     }
     else
     {
@@ -992,6 +1029,9 @@ int myst_mman_brk(myst_mman_t* mman, void* addr, void** ptr)
     /* Set the break value */
     mman->brk = (uintptr_t)addr;
 
+    if (mman->brk > mman->brk_max)
+        mman->brk_max = mman->brk;
+
     if (!_mman_is_sane(mman))
     {
         _mman_set_err(mman, "bad mman parameter");
@@ -1456,6 +1496,25 @@ void myst_mman_set_sanity(myst_mman_t* mman, bool sanity)
         mman->sanity = sanity;
 }
 
+/*
+**
+** myst_mman_set_pending()
+**
+**     Tell the mman structure that the memory from the start of the heap up
+**     to the given address is committed on first access (SGX2), so that it
+**     is not zero-filled when it is first mapped.
+**
+** Parameters:
+**     [IN] mman - mman structure
+**     [IN] end - the end of the pending memory
+**
+*/
+void myst_mman_set_pending(myst_mman_t* mman, uintptr_t end)
+{
+    if (mman)
+        mman->pending_end = end;
+}
+
 /* return the total size of the mman region */
 int myst_mman_total_size(myst_mman_t* mman, size_t* size)
 {
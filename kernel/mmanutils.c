@@ -12,9 +12,11 @@
 #include <myst/eraise.h>
 #include <myst/file.h>
 #include <myst/mmanutils.h>
+#include <myst/oom.h>
 #include <myst/panic.h>
 #include <myst/process.h>
 #include <myst/round.h>
+#include <myst/stackguard.h>
 #include <myst/strings.h>
 #include <myst/syscall.h>
 
@@ -39,6 +41,27 @@ typedef struct msync_mapping
 static msync_mapping_t* _msync_mappings;
 static myst_spinlock_t _msync_mappings_lock = MYST_SPINLOCK_INITIALIZER;
 
+/* MAP_SHARED mappings of files. The processes share one address space, so
+ * the processes that map the same range of a file are given the same
+ * memory, which they then share as they would on Linux (and which futexes
+ * in it are known by; see myst_get_shared_mapping_key()) */
+typedef struct shared_mapping
+{
+    struct shared_mapping* next;
+    dev_t dev;
+    ino_t ino;
+    off_t offset;
+    void* addr;
+    size_t length;
+
+    /* the caller of each mmap() of the range that is not unmapped yet */
+    pid_t* pids;
+    size_t npids;
+} shared_mapping_t;
+
+static shared_mapping_t* _shared_mappings;
+static myst_spinlock_t _shared_mappings_lock = MYST_SPINLOCK_INITIALIZER;
+
 static uint8_t* _min_ptr(uint8_t* x, uint8_t* y)
 {
     return (x < y) ? x : y;
@@ -49,7 +72,7 @@ static uint8_t* _max_ptr(uint8_t* x, uint8_t* y)
     return (x > y) ? x : y;
 }
 
-int myst_setup_mman(void* data, size_t size)
+int myst_setup_mman(void* data, size_t size, size_t pending_size)
 {
     int ret = -1;
 
@@ -65,6 +88,9 @@ int myst_setup_mman(void* data, size_t size)
     if (myst_mman_init(&_mman, (uintptr_t)_mman_start, _mman_size) != 0)
         goto done;
 
+    if (pending_size)
+        myst_mman_set_pending(&_mman, (uintptr_t)_mman_start + pending_size);
+
 #ifdef SCRUB
     /* Scrubbing unmapped memory causes memory reads due to musl libc */
     _mman.scrub = true;
@@ -177,6 +203,183 @@ done:
     return ret;
 }
 
+static int _add_shared_mapping_pid(shared_mapping_t* m, pid_t pid)
+{
+    pid_t* pids;
+
+    if (!(pids = realloc(m->pids, (m->npids + 1) * sizeof(pid_t))))
+        return -ENOMEM;
+
+    m->pids = pids;
+    m->pids[m->npids++] = pid;
+    return 0;
+}
+
+/* the memory of the range if a process maps it already (or NULL) */
+static void* _join_shared_mapping(int fd, off_t offset, size_t length)
+{
+    struct stat buf;
+    void* addr = NULL;
+
+    if (myst_syscall_fstat(fd, &buf) != 0)
+        return NULL;
+
+    myst_spin_lock(&_shared_mappings_lock);
+    {
+        for (shared_mapping_t* m = _shared_mappings; m; m = m->next)
+        {
+            if (m->dev == buf.st_dev && m->ino == buf.st_ino &&
+                offset >= m->offset &&
+                (size_t)(offset - m->offset) + length <= m->length)
+            {
+                if (_add_shared_mapping_pid(m, myst_getpid()) == 0)
+                    addr = (uint8_t*)m->addr + (offset - m->offset);
+                break;
+            }
+        }
+    }
+    myst_spin_unlock(&_shared_mappings_lock);
+
+    return addr;
+}
+
+/* make a new mapping of a file range available to other processes (if
+ * this fails, the mapping is simply not shared) */
+static void _add_shared_mapping(int fd, off_t offset, void* addr, size_t len)
+{
+    struct stat buf;
+    shared_mapping_t* m;
+
+    if (myst_syscall_fstat(fd, &buf) != 0)
+        return;
+
+    if (!(m = calloc(1, sizeof(shared_mapping_t))))
+        return;
+
+    if (_add_shared_mapping_pid(m, myst_getpid()) != 0)
+    {
+        free(m);
+        return;
+    }
+
+    m->dev = buf.st_dev;
+    m->ino = buf.st_ino;
+    m->offset = offset;
+    m->addr = addr;
+    m->length = len;
+
+    myst_spin_lock(&_shared_mappings_lock);
+    {
+        m->next = _shared_mappings;
+        _shared_mappings = m;
+    }
+    myst_spin_unlock(&_shared_mappings_lock);
+}
+
+/* Drop one of the caller's mappings of the shared mapping that the range
+ * overlaps: returns true if the memory is still mapped by others (and so
+ * must stay) */
+static bool _leave_shared_mapping(void* addr, size_t length)
+{
+    pid_t pid = myst_getpid();
+    uint8_t* lo = addr;
+    uint8_t* hi = lo + length;
+    bool shared = false;
+
+    myst_spin_lock(&_shared_mappings_lock);
+    {
+        shared_mapping_t* prev = NULL;
+
+        for (shared_mapping_t* m = _shared_mappings; m; m = m->next)
+        {
+            uint8_t* mlo = m->addr;
+            uint8_t* mhi = mlo + m->length;
+
+            if (_max_ptr(lo, mlo) >= _min_ptr(hi, mhi))
+            {
+                prev = m;
+                continue;
+            }
+
+            for (size_t i = 0; i < m->npids; i++)
+            {
+                if (m->pids[i] == pid)
+                {
+                    m->pids[i] = m->pids[--m->npids];
+                    break;
+                }
+            }
+
+            /* ATTN: unmapping part of a mapping that is shared unmaps
+             * nothing, and unmapping part of one that is not shared no
+             * longer shares the rest */
+            if (m->npids)
+            {
+                shared = true;
+            }
+            else
+            {
+                if (prev)
+                    prev->next = m->next;
+                else
+                    _shared_mappings = m->next;
+
+                free(m->pids);
+                free(m);
+            }
+
+            break;
+        }
+    }
+    myst_spin_unlock(&_shared_mappings_lock);
+
+    return shared;
+}
+
+int myst_get_shared_mapping_key(const void* addr, myst_mapping_key_t* key)
+{
+    int ret = -ENOENT;
+
+    myst_spin_lock(&_shared_mappings_lock);
+    {
+        for (shared_mapping_t* m = _shared_mappings; m; m = m->next)
+        {
+            const uint8_t* p = addr;
+            const uint8_t* mlo = m->addr;
+
+            if (p >= mlo && p < mlo + m->length)
+            {
+                key->dev = m->dev;
+                key->ino = m->ino;
+                key->offset = (uint64_t)m->offset + (uint64_t)(p - mlo);
+                ret = 0;
+                break;
+            }
+        }
+    }
+    myst_spin_unlock(&_shared_mappings_lock);
+
+    return ret;
+}
+
+/* note when an allocation failed or may have left too little free memory */
+static void _check_pressure(int r)
+{
+    if (r == -ENOMEM)
+    {
+        myst_oom_pressure(true);
+    }
+    else if (r == 0)
+    {
+        /* the space between the break and the mappings is always free, so
+         * only a small value calls for counting the gaps (see myst_oom_poll) */
+        size_t low_watermark = myst_oom_low_watermark();
+
+        if (low_watermark && _mman.map - _mman.brk < low_watermark)
+            myst_oom_pressure(false);
+    }
+}
+
 /* ATTN-A: fix return types for this function */
 void* myst_mmap(
     void* addr,
@@ -234,9 +437,21 @@ void* myst_mmap(
         return addr;
     }
 
+    /* share the memory of a process that maps the same range already */
+    if (fd >= 0 && (flags & MAP_SHARED) && !addr)
+    {
+        void* shared;
+
+        if ((shared = _join_shared_mapping(fd, offset, length)))
+            return shared;
+    }
+
     int tflags = MYST_MAP_ANONYMOUS | MYST_MAP_PRIVATE;
 
-    if ((r = myst_mman_mmap(&_mman, addr, length, prot, tflags, &ptr)) < 0)
+    r = myst_mman_mmap(&_mman, addr, length, prot, tflags, &ptr);
+    _check_pressure(r);
+
+    if (r < 0)
         return (void*)(long)r;
 
     if (fd >= 0 && !addr)
@@ -245,6 +460,14 @@ void* myst_mmap(
 
         if ((n = _map_file_onto_memory(fd, offset, ptr, length, flags)) < 0)
             return (void*)(long)-n;
+
+        if (flags & MAP_SHARED)
+        {
+            size_t rounded;
+
+            if (myst_round_up(length, PAGE_SIZE, &rounded) == 0)
+                _add_shared_mapping(fd, offset, ptr, rounded);
+        }
     }
 
     void* end = (uint8_t*)ptr + length;
@@ -268,6 +491,7 @@ void* myst_mremap(
         return (void*)-EINVAL;
 
     r = myst_mman_mremap(&_mman, old_address, old_size, new_size, flags, &p);
+    _check_pressure(r);
 
     if (r != 0)
         return (void*)(long)r;
@@ -399,6 +623,10 @@ int myst_munmap(void* addr, size_t length)
     /* align length to a page boundary */
     ECHECK(myst_round_up(length, PAGE_SIZE, &length));
 
+    /* memory that other processes still map stays */
+    if (_leave_shared_mapping(addr, length))
+        goto done;
+
     ECHECK(myst_mman_munmap(&_mman, addr, length));
 
     ECHECK(_release_msync_mappings(addr, length));
@@ -432,6 +660,16 @@ long myst_syscall_brk(void* addr)
     return (long)ptr;
 }
 
+bool myst_is_mman_range(const void* addr, size_t size)
+{
+    const uint8_t* p = addr;
+
+    if (p < (uint8_t*)_mman_start || p > (uint8_t*)_mman_end)
+        return false;
+
+    return size <= (size_t)((uint8_t*)_mman_end - p);
+}
+
 int myst_get_total_ram(size_t* size)
 {
     return myst_mman_total_size(&_mman, size);
@@ -483,6 +721,32 @@ done:
     return ret;
 }
 
+/* call callback for each mapping made by the given process (the mappings are
+ * those of mmap() and are not trimmed by munmap()) */
+int myst_enumerate_process_mappings(
+    pid_t pid,
+    void (*callback)(void* addr, size_t size, void* arg),
+    void* arg)
+{
+    int ret = 0;
+
+    if (pid < 0 || !callback)
+        ERAISE(-EINVAL);
+
+    myst_spin_lock(&_mappings_lock);
+    {
+        for (myst_process_mapping_t* p = _mappings; p; p = p->next)
+        {
+            if (p->pid == pid)
+                (*callback)(p->addr, p->size, arg);
+        }
+    }
+    myst_spin_unlock(&_mappings_lock);
+
+done:
+    return ret;
+}
+
 /* release mappings made the given process */
 int myst_release_process_mappings(pid_t pid)
 {
@@ -502,6 +766,7 @@ int myst_release_process_mappings(pid_t pid)
 
             if (p->pid == pid)
             {
+                myst_stack_guard_munmap(p->addr, p->size);
                 myst_munmap(p->addr, p->size);
 
                 if (prev)
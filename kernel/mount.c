@@ -3,6 +3,7 @@
 
 #include <stdlib.h>
 #include <string.h>
+#include <sys/mount.h>
 
 #include <myst/atexit.h>
 #include <myst/blkdev.h>
@@ -13,10 +14,13 @@
 #include <myst/hex.h>
 #include <myst/hostfs.h>
 #include <myst/kernel.h>
+#include <myst/manifest.h>
+#include <myst/printf.h>
 #include <myst/mount.h>
 #include <myst/pubkey.h>
 #include <myst/ramfs.h>
 #include <myst/realpath.h>
+#include <myst/resolve.h>
 #include <myst/roothash.h>
 #include <myst/sha256.h>
 #include <myst/spinlock.h>
@@ -26,6 +30,14 @@
 
 #define MOUNT_TABLE_SIZE 8
 
+/* the entry was mounted by the mount() of the application */
+#define MOUNT_FLAG_APPLICATION 1
+
+/* the mount() flags that tmpfs and proc accept and ignore */
+#define IGNORED_MOUNT_FLAGS                                          \
+    (MS_NOSUID | MS_NODEV | MS_NOEXEC | MS_NOATIME | MS_NODIRATIME | \
+     MS_RELATIME | MS_STRICTATIME | MS_SILENT)
+
 typedef struct mount_table_entry
 {
     char* path;
@@ -40,6 +52,8 @@ static myst_spinlock_t _lock = MYST_SPINLOCK_INITIALIZER;
 
 static bool _installed_free_mount_table = false;
 
+static myst_mount_policy_t _policy;
+
 static void _free_mount_table(void* arg)
 {
     (void)arg;
@@ -122,7 +136,11 @@ done:
     return ret;
 }
 
-int myst_mount(myst_fs_t* fs, const char* source, const char* target)
+static int _mount(
+    myst_fs_t* fs,
+    const char* source,
+    const char* target,
+    uint32_t flags)
 {
     int ret = -1;
     bool locked = false;
@@ -186,7 +204,7 @@ int myst_mount(myst_fs_t* fs, const char* source, const char* target)
 
         mount_table_entry.path_size = strlen(target) + 1;
         mount_table_entry.fs = fs;
-        mount_table_entry.flags = 0;
+        mount_table_entry.flags = flags;
     }
 
     _mount_table[_mount_table_size++] = mount_table_entry;
@@ -205,7 +223,60 @@ done:
     return ret;
 }
 
-int myst_umount(const char* target)
+int myst_mount(myst_fs_t* fs, const char* source, const char* target)
+{
+    return _mount(fs, source, target, 0);
+}
+
+int myst_mount_replace(
+    myst_fs_t* fs,
+    const char* source,
+    const char* target,
+    myst_fs_t** old_fs)
+{
+    int ret = 0;
+    myst_path_t realpath;
+    bool found = false;
+
+    if (old_fs)
+        *old_fs = NULL;
+
+    if (!fs || !source || !target || !old_fs)
+        ERAISE(-EINVAL);
+
+    ECHECK(myst_realpath(target, &realpath));
+
+    myst_spin_lock(&_lock);
+
+    for (size_t i = 0; i < _mount_table_size; i++)
+    {
+        mount_table_entry_t* entry = &_mount_table[i];
+
+        if (strcmp(entry->path, realpath.buf) == 0)
+        {
+            if ((ret = (*fs->fs_mount)(fs, source, realpath.buf)) == 0)
+            {
+                *old_fs = entry->fs;
+                entry->fs = fs;
+            }
+
+            found = true;
+            break;
+        }
+    }
+
+    myst_spin_unlock(&_lock);
+
+    if (!found)
+        ERAISE(-ENOENT);
+
+    ECHECK(ret);
+
+done:
+    return ret;
+}
+
+static int _umount(const char* target, bool application)
 {
     int ret = 0;
     myst_path_t realpath;
@@ -223,6 +294,13 @@ int myst_umount(const char* target)
 
         if (strcmp(entry->path, realpath.buf) == 0)
         {
+            /* the application only unmounts what it mounted */
+            if (application && _policy.enforced &&
+                !(entry->flags & MOUNT_FLAG_APPLICATION))
+            {
+                ERAISE(-EPERM);
+            }
+
             /* release the path */
             free(entry->path);
 
@@ -248,7 +326,67 @@ done:
     return ret;
 }
 
-#ifdef MYST_ENABLE_EXT2FS
+int myst_umount(const char* target)
+{
+    return _umount(target, false);
+}
+
+int myst_mount_policy_setup(const myst_mount_policy_t* policy)
+{
+    int ret = 0;
+
+    if (!policy)
+        ERAISE(-EINVAL);
+
+    for (size_t i = 0; i < policy->num_rules; i++)
+    {
+        if (!policy->rules[i].type)
+        {
+            myst_eprintf("kernel: AllowedMounts[%zu] has no Type\n", i);
+            ERAISE(-EINVAL);
+        }
+
+        if (policy->rules[i].target && policy->rules[i].target[0] != '/')
+        {
+            myst_eprintf(
+                "kernel: AllowedMounts[%zu] has a relative Target\n", i);
+            ERAISE(-EINVAL);
+        }
+    }
+
+    _policy = *policy;
+
+done:
+    return ret;
+}
+
+static bool _mount_allowed(
+    const char* type,
+    const char* source,
+    const char* target)
+{
+    /* without a configuration, the application may mount anything */
+    if (!_policy.enforced)
+        return true;
+
+    for (size_t i = 0; i < _policy.num_rules; i++)
+    {
+        const myst_mount_rule_t* rule = &_policy.rules[i];
+
+        if (strcmp(rule->type, type) != 0)
+            continue;
+
+        if (rule->source && strcmp(rule->source, source) != 0)
+            continue;
+
+        if (!rule->target || strcmp(rule->target, target) == 0)
+            return true;
+    }
+
+    return false;
+}
+
+#if defined(MYST_ENABLE_EXT2FS) || defined(MYST_ENABLE_HOSTFS)
 static const char* _find_arg(const char* args[], const char* name)
 {
     if (!args)
@@ -266,7 +404,7 @@ static const char* _find_arg(const char* args[], const char* name)
     /* not found */
     return NULL;
 }
-#endif /* MYST_ENABLE_EXT2FS */
+#endif /* MYST_ENABLE_EXT2FS || MYST_ENABLE_HOSTFS */
 
 long myst_syscall_mount(
     const char* source,
@@ -278,10 +416,19 @@ long myst_syscall_mount(
     long ret = 0;
     myst_fs_t* fs = NULL;
     myst_blkdev_t* blkdev = NULL;
+    myst_path_t realpath;
+
+    /* not while a scoped openat2() opens a file (see myst_resolve_lock) */
+    myst_resolve_lock();
 
     if (!source || !target || !filesystemtype)
         ERAISE(-EINVAL);
 
+    ECHECK(myst_realpath(target, &realpath));
+
+    if (!_mount_allowed(filesystemtype, source, realpath.buf))
+        ERAISE(-EPERM);
+
     if (strcmp(filesystemtype, "ramfs") == 0)
     {
         /* these arguments should be zero and null */
@@ -292,24 +439,69 @@ long myst_syscall_mount(
         ECHECK(myst_init_ramfs(myst_mount_resolve, &fs));
 
         /* perform the mount */
-        ECHECK(myst_mount(fs, source, target));
+        ECHECK(_mount(fs, source, target, MOUNT_FLAG_APPLICATION));
         fs = NULL;
 
         /* load the rootfs */
         ECHECK(myst_cpio_unpack(source, target));
     }
+    else if (strcmp(filesystemtype, "tmpfs") == 0)
+    {
+        /* the options (e.g., "size=" and "mode=") are ignored */
+        if (mountflags & ~IGNORED_MOUNT_FLAGS)
+            ERAISE(-EINVAL);
+
+        /* an empty ramfs instance */
+        ECHECK(myst_init_ramfs(myst_mount_resolve, &fs));
+
+        ECHECK(_mount(fs, source, target, MOUNT_FLAG_APPLICATION));
+        fs = NULL;
+    }
+    else if (strcmp(filesystemtype, "proc") == 0)
+    {
+        if (mountflags & ~IGNORED_MOUNT_FLAGS)
+            ERAISE(-EINVAL);
+
+        /* the one proc file system is already mounted on /proc */
+        if (strcmp(realpath.buf, "/proc") != 0)
+            ERAISE(-EBUSY);
+    }
 #ifdef MYST_ENABLE_HOSTFS
     else if (strcmp(filesystemtype, "hostfs") == 0)
     {
-        /* these arguments should be zero and null */
-        if (mountflags || data)
+        const char** args = (const char**)data;
+        const char* name;
+
+        if (mountflags)
             ERAISE(-EINVAL);
 
-        /* create a new ramfs instance */
+        /* create a new hostfs instance */
         ECHECK(myst_init_hostfs(&fs));
 
+        /* verify the files against a manifest from the signed archive */
+        if ((name = _find_arg(args, "manifest")))
+        {
+            myst_manifest_t* manifest;
+
+            ECHECK(myst_manifest_load(
+                __myst_kernel_args.archive_data,
+                __myst_kernel_args.archive_size,
+                name,
+                &manifest));
+
+            if (myst_hostfs_set_manifest(fs, manifest) != 0)
+            {
+                myst_manifest_free(manifest);
+                ERAISE(-EINVAL);
+            }
+        }
+        else if (args && args[0])
+        {
+            ERAISE(-EINVAL);
+        }
+
         /* perform the mount */
-        ECHECK(myst_mount(fs, source, target));
+        ECHECK(_mount(fs, source, target, MOUNT_FLAG_APPLICATION));
         fs = NULL;
     }
 #endif /* MYST_ENABLE_HOSTFS */
@@ -327,7 +519,7 @@ long myst_syscall_mount(
         ECHECK(myst_load_fs(myst_mount_resolve, source, key, &fs));
 
         /* perform the mount */
-        ECHECK(myst_mount(fs, source, target));
+        ECHECK(_mount(fs, source, target, MOUNT_FLAG_APPLICATION));
         fs = NULL;
     }
 #endif /* MYST_ENABLE_EXT2FS */
@@ -344,6 +536,8 @@ done:
     if (fs)
         (fs->fs_release)(fs);
 
+    myst_resolve_unlock();
+
     return ret;
 }
 
@@ -351,10 +545,13 @@ long myst_syscall_umount2(const char* target, int flags)
 {
     long ret = 0;
 
-    if (!target || flags != 0)
+    /* MNT_DETACH and MNT_FORCE unmount at once */
+    if (!target || (flags & ~(MNT_DETACH | MNT_FORCE)))
         ERAISE(-EINVAL);
 
-    ECHECK(myst_umount(target));
+    myst_resolve_lock();
+    ret = _umount(target, true);
+    myst_resolve_unlock();
 
 done:
     return ret;
@@ -11,6 +11,7 @@
 #include <myst/mutex.h>
 #include <myst/panic.h>
 #include <myst/printf.h>
+#include <myst/sigsafe.h>
 #include <myst/strings.h>
 #include <myst/tcall.h>
 #include <myst/thread.h>
@@ -77,6 +78,8 @@ int myst_mutex_lock(myst_mutex_t* mutex)
     if (!m)
         return EINVAL;
 
+    myst_sigsafe_assert(__FUNCTION__);
+
     /* Loop until SELF obtains mutex */
     for (;;)
     {
@@ -87,7 +90,13 @@ int myst_mutex_lock(myst_mutex_t* mutex)
             /* Attempt to acquire lock */
             if (__myst_mutex_trylock(m, self) == 0)
             {
+                bool first = m->refs == 1;
+
                 myst_spin_unlock(&m->lock);
+
+                if (first)
+                    myst_sigsafe_acquired(m, __builtin_return_address(0));
+
                 return 0;
             }
 
@@ -121,7 +130,13 @@ int myst_mutex_trylock(myst_mutex_t* mutex)
         /* Attempt to acquire lock */
         if (__myst_mutex_trylock(m, self) == 0)
         {
+            bool first = m->refs == 1;
+
             myst_spin_unlock(&m->lock);
+
+            if (first)
+                myst_sigsafe_acquired(m, __builtin_return_address(0));
+
             return 0;
         }
     }
@@ -135,6 +150,7 @@ int __myst_mutex_unlock(myst_mutex_t* mutex, myst_thread_t** waiter)
     myst_mutex_t* m = (myst_mutex_t*)mutex;
     myst_thread_t* self = myst_thread_self();
     int ret = -1;
+    bool released = false;
 
     myst_spin_lock(&m->lock);
     {
@@ -149,6 +165,7 @@ int __myst_mutex_unlock(myst_mutex_t* mutex, myst_thread_t** waiter)
 
                 /* Set waiter to the next thread on the queue (maybe none) */
                 *waiter = m->queue.front;
+                released = true;
             }
 
             ret = 0;
@@ -156,6 +173,9 @@ int __myst_mutex_unlock(myst_mutex_t* mutex, myst_thread_t** waiter)
     }
     myst_spin_unlock(&m->lock);
 
+    if (released)
+        myst_sigsafe_released(m);
+
     return ret;
 }
 
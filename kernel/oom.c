@@ -0,0 +1,276 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <signal.h>
+#include <string.h>
+
+#include <myst/eraise.h>
+#include <myst/mmanutils.h>
+#include <myst/mutex.h>
+#include <myst/oom.h>
+#include <myst/printf.h>
+#include <myst/signal.h>
+#include <myst/spinlock.h>
+#include <myst/strings.h>
+#include <myst/syscall.h>
+#include <myst/thread.h>
+
+/*
+**==============================================================================
+**
+** Out-of-memory handling:
+**
+** Allocations from the enclave memory (application mappings and the kernel
+** heap) go through myst_mmap() and myst_mremap(). When free memory drops
+** below the configured watermark, or when an allocation fails, the registered
+** pressure callbacks are invoked to release memory. The allocator may hold
+** locks at that point, so it only records the pressure and the callbacks run
+** when the thread leaves the kernel. An application mapping that still cannot
+** be satisfied fails with ENOMEM or, with the "kill" policy, the process with
+** the largest resident set is killed as well.
+**
+**==============================================================================
+*/
+
+#define PRESSURE_LOW 1
+#define PRESSURE_EXHAUSTED 2
+
+typedef struct callback
+{
+    myst_oom_callback_t callback;
+    void* arg;
+} callback_t;
+
+static bool _kill_policy;
+static size_t _low_watermark;
+static _Atomic int _pressure;
+static bool _low; /* free memory was below the watermark at the last poll */
+static _Atomic size_t _kills;
+
+static callback_t _callbacks[MYST_OOM_MAX_CALLBACKS];
+static size_t _num_callbacks;
+static myst_spinlock_t _callbacks_lock = MYST_SPINLOCK_INITIALIZER;
+
+/* serializes the running of the pressure callbacks */
+static myst_mutex_t _run_mutex;
+
+int myst_oom_setup(const char* policy, size_t low_watermark)
+{
+    int ret = 0;
+    size_t total;
+
+    if (!policy || strcmp(policy, "fail") == 0)
+        _kill_policy = false;
+    else if (strcmp(policy, "kill") == 0)
+        _kill_policy = true;
+    else
+    {
+        myst_eprintf("kernel: unknown OOM policy: %s\n", policy);
+        ERAISE(-EINVAL);
+    }
+
+    ECHECK(myst_get_total_ram(&total));
+
+    if (low_watermark >= total)
+    {
+        myst_eprintf(
+            "kernel: memory low watermark exceeds the memory size: %zu\n",
+            low_watermark);
+        ERAISE(-EINVAL);
+    }
+
+    _low_watermark = low_watermark;
+
+done:
+    return ret;
+}
+
+int myst_oom_register_callback(myst_oom_callback_t callback, void* arg)
+{
+    int ret = 0;
+
+    if (!callback)
+        ERAISE(-EINVAL);
+
+    myst_spin_lock(&_callbacks_lock);
+    {
+        if (_num_callbacks == MYST_OOM_MAX_CALLBACKS)
+        {
+            myst_spin_unlock(&_callbacks_lock);
+            ERAISE(-ENOMEM);
+        }
+
+        _callbacks[_num_callbacks].callback = callback;
+        _callbacks[_num_callbacks].arg = arg;
+        _num_callbacks++;
+    }
+    myst_spin_unlock(&_callbacks_lock);
+
+done:
+    return ret;
+}
+
+size_t myst_oom_low_watermark(void)
+{
+    return _low_watermark;
+}
+
+size_t myst_oom_kills(void)
+{
+    return _kills;
+}
+
+void myst_oom_pressure(bool exhausted)
+{
+    if (exhausted)
+        _pressure |= PRESSURE_EXHAUSTED;
+    else if (_low_watermark)
+        _pressure |= PRESSURE_LOW;
+}
+
+static size_t _free_ram(void)
+{
+    size_t size = 0;
+    myst_get_free_ram(&size);
+    return size;
+}
+
+/* invoke the callbacks (caller holds _run_mutex) and return bytes released */
+static size_t _run_callbacks(size_t needed)
+{
+    callback_t callbacks[MYST_OOM_MAX_CALLBACKS];
+    size_t num_callbacks;
+    size_t before = _free_ram();
+    size_t after;
+
+    /* callbacks may register further callbacks */
+    myst_spin_lock(&_callbacks_lock);
+    {
+        num_callbacks = _num_callbacks;
+        memcpy(callbacks, _callbacks, num_callbacks * sizeof(callback_t));
+    }
+    myst_spin_unlock(&_callbacks_lock);
+
+    for (size_t i = 0; i < num_callbacks; i++)
+        (*callbacks[i].callback)(needed, callbacks[i].arg);
+
+    after = _free_ram();
+
+    return after > before ? after - before : 0;
+}
+
+/* kill the process with the largest resident set size */
+static void _kill_largest(size_t length)
+{
+    myst_thread_t* thread = myst_thread_self();
+    myst_thread_t* self = myst_find_process_thread(thread);
+    pid_t pid = 0;
+    size_t rss = 0;
+    char name[sizeof(thread->name)];
+
+    myst_spin_lock(&myst_process_list_lock);
+    {
+        myst_thread_t* victim = self;
+        myst_thread_t* p;
+
+        for (p = self->main.prev_process_thread; p;
+             p = p->main.prev_process_thread)
+        {
+            if (p->main.rss > victim->main.rss)
+                victim = p;
+        }
+
+        for (p = self->main.next_process_thread; p;
+             p = p->main.next_process_thread)
+        {
+            if (p->main.rss > victim->main.rss)
+                victim = p;
+        }
+
+        pid = victim->pid;
+        rss = victim->main.rss;
+        MYST_STRLCPY(name, myst_get_thread_name(victim));
+    }
+    myst_spin_unlock(&myst_process_list_lock);
+
+    myst_eprintf(
+        "kernel: out of memory allocating %zu bytes: "
+        "killed process %d (%s), rss %zu kB\n",
+        length,
+        pid,
+        name,
+        rss / 1024);
+
+    _kills++;
+
+    /* the calling process is killed when the thread leaves the kernel */
+    if (pid == thread->pid)
+    {
+        if (!myst_is_process_thread(thread))
+            myst_syscall_kill(pid, SIGKILL);
+
+        myst_signal_deliver(thread, SIGKILL, NULL);
+    }
+    else
+    {
+        myst_syscall_kill(pid, SIGKILL);
+    }
+}
+
+void myst_oom_poll(void)
+{
+    int pressure;
+
+    if (!_pressure)
+        return;
+
+    /* another thread is already relieving the pressure */
+    if (myst_mutex_trylock(&_run_mutex) != 0)
+        return;
+
+    if ((pressure = __atomic_exchange_n(&_pressure, 0, __ATOMIC_SEQ_CST)))
+    {
+        size_t avail = _free_ram();
+
+        if (avail < _low_watermark)
+        {
+            /* run the callbacks once when free memory crosses the mark */
+            if (!_low || (pressure & PRESSURE_EXHAUSTED))
+            {
+                _run_callbacks(_low_watermark - avail);
+                _low = _free_ram() < _low_watermark;
+            }
+        }
+        else
+        {
+            _low = false;
+
+            /* the free memory may be too fragmented for the allocation */
+            if (pressure & PRESSURE_EXHAUSTED)
+                _run_callbacks(0);
+        }
+    }
+
+    myst_mutex_unlock(&_run_mutex);
+}
+
+int myst_oom_handle(size_t length)
+{
+    size_t released;
+
+    myst_mutex_lock(&_run_mutex);
+    {
+        _pressure = 0;
+        released = _run_callbacks(length);
+    }
+    myst_mutex_unlock(&_run_mutex);
+
+    if (released)
+        return 0;
+
+    if (_kill_policy)
+        _kill_largest(length);
+
+    return -ENOMEM;
+}
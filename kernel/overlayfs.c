@@ -0,0 +1,2291 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <dirent.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <myst/eraise.h>
+#include <myst/fs.h>
+#include <myst/id.h>
+#include <myst/ioctl.h>
+#include <myst/mutex.h>
+#include <myst/overlayfs.h>
+#include <myst/paths.h>
+#include <myst/realpath.h>
+#include <myst/strings.h>
+
+/*
+**==============================================================================
+**
+** overlayfs_t:
+**
+** Paths are looked up one component at a time and a name in the upper layer
+** hides the same name in the lower layer. A whiteout is an empty file named
+** ".wh.<name>" in the upper layer that hides <name> in the lower layer (it was
+** removed). An upper directory that contains ".wh..wh..opq" is opaque: it
+** hides the lower directory of the same name (that one was removed and this
+** one created in its place). Names that start with ".wh." are reserved.
+**
+**==============================================================================
+*/
+
+#define OVERLAYFS_MAGIC 0x4f3a6b1de28c5907
+
+#define WHITEOUT_PREFIX ".wh."
+#define WHITEOUT_PREFIX_LEN (sizeof(WHITEOUT_PREFIX) - 1)
+#define OPAQUE_NAME ".wh..wh..opq"
+
+/* the number of symbolic links followed in one lookup (as for Linux) */
+#define MAX_SYMLINKS 40
+
+/* the size of the buffer used to copy files up */
+#define COPY_UP_BUFSIZE (16 * 1024)
+
+typedef struct overlayfs
+{
+    myst_fs_t base;
+    uint64_t magic;
+    myst_mount_resolve_callback_t resolve;
+    myst_fs_t* lower;
+    myst_fs_t* upper;
+    myst_mutex_t lock; /* serializes lookups with changes to the layers */
+    char target[PATH_MAX]; /* target argument to myst_mount() */
+} overlayfs_t;
+
+static bool _overlayfs_valid(const overlayfs_t* ovl)
+{
+    return ovl && ovl->magic == OVERLAYFS_MAGIC;
+}
+
+/*
+**==============================================================================
+**
+** myst_file_t
+**
+** Files of the upper layer are handed out by the upper layer itself. These
+** wrap directories (whose entries merge both layers) and files of the lower
+** layer (which must be copied up before being changed).
+**
+**==============================================================================
+*/
+
+#define FILE_MAGIC 0x9c20e57b3a4d61f8
+
+struct myst_file
+{
+    uint64_t magic;
+    myst_fs_t* fs;       /* the layer the file is open on */
+    myst_file_t* file;   /* the file as opened on that layer */
+    char path[PATH_MAX]; /* the path within the overlay */
+    bool dir;
+    struct dirent* ents; /* the merged entries of a directory */
+    size_t nents;
+    off_t offset;
+};
+
+static bool _file_valid(const myst_file_t* file)
+{
+    return file && file->magic == FILE_MAGIC;
+}
+
+static bool _valid(myst_fs_t* fs, const myst_file_t* file)
+{
+    return _overlayfs_valid((overlayfs_t*)fs) && _file_valid(file);
+}
+
+static void _free_file(myst_file_t* file)
+{
+    free(file->ents);
+    memset(file, 0xdd, sizeof(myst_file_t));
+    free(file);
+}
+
+/*
+**==============================================================================
+**
+** paths:
+**
+**==============================================================================
+*/
+
+static bool _reserved(const char* name)
+{
+    return strncmp(name, WHITEOUT_PREFIX, WHITEOUT_PREFIX_LEN) == 0;
+}
+
+static void _dirname(const char* path, char dirname[PATH_MAX])
+{
+    char* p;
+
+    myst_strlcpy(dirname, path, PATH_MAX);
+
+    if ((p = strrchr(dirname, '/')) == dirname || !p)
+        myst_strlcpy(dirname, "/", PATH_MAX);
+    else
+        *p = '\0';
+}
+
+/* path = dirname + "/" + name */
+static int _join(char path[PATH_MAX], const char* dirname, const char* name)
+{
+    if (myst_strlcpy(path, dirname, PATH_MAX) >= PATH_MAX)
+        return -ENAMETOOLONG;
+
+    if (strcmp(dirname, "/") != 0 &&
+        myst_strlcat(path, "/", PATH_MAX) >= PATH_MAX)
+    {
+        return -ENAMETOOLONG;
+    }
+
+    if (myst_strlcat(path, name, PATH_MAX) >= PATH_MAX)
+        return -ENAMETOOLONG;
+
+    return 0;
+}
+
+/* path = head + "/" + tail (the tail may be empty) */
+static int _splice(char path[PATH_MAX], const char* head, const char* tail)
+{
+    if (myst_strlcpy(path, head, PATH_MAX) >= PATH_MAX)
+        return -ENAMETOOLONG;
+
+    if (*tail)
+    {
+        if (myst_strlcat(path, "/", PATH_MAX) >= PATH_MAX)
+            return -ENAMETOOLONG;
+
+        if (myst_strlcat(path, tail, PATH_MAX) >= PATH_MAX)
+            return -ENAMETOOLONG;
+    }
+
+    return 0;
+}
+
+/* the path of the whiteout that hides path */
+static int _whiteout_path(const char* path, char buf[PATH_MAX])
+{
+    char dirname[PATH_MAX];
+    char name[NAME_MAX + 1];
+
+    _dirname(path, dirname);
+
+    if (myst_strlcpy(name, WHITEOUT_PREFIX, sizeof(name)) >= sizeof(name) ||
+        myst_strlcat(name, myst_basename(path), sizeof(name)) >= sizeof(name))
+    {
+        return -ENAMETOOLONG;
+    }
+
+    return _join(buf, dirname, name);
+}
+
+/* remove the leading component of path into name (0 if there are none) */
+static int _pop(char* path, char name[NAME_MAX + 1])
+{
+    char* p = path;
+    size_t len;
+
+    while (*p == '/')
+        p++;
+
+    if ((len = strcspn(p, "/")) == 0)
+    {
+        *path = '\0';
+        return 0;
+    }
+
+    if (len > NAME_MAX)
+        return -ENAMETOOLONG;
+
+    memcpy(name, p, len);
+    name[len] = '\0';
+    p += len;
+    memmove(path, p, strlen(p) + 1);
+
+    return 1;
+}
+
+/*
+**==============================================================================
+**
+** lookups:
+**
+**==============================================================================
+*/
+
+typedef struct lookup
+{
+    char path[PATH_MAX];   /* the path within the overlay without symlinks */
+    char target[PATH_MAX]; /* or an absolute path left to resolve */
+    bool exists;
+    bool upper;     /* whether the path is in the upper layer */
+    bool lower;     /* whether the lower layer shows through at the path */
+    struct stat st; /* lstat() of the path in its topmost layer */
+} lookup_t;
+
+static bool _exists(myst_fs_t* fs, const char* path, struct stat* st)
+{
+    struct stat buf;
+
+    return (*fs->fs_lstat)(fs, path, st ? st : &buf) == 0;
+}
+
+static bool _whited_out(overlayfs_t* ovl, const char* path)
+{
+    char buf[PATH_MAX];
+
+    return _whiteout_path(path, buf) == 0 && _exists(ovl->upper, buf, NULL);
+}
+
+static bool _opaque(overlayfs_t* ovl, const char* dirname)
+{
+    char buf[PATH_MAX];
+
+    return _join(buf, dirname, OPAQUE_NAME) == 0 &&
+           _exists(ovl->upper, buf, NULL);
+}
+
+static myst_fs_t* _layer(overlayfs_t* ovl, const lookup_t* lk)
+{
+    return lk->upper ? ovl->upper : ovl->lower;
+}
+
+/* whether the lower layer has the path (so removing it needs a whiteout) */
+static bool _in_lower(overlayfs_t* ovl, const lookup_t* lk)
+{
+    return lk->lower && _exists(ovl->lower, lk->path, NULL);
+}
+
+/* Look up path in the merged view. Symbolic links are followed (the final one
+ * only if follow is true): relative ones within the overlay and absolute ones
+ * by setting lk->target for the caller to resolve through the mount table.
+ * If only the final component is missing, this succeeds with !lk->exists.
+ */
+static int _walk(overlayfs_t* ovl, const char* path, bool follow, lookup_t* lk)
+{
+    int ret = 0;
+    typedef struct _variables
+    {
+        char rest[PATH_MAX];
+        char link[PATH_MAX];
+        char buf[PATH_MAX];
+        char tmp[PATH_MAX];
+        char name[NAME_MAX + 1];
+        myst_path_t root;
+    } variables_t;
+    variables_t* v = NULL;
+    size_t nlinks = 0;
+    int r;
+
+    if (!(v = malloc(sizeof(variables_t))))
+        ERAISE(-ENOMEM);
+
+    if (myst_strlcpy(v->rest, path, sizeof(v->rest)) >= sizeof(v->rest))
+        ERAISE(-ENAMETOOLONG);
+
+restart:
+
+    /* the upper root directory merges with the lower one */
+    memset(lk, 0, sizeof(lookup_t));
+    myst_strlcpy(lk->path, "/", sizeof(lk->path));
+    ECHECK((*ovl->upper->fs_lstat)(ovl->upper, "/", &lk->st));
+    lk->exists = true;
+    lk->upper = true;
+    lk->lower = true;
+
+    while ((r = _pop(v->rest, v->name)) > 0)
+    {
+        bool hidden;
+        bool more;
+
+        if (strcmp(v->name, ".") == 0)
+            continue;
+
+        if (!lk->exists)
+            ERAISE_QUIET(-ENOENT);
+
+        if (!S_ISDIR(lk->st.st_mode))
+            ERAISE_QUIET(-ENOTDIR);
+
+        if (strcmp(v->name, "..") == 0)
+        {
+            /* ".." does not climb above the root of the process */
+            ECHECK(myst_realpath_fs_root(ovl->target, &v->root));
+
+            if (strcmp(lk->path, v->root.buf) == 0)
+                continue;
+
+            /* lk->path has no symbolic links so ".." is its parent */
+            _dirname(lk->path, v->buf);
+            ECHECK(_splice(v->tmp, v->buf, v->rest));
+            myst_strlcpy(v->rest, v->tmp, sizeof(v->rest));
+            goto restart;
+        }
+
+        if (_reserved(v->name))
+            ERAISE_QUIET(-ENOENT);
+
+        ECHECK(_join(v->buf, lk->path, v->name));
+
+        /* whether this name in the lower layer was removed or replaced */
+        hidden = !lk->lower;
+
+        if (!hidden && lk->upper)
+            hidden = _opaque(ovl, lk->path) || _whited_out(ovl, v->buf);
+
+        more = v->rest[strspn(v->rest, "/")] != '\0';
+
+        if (_exists(ovl->upper, v->buf, &lk->st))
+        {
+            lk->upper = true;
+            lk->lower = !hidden;
+        }
+        else if (!hidden && _exists(ovl->lower, v->buf, &lk->st))
+        {
+            lk->upper = false;
+            lk->lower = true;
+        }
+        else
+        {
+            if (more)
+                ERAISE_QUIET(-ENOENT);
+
+            lk->exists = false;
+            lk->upper = false;
+            lk->lower = !hidden;
+        }
+
+        myst_strlcpy(lk->path, v->buf, sizeof(lk->path));
+
+        if (lk->exists && S_ISLNK(lk->st.st_mode) && (follow || more))
+        {
+            myst_fs_t* fs = _layer(ovl, lk);
+            const size_t size = sizeof(v->link) - 1;
+            ssize_t n;
+
+            if (++nlinks > MAX_SYMLINKS)
+                ERAISE_QUIET(-ELOOP);
+
+            ECHECK(n = (*fs->fs_readlink)(fs, lk->path, v->link, size));
+            v->link[n] = '\0';
+
+            /* absolute links may lead to other file systems */
+            if (v->link[0] == '/')
+            {
+                ECHECK(_splice(lk->target, v->link, v->rest));
+                goto done;
+            }
+
+            /* lk->path has no symbolic links so resolve against its parent
+             * (leaving ".." to the walk, which stops it at the root) */
+            _dirname(lk->path, v->buf);
+            ECHECK(_join(v->tmp, v->buf, v->link));
+            ECHECK(_splice(v->buf, v->tmp, v->rest));
+            myst_strlcpy(v->rest, v->buf, sizeof(v->rest));
+            goto restart;
+        }
+    }
+
+    ECHECK(r);
+
+done:
+
+    if (v)
+        free(v);
+
+    return ret;
+}
+
+/* create a lookup for path (the caller frees it) */
+static int _lookup(
+    overlayfs_t* ovl,
+    const char* path,
+    bool follow,
+    lookup_t** lk_out)
+{
+    int ret = 0;
+    lookup_t* lk = NULL;
+
+    if (!(lk = malloc(sizeof(lookup_t))))
+        ERAISE(-ENOMEM);
+
+    ECHECK(_walk(ovl, path, follow, lk));
+
+    *lk_out = lk;
+    lk = NULL;
+
+done:
+
+    if (lk)
+        free(lk);
+
+    return ret;
+}
+
+/*
+**==============================================================================
+**
+** directories:
+**
+**==============================================================================
+*/
+
+static bool _has_ent(const struct dirent* ents, size_t n, const char* name)
+{
+    for (size_t i = 0; i < n; i++)
+    {
+        if (strcmp(ents[i].d_name, name) == 0)
+            return true;
+    }
+
+    return false;
+}
+
+static int _add_ent(
+    struct dirent** ents,
+    size_t* n,
+    size_t* capacity,
+    const struct dirent* ent)
+{
+    if (*n == *capacity)
+    {
+        size_t m = *capacity ? *capacity * 2 : 16;
+        struct dirent* p;
+
+        if (!(p = realloc(*ents, m * sizeof(struct dirent))))
+            return -ENOMEM;
+
+        *ents = p;
+        *capacity = m;
+    }
+
+    (*ents)[(*n)++] = *ent;
+    return 0;
+}
+
+/* read all the entries of a directory of one layer */
+static int _read_dir(
+    myst_fs_t* fs,
+    const char* path,
+    struct dirent** ents_out,
+    size_t* nents_out)
+{
+    int ret = 0;
+    myst_fs_t* dfs = NULL;
+    myst_file_t* dir = NULL;
+    struct dirent* buf = NULL;
+    const size_t count = 16;
+    struct dirent* ents = NULL;
+    size_t nents = 0;
+    size_t capacity = 0;
+    int n;
+
+    if (!(buf = malloc(count * sizeof(struct dirent))))
+        ERAISE(-ENOMEM);
+
+    ECHECK((*fs->fs_open)(fs, path, O_RDONLY | O_DIRECTORY, 0, &dfs, &dir));
+
+    while ((n = (*dfs->fs_getdents64)(dfs, dir, buf, count * sizeof(*buf))) > 0)
+    {
+        for (size_t i = 0; i < (size_t)n / sizeof(struct dirent); i++)
+            ECHECK(_add_ent(&ents, &nents, &capacity, &buf[i]));
+    }
+
+    ECHECK(n);
+
+    *ents_out = ents;
+    *nents_out = nents;
+    ents = NULL;
+
+done:
+
+    if (dir)
+        (*dfs->fs_close)(dfs, dir);
+
+    free(buf);
+    free(ents);
+
+    return ret;
+}
+
+/* the entries of a directory of the merged view */
+static int _merge_dir(
+    overlayfs_t* ovl,
+    const lookup_t* lk,
+    struct dirent** ents_out,
+    size_t* nents_out)
+{
+    int ret = 0;
+    struct dirent* upper = NULL;
+    size_t nupper = 0;
+    struct dirent* lower = NULL;
+    size_t nlower = 0;
+    struct dirent* ents = NULL;
+    size_t nents = 0;
+    size_t capacity = 0;
+    struct stat st;
+
+    if (lk->upper)
+    {
+        ECHECK(_read_dir(ovl->upper, lk->path, &upper, &nupper));
+
+        for (size_t i = 0; i < nupper; i++)
+        {
+            if (!_reserved(upper[i].d_name))
+                ECHECK(_add_ent(&ents, &nents, &capacity, &upper[i]));
+        }
+    }
+
+    if (lk->lower && _exists(ovl->lower, lk->path, &st) &&
+        S_ISDIR(st.st_mode) && !(lk->upper && _opaque(ovl, lk->path)))
+    {
+        ECHECK(_read_dir(ovl->lower, lk->path, &lower, &nlower));
+
+        for (size_t i = 0; i < nlower; i++)
+        {
+            const char* name = lower[i].d_name;
+            char whiteout[NAME_MAX + 1];
+
+            if (_has_ent(ents, nents, name))
+                continue;
+
+            if (myst_strlcpy(whiteout, WHITEOUT_PREFIX, sizeof(whiteout)) <
+                    sizeof(whiteout) &&
+                myst_strlcat(whiteout, name, sizeof(whiteout)) <
+                    sizeof(whiteout) &&
+                _has_ent(upper, nupper, whiteout))
+            {
+                continue;
+            }
+
+            ECHECK(_add_ent(&ents, &nents, &capacity, &lower[i]));
+        }
+    }
+
+    for (size_t i = 0; i < nents; i++)
+    {
+        ents[i].d_off = (off_t)((i + 1) * sizeof(struct dirent));
+        ents[i].d_reclen = sizeof(struct dirent);
+    }
+
+    *ents_out = ents;
+    *nents_out = nents;
+    ents = NULL;
+
+done:
+
+    free(upper);
+    free(lower);
+    free(ents);
+
+    return ret;
+}
+
+static int _empty_dir(overlayfs_t* ovl, const lookup_t* lk, bool* empty)
+{
+    int ret = 0;
+    struct dirent* ents = NULL;
+    size_t nents = 0;
+
+    ECHECK(_merge_dir(ovl, lk, &ents, &nents));
+
+    *empty = true;
+
+    for (size_t i = 0; i < nents; i++)
+    {
+        const char* name = ents[i].d_name;
+
+        if (strcmp(name, ".") != 0 && strcmp(name, "..") != 0)
+            *empty = false;
+    }
+
+done:
+
+    free(ents);
+
+    return ret;
+}
+
+/* remove the whiteouts from an upper directory so that it can be removed */
+static int _clean_dir(overlayfs_t* ovl, const char* path)
+{
+    int ret = 0;
+    struct dirent* ents = NULL;
+    size_t nents = 0;
+    char buf[PATH_MAX];
+
+    ECHECK(_read_dir(ovl->upper, path, &ents, &nents));
+
+    for (size_t i = 0; i < nents; i++)
+    {
+        if (_reserved(ents[i].d_name))
+        {
+            ECHECK(_join(buf, path, ents[i].d_name));
+            ECHECK((*ovl->upper->fs_unlink)(ovl->upper, buf));
+        }
+    }
+
+done:
+
+    free(ents);
+
+    return ret;
+}
+
+/*
+**==============================================================================
+**
+** copy-up and whiteouts:
+**
+**==============================================================================
+*/
+
+static int _create_empty_file(myst_fs_t* fs, const char* path)
+{
+    int ret = 0;
+    myst_fs_t* fs_out;
+    myst_file_t* file;
+    const int flags = O_CREAT | O_WRONLY | O_TRUNC;
+
+    ECHECK((*fs->fs_open)(fs, path, flags, 0, &fs_out, &file));
+    (*fs_out->fs_close)(fs_out, file);
+
+done:
+    return ret;
+}
+
+/* give a copy in the upper layer the owner of the original (after which
+ * the mode is set again, as a chown drops the set-ID bits) */
+static int _copy_up_owner(
+    overlayfs_t* ovl,
+    const char* path,
+    const struct stat* st)
+{
+    int ret = 0;
+    myst_fs_t* fs = ovl->upper;
+
+    /* the copy was created with this owner */
+    if (st->st_uid == myst_geteuid() && st->st_gid == myst_getegid())
+        goto done;
+
+    if (!fs->fs_chown || !fs->fs_chmod)
+        goto done;
+
+    ECHECK((*fs->fs_chown)(fs, path, false, st->st_uid, st->st_gid));
+
+    if (!S_ISLNK(st->st_mode))
+        ECHECK((*fs->fs_chmod)(fs, path, st->st_mode & 07777));
+
+done:
+    return ret;
+}
+
+/* make a directory of the merged view exist in the upper layer */
+static int _copy_up_dir(overlayfs_t* ovl, const char* path)
+{
+    int ret = 0;
+    struct stat st;
+    char dirname[PATH_MAX];
+
+    if (_exists(ovl->upper, path, &st))
+    {
+        if (!S_ISDIR(st.st_mode))
+            ERAISE(-ENOTDIR);
+
+        goto done;
+    }
+
+    _dirname(path, dirname);
+    ECHECK(_copy_up_dir(ovl, dirname));
+
+    ECHECK((*ovl->lower->fs_lstat)(ovl->lower, path, &st));
+
+    if (!S_ISDIR(st.st_mode))
+        ERAISE(-ENOTDIR);
+
+    ECHECK((*ovl->upper->fs_mkdir)(ovl->upper, path, st.st_mode & 07777));
+    ECHECK(_copy_up_owner(ovl, path, &st));
+
+done:
+    return ret;
+}
+
+static int _copy_up_file(
+    overlayfs_t* ovl,
+    const char* path,
+    const struct stat* st)
+{
+    int ret = 0;
+    myst_fs_t* lfs = NULL;
+    myst_file_t* lfile = NULL;
+    myst_fs_t* ufs = NULL;
+    myst_file_t* ufile = NULL;
+    const int flags = O_CREAT | O_EXCL | O_WRONLY;
+    const mode_t mode = st->st_mode & 07777;
+    char* buf = NULL;
+    ssize_t n;
+
+    if (!(buf = malloc(COPY_UP_BUFSIZE)))
+        ERAISE(-ENOMEM);
+
+    ECHECK((*ovl->lower->fs_open)(ovl->lower, path, O_RDONLY, 0, &lfs, &lfile));
+    ECHECK((*ovl->upper->fs_open)(ovl->upper, path, flags, mode, &ufs, &ufile));
+
+    while ((n = (*lfs->fs_read)(lfs, lfile, buf, COPY_UP_BUFSIZE)) > 0)
+    {
+        for (ssize_t off = 0; off < n;)
+        {
+            ssize_t m;
+
+            ECHECK(m = (*ufs->fs_write)(ufs, ufile, buf + off, n - off));
+
+            if (m == 0)
+                ERAISE(-EIO);
+
+            off += m;
+        }
+    }
+
+    ECHECK(n);
+
+    /* keep the times of the original */
+    {
+        const struct timespec times[2] = {st->st_atim, st->st_mtim};
+        ECHECK((*ufs->fs_futimens)(ufs, ufile, times));
+    }
+
+done:
+
+    if (ufile)
+    {
+        (*ufs->fs_close)(ufs, ufile);
+
+        if (ret != 0)
+            (*ovl->upper->fs_unlink)(ovl->upper, path);
+    }
+
+    if (lfile)
+        (*lfs->fs_close)(lfs, lfile);
+
+    free(buf);
+
+    return ret;
+}
+
+/* copy a file of the lower layer to the upper layer */
+static int _copy_up(overlayfs_t* ovl, lookup_t* lk)
+{
+    int ret = 0;
+    char buf[PATH_MAX];
+
+    if (lk->upper)
+        goto done;
+
+    _dirname(lk->path, buf);
+    ECHECK(_copy_up_dir(ovl, buf));
+
+    if (S_ISDIR(lk->st.st_mode))
+    {
+        ECHECK(_copy_up_dir(ovl, lk->path));
+    }
+    else if (S_ISLNK(lk->st.st_mode))
+    {
+        myst_fs_t* fs = ovl->lower;
+        ssize_t n;
+
+        ECHECK(n = (*fs->fs_readlink)(fs, lk->path, buf, sizeof(buf) - 1));
+        buf[n] = '\0';
+        ECHECK((*ovl->upper->fs_symlink)(ovl->upper, buf, lk->path));
+        ECHECK(_copy_up_owner(ovl, lk->path, &lk->st));
+    }
+    else if (S_ISREG(lk->st.st_mode))
+    {
+        ECHECK(_copy_up_file(ovl, lk->path, &lk->st));
+        ECHECK(_copy_up_owner(ovl, lk->path, &lk->st));
+    }
+    else
+    {
+        ERAISE(-ENOTSUP);
+    }
+
+    lk->upper = true;
+
+done:
+    return ret;
+}
+
+/* hide the lower file at path */
+static int _whiteout(overlayfs_t* ovl, const char* path)
+{
+    int ret = 0;
+    char buf[PATH_MAX];
+
+    _dirname(path, buf);
+    ECHECK(_copy_up_dir(ovl, buf));
+    ECHECK(_whiteout_path(path, buf));
+    ECHECK(_create_empty_file(ovl->upper, buf));
+
+done:
+    return ret;
+}
+
+/* make the parent of a new file exist in the upper layer and remove any
+ * whiteout of the name (returning whether there was one) */
+static int _prepare_create(overlayfs_t* ovl, const char* path, bool* whited)
+{
+    int ret = 0;
+    char buf[PATH_MAX];
+
+    if (whited)
+        *whited = false;
+
+    _dirname(path, buf);
+    ECHECK(_copy_up_dir(ovl, buf));
+    ECHECK(_whiteout_path(path, buf));
+
+    if (_exists(ovl->upper, buf, NULL))
+    {
+        ECHECK((*ovl->upper->fs_unlink)(ovl->upper, buf));
+
+        if (whited)
+            *whited = true;
+    }
+
+done:
+    return ret;
+}
+
+/* hide the lower directory of the same name as this upper one */
+static int _make_opaque(overlayfs_t* ovl, const char* path)
+{
+    int ret = 0;
+    char buf[PATH_MAX];
+
+    ECHECK(_join(buf, path, OPAQUE_NAME));
+    ECHECK(_create_empty_file(ovl->upper, buf));
+
+done:
+    return ret;
+}
+
+/*
+**==============================================================================
+**
+** interface:
+**
+**==============================================================================
+*/
+
+static int _fs_release(myst_fs_t* fs)
+{
+    int ret = 0;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+
+    if (!_overlayfs_valid(ovl))
+        ERAISE(-EINVAL);
+
+    ECHECK((*ovl->upper->fs_release)(ovl->upper));
+    ECHECK((*ovl->lower->fs_release)(ovl->lower));
+
+    memset(ovl, 0xdd, sizeof(overlayfs_t));
+    free(ovl);
+
+done:
+    return ret;
+}
+
+static int _fs_mount(myst_fs_t* fs, const char* source, const char* target)
+{
+    int ret = 0;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+
+    if (!_overlayfs_valid(ovl) || !target)
+        ERAISE(-EINVAL);
+
+    if (myst_strlcpy(ovl->target, target, PATH_MAX) >= PATH_MAX)
+        ERAISE(-ENAMETOOLONG);
+
+    /* both layers appear at the target (see fs_realpath) */
+    ECHECK((*ovl->lower->fs_mount)(ovl->lower, source, target));
+    ECHECK((*ovl->upper->fs_mount)(ovl->upper, source, target));
+
+done:
+    return ret;
+}
+
+static int _open_file(
+    overlayfs_t* ovl,
+    const lookup_t* lk,
+    int flags,
+    myst_file_t** file_out)
+{
+    int ret = 0;
+    myst_fs_t* fs = _layer(ovl, lk);
+    myst_file_t* file = NULL;
+
+    flags &= ~(O_CREAT | O_EXCL | O_TRUNC);
+
+    if (!(file = calloc(1, sizeof(myst_file_t))))
+        ERAISE(-ENOMEM);
+
+    file->magic = FILE_MAGIC;
+    file->dir = S_ISDIR(lk->st.st_mode);
+    myst_strlcpy(file->path, lk->path, sizeof(file->path));
+
+    if (file->dir)
+        ECHECK(_merge_dir(ovl, lk, &file->ents, &file->nents));
+
+    ECHECK((*fs->fs_open)(fs, lk->path, flags, 0, &file->fs, &file->file));
+
+    *file_out = file;
+    file = NULL;
+
+done:
+
+    if (file)
+        _free_file(file);
+
+    return ret;
+}
+
+static int _fs_open(
+    myst_fs_t* fs,
+    const char* pathname,
+    int flags,
+    mode_t mode,
+    myst_fs_t** fs_out,
+    myst_file_t** file_out)
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    const int accmode = flags & O_ACCMODE;
+    const bool excl = (flags & (O_CREAT | O_EXCL)) == (O_CREAT | O_EXCL);
+    lookup_t* lk = NULL;
+    myst_file_t* file = NULL;
+
+    if (!_overlayfs_valid(ovl) || !pathname || !fs_out || !file_out)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, pathname, !(flags & O_NOFOLLOW) && !excl, &lk));
+
+    if (*lk->target)
+    {
+        char suffix[PATH_MAX];
+        myst_fs_t* tfs;
+
+        ECHECK((*ovl->resolve)(lk->target, suffix, &tfs));
+        ECHECK((*tfs->fs_open)(tfs, suffix, flags, mode, fs_out, file_out));
+        goto done;
+    }
+
+    if (!lk->exists)
+    {
+        if (!(flags & O_CREAT))
+            ERAISE_QUIET(-ENOENT);
+
+        ECHECK(_prepare_create(ovl, lk->path, NULL));
+        ECHECK((*ovl->upper->fs_open)(
+            ovl->upper, lk->path, flags, mode, fs_out, file_out));
+        goto done;
+    }
+
+    if (excl)
+        ERAISE_QUIET(-EEXIST);
+
+    if (S_ISDIR(lk->st.st_mode))
+    {
+        if (accmode != O_RDONLY)
+            ERAISE(-EISDIR);
+
+        ECHECK(_open_file(ovl, lk, flags, &file));
+    }
+    else
+    {
+        if (flags & O_DIRECTORY)
+            ERAISE(-ENOTDIR);
+
+        /* write access copies the file up */
+        if (!lk->upper && (accmode != O_RDONLY || (flags & O_TRUNC)))
+            ECHECK(_copy_up(ovl, lk));
+
+        if (lk->upper)
+        {
+            ECHECK((*ovl->upper->fs_open)(
+                ovl->upper, lk->path, flags, mode, fs_out, file_out));
+            goto done;
+        }
+
+        ECHECK(_open_file(ovl, lk, flags, &file));
+    }
+
+    *fs_out = fs;
+    *file_out = file;
+    file = NULL;
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    if (file)
+        _free_file(file);
+
+    free(lk);
+
+    return ret;
+}
+
+static int _fs_creat(
+    myst_fs_t* fs,
+    const char* pathname,
+    mode_t mode,
+    myst_fs_t** fs_out,
+    myst_file_t** file)
+{
+    const int flags = O_CREAT | O_WRONLY | O_TRUNC;
+    return _fs_open(fs, pathname, flags, mode, fs_out, file);
+}
+
+static off_t _fs_lseek(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    off_t offset,
+    int whence)
+{
+    off_t ret = 0;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    if (!file->dir)
+    {
+        ret = (*file->fs->fs_lseek)(file->fs, file->file, offset, whence);
+        goto done;
+    }
+
+    switch (whence)
+    {
+        case SEEK_SET:
+            break;
+        case SEEK_CUR:
+            offset += file->offset;
+            break;
+        default:
+            ERAISE(-EINVAL);
+    }
+
+    if (offset < 0)
+        ERAISE(-EINVAL);
+
+    /* rewinding picks up the changes since the directory was opened */
+    if (offset == 0)
+    {
+        lookup_t* lk = NULL;
+        struct dirent* ents = NULL;
+        size_t nents = 0;
+        int r;
+
+        myst_mutex_lock(&ovl->lock);
+
+        if ((r = _lookup(ovl, file->path, false, &lk)) == 0)
+        {
+            if (*lk->target || !lk->exists || !S_ISDIR(lk->st.st_mode))
+                r = -ENOENT;
+            else
+                r = _merge_dir(ovl, lk, &ents, &nents);
+        }
+
+        myst_mutex_unlock(&ovl->lock);
+        free(lk);
+
+        /* the directory was removed: it has no entries left */
+        if (r == -ENOENT)
+            r = 0;
+
+        ECHECK(r);
+
+        free(file->ents);
+        file->ents = ents;
+        file->nents = nents;
+    }
+
+    file->offset = offset;
+    ret = offset;
+
+done:
+    return ret;
+}
+
+static ssize_t _fs_read(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    void* buf,
+    size_t count)
+{
+    ssize_t ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    if (file->dir)
+        ERAISE(-EISDIR);
+
+    ret = (*file->fs->fs_read)(file->fs, file->file, buf, count);
+
+done:
+    return ret;
+}
+
+static ssize_t _fs_write(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    const void* buf,
+    size_t count)
+{
+    ssize_t ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    /* files of the lower layer are open read-only */
+    ret = (*file->fs->fs_write)(file->fs, file->file, buf, count);
+
+done:
+    return ret;
+}
+
+static ssize_t _fs_pread(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    void* buf,
+    size_t count,
+    off_t offset)
+{
+    ssize_t ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    if (file->dir)
+        ERAISE(-EISDIR);
+
+    ret = (*file->fs->fs_pread)(file->fs, file->file, buf, count, offset);
+
+done:
+    return ret;
+}
+
+static ssize_t _fs_pwrite(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    const void* buf,
+    size_t count,
+    off_t offset)
+{
+    ssize_t ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    ret = (*file->fs->fs_pwrite)(file->fs, file->file, buf, count, offset);
+
+done:
+    return ret;
+}
+
+static ssize_t _fs_readv(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    const struct iovec* iov,
+    int iovcnt)
+{
+    ssize_t ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    if (file->dir)
+        ERAISE(-EISDIR);
+
+    ret = (*file->fs->fs_readv)(file->fs, file->file, iov, iovcnt);
+
+done:
+    return ret;
+}
+
+static ssize_t _fs_writev(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    const struct iovec* iov,
+    int iovcnt)
+{
+    ssize_t ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    ret = (*file->fs->fs_writev)(file->fs, file->file, iov, iovcnt);
+
+done:
+    return ret;
+}
+
+static int _fs_close(myst_fs_t* fs, myst_file_t* file)
+{
+    int ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    ret = (*file->fs->fs_close)(file->fs, file->file);
+    _free_file(file);
+
+done:
+    return ret;
+}
+
+static int _fs_access(myst_fs_t* fs, const char* pathname, int mode)
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* lk = NULL;
+
+    if (!_overlayfs_valid(ovl) || !pathname)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, pathname, true, &lk));
+
+    if (*lk->target)
+    {
+        char suffix[PATH_MAX];
+        myst_fs_t* tfs;
+
+        ECHECK((*ovl->resolve)(lk->target, suffix, &tfs));
+        ECHECK((*tfs->fs_access)(tfs, suffix, mode));
+        goto done;
+    }
+
+    if (!lk->exists)
+        ERAISE_QUIET(-ENOENT);
+
+    /* files of the lower layer are writable once copied up */
+    if (!lk->upper)
+        mode &= ~W_OK;
+
+    ECHECK((*_layer(ovl, lk)->fs_access)(_layer(ovl, lk), lk->path, mode));
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(lk);
+
+    return ret;
+}
+
+static int _stat(
+    myst_fs_t* fs,
+    const char* pathname,
+    bool follow,
+    struct stat* statbuf)
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* lk = NULL;
+
+    if (!_overlayfs_valid(ovl) || !pathname || !statbuf)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, pathname, follow, &lk));
+
+    if (*lk->target)
+    {
+        char suffix[PATH_MAX];
+        myst_fs_t* tfs;
+
+        ECHECK((*ovl->resolve)(lk->target, suffix, &tfs));
+
+        if (follow)
+            ECHECK((*tfs->fs_stat)(tfs, suffix, statbuf));
+        else
+            ECHECK((*tfs->fs_lstat)(tfs, suffix, statbuf));
+
+        goto done;
+    }
+
+    if (!lk->exists)
+        ERAISE_QUIET(-ENOENT);
+
+    ECHECK((*_layer(ovl, lk)->fs_lstat)(_layer(ovl, lk), lk->path, statbuf));
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(lk);
+
+    return ret;
+}
+
+static int _fs_stat(myst_fs_t* fs, const char* pathname, struct stat* statbuf)
+{
+    return _stat(fs, pathname, true, statbuf);
+}
+
+static int _fs_lstat(myst_fs_t* fs, const char* pathname, struct stat* statbuf)
+{
+    return _stat(fs, pathname, false, statbuf);
+}
+
+static int _fs_fstat(myst_fs_t* fs, myst_file_t* file, struct stat* statbuf)
+{
+    int ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    ret = (*file->fs->fs_fstat)(file->fs, file->file, statbuf);
+
+done:
+    return ret;
+}
+
+static int _fs_link(myst_fs_t* fs, const char* oldpath, const char* newpath)
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* old = NULL;
+    lookup_t* new = NULL;
+
+    if (!_overlayfs_valid(ovl) || !oldpath || !newpath)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, oldpath, false, &old));
+    ECHECK(_lookup(ovl, newpath, false, &new));
+
+    if (*old->target || *new->target)
+        ERAISE(-EXDEV);
+
+    if (!old->exists)
+        ERAISE_QUIET(-ENOENT);
+
+    if (S_ISDIR(old->st.st_mode))
+        ERAISE(-EPERM);
+
+    if (new->exists)
+        ERAISE_QUIET(-EEXIST);
+
+    ECHECK(_copy_up(ovl, old));
+    ECHECK(_prepare_create(ovl, new->path, NULL));
+    ECHECK((*ovl->upper->fs_link)(ovl->upper, old->path, new->path));
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(old);
+    free(new);
+
+    return ret;
+}
+
+static int _fs_unlink(myst_fs_t* fs, const char* pathname)
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* lk = NULL;
+    bool whiteout;
+
+    if (!_overlayfs_valid(ovl) || !pathname)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, pathname, false, &lk));
+
+    if (*lk->target)
+    {
+        char suffix[PATH_MAX];
+        myst_fs_t* tfs;
+
+        ECHECK((*ovl->resolve)(lk->target, suffix, &tfs));
+        ECHECK((*tfs->fs_unlink)(tfs, suffix));
+        goto done;
+    }
+
+    if (!lk->exists)
+        ERAISE_QUIET(-ENOENT);
+
+    if (S_ISDIR(lk->st.st_mode))
+        ERAISE(-EISDIR);
+
+    whiteout = _in_lower(ovl, lk);
+
+    if (lk->upper)
+        ECHECK((*ovl->upper->fs_unlink)(ovl->upper, lk->path));
+
+    if (whiteout)
+        ECHECK(_whiteout(ovl, lk->path));
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(lk);
+
+    return ret;
+}
+
+static int _fs_rename(myst_fs_t* fs, const char* oldpath, const char* newpath)
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* old = NULL;
+    lookup_t* new = NULL;
+    bool dir;
+    bool old_in_lower;
+    bool new_in_lower;
+    bool whited;
+
+    if (!_overlayfs_valid(ovl) || !oldpath || !newpath)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, oldpath, false, &old));
+    ECHECK(_lookup(ovl, newpath, false, &new));
+
+    if (*old->target || *new->target)
+        ERAISE(-EXDEV);
+
+    if (!old->exists)
+        ERAISE_QUIET(-ENOENT);
+
+    if (strcmp(old->path, new->path) == 0)
+        goto done;
+
+    dir = S_ISDIR(old->st.st_mode);
+    old_in_lower = _in_lower(ovl, old);
+    new_in_lower = new->exists && _in_lower(ovl, new);
+
+    /* as Linux without "redirect_dir": lower directories cannot move */
+    if (dir && old_in_lower)
+        ERAISE(-EXDEV);
+
+    if (dir)
+    {
+        size_t len = strlen(old->path);
+
+        if (strncmp(new->path, old->path, len) == 0 && new->path[len] == '/')
+            ERAISE(-EINVAL);
+    }
+
+    if (new->exists)
+    {
+        if (S_ISDIR(new->st.st_mode))
+        {
+            bool empty;
+
+            if (!dir)
+                ERAISE(-EISDIR);
+
+            ECHECK(_empty_dir(ovl, new, &empty));
+
+            if (!empty)
+                ERAISE(-ENOTEMPTY);
+
+            if (new->upper)
+                ECHECK(_clean_dir(ovl, new->path));
+        }
+        else if (dir)
+        {
+            ERAISE(-ENOTDIR);
+        }
+    }
+
+    ECHECK(_copy_up(ovl, old));
+    ECHECK(_prepare_create(ovl, new->path, &whited));
+    ECHECK((*ovl->upper->fs_rename)(ovl->upper, old->path, new->path));
+
+    /* a directory moved over a lower one must not merge with it */
+    if (dir && (new_in_lower || whited))
+        ECHECK(_make_opaque(ovl, new->path));
+
+    if (old_in_lower)
+        ECHECK(_whiteout(ovl, old->path));
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(old);
+    free(new);
+
+    return ret;
+}
+
+static int _fs_truncate(myst_fs_t* fs, const char* path, off_t length)
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* lk = NULL;
+
+    if (!_overlayfs_valid(ovl) || !path)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, path, true, &lk));
+
+    if (*lk->target)
+    {
+        char suffix[PATH_MAX];
+        myst_fs_t* tfs;
+
+        ECHECK((*ovl->resolve)(lk->target, suffix, &tfs));
+        ECHECK((*tfs->fs_truncate)(tfs, suffix, length));
+        goto done;
+    }
+
+    if (!lk->exists)
+        ERAISE_QUIET(-ENOENT);
+
+    if (S_ISDIR(lk->st.st_mode))
+        ERAISE(-EISDIR);
+
+    ECHECK(_copy_up(ovl, lk));
+    ECHECK((*ovl->upper->fs_truncate)(ovl->upper, lk->path, length));
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(lk);
+
+    return ret;
+}
+
+static int _fs_ftruncate(myst_fs_t* fs, myst_file_t* file, off_t length)
+{
+    int ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    if (file->dir)
+        ERAISE(-EISDIR);
+
+    /* files of the lower layer are open read-only */
+    ret = (*file->fs->fs_ftruncate)(file->fs, file->file, length);
+
+done:
+    return ret;
+}
+
+static int _fs_mkdir(myst_fs_t* fs, const char* pathname, mode_t mode)
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* lk = NULL;
+    bool whited;
+
+    if (!_overlayfs_valid(ovl) || !pathname)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, pathname, false, &lk));
+
+    if (*lk->target)
+    {
+        char suffix[PATH_MAX];
+        myst_fs_t* tfs;
+
+        ECHECK((*ovl->resolve)(lk->target, suffix, &tfs));
+        ECHECK((*tfs->fs_mkdir)(tfs, suffix, mode));
+        goto done;
+    }
+
+    if (lk->exists)
+        ERAISE_QUIET(-EEXIST);
+
+    ECHECK(_prepare_create(ovl, lk->path, &whited));
+    ECHECK((*ovl->upper->fs_mkdir)(ovl->upper, lk->path, mode));
+
+    /* this replaces a removed lower directory */
+    if (whited)
+        ECHECK(_make_opaque(ovl, lk->path));
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(lk);
+
+    return ret;
+}
+
+static int _fs_rmdir(myst_fs_t* fs, const char* pathname)
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* lk = NULL;
+    bool empty;
+    bool whiteout;
+
+    if (!_overlayfs_valid(ovl) || !pathname)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, pathname, false, &lk));
+
+    if (*lk->target)
+    {
+        char suffix[PATH_MAX];
+        myst_fs_t* tfs;
+
+        ECHECK((*ovl->resolve)(lk->target, suffix, &tfs));
+        ECHECK((*tfs->fs_rmdir)(tfs, suffix));
+        goto done;
+    }
+
+    if (!lk->exists)
+        ERAISE_QUIET(-ENOENT);
+
+    if (!S_ISDIR(lk->st.st_mode))
+        ERAISE(-ENOTDIR);
+
+    if (strcmp(lk->path, "/") == 0)
+        ERAISE(-EBUSY);
+
+    ECHECK(_empty_dir(ovl, lk, &empty));
+
+    if (!empty)
+        ERAISE(-ENOTEMPTY);
+
+    whiteout = _in_lower(ovl, lk);
+
+    if (lk->upper)
+    {
+        ECHECK(_clean_dir(ovl, lk->path));
+        ECHECK((*ovl->upper->fs_rmdir)(ovl->upper, lk->path));
+    }
+
+    if (whiteout)
+        ECHECK(_whiteout(ovl, lk->path));
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(lk);
+
+    return ret;
+}
+
+static int _fs_getdents64(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    struct dirent* dirp,
+    size_t count)
+{
+    int ret = 0;
+    size_t n = count / sizeof(struct dirent);
+    size_t i;
+    size_t bytes = 0;
+
+    if (!_valid(fs, file) || !dirp)
+        ERAISE(-EINVAL);
+
+    if (!file->dir)
+        ERAISE(-ENOTDIR);
+
+    i = (size_t)file->offset / sizeof(struct dirent);
+
+    for (; n > 0 && i < file->nents; n--, i++)
+    {
+        *dirp++ = file->ents[i];
+        bytes += sizeof(struct dirent);
+    }
+
+    file->offset += (off_t)bytes;
+    ret = (int)bytes;
+
+done:
+    return ret;
+}
+
+static ssize_t _fs_readlink(
+    myst_fs_t* fs,
+    const char* pathname,
+    char* buf,
+    size_t bufsiz)
+{
+    ssize_t ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* lk = NULL;
+
+    if (!_overlayfs_valid(ovl) || !pathname || !buf || !bufsiz)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, pathname, false, &lk));
+
+    if (*lk->target)
+    {
+        char suffix[PATH_MAX];
+        myst_fs_t* tfs;
+
+        ECHECK((*ovl->resolve)(lk->target, suffix, &tfs));
+        ret = (*tfs->fs_readlink)(tfs, suffix, buf, bufsiz);
+        goto done;
+    }
+
+    if (!lk->exists)
+        ERAISE_QUIET(-ENOENT);
+
+    if (!S_ISLNK(lk->st.st_mode))
+        ERAISE(-EINVAL);
+
+    ret = (*_layer(ovl, lk)->fs_readlink)(
+        _layer(ovl, lk), lk->path, buf, bufsiz);
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(lk);
+
+    return ret;
+}
+
+static int _fs_symlink(myst_fs_t* fs, const char* target, const char* linkpath)
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* lk = NULL;
+
+    if (!_overlayfs_valid(ovl) || !target || !linkpath)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, linkpath, false, &lk));
+
+    if (*lk->target)
+    {
+        char suffix[PATH_MAX];
+        myst_fs_t* tfs;
+
+        ECHECK((*ovl->resolve)(lk->target, suffix, &tfs));
+        ECHECK((*tfs->fs_symlink)(tfs, target, suffix));
+        goto done;
+    }
+
+    if (lk->exists)
+        ERAISE_QUIET(-EEXIST);
+
+    ECHECK(_prepare_create(ovl, lk->path, NULL));
+    ECHECK((*ovl->upper->fs_symlink)(ovl->upper, target, lk->path));
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(lk);
+
+    return ret;
+}
+
+static int _fs_realpath(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    char* buf,
+    size_t size)
+{
+    int ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    ret = (*file->fs->fs_realpath)(file->fs, file->file, buf, size);
+
+done:
+    return ret;
+}
+
+static int _fs_fcntl(myst_fs_t* fs, myst_file_t* file, int cmd, long arg)
+{
+    int ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    ret = (*file->fs->fs_fcntl)(file->fs, file->file, cmd, arg);
+
+done:
+    return ret;
+}
+
+static int _fs_ioctl(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    unsigned long request,
+    long arg)
+{
+    int ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EBADF);
+
+    /* the requests are those of the layer that has the file (the overlay
+     * has no table of its own) */
+    {
+        const myst_fdops_t* fdops = &file->fs->fdops;
+
+        if (fdops->fd_ioctls)
+            ECHECK(myst_ioctl_check(fdops->fd_ioctls, request, arg));
+    }
+
+    ret = (*file->fs->fs_ioctl)(file->fs, file->file, request, arg);
+
+done:
+    return ret;
+}
+
+static int _fs_dup(
+    myst_fs_t* fs,
+    const myst_file_t* file,
+    myst_file_t** file_out)
+{
+    int ret = 0;
+    myst_file_t* new_file = NULL;
+
+    if (!_valid(fs, file) || !file_out)
+        ERAISE(-EINVAL);
+
+    if (!(new_file = calloc(1, sizeof(myst_file_t))))
+        ERAISE(-ENOMEM);
+
+    *new_file = *file;
+    new_file->file = NULL;
+    new_file->ents = NULL;
+
+    if (file->nents)
+    {
+        const size_t size = file->nents * sizeof(struct dirent);
+
+        if (!(new_file->ents = malloc(size)))
+            ERAISE(-ENOMEM);
+
+        memcpy(new_file->ents, file->ents, size);
+    }
+
+    ECHECK((*file->fs->fs_dup)(file->fs, file->file, &new_file->file));
+
+    *file_out = new_file;
+    new_file = NULL;
+
+done:
+
+    if (new_file)
+        _free_file(new_file);
+
+    return ret;
+}
+
+static int _fs_target_fd(myst_fs_t* fs, myst_file_t* file)
+{
+    int ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    ret = (*file->fs->fs_target_fd)(file->fs, file->file);
+
+done:
+    return ret;
+}
+
+static int _fs_get_events(myst_fs_t* fs, myst_file_t* file)
+{
+    int ret = 0;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    ret = (*file->fs->fs_get_events)(file->fs, file->file);
+
+done:
+    return ret;
+}
+
+static int _fs_statfs(myst_fs_t* fs, const char* pathname, struct statfs* buf)
+{
+    int ret = 0;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+
+    if (!_overlayfs_valid(ovl) || !pathname || !buf)
+        ERAISE(-EINVAL);
+
+    /* as Linux, report the upper layer (where new files go) */
+    ECHECK((*ovl->upper->fs_statfs)(ovl->upper, "/", buf));
+
+done:
+    return ret;
+}
+
+static int _fs_fstatfs(myst_fs_t* fs, myst_file_t* file, struct statfs* buf)
+{
+    int ret = 0;
+
+    if (!_valid(fs, file) || !buf)
+        ERAISE(-EINVAL);
+
+    ECHECK(_fs_statfs(fs, "/", buf));
+
+done:
+    return ret;
+}
+
+static int _fs_futimens(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    const struct timespec times[2])
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* lk = NULL;
+    myst_fs_t* ufs;
+    myst_file_t* ufile;
+
+    if (!_valid(fs, file))
+        ERAISE(-EINVAL);
+
+    /* copy the file up and change that (this file stays open on the lower
+     * layer, which has the same contents) */
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, file->path, false, &lk));
+
+    if (*lk->target || !lk->exists)
+        ERAISE(-ENOENT);
+
+    ECHECK(_copy_up(ovl, lk));
+    ECHECK((*ovl->upper->fs_open)(
+        ovl->upper, lk->path, O_RDONLY, 0, &ufs, &ufile));
+    ret = (*ufs->fs_futimens)(ufs, ufile, times);
+    (*ufs->fs_close)(ufs, ufile);
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(lk);
+
+    return ret;
+}
+
+/* copy up the file at path (followed or not) for a change of its mode or
+ * owner (in the upper layer or the file system that path leads to) */
+static int _change(
+    overlayfs_t* ovl,
+    const char* path,
+    bool follow,
+    bool chown,
+    mode_t mode,
+    uid_t owner,
+    gid_t group)
+{
+    int ret = 0;
+    lookup_t* lk = NULL;
+    myst_fs_t* fs = ovl->upper;
+    const char* fspath;
+    bool fsfollow;
+    char suffix[PATH_MAX];
+
+    myst_mutex_lock(&ovl->lock);
+
+    ECHECK(_lookup(ovl, path, follow, &lk));
+
+    if (*lk->target)
+    {
+        ECHECK((*ovl->resolve)(lk->target, suffix, &fs));
+        fspath = suffix;
+        fsfollow = follow;
+    }
+    else
+    {
+        if (!lk->exists)
+            ERAISE_QUIET(-ENOENT);
+
+        /* the path within the layer has no symbolic links to follow */
+        ECHECK(_copy_up(ovl, lk));
+        fspath = lk->path;
+        fsfollow = false;
+    }
+
+    if (chown)
+    {
+        if (!fs->fs_chown)
+            ERAISE(-EPERM);
+
+        ECHECK((*fs->fs_chown)(fs, fspath, fsfollow, owner, group));
+    }
+    else
+    {
+        if (!fs->fs_chmod)
+            ERAISE(-EPERM);
+
+        ECHECK((*fs->fs_chmod)(fs, fspath, mode));
+    }
+
+done:
+
+    myst_mutex_unlock(&ovl->lock);
+    free(lk);
+
+    return ret;
+}
+
+static int _fs_chmod(myst_fs_t* fs, const char* pathname, mode_t mode)
+{
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+
+    if (!_overlayfs_valid(ovl) || !pathname)
+        return -EINVAL;
+
+    return _change(ovl, pathname, true, false, mode, 0, 0);
+}
+
+static int _fs_fchmod(myst_fs_t* fs, myst_file_t* file, mode_t mode)
+{
+    if (!_valid(fs, file))
+        return -EINVAL;
+
+    /* as with futimens(), the open file stays on its layer */
+    return _change((overlayfs_t*)fs, file->path, false, false, mode, 0, 0);
+}
+
+static int _fs_chown(
+    myst_fs_t* fs,
+    const char* pathname,
+    bool follow,
+    uid_t owner,
+    gid_t group)
+{
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+
+    if (!_overlayfs_valid(ovl) || !pathname)
+        return -EINVAL;
+
+    return _change(ovl, pathname, follow, true, 0, owner, group);
+}
+
+static int _fs_fchown(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    uid_t owner,
+    gid_t group)
+{
+    if (!_valid(fs, file))
+        return -EINVAL;
+
+    return _change((overlayfs_t*)fs, file->path, false, true, 0, owner, group);
+}
+
+static int _fs_btime(
+    myst_fs_t* fs,
+    const char* pathname,
+    bool follow,
+    struct timespec* btime)
+{
+    int ret = 0;
+    bool locked = false;
+    overlayfs_t* ovl = (overlayfs_t*)fs;
+    lookup_t* lk = NULL;
+    myst_fs_t* lfs;
+
+    if (!_overlayfs_valid(ovl) || !pathname || !btime)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&ovl->lock);
+    locked = true;
+
+    ECHECK(_lookup(ovl, pathname, follow, &lk));
+
+    if (*lk->target)
+    {
+        char suffix[PATH_MAX];
+        myst_fs_t* tfs;
+
+        ECHECK((*ovl->resolve)(lk->target, suffix, &tfs));
+
+        if (!tfs->fs_btime)
+            ERAISE_QUIET(-ENOTSUP);
+
+        ECHECK((*tfs->fs_btime)(tfs, suffix, follow, btime));
+        goto done;
+    }
+
+    if (!lk->exists)
+        ERAISE_QUIET(-ENOENT);
+
+    /* a copied-up file was created when it was copied up */
+    lfs = _layer(ovl, lk);
+
+    if (!lfs->fs_btime)
+        ERAISE_QUIET(-ENOTSUP);
+
+    ECHECK((*lfs->fs_btime)(lfs, lk->path, false, btime));
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&ovl->lock);
+
+    free(lk);
+
+    return ret;
+}
+
+int myst_init_overlayfs(
+    myst_mount_resolve_callback_t resolve_cb,
+    myst_fs_t* lower,
+    myst_fs_t* upper,
+    myst_fs_t** fs_out)
+{
+    int ret = 0;
+    overlayfs_t* ovl = NULL;
+    // clang-format off
+    static myst_fs_t _base =
+    {
+        {
+            .fd_read = (void*)_fs_read,
+            .fd_write = (void*)_fs_write,
+            .fd_readv = (void*)_fs_readv,
+            .fd_writev = (void*)_fs_writev,
+            .fd_fstat = (void*)_fs_fstat,
+            .fd_fcntl = (void*)_fs_fcntl,
+            .fd_ioctl = (void*)_fs_ioctl,
+            .fd_dup = (void*)_fs_dup,
+            .fd_close = (void*)_fs_close,
+            .fd_target_fd = (void*)_fs_target_fd,
+            .fd_get_events = (void*)_fs_get_events,
+        },
+        .fs_release = _fs_release,
+        .fs_mount = _fs_mount,
+        .fs_creat = _fs_creat,
+        .fs_open = _fs_open,
+        .fs_lseek = _fs_lseek,
+        .fs_read = _fs_read,
+        .fs_write = _fs_write,
+        .fs_pread = _fs_pread,
+        .fs_pwrite = _fs_pwrite,
+        .fs_readv = _fs_readv,
+        .fs_writev = _fs_writev,
+        .fs_close = _fs_close,
+        .fs_access = _fs_access,
+        .fs_stat = _fs_stat,
+        .fs_lstat = _fs_lstat,
+        .fs_fstat = _fs_fstat,
+        .fs_link = _fs_link,
+        .fs_unlink = _fs_unlink,
+        .fs_rename = _fs_rename,
+        .fs_truncate = _fs_truncate,
+        .fs_ftruncate = _fs_ftruncate,
+        .fs_mkdir = _fs_mkdir,
+        .fs_rmdir = _fs_rmdir,
+        .fs_getdents64 = _fs_getdents64,
+        .fs_readlink = _fs_readlink,
+        .fs_symlink = _fs_symlink,
+        .fs_realpath = _fs_realpath,
+        .fs_fcntl = _fs_fcntl,
+        .fs_ioctl = _fs_ioctl,
+        .fs_dup = _fs_dup,
+        .fs_target_fd = _fs_target_fd,
+        .fs_get_events = _fs_get_events,
+        .fs_statfs = _fs_statfs,
+        .fs_fstatfs = _fs_fstatfs,
+        .fs_futimens = _fs_futimens,
+        .fs_btime = _fs_btime,
+        .fs_chmod = _fs_chmod,
+        .fs_fchmod = _fs_fchmod,
+        .fs_chown = _fs_chown,
+        .fs_fchown = _fs_fchown,
+    };
+    // clang-format on
+
+    if (fs_out)
+        *fs_out = NULL;
+
+    if (!resolve_cb || !lower || !upper || !fs_out)
+        ERAISE(-EINVAL);
+
+    if (!(ovl = calloc(1, sizeof(overlayfs_t))))
+        ERAISE(-ENOMEM);
+
+    ovl->magic = OVERLAYFS_MAGIC;
+    ovl->base = _base;
+    ovl->resolve = resolve_cb;
+    ovl->lower = lower;
+    ovl->upper = upper;
+
+    *fs_out = &ovl->base;
+    ovl = NULL;
+
+done:
+
+    if (ovl)
+        free(ovl);
+
+    return ret;
+}
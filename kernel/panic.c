@@ -4,6 +4,7 @@
 #include <stdarg.h>
 
 #include <myst/backtrace.h>
+#include <myst/consolering.h>
 #include <myst/crash.h>
 #include <myst/panic.h>
 #include <myst/printf.h>
@@ -31,6 +32,9 @@ void __myst_panic(
 
     myst_dump_backtrace(buf, n);
 
+    /* the enclave is not coming back to write out the rest */
+    myst_console_ring_flush();
+
     myst_crash();
 
     for (;;)
@@ -0,0 +1,150 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include <myst/id.h>
+#include <myst/perms.h>
+#include <myst/spinlock.h>
+#include <myst/thread.h>
+
+/*
+**==============================================================================
+**
+** File permissions:
+**
+** These follow Linux: the owner bits of the mode apply to the owner of the
+** file, the group bits to its group and the other bits to everyone else,
+** and root (which every process is, see myst/id.h) may read and write any
+** file, and execute any that has an execute bit. Only root and the owner
+** may chmod a file, and only root may give it to another user; the owner
+** may only change its group to one of their own. A regular file that
+** changes owner loses its set-user-ID bit (and its set-group-ID bit if it
+** is group executable), so that a chown does not hand out privileges.
+**
+** The file systems check these against the mode and owner that they keep
+** for each file, so a file system that does not keep them (hostfs) leaves
+** them to the host.
+**
+**==============================================================================
+*/
+
+#define MODE_X (S_IXUSR | S_IXGRP | S_IXOTH)
+
+int myst_perms_check(mode_t mode, uid_t uid, gid_t gid, int want)
+{
+    const uid_t euid = myst_geteuid();
+    mode_t bits;
+
+    want &= (R_OK | W_OK | X_OK);
+
+    if (euid == 0)
+    {
+        /* directories are searchable by root whatever their mode */
+        if ((want & X_OK) && !S_ISDIR(mode) && !(mode & MODE_X))
+            return -EACCES;
+
+        return 0;
+    }
+
+    if (euid == uid)
+        bits = (mode >> 6);
+    else if (myst_getegid() == gid)
+        bits = (mode >> 3);
+    else
+        bits = mode;
+
+    /* R_OK, W_OK and X_OK are the bits of each class */
+    if ((bits & want) != (mode_t)want)
+        return -EACCES;
+
+    return 0;
+}
+
+int myst_perms_open_access(int flags)
+{
+    int want = 0;
+
+    if (flags & O_PATH)
+        return 0;
+
+    switch (flags & O_ACCMODE)
+    {
+        case O_RDONLY:
+            want = R_OK;
+            break;
+        case O_WRONLY:
+            want = W_OK;
+            break;
+        default:
+            want = R_OK | W_OK;
+            break;
+    }
+
+    if (flags & O_TRUNC)
+        want |= W_OK;
+
+    return want;
+}
+
+mode_t myst_perms_umask(mode_t mode)
+{
+    myst_thread_t* self;
+    myst_thread_t* process;
+    mode_t umask;
+
+    /* the kernel creates files (before there is a process) as asked */
+    if (!(self = myst_thread_self()) ||
+        !(process = myst_find_process_thread(self)))
+    {
+        return mode;
+    }
+
+    myst_spin_lock(&process->main.umask_lock);
+    umask = process->main.umask;
+    myst_spin_unlock(&process->main.umask_lock);
+
+    return mode & ~umask;
+}
+
+int myst_perms_check_chmod(uid_t uid)
+{
+    const uid_t euid = myst_geteuid();
+    return (euid == 0 || euid == uid) ? 0 : -EPERM;
+}
+
+int myst_perms_check_chown(uid_t uid, gid_t gid, uid_t owner, gid_t group)
+{
+    const uid_t euid = myst_geteuid();
+
+    if (euid == 0)
+        return 0;
+
+    if (owner != (uid_t)-1 && owner != uid)
+        return -EPERM;
+
+    if (group != (gid_t)-1 && group != gid)
+    {
+        if (euid != uid || group != myst_getegid())
+            return -EPERM;
+    }
+
+    return 0;
+}
+
+mode_t myst_perms_chown_mode(mode_t mode)
+{
+    if (S_ISDIR(mode))
+        return mode;
+
+    mode &= ~S_ISUID;
+
+    /* without group execute, set-group-ID means mandatory locking */
+    if (mode & S_IXGRP)
+        mode &= ~S_ISGID;
+
+    return mode;
+}
@@ -14,6 +14,7 @@
 #include <myst/defs.h>
 #include <myst/eraise.h>
 #include <myst/id.h>
+#include <myst/ioctl.h>
 #include <myst/panic.h>
 #include <myst/pipedev.h>
 #include <myst/process.h>
@@ -535,15 +536,13 @@ static int _pd_ioctl(
 {
     int ret = 0;
 
+    (void)request;
     (void)arg;
 
     if (!pipedev || !_valid_pipe(pipe))
         ERAISE(-EBADF);
 
-    if (request == TIOCGWINSZ)
-        ERAISE(-EINVAL);
-
-    ERAISE(-ENOTSUP);
+    ERAISE(-ENOTTY);
 
 done:
 
@@ -678,6 +677,9 @@ done:
     return ret;
 }
 
+/* no requests: each fails with ENOTTY */
+static const myst_ioctl_table_t _ioctls = MYST_IOCTL_TABLE_NONE("pipe");
+
 extern myst_pipedev_t* myst_pipedev_get(void)
 {
     // clang-format-off
@@ -690,6 +692,7 @@ extern myst_pipedev_t* myst_pipedev_get(void)
             .fd_fstat = (void*)_pd_fstat,
             .fd_fcntl = (void*)_pd_fcntl,
             .fd_ioctl = (void*)_pd_ioctl,
+            .fd_ioctls = &_ioctls,
             .fd_dup = (void*)_pd_dup,
             .fd_close = (void*)_pd_close,
             .fd_target_fd = (void*)_pd_target_fd,
@@ -6,6 +6,7 @@
 #include <stddef.h>
 #include <stdlib.h>
 
+#include <myst/deadline.h>
 #include <myst/defs.h>
 #include <myst/eraise.h>
 #include <myst/fdops.h>
@@ -56,6 +57,24 @@ done:
 }
 
 long myst_syscall_poll(struct pollfd* fds, nfds_t nfds, int timeout)
+{
+    myst_deadline_t deadline;
+
+    /* special case: if nfds is zero, SYS_myst_poll_wake ends the wait */
+    if (nfds == 0)
+    {
+        long params[6] = {(long)NULL, nfds, timeout};
+        return myst_tcall(SYS_poll, params);
+    }
+
+    myst_deadline_init_msec(&deadline, timeout);
+    return myst_poll_deadline(fds, nfds, &deadline);
+}
+
+long myst_poll_deadline(
+    struct pollfd* fds,
+    nfds_t nfds,
+    const myst_deadline_t* deadline)
 {
     long ret = 0;
     myst_fdtable_t* fdtable;
@@ -68,19 +87,12 @@ long myst_syscall_poll(struct pollfd* fds, nfds_t nfds, int timeout)
     long tevents = 0;           /* the number of target events */
     long kevents = 0;           /* the number of kernel events */
 
-    /* special case: if nfds is zero */
-    if (nfds == 0)
-    {
-        long r;
-        long params[6] = {(long)NULL, nfds, timeout};
-        ECHECK((r = myst_tcall(SYS_poll, params)));
-        ret = r;
-        goto done;
-    }
-
     if (!fds && nfds)
         ERAISE(-EFAULT);
 
+    if (!deadline)
+        ERAISE(-EINVAL);
+
     if (!(fdtable = myst_fdtable_current()))
         ERAISE(-ENOSYS);
 
@@ -129,29 +141,42 @@ long myst_syscall_poll(struct pollfd* fds, nfds_t nfds, int timeout)
         }
     }
 
-    /* pre-poll for kernel events */
+    /* The target poll also ends whenever a kernel device (such as a pipe)
+     * changes state, which may concern other threads: poll again for what
+     * is left of the timeout until there are events */
+    for (;;)
     {
-        ECHECK((kevents = _poll_kernel(kfds, knfds)));
+        int timeout;
 
-        /* if any kernel events were found, change timeout to zero */
-        if (kevents)
-            timeout = 0;
-    }
+        /* pre-poll for kernel events */
+        {
+            ECHECK((kevents = _poll_kernel(kfds, knfds)));
 
-    /* poll for target events */
-    if (tnfds && tfds)
-    {
-        ECHECK((tevents = myst_tcall_poll(tfds, tnfds, timeout)));
-    }
-    else
-    {
-        ECHECK((tevents = myst_tcall_poll(NULL, tnfds, timeout)));
-    }
+            /* if any kernel events were found, change timeout to zero */
+            if (kevents)
+                timeout = 0;
+            else
+                timeout = myst_deadline_remaining_msec(deadline);
+        }
 
-    /* post-poll for kernel events (avoid if already polled above) */
-    if (kevents == 0)
-    {
-        ECHECK((kevents = _poll_kernel(kfds, knfds)));
+        /* poll for target events */
+        if (tnfds && tfds)
+        {
+            ECHECK((tevents = myst_tcall_poll(tfds, tnfds, timeout)));
+        }
+        else
+        {
+            ECHECK((tevents = myst_tcall_poll(NULL, tnfds, timeout)));
+        }
+
+        /* post-poll for kernel events (avoid if already polled above) */
+        if (kevents == 0)
+        {
+            ECHECK((kevents = _poll_kernel(kfds, knfds)));
+        }
+
+        if (tevents || kevents || myst_deadline_expired(deadline))
+            break;
     }
 
     /* update fds[] with the target events */
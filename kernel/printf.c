@@ -4,6 +4,7 @@
 #include <stdlib.h>
 #include <string.h>
 
+#include <myst/console.h>
 #include <myst/crash.h>
 #include <myst/eraise.h>
 #include <myst/panic.h>
@@ -24,7 +25,7 @@ int myst_console_printf(int fd, const char* format, ...)
     if (count < 0 || (size_t)count >= sizeof(buf))
         return -EINVAL;
 
-    return (int)myst_tcall_write_console(fd, buf, (size_t)count);
+    return (int)myst_console_write_host(fd, buf, (size_t)count);
 }
 
 int myst_console_vprintf(int fd, const char* format, va_list ap)
@@ -37,7 +38,7 @@ int myst_console_vprintf(int fd, const char* format, va_list ap)
     if (count < 0 || (size_t)count >= sizeof(buf))
         return -EINVAL;
 
-    return (int)myst_tcall_write_console(fd, buf, (size_t)count);
+    return (int)myst_console_write_host(fd, buf, (size_t)count);
 }
 
 int myst_veprintf(const char* format, va_list ap)
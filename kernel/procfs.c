@@ -3,15 +3,21 @@
 #include <string.h>
 #include <sys/stat.h>
 
+#include <myst/audit.h>
 #include <myst/eraise.h>
 #include <myst/file.h>
 #include <myst/fs.h>
 #include <myst/kernel.h>
+#include <myst/metrics.h>
 #include <myst/mmanutils.h>
 #include <myst/mount.h>
+#include <myst/oom.h>
 #include <myst/printf.h>
 #include <myst/process.h>
 #include <myst/procfs.h>
+#include <myst/rusage.h>
+#include <myst/strings.h>
+#include <myst/thread.h>
 
 static myst_fs_t* _procfs;
 
@@ -37,16 +43,96 @@ int procfs_setup()
         ERAISE(-EINVAL);
     }
 
-    /* Create /proc/[pid]/fd directory for main thread */
-    char fdpath[PATH_MAX];
-    const size_t n = sizeof(fdpath);
-    snprintf(fdpath, n, "/proc/%d/fd", myst_getpid());
-    if (myst_mkdirhier(fdpath, 777) != 0)
+    /* Create /proc/[pid] entries for main thread */
+    ECHECK(procfs_pid_setup(myst_getpid()));
+
+done:
+    return ret;
+}
+
+static int _comm_vcallback(myst_buf_t* vbuf, void* context)
+{
+    const pid_t pid = (pid_t)(intptr_t)context;
+    myst_thread_t* process;
+    char name[sizeof(process->name) + 1];
+
+    *name = '\0';
+
+    myst_spin_lock(&myst_process_list_lock);
+    if ((process = myst_find_process_by_pid(pid)))
+        MYST_STRLCPY(name, myst_get_thread_name(process));
+    myst_spin_unlock(&myst_process_list_lock);
+
+    MYST_STRLCAT(name, "\n");
+    myst_buf_clear(vbuf);
+    myst_buf_append(vbuf, name, strlen(name));
+    return 0;
+}
+
+static int _stat_vcallback(myst_buf_t* vbuf, void* context)
+{
+    int ret = 0;
+    const pid_t pid = (pid_t)(intptr_t)context;
+    myst_thread_t* process;
+
+    myst_buf_clear(vbuf);
+
+    myst_spin_lock(&myst_process_list_lock);
+    if ((process = myst_find_process_by_pid(pid)))
+        ret = myst_rusage_format_stat(process, vbuf);
+    myst_spin_unlock(&myst_process_list_lock);
+
+    return ret;
+}
+
+static int _status_vcallback(myst_buf_t* vbuf, void* context)
+{
+    int ret = 0;
+    const pid_t pid = (pid_t)(intptr_t)context;
+    myst_thread_t* process;
+
+    myst_buf_clear(vbuf);
+
+    myst_spin_lock(&myst_process_list_lock);
+    if ((process = myst_find_process_by_pid(pid)))
+        ret = myst_rusage_format_status(process, vbuf);
+    myst_spin_unlock(&myst_process_list_lock);
+
+    return ret;
+}
+
+int procfs_pid_setup(pid_t pid)
+{
+    int ret = 0;
+    char path[PATH_MAX];
+    const size_t n = sizeof(path);
+
+    if (!pid)
+        ERAISE(-EINVAL);
+
+    /* Create /proc/[pid]/fd directory */
+    snprintf(path, n, "/proc/%d/fd", pid);
+    if (myst_mkdirhier(path, 777) != 0)
     {
         myst_eprintf("cannot create the /proc/[pid]/fd directory\n");
         ERAISE(-EINVAL);
     }
 
+    /* Create /proc/[pid]/comm */
+    snprintf(path, n, "/%d/comm", pid);
+    ECHECK(myst_create_virtual_file(
+        _procfs, path, S_IFREG, _comm_vcallback, (void*)(intptr_t)pid));
+
+    /* Create /proc/[pid]/stat */
+    snprintf(path, n, "/%d/stat", pid);
+    ECHECK(myst_create_virtual_file(
+        _procfs, path, S_IFREG, _stat_vcallback, (void*)(intptr_t)pid));
+
+    /* Create /proc/[pid]/status */
+    snprintf(path, n, "/%d/status", pid);
+    ECHECK(myst_create_virtual_file(
+        _procfs, path, S_IFREG, _status_vcallback, (void*)(intptr_t)pid));
+
 done:
     return ret;
 }
@@ -77,7 +163,7 @@ done:
     return ret;
 }
 
-static int _meminfo_vcallback(myst_buf_t* vbuf)
+static int _meminfo_vcallback(myst_buf_t* vbuf, MYST_UNUSED void* context)
 {
     int ret = 0;
     size_t totalram;
@@ -93,12 +179,16 @@ static int _meminfo_vcallback(myst_buf_t* vbuf)
     myst_buf_append(vbuf, tmp, strlen(tmp));
     snprintf(tmp, n, "MemFree:        %lu\n", freeram);
     myst_buf_append(vbuf, tmp, strlen(tmp));
+    snprintf(tmp, n, "MemLowWatermark: %lu\n", myst_oom_low_watermark());
+    myst_buf_append(vbuf, tmp, strlen(tmp));
+    snprintf(tmp, n, "OomKills:       %lu\n", myst_oom_kills());
+    myst_buf_append(vbuf, tmp, strlen(tmp));
 
 done:
     return ret;
 }
 
-static int _self_vcallback(myst_buf_t* vbuf)
+static int _self_vcallback(myst_buf_t* vbuf, MYST_UNUSED void* context)
 {
     char linkpath[PATH_MAX];
     const size_t n = sizeof(linkpath);
@@ -108,15 +198,44 @@ static int _self_vcallback(myst_buf_t* vbuf)
     return 0;
 }
 
+static int _audit_vcallback(myst_buf_t* vbuf, MYST_UNUSED void* context)
+{
+    return myst_audit_format(vbuf);
+}
+
+static int _metrics_vcallback(myst_buf_t* vbuf, MYST_UNUSED void* context)
+{
+    return myst_metrics_format(vbuf);
+}
+
 int create_proc_root_entries()
 {
     int ret;
 
     /* Create /proc/meminfo */
-    ECHECK(myst_create_virtual_file(_procfs, "/meminfo", S_IFREG, _meminfo_vcallback));
+    ECHECK(myst_create_virtual_file(
+        _procfs, "/meminfo", S_IFREG, _meminfo_vcallback, NULL));
 
     /* Create /proc/self */
-    ECHECK(myst_create_virtual_file(_procfs, "/self", S_IFLNK, _self_vcallback));
+    ECHECK(myst_create_virtual_file(
+        _procfs, "/self", S_IFLNK, _self_vcallback, NULL));
+
+    if (myst_mkdirhier("/proc/myst", 777) != 0)
+    {
+        myst_eprintf("cannot create the /proc/myst directory\n");
+        ERAISE(-EINVAL);
+    }
+
+    /* Create /proc/myst/metrics */
+    ECHECK(myst_create_virtual_file(
+        _procfs, "/myst/metrics", S_IFREG, _metrics_vcallback, NULL));
+
+    /* Create /proc/myst/audit */
+    if (myst_audit_enabled())
+    {
+        ECHECK(myst_create_virtual_file(
+            _procfs, "/myst/audit", S_IFREG, _audit_vcallback, NULL));
+    }
 
 done:
     return ret;
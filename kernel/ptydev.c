@@ -0,0 +1,1071 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <signal.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/ioctl.h>
+#include <sys/sysmacros.h>
+#include <sys/ttydefaults.h>
+#include <termios.h>
+#include <unistd.h>
+
+#include <myst/cond.h>
+#include <myst/eraise.h>
+#include <myst/id.h>
+#include <myst/ioctl.h>
+#include <myst/process.h>
+#include <myst/ptydev.h>
+#include <myst/syscall.h>
+#include <myst/tcall.h>
+
+/*
+**==============================================================================
+**
+** Pseudo-terminals:
+**
+** Opening /dev/ptmx creates a pair and returns its master; once unlocked
+** (TIOCSPTLCK), /dev/pts/<n> opens its slave, where n is given by TIOCGPTN.
+** What the master writes goes through the line discipline (input mapping,
+** terminal signals, echo and, in canonical mode, line editing) before the
+** slave can read it, and what the slave writes goes through output
+** processing before the master can read it. The terminal signals and
+** SIGWINCH go to the process that opened the slave without O_NOCTTY (or the
+** one given by TIOCSCTTY or TIOCSPGRP), as there are no process groups.
+**
+**==============================================================================
+*/
+
+#define MAGIC 0x7e1e7a9d
+
+#define BUFFER_SIZE 4096
+
+#define MASTER_MAJOR 5
+#define MASTER_MINOR 2
+#define SLAVE_MAJOR 136
+
+/* the termios structure of the ioctl interface (not that of libc) */
+#define KERNEL_NCCS 19
+
+struct kernel_termios
+{
+    tcflag_t c_iflag;
+    tcflag_t c_oflag;
+    tcflag_t c_cflag;
+    tcflag_t c_lflag;
+    cc_t c_line;
+    cc_t c_cc[KERNEL_NCCS];
+};
+
+typedef struct buffer
+{
+    char data[BUFFER_SIZE];
+    size_t size;
+} buffer_t;
+
+typedef struct pty_impl
+{
+    myst_cond_t cond;
+    myst_mutex_t mutex;
+    int index;
+    bool locked;       /* the slave cannot be opened until unlocked */
+    size_t nmasters;   /* the open master descriptions */
+    size_t nslaves;    /* the open slave descriptions */
+    bool slave_opened; /* whether the slave has ever been opened */
+    pid_t pgrp;        /* the process that gets the terminal signals */
+    struct kernel_termios termios;
+    struct winsize winsize;
+    bool eof;        /* the slave reads end-of-file next */
+    buffer_t input;  /* for the slave to read */
+    buffer_t output; /* for the master to read */
+    buffer_t line;   /* the line being edited in canonical mode */
+} pty_impl_t;
+
+struct myst_pty
+{
+    uint32_t magic;
+    bool master;
+    int flags;   /* O_NONBLOCK */
+    int fdflags; /* FD_CLOEXEC */
+    pty_impl_t* impl;
+};
+
+static pty_impl_t* _ptys[MYST_PTY_MAX];
+
+/* taken before the mutex of a pair */
+static myst_mutex_t _ptys_mutex;
+
+MYST_INLINE bool _valid_pty(const myst_pty_t* pty)
+{
+    return pty && pty->magic == MAGIC && pty->impl;
+}
+
+static void _lock(pty_impl_t* p)
+{
+    myst_mutex_lock(&p->mutex);
+}
+
+static void _unlock(pty_impl_t* p)
+{
+    myst_mutex_unlock(&p->mutex);
+}
+
+/* wake the readers and writers of both sides, and any poll() */
+static void _wake(pty_impl_t* p)
+{
+    myst_cond_broadcast(&p->cond, SIZE_MAX);
+    myst_tcall_poll_wake();
+}
+
+static void _init_termios(struct kernel_termios* t)
+{
+    static const cc_t cc[KERNEL_NCCS] = {
+        [VINTR] = CTRL('c'),
+        [VQUIT] = CTRL('\\'),
+        [VERASE] = 0177,
+        [VKILL] = CTRL('u'),
+        [VEOF] = CTRL('d'),
+        [VMIN] = 1,
+        [VSTART] = CTRL('q'),
+        [VSTOP] = CTRL('s'),
+        [VSUSP] = CTRL('z'),
+        [VREPRINT] = CTRL('r'),
+        [VDISCARD] = CTRL('o'),
+        [VWERASE] = CTRL('w'),
+        [VLNEXT] = CTRL('v'),
+    };
+
+    memset(t, 0, sizeof(struct kernel_termios));
+    t->c_iflag = ICRNL | IXON;
+    t->c_oflag = OPOST | ONLCR;
+    t->c_cflag = B38400 | CS8 | CREAD | HUPCL;
+    t->c_lflag = ISIG | ICANON | ECHO | ECHOE | ECHOK | ECHOCTL | ECHOKE |
+                 IEXTEN;
+    memcpy(t->c_cc, cc, sizeof(cc));
+}
+
+static bool _put(buffer_t* b, char c)
+{
+    if (b->size == BUFFER_SIZE)
+        return false;
+
+    b->data[b->size++] = c;
+    return true;
+}
+
+static size_t _take(buffer_t* b, void* buf, size_t count)
+{
+    const size_t n = (count < b->size) ? count : b->size;
+
+    memcpy(buf, b->data, n);
+    memmove(b->data, b->data + n, b->size - n);
+    b->size -= n;
+    return n;
+}
+
+/* output processing: returns false if there is no room */
+static bool _output(pty_impl_t* p, char c)
+{
+    const tcflag_t oflag = p->termios.c_oflag;
+
+    if ((oflag & OPOST) && (oflag & ONLCR) && c == '\n')
+    {
+        if (BUFFER_SIZE - p->output.size < 2)
+            return false;
+
+        _put(&p->output, '\r');
+    }
+
+    return _put(&p->output, c);
+}
+
+static void _echo(pty_impl_t* p, char c)
+{
+    const tcflag_t lflag = p->termios.c_lflag;
+
+    if (!(lflag & ECHO))
+        return;
+
+    /* show control characters as ^X */
+    if ((lflag & ECHOCTL) && ((unsigned char)c < ' ' || c == 0177) &&
+        c != '\n' && c != '\t')
+    {
+        _output(p, '^');
+        _output(p, c ^ 0100);
+        return;
+    }
+
+    _output(p, c);
+}
+
+static void _echo_erase(pty_impl_t* p)
+{
+    if ((p->termios.c_lflag & (ECHO | ECHOE)) == (ECHO | ECHOE))
+    {
+        _output(p, '\b');
+        _output(p, ' ');
+        _output(p, '\b');
+    }
+}
+
+/* move the edited line to the input for the slave */
+static void _commit_line(pty_impl_t* p)
+{
+    for (size_t i = 0; i < p->line.size; i++)
+        _put(&p->input, p->line.data[i]);
+
+    p->line.size = 0;
+}
+
+/* the line discipline for one character written by the master: returns the
+ * signal to send, if any */
+static int _input(pty_impl_t* p, char c)
+{
+    const struct kernel_termios* t = &p->termios;
+    const cc_t* cc = t->c_cc;
+
+    if (c == '\r')
+    {
+        if (t->c_iflag & IGNCR)
+            return 0;
+
+        if (t->c_iflag & ICRNL)
+            c = '\n';
+    }
+    else if (c == '\n' && (t->c_iflag & INLCR))
+    {
+        c = '\r';
+    }
+
+    if (t->c_lflag & ISIG)
+    {
+        int sig = 0;
+
+        if (cc[VINTR] && c == (char)cc[VINTR])
+            sig = SIGINT;
+        else if (cc[VQUIT] && c == (char)cc[VQUIT])
+            sig = SIGQUIT;
+        else if (cc[VSUSP] && c == (char)cc[VSUSP])
+            sig = SIGTSTP;
+
+        if (sig)
+        {
+            if (!(t->c_lflag & NOFLSH))
+            {
+                p->line.size = 0;
+                p->input.size = 0;
+            }
+
+            _echo(p, c);
+            return sig;
+        }
+    }
+
+    if (!(t->c_lflag & ICANON))
+    {
+        _put(&p->input, c);
+        _echo(p, c);
+        return 0;
+    }
+
+    if (cc[VERASE] && c == (char)cc[VERASE])
+    {
+        if (p->line.size)
+        {
+            p->line.size--;
+            _echo_erase(p);
+        }
+    }
+    else if (cc[VKILL] && c == (char)cc[VKILL])
+    {
+        while (p->line.size)
+        {
+            p->line.size--;
+            _echo_erase(p);
+        }
+    }
+    else if (cc[VEOF] && c == (char)cc[VEOF])
+    {
+        /* end-of-file at the start of a line; otherwise ends the line */
+        if (p->line.size == 0 && p->input.size == 0)
+            p->eof = true;
+
+        _commit_line(p);
+    }
+    else if (c == '\n' || (cc[VEOL] && c == (char)cc[VEOL]))
+    {
+        _put(&p->line, c);
+        _echo(p, c);
+        _commit_line(p);
+    }
+    else if (p->line.size < BUFFER_SIZE - 1)
+    {
+        /* leave room for the newline */
+        _put(&p->line, c);
+        _echo(p, c);
+    }
+
+    return 0;
+}
+
+static int _parse_index(const char* path)
+{
+    const char* p = path + sizeof("/dev/pts/") - 1;
+    int index = 0;
+
+    if (*p == '\0')
+        return -1;
+
+    for (; *p; p++)
+    {
+        if (*p < '0' || *p > '9' || index >= MYST_PTY_MAX)
+            return -1;
+
+        index = index * 10 + (*p - '0');
+    }
+
+    return (index < MYST_PTY_MAX) ? index : -1;
+}
+
+bool myst_is_pty_path(const char* path)
+{
+    const char pts[] = "/dev/pts/";
+
+    return path && (strcmp(path, "/dev/ptmx") == 0 ||
+                    strncmp(path, pts, sizeof(pts) - 1) == 0);
+}
+
+static int _pd_open(
+    myst_ptydev_t* ptydev,
+    const char* path,
+    int flags,
+    myst_pty_t** pty_out)
+{
+    int ret = 0;
+    myst_pty_t* pty = NULL;
+    pty_impl_t* impl = NULL;
+
+    if (pty_out)
+        *pty_out = NULL;
+
+    if (!ptydev || !myst_is_pty_path(path) || !pty_out)
+        ERAISE(-EINVAL);
+
+    if (!(pty = calloc(1, sizeof(myst_pty_t))))
+        ERAISE(-ENOMEM);
+
+    pty->magic = MAGIC;
+    pty->flags = flags & O_NONBLOCK;
+
+    if (flags & O_CLOEXEC)
+        pty->fdflags = FD_CLOEXEC;
+
+    if (strcmp(path, "/dev/ptmx") == 0)
+    {
+        if (!(impl = calloc(1, sizeof(pty_impl_t))))
+            ERAISE(-ENOMEM);
+
+        impl->locked = true;
+        impl->nmasters = 1;
+        _init_termios(&impl->termios);
+
+        myst_mutex_lock(&_ptys_mutex);
+        {
+            for (int i = 0; i < MYST_PTY_MAX; i++)
+            {
+                if (!_ptys[i])
+                {
+                    impl->index = i;
+                    _ptys[i] = impl;
+                    pty->impl = impl;
+                    break;
+                }
+            }
+        }
+        myst_mutex_unlock(&_ptys_mutex);
+
+        if (!pty->impl)
+            ERAISE(-ENOSPC);
+
+        impl = NULL;
+        pty->master = true;
+    }
+    else
+    {
+        int index;
+
+        if ((index = _parse_index(path)) < 0)
+            ERAISE(-ENOENT);
+
+        myst_mutex_lock(&_ptys_mutex);
+        {
+            pty_impl_t* p = _ptys[index];
+
+            if (!p)
+                ret = -ENOENT;
+            else if (p->locked || p->nmasters == 0)
+                ret = -EIO;
+            else
+            {
+                _lock(p);
+                p->nslaves++;
+                p->slave_opened = true;
+
+                if (!(flags & O_NOCTTY) && !p->pgrp)
+                    p->pgrp = myst_getpid();
+
+                _unlock(p);
+                pty->impl = p;
+            }
+        }
+        myst_mutex_unlock(&_ptys_mutex);
+
+        ECHECK(ret);
+    }
+
+    *pty_out = pty;
+    pty = NULL;
+
+done:
+
+    if (pty)
+        free(pty);
+
+    if (impl)
+        free(impl);
+
+    return ret;
+}
+
+static ssize_t _pd_read(
+    myst_ptydev_t* ptydev,
+    myst_pty_t* pty,
+    void* buf,
+    size_t count)
+{
+    ssize_t ret = 0;
+    pty_impl_t* p;
+    bool locked = false;
+
+    if (!ptydev || !_valid_pty(pty))
+        ERAISE(-EBADF);
+
+    if (!buf && count)
+        ERAISE(-EINVAL);
+
+    if (count == 0)
+        goto done;
+
+    p = pty->impl;
+    _lock(p);
+    locked = true;
+
+    for (;;)
+    {
+        if (pty->master)
+        {
+            if (p->output.size)
+            {
+                ret = _take(&p->output, buf, count);
+                break;
+            }
+
+            /* the slave was closed */
+            if (p->slave_opened && p->nslaves == 0)
+                ERAISE(-EIO);
+        }
+        else if (p->termios.c_lflag & ICANON)
+        {
+            if (p->input.size)
+            {
+                size_t n = 0;
+
+                /* one line at most */
+                while (n < p->input.size && n < count)
+                {
+                    if (p->input.data[n++] == '\n')
+                        break;
+                }
+
+                ret = _take(&p->input, buf, n);
+                break;
+            }
+
+            if (p->eof || p->nmasters == 0)
+            {
+                p->eof = false;
+                break;
+            }
+        }
+        else
+        {
+            const size_t vmin = p->termios.c_cc[VMIN];
+            const size_t need = (vmin < count) ? vmin : count;
+
+            if (p->input.size >= need || p->nmasters == 0)
+            {
+                ret = _take(&p->input, buf, count);
+                break;
+            }
+        }
+
+        if (pty->flags & O_NONBLOCK)
+            ERAISE(-EAGAIN);
+
+        /* wait here for the other side to write */
+        if (myst_cond_wait(&p->cond, &p->mutex) != 0)
+            ERAISE(-EIO);
+    }
+
+    /* there may be room for a blocked writer */
+    _wake(p);
+
+done:
+
+    if (locked)
+        _unlock(pty->impl);
+
+    return ret;
+}
+
+static ssize_t _pd_write(
+    myst_ptydev_t* ptydev,
+    myst_pty_t* pty,
+    const void* buf,
+    size_t count)
+{
+    ssize_t ret = 0;
+    pty_impl_t* p;
+    const char* ptr = buf;
+    size_t n = 0;
+    int sig = 0;
+    pid_t pgrp;
+
+    if (!ptydev || !_valid_pty(pty))
+        ERAISE(-EBADF);
+
+    if (!buf && count)
+        ERAISE(-EINVAL);
+
+    if (count == 0)
+        goto done;
+
+    p = pty->impl;
+    _lock(p);
+
+    if (pty->master)
+    {
+        /* input that does not fit is dropped, as on Linux */
+        for (; n < count; n++)
+        {
+            int r;
+
+            if ((r = _input(p, ptr[n])))
+                sig = r;
+        }
+    }
+    else
+    {
+        while (n < count)
+        {
+            if (p->nmasters == 0)
+            {
+                _unlock(p);
+                ERAISE(-EIO);
+            }
+
+            if (_output(p, ptr[n]))
+            {
+                n++;
+                continue;
+            }
+
+            /* the output is full: return what was written so far */
+            if (n || (pty->flags & O_NONBLOCK))
+                break;
+
+            _wake(p);
+
+            if (myst_cond_wait(&p->cond, &p->mutex) != 0)
+            {
+                _unlock(p);
+                ERAISE(-EIO);
+            }
+        }
+
+        if (n == 0)
+        {
+            _unlock(p);
+            ERAISE(-EAGAIN);
+        }
+    }
+
+    pgrp = p->pgrp;
+    _wake(p);
+    _unlock(p);
+
+    /* after unlocking, as the signal may go to this very process */
+    if (sig && pgrp)
+        myst_syscall_kill(pgrp, sig);
+
+    ret = n;
+
+done:
+    return ret;
+}
+
+static ssize_t _pd_readv(
+    myst_ptydev_t* ptydev,
+    myst_pty_t* pty,
+    const struct iovec* iov,
+    int iovcnt)
+{
+    ssize_t ret = 0;
+
+    if (!ptydev || !_valid_pty(pty))
+        ERAISE(-EINVAL);
+
+    ret = myst_fdops_readv(&ptydev->fdops, pty, iov, iovcnt);
+    ECHECK(ret);
+
+done:
+
+    return ret;
+}
+
+static ssize_t _pd_writev(
+    myst_ptydev_t* ptydev,
+    myst_pty_t* pty,
+    const struct iovec* iov,
+    int iovcnt)
+{
+    ssize_t ret = 0;
+
+    if (!ptydev || !_valid_pty(pty))
+        ERAISE(-EINVAL);
+
+    ret = myst_fdops_writev(&ptydev->fdops, pty, iov, iovcnt);
+    ECHECK(ret);
+
+done:
+
+    return ret;
+}
+
+static int _pd_fstat(
+    myst_ptydev_t* ptydev,
+    myst_pty_t* pty,
+    struct stat* statbuf)
+{
+    int ret = 0;
+    struct stat buf;
+
+    if (!ptydev || !_valid_pty(pty) || !statbuf)
+        ERAISE(-EINVAL);
+
+    memset(&buf, 0, sizeof(buf));
+    buf.st_dev = 22; /* TTY device */
+    buf.st_ino = (ino_t)pty->impl;
+    buf.st_mode = S_IFCHR | S_IRUSR | S_IWUSR;
+    buf.st_nlink = 1;
+    buf.st_uid = MYST_DEFAULT_UID;
+    buf.st_gid = MYST_DEFAULT_GID;
+
+    if (pty->master)
+        buf.st_rdev = makedev(MASTER_MAJOR, MASTER_MINOR);
+    else
+        buf.st_rdev = makedev(SLAVE_MAJOR, pty->impl->index);
+
+    buf.st_blksize = 1024;
+
+    *statbuf = buf;
+
+done:
+    return ret;
+}
+
+static int _pd_fcntl(myst_ptydev_t* ptydev, myst_pty_t* pty, int cmd, long arg)
+{
+    int ret = 0;
+
+    if (!ptydev || !_valid_pty(pty))
+        ERAISE(-EINVAL);
+
+    switch (cmd)
+    {
+        case F_SETFD:
+        {
+            if (arg != FD_CLOEXEC && arg != 0)
+                ERAISE(-EINVAL);
+
+            pty->fdflags = arg;
+            goto done;
+        }
+        case F_GETFD:
+        {
+            ret = pty->fdflags;
+            goto done;
+        }
+        case F_SETFL:
+        {
+            pty->flags = arg & O_NONBLOCK;
+            goto done;
+        }
+        case F_GETFL:
+        {
+            ret = O_RDWR | pty->flags;
+            goto done;
+        }
+        default:
+        {
+            ERAISE(-ENOTSUP);
+        }
+    }
+
+done:
+    return ret;
+}
+
+static int _pd_ioctl(
+    myst_ptydev_t* ptydev,
+    myst_pty_t* pty,
+    unsigned long request,
+    long arg)
+{
+    int ret = 0;
+    pty_impl_t* p;
+    int sig = 0;
+    pid_t pgrp;
+
+    if (!ptydev || !_valid_pty(pty))
+        ERAISE(-EBADF);
+
+    p = pty->impl;
+    _lock(p);
+
+    switch (request)
+    {
+        case TCGETS:
+        {
+            *(struct kernel_termios*)arg = p->termios;
+            break;
+        }
+        case TCSETS:
+        case TCSETSW:
+        case TCSETSF:
+        {
+            const tcflag_t lflag = p->termios.c_lflag;
+
+            p->termios = *(const struct kernel_termios*)arg;
+
+            if (request == TCSETSF)
+            {
+                p->input.size = 0;
+                p->line.size = 0;
+            }
+
+            /* leaving canonical mode makes the edited line readable */
+            if ((lflag & ICANON) && !(p->termios.c_lflag & ICANON))
+                _commit_line(p);
+
+            break;
+        }
+        case TIOCGWINSZ:
+        {
+            *(struct winsize*)arg = p->winsize;
+            break;
+        }
+        case TIOCSWINSZ:
+        {
+            const struct winsize* ws = (const struct winsize*)arg;
+
+            if (memcmp(ws, &p->winsize, sizeof(struct winsize)) != 0)
+            {
+                p->winsize = *ws;
+                sig = SIGWINCH;
+            }
+
+            break;
+        }
+        case TIOCGPTN:
+        {
+            if (!pty->master)
+                ret = -ENOTTY;
+            else
+                *(unsigned int*)arg = (unsigned int)p->index;
+            break;
+        }
+        case TIOCSPTLCK:
+        {
+            if (!pty->master)
+                ret = -ENOTTY;
+            else
+                p->locked = (*(const int*)arg != 0);
+            break;
+        }
+        case TIOCGPTLCK:
+        {
+            if (!pty->master)
+                ret = -ENOTTY;
+            else
+                *(int*)arg = p->locked;
+            break;
+        }
+        case TIOCSCTTY:
+        {
+            p->pgrp = myst_getpid();
+            break;
+        }
+        case TIOCNOTTY:
+        {
+            if (p->pgrp == myst_getpid())
+                p->pgrp = 0;
+            break;
+        }
+        case TIOCGPGRP:
+        {
+            *(pid_t*)arg = p->pgrp;
+            break;
+        }
+        case TIOCSPGRP:
+        {
+            p->pgrp = *(const pid_t*)arg;
+            break;
+        }
+        case FIONREAD:
+        {
+            if (pty->master)
+                *(int*)arg = (int)p->output.size;
+            else
+                *(int*)arg = (int)p->input.size;
+            break;
+        }
+        case TCFLSH:
+        {
+            if (arg == TCIFLUSH || arg == TCIOFLUSH)
+            {
+                p->input.size = 0;
+                p->line.size = 0;
+            }
+
+            if (arg == TCOFLUSH || arg == TCIOFLUSH)
+                p->output.size = 0;
+
+            break;
+        }
+        default:
+        {
+            ret = -ENOTTY;
+            break;
+        }
+    }
+
+    pgrp = p->pgrp;
+    _wake(p);
+    _unlock(p);
+
+    if (sig && pgrp)
+        myst_syscall_kill(pgrp, sig);
+
+done:
+
+    return ret;
+}
+
+static int _pd_dup(
+    myst_ptydev_t* ptydev,
+    const myst_pty_t* pty,
+    myst_pty_t** pty_out)
+{
+    int ret = 0;
+    myst_pty_t* new_pty = NULL;
+
+    if (pty_out)
+        *pty_out = NULL;
+
+    if (!ptydev || !_valid_pty(pty) || !pty_out)
+        ERAISE(-EINVAL);
+
+    if (!(new_pty = calloc(1, sizeof(myst_pty_t))))
+        ERAISE(-ENOMEM);
+
+    *new_pty = *pty;
+
+    /* file descriptor flags are not propagated */
+    new_pty->fdflags = 0;
+
+    _lock(new_pty->impl);
+
+    if (new_pty->master)
+        new_pty->impl->nmasters++;
+    else
+        new_pty->impl->nslaves++;
+
+    _unlock(new_pty->impl);
+
+    *pty_out = new_pty;
+    new_pty = NULL;
+
+done:
+
+    if (new_pty)
+        free(new_pty);
+
+    return ret;
+}
+
+static int _pd_close(myst_ptydev_t* ptydev, myst_pty_t* pty)
+{
+    int ret = 0;
+    pty_impl_t* p;
+    bool release;
+    pid_t hangup = 0;
+
+    if (!ptydev || !_valid_pty(pty))
+        ERAISE(-EBADF);
+
+    p = pty->impl;
+
+    /* slaves are opened under this mutex too */
+    myst_mutex_lock(&_ptys_mutex);
+    _lock(p);
+    {
+        if (pty->master)
+        {
+            /* the last master hangs up the terminal */
+            if (--p->nmasters == 0 && p->nslaves)
+                hangup = p->pgrp;
+        }
+        else
+        {
+            p->nslaves--;
+        }
+
+        if ((release = (p->nmasters == 0 && p->nslaves == 0)))
+            _ptys[p->index] = NULL;
+
+        _wake(p);
+    }
+    _unlock(p);
+    myst_mutex_unlock(&_ptys_mutex);
+
+    if (hangup)
+        myst_syscall_kill(hangup, SIGHUP);
+
+    if (release)
+    {
+        memset(p, 0, sizeof(pty_impl_t));
+        free(p);
+    }
+
+    memset(pty, 0, sizeof(myst_pty_t));
+    free(pty);
+
+done:
+
+    return ret;
+}
+
+static int _pd_target_fd(myst_ptydev_t* ptydev, myst_pty_t* pty)
+{
+    int ret = 0;
+
+    if (!ptydev || !_valid_pty(pty))
+        ERAISE(-EINVAL);
+
+    ret = -ENOTSUP;
+
+done:
+    return ret;
+}
+
+static int _pd_get_events(myst_ptydev_t* ptydev, myst_pty_t* pty)
+{
+    int ret = 0;
+    int events = 0;
+    pty_impl_t* p;
+
+    if (!ptydev || !_valid_pty(pty))
+        ERAISE(-EINVAL);
+
+    p = pty->impl;
+    _lock(p);
+    {
+        if (pty->master)
+        {
+            if (p->output.size)
+                events |= POLLIN;
+
+            if (p->slave_opened && p->nslaves == 0)
+                events |= POLLHUP;
+            else
+                events |= POLLOUT;
+        }
+        else
+        {
+            if (p->input.size || p->eof)
+                events |= POLLIN;
+
+            if (p->nmasters == 0)
+                events |= POLLHUP;
+            else if (BUFFER_SIZE - p->output.size >= 2)
+                events |= POLLOUT;
+        }
+    }
+    _unlock(p);
+
+    ret = events;
+
+done:
+    return ret;
+}
+
+static const myst_ioctl_t _ioctl_list[] = {
+    MYST_IOCTL(TCGETS, MYST_IOCTL_OUT, struct kernel_termios),
+    MYST_IOCTL(TCSETS, MYST_IOCTL_IN, struct kernel_termios),
+    MYST_IOCTL(TCSETSW, MYST_IOCTL_IN, struct kernel_termios),
+    MYST_IOCTL(TCSETSF, MYST_IOCTL_IN, struct kernel_termios),
+    MYST_IOCTL(TIOCGWINSZ, MYST_IOCTL_OUT, struct winsize),
+    MYST_IOCTL(TIOCSWINSZ, MYST_IOCTL_IN, struct winsize),
+    MYST_IOCTL(TIOCGPTN, MYST_IOCTL_OUT, unsigned int),
+    MYST_IOCTL(TIOCSPTLCK, MYST_IOCTL_IN, int),
+    MYST_IOCTL(TIOCGPTLCK, MYST_IOCTL_OUT, int),
+    MYST_IOCTL_ARG(TIOCSCTTY),
+    MYST_IOCTL_ARG(TIOCNOTTY),
+    MYST_IOCTL(TIOCGPGRP, MYST_IOCTL_OUT, pid_t),
+    MYST_IOCTL(TIOCSPGRP, MYST_IOCTL_IN, pid_t),
+    MYST_IOCTL(FIONREAD, MYST_IOCTL_OUT, int),
+    MYST_IOCTL_ARG(TCFLSH),
+};
+
+static const myst_ioctl_table_t _ioctls = MYST_IOCTL_TABLE("pty", _ioctl_list);
+
+extern myst_ptydev_t* myst_ptydev_get(void)
+{
+    // clang-format-off
+    static myst_ptydev_t _ptydev = {
+        {
+            .fd_read = (void*)_pd_read,
+            .fd_write = (void*)_pd_write,
+            .fd_readv = (void*)_pd_readv,
+            .fd_writev = (void*)_pd_writev,
+            .fd_fstat = (void*)_pd_fstat,
+            .fd_fcntl = (void*)_pd_fcntl,
+            .fd_ioctl = (void*)_pd_ioctl,
+            .fd_ioctls = &_ioctls,
+            .fd_dup = (void*)_pd_dup,
+            .fd_close = (void*)_pd_close,
+            .fd_target_fd = (void*)_pd_target_fd,
+            .fd_get_events = (void*)_pd_get_events,
+        },
+        .pd_open = _pd_open,
+    };
+    // clang-format-on
+
+    return &_ptydev;
+}
@@ -0,0 +1,319 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <limits.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <myst/atexit.h>
+#include <myst/clock.h>
+#include <myst/deadline.h>
+#include <myst/eraise.h>
+#include <myst/errno.h>
+#include <myst/metrics.h>
+#include <myst/mutex.h>
+#include <myst/printf.h>
+#include <myst/quotecache.h>
+#include <myst/tcall.h>
+#include <myst/work.h>
+
+/*
+**==============================================================================
+**
+** The quote cache:
+**
+** Generating the attested credentials takes a quote from the quoting enclave
+** (and, with ECDSA, its collateral from the host), which takes long enough
+** to dominate a TLS handshake. With "QuoteCacheLifetime" in config.json, the
+** kernel keeps the credentials it last generated and hands out copies of
+** them until they are that old. The worker thread of the work queue (see
+** kernel/work.c), which the first request starts, generates new ones when
+** three quarters of the lifetime have passed, so the cache is normally
+** refreshed before anyone has to wait for it, and never in a syscall of the
+** application. Without a worker thread (the enclave had no thread to spare)
+** the first request after the expiry generates them itself. A refresh that
+** fails is retried, and the old credentials are used until they expire.
+**
+** The same key pair then serves every connection for the lifetime, so the
+** lifetime bounds how long a key that leaks stays useful.
+**
+** The copies are kernel allocations, so myst_quote_cache_free_creds() frees
+** the ones it handed out and passes anything else to the target.
+**
+**==============================================================================
+*/
+
+/* how soon to try again after a refresh fails */
+#define RETRY_NSEC (10L * NANO_IN_SECOND)
+
+typedef struct creds
+{
+    uint8_t* cert;
+    size_t cert_size;
+    uint8_t* pkey;
+    size_t pkey_size;
+
+    /* CLOCK_MONOTONIC nanoseconds */
+    long expires;
+} creds_t;
+
+/* a copy of the credentials that has been handed out */
+typedef struct copy
+{
+    struct copy* next;
+    size_t cert_size;
+    size_t pkey_size;
+    uint8_t data[];
+} copy_t;
+
+static long _lifetime;
+static myst_mutex_t _mutex;
+static creds_t _creds;
+static copy_t* _copies;
+static myst_work_t _refresh;
+
+static myst_counter_t _hits = MYST_COUNTER_INITIALIZER(
+    "myst_quote_cache_hits_total",
+    "Requests for attested credentials served from the quote cache.");
+
+static myst_counter_t _misses = MYST_COUNTER_INITIALIZER(
+    "myst_quote_cache_misses_total",
+    "Requests for attested credentials that waited for a quote.");
+
+static myst_counter_t _refreshes = MYST_COUNTER_INITIALIZER(
+    "myst_quote_cache_refreshes_total",
+    "Attested credentials generated in the background by the quote cache.");
+
+static long _tcall_gen_creds(
+    uint8_t** cert,
+    size_t* cert_size,
+    uint8_t** pkey,
+    size_t* pkey_size)
+{
+    long params[6] = {(long)cert, (long)cert_size, (long)pkey, (long)pkey_size};
+    return myst_tcall(MYST_TCALL_GEN_CREDS, params);
+}
+
+static long _tcall_free_creds(
+    uint8_t* cert,
+    size_t cert_size,
+    uint8_t* pkey,
+    size_t pkey_size)
+{
+    long params[6] = {(long)cert, (long)cert_size, (long)pkey, (long)pkey_size};
+    return myst_tcall(MYST_TCALL_FREE_CREDS, params);
+}
+
+static void _release(creds_t* creds)
+{
+    if (creds->cert || creds->pkey)
+    {
+        _tcall_free_creds(
+            creds->cert, creds->cert_size, creds->pkey, creds->pkey_size);
+    }
+
+    memset(creds, 0, sizeof(creds_t));
+}
+
+static long _generate(creds_t* creds)
+{
+    long ret = 0;
+
+    memset(creds, 0, sizeof(creds_t));
+
+    ECHECK(_tcall_gen_creds(
+        &creds->cert, &creds->cert_size, &creds->pkey, &creds->pkey_size));
+
+    if (!creds->cert || !creds->cert_size || !creds->pkey ||
+        !creds->pkey_size)
+    {
+        _release(creds);
+        ERAISE(-EINVAL);
+    }
+
+    creds->expires = myst_deadline_now() + _lifetime;
+
+done:
+    return ret;
+}
+
+/* put new credentials in the cache (with _mutex held) */
+static void _replace(creds_t* creds)
+{
+    creds_t old = _creds;
+
+    _creds = *creds;
+    _release(&old);
+}
+
+static long _refresh_callback(myst_work_t* work, long now)
+{
+    creds_t creds;
+    long r;
+
+    (void)work;
+
+    /* the quote is generated without the lock, so that requests are served
+     * from the old credentials meanwhile */
+    if ((r = _generate(&creds)) < 0)
+    {
+        myst_eprintf(
+            "kernel: quote cache: cannot refresh the credentials: %s\n",
+            myst_error_name(-r));
+        return now + RETRY_NSEC;
+    }
+
+    myst_mutex_lock(&_mutex);
+    _replace(&creds);
+    myst_mutex_unlock(&_mutex);
+
+    myst_counter_add(&_refreshes, 1);
+
+    return creds.expires - _lifetime / 4;
+}
+
+static void _atexit(void* arg)
+{
+    copy_t* p;
+
+    (void)arg;
+
+    myst_work_cancel(&_refresh);
+
+    myst_mutex_lock(&_mutex);
+    _release(&_creds);
+
+    /* the copies that were never freed (this only clears the keys) */
+    for (p = _copies; p; p = p->next)
+        memset(p->data + p->cert_size, 0, p->pkey_size);
+
+    myst_mutex_unlock(&_mutex);
+}
+
+int myst_quote_cache_setup(size_t lifetime)
+{
+    int ret = 0;
+
+    if (!lifetime)
+        goto done;
+
+    if (lifetime > (size_t)(LONG_MAX / NANO_IN_SECOND))
+        ERAISE(-EINVAL);
+
+    _lifetime = (long)lifetime * NANO_IN_SECOND;
+
+    myst_metrics_register_counter(&_hits);
+    myst_metrics_register_counter(&_misses);
+    myst_metrics_register_counter(&_refreshes);
+
+    /* the first request generates the credentials and queues the refresh */
+    myst_work_init_worker(&_refresh, "quote-cache", _refresh_callback, NULL);
+
+    ECHECK(myst_atexit(_atexit, NULL));
+
+done:
+    return ret;
+}
+
+long myst_quote_cache_gen_creds(
+    uint8_t** cert,
+    size_t* cert_size,
+    uint8_t** pkey,
+    size_t* pkey_size)
+{
+    long ret = 0;
+    bool locked = false;
+    copy_t* copy;
+
+    if (!cert || !cert_size || !pkey || !pkey_size)
+        ERAISE(-EINVAL);
+
+    if (!_lifetime)
+    {
+        ret = _tcall_gen_creds(cert, cert_size, pkey, pkey_size);
+        goto done;
+    }
+
+    myst_mutex_lock(&_mutex);
+    locked = true;
+
+    if (!_creds.cert || myst_deadline_now() >= _creds.expires)
+    {
+        creds_t creds;
+
+        /* others wait for this quote rather than each generating one */
+        ECHECK(_generate(&creds));
+        _replace(&creds);
+        myst_counter_add(&_misses, 1);
+
+        myst_work_queue(&_refresh, creds.expires - _lifetime / 4);
+    }
+    else
+    {
+        myst_counter_add(&_hits, 1);
+    }
+
+    if (!(copy = malloc(
+              sizeof(copy_t) + _creds.cert_size + _creds.pkey_size)))
+    {
+        ERAISE(-ENOMEM);
+    }
+
+    copy->cert_size = _creds.cert_size;
+    copy->pkey_size = _creds.pkey_size;
+    memcpy(copy->data, _creds.cert, _creds.cert_size);
+    memcpy(copy->data + _creds.cert_size, _creds.pkey, _creds.pkey_size);
+    copy->next = _copies;
+    _copies = copy;
+
+    *cert = copy->data;
+    *cert_size = copy->cert_size;
+    *pkey = copy->data + copy->cert_size;
+    *pkey_size = copy->pkey_size;
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&_mutex);
+
+    return ret;
+}
+
+long myst_quote_cache_free_creds(
+    uint8_t* cert,
+    size_t cert_size,
+    uint8_t* pkey,
+    size_t pkey_size)
+{
+    copy_t* prev = NULL;
+    copy_t* p;
+
+    if (!_lifetime)
+        return _tcall_free_creds(cert, cert_size, pkey, pkey_size);
+
+    myst_mutex_lock(&_mutex);
+
+    for (p = _copies; p; prev = p, p = p->next)
+    {
+        if (p->data == cert)
+        {
+            if (prev)
+                prev->next = p->next;
+            else
+                _copies = p->next;
+            break;
+        }
+    }
+
+    myst_mutex_unlock(&_mutex);
+
+    /* not a copy: the credentials came from the target */
+    if (!p)
+        return _tcall_free_creds(cert, cert_size, pkey, pkey_size);
+
+    memset(p->data + p->cert_size, 0, p->pkey_size);
+    free(p);
+
+    return 0;
+}
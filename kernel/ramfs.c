@@ -17,8 +17,10 @@
 #include <myst/eraise.h>
 #include <myst/fs.h>
 #include <myst/id.h>
+#include <myst/ioctl.h>
 #include <myst/panic.h>
 #include <myst/paths.h>
+#include <myst/perms.h>
 #include <myst/printf.h>
 #include <myst/ramfs.h>
 #include <myst/realpath.h>
@@ -74,14 +76,18 @@ struct inode
 {
     uint64_t magic;
     uint32_t mode;         /* Type and mode */
+    uid_t uid;             /* owner */
+    gid_t gid;             /* group */
     struct timespec atime; /* time of last access */
     struct timespec ctime; /* time of last metadata change */
     struct timespec mtime; /* time of last modification */
+    struct timespec btime; /* time of creation */
     size_t nlink;          /* number of hard links to this inode */
     size_t nopens;         /* number of times file is currently opened */
     myst_buf_t buf;        /* file or directory data */
     const void* data;      /* set by myst_ramfs_set_buf() */
-    int (*vcallback)(myst_buf_t* buf);
+    int (*vcallback)(myst_buf_t* buf, void* context);
+    void* vcallback_context;
 };
 
 #define ACCESS 1
@@ -223,6 +229,8 @@ static int _inode_new(
 
     inode->magic = INODE_MAGIC;
     inode->mode = mode;
+    inode->uid = myst_geteuid();
+    inode->gid = myst_getegid();
     inode->nlink = 1;
 
     /* The root directory is its own parent */
@@ -266,6 +274,7 @@ static int _inode_new(
     }
 
     _update_timestamps(inode, ACCESS | CHANGE | MODIFY);
+    inode->btime = inode->mtime;
 
     if (inode_out)
         *inode_out = inode;
@@ -393,7 +402,7 @@ done:
 static const char* _inode_target(inode_t* inode)
 {
     if (inode->vcallback)
-        inode->vcallback(&inode->buf);
+        inode->vcallback(&inode->buf, inode->vcallback_context);
     return (const char*)inode->buf.data;
 }
 
@@ -463,6 +472,92 @@ _Static_assert(sizeof(((struct dirent*)0)->d_ino) == 8, "d_ino");
 /* Assume struct dirent is eight-byte aligned */
 _Static_assert(sizeof(struct dirent) % 8 == 0, "dirent");
 
+/* whether dir is the root directory of the calling process (see chroot), so
+ * that ".." in a symbolic link stays there instead of leaving the root. The
+ * root has no symbolic links so its components are not followed.
+ */
+/* find the directory that is the root of the process within this file system
+ * (the root of the file system when the process is not confined to it) */
+static int _get_process_root(
+    ramfs_t* ramfs,
+    inode_t** inode_out,
+    char realpath[PATH_MAX])
+{
+    int ret = 0;
+    myst_path_t* root = NULL;
+    char** toks = NULL;
+    size_t ntoks = 0;
+    inode_t* inode = ramfs->root;
+
+    *inode_out = NULL;
+
+    if (!(root = malloc(sizeof(myst_path_t))))
+        ERAISE(-ENOMEM);
+
+    ECHECK(myst_realpath_fs_root(ramfs->target, root));
+
+    if (*root->buf != '\0' && strcmp(root->buf, "/") != 0)
+    {
+        ECHECK(myst_strsplit(root->buf, "/", &toks, &ntoks));
+
+        for (size_t i = 0; i < ntoks; i++)
+        {
+            if (!S_ISDIR(inode->mode))
+                ERAISE_QUIET(-ENOTDIR);
+
+            if (!(inode = _inode_find_child(inode, toks[i])))
+                ERAISE_QUIET(-ENOENT);
+        }
+
+        if (realpath && myst_strlcpy(realpath, root->buf, PATH_MAX) >= PATH_MAX)
+            ERAISE_QUIET(-ENAMETOOLONG);
+    }
+    else if (realpath)
+    {
+        *realpath = '\0';
+    }
+
+    *inode_out = inode;
+
+done:
+
+    if (root)
+        free(root);
+
+    if (toks)
+        free(toks);
+
+    return ret;
+}
+
+static int _is_process_root(ramfs_t* ramfs, inode_t* dir, bool* result)
+{
+    int ret = 0;
+    myst_path_t* root = NULL;
+    inode_t* inode;
+
+    *result = false;
+
+    if (!(root = malloc(sizeof(myst_path_t))))
+        ERAISE(-ENOMEM);
+
+    ECHECK(myst_realpath_fs_root(ramfs->target, root));
+
+    /* not confined to this file system */
+    if (*root->buf == '\0')
+        goto done;
+
+    if (_get_process_root(ramfs, &inode, NULL) == 0)
+        *result = (inode == dir);
+
+done:
+
+    if (root)
+        free(root);
+
+    return ret;
+}
+
 static int _path_to_inode_recursive(
     ramfs_t* ramfs,
     const char* path,
@@ -512,11 +607,18 @@ static int _path_to_inode_recursive(
         for (size_t i = 0; i < ntoks; i++)
         {
             inode_t* p;
+            bool clamp = false;
+
+            /* ".." does not climb above the root of the process */
+            if (strcmp(toks[i], "..") == 0)
+                ECHECK(_is_process_root(ramfs, parent, &clamp));
 
-            if (!(p = _inode_find_child(parent, toks[i])))
+            if (clamp)
+                p = parent;
+            else if (!(p = _inode_find_child(parent, toks[i])))
                 ERAISE_QUIET(-ENOENT);
 
-            if (!S_ISLNK(p->mode))
+            if (!S_ISLNK(p->mode) && !clamp)
             {
                 if (realpath)
                 {
@@ -556,22 +658,31 @@ static int _path_to_inode_recursive(
                     }
                     else
                     {
-                        if (realpath)
-                            *realpath = '\0';
-
-                        parent = ramfs->root;
+                        /* resolve against the root of the process */
+                        ECHECK(_get_process_root(ramfs, &parent, realpath));
                     }
                 }
 
-                ECHECK(_path_to_inode_recursive(
-                    ramfs,
-                    target,
-                    parent,
-                    true,
-                    &parent,
-                    &p,
-                    realpath,
-                    target_out));
+                if (*target == '/' && strspn(target, "/") == strlen(target))
+                {
+                    /* the link names the root of the process */
+                    p = parent;
+
+                    if (realpath && *realpath == '\0')
+                        myst_strlcpy(realpath, "/", PATH_MAX);
+                }
+                else
+                {
+                    ECHECK(_path_to_inode_recursive(
+                        ramfs,
+                        target,
+                        parent,
+                        true,
+                        &parent,
+                        &p,
+                        realpath,
+                        target_out));
+                }
 
                 assert(target != NULL);
             }
@@ -786,19 +897,23 @@ static int _fs_open(
 
         /* Check file access permissions */
         {
-            const int access = flags & 0x03;
+            const int want = myst_perms_open_access(flags);
 
-            if (access == O_RDONLY && !(inode->mode & S_IRUSR))
-                ERAISE(-EPERM);
-
-            if (access == O_WRONLY && !(inode->mode & S_IWUSR))
-                ERAISE(-EPERM);
-
-            if (access == O_RDWR && !(inode->mode & S_IRUSR))
-                ERAISE(-EPERM);
+            /* a virtual file (of /proc) is only as writable as its mode
+             * says, even for root */
+            if (inode->vcallback)
+            {
+                if ((want & R_OK) && !(inode->mode & S_IRUSR))
+                    ERAISE(-EPERM);
 
-            if (access == O_RDWR && !(inode->mode & S_IWUSR))
-                ERAISE(-EPERM);
+                if ((want & W_OK) && !(inode->mode & S_IWUSR))
+                    ERAISE(-EPERM);
+            }
+            else
+            {
+                ECHECK(myst_perms_check(
+                    inode->mode, inode->uid, inode->gid, want));
+            }
         }
 
         if ((flags & O_DIRECTORY) && !S_ISDIR(inode->mode))
@@ -811,7 +926,8 @@ static int _fs_open(
             file->offset = inode->buf.size;
 
         if (inode->vcallback)
-            ECHECK((*inode->vcallback)(&file->vbuf));
+            ECHECK((*inode->vcallback)(
+                &file->vbuf, inode->vcallback_context));
     }
     else if (errnum == -ENOENT)
     {
@@ -1256,14 +1372,7 @@ static int _fs_access(myst_fs_t* fs, const char* pathname, int mode)
     if (mode == F_OK)
         goto done;
 
-    if ((mode & R_OK) && !(inode->mode & S_IRUSR))
-        ERAISE(-EACCES);
-
-    if ((mode & W_OK) && !(inode->mode & S_IWUSR))
-        ERAISE(-EACCES);
-
-    if ((mode & X_OK) && !(inode->mode & S_IXUSR))
-        ERAISE(-EACCES);
+    ECHECK(myst_perms_check(inode->mode, inode->uid, inode->gid, mode));
 
     _update_timestamps(inode, ACCESS);
 
@@ -1285,7 +1394,7 @@ static int _stat(inode_t* inode, struct stat* statbuf)
 
     if (inode->vcallback)
     {
-        ECHECK((*inode->vcallback)(&vbuf));
+        ECHECK((*inode->vcallback)(&vbuf, inode->vcallback_context));
         size = vbuf.size;
         ECHECK(myst_round_up_signed(size, BLKSIZE, &rounded));
     }
@@ -1300,8 +1409,8 @@ static int _stat(inode_t* inode, struct stat* statbuf)
     buf.st_ino = (ino_t)inode;
     buf.st_mode = inode->mode;
     buf.st_nlink = inode->nlink;
-    buf.st_uid = MYST_DEFAULT_UID;
-    buf.st_gid = MYST_DEFAULT_GID;
+    buf.st_uid = inode->uid;
+    buf.st_gid = inode->gid;
     buf.st_rdev = 0;
     buf.st_size = (off_t)size;
     buf.st_blksize = BLKSIZE;
@@ -1365,6 +1474,38 @@ done:
     return ret;
 }
 
+static int _fs_btime(
+    myst_fs_t* fs,
+    const char* pathname,
+    bool follow,
+    struct timespec* btime)
+{
+    int ret = 0;
+    ramfs_t* ramfs = (ramfs_t*)fs;
+    inode_t* inode;
+    char suffix[PATH_MAX];
+    myst_fs_t* tfs = NULL;
+
+    if (!_ramfs_valid(ramfs) || !pathname || !btime)
+        ERAISE(-EINVAL);
+
+    ECHECK(_path_to_inode(ramfs, pathname, follow, NULL, &inode, suffix, &tfs));
+    if (tfs)
+    {
+        /* delegate operation to target filesystem */
+        if (!tfs->fs_btime)
+            ERAISE_QUIET(-ENOTSUP);
+
+        ECHECK(tfs->fs_btime(tfs, suffix, follow, btime));
+        goto done;
+    }
+
+    *btime = inode->btime;
+
+done:
+    return ret;
+}
+
 static int _fs_fstat(myst_fs_t* fs, myst_file_t* file, struct stat* statbuf)
 {
     int ret = 0;
@@ -1812,7 +1953,7 @@ static ssize_t _fs_readlink(
 
     if (inode->vcallback)
     {
-        inode->vcallback(&inode->buf);
+        inode->vcallback(&inode->buf, inode->vcallback_context);
     }
     else
     {
@@ -1961,15 +2102,13 @@ static int _fs_ioctl(
     ramfs_t* ramfs = (ramfs_t*)fs;
     int ret = 0;
 
+    (void)request;
     (void)arg;
 
     if (!_ramfs_valid(ramfs) || !_file_valid(file))
         ERAISE(-EBADF);
 
-    if (request == TIOCGWINSZ)
-        ERAISE(-EINVAL);
-
-    ERAISE(-ENOTSUP);
+    ERAISE(-ENOTTY);
 
 done:
 
@@ -2143,6 +2282,143 @@ done:
     return ret;
 }
 
+static int _chmod(inode_t* inode, mode_t mode)
+{
+    int ret = 0;
+
+    /* the virtual files describe the kernel, which decides their mode */
+    if (inode->vcallback)
+        ERAISE(-EPERM);
+
+    ECHECK(myst_perms_check_chmod(inode->uid));
+
+    inode->mode = (inode->mode & S_IFMT) | (mode & 07777);
+    _update_timestamps(inode, CHANGE);
+
+done:
+    return ret;
+}
+
+static int _chown(inode_t* inode, uid_t owner, gid_t group)
+{
+    int ret = 0;
+
+    if (inode->vcallback)
+        ERAISE(-EPERM);
+
+    ECHECK(myst_perms_check_chown(inode->uid, inode->gid, owner, group));
+
+    if (owner != (uid_t)-1)
+        inode->uid = owner;
+
+    if (group != (gid_t)-1)
+        inode->gid = group;
+
+    if (!S_ISLNK(inode->mode))
+        inode->mode = myst_perms_chown_mode(inode->mode);
+
+    _update_timestamps(inode, CHANGE);
+
+done:
+    return ret;
+}
+
+static int _fs_chmod(myst_fs_t* fs, const char* pathname, mode_t mode)
+{
+    int ret = 0;
+    ramfs_t* ramfs = (ramfs_t*)fs;
+    inode_t* inode;
+    char suffix[PATH_MAX];
+    myst_fs_t* tfs = NULL;
+
+    if (!_ramfs_valid(ramfs) || !pathname)
+        ERAISE(-EINVAL);
+
+    ECHECK(_path_to_inode(ramfs, pathname, true, NULL, &inode, suffix, &tfs));
+    if (tfs)
+    {
+        /* delegate operation to target filesystem */
+        if (!tfs->fs_chmod)
+            ERAISE(-EPERM);
+
+        ECHECK(tfs->fs_chmod(tfs, suffix, mode));
+        goto done;
+    }
+
+    ECHECK(_chmod(inode, mode));
+
+done:
+    return ret;
+}
+
+static int _fs_fchmod(myst_fs_t* fs, myst_file_t* file, mode_t mode)
+{
+    int ret = 0;
+    ramfs_t* ramfs = (ramfs_t*)fs;
+
+    if (!_ramfs_valid(ramfs) || !_file_valid(file))
+        ERAISE(-EINVAL);
+
+    ECHECK(_chmod(file->inode, mode));
+
+done:
+    return ret;
+}
+
+static int _fs_chown(
+    myst_fs_t* fs,
+    const char* pathname,
+    bool follow,
+    uid_t owner,
+    gid_t group)
+{
+    int ret = 0;
+    ramfs_t* ramfs = (ramfs_t*)fs;
+    inode_t* inode;
+    char suffix[PATH_MAX];
+    myst_fs_t* tfs = NULL;
+
+    if (!_ramfs_valid(ramfs) || !pathname)
+        ERAISE(-EINVAL);
+
+    ECHECK(_path_to_inode(ramfs, pathname, follow, NULL, &inode, suffix, &tfs));
+    if (tfs)
+    {
+        /* delegate operation to target filesystem */
+        if (!tfs->fs_chown)
+            ERAISE(-EPERM);
+
+        ECHECK(tfs->fs_chown(tfs, suffix, follow, owner, group));
+        goto done;
+    }
+
+    ECHECK(_chown(inode, owner, group));
+
+done:
+    return ret;
+}
+
+static int _fs_fchown(
+    myst_fs_t* fs,
+    myst_file_t* file,
+    uid_t owner,
+    gid_t group)
+{
+    int ret = 0;
+    ramfs_t* ramfs = (ramfs_t*)fs;
+
+    if (!_ramfs_valid(ramfs) || !_file_valid(file))
+        ERAISE(-EINVAL);
+
+    ECHECK(_chown(file->inode, owner, group));
+
+done:
+    return ret;
+}
+
+/* no requests: each fails with ENOTTY */
+static const myst_ioctl_table_t _ioctls = MYST_IOCTL_TABLE_NONE("ramfs");
+
 int myst_init_ramfs(
     myst_mount_resolve_callback_t resolve_cb,
     myst_fs_t** fs_out)
@@ -2160,6 +2436,7 @@ int myst_init_ramfs(
             .fd_fstat = (void*)_fs_fstat,
             .fd_fcntl = (void*)_fs_fcntl,
             .fd_ioctl = (void*)_fs_ioctl,
+            .fd_ioctls = &_ioctls,
             .fd_dup = (void*)_fs_dup,
             .fd_close = (void*)_fs_close,
             .fd_target_fd = (void*)_fs_target_fd,
@@ -2200,6 +2477,11 @@ int myst_init_ramfs(
         .fs_statfs = _fs_statfs,
         .fs_fstatfs = _fs_fstatfs,
         .fs_futimens = _fs_futimens,
+        .fs_btime = _fs_btime,
+        .fs_chmod = _fs_chmod,
+        .fs_fchmod = _fs_fchmod,
+        .fs_chown = _fs_chown,
+        .fs_fchown = _fs_fchown,
     };
     // clang-format on
     inode_t* root_inode = NULL;
@@ -2273,7 +2555,8 @@ int myst_create_virtual_file(
     myst_fs_t* fs,
     const char* pathname,
     mode_t mode,
-    int (*vcallback)(myst_buf_t* buf))
+    int (*vcallback)(myst_buf_t* buf, void* context),
+    void* context)
 {
     int ret = 0;
     ramfs_t* ramfs = (ramfs_t*)fs;
@@ -2309,6 +2592,7 @@ int myst_create_virtual_file(
         ECHECK(
             _path_to_inode(ramfs, pathname, false, NULL, &inode, NULL, NULL));
         inode->vcallback = vcallback;
+        inode->vcallback_context = context;
     }
 
     ret = 0;
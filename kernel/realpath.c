@@ -6,10 +6,82 @@
 #include <myst/realpath.h>
 #include <myst/strings.h>
 #include <myst/syscall.h>
+#include <myst/thread.h>
 #include <myst/types.h>
 #include <stdlib.h>
 #include <string.h>
 
+/* get the root directory of the calling process ("" if it is "/") */
+static int _get_root(myst_path_t* root)
+{
+    int ret = 0;
+    myst_thread_t* process;
+
+    *root->buf = '\0';
+
+    /* no processes yet while the kernel is still booting */
+    if (!__myst_main_thread)
+        goto done;
+
+    process = myst_find_process_thread(myst_thread_self());
+    myst_spin_lock(&process->main.root_lock);
+
+    if (process->main.root)
+    {
+        const size_t n = sizeof(root->buf);
+
+        if (myst_strlcpy(root->buf, process->main.root, n) >= n)
+            ret = -ENAMETOOLONG;
+    }
+
+    myst_spin_unlock(&process->main.root_lock);
+
+done:
+    return ret;
+}
+
+/* /proc is shared by every process whatever its root directory, as if it
+ * were mounted into each root, so kernel bookkeeping under /proc is
+ * unaffected by chroot */
+static bool _is_proc_path(const char* path)
+{
+    return strncmp(path, "/proc", 5) == 0 && (path[5] == '/' || !path[5]);
+}
+
+/* prefix a normalized path with the root directory of the process */
+static int _apply_root(myst_path_t* path)
+{
+    int ret = 0;
+    myst_path_t* root = NULL;
+    const size_t n = sizeof(path->buf);
+
+    if (_is_proc_path(path->buf))
+        goto done;
+
+    if (!(root = malloc(sizeof(myst_path_t))))
+        ERAISE(-ENOMEM);
+
+    ECHECK(_get_root(root));
+
+    if (*root->buf == '\0')
+        goto done;
+
+    if (strcmp(path->buf, "/") != 0)
+    {
+        if (myst_strlcat(root->buf, path->buf, n) >= n)
+            ERAISE(-ENAMETOOLONG);
+    }
+
+    myst_strlcpy(path->buf, root->buf, n);
+
+done:
+
+    if (root)
+        free(root);
+
+    return ret;
+}
+
 int myst_realpath(const char* path, myst_path_t* resolved_path)
 {
     int ret = 0;
@@ -114,6 +186,9 @@ int myst_realpath(const char* path, myst_path_t* resolved_path)
         }
     }
 
+    /* ".." was resolved above so the path cannot leave the root */
+    ECHECK(_apply_root(resolved_path));
+
 done:
 
     if (v)
@@ -121,3 +196,86 @@ done:
 
     return ret;
 }
+
+int myst_realpath_unroot(const char* realpath, myst_path_t* path)
+{
+    int ret = 0;
+    myst_path_t* root = NULL;
+    const size_t n = sizeof(path->buf);
+    const char* p = realpath;
+
+    if (!realpath || !path)
+        ERAISE(-EINVAL);
+
+    if (!(root = malloc(sizeof(myst_path_t))))
+        ERAISE(-ENOMEM);
+
+    ECHECK(_get_root(root));
+
+    if (*root->buf != '\0' && !_is_proc_path(realpath))
+    {
+        size_t len = strlen(root->buf);
+
+        if (strncmp(realpath, root->buf, len) == 0 &&
+            (realpath[len] == '/' || realpath[len] == '\0'))
+        {
+            p = realpath + len;
+        }
+        else
+        {
+            /* outside the root: the process cannot name it */
+            p = "/";
+        }
+    }
+
+    if (*p == '\0')
+        p = "/";
+
+    if (myst_strlcpy(path->buf, p, n) >= n)
+        ERAISE(-ENAMETOOLONG);
+
+done:
+
+    if (root)
+        free(root);
+
+    return ret;
+}
+
+int myst_realpath_fs_root(const char* mntpath, myst_path_t* root)
+{
+    int ret = 0;
+    const size_t n = sizeof(root->buf);
+    size_t len;
+
+    if (!mntpath || !root)
+        ERAISE(-EINVAL);
+
+    ECHECK(_get_root(root));
+
+    if (*root->buf == '\0')
+        goto done;
+
+    if (strcmp(mntpath, "/") == 0)
+        goto done;
+
+    len = strlen(mntpath);
+
+    if (strncmp(root->buf, mntpath, len) == 0 &&
+        (root->buf[len] == '/' || root->buf[len] == '\0'))
+    {
+        /* the root is within this file system */
+        memmove(root->buf, root->buf + len, strlen(root->buf + len) + 1);
+
+        if (*root->buf == '\0')
+            myst_strlcpy(root->buf, "/", n);
+    }
+    else
+    {
+        /* the file system is beneath the root or outside it */
+        *root->buf = '\0';
+    }
+
+done:
+    return ret;
+}
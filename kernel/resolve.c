@@ -0,0 +1,285 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <limits.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+
+#include <myst/eraise.h>
+#include <myst/mount.h>
+#include <myst/mutex.h>
+#include <myst/paths.h>
+#include <myst/resolve.h>
+#include <myst/strings.h>
+#include <myst/syscall.h>
+
+/* the number of symbolic links followed in one lookup (as for Linux) */
+#define MAX_SYMLINKS 40
+
+typedef struct variables
+{
+    char base[PATH_MAX];
+    char rest[PATH_MAX];
+    char next[PATH_MAX];
+    char link[PATH_MAX];
+    char tmp[PATH_MAX];
+    char suffix[PATH_MAX];
+    char name[NAME_MAX + 1];
+} variables_t;
+
+static myst_mutex_t _lock;
+
+void myst_resolve_lock(void)
+{
+    myst_mutex_lock(&_lock);
+}
+
+void myst_resolve_unlock(void)
+{
+    myst_mutex_unlock(&_lock);
+}
+
+/* remove the leading component of path into name (0 if there are none) */
+static int _pop(char* path, char name[NAME_MAX + 1])
+{
+    char* p = path;
+    size_t len;
+
+    while (*p == '/')
+        p++;
+
+    if ((len = strcspn(p, "/")) == 0)
+    {
+        *path = '\0';
+        return 0;
+    }
+
+    if (len > NAME_MAX)
+        return -ENAMETOOLONG;
+
+    memcpy(name, p, len);
+    name[len] = '\0';
+    p += len;
+    memmove(path, p, strlen(p) + 1);
+
+    return 1;
+}
+
+/* path = head + "/" + tail */
+static int _splice(char path[PATH_MAX], const char* head, const char* tail)
+{
+    if (myst_strlcpy(path, head, PATH_MAX) >= PATH_MAX ||
+        myst_strlcat(path, "/", PATH_MAX) >= PATH_MAX ||
+        myst_strlcat(path, tail, PATH_MAX) >= PATH_MAX)
+    {
+        return -ENAMETOOLONG;
+    }
+
+    return 0;
+}
+
+static void _parent(char* path)
+{
+    char* p = strrchr(path, '/');
+
+    if (p == path || !p)
+        myst_strlcpy(path, "/", PATH_MAX);
+    else
+        *p = '\0';
+}
+
+/* the /proc links that Linux resolves to the object itself ("magic links") */
+static bool _is_magic_link(const char* path)
+{
+    const char* p;
+
+    if (strncmp(path, "/proc/", 6) != 0)
+        return false;
+
+    /* skip the pid or "self" */
+    if (!(p = strchr(path + 6, '/')))
+        return false;
+
+    if (strcmp(p, "/exe") == 0 || strcmp(p, "/cwd") == 0 ||
+        strcmp(p, "/root") == 0)
+    {
+        return true;
+    }
+
+    return strncmp(p, "/fd/", 4) == 0 && p[4] && !strchr(p + 4, '/');
+}
+
+static int _fs_of(const char* path, char suffix[PATH_MAX], myst_fs_t** fs)
+{
+    return myst_mount_resolve(path, suffix, fs);
+}
+
+int myst_resolve_path(
+    const char* dirpath,
+    const char* pathname,
+    uint64_t resolve,
+    bool follow,
+    myst_path_t* path)
+{
+    int ret = 0;
+    variables_t* v = NULL;
+    const bool scoped = resolve & (MYST_RESOLVE_BENEATH | MYST_RESOLVE_IN_ROOT);
+    char* cur;
+    size_t nlinks = 0;
+    myst_fs_t* base_fs = NULL;
+    int r;
+
+    if (!dirpath || *dirpath != '/' || !pathname || !path)
+        ERAISE(-EINVAL);
+
+    if (resolve & ~(uint64_t)MYST_RESOLVE_ALL)
+        ERAISE(-EINVAL);
+
+    if ((resolve & MYST_RESOLVE_BENEATH) && (resolve & MYST_RESOLVE_IN_ROOT))
+        ERAISE(-EINVAL);
+
+    if (!(v = malloc(sizeof(variables_t))))
+        ERAISE(-ENOMEM);
+
+    ECHECK(myst_normalize(dirpath, v->base, sizeof(v->base)));
+
+    if (myst_strlcpy(v->rest, pathname, PATH_MAX) >= PATH_MAX)
+        ERAISE(-ENAMETOOLONG);
+
+    /* the path is built up in the result */
+    cur = path->buf;
+
+    if (*pathname == '/')
+    {
+        /* absolute paths escape the directory unless it is the root */
+        if (resolve & MYST_RESOLVE_BENEATH)
+            ERAISE_QUIET(-EXDEV);
+
+        if (resolve & MYST_RESOLVE_IN_ROOT)
+            myst_strlcpy(cur, v->base, PATH_MAX);
+        else
+            myst_strlcpy(cur, "/", PATH_MAX);
+    }
+    else
+    {
+        myst_strlcpy(cur, v->base, PATH_MAX);
+    }
+
+    if (resolve & MYST_RESOLVE_NO_XDEV)
+        ECHECK(_fs_of(cur, v->suffix, &base_fs));
+
+    while ((r = _pop(v->rest, v->name)) > 0)
+    {
+        const bool more = v->rest[strspn(v->rest, "/")] != '\0';
+        struct stat st;
+        long n;
+
+        if (strcmp(v->name, ".") == 0)
+            continue;
+
+        if (strcmp(v->name, "..") == 0)
+        {
+            /* cur has no symbolic links, so ".." is its parent */
+            if (scoped && strcmp(cur, v->base) == 0)
+            {
+                if (resolve & MYST_RESOLVE_BENEATH)
+                    ERAISE_QUIET(-EXDEV);
+
+                /* RESOLVE_IN_ROOT: ".." of the root is the root */
+                continue;
+            }
+
+            _parent(cur);
+        }
+        else
+        {
+            ECHECK(myst_make_path(v->next, PATH_MAX, cur, v->name));
+            ECHECK(myst_normalize(v->next, v->tmp, sizeof(v->tmp)));
+            myst_strlcpy(v->next, v->tmp, PATH_MAX);
+
+            if ((n = myst_syscall_lstat(v->next, &st)) != 0)
+            {
+                /* only the final component may be missing (to create it) */
+                if (n == -ENOENT && !more)
+                {
+                    myst_strlcpy(cur, v->next, PATH_MAX);
+                    break;
+                }
+
+                ERAISE_QUIET((int)n);
+            }
+
+            if (S_ISLNK(st.st_mode) && (follow || more))
+            {
+                if (resolve & MYST_RESOLVE_NO_SYMLINKS)
+                    ERAISE_QUIET(-ELOOP);
+
+                if (++nlinks > MAX_SYMLINKS)
+                    ERAISE_QUIET(-ELOOP);
+
+                /* the scoped lookups never follow magic links either */
+                if ((resolve & MYST_RESOLVE_NO_MAGICLINKS || scoped) &&
+                    _is_magic_link(v->next))
+                {
+                    ERAISE_QUIET(-ELOOP);
+                }
+
+                ECHECK(
+                    n = myst_syscall_readlink(
+                        v->next, v->link, sizeof(v->link) - 1));
+                v->link[n] = '\0';
+
+                if (v->link[0] == '/')
+                {
+                    if (resolve & MYST_RESOLVE_BENEATH)
+                        ERAISE_QUIET(-EXDEV);
+
+                    if (resolve & MYST_RESOLVE_IN_ROOT)
+                        myst_strlcpy(cur, v->base, PATH_MAX);
+                    else
+                        myst_strlcpy(cur, "/", PATH_MAX);
+                }
+
+                /* go on with the target of the link */
+                ECHECK(_splice(v->tmp, v->link, v->rest));
+                myst_strlcpy(v->rest, v->tmp, PATH_MAX);
+                continue;
+            }
+
+            myst_strlcpy(cur, v->next, PATH_MAX);
+        }
+
+        /* RESOLVE_NO_XDEV: stay within the file system of the directory */
+        if (base_fs)
+        {
+            myst_fs_t* fs;
+
+            ECHECK(_fs_of(cur, v->suffix, &fs));
+
+            if (fs != base_fs)
+                ERAISE_QUIET(-EXDEV);
+        }
+    }
+
+    ECHECK(r);
+
+    /* the final component, when it was created or not followed */
+    if (base_fs)
+    {
+        myst_fs_t* fs;
+
+        ECHECK(_fs_of(cur, v->suffix, &fs));
+
+        if (fs != base_fs)
+            ERAISE_QUIET(-EXDEV);
+    }
+
+done:
+
+    if (v)
+        free(v);
+
+    return ret;
+}
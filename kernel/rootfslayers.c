@@ -0,0 +1,193 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+
+#include <myst/cpio.h>
+#include <myst/eraise.h>
+#include <myst/errno.h>
+#include <myst/hex.h>
+#include <myst/printf.h>
+#include <myst/rootfslayers.h>
+#include <myst/sha256.h>
+#include <myst/syscall.h>
+#include <myst/tcall.h>
+
+/*
+**==============================================================================
+**
+** Root file system layers:
+**
+** With "RootfsLayers" in config.json, the root file system is the root file
+** system of the package with each layer applied on top of it in turn. A
+** layer holds what changed from the tree below it (see "myst mklayer" and
+** myst_cpio_make_layer()) and is named by its hash in the host directory
+** "RootfsLayerStore". The kernel reads each layer into enclave memory and
+** checks its hash against the signed configuration before applying it, so
+** the store itself need not be trusted, and an update only ships and hashes
+** the files that changed.
+**
+**==============================================================================
+*/
+
+#define READ_BUFSIZE (64 * 1024)
+
+static long _host_open(const char* path, int flags)
+{
+    long params[6] = {(long)path, flags, 0};
+    return myst_tcall(SYS_open, params);
+}
+
+static void _host_close(int fd)
+{
+    long params[6] = {fd};
+    myst_tcall(SYS_close, params);
+}
+
+static int _host_fstat(int fd, struct stat* st)
+{
+    long params[6] = {fd, (long)st};
+    return (int)myst_tcall(SYS_fstat, params);
+}
+
+/* read the layer from the host into enclave memory */
+static int _read_layer(const char* path, uint8_t** data_out, size_t* size_out)
+{
+    int ret = 0;
+    int fd = -1;
+    struct stat st;
+    uint8_t* data = NULL;
+    size_t size;
+
+    ECHECK(fd = _host_open(path, O_RDONLY));
+    ECHECK(_host_fstat(fd, &st));
+
+    if (!S_ISREG(st.st_mode) || st.st_size <= 0)
+        ERAISE(-EINVAL);
+
+    size = (size_t)st.st_size;
+
+    if (!(data = malloc(size)))
+        ERAISE(-ENOMEM);
+
+    /* read exactly the size that was checked */
+    for (size_t pos = 0; pos < size;)
+    {
+        size_t count = size - pos;
+        long n;
+
+        if (count > READ_BUFSIZE)
+            count = READ_BUFSIZE;
+
+        long params[6] = {fd, (long)(data + pos), (long)count};
+        ECHECK(n = myst_tcall(SYS_read, params));
+
+        /* do not trust the host to stay within the buffer */
+        if (n == 0 || n > (long)count)
+            ERAISE(-EIO);
+
+        pos += (size_t)n;
+    }
+
+    *data_out = data;
+    *size_out = size;
+    data = NULL;
+
+done:
+
+    if (data)
+        free(data);
+
+    if (fd >= 0)
+        _host_close(fd);
+
+    return ret;
+}
+
+static int _apply(const char* store, const char* layer)
+{
+    int ret = 0;
+    uint8_t expected[MYST_SHA256_SIZE];
+    myst_sha256_t sha256;
+    char hex[2 * MYST_SHA256_SIZE + 1];
+    char path[PATH_MAX];
+    uint8_t* data = NULL;
+    size_t size = 0;
+
+    if (myst_ascii_to_bin(layer, expected, sizeof(expected)) !=
+        sizeof(expected))
+    {
+        myst_eprintf("kernel: bad hash in RootfsLayers: %s\n", layer);
+        ERAISE(-EINVAL);
+    }
+
+    /* the name in the store is the hash in lowercase */
+    for (size_t i = 0; i < sizeof(expected); i++)
+        snprintf(hex + 2 * i, 3, "%02x", expected[i]);
+
+    if (snprintf(path, sizeof(path), "%s/%s.cpio", store, hex) >=
+        (int)sizeof(path))
+    {
+        ERAISE(-ENAMETOOLONG);
+    }
+
+    ECHECK(_read_layer(path, &data, &size));
+    ECHECK(myst_sha256(&sha256, data, size));
+
+    if (memcmp(sha256.data, expected, sizeof(expected)) != 0)
+    {
+        myst_eprintf("kernel: root file system layer %s is corrupt\n", path);
+        ERAISE(-EPERM);
+    }
+
+    if (myst_cpio_mem_apply_layer(data, size, "/") != 0)
+        ERAISE(-EINVAL);
+
+done:
+
+    if (data)
+        free(data);
+
+    return ret;
+}
+
+int myst_apply_rootfs_layers(const myst_rootfs_layers_config_t* config)
+{
+    int ret = 0;
+
+    if (!config)
+        ERAISE(-EINVAL);
+
+    if (config->num_layers == 0)
+        goto done;
+
+    if (!config->store)
+    {
+        myst_eprintf("kernel: RootfsLayers requires RootfsLayerStore\n");
+        ERAISE(-EINVAL);
+    }
+
+    for (size_t i = 0; i < config->num_layers; i++)
+    {
+        const char* layer = config->layers[i];
+        int r;
+
+        if ((r = _apply(config->store, layer)) != 0)
+        {
+            myst_eprintf(
+                "kernel: failed to apply root file system layer %s: %s\n",
+                layer,
+                myst_error_name(-r));
+            ERAISE(r);
+        }
+    }
+
+done:
+    return ret;
+}
@@ -0,0 +1,341 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <time.h>
+
+#include <myst/clock.h>
+#include <myst/eraise.h>
+#include <myst/fdtable.h>
+#include <myst/round.h>
+#include <myst/rusage.h>
+#include <myst/syscall.h>
+#include <myst/thread.h>
+
+/*
+**==============================================================================
+**
+** Resource usage accounting:
+**
+** Each thread accumulates its own user and system time (kernel/times.c) and
+** its voluntary context switches, which are counted whenever the thread blocks
+** in the target. Time spent blocked is not counted as system time. Threads are
+** never removed from the thread group, so the usage of a process is the sum
+** over its thread group, including exited threads. The resident set size of a
+** process is the size of its CRT image, its stack and its mappings. Mystikos
+** cannot observe involuntary context switches (the host schedules threads), so
+** those remain zero.
+**
+**==============================================================================
+*/
+
+/* the clock ticks per second reported by sysconf(_SC_CLK_TCK) */
+#define TICKS_PER_SECOND 100
+
+static long _now(void)
+{
+    struct timespec ts;
+
+    if (myst_syscall_clock_gettime(CLOCK_MONOTONIC, &ts) != 0)
+        return 0;
+
+    return ts.tv_sec * NANO_IN_SECOND + ts.tv_nsec;
+}
+
+static unsigned long _ticks(long nsecs)
+{
+    return (unsigned long)nsecs / (NANO_IN_SECOND / TICKS_PER_SECOND);
+}
+
+long myst_rusage_block_begin(void)
+{
+    return _now();
+}
+
+void myst_rusage_block_end(long begin)
+{
+    myst_thread_t* thread = myst_thread_self();
+    long lapsed = _now() - begin;
+
+    if (!myst_valid_thread(thread))
+        return;
+
+    if (lapsed > 0)
+        thread->blocked_nsecs += lapsed;
+
+    __atomic_fetch_add(&thread->rusage.nvcsw, 1, __ATOMIC_SEQ_CST);
+}
+
+void myst_rusage_add_rss(long delta)
+{
+    myst_thread_t* process = myst_find_process_thread(myst_thread_self());
+
+    if (!process)
+        return;
+
+    myst_spin_lock(process->thread_lock);
+    {
+        /* unmapping memory that was never mapped must not underflow */
+        if (delta < 0 && (size_t)-delta > process->main.rss)
+            process->main.rss = 0;
+        else
+            process->main.rss += delta;
+
+        if (process->main.rss > process->main.maxrss)
+            process->main.maxrss = process->main.rss;
+    }
+    myst_spin_unlock(process->thread_lock);
+}
+
+static void _add(myst_rusage_t* usage, const myst_rusage_t* x)
+{
+    usage->utime += x->utime;
+    usage->stime += x->stime;
+    usage->nvcsw += x->nvcsw;
+    usage->nivcsw += x->nivcsw;
+
+    if (x->maxrss > usage->maxrss)
+        usage->maxrss = x->maxrss;
+}
+
+/* sum the usage of the process threads (caller holds process->thread_lock) */
+static void _sum_locked(myst_thread_t* process, myst_rusage_t* usage)
+{
+    memset(usage, 0, sizeof(myst_rusage_t));
+
+    for (myst_thread_t* t = process; t; t = t->group_next)
+        _add(usage, &t->rusage);
+
+    usage->maxrss = process->main.maxrss;
+}
+
+void myst_rusage_reap(
+    myst_thread_t* parent,
+    myst_thread_t* child,
+    myst_rusage_t* usage_out)
+{
+    myst_rusage_t usage;
+
+    if (!parent || !child)
+        return;
+
+    myst_spin_lock(child->thread_lock);
+    {
+        _sum_locked(child, &usage);
+        _add(&usage, &child->main.children_rusage);
+    }
+    myst_spin_unlock(child->thread_lock);
+
+    /* wait4() may find the same zombie more than once */
+    if (!child->main.reaped)
+    {
+        myst_spin_lock(parent->thread_lock);
+        _add(&parent->main.children_rusage, &usage);
+        myst_spin_unlock(parent->thread_lock);
+
+        child->main.reaped = true;
+    }
+
+    if (usage_out)
+        *usage_out = usage;
+}
+
+int myst_rusage_get(myst_thread_t* thread, int who, myst_rusage_t* usage)
+{
+    int ret = 0;
+    myst_thread_t* process;
+
+    if (!thread || !usage)
+        ERAISE(-EINVAL);
+
+    if (!(process = myst_find_process_thread(thread)))
+        ERAISE(-ESRCH);
+
+    myst_spin_lock(process->thread_lock);
+    {
+        switch (who)
+        {
+            case RUSAGE_SELF:
+                _sum_locked(process, usage);
+                break;
+            case RUSAGE_CHILDREN:
+                *usage = process->main.children_rusage;
+                break;
+            case RUSAGE_THREAD:
+                /* like Linux, report the peak RSS of the whole process */
+                *usage = thread->rusage;
+                usage->maxrss = process->main.maxrss;
+                break;
+            default:
+                ret = -EINVAL;
+                break;
+        }
+    }
+    myst_spin_unlock(process->thread_lock);
+
+done:
+    return ret;
+}
+
+static void _to_timeval(long nsecs, struct timeval* tv)
+{
+    tv->tv_sec = nsecs / NANO_IN_SECOND;
+    tv->tv_usec = (nsecs % NANO_IN_SECOND) / 1000;
+}
+
+void myst_rusage_to_rusage(const myst_rusage_t* usage, struct rusage* rusage)
+{
+    memset(rusage, 0, sizeof(struct rusage));
+    _to_timeval(usage->utime, &rusage->ru_utime);
+    _to_timeval(usage->stime, &rusage->ru_stime);
+    rusage->ru_maxrss = (long)(usage->maxrss / 1024);
+    rusage->ru_nvcsw = usage->nvcsw;
+    rusage->ru_nivcsw = usage->nivcsw;
+}
+
+typedef struct stat_info
+{
+    myst_rusage_t self;
+    myst_rusage_t children;
+    size_t rss;
+    size_t num_threads;
+    char state;
+} stat_info_t;
+
+static void _get_stat_info(myst_thread_t* process, stat_info_t* info)
+{
+    memset(info, 0, sizeof(stat_info_t));
+
+    myst_spin_lock(process->thread_lock);
+    {
+        _sum_locked(process, &info->self);
+        info->children = process->main.children_rusage;
+        info->rss = process->main.rss;
+
+        for (myst_thread_t* t = process; t; t = t->group_next)
+        {
+            if (t->status != MYST_ZOMBIE)
+                info->num_threads++;
+        }
+    }
+    myst_spin_unlock(process->thread_lock);
+
+    /* only the calling process is known to be running */
+    if (process == myst_find_process_thread(myst_thread_self()))
+        info->state = 'R';
+    else
+        info->state = 'S';
+}
+
+/* append the output of snprintf(line, size, ...), which returned n */
+static int _append(myst_buf_t* buf, const char* line, size_t size, int n)
+{
+    if (n < 0 || (size_t)n >= size)
+        return -EINVAL;
+
+    return myst_buf_append(buf, line, (size_t)n);
+}
+
+int myst_rusage_format_stat(myst_thread_t* process, myst_buf_t* buf)
+{
+    int ret = 0;
+    stat_info_t info;
+    char line[1024];
+    const size_t n = sizeof(line);
+    long start_nsecs;
+    int r;
+
+    if (!process || !buf)
+        ERAISE(-EINVAL);
+
+    _get_stat_info(process, &info);
+    start_nsecs =
+        process->start_ts.tv_sec * NANO_IN_SECOND + process->start_ts.tv_nsec;
+
+    /* fields 1-22: pid, comm, state, ppid, pgrp, session, tty_nr, tpgid,
+     * flags, minflt, cminflt, majflt, cmajflt, utime, stime, cutime, cstime,
+     * priority, nice, num_threads, itrealvalue, starttime */
+    r = snprintf(
+        line,
+        n,
+        "%d (%s) %c %d %d %d 0 -1 0 0 0 0 0 %lu %lu %lu %lu %d %d %zu 0 %lu ",
+        process->pid,
+        myst_get_thread_name(process),
+        info.state,
+        process->ppid,
+        process->pid,
+        process->sid,
+        _ticks(info.self.utime),
+        _ticks(info.self.stime),
+        _ticks(info.children.utime),
+        _ticks(info.children.stime),
+        20 + process->sched.nice,
+        process->sched.nice,
+        info.num_threads,
+        _ticks(start_nsecs));
+    ECHECK(_append(buf, line, n, r));
+
+    /* fields 23-52: vsize, rss, rsslim, and so on; enclave memory is always
+     * resident so vsize and rss describe the same memory; exit_signal is
+     * SIGCHLD and the scheduling policy is the one set by the application */
+    r = snprintf(
+        line,
+        n,
+        "%zu %zu %lu 0 0 0 0 0 0 0 0 0 0 0 0 17 0 0 %d 0 0 0 0 0 0 0 0 0 0 0\n",
+        info.rss,
+        info.rss / PAGE_SIZE,
+        (unsigned long)RLIM_INFINITY,
+        process->sched.policy);
+    ECHECK(_append(buf, line, n, r));
+
+done:
+    return ret;
+}
+
+int myst_rusage_format_status(myst_thread_t* process, myst_buf_t* buf)
+{
+    int ret = 0;
+    stat_info_t info;
+    char line[1024];
+    const size_t n = sizeof(line);
+    int r;
+
+    if (!process || !buf)
+        ERAISE(-EINVAL);
+
+    _get_stat_info(process, &info);
+
+    r = snprintf(
+        line,
+        n,
+        "Name:\t%s\n"
+        "State:\t%c\n"
+        "Tgid:\t%d\n"
+        "Pid:\t%d\n"
+        "PPid:\t%d\n"
+        "FDSize:\t%d\n"
+        "FDCount:\t%zu\n"
+        "VmHWM:\t%8zu kB\n"
+        "VmRSS:\t%8zu kB\n"
+        "Threads:\t%zu\n"
+        "voluntary_ctxt_switches:\t%ld\n"
+        "nonvoluntary_ctxt_switches:\t%ld\n",
+        myst_get_thread_name(process),
+        info.state,
+        process->pid,
+        process->pid,
+        process->ppid,
+        MYST_FDTABLE_SIZE,
+        myst_fdtable_count(process->fdtable),
+        info.self.maxrss / 1024,
+        info.rss / 1024,
+        info.num_threads,
+        info.self.nvcsw,
+        info.self.nivcsw);
+    ECHECK(_append(buf, line, n, r));
+
+done:
+    return ret;
+}
@@ -0,0 +1,428 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <sched.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <sys/syscall.h>
+
+#include <myst/eraise.h>
+#include <myst/kernel.h>
+#include <myst/sched.h>
+#include <myst/spinlock.h>
+#include <myst/tcall.h>
+#include <myst/thread.h>
+
+/*
+**==============================================================================
+**
+** Thread scheduling attributes:
+**
+** Every guest thread runs on its own host thread, so the guest scheduling
+** attributes are mapped onto that host thread: the nice value is passed to
+** the host with setpriority() and SCHED_IDLE threads get the lowest host
+** priority. The real-time policies are recorded and reported back but do
+** not change the host scheduling (that would need host privileges).
+**
+** ATTN: there is no scheduler over the pool of enclave threads (TCSs): a
+** guest thread is bound to the TCS it starts on until it exits, and moving
+** a blocked thread off its TCS would need the kernel to switch contexts
+** between guest threads. Starvation is therefore up to the host scheduler
+** (see doc/kernel-limitations.md).
+**
+**==============================================================================
+*/
+
+#define NICE_MIN -20
+#define NICE_MAX 19
+
+/* the nice value given to the host thread for the calling thread */
+static int _host_nice(const myst_thread_t* thread)
+{
+    if (thread->sched.policy == SCHED_IDLE)
+        return NICE_MAX;
+
+    return thread->sched.nice;
+}
+
+void myst_sched_clone(const myst_thread_t* parent, myst_thread_t* child)
+{
+    child->sched = parent->sched;
+
+    /* SCHED_RESET_ON_FORK reverts the child to the default attributes */
+    if (parent->sched.reset_on_fork)
+    {
+        child->sched.policy = SCHED_OTHER;
+        child->sched.priority = 0;
+        child->sched.reset_on_fork = 0;
+
+        if (child->sched.nice < 0)
+            child->sched.nice = 0;
+    }
+}
+
+void myst_sched_apply(void)
+{
+    myst_thread_t* thread = myst_thread_self();
+    long params[6] = {PRIO_PROCESS, 0, _host_nice(thread)};
+
+    /* best effort: the host may not allow raising the priority */
+    myst_tcall(SYS_setpriority, params);
+}
+
+/* resolve the thread targeted by a scheduling call (0 means the caller) */
+static myst_thread_t* _find_thread(pid_t pid)
+{
+    if (pid == 0)
+        return myst_thread_self();
+
+    return myst_find_thread(pid);
+}
+
+/* ATTN: the host thread of another thread is only updated when that thread
+ * changes its own attributes, since the host thread cannot be addressed */
+static void _changed(myst_thread_t* thread)
+{
+    if (thread == myst_thread_self())
+        myst_sched_apply();
+}
+
+long myst_syscall_getpriority(int which, id_t who)
+{
+    long ret = 0;
+    myst_thread_t* thread;
+
+    if (which != PRIO_PROCESS)
+        ERAISE(-EINVAL);
+
+    if (!(thread = _find_thread((pid_t)who)))
+        ERAISE(-ESRCH);
+
+    /* the system call returns 20 - nice so that the result is positive */
+    ret = 20 - thread->sched.nice;
+
+done:
+    return ret;
+}
+
+long myst_syscall_setpriority(int which, id_t who, int prio)
+{
+    long ret = 0;
+    myst_thread_t* thread;
+
+    if (which != PRIO_PROCESS)
+        ERAISE(-EINVAL);
+
+    if (!(thread = _find_thread((pid_t)who)))
+        ERAISE(-ESRCH);
+
+    if (prio < NICE_MIN)
+        prio = NICE_MIN;
+    else if (prio > NICE_MAX)
+        prio = NICE_MAX;
+
+    thread->sched.nice = prio;
+    _changed(thread);
+
+done:
+    return ret;
+}
+
+long myst_syscall_sched_get_priority_max(int policy)
+{
+    switch (policy)
+    {
+        case SCHED_FIFO:
+        case SCHED_RR:
+            return 99;
+        case SCHED_OTHER:
+        case SCHED_BATCH:
+        case SCHED_IDLE:
+            return 0;
+        default:
+            return -EINVAL;
+    }
+}
+
+long myst_syscall_sched_get_priority_min(int policy)
+{
+    switch (policy)
+    {
+        case SCHED_FIFO:
+        case SCHED_RR:
+            return 1;
+        case SCHED_OTHER:
+        case SCHED_BATCH:
+        case SCHED_IDLE:
+            return 0;
+        default:
+            return -EINVAL;
+    }
+}
+
+static long _check_priority(int policy, const struct sched_param* param)
+{
+    long min;
+    long max;
+
+    if (!param)
+        return -EINVAL;
+
+    if ((min = myst_syscall_sched_get_priority_min(policy)) < 0)
+        return min;
+
+    max = myst_syscall_sched_get_priority_max(policy);
+
+    if (param->sched_priority < min || param->sched_priority > max)
+        return -EINVAL;
+
+    return 0;
+}
+
+long myst_syscall_sched_setscheduler(
+    pid_t pid,
+    int policy,
+    const struct sched_param* param)
+{
+    long ret = 0;
+    myst_thread_t* thread;
+    const int reset_on_fork = (policy & SCHED_RESET_ON_FORK) ? 1 : 0;
+
+    if (pid < 0)
+        ERAISE(-EINVAL);
+
+    policy &= ~SCHED_RESET_ON_FORK;
+    ECHECK(_check_priority(policy, param));
+
+    if (!(thread = _find_thread(pid)))
+        ERAISE(-ESRCH);
+
+    thread->sched.policy = policy;
+    thread->sched.priority = param->sched_priority;
+    thread->sched.reset_on_fork = reset_on_fork;
+    _changed(thread);
+
+done:
+    return ret;
+}
+
+long myst_syscall_sched_getscheduler(pid_t pid)
+{
+    long ret = 0;
+    myst_thread_t* thread;
+
+    if (pid < 0)
+        ERAISE(-EINVAL);
+
+    if (!(thread = _find_thread(pid)))
+        ERAISE(-ESRCH);
+
+    ret = thread->sched.policy;
+
+    if (thread->sched.reset_on_fork)
+        ret |= SCHED_RESET_ON_FORK;
+
+done:
+    return ret;
+}
+
+long myst_syscall_sched_setparam(pid_t pid, const struct sched_param* param)
+{
+    long ret = 0;
+    myst_thread_t* thread;
+
+    if (pid < 0)
+        ERAISE(-EINVAL);
+
+    if (!(thread = _find_thread(pid)))
+        ERAISE(-ESRCH);
+
+    ECHECK(_check_priority(thread->sched.policy, param));
+
+    thread->sched.priority = param->sched_priority;
+    _changed(thread);
+
+done:
+    return ret;
+}
+
+long myst_syscall_sched_getparam(pid_t pid, struct sched_param* param)
+{
+    long ret = 0;
+    myst_thread_t* thread;
+
+    if (pid < 0 || !param)
+        ERAISE(-EINVAL);
+
+    if (!(thread = _find_thread(pid)))
+        ERAISE(-ESRCH);
+
+    param->sched_priority = thread->sched.priority;
+
+done:
+    return ret;
+}
+
+/*
+**==============================================================================
+**
+** Virtual CPUs:
+**
+** On SGX the application sees one virtual CPU per enclave thread (which is
+** also the processor count reported by CPUID); on Linux it sees the host's
+** CPUs. A thread takes the lowest free virtual CPU when it starts and keeps
+** it until it exits, so getcpu() never changes during the life of a thread.
+** Running threads only share a virtual CPU when there are more threads than
+** virtual CPUs, which cannot happen on SGX.
+**
+**==============================================================================
+*/
+
+static myst_spinlock_t _cpus_lock = MYST_SPINLOCK_INITIALIZER;
+static uint64_t _cpus_used[CPU_SETSIZE / 64];
+
+size_t myst_get_num_cpus(void)
+{
+    size_t n = __myst_kernel_args.num_cpus;
+
+    if (n == 0)
+        return 1;
+
+    if (n > CPU_SETSIZE)
+        return CPU_SETSIZE;
+
+    return n;
+}
+
+void myst_sched_take_cpu(struct myst_thread* thread)
+{
+    const size_t n = myst_get_num_cpus();
+
+    /* shared with another thread unless a free one is found */
+    thread->sched.cpu = (int)((size_t)thread->tid % n);
+    thread->sched.cpu_held = 0;
+
+    myst_spin_lock(&_cpus_lock);
+
+    for (size_t i = 0; i < n; i++)
+    {
+        if (!(_cpus_used[i / 64] & (1UL << (i % 64))))
+        {
+            _cpus_used[i / 64] |= (1UL << (i % 64));
+            thread->sched.cpu = (int)i;
+            thread->sched.cpu_held = 1;
+            break;
+        }
+    }
+
+    myst_spin_unlock(&_cpus_lock);
+}
+
+void myst_sched_release_cpu(struct myst_thread* thread)
+{
+    if (thread->sched.cpu_held)
+    {
+        const size_t i = (size_t)thread->sched.cpu;
+
+        myst_spin_lock(&_cpus_lock);
+        _cpus_used[i / 64] &= ~(1UL << (i % 64));
+        myst_spin_unlock(&_cpus_lock);
+
+        thread->sched.cpu_held = 0;
+    }
+}
+
+long myst_syscall_getcpu(unsigned* cpu, unsigned* node)
+{
+    myst_thread_t* thread = myst_thread_self();
+
+    if (cpu)
+        *cpu = (unsigned)thread->sched.cpu;
+
+    /* a single NUMA node */
+    if (node)
+        *node = 0;
+
+    return 0;
+}
+
+/* the size of the kernel's CPU mask: enough longs for every virtual CPU */
+static size_t _cpu_mask_size(void)
+{
+    const size_t bits = 8 * sizeof(unsigned long);
+    return (myst_get_num_cpus() + bits - 1) / bits * sizeof(unsigned long);
+}
+
+long myst_syscall_sched_getaffinity(
+    pid_t pid,
+    size_t cpusetsize,
+    cpu_set_t* mask)
+{
+    long ret = 0;
+    const size_t size = _cpu_mask_size();
+    const size_t n = myst_get_num_cpus();
+
+    if (pid < 0)
+        ERAISE(-ESRCH);
+
+    if (!_find_thread(pid))
+        ERAISE(-ESRCH);
+
+    if (cpusetsize < size || cpusetsize % sizeof(unsigned long))
+        ERAISE(-EINVAL);
+
+    if (!mask)
+        ERAISE(-EFAULT);
+
+    /* every thread may run on any virtual CPU */
+    memset(mask, 0, size);
+
+    for (size_t i = 0; i < n; i++)
+        CPU_SET_S(i, size, mask);
+
+    /* like Linux, return the number of bytes written */
+    ret = (long)size;
+
+done:
+    return ret;
+}
+
+long myst_syscall_sched_setaffinity(
+    pid_t pid,
+    size_t cpusetsize,
+    const cpu_set_t* mask)
+{
+    long ret = 0;
+    const size_t n = myst_get_num_cpus();
+    bool found = false;
+
+    if (pid < 0)
+        ERAISE(-ESRCH);
+
+    if (!_find_thread(pid))
+        ERAISE(-ESRCH);
+
+    if (!mask)
+        ERAISE(-EFAULT);
+
+    /* the mask must include at least one virtual CPU */
+    for (size_t i = 0; i < n && i < 8 * cpusetsize; i++)
+    {
+        if (CPU_ISSET_S(i, cpusetsize, mask))
+        {
+            found = true;
+            break;
+        }
+    }
+
+    if (!found)
+        ERAISE(-EINVAL);
+
+    /* ATTN: the host threads are not pinned, and getcpu() keeps reporting
+     * the virtual CPU the thread started on */
+
+done:
+    return ret;
+}
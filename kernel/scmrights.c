@@ -0,0 +1,562 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <stdlib.h>
+#include <string.h>
+#include <syscall.h>
+
+#include <myst/eraise.h>
+#include <myst/fdops.h>
+#include <myst/scmrights.h>
+#include <myst/spinlock.h>
+#include <myst/syscall.h>
+#include <myst/tcall.h>
+
+/*
+**==============================================================================
+**
+** SCM_RIGHTS:
+**
+** Sockets are host sockets, but the descriptors a process sends over them
+** are kernel descriptors that mean nothing to the host. So sendmsg() replaces
+** them with a carrier: one end of a new host socket pair, which the host
+** passes to the receiver like any other descriptor. The sender writes a random
+** ticket into the other end and keeps it, along with a duplicate of each
+** descriptor (see fd_dup), in the list of messages in flight. When recvmsg()
+** gets a carrier, it reads the ticket, takes the matching descriptors from the
+** list and installs them in the receiver's fdtable in place of the carrier.
+**
+** If the message is never received (for example, the receiving socket is
+** closed first), the host closes the carrier and the sender's end reports a
+** hangup: the next sendmsg() then drops the descriptors of that message.
+**
+** The ticket travels in the clear through a host socket, and is bound
+** neither to the sender nor to the receiver. So the host can read it and
+** pass a carrier of its own with that ticket to any process of the enclave
+** that receives on a host socket, which then gets the descriptors instead
+** of the intended receiver. A ticket is claimed only once, so the host
+** cannot replay it, but it can redirect the descriptors or drop them.
+**
+**==============================================================================
+*/
+
+struct myst_scm_rights
+{
+    myst_scm_rights_t* next;
+    uint64_t ticket;
+    int peer;      /* the host end that the kernel keeps (or -1) */
+    int carrier;   /* the host end that is sent (or -1) */
+    void* control; /* the control buffer that is sent */
+    size_t count;
+    myst_fdtable_entry_t entries[];
+};
+
+/* the messages in flight */
+static myst_scm_rights_t* _list;
+static myst_spinlock_t _lock = MYST_SPINLOCK_INITIALIZER;
+
+static void _host_close(int fd)
+{
+    if (fd >= 0)
+    {
+        long params[6] = {fd};
+        myst_tcall(SYS_close, params);
+    }
+}
+
+static myst_scm_rights_t* _new_rights(size_t count)
+{
+    myst_scm_rights_t* p;
+    const size_t size = sizeof(myst_fdtable_entry_t) * count;
+
+    if (!(p = calloc(1, sizeof(myst_scm_rights_t) + size)))
+        return NULL;
+
+    p->peer = -1;
+    p->carrier = -1;
+    return p;
+}
+
+/* closes the descriptors that were not installed */
+static void _free_rights(myst_scm_rights_t* p)
+{
+    for (size_t i = 0; i < p->count; i++)
+    {
+        myst_fdtable_entry_t* entry = &p->entries[i];
+
+        if (entry->type != MYST_FDTABLE_TYPE_NONE)
+        {
+            myst_fdops_t* fdops = entry->device;
+            (*fdops->fd_close)(fdops, entry->object);
+        }
+    }
+
+    _host_close(p->peer);
+    _host_close(p->carrier);
+    free(p->control);
+    free(p);
+}
+
+/* drop the messages whose carriers the host has closed */
+static void _sweep(void)
+{
+    myst_scm_rights_t* dead = NULL;
+    struct pollfd* fds = NULL;
+    uint64_t* tickets = NULL;
+    size_t n = 0;
+
+    /* collect the peers under the lock, but poll the host without it */
+    myst_spin_lock(&_lock);
+    {
+        size_t count = 0;
+
+        for (myst_scm_rights_t* p = _list; p; p = p->next)
+            count++;
+
+        if (count && (fds = calloc(count, sizeof(struct pollfd))) &&
+            (tickets = calloc(count, sizeof(uint64_t))))
+        {
+            for (myst_scm_rights_t* p = _list; p; p = p->next, n++)
+            {
+                fds[n].fd = p->peer;
+                fds[n].events = POLLIN;
+                tickets[n] = p->ticket;
+            }
+        }
+    }
+    myst_spin_unlock(&_lock);
+
+    if (n)
+    {
+        long params[6] = {(long)fds, (long)n, 0};
+
+        if (myst_tcall(SYS_poll, params) <= 0)
+            n = 0;
+    }
+
+    /* a message that was received meanwhile is no longer in the list (and
+     * its peer may be another descriptor now), so match the ticket too */
+    myst_spin_lock(&_lock);
+    for (size_t i = 0; i < n; i++)
+    {
+        if (!(fds[i].revents & (POLLHUP | POLLERR | POLLNVAL)))
+            continue;
+
+        for (myst_scm_rights_t** p = &_list; *p; p = &(*p)->next)
+        {
+            myst_scm_rights_t* q = *p;
+
+            if (q->ticket == tickets[i] && q->peer == fds[i].fd)
+            {
+                *p = q->next;
+                q->next = dead;
+                dead = q;
+                break;
+            }
+        }
+    }
+    myst_spin_unlock(&_lock);
+
+    free(fds);
+    free(tickets);
+
+    while (dead)
+    {
+        myst_scm_rights_t* next = dead->next;
+        _free_rights(dead);
+        dead = next;
+    }
+}
+
+/* the number of descriptors in a SCM_RIGHTS message */
+static size_t _num_fds(const struct cmsghdr* cmsg)
+{
+    return (cmsg->cmsg_len - CMSG_LEN(0)) / sizeof(int);
+}
+
+static bool _is_rights(const struct cmsghdr* cmsg)
+{
+    return cmsg->cmsg_level == SOL_SOCKET && cmsg->cmsg_type == SCM_RIGHTS;
+}
+
+/* whether the message lies within the control buffer */
+static bool _valid_cmsg(const struct msghdr* msg, const struct cmsghdr* cmsg)
+{
+    const char* end = (const char*)msg->msg_control + msg->msg_controllen;
+
+    return cmsg->cmsg_len >= CMSG_LEN(0) &&
+           cmsg->cmsg_len <= (size_t)(end - (const char*)cmsg);
+}
+
+int myst_scm_rights_send(
+    myst_fdtable_t* fdtable,
+    const struct msghdr* msg,
+    struct msghdr* msg_out,
+    myst_scm_rights_t** rights_out)
+{
+    int ret = 0;
+    myst_scm_rights_t* rights = NULL;
+    size_t nfds = 0;
+    size_t size = CMSG_SPACE(sizeof(int));
+    struct msghdr* m = (struct msghdr*)msg;
+    struct cmsghdr* out;
+    int sv[2];
+
+    if (rights_out)
+        *rights_out = NULL;
+
+    if (!fdtable || !msg || !msg_out || !rights_out)
+        ERAISE(-EINVAL);
+
+    *msg_out = *msg;
+
+    if (!msg->msg_control || msg->msg_controllen < sizeof(struct cmsghdr))
+        goto done;
+
+    /* count the descriptors and find the size of the other messages */
+    for (struct cmsghdr* c = CMSG_FIRSTHDR(m); c; c = CMSG_NXTHDR(m, c))
+    {
+        if (!_valid_cmsg(m, c))
+            ERAISE(-EINVAL);
+
+        if (_is_rights(c))
+            nfds += _num_fds(c);
+        else
+            size += CMSG_SPACE(c->cmsg_len - CMSG_LEN(0));
+    }
+
+    if (nfds == 0)
+        goto done;
+
+    if (nfds > MYST_SCM_MAX_FD)
+        ERAISE(-EINVAL);
+
+    if (!(rights = _new_rights(nfds)))
+        ERAISE(-ENOMEM);
+
+    /* take a reference to each descriptor */
+    for (struct cmsghdr* c = CMSG_FIRSTHDR(m); c; c = CMSG_NXTHDR(m, c))
+    {
+        const int* fds = (const int*)CMSG_DATA(c);
+
+        if (!_is_rights(c))
+            continue;
+
+        for (size_t i = 0; i < _num_fds(c); i++)
+        {
+            myst_fdtable_entry_t* entry = &rights->entries[rights->count];
+            myst_fdtable_type_t type;
+            void* device;
+            void* object;
+            myst_fdops_t* fdops;
+
+            if (myst_fdtable_get_any(fdtable, fds[i], &type, &device, &object))
+                ERAISE(-EBADF);
+
+            fdops = device;
+            ECHECK((*fdops->fd_dup)(device, object, &entry->object));
+            entry->type = type;
+            entry->device = device;
+            rights->count++;
+        }
+    }
+
+    if (myst_syscall_getrandom(&rights->ticket, sizeof(uint64_t), 0) !=
+        sizeof(uint64_t))
+    {
+        ERAISE(-EIO);
+    }
+
+    /* create the carrier and write the ticket into it */
+    {
+        long params[6] = {AF_UNIX, SOCK_STREAM | SOCK_CLOEXEC, 0, (long)sv};
+        ECHECK(myst_tcall(SYS_socketpair, params));
+        rights->carrier = sv[0];
+        rights->peer = sv[1];
+    }
+    {
+        const size_t n = sizeof(uint64_t);
+        long params[6] = {rights->peer, (long)&rights->ticket, n};
+
+        if (myst_tcall(SYS_sendto, params) != (long)n)
+            ERAISE(-EIO);
+    }
+
+    /* copy the other messages and add one carrying the carrier */
+    if (!(rights->control = calloc(1, size)))
+        ERAISE(-ENOMEM);
+
+    out = rights->control;
+
+    for (struct cmsghdr* c = CMSG_FIRSTHDR(m); c; c = CMSG_NXTHDR(m, c))
+    {
+        if (!_is_rights(c))
+        {
+            memcpy(out, c, c->cmsg_len);
+            out = (void*)((char*)out + CMSG_SPACE(c->cmsg_len - CMSG_LEN(0)));
+        }
+    }
+
+    out->cmsg_level = SOL_SOCKET;
+    out->cmsg_type = SCM_RIGHTS;
+    out->cmsg_len = CMSG_LEN(sizeof(int));
+    memcpy(CMSG_DATA(out), &rights->carrier, sizeof(int));
+
+    msg_out->msg_control = rights->control;
+    msg_out->msg_controllen = size;
+
+    *rights_out = rights;
+    rights = NULL;
+
+done:
+
+    if (rights)
+        _free_rights(rights);
+
+    return ret;
+}
+
+void myst_scm_rights_sent(myst_scm_rights_t* rights, bool sent)
+{
+    if (!rights)
+        return;
+
+    /* the host holds the carrier while the message is in flight */
+    _host_close(rights->carrier);
+    rights->carrier = -1;
+    free(rights->control);
+    rights->control = NULL;
+
+    if (!sent)
+    {
+        _free_rights(rights);
+        return;
+    }
+
+    _sweep();
+
+    myst_spin_lock(&_lock);
+    rights->next = _list;
+    _list = rights;
+    myst_spin_unlock(&_lock);
+}
+
+/* find the message a carrier belongs to: remove it from the list, or copy it
+ * if the receiver only peeks at the message */
+static myst_scm_rights_t* _claim(int carrier, bool peek)
+{
+    myst_scm_rights_t* ret = NULL;
+    uint64_t ticket;
+    const size_t n = sizeof(ticket);
+    bool failed = false;
+
+    {
+        int flags = MSG_DONTWAIT | (peek ? MSG_PEEK : 0);
+        long params[6] = {carrier, (long)&ticket, n, flags};
+
+        /* a host descriptor that is not a carrier has no ticket */
+        if (myst_tcall(SYS_recvfrom, params) != (long)n)
+            return NULL;
+    }
+
+    myst_spin_lock(&_lock);
+    {
+        for (myst_scm_rights_t** p = &_list; *p; p = &(*p)->next)
+        {
+            myst_scm_rights_t* q = *p;
+
+            if (q->ticket != ticket)
+                continue;
+
+            if (!peek)
+            {
+                *p = q->next;
+                ret = q;
+                break;
+            }
+
+            if (!(ret = _new_rights(q->count)))
+                break;
+
+            for (size_t i = 0; i < q->count; i++)
+            {
+                const myst_fdtable_entry_t* entry = &q->entries[i];
+                myst_fdops_t* fdops = entry->device;
+                void* object;
+
+                if ((*fdops->fd_dup)(fdops, entry->object, &object) != 0)
+                {
+                    failed = true;
+                    break;
+                }
+
+                ret->entries[i] = *entry;
+                ret->entries[i].object = object;
+                ret->count++;
+            }
+
+            break;
+        }
+    }
+    myst_spin_unlock(&_lock);
+
+    if (failed)
+    {
+        _free_rights(ret);
+        return NULL;
+    }
+
+    return ret;
+}
+
+/* install the descriptors of the carriers of a host SCM_RIGHTS message, as
+ * many as fit in fds[] */
+static size_t _install(
+    myst_fdtable_t* fdtable,
+    const struct cmsghdr* cmsg,
+    int* fds,
+    size_t max,
+    int flags,
+    bool* truncated)
+{
+    size_t n = 0;
+    const int* carriers = (const int*)CMSG_DATA(cmsg);
+    const int cloexec = (flags & MSG_CMSG_CLOEXEC) ? FD_CLOEXEC : 0;
+
+    for (size_t i = 0; i < _num_fds(cmsg); i++)
+    {
+        myst_scm_rights_t* rights;
+
+        rights = _claim(carriers[i], (flags & MSG_PEEK));
+        _host_close(carriers[i]);
+
+        if (!rights)
+            continue;
+
+        for (size_t j = 0; j < rights->count; j++)
+        {
+            myst_fdtable_entry_t* entry = &rights->entries[j];
+            myst_fdops_t* fdops = entry->device;
+            int fd;
+
+            if (n == max)
+            {
+                *truncated = true;
+                break;
+            }
+
+            fd = myst_fdtable_assign(
+                fdtable, entry->type, entry->device, entry->object);
+
+            if (fd < 0)
+            {
+                *truncated = true;
+                break;
+            }
+
+            /* received descriptors only have the flag the receiver asks for */
+            (*fdops->fd_fcntl)(fdops, entry->object, F_SETFD, cloexec);
+
+            memset(entry, 0, sizeof(myst_fdtable_entry_t));
+            fds[n++] = fd;
+        }
+
+        _free_rights(rights);
+    }
+
+    return n;
+}
+
+void myst_scm_rights_recv(
+    myst_fdtable_t* fdtable,
+    struct msghdr* msg,
+    socklen_t size,
+    int flags)
+{
+    struct msghdr m;
+    void* control = NULL;
+    size_t offset = 0;
+    bool truncated = false;
+
+    if (!fdtable || !msg || !msg->msg_control)
+        return;
+
+    /* the host may report a longer length if the messages were truncated */
+    if (msg->msg_controllen > size)
+        msg->msg_controllen = size;
+
+    if (msg->msg_controllen < sizeof(struct cmsghdr))
+        return;
+
+    /* walk a copy of the host messages while rewriting the caller's buffer */
+    if (!(control = malloc(msg->msg_controllen)))
+    {
+        msg->msg_controllen = 0;
+        msg->msg_flags |= MSG_CTRUNC;
+        return;
+    }
+
+    memcpy(control, msg->msg_control, msg->msg_controllen);
+    memset(&m, 0, sizeof(m));
+    m.msg_control = control;
+    m.msg_controllen = msg->msg_controllen;
+
+    for (struct cmsghdr* c = CMSG_FIRSTHDR(&m); c; c = CMSG_NXTHDR(&m, c))
+    {
+        struct cmsghdr* out = (void*)((char*)msg->msg_control + offset);
+        const size_t avail = size - offset;
+        size_t len;
+
+        if (!_valid_cmsg(&m, c))
+            break;
+
+        if (_is_rights(c))
+        {
+            int fds[MYST_SCM_MAX_FD];
+            size_t max = 0;
+            size_t n;
+
+            if (avail >= CMSG_LEN(sizeof(int)))
+                max = (avail - CMSG_LEN(0)) / sizeof(int);
+
+            if (max > MYST_SCM_MAX_FD)
+                max = MYST_SCM_MAX_FD;
+
+            if (!(n = _install(fdtable, c, fds, max, flags, &truncated)))
+                continue;
+
+            out->cmsg_level = SOL_SOCKET;
+            out->cmsg_type = SCM_RIGHTS;
+            out->cmsg_len = CMSG_LEN(n * sizeof(int));
+            memcpy(CMSG_DATA(out), fds, n * sizeof(int));
+            len = n * sizeof(int);
+        }
+        else
+        {
+            /* a message that came after truncated descriptors */
+            if (c->cmsg_len > avail)
+            {
+                truncated = true;
+                continue;
+            }
+
+            memcpy(out, c, c->cmsg_len);
+            len = c->cmsg_len - CMSG_LEN(0);
+        }
+
+        offset += CMSG_SPACE(len);
+
+        if (offset > size)
+            offset = size;
+    }
+
+    msg->msg_controllen = offset;
+
+    if (truncated)
+        msg->msg_flags |= MSG_CTRUNC;
+
+    free(control);
+}
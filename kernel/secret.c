@@ -0,0 +1,358 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#include <myst/atexit.h>
+#include <myst/audit.h>
+#include <myst/defs.h>
+#include <myst/eraise.h>
+#include <myst/errno.h>
+#include <myst/file.h>
+#include <myst/paths.h>
+#include <myst/printf.h>
+#include <myst/secret.h>
+
+/*
+**==============================================================================
+**
+** Secrets:
+**
+** Before the application starts, the kernel fetches each secret listed in
+** the "Secrets" section of config.json from the named provider, then writes
+** it to a file in the root file system or adds it to the environment.
+** Providers are registered by name:
+**
+**     "key-release"     releases a key from Azure Key Vault (keyrelease.c)
+**     "sealed-file"     unseals a file sealed to the enclave's signer
+**     "attested-https"  fetches a URL with an attested client certificate
+**
+** Additional providers are registered by myst_register_custom_secret_providers,
+** which a kernel built with them defines.
+**
+**==============================================================================
+*/
+
+static myst_secret_provider_t* _providers;
+
+/* the environment passed to the application (freed at exit) */
+static const char** _envp;
+static char** _env_strings;
+static size_t _num_env_strings;
+
+MYST_WEAK
+void myst_register_custom_secret_providers(void)
+{
+}
+
+int myst_register_secret_provider(myst_secret_provider_t* provider)
+{
+    int ret = 0;
+
+    if (!provider || !provider->name || !provider->get)
+        ERAISE(-EINVAL);
+
+    if (myst_find_secret_provider(provider->name))
+        ERAISE(-EEXIST);
+
+    provider->next = _providers;
+    _providers = provider;
+
+done:
+    return ret;
+}
+
+myst_secret_provider_t* myst_find_secret_provider(const char* name)
+{
+    for (myst_secret_provider_t* p = _providers; p; p = p->next)
+    {
+        if (strcmp(p->name, name) == 0)
+            return p;
+    }
+
+    return NULL;
+}
+
+static void _release_providers(void* arg)
+{
+    (void)arg;
+
+    for (myst_secret_provider_t* p = _providers; p; p = p->next)
+    {
+        if (p->release)
+            (*p->release)(p);
+    }
+}
+
+static void _free_env(void* arg)
+{
+    (void)arg;
+
+    for (size_t i = 0; i < _num_env_strings; i++)
+    {
+        /* the strings hold secrets */
+        memset(_env_strings[i], 0, strlen(_env_strings[i]));
+        free(_env_strings[i]);
+    }
+
+    free(_env_strings);
+    free(_envp);
+    _envp = NULL;
+    _env_strings = NULL;
+    _num_env_strings = 0;
+}
+
+static bool _valid_env_name(const char* name)
+{
+    if (!*name)
+        return false;
+
+    for (const char* p = name; *p; p++)
+    {
+        if (*p == '=')
+            return false;
+    }
+
+    return true;
+}
+
+static int _write_file(const char* path, const void* data, size_t size)
+{
+    int ret = 0;
+    int fd = -1;
+    char dirname[PATH_MAX];
+    char basename[PATH_MAX];
+
+    if (path[0] != '/')
+        ERAISE(-EINVAL);
+
+    ECHECK(myst_split_path(
+        path, dirname, sizeof(dirname), basename, sizeof(basename)));
+
+    if (myst_mkdirhier(dirname, 0700) != 0)
+        ERAISE(-EINVAL);
+
+    if ((fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, 0400)) < 0)
+        ERAISE(fd);
+
+    if (myst_writen(fd, data, size) != 0)
+        ERAISE(-EIO);
+
+done:
+
+    if (fd >= 0)
+        close(fd);
+
+    return ret;
+}
+
+/* format name=<hex> */
+static char* _format_env(const char* name, const uint8_t* data, size_t size)
+{
+    static const char digits[] = "0123456789abcdef";
+    const size_t len = strlen(name);
+    char* str;
+    char* p;
+
+    if (!(str = malloc(len + 1 + 2 * size + 1)))
+        return NULL;
+
+    memcpy(str, name, len);
+    p = str + len;
+    *p++ = '=';
+
+    for (size_t i = 0; i < size; i++)
+    {
+        *p++ = digits[data[i] >> 4];
+        *p++ = digits[data[i] & 0xf];
+    }
+
+    *p = '\0';
+
+    return str;
+}
+
+/* add name=value to the environment, replacing an earlier definition */
+static void _set_env(const char** envp, size_t* envc, const char* str)
+{
+    const size_t len = strchr(str, '=') - str + 1;
+
+    for (size_t i = 0; i < *envc; i++)
+    {
+        if (strncmp(envp[i], str, len) == 0)
+        {
+            envp[i] = str;
+            return;
+        }
+    }
+
+    envp[(*envc)++] = str;
+    envp[*envc] = NULL;
+}
+
+static int _store(
+    const myst_secret_t* secret,
+    const uint8_t* data,
+    size_t size,
+    const char** envp,
+    size_t* envc)
+{
+    int ret = 0;
+
+    if (secret->file)
+    {
+        if (_write_file(secret->file, data, size) != 0)
+        {
+            myst_eprintf("kernel: secrets: cannot write %s\n", secret->file);
+            ERAISE(-EIO);
+        }
+    }
+
+    if (secret->environment)
+    {
+        char* str;
+
+        if (!(str = _format_env(secret->environment, data, size)))
+            ERAISE(-ENOMEM);
+
+        _env_strings[_num_env_strings++] = str;
+        _set_env(envp, envc, str);
+    }
+
+done:
+    return ret;
+}
+
+static int _fetch(
+    const myst_secret_t* secret,
+    uint8_t* buf,
+    const char** envp,
+    size_t* envc)
+{
+    int ret = 0;
+    myst_secret_provider_t* provider;
+    size_t size = 0;
+    int r;
+
+    if (!secret->provider || !secret->id ||
+        (!secret->environment && !secret->file))
+    {
+        myst_eprintf("kernel: secrets: each secret needs a Provider, an Id "
+                     "and an Environment or File\n");
+        ERAISE(-EINVAL);
+    }
+
+    if (secret->environment && !_valid_env_name(secret->environment))
+    {
+        myst_eprintf(
+            "kernel: secrets: bad environment variable: %s\n",
+            secret->environment);
+        ERAISE(-EINVAL);
+    }
+
+    if (!(provider = myst_find_secret_provider(secret->provider)))
+    {
+        myst_eprintf("kernel: secrets: no provider: %s\n", secret->provider);
+        ERAISE(-EINVAL);
+    }
+
+    r = (*provider->get)(provider, secret, buf, MYST_SECRET_MAX_SIZE, &size);
+    myst_audit_attestation(provider->name, r);
+
+    if (r == -ENOTSUP)
+    {
+        myst_eprintf(
+            "kernel: secrets: %s is not supported on this target\n",
+            provider->name);
+        ERAISE(-ENOTSUP);
+    }
+    else if (r != 0)
+    {
+        myst_eprintf(
+            "kernel: secrets: %s failed: %s: %s\n",
+            provider->name,
+            secret->id,
+            myst_error_name(-r));
+        ERAISE(r);
+    }
+
+    if (size > MYST_SECRET_MAX_SIZE)
+        ERAISE(-EINVAL);
+
+    ret = _store(secret, buf, size, envp, envc);
+    memset(buf, 0, size);
+
+done:
+    return ret;
+}
+
+int myst_secrets_setup(
+    const myst_secrets_config_t* config,
+    const myst_key_release_config_t* key_release,
+    size_t* envc,
+    const char*** envp)
+{
+    int ret = 0;
+    uint8_t* buf = NULL;
+    const char** new_envp = NULL;
+    size_t new_envc;
+    size_t num_secrets;
+
+    if (!config || !key_release || !envc || !envp)
+        ERAISE(-EINVAL);
+
+    num_secrets = config->num_secrets;
+
+    if (num_secrets == 0)
+        goto done;
+
+    ECHECK(myst_register_key_release_provider(key_release));
+    ECHECK(myst_register_sealed_file_provider());
+    ECHECK(myst_register_attested_https_provider(config->ca_certificates));
+    myst_register_custom_secret_providers();
+    myst_atexit(_release_providers, NULL);
+
+    if (!(buf = malloc(MYST_SECRET_MAX_SIZE)))
+        ERAISE(-ENOMEM);
+
+    /* room for the existing environment, the secrets and the terminator */
+    if (!(new_envp = calloc(*envc + num_secrets + 1, sizeof(char*))))
+        ERAISE(-ENOMEM);
+
+    if (!(_env_strings = calloc(num_secrets + 1, sizeof(char*))))
+        ERAISE(-ENOMEM);
+
+    memcpy(new_envp, *envp, *envc * sizeof(char*));
+    new_envc = *envc;
+
+    for (size_t i = 0; i < config->num_secrets; i++)
+        ECHECK(_fetch(&config->secrets[i], buf, new_envp, &new_envc));
+
+    _envp = new_envp;
+    new_envp = NULL;
+    myst_atexit(_free_env, NULL);
+
+    *envp = _envp;
+    *envc = new_envc;
+
+done:
+
+    if (buf)
+    {
+        memset(buf, 0, MYST_SECRET_MAX_SIZE);
+        free(buf);
+    }
+
+    if (new_envp)
+    {
+        free(new_envp);
+        _free_env(NULL);
+    }
+
+    return ret;
+}
@@ -0,0 +1,147 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <myst/eraise.h>
+#include <myst/file.h>
+#include <myst/printf.h>
+#include <myst/secret.h>
+#include <myst/tcall.h>
+
+/*
+**==============================================================================
+**
+** The "sealed-file" secret provider:
+**
+** The secret's id is a file in the kernel's view of the file system (the
+** root file system or a mount) that holds a blob made by oe_seal(). Only an
+** enclave allowed by the blob's seal policy can unseal it, so the file may
+** come from the host.
+**
+**==============================================================================
+*/
+
+static int _sealed_file_get(
+    myst_secret_provider_t* provider,
+    const myst_secret_t* secret,
+    void* buf,
+    size_t size,
+    size_t* size_out)
+{
+    int ret = 0;
+    void* blob = NULL;
+    size_t blob_size;
+
+    (void)provider;
+
+    if (myst_load_file(secret->id, &blob, &blob_size) != 0)
+    {
+        myst_eprintf("kernel: sealed-file: cannot load %s\n", secret->id);
+        ERAISE(-ENOENT);
+    }
+
+    ECHECK(myst_tcall_unseal_secret(blob, blob_size, buf, size, size_out));
+
+done:
+
+    if (blob)
+        free(blob);
+
+    return ret;
+}
+
+static myst_secret_provider_t _sealed_file_provider = {
+    "sealed-file",
+    _sealed_file_get,
+};
+
+int myst_register_sealed_file_provider(void)
+{
+    return myst_register_secret_provider(&_sealed_file_provider);
+}
+
+/*
+**==============================================================================
+**
+** The "attested-https" secret provider:
+**
+** The secret's id is an HTTPS URL. The target connects with TLS, checks the
+** server against the CA certificates named by Secrets.CaCertificates and
+** presents a client certificate that embeds the enclave's attestation
+** evidence (as returned by SYS_myst_gen_creds). The server verifies the
+** evidence before it returns the secret as the response body.
+**
+**==============================================================================
+*/
+
+typedef struct attested_https_provider
+{
+    myst_secret_provider_t base;
+    const char* ca_certificates;
+
+    /* the CA certificates, loaded on first use */
+    void* ca_data;
+    size_t ca_size;
+} attested_https_provider_t;
+
+static int _attested_https_get(
+    myst_secret_provider_t* provider,
+    const myst_secret_t* secret,
+    void* buf,
+    size_t size,
+    size_t* size_out)
+{
+    int ret = 0;
+    attested_https_provider_t* p = (attested_https_provider_t*)provider;
+    myst_attested_https_request_t request;
+
+    if (!p->ca_certificates)
+    {
+        myst_eprintf("kernel: attested-https: no Secrets.CaCertificates\n");
+        ERAISE(-EINVAL);
+    }
+
+    if (strncmp(secret->id, "https://", 8) != 0)
+    {
+        myst_eprintf("kernel: attested-https: not an HTTPS URL\n");
+        ERAISE(-EINVAL);
+    }
+
+    if (!p->ca_data &&
+        myst_load_file(p->ca_certificates, &p->ca_data, &p->ca_size) != 0)
+    {
+        myst_eprintf(
+            "kernel: attested-https: cannot load %s\n", p->ca_certificates);
+        ERAISE(-ENOENT);
+    }
+
+    request.url = secret->id;
+    request.ca_data = p->ca_data;
+
+    ECHECK(myst_tcall_attested_https_get(&request, buf, size, size_out));
+
+done:
+    return ret;
+}
+
+static void _attested_https_release(myst_secret_provider_t* provider)
+{
+    attested_https_provider_t* p = (attested_https_provider_t*)provider;
+
+    free(p->ca_data);
+    p->ca_data = NULL;
+}
+
+static attested_https_provider_t _attested_https_provider = {
+    .base = {"attested-https", _attested_https_get, _attested_https_release},
+};
+
+int myst_register_attested_https_provider(const char* ca_certificates)
+{
+    _attested_https_provider.ca_certificates = ca_certificates;
+
+    return myst_register_secret_provider(&_attested_https_provider.base);
+}
@@ -9,6 +9,7 @@
 #include <stddef.h>
 #include <stdlib.h>
 
+#include <myst/clock.h>
 #include <myst/defs.h>
 #include <myst/eraise.h>
 #include <myst/fdops.h>
@@ -97,13 +98,9 @@ long myst_syscall_select(
     long ret = 0;
     int num_ready = 0;
     poll_fds_t fds = {0};
-    int poll_timeout = -1;
+    myst_deadline_t deadline;
 
-    if (timeout)
-    {
-        poll_timeout = (int)timeout->tv_sec * 1000;
-        poll_timeout += (int)(timeout->tv_usec / 1000);
-    }
+    ECHECK(myst_deadline_init_timeval(&deadline, timeout));
 
     if (readfds)
     {
@@ -123,7 +120,24 @@ long myst_syscall_select(
         ECHECK(_fdset_to_fds(&fds, events, exceptfds, nfds));
     }
 
-    ECHECK(myst_syscall_poll(fds.data, fds.size, poll_timeout));
+    if (fds.size == 0)
+    {
+        /* no descriptors: just wait for the timeout */
+        int msec = myst_deadline_remaining_msec(&deadline);
+        ECHECK(myst_syscall_poll(NULL, 0, msec));
+    }
+    else
+    {
+        ECHECK(myst_poll_deadline(fds.data, fds.size, &deadline));
+    }
+
+    /* like Linux, leave the time that was not slept in the timeout */
+    if (timeout)
+    {
+        long remaining = myst_deadline_remaining(&deadline);
+        timeout->tv_sec = remaining / NANO_IN_SECOND;
+        timeout->tv_usec = (remaining % NANO_IN_SECOND) / 1000;
+    }
 
     if (readfds)
         FD_ZERO(readfds);
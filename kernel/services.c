@@ -0,0 +1,352 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <sched.h>
+#include <signal.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include <myst/eraise.h>
+#include <myst/errno.h>
+#include <myst/printf.h>
+#include <myst/services.h>
+#include <myst/setjmp.h>
+#include <myst/shutdown.h>
+#include <myst/syscall.h>
+#include <myst/thread.h>
+#include <myst/time.h>
+
+/*
+**==============================================================================
+**
+** Services:
+**
+** If config.json has a "Services" section, the main thread of the kernel
+** acts as an init process (pid 1) instead of running one application. It
+** starts each service in order as a child process: a vfork-style clone whose
+** child thread runs _service_start() on the target stack and execs the
+** service. When a service has a ReadyPath, the next one is started only once
+** that file exists. The supervisor then reaps the services as they exit and
+** starts them again as their restart policy allows. The last service is the
+** main one (the others are the ones it depends on): when it exits for good,
+** its exit status becomes that of the enclave and the others are shut down
+** (see kernel/shutdown.c). A shutdown request from the host stops the
+** supervisor the same way.
+**
+**==============================================================================
+*/
+
+/* how often to check for ReadyPath and for exited services */
+#define POLL_MSEC 10
+
+typedef struct service_state
+{
+    const myst_service_t* service;
+    pid_t pid; /* zero if not running */
+    size_t restarts;
+    char** argv;
+    char** envp;
+} service_state_t;
+
+static const char* _name(const myst_service_t* service)
+{
+    return service->name ? service->name : service->path;
+}
+
+static int _check_restart(const char* restart)
+{
+    if (!restart || strcmp(restart, "never") == 0 ||
+        strcmp(restart, "on-failure") == 0 || strcmp(restart, "always") == 0)
+    {
+        return 0;
+    }
+
+    return -EINVAL;
+}
+
+/* whether the restart policy allows the service to run again */
+static bool _should_restart(service_state_t* state, int wstatus)
+{
+    const myst_service_t* service = state->service;
+    const char* restart = service->restart;
+
+    if (!restart || strcmp(restart, "never") == 0)
+        return false;
+
+    if (strcmp(restart, "on-failure") == 0 && WEXITSTATUS(wstatus) == 0)
+        return false;
+
+    if (service->max_restarts && state->restarts >= service->max_restarts)
+        return false;
+
+    return true;
+}
+
+/* the application environment, with the service's variables replacing any
+ * of the same name */
+static char** _make_envp(
+    const myst_service_t* service,
+    size_t envc,
+    const char* envp[])
+{
+    char** p;
+    size_t n = 0;
+
+    if (!(p = calloc(envc + service->envc + 1, sizeof(char*))))
+        return NULL;
+
+    for (size_t i = 0; i < envc; i++)
+    {
+        const char* eq = strchr(envp[i], '=');
+        size_t len = eq ? (size_t)(eq - envp[i]) + 1 : strlen(envp[i]);
+        bool replaced = false;
+
+        for (size_t j = 0; j < service->envc; j++)
+        {
+            if (strncmp(service->envp[j], envp[i], len) == 0)
+            {
+                replaced = true;
+                break;
+            }
+        }
+
+        if (!replaced)
+            p[n++] = (char*)envp[i];
+    }
+
+    for (size_t i = 0; i < service->envc; i++)
+        p[n++] = service->envp[i];
+
+    return p;
+}
+
+static char** _make_argv(const myst_service_t* service)
+{
+    char** p;
+
+    if (!(p = calloc(service->argc + 2, sizeof(char*))))
+        return NULL;
+
+    p[0] = (char*)service->path;
+
+    for (size_t i = 0; i < service->argc; i++)
+        p[i + 1] = service->argv[i];
+
+    return p;
+}
+
+/* runs in the new process (on the target stack) */
+static int _service_start(void* arg)
+{
+    service_state_t* state = arg;
+    myst_thread_t* thread = myst_thread_self();
+
+    /* only returns on failure */
+    myst_syscall_execve(state->service->path, state->argv, state->envp);
+
+    myst_eprintf(
+        "kernel: service %s: cannot execute %s\n",
+        _name(state->service),
+        state->service->path);
+
+    /* exit like an application that could not be found */
+    thread->exit_status = 127;
+    myst_longjmp(&thread->jmpbuf, 1);
+
+    /* unreachable */
+    return 0;
+}
+
+static int _start(service_state_t* state)
+{
+    long pid;
+
+    pid = myst_syscall_clone(
+        _service_start,
+        NULL,
+        CLONE_VM | CLONE_VFORK | SIGCHLD,
+        state,
+        NULL,
+        NULL,
+        NULL);
+
+    if (pid < 0)
+    {
+        myst_eprintf(
+            "kernel: service %s: cannot start: %s\n",
+            _name(state->service),
+            myst_error_name((int)-pid));
+        return (int)pid;
+    }
+
+    state->pid = (pid_t)pid;
+    return 0;
+}
+
+static int _wait_ready(service_state_t* state)
+{
+    const myst_service_t* service = state->service;
+    uint64_t timeout = service->ready_timeout;
+    uint64_t polls;
+
+    if (!service->ready_path)
+        return 0;
+
+    if (!timeout)
+        timeout = MYST_SERVICE_DEFAULT_READY_TIMEOUT;
+
+    polls = timeout * (1000 / POLL_MSEC);
+
+    for (uint64_t i = 0; i < polls; i++)
+    {
+        int wstatus;
+
+        if (myst_syscall_access(service->ready_path, F_OK) == 0)
+            return 0;
+
+        /* fail early if the service exited before it became ready */
+        if (myst_syscall_wait4(state->pid, &wstatus, WNOHANG, NULL) ==
+            state->pid)
+        {
+            myst_eprintf(
+                "kernel: service %s exited before it was ready (status=%d)\n",
+                _name(service),
+                WEXITSTATUS(wstatus));
+            state->pid = 0;
+            return -ECHILD;
+        }
+
+        myst_sleep_msec(POLL_MSEC);
+    }
+
+    myst_eprintf(
+        "kernel: service %s was not ready after %lu seconds\n",
+        _name(service),
+        timeout);
+
+    return -ETIMEDOUT;
+}
+
+int myst_run_services(
+    const myst_services_config_t* config,
+    size_t envc,
+    const char* envp[])
+{
+    int ret = 0;
+    service_state_t* states = NULL;
+    const size_t count = config->num_services;
+
+    if (!(states = calloc(count, sizeof(service_state_t))))
+        ERAISE(-ENOMEM);
+
+    for (size_t i = 0; i < count; i++)
+    {
+        const myst_service_t* service = &config->services[i];
+
+        if (!service->path || service->path[0] != '/')
+        {
+            myst_eprintf("kernel: service %zu: bad ApplicationPath\n", i);
+            ERAISE(-EINVAL);
+        }
+
+        if (_check_restart(service->restart) != 0)
+        {
+            myst_eprintf(
+                "kernel: service %s: bad Restart: %s\n",
+                _name(service),
+                service->restart);
+            ERAISE(-EINVAL);
+        }
+
+        states[i].service = service;
+
+        if (!(states[i].argv = _make_argv(service)) ||
+            !(states[i].envp = _make_envp(service, envc, envp)))
+        {
+            ERAISE(-ENOMEM);
+        }
+    }
+
+    /* start the services in order, each after the previous one is ready */
+    for (size_t i = 0; i < count; i++)
+    {
+        ECHECK(_start(&states[i]));
+        ECHECK(_wait_ready(&states[i]));
+    }
+
+    /* supervise the services until the main one exits for good */
+    for (;;)
+    {
+        int wstatus;
+        long pid;
+        service_state_t* state = NULL;
+
+        /* the host asked for a shutdown (pid 1 gets no signals here) */
+        if (myst_shutdown_requested())
+        {
+            ret = 128 + SIGTERM;
+            break;
+        }
+
+        if ((pid = myst_syscall_wait4(-1, &wstatus, WNOHANG, NULL)) < 0)
+            ERAISE((int)pid);
+
+        if (pid == 0)
+        {
+            myst_sleep_msec(POLL_MSEC);
+            continue;
+        }
+
+        for (size_t i = 0; i < count; i++)
+        {
+            if (states[i].pid == pid)
+            {
+                state = &states[i];
+                break;
+            }
+        }
+
+        /* not a service (for example, a process that pid 1 inherited) */
+        if (!state)
+            continue;
+
+        state->pid = 0;
+
+        if (_should_restart(state, wstatus))
+        {
+            state->restarts++;
+            myst_eprintf(
+                "kernel: restarting service %s (status=%d)\n",
+                _name(state->service),
+                WEXITSTATUS(wstatus));
+            ECHECK(_start(state));
+            continue;
+        }
+
+        if (state == &states[count - 1])
+        {
+            ret = WEXITSTATUS(wstatus);
+            break;
+        }
+    }
+
+done:
+
+    /* the remaining services are stopped by myst_shutdown_processes() */
+    if (states)
+    {
+        for (size_t i = 0; i < count; i++)
+        {
+            free(states[i].argv);
+            free(states[i].envp);
+        }
+
+        free(states);
+    }
+
+    return ret;
+}
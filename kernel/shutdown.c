@@ -0,0 +1,232 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <signal.h>
+#include <stdlib.h>
+#include <time.h>
+
+#include <myst/console.h>
+#include <myst/eraise.h>
+#include <myst/printf.h>
+#include <myst/shutdown.h>
+#include <myst/syscall.h>
+#include <myst/tcall.h>
+#include <myst/thread.h>
+#include <myst/time.h>
+
+/*
+**==============================================================================
+**
+** Shutdown:
+**
+** The host launcher sets a flag in host memory when it receives SIGTERM and
+** wakes the host event of the main thread. Threads check the flag as they
+** leave the kernel; a main thread that the wakeup finds in an interruptible
+** wait (a futex, see myst_cond_wait_deadline()) leaves it with EINTR to do
+** so. The first check to see it sends SIGTERM to the main process (or stops
+** the service supervisor, see kernel/services.c) and starts the grace
+** period, after which the main process is killed; until then, the waits of
+** the main thread end in time to check. Once the main process has exited,
+** the remaining processes get SIGTERM, then SIGKILL when the grace period is
+** over, before the console logs are flushed and the kernel returns the exit
+** status to the launcher.
+**
+**==============================================================================
+*/
+
+/* how often to check whether the processes have exited */
+#define POLL_MSEC 10
+
+static volatile const int* _request;
+static uint64_t _grace_period = MYST_SHUTDOWN_DEFAULT_GRACE_PERIOD;
+
+/* when the main process is killed (0 until a shutdown is requested) */
+static volatile uint64_t _deadline;
+static volatile int _killed;
+
+static uint64_t _now(void)
+{
+    struct timespec ts;
+
+    if (myst_syscall_clock_gettime(CLOCK_MONOTONIC, &ts) != 0)
+        return 0;
+
+    return (uint64_t)ts.tv_sec;
+}
+
+/* send sig to the main process and wake its thread from any host wait, so
+ * that it leaves the kernel to handle the signal */
+static void _signal_main(myst_thread_t* main, int sig)
+{
+    myst_syscall_kill(main->pid, sig);
+    myst_tcall_wake(main->event);
+}
+
+int myst_shutdown_setup(const myst_shutdown_config_t* config)
+{
+    if (!config)
+        return -EINVAL;
+
+    _request = config->request;
+
+    if (config->grace_period)
+        _grace_period = config->grace_period;
+
+    return 0;
+}
+
+bool myst_shutdown_requested(void)
+{
+    return _request && *_request;
+}
+
+void myst_shutdown_poll(void)
+{
+    myst_thread_t* main = __myst_main_thread;
+
+    /* this is called on every syscall, so make the common case cheap */
+    if (!_request || !*_request || _killed || !main)
+        return;
+
+    if (!_deadline)
+    {
+        uint64_t deadline = _now() + _grace_period;
+
+        /* only the first check to see the request acts on it */
+        if (!__sync_bool_compare_and_swap(&_deadline, 0, deadline))
+            return;
+
+        myst_eprintf(
+            "kernel: shutdown requested; waiting %lu seconds\n", _grace_period);
+        _signal_main(main, SIGTERM);
+    }
+    else if (_now() >= _deadline && __sync_lock_test_and_set(&_killed, 1) == 0)
+    {
+        myst_eprintf("kernel: the application did not exit; killing it\n");
+        _signal_main(main, SIGKILL);
+    }
+}
+
+bool myst_shutdown_interrupted(void)
+{
+    myst_thread_t* self = myst_thread_self();
+    const uint64_t sigs = ((uint64_t)1 << (SIGTERM - 1)) |
+                          ((uint64_t)1 << (SIGKILL - 1));
+
+    if (!_request || !*_request || self != __myst_main_thread)
+        return false;
+
+    /* the request has not been acted on */
+    if (!_deadline)
+        return true;
+
+    /* the signal has not been handled */
+    if (self->signal.pending & ~self->signal.mask & sigs)
+        return true;
+
+    return !_killed && _now() >= _deadline;
+}
+
+const struct timespec* myst_shutdown_timeout(
+    const struct timespec* timeout,
+    struct timespec* buf)
+{
+    uint64_t deadline = _deadline;
+    uint64_t now;
+
+    if (!deadline || _killed || myst_thread_self() != __myst_main_thread)
+        return timeout;
+
+    now = _now();
+    buf->tv_sec = (time_t)(deadline > now ? deadline - now : 0);
+    buf->tv_nsec = 0;
+
+    if (timeout && timeout->tv_sec < buf->tv_sec)
+        return timeout;
+
+    return buf;
+}
+
+/* send sig to every other running process; returns how many there were */
+static size_t _signal_processes(myst_thread_t* self, int sig)
+{
+    size_t count = 0;
+    pid_t* pids = NULL;
+    size_t n = 0;
+
+    /* count the processes, then collect their pids */
+    for (int pass = 0; pass < 2; pass++)
+    {
+        myst_spin_lock(&myst_process_list_lock);
+
+        for (myst_thread_t* p = self->main.next_process_thread; p;
+             p = p->main.next_process_thread)
+        {
+            if (p->status != MYST_RUNNING)
+                continue;
+
+            if (pass == 0)
+                count++;
+            else if (n < count)
+                pids[n++] = p->pid;
+        }
+
+        myst_spin_unlock(&myst_process_list_lock);
+
+        if (pass == 0 && (count == 0 || !(pids = calloc(count, sizeof(pid_t)))))
+            return count;
+    }
+
+    for (size_t i = 0; i < n; i++)
+        myst_syscall_kill(pids[i], sig);
+
+    free(pids);
+    return n;
+}
+
+void myst_shutdown_processes(void)
+{
+    myst_thread_t* self = myst_find_process_thread(myst_thread_self());
+    uint64_t deadline;
+
+    if (_signal_processes(self, SIGTERM) == 0)
+        goto done;
+
+    deadline = _now() + _grace_period;
+
+    while (_now() < deadline)
+    {
+        bool running = false;
+
+        myst_spin_lock(&myst_process_list_lock);
+
+        for (myst_thread_t* p = self->main.next_process_thread; p;
+             p = p->main.next_process_thread)
+        {
+            if (p->status == MYST_RUNNING)
+            {
+                running = true;
+                break;
+            }
+        }
+
+        myst_spin_unlock(&myst_process_list_lock);
+
+        if (!running)
+            goto done;
+
+        myst_sleep_msec(POLL_MSEC);
+    }
+
+    if (_signal_processes(self, SIGKILL))
+    {
+        myst_eprintf("kernel: killed the processes that did not exit\n");
+
+        /* give the killed processes a moment to leave the kernel */
+        myst_sleep_msec(100 * POLL_MSEC);
+    }
+
+done:
+    myst_console_flush();
+}
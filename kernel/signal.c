@@ -2,11 +2,18 @@
 #include <stdlib.h>
 #include <string.h>
 
+#include <myst/coredump.h>
 #include <myst/eraise.h>
 #include <myst/fsgs.h>
 #include <myst/printf.h>
+#include <myst/setjmp.h>
+#include <myst/sigsafe.h>
 #include <myst/signal.h>
 
+#ifndef SS_AUTODISARM
+#define SS_AUTODISARM (1U << 31)
+#endif
+
 /* The lock for installing signal dispositions */
 static myst_spinlock_t _lock = MYST_SPINLOCK_INITIALIZER;
 
@@ -118,8 +125,12 @@ static long _default_signal_handler(unsigned signum)
 
     myst_thread_t* thread = myst_thread_self();
 
-    // A hard kill. Never returns.
-    thread->exit_status = -1;
+    // Write a core dump to the host if configured (see kernel/coredump.c)
+    if (myst_coredump_signal(signum))
+        myst_coredump(signum, NULL);
+
+    // A hard kill. Never returns. Report the signal as a shell would.
+    thread->exit_status = 128 + (int)signum;
     thread->status = MYST_KILLED;
     myst_longjmp(&thread->jmpbuf, 1);
 
@@ -128,6 +139,91 @@ static long _default_signal_handler(unsigned signum)
     return 0;
 }
 
+static void _call_handler(
+    const posix_sigaction_t* action,
+    unsigned signum,
+    siginfo_t* siginfo)
+{
+    if ((action->flags & SA_SIGINFO) != 0)
+    {
+        // Use a zeroed ucontext_t. Only usage in libc seems to be
+        // pthread_cancel, which we modified to avoid the dependency.
+        ucontext_t context = {0};
+        ((sigaction_function_t)(action->handler))(signum, siginfo, &context);
+    }
+    else
+    {
+        ((sigaction_handler_t)(action->handler))(signum);
+    }
+}
+
+/* whether sp lies on the alternate signal stack of the thread */
+static bool _on_altstack(const myst_thread_t* thread, const void* sp)
+{
+    const stack_t* ss = &thread->signal.altstack;
+    const uint8_t* p = sp;
+
+    return ss->ss_size && p > (uint8_t*)ss->ss_sp &&
+           (size_t)(p - (uint8_t*)ss->ss_sp) <= ss->ss_size;
+}
+
+/* the handler call that is made on the alternate signal stack */
+typedef struct altstack_call
+{
+    myst_jmp_buf_t env;
+    const posix_sigaction_t* action;
+    unsigned signum;
+    siginfo_t* siginfo;
+} altstack_call_t;
+
+/* Force the alternate stack to be aligned */
+__attribute__((force_align_arg_pointer)) static void _call_altstack_handler(
+    void)
+{
+    myst_thread_t* thread = myst_thread_self();
+    altstack_call_t* call = thread->signal.altstack_call;
+
+    _call_handler(call->action, call->signum, call->siginfo);
+
+    /* return to the original stack */
+    myst_longjmp(&call->env, 1);
+}
+
+static void _call_handler_on_altstack(
+    myst_thread_t* thread,
+    const posix_sigaction_t* action,
+    unsigned signum,
+    siginfo_t* siginfo)
+{
+    altstack_call_t call;
+    stack_t ss = thread->signal.altstack;
+
+    call.action = action;
+    call.signum = signum;
+    call.siginfo = siginfo;
+
+    /* SS_AUTODISARM clears the alternate stack while the handler runs */
+    if (ss.ss_flags & SS_AUTODISARM)
+        memset(&thread->signal.altstack, 0, sizeof(stack_t));
+
+    if (myst_setjmp(&call.env) == 0)
+    {
+        myst_jmp_buf_t env = call.env;
+        uint64_t top = (uint64_t)ss.ss_sp + ss.ss_size;
+
+        thread->signal.altstack_call = &call;
+        env.rip = (uint64_t)_call_altstack_handler;
+        env.rsp = top & ~(uint64_t)15;
+        env.rbp = env.rsp;
+        myst_jump(&env);
+    }
+
+    thread->signal.altstack_call = NULL;
+
+    if (ss.ss_flags & SS_AUTODISARM)
+        thread->signal.altstack = ss;
+}
+
 static long _handle_one_signal(unsigned signum, siginfo_t* siginfo)
 {
     long ret = 0;
@@ -143,6 +239,8 @@ static long _handle_one_signal(unsigned signum, siginfo_t* siginfo)
     posix_sigaction_t* action = &thread->signal.sigactions[signum - 1];
     if (action->handler == (uint64_t)SIG_DFL)
     {
+        /* the default action may write a core dump and end the thread */
+        myst_sigsafe_end_delivery(thread);
         ret = _default_signal_handler(signum);
     }
     else if (action->handler == (uint64_t)SIG_IGN)
@@ -152,6 +250,7 @@ static long _handle_one_signal(unsigned signum, siginfo_t* siginfo)
     else
     {
         uint64_t orig_mask = thread->signal.mask;
+        bool altstack = false;
 
         // add mask specified in action->sa_mask
         thread->signal.mask |= action->mask;
@@ -159,7 +258,14 @@ static long _handle_one_signal(unsigned signum, siginfo_t* siginfo)
             thread->signal.mask |= mask;
 
         // ATTN: handle other signal flags, e.g., SA_NOCLDSTOP, SA_NOCLDWAIT,
-        // SA_ONSTACK, SA_RESETHAND, SA_RESTART, etc.
+        // SA_RESETHAND, SA_RESTART, etc.
+
+        /* run on the alternate stack unless already running on it */
+        if ((action->flags & SA_ONSTACK) && thread->signal.altstack.ss_size &&
+            !_on_altstack(thread, __builtin_frame_address(0)))
+        {
+            altstack = true;
+        }
 
         /* save the original fsbase */
         void* original_fsbase = myst_get_fsbase();
@@ -167,18 +273,16 @@ static long _handle_one_signal(unsigned signum, siginfo_t* siginfo)
         /* restore the user-space fsbase, which is pthread_self() */
         myst_set_fsbase(thread->crt_td);
 
-        if ((action->flags & SA_SIGINFO) != 0)
-        {
-            // Use a zeroed ucontext_t. Only usage in libc seems to be
-            // pthread_cancel, which we modified to avoid the dependency.
-            ucontext_t context = {0};
-            ((sigaction_function_t)(action->handler))(
-                signum, siginfo, &context);
-        }
+        /* the handler may make syscalls (and so lock kernel mutexes) */
+        myst_sigsafe_check_handler(thread, signum);
+        myst_sigsafe_end_delivery(thread);
+
+        if (altstack)
+            _call_handler_on_altstack(thread, action, signum, siginfo);
         else
-        {
-            ((sigaction_handler_t)(action->handler))(signum);
-        }
+            _call_handler(action, signum, siginfo);
+
+        myst_sigsafe_begin_delivery(thread);
 
         /* restore the original fsbase */
         myst_set_fsbase(original_fsbase);
@@ -196,6 +300,9 @@ long myst_signal_process(myst_thread_t* thread)
     {
         unsigned bitnum = __builtin_ctzl(thread->signal.pending);
 
+        /* what runs from here (but the handler) must be async-signal-safe */
+        myst_sigsafe_begin_delivery(thread);
+
         // Create a local copy and free the global one.
         siginfo_t local_siginfo = {0};
         siginfo_t* siginfo = NULL;
@@ -213,7 +320,47 @@ long myst_signal_process(myst_thread_t* thread)
         // Signal numbers are 1 based.
         unsigned signum = bitnum + 1;
         _handle_one_signal(signum, siginfo);
+
+        myst_sigsafe_end_delivery(thread);
+    }
+    return 0;
+}
+
+long myst_signal_set_syscall_mask(const sigset_t* set)
+{
+    long ret = 0;
+    myst_thread_t* thread = myst_thread_self();
+
+    if (!set)
+        ERAISE(-EINVAL);
+
+    /* keep the mask from before the first call in this syscall */
+    if (!thread->signal.restore_mask)
+    {
+        thread->signal.saved_mask = thread->signal.mask;
+        thread->signal.restore_mask = true;
     }
+
+    thread->signal.mask = _sigset_to_uint64(set);
+
+done:
+    return ret;
+}
+
+long myst_signal_process_syscall_exit(myst_thread_t* thread)
+{
+    const bool restore = thread->signal.restore_mask;
+    const uint64_t saved_mask = thread->signal.saved_mask;
+
+    /* syscalls made by the handlers below must not restore it early */
+    thread->signal.restore_mask = false;
+
+    /* the signals are delivered with the mask of the syscall */
+    myst_signal_process(thread);
+
+    if (restore)
+        thread->signal.mask = saved_mask;
+
     return 0;
 }
 
@@ -251,6 +398,82 @@ done:
     return ret;
 }
 
+long myst_signal_force(
+    myst_thread_t* thread,
+    unsigned signum,
+    siginfo_t* siginfo)
+{
+    long ret = 0;
+    ECHECK(_check_signum(signum));
+
+    uint64_t mask = (uint64_t)1 << (signum - 1);
+    posix_sigaction_t* action = &thread->signal.sigactions[signum - 1];
+
+    // Like Linux, a blocked or ignored fault reverts to the default action
+    // since the thread cannot continue past the fault.
+    myst_spin_lock(&_lock);
+    if ((thread->signal.mask & mask) || action->handler == (uint64_t)SIG_IGN)
+        action->handler = (uint64_t)SIG_DFL;
+    myst_spin_unlock(&_lock);
+
+    thread->signal.mask &= ~mask;
+
+    ret = myst_signal_deliver(thread, signum, siginfo);
+
+done:
+    return ret;
+}
+
+long myst_signal_sigaltstack(const stack_t* ss, stack_t* old_ss)
+{
+    long ret = 0;
+    myst_thread_t* thread = myst_thread_self();
+    bool onstack = _on_altstack(thread, __builtin_frame_address(0));
+    stack_t old = thread->signal.altstack;
+
+    if (!old.ss_size)
+    {
+        old.ss_sp = NULL;
+        old.ss_flags = SS_DISABLE;
+    }
+    else if (onstack)
+    {
+        old.ss_flags |= SS_ONSTACK;
+    }
+
+    if (ss)
+    {
+        int mode = ss->ss_flags & ~SS_AUTODISARM;
+
+        // The stack cannot be changed while a handler is running on it.
+        if (onstack)
+            ERAISE(-EPERM);
+
+        if (mode != 0 && mode != SS_ONSTACK && mode != SS_DISABLE)
+            ERAISE(-EINVAL);
+
+        if (mode == SS_DISABLE)
+        {
+            memset(&thread->signal.altstack, 0, sizeof(stack_t));
+        }
+        else
+        {
+            if (ss->ss_size < (size_t)MINSIGSTKSZ)
+                ERAISE(-ENOMEM);
+
+            thread->signal.altstack.ss_sp = ss->ss_sp;
+            thread->signal.altstack.ss_size = ss->ss_size;
+            thread->signal.altstack.ss_flags = ss->ss_flags & SS_AUTODISARM;
+        }
+    }
+
+    if (old_ss)
+        *old_ss = old;
+
+done:
+    return ret;
+}
+
 long myst_signal_sigpending(sigset_t* set, unsigned size)
 {
     if (size > sizeof(sigset_t) || !set)
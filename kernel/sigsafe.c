@@ -0,0 +1,126 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <myst/backtrace.h>
+#include <myst/panic.h>
+#include <myst/printf.h>
+#include <myst/sigsafe.h>
+#include <myst/thread.h>
+
+static bool _enabled;
+
+void myst_sigsafe_setup(bool enable)
+{
+    _enabled = enable;
+}
+
+bool myst_sigsafe_enabled(void)
+{
+    return _enabled;
+}
+
+/* the calling thread (or NULL if the audit is off) */
+static myst_thread_t* _self(void)
+{
+    return _enabled ? myst_thread_self() : NULL;
+}
+
+void myst_sigsafe_acquired(const void* lock, void* caller)
+{
+    myst_thread_t* self = _self();
+    myst_sigsafe_t* ss;
+
+    if (!self)
+        return;
+
+    ss = &self->signal.sigsafe;
+
+    if (ss->nlocks < MYST_SIGSAFE_MAX_LOCKS)
+    {
+        ss->locks[ss->nlocks] = lock;
+        ss->callers[ss->nlocks] = caller;
+    }
+
+    ss->nlocks++;
+}
+
+void myst_sigsafe_released(const void* lock)
+{
+    myst_thread_t* self = _self();
+    myst_sigsafe_t* ss;
+    size_t n;
+
+    if (!self || !(ss = &self->signal.sigsafe)->nlocks)
+        return;
+
+    n = ss->nlocks < MYST_SIGSAFE_MAX_LOCKS ? ss->nlocks
+                                             : MYST_SIGSAFE_MAX_LOCKS;
+
+    /* mutexes are mostly released in the reverse order */
+    for (size_t i = n; i > 0; i--)
+    {
+        if (ss->locks[i - 1] == lock)
+        {
+            for (size_t j = i; j < n; j++)
+            {
+                ss->locks[j - 1] = ss->locks[j];
+                ss->callers[j - 1] = ss->callers[j];
+            }
+            break;
+        }
+    }
+
+    ss->nlocks--;
+}
+
+void myst_sigsafe_begin_delivery(myst_thread_t* thread)
+{
+    if (_enabled)
+        thread->signal.sigsafe.delivering = true;
+}
+
+void myst_sigsafe_end_delivery(myst_thread_t* thread)
+{
+    if (_enabled)
+        thread->signal.sigsafe.delivering = false;
+}
+
+void myst_sigsafe_check_handler(myst_thread_t* thread, unsigned signum)
+{
+    myst_sigsafe_t* ss = &thread->signal.sigsafe;
+    size_t n;
+
+    if (!_enabled || ss->nlocks == 0)
+        return;
+
+    n = ss->nlocks < MYST_SIGSAFE_MAX_LOCKS ? ss->nlocks
+                                             : MYST_SIGSAFE_MAX_LOCKS;
+
+    myst_eprintf(
+        "kernel: the handler of signal %u would run on thread %d with %zu "
+        "kernel mutexes held:\n",
+        signum,
+        thread->tid,
+        ss->nlocks);
+
+    for (size_t i = 0; i < n; i++)
+    {
+        myst_eprintf("kernel: mutex %p locked at:\n", ss->locks[i]);
+        myst_dump_backtrace(&ss->callers[i], 1);
+    }
+
+    myst_panic("signal handler reentry hazard (DebugSignalSafety)");
+}
+
+void myst_sigsafe_assert(const char* func)
+{
+    myst_thread_t* self = _self();
+
+    if (self && self->signal.sigsafe.delivering)
+    {
+        myst_panic(
+            "%s() is not async-signal-safe but was called while a signal "
+            "was being delivered (DebugSignalSafety)",
+            func);
+    }
+}
@@ -5,8 +5,10 @@
 #include <limits.h>
 #include <stdlib.h>
 #include <string.h>
+#include <sys/ioctl.h>
 
 #include <myst/eraise.h>
+#include <myst/ioctl.h>
 #include <myst/panic.h>
 #include <myst/sockdev.h>
 #include <myst/spinlock.h>
@@ -661,6 +663,15 @@ done:
     return ret;
 }
 
+/* the requests that the host performs on the socket (see _ioctl() in
+ * tools/myst/enc/syscall.c) */
+static const myst_ioctl_t _ioctl_list[] = {
+    MYST_IOCTL(FIONREAD, MYST_IOCTL_OUT, int),
+};
+
+static const myst_ioctl_table_t _ioctls =
+    MYST_IOCTL_TABLE("socket", _ioctl_list);
+
 extern myst_sockdev_t* myst_sockdev_get(void)
 {
     // clang-format-off
@@ -673,6 +684,7 @@ extern myst_sockdev_t* myst_sockdev_get(void)
             .fd_fstat = (void*)_sd_fstat,
             .fd_fcntl = (void*)_sd_fcntl,
             .fd_ioctl = (void*)_sd_ioctl,
+            .fd_ioctls = &_ioctls,
             .fd_dup = (void*)_sd_dup,
             .fd_close = (void*)_sd_close,
             .fd_target_fd = (void*)_sd_target_fd,
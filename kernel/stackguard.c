@@ -0,0 +1,448 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <signal.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+
+#include <myst/panic.h>
+#include <myst/printf.h>
+#include <myst/signal.h>
+#include <myst/spinlock.h>
+#include <myst/stackguard.h>
+#include <myst/syscall.h>
+#include <myst/tcall.h>
+#include <myst/thread.h>
+
+/*
+**==============================================================================
+**
+** Stack guards:
+**
+** The C runtime allocates a thread stack by mapping it PROT_NONE and then
+** making everything but the lowest pages accessible, which leaves a guard
+** below the stack. Where the target honors page protections (the Linux
+** target, and SGX2 enclaves that use EDMM), the kernel makes the guard
+** inaccessible, so an overflow faults at the instruction that touches it.
+** The target passes the fault to the kernel, which resumes the thread on its
+** alternate signal stack to take SIGSEGV with SEGV_ACCERR. Without a handler
+** there (or if the handler returns), the default action ends the process and
+** dumps core if that is configured (see doc/coredump.md).
+**
+** SGX1 enclaves cannot change page permissions, so there the kernel fills the
+** guard with a pattern instead and checks the pattern (and the stack pointer)
+** whenever the owning thread enters the kernel. A thread that has overrun its
+** stack receives SIGSEGV with SEGV_ACCERR, which a handler can catch on the
+** alternate signal stack. An overflow is therefore detected at the next
+** system call rather than at the faulting instruction. The kernel always uses
+** the pattern for the stack of the main thread, which is kernel memory.
+**
+**==============================================================================
+*/
+
+#define PATTERN 0x4f4f4f4f4f4f4f4fUL
+
+/* the protection of the pages that the kernel maps */
+#define KERNEL_PROT (PROT_READ | PROT_WRITE | PROT_EXEC)
+
+typedef struct range
+{
+    struct range* next;
+    uint8_t* addr;
+    size_t length;
+
+    /* a guard (rather than a PROT_NONE mapping that is not yet split) */
+    bool guard;
+
+    /* the guard is inaccessible (rather than filled with the pattern) */
+    bool protected;
+
+    /* the end of the stack above the guard */
+    uint8_t* stack_end;
+
+    /* the thread running on that stack */
+    myst_thread_t* owner;
+} range_t;
+
+static range_t* _ranges;
+static myst_spinlock_t _lock = MYST_SPINLOCK_INITIALIZER;
+
+static bool _overlaps(const range_t* r, const uint8_t* addr, size_t length)
+{
+    return addr < r->addr + r->length && r->addr < addr + length;
+}
+
+/* forget the ranges that overlap [addr, addr+length) (caller holds _lock) */
+static void _remove_locked(const uint8_t* addr, size_t length)
+{
+    range_t* prev = NULL;
+    range_t* r = _ranges;
+
+    while (r)
+    {
+        range_t* next = r->next;
+
+        if (_overlaps(r, addr, length))
+        {
+            if (r->owner)
+            {
+                r->owner->stack_guard.length = 0;
+                r->owner->stack_guard.addr = NULL;
+                r->owner->stack_guard.protected_length = 0;
+                r->owner->stack_guard.protected_addr = NULL;
+            }
+
+            /* the pages go back to the kernel, which needs access to them */
+            if (r->protected)
+                myst_tcall_mprotect(r->addr, r->length, KERNEL_PROT);
+
+            if (prev)
+                prev->next = next;
+            else
+                _ranges = next;
+
+            free(r);
+        }
+        else
+        {
+            prev = r;
+        }
+
+        r = next;
+    }
+}
+
+static void _add_locked(uint8_t* addr, size_t length, uint8_t* stack_end)
+{
+    range_t* r;
+
+    if (!(r = calloc(1, sizeof(range_t))))
+        return;
+
+    r->addr = addr;
+    r->length = length;
+
+    if (stack_end)
+    {
+        r->guard = true;
+        r->stack_end = stack_end;
+
+        if (myst_tcall_mprotect(addr, length, PROT_NONE) == 0)
+            r->protected = true;
+        else
+            memset(addr, MYST_STACK_GUARD_BYTE, length);
+    }
+
+    r->next = _ranges;
+    _ranges = r;
+}
+
+void myst_stack_guard_mmap(void* addr, size_t length, int prot, int flags)
+{
+    myst_spin_lock(&_lock);
+    {
+        /* MAP_FIXED may replace existing ranges */
+        _remove_locked(addr, length);
+
+        if (prot == PROT_NONE && (flags & MAP_ANONYMOUS))
+            _add_locked(addr, length, NULL);
+    }
+    myst_spin_unlock(&_lock);
+}
+
+void myst_stack_guard_mprotect(void* addr_, size_t length, int prot)
+{
+    uint8_t* addr = addr_;
+    uint8_t* end = addr + length;
+    range_t* r;
+
+    if (prot == PROT_NONE)
+        return;
+
+    myst_spin_lock(&_lock);
+    {
+        for (r = _ranges; r; r = r->next)
+        {
+            if (_overlaps(r, addr, length))
+                break;
+        }
+
+        if (r && !r->guard && addr > r->addr && end <= r->addr + r->length)
+        {
+            /* the inaccessible pages below the stack become its guard */
+            uint8_t* base = r->addr;
+            uint8_t* rend = r->addr + r->length;
+            size_t size = (size_t)(addr - base);
+
+            _remove_locked(base, r->length);
+
+            if (size <= MYST_STACK_GUARD_MAX_SIZE)
+                _add_locked(base, size, end);
+
+            if (end < rend)
+                _add_locked(end, (size_t)(rend - end), NULL);
+        }
+        else if (r)
+        {
+            /* the guard or the reservation is accessible now */
+            _remove_locked(addr, length);
+        }
+    }
+    myst_spin_unlock(&_lock);
+}
+
+void myst_stack_guard_munmap(void* addr, size_t length)
+{
+    myst_spin_lock(&_lock);
+    _remove_locked(addr, length);
+    myst_spin_unlock(&_lock);
+}
+
+void myst_stack_guard_attach(myst_thread_t* thread, void* child_stack)
+{
+    const uint8_t* sp = child_stack;
+    range_t* best = NULL;
+
+    myst_spin_lock(&_lock);
+    {
+        for (range_t* r = _ranges; r; r = r->next)
+        {
+            if (!r->guard || r->owner)
+                continue;
+
+            if (sp < r->addr + r->length || sp > r->stack_end)
+                continue;
+
+            if (!best || r->addr > best->addr)
+                best = r;
+        }
+
+        if (best)
+        {
+            best->owner = thread;
+
+            /* an inaccessible guard faults rather than being checked */
+            if (best->protected)
+            {
+                thread->stack_guard.protected_addr = best->addr;
+                thread->stack_guard.protected_length = best->length;
+                thread->stack_guard.start_sp = (uint8_t*)sp;
+            }
+            else
+            {
+                thread->stack_guard.addr = best->addr;
+                thread->stack_guard.length = best->length;
+            }
+        }
+    }
+    myst_spin_unlock(&_lock);
+}
+
+void myst_stack_guard_set(myst_thread_t* thread, void* addr, size_t length)
+{
+    myst_stack_guard_detach(thread);
+    thread->stack_guard.addr = addr;
+    thread->stack_guard.length = length;
+}
+
+void myst_stack_guard_detach(myst_thread_t* thread)
+{
+    myst_spin_lock(&_lock);
+    {
+        for (range_t* r = _ranges; r; r = r->next)
+        {
+            if (r->owner == thread)
+            {
+                /* the stack is released with the thread */
+                _remove_locked(r->addr, r->length);
+                break;
+            }
+        }
+
+        thread->stack_guard.addr = NULL;
+        thread->stack_guard.length = 0;
+        thread->stack_guard.protected_addr = NULL;
+        thread->stack_guard.protected_length = 0;
+    }
+    myst_spin_unlock(&_lock);
+}
+
+/* return the highest address in the guard that was written, if any */
+static uint8_t* _find_overrun(uint8_t* addr, size_t length)
+{
+    uint64_t* p = (uint64_t*)(addr + length);
+
+    while (p > (uint64_t*)addr)
+    {
+        if (*--p != PATTERN)
+        {
+            uint8_t* q = (uint8_t*)(p + 1);
+
+            while (*--q == MYST_STACK_GUARD_BYTE)
+                ;
+
+            return q;
+        }
+    }
+
+    return NULL;
+}
+
+static bool _on_altstack(const myst_thread_t* thread, const uint8_t* sp)
+{
+    const uint8_t* base = thread->signal.altstack.ss_sp;
+
+    return sp > base && sp <= base + thread->signal.altstack.ss_size;
+}
+
+static bool _is_default_action(myst_thread_t* thread, unsigned signum)
+{
+    const posix_sigaction_t* action = &thread->signal.sigactions[signum - 1];
+    const uint64_t mask = (uint64_t)1 << (signum - 1);
+
+    return action->handler == (uint64_t)SIG_DFL ||
+           action->handler == (uint64_t)SIG_IGN || (thread->signal.mask & mask);
+}
+
+/* whether the SIGSEGV handler of the thread runs on its alternate stack */
+static bool _has_altstack_handler(myst_thread_t* thread)
+{
+    const posix_sigaction_t* action = &thread->signal.sigactions[SIGSEGV - 1];
+
+    return !_is_default_action(thread, SIGSEGV) &&
+           (action->flags & SA_ONSTACK) && thread->signal.altstack.ss_size;
+}
+
+/* raise SIGSEGV for an access to the guard at hit; fatal if the handler
+ * cannot run */
+static void _raise(myst_thread_t* thread, uint8_t* hit, bool fatal)
+{
+    siginfo_t* siginfo;
+
+    if (fatal || _is_default_action(thread, SIGSEGV))
+    {
+        myst_eprintf(
+            "kernel: stack overflow: thread %d wrote to the guard at %p\n",
+            thread->tid,
+            hit);
+
+        /* like Linux, a fault that cannot be handled takes the default */
+        thread->signal.sigactions[SIGSEGV - 1].handler = (uint64_t)SIG_DFL;
+
+        /* the whole process crashes, as on Linux */
+        if (!myst_is_process_thread(thread))
+            myst_syscall_kill(thread->pid, SIGKILL);
+    }
+
+    if ((siginfo = calloc(1, sizeof(siginfo_t))))
+    {
+        siginfo->si_signo = SIGSEGV;
+        siginfo->si_code = SEGV_ACCERR;
+        siginfo->si_addr = hit;
+    }
+
+    myst_signal_force(thread, SIGSEGV, siginfo);
+}
+
+void myst_stack_guard_check(myst_thread_t* thread)
+{
+    uint8_t* addr = thread->stack_guard.addr;
+    size_t length = thread->stack_guard.length;
+    uint8_t* sp = __builtin_frame_address(0);
+    uint8_t* hit;
+
+    /* the thread faulted in its inaccessible guard (see _handle_fault) */
+    if ((hit = thread->stack_guard.fault))
+    {
+        thread->stack_guard.fault = NULL;
+        _raise(thread, hit, !_has_altstack_handler(thread));
+
+        /* the handler runs here, on the alternate stack */
+        myst_signal_process(thread);
+
+        /* the fault would recur if the thread went back to it */
+        _raise(thread, hit, true);
+        myst_signal_process(thread);
+        myst_panic("SIGSEGV did not end thread %d", thread->tid);
+    }
+
+    if (!length)
+        return;
+
+    /* the kernel frames of a system call may reach below the guard */
+    if (sp >= addr - MYST_STACK_GUARD_MAX_SIZE && sp < addr + length &&
+        !_on_altstack(thread, sp))
+    {
+        /* still running on the guard: disarm it rather than overwrite it */
+        hit = sp < addr ? addr : sp;
+        thread->stack_guard.length = 0;
+    }
+    else if ((hit = _find_overrun(addr, length)))
+    {
+        /* rearm the guard in case the handler recovers */
+        memset(addr, MYST_STACK_GUARD_BYTE, length);
+    }
+    else
+    {
+        return;
+    }
+
+    _raise(thread, hit, false);
+}
+
+/* where a thread resumes after it faults in its inaccessible guard */
+__attribute__((force_align_arg_pointer)) static void _fault_entry(void)
+{
+    long params[6] = {0};
+
+    /* entering the kernel raises SIGSEGV (see myst_stack_guard_check) */
+    myst_syscall(SYS_sched_yield, params);
+}
+
+static bool _handle_fault(uint64_t addr, uint64_t* rip, uint64_t* rsp)
+{
+    myst_thread_t* thread;
+    uint8_t* p = (uint8_t*)addr;
+    uint8_t* base;
+    uint64_t sp;
+
+    if (myst_tcall_get_tsd((uint64_t*)&thread) != 0 ||
+        !myst_valid_thread(thread))
+    {
+        return false;
+    }
+
+    base = thread->stack_guard.protected_addr;
+
+    if (!base || p < base || p >= base + thread->stack_guard.protected_length)
+        return false;
+
+    /* take the signal on the alternate stack; otherwise the process dies,
+     * so the stack that the thread cannot go back to will do */
+    if (_has_altstack_handler(thread))
+    {
+        const stack_t* ss = &thread->signal.altstack;
+        sp = (uint64_t)ss->ss_sp + ss->ss_size;
+    }
+    else
+    {
+        sp = (uint64_t)thread->stack_guard.start_sp;
+    }
+
+    /* as if _fault_entry() were called on that stack */
+    thread->stack_guard.fault = p;
+    *rsp = (sp & ~(uint64_t)15) - sizeof(uint64_t);
+    *rip = (uint64_t)_fault_entry;
+
+    return true;
+}
+
+int myst_stack_guard_setup(void)
+{
+    long ret = myst_tcall_set_fault_handler(_handle_fault);
+
+    /* the guards of targets without an exception handler are checked */
+    return ret == -ENOTSUP ? 0 : (int)ret;
+}
@@ -14,11 +14,13 @@
 #include <string.h>
 #include <sys/ioctl.h>
 #include <sys/mman.h>
+#include <sys/personality.h>
 #include <sys/prctl.h>
 #include <sys/resource.h>
 #include <sys/socket.h>
 #include <sys/stat.h>
 #include <sys/sysinfo.h>
+#include <sys/sysmacros.h>
 #include <sys/times.h>
 #include <sys/types.h>
 #include <sys/uio.h>
@@ -26,13 +28,16 @@
 #include <sys/vfs.h>
 #include <unistd.h>
 
+#include <myst/audit.h>
 #include <myst/backtrace.h>
 #include <myst/barrier.h>
 #include <myst/blkdev.h>
+#include <myst/budget.h>
 #include <myst/buf.h>
 #include <myst/clock.h>
 #include <myst/cpio.h>
 #include <myst/cwd.h>
+#include <myst/disk.h>
 #include <myst/epolldev.h>
 #include <myst/eraise.h>
 #include <myst/errno.h>
@@ -43,34 +48,52 @@
 #include <myst/file.h>
 #include <myst/fs.h>
 #include <myst/fsgs.h>
+#include <myst/futex.h>
 #include <myst/gcov.h>
 #include <myst/hex.h>
 #include <myst/hostfs.h>
 #include <myst/id.h>
 #include <myst/initfini.h>
 #include <myst/inotifydev.h>
+#include <myst/ioctl.h>
+#include <myst/iouringdev.h>
 #include <myst/kernel.h>
 #include <myst/libc.h>
 #include <myst/lsr.h>
 #include <myst/mmanutils.h>
 #include <myst/mount.h>
+#include <myst/oom.h>
 #include <myst/options.h>
 #include <myst/panic.h>
 #include <myst/paths.h>
+#include <myst/perms.h>
 #include <myst/pipedev.h>
 #include <myst/printf.h>
 #include <myst/process.h>
 #include <myst/pubkey.h>
+#include <myst/quotecache.h>
 #include <myst/ramfs.h>
+#include <myst/realpath.h>
+#include <myst/resolve.h>
+#include <myst/rusage.h>
+#include <myst/sched.h>
+#include <myst/scmrights.h>
 #include <myst/setjmp.h>
+#include <myst/shutdown.h>
 #include <myst/signal.h>
 #include <myst/spinlock.h>
+#include <myst/stackguard.h>
+#include <myst/statx.h>
 #include <myst/strings.h>
 #include <myst/syscall.h>
+#include <myst/syscallpolicy.h>
 #include <myst/tcall.h>
 #include <myst/thread.h>
 #include <myst/times.h>
 #include <myst/trace.h>
+#include <myst/uaccess.h>
+#include <myst/work.h>
+#include <myst/wxpolicy.h>
 
 #define DEV_URANDOM_FD MYST_FDTABLE_SIZE
 
@@ -423,6 +446,11 @@ static pair_t _pairs[] = {
     {SYS_statx, "SYS_statx"},
     {SYS_io_pgetevents, "SYS_io_pgetevents"},
     {SYS_rseq, "SYS_rseq"},
+    {SYS_io_uring_setup, "SYS_io_uring_setup"},
+    {SYS_io_uring_enter, "SYS_io_uring_enter"},
+    {SYS_io_uring_register, "SYS_io_uring_register"},
+    {SYS_openat2, "SYS_openat2"},
+    {SYS_faccessat2, "SYS_faccessat2"},
     {SYS_myst_trace, "SYS_myst_trace"},
     {SYS_myst_trace_ptr, "SYS_myst_trace_ptr"},
     {SYS_myst_dump_ehdr, "SYS_myst_dump_ehdr"},
@@ -462,6 +490,7 @@ static pair_t _pairs[] = {
     {SYS_myst_oe_verify_attestation_certificate,
      "SYS_myst_oe_verify_attestation_certificate"},
     {SYS_myst_oe_result_str, "SYS_myst_oe_result_str"},
+    {SYS_myst_run_work, "SYS_myst_run_work"},
 };
 
 // The kernel should eventually use _bad_addr() to check all incoming addresses
@@ -504,6 +533,26 @@ const char* syscall_str(long n)
     return "unknown";
 }
 
+long myst_syscall_num(const char* name)
+{
+    const char prefix[] = "SYS_";
+
+    if (!name)
+        return -1;
+
+    /* accept names with and without the "SYS_" prefix */
+    if (strncmp(name, prefix, sizeof(prefix) - 1) == 0)
+        name += sizeof(prefix) - 1;
+
+    for (size_t i = 0; i < _n_pairs; i++)
+    {
+        if (strcmp(name, _pairs[i].str + sizeof(prefix) - 1) == 0)
+            return _pairs[i].num;
+    }
+
+    return -1;
+}
+
 __attribute__((format(printf, 2, 3))) static void _strace(
     long n,
     const char* fmt,
@@ -546,6 +595,25 @@ static long _forward_syscall(long n, long params[6])
     return myst_tcall(n, params);
 }
 
+/* Open Enclave extensions that produce evidence or key material */
+static bool _is_attestation_request(long n)
+{
+    switch (n)
+    {
+        case SYS_myst_oe_get_report_v2:
+        case SYS_myst_oe_get_seal_key_by_policy_v2:
+        case SYS_myst_oe_get_public_key_by_policy:
+        case SYS_myst_oe_get_public_key:
+        case SYS_myst_oe_get_private_key_by_policy:
+        case SYS_myst_oe_get_private_key:
+        case SYS_myst_oe_get_seal_key_v2:
+        case SYS_myst_oe_generate_attestation_certificate:
+            return true;
+        default:
+            return false;
+    }
+}
+
 typedef struct fd_entry
 {
     int fd;
@@ -610,7 +678,7 @@ static int _add_fd_link(myst_fs_t* fs, myst_file_t* file, int fd)
     if (!fs || !file)
         ERAISE(-EINVAL);
 
-    ECHECK((*fs->fs_realpath)(fs, file, realpath, sizeof(realpath)));
+    ECHECK(myst_fs_path(fs, file, realpath, sizeof(realpath)));
 
     if (snprintf(linkpath, n, "/proc/%d/fd/%d", myst_getpid(), fd) >= (int)n)
         ERAISE(-ENAMETOOLONG);
@@ -655,6 +723,52 @@ done:
     return ret;
 }
 
+static long _open_pty(const char* pathname, int flags)
+{
+    long ret = 0;
+    myst_ptydev_t* ptydev = myst_ptydev_get();
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    const myst_fdtable_type_t fdtype = MYST_FDTABLE_TYPE_PTY;
+    myst_pty_t* pty;
+    int fd;
+
+    ECHECK((*ptydev->pd_open)(ptydev, pathname, flags, &pty));
+
+    if ((fd = myst_fdtable_assign(fdtable, fdtype, ptydev, pty)) < 0)
+    {
+        (*ptydev->fdops.fd_close)(ptydev, pty);
+        ERAISE(fd);
+    }
+
+    ret = fd;
+
+done:
+    return ret;
+}
+
+static long _open_disk(const char* pathname, int flags)
+{
+    long ret = 0;
+    myst_diskdev_t* diskdev = myst_diskdev_get();
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    const myst_fdtable_type_t fdtype = MYST_FDTABLE_TYPE_DISK;
+    myst_disk_file_t* file;
+    int fd;
+
+    ECHECK((*diskdev->dd_open)(diskdev, pathname, flags, &file));
+
+    if ((fd = myst_fdtable_assign(fdtable, fdtype, diskdev, file)) < 0)
+    {
+        (*diskdev->fdops.fd_close)(diskdev, file);
+        ERAISE(fd);
+    }
+
+    ret = fd;
+
+done:
+    return ret;
+}
+
 long myst_syscall_open(const char* pathname, int flags, mode_t mode)
 {
     long ret = 0;
@@ -673,6 +787,14 @@ long myst_syscall_open(const char* pathname, int flags, mode_t mode)
         return DEV_URANDOM_FD;
     }
 
+    /* pseudo-terminals are not in any file system (see kernel/ptydev.c) */
+    if (myst_is_pty_path(pathname))
+        return _open_pty(pathname, flags);
+
+    /* nor are the disks of config.json (see kernel/disk.c) */
+    if (myst_disk_find(pathname) >= 0)
+        return _open_disk(pathname, flags);
+
     ECHECK(myst_mount_resolve(pathname, suffix, &fs));
     ECHECK((*fs->fs_open)(fs, suffix, flags, mode, &fs_out, &file));
 
@@ -758,7 +880,7 @@ static long _openat(
         }
 
         /* get the full path of dirfd */
-        ECHECK((*fs->fs_realpath)(fs, file, dirname, sizeof(dirname)));
+        ECHECK(myst_fs_path(fs, file, dirname, sizeof(dirname)));
         ECHECK(myst_make_path(filename, sizeof(filename), dirname, pathname));
 
         if (fs_out && file_out)
@@ -788,6 +910,151 @@ long myst_syscall_openat(
     return _openat(dirfd, pathname, flags, mode, NULL, NULL);
 }
 
+/* the absolute path of the directory that dirfd refers to */
+static int _dirfd_path(int dirfd, char* buf, size_t size)
+{
+    int ret = 0;
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    myst_fs_t* fs;
+    myst_file_t* file;
+    struct stat st;
+
+    if (dirfd == AT_FDCWD)
+    {
+        ECHECK(myst_syscall_getcwd(buf, size));
+        goto done;
+    }
+
+    if (dirfd < 0)
+        ERAISE(-EBADF);
+
+    ECHECK(myst_fdtable_get_file(fdtable, dirfd, &fs, &file));
+    ECHECK((*fs->fs_fstat)(fs, file, &st));
+
+    if (!S_ISDIR(st.st_mode))
+        ERAISE(-ENOTDIR);
+
+    ECHECK(myst_fs_path(fs, file, buf, size));
+
+done:
+    return ret;
+}
+
+long myst_syscall_openat2(
+    int dirfd,
+    const char* pathname,
+    struct myst_open_how* how,
+    size_t size)
+{
+    long ret = 0;
+    const uint64_t valid_flags =
+        O_ACCMODE | O_CREAT | O_EXCL | O_NOCTTY | O_TRUNC | O_APPEND |
+        O_NONBLOCK | O_DSYNC | O_ASYNC | O_DIRECT | O_LARGEFILE | O_DIRECTORY |
+        O_NOFOLLOW | O_NOATIME | O_CLOEXEC | O_SYNC | O_PATH | O_TMPFILE;
+    const uint64_t scoped = MYST_RESOLVE_BENEATH | MYST_RESOLVE_IN_ROOT;
+    myst_open_how_t h;
+    typedef struct _variables
+    {
+        char dirpath[PATH_MAX];
+        myst_path_t path;
+    } variables_t;
+    variables_t* v = NULL;
+    bool creat;
+    bool follow;
+    bool locked = false;
+    int flags;
+
+    if (!pathname || !how)
+        ERAISE(-EFAULT);
+
+    if (size < sizeof(myst_open_how_t))
+        ERAISE(-EINVAL);
+
+    if (size > PAGE_SIZE)
+        ERAISE(-E2BIG);
+
+    /* a newer struct open_how is fine as long as the new fields are zero */
+    for (size_t i = sizeof(myst_open_how_t); i < size; i++)
+    {
+        if (((const uint8_t*)how)[i])
+            ERAISE(-E2BIG);
+    }
+
+    memcpy(&h, how, sizeof(h));
+
+    /* unlike openat(), unknown flags and modes are errors */
+    if (h.flags & ~valid_flags)
+        ERAISE(-EINVAL);
+
+    creat = (h.flags & O_CREAT) || (h.flags & O_TMPFILE) == O_TMPFILE;
+
+    if (h.mode & ~(uint64_t)07777 || (h.mode && !creat))
+        ERAISE(-EINVAL);
+
+    if (h.resolve & ~(uint64_t)MYST_RESOLVE_ALL ||
+        (h.resolve & scoped) == scoped)
+    {
+        ERAISE(-EINVAL);
+    }
+
+    /* lookups never block here, but creating and truncating might */
+    if ((h.resolve & MYST_RESOLVE_CACHED) && (creat || h.flags & O_TRUNC))
+        ERAISE(-EAGAIN);
+
+    if (*pathname == '\0')
+        ERAISE(-ENOENT);
+
+    if (!(v = malloc(sizeof(variables_t))))
+        ERAISE(-ENOMEM);
+
+    /* absolute paths only depend on the directory when they are scoped */
+    if (*pathname == '/' && !(h.resolve & scoped))
+        myst_strlcpy(v->dirpath, "/", sizeof(v->dirpath));
+    else
+        ECHECK(_dirfd_path(dirfd, v->dirpath, sizeof(v->dirpath)));
+
+    flags = (int)h.flags;
+    follow = !(flags & O_NOFOLLOW) && !((flags & O_CREAT) && (flags & O_EXCL));
+
+    /* no path may change between the restricted lookup and the open */
+    if (h.resolve & ~(uint64_t)MYST_RESOLVE_CACHED)
+    {
+        myst_resolve_lock();
+        locked = true;
+    }
+
+    ECHECK(myst_resolve_path(
+        v->dirpath, pathname, h.resolve, follow, &v->path));
+
+    if (follow)
+    {
+        /* every link was followed already */
+        flags |= O_NOFOLLOW;
+    }
+    else if (!(flags & O_PATH))
+    {
+        struct stat st;
+
+        /* the file systems follow a final symbolic link whatever the flags */
+        if (myst_syscall_lstat(v->path.buf, &st) == 0 && S_ISLNK(st.st_mode))
+            ERAISE((flags & O_CREAT) && (flags & O_EXCL) ? -EEXIST : -ELOOP);
+    }
+
+    /* as the dispatcher does for open() and openat() */
+    ret = myst_syscall_open(
+        v->path.buf, flags, myst_perms_umask((mode_t)h.mode));
+
+done:
+
+    if (locked)
+        myst_resolve_unlock();
+
+    if (v)
+        free(v);
+
+    return ret;
+}
+
 long myst_syscall_epoll_create1(int flags)
 {
     long ret = 0;
@@ -823,14 +1090,27 @@ done:
 long myst_syscall_lseek(int fd, off_t offset, int whence)
 {
     long ret = 0;
-    myst_fs_t* fs;
-    myst_file_t* file;
-    const myst_fdtable_type_t type = MYST_FDTABLE_TYPE_FILE;
     myst_fdtable_t* fdtable = myst_fdtable_current();
+    myst_fdtable_type_t type;
+    void* device = NULL;
+    void* object = NULL;
 
-    ECHECK(myst_fdtable_get(fdtable, fd, type, (void**)&fs, (void**)&file));
+    ECHECK(myst_fdtable_get_any(fdtable, fd, &type, &device, &object));
+
+    if (type == MYST_FDTABLE_TYPE_DISK)
+    {
+        myst_diskdev_t* diskdev = device;
+        ret = (*diskdev->dd_lseek)(diskdev, object, offset, whence);
+    }
+    else
+    {
+        myst_fs_t* fs;
+        myst_file_t* file;
 
-    ret = ((*fs->fs_lseek)(fs, file, offset, whence));
+        ECHECK(myst_fdtable_get(
+            fdtable, fd, MYST_FDTABLE_TYPE_FILE, (void**)&fs, (void**)&file));
+        ret = ((*fs->fs_lseek)(fs, file, offset, whence));
+    }
 
 done:
     return ret;
@@ -926,6 +1206,12 @@ long myst_syscall_pread(int fd, void* buf, size_t count, off_t offset)
             ret = (*fs->fs_pread)(fs, file, buf, count, offset);
             break;
         }
+        case MYST_FDTABLE_TYPE_DISK:
+        {
+            myst_diskdev_t* diskdev = device;
+            ret = (*diskdev->dd_pread)(diskdev, object, buf, count, offset);
+            break;
+        }
         case MYST_FDTABLE_TYPE_PIPE:
         {
             ret = -ESPIPE;
@@ -967,6 +1253,12 @@ long myst_syscall_pwrite(int fd, const void* buf, size_t count, off_t offset)
             ret = (*fs->fs_pwrite)(fs, file, buf, count, offset);
             break;
         }
+        case MYST_FDTABLE_TYPE_DISK:
+        {
+            myst_diskdev_t* diskdev = device;
+            ret = (*diskdev->dd_pwrite)(diskdev, object, buf, count, offset);
+            break;
+        }
         case MYST_FDTABLE_TYPE_PIPE:
         {
             ret = -ESPIPE;
@@ -1025,12 +1317,30 @@ done:
     return ret;
 }
 
+/* the status of a disk, which is in no file system */
+static long _stat_disk(const char* pathname, struct stat* statbuf)
+{
+    long ret = 0;
+    myst_diskdev_t* diskdev = myst_diskdev_get();
+    myst_disk_file_t* file;
+
+    ECHECK((*diskdev->dd_open)(diskdev, pathname, O_RDONLY, &file));
+    ret = (*diskdev->fdops.fd_fstat)(diskdev, file, statbuf);
+    (*diskdev->fdops.fd_close)(diskdev, file);
+
+done:
+    return ret;
+}
+
 long myst_syscall_stat(const char* pathname, struct stat* statbuf)
 {
     long ret = 0;
     char suffix[PATH_MAX];
     myst_fs_t* fs;
 
+    if (myst_disk_find(pathname) >= 0)
+        return _stat_disk(pathname, statbuf);
+
     ECHECK(myst_mount_resolve(pathname, suffix, &fs));
     ECHECK((*fs->fs_stat)(fs, suffix, statbuf));
 
@@ -1044,6 +1354,9 @@ long myst_syscall_lstat(const char* pathname, struct stat* statbuf)
     char suffix[PATH_MAX];
     myst_fs_t* fs;
 
+    if (myst_disk_find(pathname) >= 0)
+        return _stat_disk(pathname, statbuf);
+
     ECHECK(myst_mount_resolve(pathname, suffix, &fs));
     ECHECK((*fs->fs_lstat)(fs, suffix, statbuf));
 
@@ -1107,7 +1420,7 @@ long myst_syscall_fstatat(
             char realpath[PATH_MAX];
 
             ECHECK(myst_fdtable_get_file(fdtable, dirfd, &fs, &file));
-            ECHECK((*fs->fs_realpath)(fs, file, realpath, sizeof(realpath)));
+            ECHECK(myst_fs_path(fs, file, realpath, sizeof(realpath)));
             ECHECK(myst_syscall_lstat(realpath, statbuf));
             goto done;
         }
@@ -1126,7 +1439,7 @@ long myst_syscall_fstatat(
         char path[PATH_MAX];
 
         ECHECK(myst_fdtable_get_file(fdtable, dirfd, &fs, &file));
-        ECHECK((*fs->fs_realpath)(fs, file, dirpath, sizeof(dirpath)));
+        ECHECK(myst_fs_path(fs, file, dirpath, sizeof(dirpath)));
         ECHECK(myst_make_path(path, sizeof(path), dirpath, pathname));
 
         if (flags & AT_SYMLINK_NOFOLLOW)
@@ -1145,6 +1458,129 @@ done:
     return ret;
 }
 
+/* get the path of the file that fstatat() with these arguments gets */
+static int _fstatat_path(
+    int dirfd,
+    const char* pathname,
+    char* buf,
+    size_t size)
+{
+    int ret = 0;
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    myst_fs_t* fs;
+    myst_file_t* file;
+    char dirpath[PATH_MAX];
+
+    if (*pathname == '/' || dirfd == AT_FDCWD)
+    {
+        if (myst_strlcpy(buf, pathname, size) >= size)
+            ERAISE(-ENAMETOOLONG);
+    }
+    else if (*pathname == '\0')
+    {
+        ECHECK(myst_fdtable_get_file(fdtable, dirfd, &fs, &file));
+        ECHECK(myst_fs_path(fs, file, buf, size));
+    }
+    else
+    {
+        ECHECK(myst_fdtable_get_file(fdtable, dirfd, &fs, &file));
+        ECHECK(myst_fs_path(fs, file, dirpath, sizeof(dirpath)));
+        ECHECK(myst_make_path(buf, size, dirpath, pathname));
+    }
+
+done:
+    return ret;
+}
+
+static void _to_statx_timestamp(
+    myst_statx_timestamp_t* ts,
+    const struct timespec* tp)
+{
+    ts->tv_sec = tp->tv_sec;
+    ts->tv_nsec = (uint32_t)tp->tv_nsec;
+}
+
+long myst_syscall_statx(
+    int dirfd,
+    const char* pathname,
+    int flags,
+    unsigned int mask,
+    struct myst_statx* statxbuf)
+{
+    long ret = 0;
+    const int valid_flags = AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH |
+                            MYST_AT_NO_AUTOMOUNT | MYST_AT_STATX_SYNC_TYPE;
+    myst_statx_t* stx = statxbuf;
+    struct stat st;
+
+    if (!pathname || !stx)
+        ERAISE(-EFAULT);
+
+    if ((flags & ~valid_flags) ||
+        (flags & MYST_AT_STATX_SYNC_TYPE) == MYST_AT_STATX_SYNC_TYPE)
+    {
+        ERAISE(-EINVAL);
+    }
+
+    if (mask & MYST_STATX__RESERVED)
+        ERAISE(-EINVAL);
+
+    if (*pathname == '\0')
+    {
+        if (!(flags & AT_EMPTY_PATH))
+            ERAISE(-ENOENT);
+
+        /* the working directory itself */
+        if (dirfd == AT_FDCWD)
+            pathname = ".";
+    }
+
+    /* there is nothing to synchronize (so the sync type is ignored) */
+    ECHECK(myst_syscall_fstatat(
+        dirfd, pathname, &st, flags & (AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH)));
+
+    memset(stx, 0, sizeof(myst_statx_t));
+
+    /* as Linux, return all the basic fields whatever was asked for */
+    stx->stx_mask = MYST_STATX_BASIC_STATS;
+    stx->stx_blksize = (uint32_t)st.st_blksize;
+    stx->stx_nlink = (uint32_t)st.st_nlink;
+    stx->stx_uid = st.st_uid;
+    stx->stx_gid = st.st_gid;
+    stx->stx_mode = (uint16_t)st.st_mode;
+    stx->stx_ino = st.st_ino;
+    stx->stx_size = (uint64_t)st.st_size;
+    stx->stx_blocks = (uint64_t)st.st_blocks;
+    _to_statx_timestamp(&stx->stx_atime, &st.st_atim);
+    _to_statx_timestamp(&stx->stx_ctime, &st.st_ctim);
+    _to_statx_timestamp(&stx->stx_mtime, &st.st_mtim);
+    stx->stx_rdev_major = major(st.st_rdev);
+    stx->stx_rdev_minor = minor(st.st_rdev);
+    stx->stx_dev_major = major(st.st_dev);
+    stx->stx_dev_minor = minor(st.st_dev);
+
+    /* only some file systems record the creation time */
+    if (mask & MYST_STATX_BTIME)
+    {
+        const bool follow = !(flags & AT_SYMLINK_NOFOLLOW);
+        char path[PATH_MAX];
+        char suffix[PATH_MAX];
+        myst_fs_t* fs;
+        struct timespec btime;
+
+        if (_fstatat_path(dirfd, pathname, path, sizeof(path)) == 0 &&
+            myst_mount_resolve(path, suffix, &fs) == 0 && fs->fs_btime &&
+            (*fs->fs_btime)(fs, suffix, follow, &btime) == 0)
+        {
+            _to_statx_timestamp(&stx->stx_btime, &btime);
+            stx->stx_mask |= MYST_STATX_BTIME;
+        }
+    }
+
+done:
+    return ret;
+}
+
 static const char* _trim_trailing_slashes(
     const char* pathname,
     char* buf,
@@ -1227,6 +1663,9 @@ long myst_syscall_link(const char* oldpath, const char* newpath)
     myst_fs_t* old_fs;
     myst_fs_t* new_fs;
 
+    /* not while a scoped openat2() opens a file (see myst_resolve_lock) */
+    myst_resolve_lock();
+
     ECHECK(myst_mount_resolve(oldpath, old_suffix, &old_fs));
     ECHECK(myst_mount_resolve(newpath, new_suffix, &new_fs));
 
@@ -1239,6 +1678,7 @@ long myst_syscall_link(const char* oldpath, const char* newpath)
     ECHECK((*old_fs->fs_link)(old_fs, old_suffix, new_suffix));
 
 done:
+    myst_resolve_unlock();
     return ret;
 }
 
@@ -1268,30 +1708,79 @@ done:
     return ret;
 }
 
-long myst_syscall_rename(const char* oldpath, const char* newpath)
+long myst_syscall_faccessat(
+    int dirfd,
+    const char* pathname,
+    int mode,
+    int flags)
 {
     long ret = 0;
-    char old_suffix[PATH_MAX];
-    char new_suffix[PATH_MAX];
-    myst_fs_t* old_fs;
-    myst_fs_t* new_fs;
+    const int valid_flags = AT_EACCESS | AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH;
+    char path[PATH_MAX];
 
-    ECHECK(myst_mount_resolve(oldpath, old_suffix, &old_fs));
-    ECHECK(myst_mount_resolve(newpath, new_suffix, &new_fs));
+    if (!pathname)
+        ERAISE(-EFAULT);
 
-    if (old_fs != new_fs)
+    if ((flags & ~valid_flags) || (mode & ~(R_OK | W_OK | X_OK)))
+        ERAISE(-EINVAL);
+
+    if (*pathname == '\0')
     {
-        /* oldpath and newpath are not on the same mounted file system */
-        ERAISE(-EXDEV);
+        if (!(flags & AT_EMPTY_PATH))
+            ERAISE(-ENOENT);
+
+        /* the working directory itself */
+        if (dirfd == AT_FDCWD)
+            pathname = ".";
     }
 
-    ECHECK((*old_fs->fs_rename)(old_fs, old_suffix, new_suffix));
+    ECHECK(_fstatat_path(dirfd, pathname, path, sizeof(path)));
+
+    /* a symbolic link itself grants every access (as its mode is 0777) */
+    if (flags & AT_SYMLINK_NOFOLLOW)
+    {
+        struct stat st;
+
+        ECHECK(myst_syscall_lstat(path, &st));
+
+        if (S_ISLNK(st.st_mode))
+            goto done;
+    }
+
+    /* the real and effective IDs are the same (so AT_EACCESS is ignored) */
+    ECHECK(myst_syscall_access(path, mode));
 
 done:
     return ret;
 }
 
-long myst_syscall_truncate(const char* path, off_t length)
+long myst_syscall_rename(const char* oldpath, const char* newpath)
+{
+    long ret = 0;
+    char old_suffix[PATH_MAX];
+    char new_suffix[PATH_MAX];
+    myst_fs_t* old_fs;
+    myst_fs_t* new_fs;
+
+    myst_resolve_lock();
+
+    ECHECK(myst_mount_resolve(oldpath, old_suffix, &old_fs));
+    ECHECK(myst_mount_resolve(newpath, new_suffix, &new_fs));
+
+    if (old_fs != new_fs)
+    {
+        /* oldpath and newpath are not on the same mounted file system */
+        ERAISE(-EXDEV);
+    }
+
+    ECHECK((*old_fs->fs_rename)(old_fs, old_suffix, new_suffix));
+
+done:
+    myst_resolve_unlock();
+    return ret;
+}
+
+long myst_syscall_truncate(const char* path, off_t length)
 {
     long ret = 0;
     char suffix[PATH_MAX];
@@ -1338,10 +1827,13 @@ long myst_syscall_symlink(const char* target, const char* linkpath)
     char suffix[PATH_MAX];
     myst_fs_t* fs;
 
+    myst_resolve_lock();
+
     ECHECK(myst_mount_resolve(linkpath, suffix, &fs));
     ERAISE((*fs->fs_symlink)(fs, target, suffix));
 
 done:
+    myst_resolve_unlock();
     return ret;
 }
 
@@ -1413,6 +1905,126 @@ done:
     return ret;
 }
 
+long myst_syscall_fchdir(int fd)
+{
+    long ret = 0;
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    myst_fs_t* fs;
+    myst_file_t* file;
+    struct stat buf;
+    char path[PATH_MAX];
+
+    ECHECK(myst_fdtable_get_file(fdtable, fd, &fs, &file));
+    ECHECK((*fs->fs_fstat)(fs, file, &buf));
+
+    if (!S_ISDIR(buf.st_mode))
+        ERAISE(-ENOTDIR);
+
+    /* a directory outside the root of the process resolves to the root */
+    ECHECK(myst_fs_path(fs, file, path, sizeof(path)));
+    ECHECK(myst_syscall_chdir(path));
+
+done:
+    return ret;
+}
+
+long myst_syscall_chroot(const char* path)
+{
+    long ret = 0;
+    myst_thread_t* thread = myst_thread_self();
+    myst_thread_t* process_thread = myst_find_process_thread(thread);
+    typedef struct _variables
+    {
+        char dirpath[PATH_MAX];
+        myst_path_t resolved;
+        myst_path_t root;
+        myst_path_t cwd;
+    } variables_t;
+    variables_t* v = NULL;
+    bool locked = false;
+    char* root = NULL;
+    char* cwd = NULL;
+    const char* p = "/";
+
+    if (!path || _bad_addr(path))
+        ERAISE(-EFAULT);
+
+    if (!(v = malloc(sizeof(variables_t))))
+        ERAISE(-ENOMEM);
+
+    /* the new root must be a directory */
+    {
+        struct stat buf;
+
+        ECHECK(myst_syscall_stat(path, &buf));
+
+        if (!S_ISDIR(buf.st_mode))
+            ERAISE(-ENOTDIR);
+    }
+
+    /* resolve the new root beneath the current one, without symbolic links
+     * so that the file systems can find it while they follow a link */
+    ECHECK(myst_syscall_getcwd(v->dirpath, sizeof(v->dirpath)));
+    ECHECK(myst_resolve_path(v->dirpath, path, 0, true, &v->resolved));
+    ECHECK(myst_realpath(v->resolved.buf, &v->root));
+
+    myst_spin_lock(&process_thread->main.cwd_lock);
+    locked = true;
+
+    ECHECK(myst_realpath(process_thread->main.cwd, &v->cwd));
+
+    /* The working directory is kept relative to the root, so move it beneath
+     * the new root. If it lies outside then it moves to the new root itself
+     * (Linux would leave it there, which lets the process escape).
+     */
+    if (strcmp(v->root.buf, "/") == 0)
+    {
+        p = v->cwd.buf;
+    }
+    else
+    {
+        size_t len = strlen(v->root.buf);
+
+        if (strncmp(v->cwd.buf, v->root.buf, len) == 0 &&
+            v->cwd.buf[len] == '/')
+        {
+            p = v->cwd.buf + len;
+        }
+
+        if (!(root = strdup(v->root.buf)))
+            ERAISE(-ENOMEM);
+    }
+
+    if (!(cwd = strdup(p)))
+        ERAISE(-ENOMEM);
+
+    myst_spin_lock(&process_thread->main.root_lock);
+    free(process_thread->main.root);
+    process_thread->main.root = root;
+    root = NULL;
+    myst_spin_unlock(&process_thread->main.root_lock);
+
+    free(process_thread->main.cwd);
+    process_thread->main.cwd = cwd;
+    cwd = NULL;
+
+done:
+
+    if (locked)
+        myst_spin_unlock(&process_thread->main.cwd_lock);
+
+    if (v)
+        free(v);
+
+    if (root)
+        free(root);
+
+    if (cwd)
+        free(cwd);
+
+    return ret;
+}
+
 long myst_syscall_statfs(const char* path, struct statfs* buf)
 {
     long ret = 0;
@@ -1448,35 +2060,240 @@ done:
     return ret;
 }
 
-static char _hostname[HOST_NAME_MAX] = "TEE";
-static myst_spinlock_t _hostname_lock = MYST_SPINLOCK_INITIALIZER;
-
-long myst_syscall_uname(struct utsname* buf)
-{
+/* The uname strings; the hostname and domainname may be changed by the
+ * application, the rest are fixed once the kernel starts */
+static struct utsname _uname = {
+    .sysname = "Linux",
+    .nodename = "TEE",
     // We are emulating Linux syscalls. 5.4.0 is the LTS release we
     // try to emulate. The release number should be updated when
     // Mystikos adapts to syscall API changes in future Linux releases.
-    MYST_STRLCPY(buf->sysname, "Linux");
-    MYST_STRLCPY(buf->release, "5.4.0");
-    MYST_STRLCPY(buf->version, "Mystikos 1.0.0");
-    MYST_STRLCPY(buf->machine, "x86_64");
+    .release = "5.4.0",
+    .version = "Mystikos 1.0.0",
+    .machine = "x86_64",
+    .domainname = "(none)",
+};
+static myst_spinlock_t _uname_lock = MYST_SPINLOCK_INITIALIZER;
 
-    myst_spin_lock(&_hostname_lock);
-    MYST_STRLCPY(buf->nodename, _hostname);
-    myst_spin_unlock(&_hostname_lock);
+int myst_set_uname(
+    const char* release,
+    const char* version,
+    const char* machine)
+{
+    if ((release && strlen(release) >= sizeof(_uname.release)) ||
+        (version && strlen(version) >= sizeof(_uname.version)) ||
+        (machine && strlen(machine) >= sizeof(_uname.machine)))
+    {
+        return -ENAMETOOLONG;
+    }
+
+    myst_spin_lock(&_uname_lock);
+
+    if (release)
+        MYST_STRLCPY(_uname.release, release);
+
+    if (version)
+        MYST_STRLCPY(_uname.version, version);
+
+    if (machine)
+        MYST_STRLCPY(_uname.machine, machine);
+
+    myst_spin_unlock(&_uname_lock);
 
     return 0;
 }
 
-long myst_syscall_sethostname(const char* hostname, MYST_UNUSED size_t len)
+long myst_syscall_uname(struct utsname* buf)
 {
-    myst_spin_lock(&_hostname_lock);
-    MYST_STRLCPY(_hostname, hostname);
-    myst_spin_unlock(&_hostname_lock);
+    if (!buf)
+        return -EFAULT;
+
+    myst_spin_lock(&_uname_lock);
+    *buf = _uname;
+    myst_spin_unlock(&_uname_lock);
 
     return 0;
 }
 
+/* set a utsname field from a name that need not be null-terminated */
+static long _set_uts_field(
+    char* field,
+    size_t size,
+    const char* name,
+    size_t len)
+{
+    if (len >= size)
+        return -EINVAL;
+
+    if (!name && len)
+        return -EFAULT;
+
+    myst_spin_lock(&_uname_lock);
+    memcpy(field, name, len);
+    field[len] = '\0';
+    myst_spin_unlock(&_uname_lock);
+
+    return 0;
+}
+
+long myst_syscall_sethostname(const char* hostname, size_t len)
+{
+    return _set_uts_field(
+        _uname.nodename, sizeof(_uname.nodename), hostname, len);
+}
+
+long myst_syscall_setdomainname(const char* name, size_t len)
+{
+    return _set_uts_field(
+        _uname.domainname, sizeof(_uname.domainname), name, len);
+}
+
+long myst_syscall_prctl(int option, unsigned long arg2)
+{
+    long ret = 0;
+    myst_thread_t* thread = myst_thread_self();
+
+    switch (option)
+    {
+        case PR_GET_NAME:
+        {
+            char* name = (char*)arg2;
+
+            if (!name)
+                ERAISE(-EFAULT);
+
+            /* the caller's buffer is 16 bytes, including the null byte */
+            memcpy(name, myst_get_thread_name(thread), sizeof(thread->name));
+            break;
+        }
+        case PR_SET_NAME:
+        {
+            const char* name = (const char*)arg2;
+
+            if (!name)
+                ERAISE(-EFAULT);
+
+            ECHECK(myst_set_thread_name(thread, name));
+            break;
+        }
+        case PR_GET_DUMPABLE:
+        {
+            ret = myst_find_process_thread(thread)->main.dumpable;
+            break;
+        }
+        case PR_SET_DUMPABLE:
+        {
+            /* only SUID_DUMP_DISABLE and SUID_DUMP_USER may be set */
+            if (arg2 != 0 && arg2 != 1)
+                ERAISE(-EINVAL);
+
+            myst_find_process_thread(thread)->main.dumpable = (int)arg2;
+            break;
+        }
+        default:
+        {
+            ERAISE(-EINVAL);
+        }
+    }
+
+done:
+    return ret;
+}
+
+/* arch_prctl() codes (asm/prctl.h) */
+#define ARCH_SET_GS 0x1001
+#define ARCH_SET_FS 0x1002
+#define ARCH_GET_FS 0x1003
+#define ARCH_GET_GS 0x1004
+#define ARCH_GET_CPUID 0x1011
+#define ARCH_SET_CPUID 0x1012
+
+/* ARCH_SET_FS is handled by myst_syscall(), which owns the FS base */
+long myst_syscall_arch_prctl(int code, unsigned long addr)
+{
+    long ret = 0;
+    myst_thread_t* thread = myst_thread_self();
+
+    switch (code)
+    {
+        case ARCH_GET_FS:
+        {
+            unsigned long* fs = (unsigned long*)addr;
+
+            if (!fs)
+                ERAISE(-EFAULT);
+
+            /* the FS base of user space is the C-runtime thread descriptor
+             * once it has installed one */
+            if (thread->crt_td)
+                *fs = (unsigned long)thread->crt_td;
+            else
+                *fs = (unsigned long)thread->target_td;
+
+            break;
+        }
+        case ARCH_GET_GS:
+        {
+            unsigned long* gs = (unsigned long*)addr;
+
+            if (!gs)
+                ERAISE(-EFAULT);
+
+            /* the GS base belongs to the kernel; user space never set one */
+            *gs = 0;
+            break;
+        }
+        case ARCH_SET_GS:
+        {
+            /* the kernel keeps the target thread descriptor in GS */
+            ERAISE(-EPERM);
+        }
+        case ARCH_GET_CPUID:
+        {
+            /* the CPUID instruction is always available (it is emulated) */
+            ret = 1;
+            break;
+        }
+        case ARCH_SET_CPUID:
+        {
+            /* CPUID faulting is not available, as on CPUs without it */
+            if (addr == 0)
+                ERAISE(-ENODEV);
+
+            break;
+        }
+        default:
+        {
+            ERAISE(-EINVAL);
+        }
+    }
+
+done:
+    return ret;
+}
+
+long myst_syscall_personality(unsigned long persona)
+{
+    long ret = 0;
+    myst_thread_t* process = myst_find_process_thread(myst_thread_self());
+
+    ret = (long)process->main.personality;
+
+    /* 0xffffffff only queries the current persona */
+    if (persona == 0xffffffff)
+        goto done;
+
+    /* only the Linux execution domain is supported; the flags, such as
+     * ADDR_NO_RANDOMIZE, are recorded but have no effect */
+    if ((persona & PER_MASK) != PER_LINUX)
+        ERAISE(-EINVAL);
+
+    process->main.personality = persona;
+
+done:
+    return ret;
+}
+
 long myst_syscall_getrandom(void* buf, size_t buflen, unsigned int flags)
 {
     long ret = 0;
@@ -1526,9 +2343,19 @@ done:
 
 long myst_syscall_chmod(const char* pathname, mode_t mode)
 {
-    (void)pathname;
-    (void)mode;
-    return 0;
+    long ret = 0;
+    char suffix[PATH_MAX];
+    myst_fs_t* fs;
+
+    ECHECK(myst_mount_resolve(pathname, suffix, &fs));
+
+    if (!fs->fs_chmod)
+        ERAISE(-EPERM);
+
+    ECHECK((*fs->fs_chmod)(fs, suffix, mode));
+
+done:
+    return ret;
 }
 
 long myst_syscall_fchmod(int fd, mode_t mode)
@@ -1554,7 +2381,12 @@ long myst_syscall_fchmod(int fd, mode_t mode)
     }
     else if (type == MYST_FDTABLE_TYPE_FILE)
     {
-        /* ignore fchmod on files */
+        myst_fs_t* fs = device;
+
+        if (!fs->fs_fchmod)
+            ERAISE(-EPERM);
+
+        ECHECK((*fs->fs_fchmod)(fs, object, mode));
     }
     else
     {
@@ -1565,6 +2397,125 @@ done:
     return ret;
 }
 
+long myst_syscall_fchmodat(
+    int dirfd,
+    const char* pathname,
+    mode_t mode,
+    int flags)
+{
+    long ret = 0;
+    char path[PATH_MAX];
+
+    if (!pathname)
+        ERAISE(-EFAULT);
+
+    /* as on Linux, the mode of a symbolic link cannot be changed */
+    if (flags & AT_SYMLINK_NOFOLLOW)
+        ERAISE(-EOPNOTSUPP);
+
+    if (flags)
+        ERAISE(-EINVAL);
+
+    if (*pathname == '\0')
+        ERAISE(-ENOENT);
+
+    ECHECK(_fstatat_path(dirfd, pathname, path, sizeof(path)));
+    ECHECK(myst_syscall_chmod(path, mode));
+
+done:
+    return ret;
+}
+
+static long _chown(const char* pathname, bool follow, uid_t owner, gid_t group)
+{
+    long ret = 0;
+    char suffix[PATH_MAX];
+    myst_fs_t* fs;
+
+    ECHECK(myst_mount_resolve(pathname, suffix, &fs));
+
+    if (!fs->fs_chown)
+        ERAISE(-EPERM);
+
+    ECHECK((*fs->fs_chown)(fs, suffix, follow, owner, group));
+
+done:
+    return ret;
+}
+
+long myst_syscall_chown(const char* pathname, uid_t owner, gid_t group)
+{
+    return _chown(pathname, true, owner, group);
+}
+
+long myst_syscall_lchown(const char* pathname, uid_t owner, gid_t group)
+{
+    return _chown(pathname, false, owner, group);
+}
+
+long myst_syscall_fchown(int fd, uid_t owner, gid_t group)
+{
+    long ret = 0;
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    myst_fdtable_type_t type;
+    void* device = NULL;
+    void* object = NULL;
+    myst_fs_t* fs;
+
+    ECHECK(myst_fdtable_get_any(fdtable, fd, &type, &device, &object));
+
+    if (type != MYST_FDTABLE_TYPE_FILE)
+        ERAISE(-ENOTSUP);
+
+    fs = device;
+
+    if (!fs->fs_fchown)
+        ERAISE(-EPERM);
+
+    ECHECK((*fs->fs_fchown)(fs, object, owner, group));
+
+done:
+    return ret;
+}
+
+long myst_syscall_fchownat(
+    int dirfd,
+    const char* pathname,
+    uid_t owner,
+    gid_t group,
+    int flags)
+{
+    long ret = 0;
+    char path[PATH_MAX];
+
+    if (!pathname)
+        ERAISE(-EFAULT);
+
+    if (flags & ~(AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH))
+        ERAISE(-EINVAL);
+
+    if (*pathname == '\0')
+    {
+        if (!(flags & AT_EMPTY_PATH))
+            ERAISE(-ENOENT);
+
+        if (dirfd != AT_FDCWD)
+        {
+            ECHECK(myst_syscall_fchown(dirfd, owner, group));
+            goto done;
+        }
+
+        /* the working directory itself */
+        pathname = ".";
+    }
+
+    ECHECK(_fstatat_path(dirfd, pathname, path, sizeof(path)));
+    ECHECK(_chown(path, !(flags & AT_SYMLINK_NOFOLLOW), owner, group));
+
+done:
+    return ret;
+}
+
 long myst_syscall_pipe2(int pipefd[2], int flags)
 {
     int ret = 0;
@@ -1658,6 +2609,163 @@ done:
     return ret;
 }
 
+long myst_syscall_io_uring_setup(
+    uint32_t entries,
+    struct myst_io_uring_params* params)
+{
+    long ret = 0;
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    const myst_fdtable_type_t type = MYST_FDTABLE_TYPE_IO_URING;
+    myst_iouringdev_t* dev = myst_iouringdev_get();
+    myst_iouring_t* obj = NULL;
+    int fd;
+
+    if (!params)
+        ERAISE(-EFAULT);
+
+    ECHECK((*dev->iu_setup)(dev, entries, params, &obj));
+
+    if ((fd = myst_fdtable_assign(fdtable, type, dev, obj)) < 0)
+    {
+        (*dev->iu_close)(dev, obj);
+        ERAISE(fd);
+    }
+
+    ret = fd;
+
+done:
+    return ret;
+}
+
+long myst_syscall_io_uring_enter(
+    int fd,
+    uint32_t to_submit,
+    uint32_t min_complete,
+    uint32_t flags,
+    const sigset_t* sig,
+    size_t sigsz)
+{
+    long ret = 0;
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    myst_iouringdev_t* dev;
+    myst_iouring_t* obj;
+    sigset_t mask;
+
+    /* the signal mask to wait with, as for ppoll() */
+    if (sig)
+    {
+        if (sigsz != sizeof(uint64_t))
+            ERAISE(-EINVAL);
+
+        memset(&mask, 0, sizeof(mask));
+        ECHECK(myst_copy_from_user(&mask, sig, sizeof(uint64_t)));
+    }
+
+    ECHECK(myst_fdtable_get_iouring(fdtable, fd, &dev, &obj));
+
+    /* a signal let in meanwhile is delivered as the syscall returns, before
+     * the original mask is restored */
+    if (sig)
+        ECHECK(myst_signal_set_syscall_mask(&mask));
+
+    ret = (*dev->iu_enter)(dev, obj, to_submit, min_complete, flags);
+
+done:
+    return ret;
+}
+
+long myst_syscall_io_uring_register(
+    int fd,
+    uint32_t opcode,
+    void* arg,
+    uint32_t nr_args)
+{
+    long ret = 0;
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    myst_iouringdev_t* dev;
+    myst_iouring_t* obj;
+
+    ECHECK(myst_fdtable_get_iouring(fdtable, fd, &dev, &obj));
+    ret = (*dev->iu_register)(dev, obj, opcode, arg, nr_args);
+
+done:
+    return ret;
+}
+
+long myst_syscall_timerfd_create(int clockid, int flags)
+{
+    long ret = 0;
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    const myst_fdtable_type_t type = MYST_FDTABLE_TYPE_TIMERFD;
+    myst_timerfddev_t* dev = myst_timerfddev_get();
+    myst_timerfd_t* obj = NULL;
+    int fd;
+
+    ECHECK((*dev->td_create)(dev, clockid, flags, &obj));
+
+    if ((fd = myst_fdtable_assign(fdtable, type, dev, obj)) < 0)
+    {
+        (*dev->fdops.fd_close)(dev, obj);
+        ERAISE(fd);
+    }
+
+    ret = fd;
+
+done:
+    return ret;
+}
+
+/* like Linux, a descriptor that is not a timer fails with EINVAL */
+static int _get_timerfd(int fd, myst_timerfddev_t** dev, myst_timerfd_t** obj)
+{
+    int ret = 0;
+    myst_fdtable_t* fdtable = myst_fdtable_current();
+    myst_fdtable_type_t type;
+    void* device;
+    void* object;
+
+    ECHECK(myst_fdtable_get_any(fdtable, fd, &type, &device, &object));
+
+    if (type != MYST_FDTABLE_TYPE_TIMERFD)
+        ERAISE(-EINVAL);
+
+    *dev = device;
+    *obj = object;
+
+done:
+    return ret;
+}
+
+long myst_syscall_timerfd_settime(
+    int fd,
+    int flags,
+    const struct itimerspec* new_value,
+    struct itimerspec* old_value)
+{
+    long ret = 0;
+    myst_timerfddev_t* dev;
+    myst_timerfd_t* obj;
+
+    ECHECK(_get_timerfd(fd, &dev, &obj));
+    ret = (*dev->td_settime)(dev, obj, flags, new_value, old_value);
+
+done:
+    return ret;
+}
+
+long myst_syscall_timerfd_gettime(int fd, struct itimerspec* curr_value)
+{
+    long ret = 0;
+    myst_timerfddev_t* dev;
+    myst_timerfd_t* obj;
+
+    ECHECK(_get_timerfd(fd, &dev, &obj));
+    ret = (*dev->td_gettime)(dev, obj, curr_value);
+
+done:
+    return ret;
+}
+
 static size_t _count_args(const char* const args[])
 {
     size_t n = 0;
@@ -1731,6 +2839,31 @@ long myst_syscall_ioctl(int fd, unsigned long request, long arg)
     ECHECK(myst_fdtable_get_any(fdtable, fd, &type, &device, &object));
     fdops = device;
 
+    /* these apply to the file descriptor rather than the device */
+    if (request == FIOCLEX || request == FIONCLEX)
+    {
+        long cloexec = (request == FIOCLEX) ? FD_CLOEXEC : 0;
+        ret = (*fdops->fd_fcntl)(device, object, F_SETFD, cloexec);
+        goto done;
+    }
+
+    /* these set a file status flag, as fcntl(F_SETFL) does */
+    if (request == FIONBIO || request == FIOASYNC)
+    {
+        const long flag = (request == FIONBIO) ? O_NONBLOCK : O_ASYNC;
+        int on;
+        long flags;
+
+        ECHECK(MYST_COPY_FROM_USER(&on, (const int*)arg));
+        ECHECK(flags = (*fdops->fd_fcntl)(device, object, F_GETFL, 0));
+        flags = on ? (flags | flag) : (flags & ~flag);
+        ret = (*fdops->fd_fcntl)(device, object, F_SETFL, flags);
+        goto done;
+    }
+
+    if (fdops->fd_ioctls)
+        ECHECK(myst_ioctl_check(fdops->fd_ioctls, request, arg));
+
     ret = (*fdops->fd_ioctl)(device, object, request, arg);
 
 done:
@@ -1871,8 +3004,18 @@ long myst_syscall_sendmsg(int sockfd, const struct msghdr* msg, int flags)
     myst_sockdev_t* sd;
     myst_sock_t* sock;
 
+    myst_scm_rights_t* rights;
+    struct msghdr copy;
+
     ECHECK(myst_fdtable_get_sock(fdtable, sockfd, &sd, &sock));
-    ret = (*sd->sd_sendmsg)(sd, sock, msg, flags);
+
+    if (!msg)
+        ERAISE(-EFAULT);
+
+    /* pass the descriptors of SCM_RIGHTS messages (see kernel/scmrights.c) */
+    ECHECK(myst_scm_rights_send(fdtable, msg, &copy, &rights));
+    ret = (*sd->sd_sendmsg)(sd, sock, &copy, flags);
+    myst_scm_rights_sent(rights, ret >= 0);
 
 done:
     return ret;
@@ -1885,8 +3028,16 @@ long myst_syscall_recvmsg(int sockfd, struct msghdr* msg, int flags)
     myst_sockdev_t* sd;
     myst_sock_t* sock;
 
+    socklen_t size;
+
     ECHECK(myst_fdtable_get_sock(fdtable, sockfd, &sd, &sock));
-    ret = (*sd->sd_recvmsg)(sd, sock, msg, flags);
+
+    if (!msg)
+        ERAISE(-EFAULT);
+
+    size = msg->msg_controllen;
+    ECHECK(ret = (*sd->sd_recvmsg)(sd, sock, msg, flags));
+    myst_scm_rights_recv(fdtable, msg, size, flags);
 
 done:
     return ret;
@@ -2068,13 +3219,19 @@ done:
 long myst_syscall_sched_yield(void)
 {
     long params[] = {0};
-    return myst_tcall(SYS_sched_yield, params);
+    long begin = myst_rusage_block_begin();
+    long ret = myst_tcall(SYS_sched_yield, params);
+    myst_rusage_block_end(begin);
+    return ret;
 }
 
 long myst_syscall_nanosleep(const struct timespec* req, struct timespec* rem)
 {
     long params[6] = {(long)req, (long)rem};
-    return _forward_syscall(SYS_nanosleep, params);
+    long begin = myst_rusage_block_begin();
+    long ret = _forward_syscall(SYS_nanosleep, params);
+    myst_rusage_block_end(begin);
+    return ret;
 }
 #define NANO_IN_SECOND 1000000000
 
@@ -2144,18 +3301,17 @@ done:
 
 long myst_syscall_getrusage(int who, struct rusage* usage)
 {
-    // ATTN: support child process and per-thread usage reporting.
-    if (who == RUSAGE_CHILDREN || who == RUSAGE_THREAD)
-        return -EINVAL;
+    long ret = 0;
+    myst_rusage_t rusage;
 
-    long stime = myst_times_system_time();
-    long utime = myst_times_user_time();
-    usage->ru_utime.tv_sec = utime / 1000000000;
-    usage->ru_utime.tv_usec = utime % 1000000000 * 1000;
-    usage->ru_stime.tv_sec = stime / 1000000000;
-    usage->ru_stime.tv_usec = stime % 1000000000 * 1000;
+    if (!usage)
+        ERAISE(-EFAULT);
 
-    return 0;
+    ECHECK(myst_rusage_get(myst_thread_self(), who, &rusage));
+    myst_rusage_to_rusage(&rusage, usage);
+
+done:
+    return ret;
 }
 
 long myst_syscall_prlimit64(
@@ -2171,6 +3327,9 @@ long myst_syscall_prlimit64(
     if (pid)
         return -EINVAL;
 
+    if (resource == RLIMIT_CPU)
+        return myst_budget_prlimit(new_rlim, old_rlim);
+
     // Only support resource NOFILE
     if (resource != RLIMIT_NOFILE)
         return -EINVAL;
@@ -2203,8 +3362,15 @@ long myst_syscall_fsync(int fd)
 
     ECHECK(myst_fdtable_get_any(fdtable, fd, &type, &device, &object));
 
-    if (type != MYST_FDTABLE_TYPE_FILE)
+    if (type == MYST_FDTABLE_TYPE_DISK)
+    {
+        myst_diskdev_t* diskdev = device;
+        ECHECK((*diskdev->dd_fsync)(diskdev, object));
+    }
+    else if (type != MYST_FDTABLE_TYPE_FILE)
+    {
         ERAISE(-EROFS);
+    }
 
 done:
     return ret;
@@ -2434,7 +3600,9 @@ void myst_dump_ramfs(void)
     myst_strarr_release(&paths);
 }
 
-int myst_export_ramfs(void)
+static int _export_files(
+    bool (*filter)(const char* path),
+    long (*export)(const char* path, const void* data, size_t size))
 {
     int ret = -1;
     myst_strarr_t paths = MYST_STRARR_INITIALIZER;
@@ -2452,13 +3620,16 @@ int myst_export_ramfs(void)
         if (strncmp(path, "/proc", 5) == 0)
             continue;
 
+        if (filter && !(*filter)(path))
+            continue;
+
         if (myst_load_file(path, &data, &size) != 0)
         {
             myst_eprintf("Warning! failed to load %s from ramfs\n", path);
             continue;
         }
 
-        if (myst_tcall_export_file(path, data, size) != 0)
+        if ((*export)(path, data, size) != 0)
         {
             myst_eprintf("Warning! failed to export %s from ramfs\n", path);
             continue;
@@ -2480,6 +3651,52 @@ done:
     return ret;
 }
 
+int myst_export_ramfs(void)
+{
+    return _export_files(NULL, myst_tcall_export_file);
+}
+
+/* gcov (.gcda) and LLVM source-based coverage (.profraw) data files */
+static bool _is_coverage_file(const char* path)
+{
+    static const char* _suffixes[] = {".gcda", ".profraw"};
+    const size_t len = strlen(path);
+
+    for (size_t i = 0; i < MYST_COUNTOF(_suffixes); i++)
+    {
+        const size_t n = strlen(_suffixes[i]);
+
+        if (len > n && strcmp(path + len - n, _suffixes[i]) == 0)
+            return true;
+    }
+
+    return false;
+}
+
+int myst_export_coverage(void)
+{
+    return _export_files(_is_coverage_file, myst_tcall_export_coverage);
+}
+
+/* clock_adjtime() and adjtimex() on a copy of the user's timex */
+static long _clock_adjtime(clockid_t clk_id, struct timex* buf)
+{
+    struct timex tx;
+    long ret;
+
+    if ((ret = MYST_COPY_FROM_USER(&tx, buf)) != 0)
+        return ret;
+
+    /* the result is the clock state, which is not negative */
+    if ((ret = myst_syscall_clock_adjtime(clk_id, &tx)) >= 0 &&
+        MYST_COPY_TO_USER(buf, &tx) != 0)
+    {
+        ret = -EFAULT;
+    }
+
+    return ret;
+}
+
 #define BREAK(RET)           \
     do                       \
     {                        \
@@ -2500,6 +3717,8 @@ long myst_syscall(long n, long params[6])
     myst_td_t* target_td = NULL;
     myst_td_t* crt_td = NULL;
     myst_thread_t* thread = NULL;
+    myst_syscall_policy_args_t* policy_args = NULL;
+    bool policy_locked = false;
 
     myst_times_enter_kernel();
 
@@ -2538,14 +3757,58 @@ long myst_syscall(long n, long params[6])
         /* crt_td is null */
     }
 
+    /* raise SIGSEGV if this thread has overrun its stack (where the guard is
+     * a pattern to check, or the thread has just faulted in the guard) */
+    if (thread->stack_guard.length || thread->stack_guard.fault)
+        myst_stack_guard_check(thread);
+
     // Process signals pending for this thread, if there is any.
     myst_signal_process(thread);
 
+    /* signal a process that has used up its CPU or wall-clock budget */
+    myst_budget_check(thread);
+
+    /* run the background work of the kernel that is due */
+    myst_work_run();
+
     /* ---------- running target thread descriptor ---------- */
 
     myst_assume(target_td != NULL);
     myst_assume(thread != NULL);
 
+    /* enforce the "SyscallPolicy" from config.json */
+    if (myst_syscall_policy_enabled())
+    {
+        long ret;
+
+        /* the path rules are checked against a copy of the path, and no path
+         * may change until the file is open (see myst_resolve_lock) */
+        if (myst_syscall_policy_checks_path(n))
+        {
+            if (!(policy_args = malloc(sizeof(myst_syscall_policy_args_t))))
+                BREAK(_return(n, -ENOMEM));
+
+            ret = myst_syscall_policy_copy_args(n, params, policy_args);
+
+            if (ret != 0)
+                BREAK(_return(n, ret));
+
+            x1 = params[0];
+            x2 = params[1];
+            x3 = params[2];
+            x4 = params[3];
+
+            myst_resolve_lock();
+            policy_locked = true;
+        }
+
+        if ((ret = myst_syscall_policy_check(n, params)) != 0)
+        {
+            _strace(n, "denied by syscall policy");
+            BREAK(_return(n, ret));
+        }
+    }
+
     switch (n)
     {
 #ifdef MYST_ENABLE_GCOV
@@ -2644,13 +3907,43 @@ long myst_syscall(long n, long params[6])
         }
         case SYS_myst_gen_creds:
         {
+            uint8_t** cert = (uint8_t**)x1;
+            size_t* cert_size = (size_t*)x2;
+            uint8_t** pkey = (uint8_t**)x3;
+            size_t* pkey_size = (size_t*)x4;
+            uint8_t* c = NULL;
+            size_t cn = 0;
+            uint8_t* k = NULL;
+            size_t kn = 0;
+            long ret;
+
             _strace(n, NULL);
-            BREAK(_forward_syscall(MYST_TCALL_GEN_CREDS, params));
+            ret = myst_quote_cache_gen_creds(&c, &cn, &k, &kn);
+
+            if (ret == 0 && (MYST_COPY_TO_USER(cert, &c) != 0 ||
+                             MYST_COPY_TO_USER(cert_size, &cn) != 0 ||
+                             MYST_COPY_TO_USER(pkey, &k) != 0 ||
+                             MYST_COPY_TO_USER(pkey_size, &kn) != 0))
+            {
+                myst_quote_cache_free_creds(c, cn, k, kn);
+                ret = -EFAULT;
+            }
+
+            myst_audit_attestation("gen_creds", ret);
+            BREAK(_return(n, ret));
         }
         case SYS_myst_free_creds:
         {
+            uint8_t* cert = (uint8_t*)x1;
+            size_t cert_size = (size_t)x2;
+            uint8_t* pkey = (uint8_t*)x3;
+            size_t pkey_size = (size_t)x4;
+
             _strace(n, NULL);
-            BREAK(_forward_syscall(MYST_TCALL_FREE_CREDS, params));
+            BREAK(_return(
+                n,
+                myst_quote_cache_free_creds(
+                    cert, cert_size, pkey, pkey_size)));
         }
         case SYS_myst_verify_cert:
         {
@@ -2726,7 +4019,7 @@ long myst_syscall(long n, long params[6])
 
             _strace(n, "path=\"%s\" flags=0%o mode=0%o", path, flags, mode);
 
-            ret = myst_syscall_open(path, flags, mode);
+            ret = myst_syscall_open(path, flags, myst_perms_umask(mode));
 
             BREAK(_return(n, ret));
         }
@@ -2819,8 +4112,33 @@ long myst_syscall(long n, long params[6])
                 fd,
                 offset);
 
+            if ((ret = myst_wx_check(n, addr, length, prot)) != 0)
+                BREAK(_return(n, ret));
+
+            /* the io_uring rings are already in enclave memory */
+            if (!(flags & MAP_ANONYMOUS))
+            {
+                myst_fdtable_t* fdtable = myst_fdtable_current();
+                myst_iouringdev_t* dev;
+                myst_iouring_t* obj;
+
+                if (myst_fdtable_get_iouring(fdtable, fd, &dev, &obj) == 0)
+                {
+                    ret = (*dev->iu_mmap)(dev, obj, offset, length, &ptr);
+                    BREAK(_return(n, ret == 0 ? (long)ptr : ret));
+                }
+            }
+
+            /* a fixed mapping may replace (and clear) a guard */
+            if (flags & MAP_FIXED)
+                myst_stack_guard_munmap(addr, length);
+
             ptr = myst_mmap(addr, length, prot, flags, fd, offset);
 
+            /* retry once if the pressure callbacks released memory */
+            if ((long)ptr == -ENOMEM && myst_oom_handle(length) == 0)
+                ptr = myst_mmap(addr, length, prot, flags, fd, offset);
+
             if (ptr == MAP_FAILED || !ptr)
             {
                 ret = -ENOMEM;
@@ -2832,6 +4150,8 @@ long myst_syscall(long n, long params[6])
                 if (myst_register_process_mapping(pid, ptr, length) != 0)
                     myst_panic("failed to register process mapping");
 
+                myst_rusage_add_rss((long)length);
+                myst_stack_guard_mmap(ptr, length, prot, flags);
                 ret = (long)ptr;
             }
 
@@ -2842,6 +4162,7 @@ long myst_syscall(long n, long params[6])
             const void* addr = (void*)x1;
             const size_t length = (size_t)x2;
             const int prot = (int)x3;
+            long ret;
 
             _strace(
                 n,
@@ -2851,6 +4172,12 @@ long myst_syscall(long n, long params[6])
                 length,
                 prot);
 
+            if ((ret = myst_wx_check(n, addr, length, prot)) != 0)
+                BREAK(_return(n, ret));
+
+            /* only the stack guards are protected (see kernel/stackguard.c) */
+            myst_stack_guard_mprotect((void*)addr, length, prot);
+
             BREAK(_return(n, 0));
         }
         case SYS_munmap:
@@ -2886,7 +4213,19 @@ long myst_syscall(long n, long params[6])
                 }
             }
 
-            BREAK(_return(n, (long)myst_munmap(addr, length)));
+            /* the io_uring rings are not mapped memory (see SYS_mmap) */
+            if (myst_iouring_munmap(addr, length))
+                BREAK(_return(n, 0));
+
+            /* a guard must be accessible before the pages are released */
+            myst_stack_guard_munmap(addr, length);
+
+            long ret = (long)myst_munmap(addr, length);
+
+            if (ret == 0)
+                myst_rusage_add_rss(-(long)length);
+
+            BREAK(_return(n, ret));
         }
         case SYS_brk:
         {
@@ -2927,8 +4266,8 @@ long myst_syscall(long n, long params[6])
             long arg = (long)x3;
             int iarg = -1;
 
-            if (request == FIONBIO && arg)
-                iarg = *(int*)arg;
+            if ((request == FIONBIO || request == FIOASYNC) && arg)
+                MYST_COPY_FROM_USER(&iarg, (const int*)arg);
 
             _strace(
                 n,
@@ -3029,9 +4368,22 @@ long myst_syscall(long n, long params[6])
                 flags,
                 new_address);
 
+            /* the pages may be copied or released */
+            myst_stack_guard_munmap(old_address, old_size);
+
             ret = (long)myst_mremap(
                 old_address, old_size, new_size, flags, new_address);
 
+            /* retry once if the pressure callbacks released memory */
+            if (ret == -ENOMEM && myst_oom_handle(new_size - old_size) == 0)
+            {
+                ret = (long)myst_mremap(
+                    old_address, old_size, new_size, flags, new_address);
+            }
+
+            if (ret >= 0)
+                myst_rusage_add_rss((long)new_size - (long)old_size);
+
             BREAK(_return(n, ret));
         }
         case SYS_msync:
@@ -3103,15 +4455,33 @@ long myst_syscall(long n, long params[6])
             const struct timespec* req = (const struct timespec*)x1;
             struct timespec* rem = (struct timespec*)x2;
 
+            struct timespec kreq;
+            struct timespec krem;
+            long ret;
+
             _strace(n, "req=%p rem=%p", req, rem);
 
-            BREAK(_return(n, myst_syscall_nanosleep(req, rem)));
+            if ((ret = MYST_COPY_FROM_USER(&kreq, req)) != 0)
+                BREAK(_return(n, ret));
+
+            /* rem may be null and is only set when the sleep is cut short */
+            ret = myst_syscall_nanosleep(&kreq, rem ? &krem : NULL);
+
+            if (ret == -EINTR && rem && MYST_COPY_TO_USER(rem, &krem) != 0)
+                ret = -EFAULT;
+
+            BREAK(_return(n, ret));
         }
         case SYS_myst_run_itimer:
         {
             _strace(n, NULL);
             BREAK(_return(n, myst_syscall_run_itimer()));
         }
+        case SYS_myst_run_work:
+        {
+            _strace(n, NULL);
+            BREAK(_return(n, myst_work_run_worker()));
+        }
         case SYS_getitimer:
         {
             int which = (int)x1;
@@ -3219,6 +4589,9 @@ long myst_syscall(long n, long params[6])
 
                 if (__options.export_ramfs)
                     myst_export_ramfs();
+
+                if (__options.export_coverage)
+                    myst_export_coverage();
             }
 
             myst_longjmp(&thread->jmpbuf, 1);
@@ -3299,7 +4672,13 @@ long myst_syscall(long n, long params[6])
             BREAK(_return(n, myst_syscall_fsync(fd)));
         }
         case SYS_fdatasync:
-            break;
+        {
+            int fd = (int)x1;
+
+            _strace(n, "fd=%d", fd);
+
+            BREAK(_return(n, myst_syscall_fsync(fd)));
+        }
         case SYS_truncate:
         {
             const char* path = (const char*)x1;
@@ -3338,7 +4717,13 @@ long myst_syscall(long n, long params[6])
             BREAK(_return(n, myst_syscall_chdir(path)));
         }
         case SYS_fchdir:
-            break;
+        {
+            int fd = (int)x1;
+
+            _strace(n, "fd=%d", fd);
+
+            BREAK(_return(n, myst_syscall_fchdir(fd)));
+        }
         case SYS_rename:
         {
             const char* oldpath = (const char*)x1;
@@ -3355,6 +4740,7 @@ long myst_syscall(long n, long params[6])
 
             _strace(n, "pathname=\"%s\" mode=0%o", pathname, mode);
 
+            mode = myst_perms_umask(mode);
             BREAK(_return(n, myst_syscall_mkdir(pathname, mode)));
         }
         case SYS_rmdir:
@@ -3372,6 +4758,7 @@ long myst_syscall(long n, long params[6])
 
             _strace(n, "pathname=\"%s\" mode=%x", pathname, mode);
 
+            mode = myst_perms_umask(mode);
             BREAK(_return(n, myst_syscall_creat(pathname, mode)));
         }
         case SYS_link:
@@ -3437,13 +4824,28 @@ long myst_syscall(long n, long params[6])
 
             _strace(n, "pathname=%s owner=%u group=%u", pathname, owner, group);
 
-            /* owner is a no-op for now since kernel executes as root */
-            BREAK(_return(n, 0));
+            BREAK(_return(n, myst_syscall_chown(pathname, owner, group)));
         }
         case SYS_fchown:
-            break;
+        {
+            int fd = (int)x1;
+            uid_t owner = (uid_t)x2;
+            gid_t group = (gid_t)x3;
+
+            _strace(n, "fd=%d owner=%u group=%u", fd, owner, group);
+
+            BREAK(_return(n, myst_syscall_fchown(fd, owner, group)));
+        }
         case SYS_lchown:
-            break;
+        {
+            const char* pathname = (const char*)x1;
+            uid_t owner = (uid_t)x2;
+            gid_t group = (gid_t)x3;
+
+            _strace(n, "pathname=%s owner=%u group=%u", pathname, owner, group);
+
+            BREAK(_return(n, myst_syscall_lchown(pathname, owner, group)));
+        }
         case SYS_umask:
         {
             mode_t mask = (mode_t)x1;
@@ -3457,9 +4859,16 @@ long myst_syscall(long n, long params[6])
             struct timeval* tv = (struct timeval*)x1;
             struct timezone* tz = (void*)x2;
 
+            struct timeval ktv;
+
             _strace(n, "tv=%p tz=%p", tv, tz);
 
-            long ret = myst_syscall_gettimeofday(tv, tz);
+            /* tv may be null */
+            long ret = myst_syscall_gettimeofday(tv ? &ktv : NULL, tz);
+
+            if (ret == 0 && tv)
+                ret = MYST_COPY_TO_USER(tv, &ktv);
+
             BREAK(_return(n, ret));
         }
         case SYS_getrlimit:
@@ -3621,8 +5030,12 @@ long myst_syscall(long n, long params[6])
             break;
         case SYS_sigaltstack:
         {
-            /* ATTN: support user space stack for segv handling. */
-            BREAK(_return(n, 0));
+            const stack_t* ss = (const stack_t*)x1;
+            stack_t* old_ss = (stack_t*)x2;
+
+            _strace(n, "ss=%p old_ss=%p", ss, old_ss);
+
+            BREAK(_return(n, myst_signal_sigaltstack(ss, old_ss)));
         }
         case SYS_utime:
             break;
@@ -3649,7 +5062,13 @@ long myst_syscall(long n, long params[6])
         case SYS_uselib:
             break;
         case SYS_personality:
-            break;
+        {
+            unsigned long persona = (unsigned long)x1;
+
+            _strace(n, "persona=0x%lx", persona);
+
+            BREAK(_return(n, myst_syscall_personality(persona)));
+        }
         case SYS_ustat:
             break;
         case SYS_statfs:
@@ -3677,13 +5096,32 @@ long myst_syscall(long n, long params[6])
         case SYS_sysfs:
             break;
         case SYS_getpriority:
-            break;
+        {
+            int which = (int)x1;
+            id_t who = (id_t)x2;
+
+            _strace(n, "which=%d who=%u", which, who);
+
+            BREAK(_return(n, myst_syscall_getpriority(which, who)));
+        }
         case SYS_setpriority:
-            break;
+        {
+            int which = (int)x1;
+            id_t who = (id_t)x2;
+            int prio = (int)x3;
+
+            _strace(n, "which=%d who=%u prio=%d", which, who, prio);
+
+            BREAK(_return(n, myst_syscall_setpriority(which, who, prio)));
+        }
         case SYS_sched_setparam:
         {
-            /* ATTN: support setting thread priorities. */
-            BREAK(_return(n, 0));
+            pid_t pid = (pid_t)x1;
+            const struct sched_param* param = (const struct sched_param*)x2;
+
+            _strace(n, "pid=%d param=%p", pid, param);
+
+            BREAK(_return(n, myst_syscall_sched_setparam(pid, param)));
         }
         case SYS_sched_getparam:
         {
@@ -3692,33 +5130,43 @@ long myst_syscall(long n, long params[6])
 
             _strace(n, "pid=%d param=%p", pid, param);
 
-            // ATTN: Return the priority from SYS_sched_setparam.
-            if (param != NULL)
-                memset(param, 0, sizeof(*param));
-
-            BREAK(_return(n, 0));
+            BREAK(_return(n, myst_syscall_sched_getparam(pid, param)));
         }
         case SYS_sched_setscheduler:
         {
-            // ATTN: support different schedules, FIFO, RR, BATCH, etc.
-            // The more control we have on threads inside the kernel, the more
-            // schedulers we could support.
-            BREAK(_return(n, 0));
+            pid_t pid = (pid_t)x1;
+            int policy = (int)x2;
+            const struct sched_param* param = (const struct sched_param*)x3;
+            long ret;
+
+            _strace(n, "pid=%d policy=%d param=%p", pid, policy, param);
+
+            ret = myst_syscall_sched_setscheduler(pid, policy, param);
+            BREAK(_return(n, ret));
         }
         case SYS_sched_getscheduler:
         {
-            /* ATTN: return the scheduler installed from sched_setscheduler. */
-            BREAK(_return(n, SCHED_OTHER));
+            pid_t pid = (pid_t)x1;
+
+            _strace(n, "pid=%d", pid);
+
+            BREAK(_return(n, myst_syscall_sched_getscheduler(pid)));
         }
         case SYS_sched_get_priority_max:
         {
-            /* ATTN: support thread priorities */
-            BREAK(_return(n, 0));
+            int policy = (int)x1;
+
+            _strace(n, "policy=%d", policy);
+
+            BREAK(_return(n, myst_syscall_sched_get_priority_max(policy)));
         }
         case SYS_sched_get_priority_min:
         {
-            /* ATTN: support thread priorities */
-            BREAK(_return(n, 0));
+            int policy = (int)x1;
+
+            _strace(n, "policy=%d", policy);
+
+            BREAK(_return(n, myst_syscall_sched_get_priority_min(policy)));
         }
         case SYS_sched_rr_get_interval:
             break;
@@ -3755,43 +5203,66 @@ long myst_syscall(long n, long params[6])
         case SYS_prctl:
         {
             int option = (int)x1;
-            long ret = 0;
+            unsigned long arg2 = (unsigned long)x2;
 
-            _strace(n, "option=%d\n", option);
+            _strace(n, "option=%d arg2=0x%lx", option, arg2);
 
-            if (option == PR_GET_NAME)
-            {
-                char* arg2 = (char*)x2;
-                if (!arg2)
-                    BREAK(_return(n, -EINVAL));
+            BREAK(_return(n, myst_syscall_prctl(option, arg2)));
+        }
+        case SYS_arch_prctl:
+        {
+            int code = (int)x1;
+            unsigned long addr = (unsigned long)x2;
 
-                strcpy(arg2, myst_get_thread_name(myst_thread_self()));
-            }
-            else if (option == PR_SET_NAME)
+            _strace(n, "code=0x%x addr=0x%lx", code, addr);
+
+            if (code == ARCH_SET_FS)
             {
-                char* arg2 = (char*)x2;
-                if (!arg2)
+                myst_td_t* td = (myst_td_t*)addr;
+
+                /* the kernel finds the C-runtime thread descriptor through
+                 * the FS base, so it must be one (like SYS_set_thread_area) */
+                if (!myst_valid_td(td))
                     BREAK(_return(n, -EINVAL));
 
-                ret = myst_set_thread_name(myst_thread_self(), arg2);
-            }
-            else
-            {
-                ret = -EINVAL;
+                /* restored as the FS base when the syscall returns */
+                crt_td = td;
+                thread->crt_td = crt_td;
+                crt_td->canary = target_td->canary;
+                _set_thread_area_called = true;
+
+                BREAK(_return(n, 0));
             }
 
-            BREAK(_return(n, ret));
+            BREAK(_return(n, myst_syscall_arch_prctl(code, addr)));
         }
-        case SYS_arch_prctl:
-            break;
         case SYS_adjtimex:
-            break;
+        {
+            struct timex* buf = (struct timex*)x1;
+
+            _strace(n, "buf=%p", buf);
+
+            BREAK(_return(n, _clock_adjtime(CLOCK_REALTIME, buf)));
+        }
         case SYS_setrlimit:
             break;
         case SYS_chroot:
-            break;
+        {
+            const char* path = (const char*)x1;
+
+            _strace(n, "path=\"%s\"", path);
+
+            BREAK(_return(n, myst_syscall_chroot(path)));
+        }
         case SYS_sync:
-            break;
+        {
+            _strace(n, NULL);
+
+            /* sync() does not fail */
+            myst_disks_sync();
+
+            BREAK(_return(n, 0));
+        }
         case SYS_acct:
             break;
         case SYS_settimeofday:
@@ -3816,6 +5287,7 @@ long myst_syscall(long n, long params[6])
 
             ret = myst_syscall_mount(
                 source, target, filesystemtype, mountflags, data);
+            myst_audit_mount(source, target, filesystemtype, mountflags, ret);
 
             BREAK(_return(n, ret));
         }
@@ -3842,12 +5314,19 @@ long myst_syscall(long n, long params[6])
             const char* name = (const char*)x1;
             size_t len = (size_t)x2;
 
-            _strace(n, "name=\"%s\" len=%zu", name, len);
+            _strace(n, "name=\"%.*s\" len=%zu", (int)len, name, len);
 
             BREAK(_return(n, myst_syscall_sethostname(name, len)));
         }
         case SYS_setdomainname:
-            break;
+        {
+            const char* name = (const char*)x1;
+            size_t len = (size_t)x2;
+
+            _strace(n, "name=\"%.*s\" len=%zu", (int)len, name, len);
+
+            BREAK(_return(n, myst_syscall_setdomainname(name, len)));
+        }
         case SYS_iopl:
             break;
         case SYS_ioperm:
@@ -3914,18 +5393,22 @@ long myst_syscall(long n, long params[6])
 
             _strace(n, "tid=%d sig=%d", tid, sig);
 
-            myst_thread_t* thread = myst_thread_self();
-            int tgid = thread->pid;
-
-            long ret = myst_syscall_tgkill(tgid, tid, sig);
+            long ret = myst_syscall_tkill(tid, sig);
             BREAK(_return(n, ret));
         }
         case SYS_time:
         {
             time_t* tloc = (time_t*)x1;
 
+            time_t t;
+
             _strace(n, "tloc=%p", tloc);
-            long ret = myst_syscall_time(tloc);
+            long ret = myst_syscall_time(&t);
+
+            /* tloc may be null */
+            if (ret >= 0 && tloc && MYST_COPY_TO_USER(tloc, &t) != 0)
+                ret = -EFAULT;
+
             BREAK(_return(n, ret));
         }
         case SYS_futex:
@@ -3953,34 +5436,26 @@ long myst_syscall(long n, long params[6])
         case SYS_sched_setaffinity:
         {
             pid_t pid = (pid_t)x1;
-            size_t cpusetsize = (pid_t)x2;
-            cpu_set_t* mask = (cpu_set_t*)x3;
-
-            _strace(
-                n, "pid=%d cpusetsize=%zu mask=%p\n", pid, cpusetsize, mask);
+            size_t cpusetsize = (size_t)x2;
+            const cpu_set_t* mask = (const cpu_set_t*)x3;
+            long ret;
 
-            /* ATTN: support set affinity requests */
+            _strace(n, "pid=%d cpusetsize=%zu mask=%p", pid, cpusetsize, mask);
 
-            BREAK(_return(n, 0));
+            ret = myst_syscall_sched_setaffinity(pid, cpusetsize, mask);
+            BREAK(_return(n, ret));
         }
         case SYS_sched_getaffinity:
         {
             pid_t pid = (pid_t)x1;
-            size_t cpusetsize = (pid_t)x2;
+            size_t cpusetsize = (size_t)x2;
             cpu_set_t* mask = (cpu_set_t*)x3;
+            long ret;
 
-            _strace(
-                n, "pid=%d cpusetsize=%zu mask=%p\n", pid, cpusetsize, mask);
-
-            // ATTN: return the cpu id from sched_setaffinity.
-            // for now, make all threads fixed to cpu 0.
-            if (mask != NULL)
-            {
-                CPU_ZERO(mask);
-                CPU_SET(0, mask);
-            }
+            _strace(n, "pid=%d cpusetsize=%zu mask=%p", pid, cpusetsize, mask);
 
-            BREAK(_return(n, cpusetsize));
+            ret = myst_syscall_sched_getaffinity(pid, cpusetsize, mask);
+            BREAK(_return(n, ret));
         }
         case SYS_set_thread_area:
         {
@@ -4083,41 +5558,109 @@ long myst_syscall(long n, long params[6])
             BREAK(_return(n, 0));
         }
         case SYS_timer_create:
-            break;
+        {
+            clockid_t clockid = (clockid_t)x1;
+            const struct sigevent* sevp = (const struct sigevent*)x2;
+            int* timerid = (int*)x3;
+
+            _strace(
+                n, "clockid=%d sevp=%p timerid=%p", clockid, sevp, timerid);
+
+            BREAK(_return(
+                n, myst_syscall_timer_create(clockid, sevp, timerid)));
+        }
         case SYS_timer_settime:
-            break;
+        {
+            int timerid = (int)x1;
+            int flags = (int)x2;
+            const struct itimerspec* new_value = (void*)x3;
+            struct itimerspec* old_value = (void*)x4;
+
+            _strace(
+                n,
+                "timerid=%d flags=%d new_value=%p old_value=%p",
+                timerid,
+                flags,
+                new_value,
+                old_value);
+
+            BREAK(_return(
+                n,
+                myst_syscall_timer_settime(
+                    timerid, flags, new_value, old_value)));
+        }
         case SYS_timer_gettime:
-            break;
+        {
+            int timerid = (int)x1;
+            struct itimerspec* curr_value = (void*)x2;
+
+            _strace(n, "timerid=%d curr_value=%p", timerid, curr_value);
+
+            BREAK(_return(n, myst_syscall_timer_gettime(timerid, curr_value)));
+        }
         case SYS_timer_getoverrun:
-            break;
+        {
+            int timerid = (int)x1;
+
+            _strace(n, "timerid=%d", timerid);
+
+            BREAK(_return(n, myst_syscall_timer_getoverrun(timerid)));
+        }
         case SYS_timer_delete:
-            break;
+        {
+            int timerid = (int)x1;
+
+            _strace(n, "timerid=%d", timerid);
+
+            BREAK(_return(n, myst_syscall_timer_delete(timerid)));
+        }
         case SYS_clock_settime:
         {
             clockid_t clk_id = (clockid_t)x1;
             struct timespec* tp = (struct timespec*)x2;
 
+            struct timespec ts;
+            long ret;
+
             _strace(n, "clk_id=%u tp=%p", clk_id, tp);
 
-            BREAK(_return(n, myst_syscall_clock_settime(clk_id, tp)));
+            if ((ret = MYST_COPY_FROM_USER(&ts, tp)) == 0)
+                ret = myst_syscall_clock_settime(clk_id, &ts);
+
+            BREAK(_return(n, ret));
         }
         case SYS_clock_gettime:
         {
             clockid_t clk_id = (clockid_t)x1;
             struct timespec* tp = (struct timespec*)x2;
 
+            struct timespec ts;
+            long ret;
+
             _strace(n, "clk_id=%u tp=%p", clk_id, tp);
 
-            BREAK(_return(n, myst_syscall_clock_gettime(clk_id, tp)));
+            if ((ret = myst_syscall_clock_gettime(clk_id, &ts)) == 0)
+                ret = MYST_COPY_TO_USER(tp, &ts);
+
+            BREAK(_return(n, ret));
         }
         case SYS_clock_getres:
         {
             clockid_t clk_id = (clockid_t)x1;
             struct timespec* res = (struct timespec*)x2;
 
+            struct timespec ts;
+            long ret;
+
             _strace(n, "clk_id=%u tp=%p", clk_id, res);
 
-            BREAK(_return(n, myst_syscall_clock_getres(clk_id, res)));
+            /* res may be null */
+            ret = myst_syscall_clock_getres(clk_id, res ? &ts : NULL);
+
+            if (ret == 0 && res)
+                ret = MYST_COPY_TO_USER(res, &ts);
+
+            BREAK(_return(n, ret));
         }
         case SYS_clock_nanosleep:
             break;
@@ -4256,6 +5799,7 @@ long myst_syscall(long n, long params[6])
                 flags,
                 mode);
 
+            mode = myst_perms_umask(mode);
             ret = myst_syscall_openat(dirfd, path, flags, mode);
 
             BREAK(_return(n, ret));
@@ -4265,7 +5809,26 @@ long myst_syscall(long n, long params[6])
         case SYS_mknodat:
             break;
         case SYS_fchownat:
-            break;
+        {
+            int dirfd = (int)x1;
+            const char* pathname = (const char*)x2;
+            uid_t owner = (uid_t)x3;
+            gid_t group = (gid_t)x4;
+            int flags = (int)x5;
+            long ret;
+
+            _strace(
+                n,
+                "dirfd=%d pathname=\"%s\" owner=%u group=%u flags=%d",
+                dirfd,
+                pathname,
+                owner,
+                group,
+                flags);
+
+            ret = myst_syscall_fchownat(dirfd, pathname, owner, group, flags);
+            BREAK(_return(n, ret));
+        }
         case SYS_futimesat:
         {
             int dirfd = (int)x1;
@@ -4309,9 +5872,32 @@ long myst_syscall(long n, long params[6])
         case SYS_readlinkat:
             break;
         case SYS_fchmodat:
-            break;
+        {
+            int dirfd = (int)x1;
+            const char* pathname = (const char*)x2;
+            mode_t mode = (mode_t)x3;
+            long ret;
+
+            _strace(
+                n, "dirfd=%d pathname=\"%s\" mode=%o", dirfd, pathname, mode);
+
+            /* the system call has no flags (unlike the libc function) */
+            ret = myst_syscall_fchmodat(dirfd, pathname, mode, 0);
+            BREAK(_return(n, ret));
+        }
         case SYS_faccessat:
-            break;
+        {
+            int dirfd = (int)x1;
+            const char* pathname = (const char*)x2;
+            int mode = (int)x3;
+            long ret;
+
+            _strace(
+                n, "dirfd=%d pathname=\"%s\" mode=%d", dirfd, pathname, mode);
+
+            ret = myst_syscall_faccessat(dirfd, pathname, mode, 0);
+            BREAK(_return(n, ret));
+        }
         case SYS_pselect6:
             break;
         case SYS_ppoll:
@@ -4319,9 +5905,26 @@ long myst_syscall(long n, long params[6])
         case SYS_unshare:
             break;
         case SYS_set_robust_list:
-            break;
+        {
+            struct robust_list_head* head = (struct robust_list_head*)x1;
+            size_t len = (size_t)x2;
+
+            _strace(n, "head=%p len=%zu", head, len);
+
+            BREAK(_return(n, myst_syscall_set_robust_list(head, len)));
+        }
         case SYS_get_robust_list:
-            break;
+        {
+            int pid = (int)x1;
+            struct robust_list_head** head_ptr = (struct robust_list_head**)x2;
+            size_t* len_ptr = (size_t*)x3;
+            long ret;
+
+            _strace(n, "pid=%d head_ptr=%p len_ptr=%p", pid, head_ptr, len_ptr);
+
+            ret = myst_syscall_get_robust_list(pid, head_ptr, len_ptr);
+            BREAK(_return(n, ret));
+        }
         case SYS_splice:
             break;
         case SYS_tee:
@@ -4376,7 +5979,14 @@ long myst_syscall(long n, long params[6])
         case SYS_signalfd:
             break;
         case SYS_timerfd_create:
-            break;
+        {
+            int clockid = (int)x1;
+            int flags = (int)x2;
+
+            _strace(n, "clockid=%d flags=%d", clockid, flags);
+
+            BREAK(_return(n, myst_syscall_timerfd_create(clockid, flags)));
+        }
         case SYS_eventfd:
             break;
         case SYS_fallocate:
@@ -4393,9 +6003,33 @@ long myst_syscall(long n, long params[6])
             BREAK(_return(n, 0));
         }
         case SYS_timerfd_settime:
-            break;
+        {
+            int fd = (int)x1;
+            int flags = (int)x2;
+            const struct itimerspec* new_value = (void*)x3;
+            struct itimerspec* old_value = (void*)x4;
+
+            _strace(
+                n,
+                "fd=%d flags=%d new_value=%p old_value=%p",
+                fd,
+                flags,
+                new_value,
+                old_value);
+
+            BREAK(_return(
+                n,
+                myst_syscall_timerfd_settime(fd, flags, new_value, old_value)));
+        }
         case SYS_timerfd_gettime:
-            break;
+        {
+            int fd = (int)x1;
+            struct itimerspec* curr_value = (void*)x2;
+
+            _strace(n, "fd=%d curr_value=%p", fd, curr_value);
+
+            BREAK(_return(n, myst_syscall_timerfd_gettime(fd, curr_value)));
+        }
         case SYS_accept4:
         {
             int sockfd = (int)x1;
@@ -4486,7 +6120,14 @@ long myst_syscall(long n, long params[6])
         case SYS_open_by_handle_at:
             break;
         case SYS_clock_adjtime:
-            break;
+        {
+            clockid_t clk_id = (clockid_t)x1;
+            struct timex* buf = (struct timex*)x2;
+
+            _strace(n, "clk_id=%u buf=%p", clk_id, buf);
+
+            BREAK(_return(n, _clock_adjtime(clk_id, buf)));
+        }
         case SYS_syncfs:
             break;
         case SYS_sendmmsg:
@@ -4501,15 +6142,8 @@ long myst_syscall(long n, long params[6])
 
             _strace(n, "cpu=%p node=%p, tcache=%p", cpu, node, tcache);
 
-            // ATTN: report the real NUMA node id and cpu id.
-            // For now, always report id 0 for them.
-            if (cpu)
-                *cpu = 0;
-
-            if (node)
-                *node = 0;
-
-            BREAK(_return(n, 0));
+            /* tcache is unused, as on Linux since 2.6.24 */
+            BREAK(_return(n, myst_syscall_getcpu(cpu, node)));
         }
         case SYS_process_vm_readv:
             break;
@@ -4573,11 +6207,122 @@ long myst_syscall(long n, long params[6])
         case SYS_pkey_free:
             break;
         case SYS_statx:
-            break;
+        {
+            int dirfd = (int)x1;
+            const char* pathname = (const char*)x2;
+            int flags = (int)x3;
+            unsigned int mask = (unsigned int)x4;
+            struct myst_statx* statxbuf = (struct myst_statx*)x5;
+            long ret;
+
+            _strace(
+                n,
+                "dirfd=%d pathname=%s flags=0x%x mask=0x%x statxbuf=%p",
+                dirfd,
+                pathname,
+                flags,
+                mask,
+                statxbuf);
+
+            ret = myst_syscall_statx(dirfd, pathname, flags, mask, statxbuf);
+            BREAK(_return(n, ret));
+        }
         case SYS_io_pgetevents:
             break;
         case SYS_rseq:
             break;
+        case SYS_io_uring_setup:
+        {
+            uint32_t entries = (uint32_t)x1;
+            struct myst_io_uring_params* params = (void*)x2;
+            long ret;
+
+            _strace(n, "entries=%u params=%p", entries, params);
+
+            ret = myst_syscall_io_uring_setup(entries, params);
+            BREAK(_return(n, ret));
+        }
+        case SYS_io_uring_enter:
+        {
+            int fd = (int)x1;
+            uint32_t to_submit = (uint32_t)x2;
+            uint32_t min_complete = (uint32_t)x3;
+            uint32_t flags = (uint32_t)x4;
+            const sigset_t* sig = (const sigset_t*)x5;
+            size_t sigsz = (size_t)x6;
+            long ret;
+
+            _strace(
+                n,
+                "fd=%d to_submit=%u min_complete=%u flags=%x sig=%p sigsz=%zu",
+                fd,
+                to_submit,
+                min_complete,
+                flags,
+                sig,
+                sigsz);
+
+            ret = myst_syscall_io_uring_enter(
+                fd, to_submit, min_complete, flags, sig, sigsz);
+            BREAK(_return(n, ret));
+        }
+        case SYS_io_uring_register:
+        {
+            int fd = (int)x1;
+            uint32_t opcode = (uint32_t)x2;
+            void* arg = (void*)x3;
+            uint32_t nr_args = (uint32_t)x4;
+            long ret;
+
+            _strace(
+                n,
+                "fd=%d opcode=%u arg=%p nr_args=%u",
+                fd,
+                opcode,
+                arg,
+                nr_args);
+
+            ret = myst_syscall_io_uring_register(fd, opcode, arg, nr_args);
+            BREAK(_return(n, ret));
+        }
+        case SYS_openat2:
+        {
+            int dirfd = (int)x1;
+            const char* pathname = (const char*)x2;
+            struct myst_open_how* how = (struct myst_open_how*)x3;
+            size_t size = (size_t)x4;
+            long ret;
+
+            _strace(
+                n,
+                "dirfd=%d pathname=\"%s\" how=%p size=%zu",
+                dirfd,
+                pathname,
+                how,
+                size);
+
+            ret = myst_syscall_openat2(dirfd, pathname, how, size);
+            BREAK(_return(n, ret));
+        }
+        case SYS_faccessat2:
+        {
+            int dirfd = (int)x1;
+            const char* pathname = (const char*)x2;
+            int mode = (int)x3;
+            int flags = (int)x4;
+            long ret;
+
+            _strace(
+                n,
+                "dirfd=%d pathname=\"%s\" mode=%d flags=0x%x",
+                dirfd,
+                pathname,
+                mode,
+                flags);
+
+            ret = myst_syscall_faccessat(dirfd, pathname, mode, flags);
+            BREAK(_return(n, ret));
+        }
         case SYS_bind:
         {
             int sockfd = (int)x1;
@@ -4617,6 +6362,7 @@ long myst_syscall(long n, long params[6])
                 port);
 
             ret = myst_syscall_connect(sockfd, addr, addrlen);
+            myst_audit_connect(sockfd, addr, addrlen, ret);
             BREAK(_return(n, ret));
         }
         case SYS_recvfrom:
@@ -4647,23 +6393,34 @@ long myst_syscall(long n, long params[6])
             // This mitigation works around a problem with a certain
             // application that fails handle EGAIN. This should be removed
             // when possible.
-            for (size_t i = 0; i < 10; i++)
             {
-                ret = myst_syscall_recvfrom(
-                    sockfd, buf, len, flags, src_addr, addrlen);
+                myst_deadline_t deadline;
 
-                if (ret != -EAGAIN)
-                    break;
+                /* retry every 100 milliseconds for up to a second */
+                myst_deadline_init_msec(&deadline, 1000);
 
+                for (;;)
                 {
-                    struct timespec req;
-                    req.tv_sec = 0;
-                    req.tv_nsec = 1000000000 / 10;
-                    long args[6];
-                    args[0] = (long)&req;
-                    args[1] = (long)NULL;
-                    _forward_syscall(SYS_nanosleep, args);
-                    continue;
+                    ret = myst_syscall_recvfrom(
+                        sockfd, buf, len, flags, src_addr, addrlen);
+
+                    if (ret != -EAGAIN || myst_deadline_expired(&deadline))
+                        break;
+
+                    {
+                        struct timespec req;
+                        long remaining = myst_deadline_remaining(&deadline);
+                        req.tv_sec = 0;
+                        req.tv_nsec = 1000000000 / 10;
+
+                        if (remaining < req.tv_nsec)
+                            req.tv_nsec = remaining;
+
+                        long args[6];
+                        args[0] = (long)&req;
+                        args[1] = (long)NULL;
+                        _forward_syscall(SYS_nanosleep, args);
+                    }
                 }
             }
 #endif /* MYST_NO_RECVMSG_WORKAROUND */
@@ -4892,8 +6649,15 @@ long myst_syscall(long n, long params[6])
         case SYS_myst_oe_verify_attestation_certificate:
         case SYS_myst_oe_result_str:
         {
+            long ret;
+
             _strace(n, "forwarded");
-            BREAK(_return(n, _forward_syscall(n, params)));
+            ret = _forward_syscall(n, params);
+
+            if (_is_attestation_request(n))
+                myst_audit_attestation(syscall_str(n), ret);
+
+            BREAK(_return(n, ret));
         }
         default:
         {
@@ -4905,6 +6669,18 @@ long myst_syscall(long n, long params[6])
 
 done:
 
+    if (policy_locked)
+        myst_resolve_unlock();
+
+    if (policy_args)
+        free(policy_args);
+
+    /* relieve any memory pressure noted by the allocators */
+    myst_oom_poll();
+
+    /* act on a shutdown request from the host */
+    myst_shutdown_poll();
+
     /* ---------- running target thread descriptor ---------- */
 
     /* the C-runtime must execute on its own thread descriptor */
@@ -4913,8 +6689,9 @@ done:
 
     myst_times_leave_kernel();
 
-    // Process signals pending for this thread, if there is any.
-    myst_signal_process(thread);
+    // Process signals pending for this thread, if there is any, and restore
+    // the signal mask that the syscall replaced (see io_uring_enter).
+    myst_signal_process_syscall_exit(thread);
 
     return syscall_ret;
 }
@@ -4930,6 +6707,18 @@ done:
 static myst_spinlock_t _get_time_lock = MYST_SPINLOCK_INITIALIZER;
 static myst_spinlock_t _set_time_lock = MYST_SPINLOCK_INITIALIZER;
 
+/* the enclave is never suspended, so an alarm clock is its plain clock */
+static clockid_t _plain_clock(clockid_t clk_id)
+{
+    if (clk_id == CLOCK_REALTIME_ALARM)
+        return CLOCK_REALTIME;
+
+    if (clk_id == CLOCK_BOOTTIME_ALARM)
+        return CLOCK_BOOTTIME;
+
+    return clk_id;
+}
+
 long myst_syscall_clock_gettime(clockid_t clk_id, struct timespec* tp)
 {
     if (!tp)
@@ -4960,18 +6749,125 @@ long myst_syscall_clock_gettime(clockid_t clk_id, struct timespec* tp)
     }
 
     myst_spin_lock(&_get_time_lock);
-    long params[6] = {(long)clk_id, (long)tp};
+    long params[6] = {(long)_plain_clock(clk_id), (long)tp};
     long ret = myst_tcall(MYST_TCALL_CLOCK_GETTIME, params);
     myst_spin_unlock(&_get_time_lock);
     return ret;
 }
 
+/* CLOCK_REALTIME less CLOCK_MONOTONIC, which setting the realtime clock
+ * changes by as much as it moves the clock */
+static long _realtime_offset(void)
+{
+    struct timespec real;
+    struct timespec mono;
+
+    if (myst_syscall_clock_gettime(CLOCK_REALTIME, &real) != 0 ||
+        myst_syscall_clock_gettime(CLOCK_MONOTONIC, &mono) != 0)
+    {
+        return 0;
+    }
+
+    return (real.tv_sec - mono.tv_sec) * NANO_IN_SECOND +
+           (real.tv_nsec - mono.tv_nsec);
+}
+
+/* how often to check whether the host stepped the realtime clock, and by
+ * how much it must have moved (more than slewing does in the meantime) */
+#define RESYNC_NSEC (1L * NANO_IN_SECOND)
+#define RESYNC_STEP_NSEC (NANO_IN_SECOND / 10)
+
+/* the realtime offset when last checked (with _set_time_lock held) */
+static long _resync_offset;
+static myst_work_t _resync;
+
+/* CLOCK_REALTIME also steps without clock_settime(): at a leap second, or
+ * when the host sets its clock (where the target follows it). Either moves
+ * the absolute realtime timers as clock_settime() would. */
+static long _resync_callback(myst_work_t* work, long now)
+{
+    long delta;
+
+    (void)work;
+
+    myst_spin_lock(&_set_time_lock);
+    {
+        const long offset = _realtime_offset();
+
+        delta = offset - _resync_offset;
+        _resync_offset = offset;
+    }
+    myst_spin_unlock(&_set_time_lock);
+
+    if (delta > RESYNC_STEP_NSEC || delta < -RESYNC_STEP_NSEC)
+    {
+        myst_timers_clock_was_set(delta);
+        myst_timerfd_clock_was_set(delta);
+    }
+
+    return now + RESYNC_NSEC;
+}
+
+void myst_clock_resync_start(void)
+{
+    myst_spin_lock(&_set_time_lock);
+    _resync_offset = _realtime_offset();
+    myst_spin_unlock(&_set_time_lock);
+
+    myst_work_init(&_resync, "clock-resync", _resync_callback, NULL);
+    myst_work_queue(&_resync, myst_deadline_now() + RESYNC_NSEC);
+}
+
 long myst_syscall_clock_settime(clockid_t clk_id, struct timespec* tp)
 {
     long params[6] = {(long)clk_id, (long)tp};
+    long delta = 0;
+
     myst_spin_lock(&_set_time_lock);
+
+    if (clk_id == CLOCK_REALTIME)
+        delta = -_realtime_offset();
+
     long ret = myst_tcall(MYST_TCALL_CLOCK_SETTIME, params);
+
+    if (clk_id == CLOCK_REALTIME)
+    {
+        /* the resync does not count this change again */
+        _resync_offset = _realtime_offset();
+        delta += _resync_offset;
+    }
+
     myst_spin_unlock(&_set_time_lock);
+
+    /* move the absolute realtime timers (even if the clock did not move, a
+     * timer set with TFD_TIMER_CANCEL_ON_SET is cancelled, as on Linux) */
+    if (clk_id == CLOCK_REALTIME && ret == 0)
+    {
+        myst_timers_clock_was_set(delta);
+        myst_timerfd_clock_was_set(delta);
+    }
+
+    return ret;
+}
+
+long myst_syscall_clock_adjtime(clockid_t clk_id, struct timex* tx)
+{
+    long ret;
+
+    if (!tx)
+        return -EFAULT;
+
+    if (clk_id < 0 || clk_id > CLOCK_TAI)
+        return -EINVAL;
+
+    /* only the realtime clock is disciplined (by the host) */
+    if (clk_id != CLOCK_REALTIME)
+        return -EOPNOTSUPP;
+
+    long params[6] = {(long)clk_id, (long)tx};
+    myst_spin_lock(&_get_time_lock);
+    ret = myst_tcall(MYST_TCALL_CLOCK_ADJTIME, params);
+    myst_spin_unlock(&_get_time_lock);
     return ret;
 }
 
@@ -5006,33 +6902,123 @@ long myst_syscall_time(time_t* tloc)
 
 long myst_syscall_clock_getres(clockid_t clk_id, struct timespec* res)
 {
-    long params[6] = {(long)clk_id, (long)res};
+    long params[6] = {(long)_plain_clock(clk_id), (long)res};
     long ret = myst_tcall(MYST_TCALL_CLOCK_GETRES, params);
     return ret;
 }
 
-long myst_syscall_tgkill(int tgid, int tid, int sig)
+/* find the thread with the given tid in the thread group of process */
+static myst_thread_t* _find_thread_in_group(myst_thread_t* process, pid_t tid)
+{
+    myst_thread_t* target = NULL;
+
+    myst_spin_lock(process->thread_lock);
+
+    for (myst_thread_t* t = process; t && !target; t = t->group_next)
+    {
+        if (t->tid == tid)
+            target = t;
+    }
+
+    for (myst_thread_t* t = process->group_prev; t && !target; t = t->group_prev)
+    {
+        if (t->tid == tid)
+            target = t;
+    }
+
+    myst_spin_unlock(process->thread_lock);
+
+    return target;
+}
+
+myst_thread_t* myst_find_thread_by_tid(pid_t tgid, pid_t tid)
+{
+    myst_thread_t* self = myst_thread_self();
+    myst_thread_t* target = NULL;
+    myst_thread_t* process;
+
+    /* the common case: a thread of the caller's own group */
+    if (tgid == self->pid)
+        return myst_find_thread(tid);
+
+    if (tgid == -1 && (target = myst_find_thread(tid)))
+        return target;
+
+    myst_spin_lock(&myst_process_list_lock);
+
+    if (tgid != -1)
+    {
+        if ((process = myst_find_process_by_pid(tgid)))
+            target = _find_thread_in_group(process, tid);
+    }
+    else
+    {
+        process = myst_find_process_thread(self);
+
+        for (myst_thread_t* p = process; p && !target;
+             p = p->main.prev_process_thread)
+        {
+            target = _find_thread_in_group(p, tid);
+        }
+
+        for (myst_thread_t* p = process->main.next_process_thread;
+             p && !target;
+             p = p->main.next_process_thread)
+        {
+            target = _find_thread_in_group(p, tid);
+        }
+    }
+
+    myst_spin_unlock(&myst_process_list_lock);
+
+    return target;
+}
+
+static long _kill_thread(pid_t tgid, pid_t tid, int sig)
 {
     long ret = 0;
     myst_thread_t* thread = myst_thread_self();
-    myst_thread_t* target = myst_find_thread(tid);
+    myst_thread_t* target;
+    siginfo_t* siginfo;
 
-    if (target == NULL)
+    if (tid <= 0 || sig < 0 || sig > NSIG - 1)
+        ERAISE(-EINVAL);
+
+    if (!(target = myst_find_thread_by_tid(tgid, tid)))
         ERAISE(-ESRCH);
 
-    // Only allow a thread to kill other threads in the same group.
-    if (tgid != thread->pid)
-        ERAISE(-EINVAL);
+    /* signal 0 only checks that the thread exists */
+    if (sig == 0)
+        goto done;
+
+    if (!(siginfo = calloc(1, sizeof(siginfo_t))))
+        ERAISE(-ENOMEM);
 
-    siginfo_t* siginfo = calloc(1, sizeof(siginfo_t));
     siginfo->si_code = SI_TKILL;
     siginfo->si_signo = sig;
-    myst_signal_deliver(target, sig, siginfo);
+    siginfo->si_pid = thread->pid;
+    siginfo->si_uid = MYST_DEFAULT_UID;
+
+    ret = myst_signal_deliver(target, sig, siginfo);
 
 done:
     return ret;
 }
 
+long myst_syscall_tgkill(int tgid, int tid, int sig)
+{
+    if (tgid <= 0)
+        return -EINVAL;
+
+    /* ESRCH if the thread is not in the given thread group */
+    return _kill_thread(tgid, tid, sig);
+}
+
+long myst_syscall_tkill(int tid, int sig)
+{
+    return _kill_thread(-1, tid, sig);
+}
+
 long myst_syscall_kill(int pid, int sig)
 {
     long ret = 0;
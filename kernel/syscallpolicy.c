@@ -0,0 +1,588 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <netinet/in.h>
+#include <signal.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/un.h>
+#include <syscall.h>
+
+#include <myst/eraise.h>
+#include <myst/errno.h>
+#include <myst/fdtable.h>
+#include <myst/paths.h>
+#include <myst/printf.h>
+#include <myst/process.h>
+#include <myst/realpath.h>
+#include <myst/resolve.h>
+#include <myst/signal.h>
+#include <myst/strings.h>
+#include <myst/syscall.h>
+#include <myst/syscallext.h>
+#include <myst/syscallpolicy.h>
+#include <myst/thread.h>
+#include <myst/uaccess.h>
+
+/*
+**==============================================================================
+**
+** Syscall policy:
+**
+** The "SyscallPolicy" section of config.json lists rules that are checked in
+** order each time the application enters the kernel. The first rule that names
+** the syscall and whose predicates (path, address, port) match decides what
+** happens: "allow" runs the syscall, "errno" fails it with the given error and
+** "kill" terminates the process. If no rule matches, the default action is
+** taken, except for Mystikos extensions (SYS_myst_*), which the C runtime uses
+** internally and which are only restricted by rules that name them.
+**
+**==============================================================================
+*/
+
+typedef enum action
+{
+    ACTION_ALLOW,
+    ACTION_ERRNO,
+    ACTION_KILL,
+} action_t;
+
+typedef struct rule
+{
+    long num;
+    action_t action;
+    long error;
+
+    /* path predicate (open, openat, openat2, creat) */
+    char* path;
+
+    /* address predicate (connect) */
+    int family;
+    uint8_t addr[4];
+    unsigned int prefix;
+    char* unix_path;
+    long port;
+} rule_t;
+
+static bool _enabled;
+static rule_t* _rules;
+static size_t _num_rules;
+static action_t _default_action;
+static long _default_error;
+
+/* match a pattern in which '*' matches any string and '?' any character */
+static bool _match(const char* pattern, const char* s)
+{
+    const char* star = NULL;
+    const char* resume = NULL;
+
+    while (*s)
+    {
+        if (*pattern == '*')
+        {
+            star = pattern++;
+            resume = s;
+        }
+        else if (*pattern == '?' || *pattern == *s)
+        {
+            pattern++;
+            s++;
+        }
+        else if (star)
+        {
+            pattern = star + 1;
+            s = ++resume;
+        }
+        else
+        {
+            return false;
+        }
+    }
+
+    while (*pattern == '*')
+        pattern++;
+
+    return *pattern == '\0';
+}
+
+static int _parse_action(const char* name, action_t* action)
+{
+    if (!name || strcmp(name, "allow") == 0)
+        *action = ACTION_ALLOW;
+    else if (strcmp(name, "errno") == 0)
+        *action = ACTION_ERRNO;
+    else if (strcmp(name, "kill") == 0)
+        *action = ACTION_KILL;
+    else
+        return -EINVAL;
+
+    return 0;
+}
+
+static int _parse_error(const char* name, long* error)
+{
+    if (!name)
+        *error = EPERM;
+    else if ((*error = myst_error_num(name)) == 0)
+        return -EINVAL;
+
+    return 0;
+}
+
+/* parse "a.b.c.d" or "a.b.c.d/n" */
+static int _parse_ipv4(const char* s, uint8_t addr[4], unsigned int* prefix)
+{
+    *prefix = 32;
+
+    for (size_t i = 0; i < 4; i++)
+    {
+        unsigned int x = 0;
+        size_t n = 0;
+
+        for (; *s >= '0' && *s <= '9' && n < 3; s++, n++)
+            x = x * 10 + (unsigned int)(*s - '0');
+
+        if (n == 0 || x > 255)
+            return -EINVAL;
+
+        addr[i] = (uint8_t)x;
+
+        if (i < 3 && *s++ != '.')
+            return -EINVAL;
+    }
+
+    if (*s == '/')
+    {
+        unsigned int x = 0;
+        size_t n = 0;
+
+        for (s++; *s >= '0' && *s <= '9' && n < 2; s++, n++)
+            x = x * 10 + (unsigned int)(*s - '0');
+
+        if (n == 0 || x > 32)
+            return -EINVAL;
+
+        *prefix = x;
+    }
+
+    return *s == '\0' ? 0 : -EINVAL;
+}
+
+static bool _is_open(long n)
+{
+    return n == SYS_open || n == SYS_openat || n == SYS_openat2 ||
+           n == SYS_creat;
+}
+
+static int _compile_rule(const myst_syscall_rule_t* in, rule_t* out)
+{
+    int ret = 0;
+
+    if (!in->syscall || (out->num = myst_syscall_num(in->syscall)) < 0)
+    {
+        myst_eprintf(
+            "kernel: syscall policy: unknown syscall: %s\n",
+            in->syscall ? in->syscall : "");
+        ERAISE(-EINVAL);
+    }
+
+    if (_parse_action(in->action, &out->action) != 0 ||
+        _parse_error(in->error, &out->error) != 0)
+    {
+        myst_eprintf(
+            "kernel: syscall policy: bad action for %s: %s %s\n",
+            in->syscall,
+            in->action ? in->action : "",
+            in->error ? in->error : "");
+        ERAISE(-EINVAL);
+    }
+
+    if (in->path)
+    {
+        if (!_is_open(out->num))
+        {
+            myst_eprintf(
+                "kernel: syscall policy: path predicate on %s\n", in->syscall);
+            ERAISE(-EINVAL);
+        }
+
+        if (!(out->path = strdup(in->path)))
+            ERAISE(-ENOMEM);
+    }
+
+    if (in->address || in->port)
+    {
+        if (out->num != SYS_connect || in->port < 0 || in->port > 65535)
+        {
+            myst_eprintf(
+                "kernel: syscall policy: bad address predicate on %s\n",
+                in->syscall);
+            ERAISE(-EINVAL);
+        }
+
+        out->port = in->port;
+    }
+
+    if (in->address)
+    {
+        const char unix_prefix[] = "unix:";
+
+        if (strncmp(in->address, unix_prefix, sizeof(unix_prefix) - 1) == 0)
+        {
+            out->family = AF_UNIX;
+            out->unix_path = strdup(in->address + sizeof(unix_prefix) - 1);
+
+            if (!out->unix_path)
+                ERAISE(-ENOMEM);
+        }
+        else if (_parse_ipv4(in->address, out->addr, &out->prefix) == 0)
+        {
+            out->family = AF_INET;
+        }
+        else
+        {
+            myst_eprintf(
+                "kernel: syscall policy: bad address: %s\n", in->address);
+            ERAISE(-EINVAL);
+        }
+    }
+
+done:
+    return ret;
+}
+
+int myst_syscall_policy_setup(const myst_syscall_policy_t* policy)
+{
+    int ret = 0;
+
+    if (!policy)
+        ERAISE(-EINVAL);
+
+    /* a missing "SyscallPolicy" section allows everything */
+    if (!policy->default_action && !policy->num_rules)
+        goto done;
+
+    if (_parse_action(policy->default_action, &_default_action) != 0 ||
+        _parse_error(policy->default_error, &_default_error) != 0)
+    {
+        myst_eprintf("kernel: syscall policy: bad default action\n");
+        ERAISE(-EINVAL);
+    }
+
+    if (policy->num_rules)
+    {
+        if (!(_rules = calloc(policy->num_rules, sizeof(rule_t))))
+            ERAISE(-ENOMEM);
+
+        for (size_t i = 0; i < policy->num_rules; i++)
+            ECHECK(_compile_rule(&policy->rules[i], &_rules[i]));
+
+        _num_rules = policy->num_rules;
+    }
+
+    _enabled = true;
+
+done:
+    return ret;
+}
+
+bool myst_syscall_policy_enabled(void)
+{
+    return _enabled;
+}
+
+bool myst_syscall_policy_checks_path(long n)
+{
+    for (size_t i = 0; i < _num_rules; i++)
+    {
+        if (_rules[i].num == n && _rules[i].path)
+            return true;
+    }
+
+    return false;
+}
+
+long myst_syscall_policy_copy_args(
+    long n,
+    long params[6],
+    myst_syscall_policy_args_t* args)
+{
+    long ret = 0;
+    const size_t i = (n == SYS_open || n == SYS_creat) ? 0 : 1;
+
+    if (!_is_open(n) || !args)
+        ERAISE(-EINVAL);
+
+    /* a null path fails in the syscall itself */
+    if (params[i])
+    {
+        ECHECK(myst_strncpy_from_user(
+            args->path, (const char*)params[i], sizeof(args->path)));
+        params[i] = (long)args->path;
+    }
+
+    if (n == SYS_openat2 && params[2])
+    {
+        const uint8_t* how = (const uint8_t*)params[2];
+        const size_t size = (size_t)params[3];
+
+        /* as openat2() checks a newer struct open_how */
+        if (size < sizeof(args->how))
+            ERAISE(-EINVAL);
+
+        if (size > PAGE_SIZE)
+            ERAISE(-E2BIG);
+
+        if (MYST_COPY_FROM_USER(&args->how, (const myst_open_how_t*)how) != 0)
+            ERAISE(-EFAULT);
+
+        for (size_t j = sizeof(args->how); j < size; j++)
+        {
+            uint8_t byte;
+
+            if (myst_copy_from_user(&byte, how + j, 1) != 0)
+                ERAISE(-EFAULT);
+
+            if (byte)
+                ERAISE(-E2BIG);
+        }
+
+        params[2] = (long)&args->how;
+        params[3] = sizeof(args->how);
+    }
+
+    ret = 0;
+
+done:
+    return ret;
+}
+
+/* form the absolute path of the file that an open call refers to, following
+ * the symbolic links as the open itself does */
+static int _open_path(long n, const long params[6], myst_path_t* path)
+{
+    int ret = 0;
+    int dirfd = AT_FDCWD;
+    const char* pathname;
+    int flags;
+    uint64_t resolve = 0;
+    bool follow;
+    typedef struct _variables
+    {
+        char dirpath[PATH_MAX];
+        myst_path_t resolved;
+    } variables_t;
+    variables_t* v = NULL;
+
+    if (n == SYS_openat)
+    {
+        dirfd = (int)params[0];
+        pathname = (const char*)params[1];
+        flags = (int)params[2];
+    }
+    else if (n == SYS_openat2)
+    {
+        /* copied by myst_syscall_policy_copy_args() */
+        const myst_open_how_t* how = (const myst_open_how_t*)params[2];
+
+        if (!how)
+            ERAISE(-EFAULT);
+
+        dirfd = (int)params[0];
+        pathname = (const char*)params[1];
+        flags = (int)how->flags;
+        resolve = how->resolve;
+    }
+    else if (n == SYS_open)
+    {
+        pathname = (const char*)params[0];
+        flags = (int)params[1];
+    }
+    else
+    {
+        pathname = (const char*)params[0];
+        flags = O_CREAT | O_WRONLY | O_TRUNC;
+    }
+
+    if (!pathname || *pathname == '\0')
+        ERAISE(-EINVAL);
+
+    if (!(v = malloc(sizeof(variables_t))))
+        ERAISE(-ENOMEM);
+
+    /* scoped openat2() lookups keep absolute paths within the directory */
+    if (*pathname == '/' &&
+        !(resolve & (MYST_RESOLVE_BENEATH | MYST_RESOLVE_IN_ROOT)))
+    {
+        myst_strlcpy(v->dirpath, "/", sizeof(v->dirpath));
+    }
+    else if (dirfd == AT_FDCWD)
+    {
+        ECHECK(myst_syscall_getcwd(v->dirpath, sizeof(v->dirpath)));
+    }
+    else
+    {
+        myst_fdtable_t* fdtable = myst_fdtable_current();
+        myst_fs_t* fs;
+        myst_file_t* file;
+
+        ECHECK(myst_fdtable_get_file(fdtable, dirfd, &fs, &file));
+        ECHECK(myst_fs_path(fs, file, v->dirpath, sizeof(v->dirpath)));
+    }
+
+    /* a final link is not followed with O_NOFOLLOW or O_CREAT|O_EXCL */
+    follow = !(flags & O_NOFOLLOW) && !((flags & O_CREAT) && (flags & O_EXCL));
+
+    ECHECK(myst_resolve_path(
+        v->dirpath, pathname, resolve, follow, &v->resolved));
+    ECHECK(myst_realpath(v->resolved.buf, path));
+
+done:
+
+    if (v)
+        free(v);
+
+    return ret;
+}
+
+static bool _match_path(const rule_t* rule, long n, const long params[6])
+{
+    myst_path_t path;
+
+    /* paths that cannot be resolved fail in the syscall itself */
+    if (_open_path(n, params, &path) != 0)
+        return false;
+
+    return _match(rule->path, path.buf);
+}
+
+/* return 1 if the connect() address matches, 0 if not or -EFAULT */
+static int _match_address(const rule_t* rule, const long params[6])
+{
+    struct sockaddr_storage buf;
+    const struct sockaddr* addr = (const struct sockaddr*)&buf;
+    socklen_t addrlen = (socklen_t)params[2];
+
+    if (!params[1] || addrlen < sizeof(sa_family_t))
+        return 0;
+
+    /* a longer address than any family has is read no further */
+    if (addrlen > sizeof(buf))
+        addrlen = sizeof(buf);
+
+    if (myst_copy_from_user(&buf, (const void*)params[1], addrlen) != 0)
+        return -EFAULT;
+
+    if (rule->family == AF_UNIX)
+    {
+        const struct sockaddr_un* un = (const struct sockaddr_un*)addr;
+        char path[sizeof(un->sun_path) + 1];
+        size_t n = addrlen - sizeof(sa_family_t);
+
+        if (addr->sa_family != AF_UNIX)
+            return 0;
+
+        if (n > sizeof(un->sun_path))
+            n = sizeof(un->sun_path);
+
+        memcpy(path, un->sun_path, n);
+        path[n] = '\0';
+
+        return _match(rule->unix_path, path);
+    }
+
+    if (addr->sa_family != AF_INET || addrlen < sizeof(struct sockaddr_in))
+        return 0;
+
+    {
+        const struct sockaddr_in* in = (const struct sockaddr_in*)addr;
+        const uint8_t* ip = (const uint8_t*)&in->sin_addr;
+        const uint8_t* port = (const uint8_t*)&in->sin_port;
+
+        if (rule->port && rule->port != ((port[0] << 8) | port[1]))
+            return 0;
+
+        if (rule->family == AF_INET)
+        {
+            for (unsigned int i = 0; i < rule->prefix; i += 8)
+            {
+                const unsigned int bits = rule->prefix - i;
+                const uint8_t mask =
+                    bits >= 8 ? 0xff : (uint8_t)(0xff << (8 - bits));
+
+                if ((ip[i / 8] & mask) != (rule->addr[i / 8] & mask))
+                    return 0;
+            }
+        }
+    }
+
+    return 1;
+}
+
+/* terminate the process once the calling thread leaves the kernel */
+static void _kill(long n)
+{
+    myst_thread_t* thread = myst_thread_self();
+
+    myst_eprintf(
+        "kernel: syscall policy: killed process %d: %s\n",
+        thread->pid,
+        syscall_str(n));
+
+    if (!myst_is_process_thread(thread))
+        myst_syscall_kill(thread->pid, SIGKILL);
+
+    myst_signal_deliver(thread, SIGKILL, NULL);
+}
+
+static long _apply(long n, action_t action, long error)
+{
+    switch (action)
+    {
+        case ACTION_ALLOW:
+            return 0;
+        case ACTION_ERRNO:
+            return -error;
+        case ACTION_KILL:
+            _kill(n);
+            return -ENOSYS;
+    }
+
+    return 0;
+}
+
+long myst_syscall_policy_check(long n, const long params[6])
+{
+    for (size_t i = 0; i < _num_rules; i++)
+    {
+        const rule_t* rule = &_rules[i];
+
+        if (rule->num != n)
+            continue;
+
+        if (rule->path && !_match_path(rule, n, params))
+            continue;
+
+        if (rule->family || rule->port)
+        {
+            const int r = _match_address(rule, params);
+
+            if (r < 0)
+                return r;
+
+            if (r == 0)
+                continue;
+        }
+
+        return _apply(n, rule->action, rule->error);
+    }
+
+    /* the C runtime relies on the Mystikos extensions */
+    if (n >= SYS_myst_trace)
+        return 0;
+
+    return _apply(n, _default_action, _default_error);
+}
@@ -8,7 +8,9 @@
 #include <myst/blockdevice.h>
 #include <myst/fsgs.h>
 #include <myst/luks.h>
+#include <myst/rusage.h>
 #include <myst/sha256.h>
+#include <myst/sigsafe.h>
 #include <myst/signal.h>
 #include <myst/strings.h>
 #include <myst/tcall.h>
@@ -62,9 +64,14 @@ long myst_tcall_create_thread(uint64_t cookie)
 long myst_tcall_wait(uint64_t event, const struct timespec* timeout)
 {
     long params[6] = {0};
+
+    myst_sigsafe_assert(__FUNCTION__);
+
     params[0] = (long)event;
     params[1] = (long)timeout;
+    long begin = myst_rusage_block_begin();
     long ret = myst_tcall(MYST_TCALL_WAIT, params);
+    myst_rusage_block_end(begin);
     // check for signals
     myst_signal_process(myst_thread_self());
     return ret;
@@ -77,12 +84,33 @@ long myst_tcall_wake(uint64_t event)
     return myst_tcall(MYST_TCALL_WAKE, params);
 }
 
+long myst_tcall_write_log(
+    const char* path,
+    const void* buf,
+    size_t count,
+    size_t max_size,
+    size_t max_files)
+{
+    long params[6] = {
+        (long)path, (long)buf, (long)count, (long)max_size, (long)max_files};
+    return myst_tcall(MYST_TCALL_WRITE_LOG, params);
+}
+
 long myst_tcall_export_file(const char* path, const void* data, size_t size)
 {
     long params[6] = {(long)path, (long)data, (long)size};
     return myst_tcall(MYST_TCALL_EXPORT_FILE, params);
 }
 
+long myst_tcall_export_coverage(
+    const char* path,
+    const void* data,
+    size_t size)
+{
+    long params[6] = {(long)path, (long)data, (long)size};
+    return myst_tcall(MYST_TCALL_EXPORT_COVERAGE, params);
+}
+
 long myst_tcall_wake_wait(
     uint64_t waiter_event,
     uint64_t self_event,
@@ -131,9 +159,25 @@ long myst_tcall_poll_wake(void)
     return myst_tcall(MYST_TCALL_POLL_WAKE, params);
 }
 
+long myst_tcall_mprotect(void* addr, size_t length, int prot)
+{
+    long params[6] = {(long)addr, (long)length, prot};
+    return myst_tcall(MYST_TCALL_MPROTECT, params);
+}
+
 long myst_tcall_poll(struct pollfd* fds, nfds_t nfds, int timeout)
 {
     long params[6] = {(long)fds, nfds, timeout};
+
+    /* a zero timeout polls without blocking */
+    if (timeout != 0)
+    {
+        long begin = myst_rusage_block_begin();
+        long ret = myst_tcall(SYS_poll, params);
+        myst_rusage_block_end(begin);
+        return ret;
+    }
+
     return myst_tcall(SYS_poll, params);
 }
 
@@ -169,6 +213,18 @@ int myst_write_block_device(
     return myst_tcall(MYST_TCALL_WRITE_BLOCK_DEVICE, params);
 }
 
+int myst_get_block_device_size(int blkdev, uint64_t* size)
+{
+    long params[6] = {blkdev, (long)size};
+    return myst_tcall(MYST_TCALL_GET_BLOCK_DEVICE_SIZE, params);
+}
+
+int myst_sync_block_device(int blkdev)
+{
+    long params[6] = {blkdev};
+    return myst_tcall(MYST_TCALL_SYNC_BLOCK_DEVICE, params);
+}
+
 int myst_luks_encrypt(
     const luks_phdr_t* phdr,
     const void* key,
@@ -236,3 +292,78 @@ int myst_tcall_load_fssig(const char* path, myst_fssig_t* fssig)
     long params[6] = {(long)path, (long)fssig};
     return myst_tcall(MYST_TCALL_LOAD_FSSIG, params);
 }
+
+long myst_tcall_key_release(
+    const myst_key_release_request_t* request,
+    void* key,
+    size_t key_size,
+    size_t* key_size_out)
+{
+    long params[6] = {(long)request, (long)key, key_size, (long)key_size_out};
+    return myst_tcall(MYST_TCALL_KEY_RELEASE, params);
+}
+
+long myst_tcall_unseal_secret(
+    const void* blob,
+    size_t blob_size,
+    void* buf,
+    size_t size,
+    size_t* size_out)
+{
+    long params[6] = {
+        (long)blob, blob_size, (long)buf, size, (long)size_out};
+    return myst_tcall(MYST_TCALL_UNSEAL_SECRET, params);
+}
+
+long myst_tcall_attested_https_get(
+    const myst_attested_https_request_t* request,
+    void* buf,
+    size_t size,
+    size_t* size_out)
+{
+    long params[6] = {(long)request, (long)buf, size, (long)size_out};
+    return myst_tcall(MYST_TCALL_ATTESTED_HTTPS_GET, params);
+}
+
+long myst_tcall_set_crash_handler(myst_crash_handler_t handler)
+{
+    long params[6] = {(long)handler};
+    return myst_tcall(MYST_TCALL_SET_CRASH_HANDLER, params);
+}
+
+long myst_tcall_set_fault_handler(myst_fault_handler_t handler)
+{
+    long params[6] = {(long)handler};
+    return myst_tcall(MYST_TCALL_SET_FAULT_HANDLER, params);
+}
+
+long myst_tcall_rsa_oaep_encrypt(
+    const char* pem_public_key,
+    const void* in,
+    size_t in_size,
+    void* out,
+    size_t out_size,
+    size_t* out_size_out)
+{
+    long params[6] = {
+        (long)pem_public_key,
+        (long)in,
+        in_size,
+        (long)out,
+        out_size,
+        (long)out_size_out};
+    return myst_tcall(MYST_TCALL_RSA_OAEP_ENCRYPT, params);
+}
+
+long myst_tcall_aes_gcm_encrypt(
+    const uint8_t key[32],
+    const uint8_t nonce[12],
+    const void* in,
+    size_t size,
+    void* out,
+    uint8_t tag[16])
+{
+    long params[6] = {
+        (long)key, (long)nonce, (long)in, size, (long)out, (long)tag};
+    return myst_tcall(MYST_TCALL_AES_GCM_ENCRYPT, params);
+}
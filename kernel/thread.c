@@ -16,6 +16,7 @@
 #include <myst/fdtable.h>
 #include <myst/file.h>
 #include <myst/fsgs.h>
+#include <myst/iouringdev.h>
 #include <myst/futex.h>
 #include <myst/kernel.h>
 #include <myst/lfence.h>
@@ -24,9 +25,12 @@
 #include <myst/panic.h>
 #include <myst/printf.h>
 #include <myst/procfs.h>
+#include <myst/rusage.h>
+#include <myst/sched.h>
 #include <myst/setjmp.h>
 #include <myst/signal.h>
 #include <myst/spinlock.h>
+#include <myst/stackguard.h>
 #include <myst/strings.h>
 #include <myst/syscall.h>
 #include <myst/tcall.h>
@@ -264,9 +268,6 @@ long myst_syscall_wait4(
     bool locked = false;
     myst_thread_t* process = myst_find_process_thread(myst_thread_self());
 
-    if (rusage)
-        ERAISE(-EINVAL);
-
     if (options & ~(WNOHANG | WUNTRACED | WCONTINUED))
         ERAISE(-EINVAL);
 
@@ -312,9 +313,16 @@ long myst_syscall_wait4(
 
             if (match)
             {
+                myst_rusage_t usage;
+
+                myst_rusage_reap(process, p, &usage);
+
                 if (wstatus)
                     *wstatus = (p->exit_status << 8);
 
+                if (rusage)
+                    myst_rusage_to_rusage(&usage, rusage);
+
                 ret = p->pid;
                 goto done;
             }
@@ -372,6 +380,27 @@ myst_thread_t* myst_find_thread(int tid)
     return target;
 }
 
+myst_thread_t* myst_find_process_by_pid(pid_t pid)
+{
+    myst_thread_t* process = myst_find_process_thread(myst_thread_self());
+    myst_thread_t* t;
+
+    for (t = process; t != NULL; t = t->main.prev_process_thread)
+    {
+        if (t->pid == pid)
+            return t;
+    }
+
+    for (t = process->main.next_process_thread; t != NULL;
+         t = t->main.next_process_thread)
+    {
+        if (t->pid == pid)
+            return t;
+    }
+
+    return NULL;
+}
+
 /*
 **==============================================================================
 **
@@ -380,6 +409,17 @@ myst_thread_t* myst_find_thread(int tid)
 **==============================================================================
 */
 
+/* The C runtime builds the TLS area of every new thread (copying the PT_TLS
+ * image and setting up the DTV) and passes it to SYS_clone as newtls. The
+ * kernel only installs it as the FS base, after checking the fields that it
+ * shares with the musl (struct pthread) and glibc (tcbhead_t) layouts:
+ *
+ *     0x00 self pointer (musl: self, glibc: tcb)
+ *     0x28 stack canary (musl: canary, glibc: stack_guard)
+ */
+MYST_STATIC_ASSERT(MYST_OFFSETOF(myst_td_t, self) == 0x00);
+MYST_STATIC_ASSERT(MYST_OFFSETOF(myst_td_t, canary) == 0x28);
+
 bool myst_valid_td(const void* td)
 {
     return td && ((const myst_td_t*)td)->self == td;
@@ -451,11 +491,18 @@ long myst_run_thread(uint64_t cookie, uint64_t event)
             const int futex_op = FUTEX_WAKE | FUTEX_PRIVATE;
             myst_syscall_futex(thread->clone.ptid, futex_op, 1, 0, NULL, 0);
         }
-
-        /* Start time tracking for this thread */
-        myst_times_start();
     }
 
+    /* Start time tracking for this thread (or new process) */
+    myst_times_start();
+
+    /* Give the thread its virtual CPU (reported by getcpu) */
+    myst_sched_take_cpu(thread);
+
+    /* Map the inherited scheduling attributes onto the host thread */
+    if (thread->sched.nice || thread->sched.policy != SCHED_OTHER)
+        myst_sched_apply();
+
     /* Jump back here from exit */
     if (myst_setjmp(&thread->jmpbuf) != 0)
     {
@@ -468,6 +515,15 @@ long myst_run_thread(uint64_t cookie, uint64_t event)
 
         /* ---------- running target thread descriptor ---------- */
 
+        /* Release the robust futexes that this thread still holds */
+        myst_futex_exit_robust_list(thread);
+
+        /* The stack is no longer checked for overruns */
+        myst_stack_guard_detach(thread);
+
+        /* Free the virtual CPU for the next thread */
+        myst_sched_release_cpu(thread);
+
         /* Wake up any thread waiting on ctid */
         if (is_child_thread)
         {
@@ -485,6 +541,9 @@ long myst_run_thread(uint64_t cookie, uint64_t event)
                 thread->fdtable = NULL;
             }
 
+            /* stop the timers before they signal a freed thread */
+            myst_release_process_timers(thread->pid);
+
             myst_signal_free(thread);
 
             if (thread->main.exec_stack)
@@ -516,11 +575,15 @@ long myst_run_thread(uint64_t cookie, uint64_t event)
 
                 /* unmap any mapping made by the process */
                 myst_release_process_mappings(thread->pid);
+                myst_iouring_release_mappings(thread->pid);
             }
 
             free(thread->main.cwd);
             thread->main.cwd = NULL;
 
+            free(thread->main.root);
+            thread->main.root = NULL;
+
             procfs_pid_cleanup(thread->pid);
         }
 
@@ -609,6 +672,7 @@ static long _syscall_clone(
         child->crt_td = newtls;
         child->run_thread = myst_run_thread;
         child->thread_lock = parent->thread_lock;
+        myst_sched_clone(parent, child);
         /* ATTN: we don't take a lock on _num_threads,
             thread names could be duplicates */
         snprintf(
@@ -642,6 +706,9 @@ static long _syscall_clone(
         child->clone.ptid = ptid;
         child->clone.newtls = newtls;
         child->clone.ctid = ctid;
+
+        /* check the guard below the stack of the new thread */
+        myst_stack_guard_attach(child, child_stack);
     }
 
     cookie = _get_cookie(child);
@@ -691,13 +758,9 @@ static long _syscall_clone_vfork(
         child->run_thread = myst_run_thread;
         child->main.thread_group_lock = MYST_SPINLOCK_INITIALIZER;
         child->thread_lock = &child->main.thread_group_lock;
-        /* ATTN: we don't take a lock on _num_threads,
-            thread names could be duplicates */
-        snprintf(
-            child->name,
-            sizeof(child->name),
-            "thread-%ld",
-            _num_threads % 99999999);
+        myst_sched_clone(parent, child);
+        /* the child process inherits the name of the parent thread */
+        MYST_STRLCPY(child->name, parent->name);
 
         /* Inherit parent current working directory */
         child->main.cwd_lock = MYST_SPINLOCK_INITIALIZER;
@@ -705,9 +768,39 @@ static long _syscall_clone_vfork(
         if (child->main.cwd == NULL)
             ERAISE(-ENOMEM);
 
+        /* Inherit parent root directory */
+        {
+            myst_thread_t* process = myst_find_process_thread(parent);
+
+            child->main.root_lock = MYST_SPINLOCK_INITIALIZER;
+            myst_spin_lock(&process->main.root_lock);
+
+            if (process->main.root)
+                child->main.root = strdup(process->main.root);
+
+            myst_spin_unlock(&process->main.root_lock);
+
+            if (process->main.root && !child->main.root)
+                ERAISE(-ENOMEM);
+        }
+
         /* inherit the umask from the parent process */
         child->main.umask = parent->main.umask;
 
+        /* inherit the dumpable attribute from the parent process */
+        child->main.dumpable = myst_find_process_thread(parent)->main.dumpable;
+
+        /* inherit the personality from the parent process */
+        child->main.personality =
+            myst_find_process_thread(parent)->main.personality;
+
+        /* inherit RLIMIT_CPU from the parent process */
+        myst_budget_init(child, myst_find_process_thread(parent));
+
+        /* the child runs the same program until it execs */
+        child->main.wx_exempt =
+            myst_find_process_thread(parent)->main.wx_exempt;
+
         if (myst_fdtable_clone(parent->fdtable, &child->fdtable) != 0)
             ERAISE(-ENOMEM);
 
@@ -737,17 +830,8 @@ static long _syscall_clone_vfork(
         parent_main_thread->main.next_process_thread = child;
         myst_spin_unlock(&myst_process_list_lock);
 
-        /* Create /proc/[pid]/fd directory for new process thread */
-        {
-            char fdpath[PATH_MAX];
-            const size_t n = sizeof(fdpath);
-            snprintf(fdpath, n, "/proc/%d/fd", child->pid);
-            if (myst_mkdirhier(fdpath, 777) != 0)
-            {
-                myst_eprintf("cannot create the /proc/[pid]/fd directory\n");
-                ERAISE(-EINVAL);
-            }
-        }
+        /* Create /proc/[pid] entries for new process thread */
+        ECHECK(procfs_pid_setup(child->pid));
     }
 
     cookie = _get_cookie(child);
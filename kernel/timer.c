@@ -0,0 +1,488 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <limits.h>
+#include <signal.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+
+#include <myst/clock.h>
+#include <myst/deadline.h>
+#include <myst/eraise.h>
+#include <myst/mutex.h>
+#include <myst/process.h>
+#include <myst/signal.h>
+#include <myst/syscall.h>
+#include <myst/thread.h>
+#include <myst/timerwheel.h>
+
+/* the most timers there may be at once (over all processes) */
+#define MAX_TIMERS (1024 * 1024)
+
+#ifndef SIGEV_THREAD_ID
+#define SIGEV_THREAD_ID 4
+#endif
+
+/* the sigevent that the kernel takes (libc turns SIGEV_THREAD into
+ * SIGEV_THREAD_ID for a thread of its own) */
+struct ksigevent
+{
+    union sigval sigev_value;
+    int sigev_signo;
+    int sigev_notify;
+    int sigev_tid;
+};
+
+typedef struct posix_timer
+{
+    myst_timer_t timer;
+    int id;
+    pid_t pid;   /* the process that owns the timer */
+    pid_t tid;   /* the thread to signal (or 0 for the process) */
+    int signo;   /* 0 for SIGEV_NONE */
+    union sigval value;
+    clockid_t clockid;
+    long interval;
+
+    /* set with TIMER_ABSTIME on CLOCK_REALTIME (see
+     * myst_timers_clock_was_set) */
+    bool absolute;
+
+    /* the expirations that were not signalled (the signal was pending) */
+    int missed;
+
+    /* the overrun of the last signal */
+    int overrun;
+} posix_timer_t;
+
+/* the timers by id (the table and the settings of the timers) */
+static myst_mutex_t _mutex;
+static posix_timer_t** _timers;
+static size_t _capacity;
+
+/* no id below this one is free */
+static size_t _free;
+
+static int _add_overruns(int overruns, long n)
+{
+    return n > DELAYTIMER_MAX - overruns ? DELAYTIMER_MAX : overruns + (int)n;
+}
+
+/* signal the expiry, unless the last signal is still pending */
+static bool _notify(posix_timer_t* t)
+{
+    myst_thread_t* target;
+    siginfo_t* siginfo;
+    uint64_t mask = (uint64_t)1 << (t->signo - 1);
+
+    if (t->tid)
+    {
+        target = myst_find_thread_by_tid(t->pid, t->tid);
+    }
+    else
+    {
+        myst_spin_lock(&myst_process_list_lock);
+        target = myst_find_process_by_pid(t->pid);
+        myst_spin_unlock(&myst_process_list_lock);
+    }
+
+    /* the thread is gone: there is no one left to tell */
+    if (!target)
+        return true;
+
+    if (target->signal.pending & mask)
+        return false;
+
+    if (!(siginfo = calloc(1, sizeof(siginfo_t))))
+        return false;
+
+    siginfo->si_code = SI_TIMER;
+    siginfo->si_signo = t->signo;
+    siginfo->si_timerid = t->id;
+    siginfo->si_overrun = t->missed;
+    siginfo->si_value = t->value;
+
+    myst_signal_deliver(target, t->signo, siginfo);
+    return true;
+}
+
+/* runs on the timer thread when a timer expires */
+static long _expire(myst_timer_t* timer, long now)
+{
+    posix_timer_t* t = timer->arg;
+    long late = 0;
+    long next = 0;
+
+    if (t->interval)
+    {
+        /* the periods that went by while the timer thread was late */
+        if (now > timer->expires)
+            late = (now - timer->expires) / t->interval;
+
+        if (__builtin_smull_overflow(late + 1, t->interval, &next) ||
+            __builtin_saddl_overflow(timer->expires, next, &next))
+        {
+            next = LONG_MAX;
+        }
+    }
+
+    if (t->signo)
+    {
+        if (_notify(t))
+        {
+            t->overrun = _add_overruns(t->missed, late);
+            t->missed = 0;
+        }
+        else
+        {
+            t->missed = _add_overruns(t->missed, late + 1);
+        }
+    }
+
+    return next;
+}
+
+/* get a timer of the calling process (with _mutex held) */
+static posix_timer_t* _get(int timerid)
+{
+    posix_timer_t* t;
+
+    if (timerid < 0 || (size_t)timerid >= _capacity)
+        return NULL;
+
+    if (!(t = _timers[timerid]) || t->pid != myst_getpid())
+        return NULL;
+
+    return t;
+}
+
+/* claim the lowest free id (with _mutex held) */
+static int _alloc_id(posix_timer_t* t)
+{
+    while (_free < _capacity && _timers[_free])
+        _free++;
+
+    if (_free == _capacity)
+    {
+        size_t capacity = _capacity ? _capacity * 2 : 64;
+        posix_timer_t** timers;
+
+        if (_capacity == MAX_TIMERS)
+            return -EAGAIN;
+
+        if (capacity > MAX_TIMERS)
+            capacity = MAX_TIMERS;
+
+        if (!(timers = realloc(_timers, capacity * sizeof(posix_timer_t*))))
+            return -EAGAIN;
+
+        memset(timers + _capacity, 0, (capacity - _capacity) * sizeof(t));
+        _timers = timers;
+        _capacity = capacity;
+    }
+
+    t->id = (int)_free;
+    _timers[_free++] = t;
+    return t->id;
+}
+
+/* release an id (with _mutex held) */
+static void _free_timer(posix_timer_t* t)
+{
+    myst_timer_cancel(&t->timer);
+    _timers[t->id] = NULL;
+
+    if ((size_t)t->id < _free)
+        _free = t->id;
+
+    free(t);
+}
+
+static long _now(clockid_t clockid)
+{
+    struct timespec ts;
+
+    if (myst_syscall_clock_gettime(clockid, &ts) != 0)
+        return 0;
+
+    return ts.tv_sec * NANO_IN_SECOND + ts.tv_nsec;
+}
+
+static int _to_nsec(const struct timespec* ts, long* nsec)
+{
+    if (ts->tv_sec < 0 || ts->tv_nsec < 0 || ts->tv_nsec >= NANO_IN_SECOND)
+        return -EINVAL;
+
+    if (__builtin_smull_overflow(ts->tv_sec, NANO_IN_SECOND, nsec) ||
+        __builtin_saddl_overflow(*nsec, ts->tv_nsec, nsec))
+    {
+        *nsec = LONG_MAX;
+    }
+
+    return 0;
+}
+
+static void _to_timespec(long nsec, struct timespec* ts)
+{
+    ts->tv_sec = nsec / NANO_IN_SECOND;
+    ts->tv_nsec = nsec % NANO_IN_SECOND;
+}
+
+/* get the time to the next expiry and the interval (with _mutex held) */
+static void _get_time(posix_timer_t* t, struct itimerspec* value)
+{
+    long expires = myst_timer_expires(&t->timer);
+    long remaining = 0;
+
+    if (expires)
+    {
+        long now = myst_deadline_now();
+
+        /* an expired timer that has not run yet is all but due */
+        remaining = expires > now ? expires - now : 1;
+    }
+
+    _to_timespec(remaining, &value->it_value);
+    _to_timespec(t->interval, &value->it_interval);
+}
+
+long myst_syscall_timer_create(
+    clockid_t clockid,
+    const struct sigevent* sevp,
+    int* timerid)
+{
+    long ret = 0;
+    const struct ksigevent* sev = (const struct ksigevent*)sevp;
+    myst_thread_t* self = myst_thread_self();
+    posix_timer_t* t = NULL;
+    bool locked = false;
+
+    if (!timerid)
+        ERAISE(-EINVAL);
+
+    /* the alarm clocks are their plain clocks (see kernel/timerfddev.c) */
+    switch (clockid)
+    {
+        case CLOCK_REALTIME:
+        case CLOCK_MONOTONIC:
+        case CLOCK_BOOTTIME:
+        case CLOCK_REALTIME_ALARM:
+        case CLOCK_BOOTTIME_ALARM:
+            break;
+        default:
+            ERAISE(-EINVAL);
+    }
+
+    if (!(t = calloc(1, sizeof(posix_timer_t))))
+        ERAISE(-EAGAIN);
+
+    myst_timer_init(&t->timer, _expire, t);
+    t->pid = self->pid;
+    t->clockid = clockid;
+
+    if (!sev)
+    {
+        /* like Linux: SIGALRM with the timer id */
+        t->signo = SIGALRM;
+    }
+    else
+    {
+        switch (sev->sigev_notify)
+        {
+            case SIGEV_NONE:
+                break;
+            case SIGEV_THREAD_ID:
+                if (!myst_find_thread_by_tid(self->pid, sev->sigev_tid))
+                    ERAISE(-EINVAL);
+                t->tid = sev->sigev_tid;
+                /* fallthrough */
+            case SIGEV_SIGNAL:
+                if (sev->sigev_signo <= 0 || sev->sigev_signo >= NSIG)
+                    ERAISE(-EINVAL);
+                t->signo = sev->sigev_signo;
+                t->value = sev->sigev_value;
+                break;
+            default:
+                ERAISE(-EINVAL);
+        }
+    }
+
+    myst_mutex_lock(&_mutex);
+    locked = true;
+
+    ECHECK(_alloc_id(t));
+
+    if (!sev)
+        t->value.sival_int = t->id;
+
+    *timerid = t->id;
+    t = NULL;
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&_mutex);
+
+    if (t)
+        free(t);
+
+    return ret;
+}
+
+long myst_syscall_timer_settime(
+    int timerid,
+    int flags,
+    const struct itimerspec* new_value,
+    struct itimerspec* old_value)
+{
+    long ret = 0;
+    posix_timer_t* t;
+    long value;
+    long interval;
+
+    /* like Linux, flags other than TIMER_ABSTIME are ignored */
+    if (!new_value)
+        ERAISE(-EINVAL);
+
+    ECHECK(_to_nsec(&new_value->it_value, &value));
+    ECHECK(_to_nsec(&new_value->it_interval, &interval));
+
+    myst_mutex_lock(&_mutex);
+
+    if (!(t = _get(timerid)))
+    {
+        myst_mutex_unlock(&_mutex);
+        ERAISE(-EINVAL);
+    }
+
+    if (old_value)
+        _get_time(t, old_value);
+
+    /* the callback does not run again once the timer is cancelled */
+    myst_timer_cancel(&t->timer);
+    t->interval = value ? interval : 0;
+    t->missed = 0;
+    t->absolute = value && (flags & TIMER_ABSTIME) &&
+                  (t->clockid == CLOCK_REALTIME ||
+                   t->clockid == CLOCK_REALTIME_ALARM);
+
+    if (value)
+    {
+        long now = myst_deadline_now();
+        long expires;
+
+        /* the wheel runs on the monotonic clock, so an absolute time is
+         * taken relative to the timer's clock now */
+        if (flags & TIMER_ABSTIME)
+            value = value - _now(t->clockid);
+
+        if (value < 0)
+            value = 0;
+
+        if (__builtin_saddl_overflow(now, value, &expires))
+            expires = LONG_MAX;
+
+        myst_timer_add(&t->timer, expires);
+    }
+
+    myst_mutex_unlock(&_mutex);
+
+done:
+    return ret;
+}
+
+long myst_syscall_timer_gettime(int timerid, struct itimerspec* curr_value)
+{
+    long ret = 0;
+    posix_timer_t* t;
+
+    if (!curr_value)
+        ERAISE(-EINVAL);
+
+    myst_mutex_lock(&_mutex);
+
+    if (!(t = _get(timerid)))
+    {
+        myst_mutex_unlock(&_mutex);
+        ERAISE(-EINVAL);
+    }
+
+    _get_time(t, curr_value);
+    myst_mutex_unlock(&_mutex);
+
+done:
+    return ret;
+}
+
+long myst_syscall_timer_getoverrun(int timerid)
+{
+    long ret;
+    posix_timer_t* t;
+
+    myst_mutex_lock(&_mutex);
+    ret = (t = _get(timerid)) ? t->overrun : -EINVAL;
+    myst_mutex_unlock(&_mutex);
+
+    return ret;
+}
+
+long myst_syscall_timer_delete(int timerid)
+{
+    long ret = 0;
+    posix_timer_t* t;
+
+    myst_mutex_lock(&_mutex);
+
+    if ((t = _get(timerid)))
+        _free_timer(t);
+    else
+        ret = -EINVAL;
+
+    myst_mutex_unlock(&_mutex);
+
+    return ret;
+}
+
+void myst_release_process_timers(pid_t pid)
+{
+    myst_mutex_lock(&_mutex);
+
+    for (size_t i = 0; i < _capacity; i++)
+    {
+        if (_timers[i] && _timers[i]->pid == pid)
+            _free_timer(_timers[i]);
+    }
+
+    myst_mutex_unlock(&_mutex);
+}
+
+void myst_timers_clock_was_set(long delta)
+{
+    const long now = myst_deadline_now();
+
+    myst_mutex_lock(&_mutex);
+
+    for (size_t i = 0; i < _capacity; i++)
+    {
+        posix_timer_t* t = _timers[i];
+        long expires;
+
+        if (!t || !t->absolute || !(expires = myst_timer_expires(&t->timer)))
+            continue;
+
+        /* the expiry is as far from the clock as it was, so it may now be
+         * in the past (and count the periods that the clock skipped as
+         * overruns) */
+        if (__builtin_ssubl_overflow(expires, delta, &expires))
+            expires = delta < 0 ? LONG_MAX : now;
+
+        if (expires <= 0)
+            expires = now;
+
+        myst_timer_add(&t->timer, expires);
+    }
+
+    myst_mutex_unlock(&_mutex);
+}
@@ -0,0 +1,761 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <poll.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/timerfd.h>
+
+#include <myst/clock.h>
+#include <myst/cond.h>
+#include <myst/deadline.h>
+#include <myst/eraise.h>
+#include <myst/id.h>
+#include <myst/ioctl.h>
+#include <myst/list.h>
+#include <myst/mutex.h>
+#include <myst/syscall.h>
+#include <myst/tcall.h>
+#include <myst/thread.h>
+#include <myst/timerfddev.h>
+#include <myst/timerwheel.h>
+
+/*
+**==============================================================================
+**
+** Timer file descriptors:
+**
+** Whatever its clock, a timer runs on the monotonic clock of the timer wheel
+** (see kernel/timerwheel.c), and its expirations are counted when it is read
+** or polled rather than as they happen: the wheel only ends the poll() waits
+** when the timer is due. An absolute timer of CLOCK_REALTIME moves with the
+** clock when clock_settime() sets it, and one set with TFD_TIMER_CANCEL_ON_SET
+** becomes readable so that the next read fails with ECANCELED. The enclave is
+** never suspended, so the alarm clocks are their plain clocks.
+**
+**==============================================================================
+*/
+
+#define MAGIC 0x54fd2b1e
+
+/* what the descriptors made by dup() share */
+typedef struct description
+{
+    /* the links of the _realtime list */
+    struct description* prev;
+    struct description* next;
+
+    myst_mutex_t mutex;
+
+    /* broadcast when the timer is set or cancelled */
+    myst_cond_t cond;
+
+    size_t refs;
+    clockid_t clockid;
+    int flags; /* O_NONBLOCK */
+
+    /* the next expiry (CLOCK_MONOTONIC nanoseconds, 0 when disarmed) */
+    long expires;
+    long interval;
+
+    /* the expirations that were not read */
+    uint64_t ticks;
+
+    /* set with TFD_TIMER_ABSTIME on a realtime clock */
+    bool absolute;
+
+    /* set with TFD_TIMER_CANCEL_ON_SET (as well) */
+    bool cancel_on_set;
+
+    /* the realtime clock was set since the last read */
+    bool canceled;
+
+    /* whether the description is in the _realtime list */
+    bool listed;
+
+    /* ends the poll() waits when the timer is due */
+    myst_timer_t timer;
+} description_t;
+
+struct myst_timerfd
+{
+    uint32_t magic;
+    int fdflags; /* FD_CLOEXEC */
+    description_t* desc;
+};
+
+/* the descriptions that myst_timerfd_clock_was_set() concerns (the mutex is
+ * taken before that of a description) */
+static myst_list_t _realtime;
+static myst_mutex_t _realtime_mutex;
+
+MYST_INLINE bool _valid_timerfd(const myst_timerfd_t* timerfd)
+{
+    return timerfd && timerfd->magic == MAGIC && timerfd->desc;
+}
+
+MYST_INLINE bool _is_realtime(clockid_t clockid)
+{
+    return clockid == CLOCK_REALTIME || clockid == CLOCK_REALTIME_ALARM;
+}
+
+static int _to_nsec(const struct timespec* ts, long* nsec)
+{
+    if (ts->tv_sec < 0 || ts->tv_nsec < 0 || ts->tv_nsec >= NANO_IN_SECOND)
+        return -EINVAL;
+
+    if (__builtin_smull_overflow(ts->tv_sec, NANO_IN_SECOND, nsec) ||
+        __builtin_saddl_overflow(*nsec, ts->tv_nsec, nsec))
+    {
+        *nsec = LONG_MAX;
+    }
+
+    return 0;
+}
+
+static void _to_timespec(long nsec, struct timespec* ts)
+{
+    ts->tv_sec = nsec / NANO_IN_SECOND;
+    ts->tv_nsec = nsec % NANO_IN_SECOND;
+}
+
+static long _now(clockid_t clockid)
+{
+    struct timespec ts;
+
+    if (myst_syscall_clock_gettime(clockid, &ts) != 0)
+        return 0;
+
+    return ts.tv_sec * NANO_IN_SECOND + ts.tv_nsec;
+}
+
+/* runs on the timer thread when the timer is due */
+static long _expire(myst_timer_t* timer, long now)
+{
+    (void)timer;
+    (void)now;
+
+    /* a blocked read waits for the expiry by itself */
+    myst_tcall_poll_wake();
+    return 0;
+}
+
+/* count the expirations up to now (with desc->mutex held) */
+static void _update(description_t* desc, long now)
+{
+    long n = 1;
+
+    if (!desc->expires || now < desc->expires)
+        return;
+
+    if (desc->interval)
+    {
+        long next;
+
+        n += (now - desc->expires) / desc->interval;
+
+        if (__builtin_smull_overflow(n, desc->interval, &next) ||
+            __builtin_saddl_overflow(desc->expires, next, &next))
+        {
+            next = LONG_MAX;
+        }
+
+        desc->expires = next;
+    }
+    else
+    {
+        desc->expires = 0;
+    }
+
+    desc->ticks += (uint64_t)n;
+}
+
+/* have the wheel end the poll() waits at the next expiry that is not
+ * counted yet (with desc->mutex held) */
+static void _arm(description_t* desc)
+{
+    if (desc->expires && !desc->ticks && !desc->canceled)
+        myst_timer_add(&desc->timer, desc->expires);
+    else
+        myst_timer_cancel(&desc->timer);
+}
+
+/* get the time to the next expiry and the interval (with desc->mutex held
+ * and the expirations counted) */
+static void _get_time(description_t* desc, long now, struct itimerspec* value)
+{
+    long remaining = 0;
+
+    if (desc->expires)
+        remaining = desc->expires > now ? desc->expires - now : 1;
+
+    _to_timespec(remaining, &value->it_value);
+    _to_timespec(desc->interval, &value->it_interval);
+}
+
+static int _td_create(
+    myst_timerfddev_t* timerfddev,
+    clockid_t clockid,
+    int flags,
+    myst_timerfd_t** timerfd_out)
+{
+    int ret = 0;
+    myst_timerfd_t* timerfd = NULL;
+    description_t* desc = NULL;
+
+    if (timerfd_out)
+        *timerfd_out = NULL;
+
+    if (!timerfddev || !timerfd_out)
+        ERAISE(-EINVAL);
+
+    switch (clockid)
+    {
+        case CLOCK_REALTIME:
+        case CLOCK_MONOTONIC:
+        case CLOCK_BOOTTIME:
+        case CLOCK_REALTIME_ALARM:
+        case CLOCK_BOOTTIME_ALARM:
+            break;
+        default:
+            ERAISE(-EINVAL);
+    }
+
+    if (flags & ~(TFD_NONBLOCK | TFD_CLOEXEC))
+        ERAISE(-EINVAL);
+
+    if (!(timerfd = calloc(1, sizeof(myst_timerfd_t))))
+        ERAISE(-ENOMEM);
+
+    if (!(desc = calloc(1, sizeof(description_t))))
+        ERAISE(-ENOMEM);
+
+    desc->refs = 1;
+    desc->clockid = clockid;
+    desc->flags = flags & O_NONBLOCK;
+    myst_timer_init(&desc->timer, _expire, desc);
+
+    timerfd->magic = MAGIC;
+    timerfd->desc = desc;
+    desc = NULL;
+
+    if (flags & TFD_CLOEXEC)
+        timerfd->fdflags = FD_CLOEXEC;
+
+    *timerfd_out = timerfd;
+    timerfd = NULL;
+
+done:
+
+    if (desc)
+        free(desc);
+
+    if (timerfd)
+        free(timerfd);
+
+    return ret;
+}
+
+static int _td_settime(
+    myst_timerfddev_t* timerfddev,
+    myst_timerfd_t* timerfd,
+    int flags,
+    const struct itimerspec* new_value,
+    struct itimerspec* old_value)
+{
+    int ret = 0;
+    const int mask = TFD_TIMER_ABSTIME | TFD_TIMER_CANCEL_ON_SET;
+    description_t* desc;
+    bool realtime_abs;
+    long value;
+    long interval;
+    long now;
+
+    if (!timerfddev || !_valid_timerfd(timerfd))
+        ERAISE(-EBADF);
+
+    if (flags & ~mask)
+        ERAISE(-EINVAL);
+
+    if (!new_value)
+        ERAISE(-EFAULT);
+
+    ECHECK(_to_nsec(&new_value->it_value, &value));
+    ECHECK(_to_nsec(&new_value->it_interval, &interval));
+
+    desc = timerfd->desc;
+    realtime_abs = _is_realtime(desc->clockid) && (flags & TFD_TIMER_ABSTIME);
+
+    myst_mutex_lock(&_realtime_mutex);
+    myst_mutex_lock(&desc->mutex);
+
+    now = myst_deadline_now();
+    _update(desc, now);
+
+    if (old_value)
+        _get_time(desc, now, old_value);
+
+    desc->expires = 0;
+    desc->interval = 0;
+
+    if (value)
+    {
+        /* the wheel runs on the monotonic clock, so an absolute time is
+         * taken relative to the timer's clock now */
+        if (flags & TFD_TIMER_ABSTIME)
+            value = value - _now(desc->clockid);
+
+        if (value < 0)
+            value = 0;
+
+        if (__builtin_saddl_overflow(now, value, &desc->expires))
+            desc->expires = LONG_MAX;
+
+        desc->interval = interval;
+    }
+
+    desc->ticks = 0;
+    desc->canceled = false;
+    desc->absolute = realtime_abs && desc->expires;
+    desc->cancel_on_set = realtime_abs && (flags & TFD_TIMER_CANCEL_ON_SET);
+
+    /* only the timers that the realtime clock concerns are listed */
+    if ((desc->absolute || desc->cancel_on_set) != desc->listed)
+    {
+        if (desc->listed)
+            myst_list_remove(&_realtime, (myst_list_node_t*)desc);
+        else
+            myst_list_append(&_realtime, (myst_list_node_t*)desc);
+
+        desc->listed = !desc->listed;
+    }
+
+    _arm(desc);
+
+    /* a blocked read waits for the new expiry */
+    myst_cond_broadcast(&desc->cond, SIZE_MAX);
+
+    myst_mutex_unlock(&desc->mutex);
+    myst_mutex_unlock(&_realtime_mutex);
+
+done:
+    return ret;
+}
+
+static int _td_gettime(
+    myst_timerfddev_t* timerfddev,
+    myst_timerfd_t* timerfd,
+    struct itimerspec* curr_value)
+{
+    int ret = 0;
+    description_t* desc;
+    long now;
+
+    if (!timerfddev || !_valid_timerfd(timerfd))
+        ERAISE(-EBADF);
+
+    if (!curr_value)
+        ERAISE(-EFAULT);
+
+    desc = timerfd->desc;
+    myst_mutex_lock(&desc->mutex);
+
+    now = myst_deadline_now();
+    _update(desc, now);
+    _get_time(desc, now, curr_value);
+
+    myst_mutex_unlock(&desc->mutex);
+
+done:
+    return ret;
+}
+
+static ssize_t _td_read(
+    myst_timerfddev_t* timerfddev,
+    myst_timerfd_t* timerfd,
+    void* buf,
+    size_t count)
+{
+    ssize_t ret = 0;
+    myst_thread_t* self = myst_thread_self();
+    description_t* desc;
+    bool locked = false;
+    uint64_t ticks;
+
+    if (!timerfddev || !_valid_timerfd(timerfd))
+        ERAISE(-EBADF);
+
+    if (count < sizeof(uint64_t))
+        ERAISE(-EINVAL);
+
+    if (!buf)
+        ERAISE(-EFAULT);
+
+    desc = timerfd->desc;
+    myst_mutex_lock(&desc->mutex);
+    locked = true;
+
+    for (;;)
+    {
+        myst_deadline_t deadline = {MYST_DEADLINE_NEVER};
+
+        _update(desc, myst_deadline_now());
+
+        /* like Linux, the expirations so far are lost */
+        if (desc->canceled)
+        {
+            desc->canceled = false;
+            desc->ticks = 0;
+            _arm(desc);
+            ERAISE(-ECANCELED);
+        }
+
+        if (desc->ticks)
+            break;
+
+        if (desc->flags & O_NONBLOCK)
+            ERAISE(-EAGAIN);
+
+        if (desc->expires)
+            deadline.nsec = desc->expires;
+
+        /* let the process be killed while the thread waits */
+        self->signal.cond_wait = &desc->cond;
+        myst_cond_wait_deadline(&desc->cond, &desc->mutex, &deadline);
+        self->signal.cond_wait = NULL;
+
+        if (self->signal.pending & ~self->signal.mask)
+            ERAISE(-EINTR);
+    }
+
+    ticks = desc->ticks;
+    desc->ticks = 0;
+    _arm(desc);
+
+    memcpy(buf, &ticks, sizeof(ticks));
+    ret = sizeof(ticks);
+
+done:
+
+    if (locked)
+        myst_mutex_unlock(&desc->mutex);
+
+    return ret;
+}
+
+static ssize_t _td_write(
+    myst_timerfddev_t* timerfddev,
+    myst_timerfd_t* timerfd,
+    const void* buf,
+    size_t count)
+{
+    (void)timerfddev;
+    (void)timerfd;
+    (void)buf;
+    (void)count;
+    return -EINVAL;
+}
+
+static ssize_t _td_readv(
+    myst_timerfddev_t* timerfddev,
+    myst_timerfd_t* timerfd,
+    const struct iovec* iov,
+    int iovcnt)
+{
+    ssize_t ret = 0;
+
+    if (!timerfddev || !_valid_timerfd(timerfd))
+        ERAISE(-EBADF);
+
+    ret = myst_fdops_readv(&timerfddev->fdops, timerfd, iov, iovcnt);
+    ECHECK(ret);
+
+done:
+    return ret;
+}
+
+static ssize_t _td_writev(
+    myst_timerfddev_t* timerfddev,
+    myst_timerfd_t* timerfd,
+    const struct iovec* iov,
+    int iovcnt)
+{
+    (void)timerfddev;
+    (void)timerfd;
+    (void)iov;
+    (void)iovcnt;
+    return -EINVAL;
+}
+
+static int _td_fstat(
+    myst_timerfddev_t* timerfddev,
+    myst_timerfd_t* timerfd,
+    struct stat* statbuf)
+{
+    int ret = 0;
+    struct stat buf;
+
+    if (!timerfddev || !_valid_timerfd(timerfd) || !statbuf)
+        ERAISE(-EINVAL);
+
+    /* an anonymous inode, as on Linux */
+    memset(&buf, 0, sizeof(buf));
+    buf.st_mode = S_IRUSR | S_IWUSR;
+    buf.st_nlink = 1;
+    buf.st_uid = MYST_DEFAULT_UID;
+    buf.st_gid = MYST_DEFAULT_GID;
+    buf.st_blksize = 4096;
+
+    *statbuf = buf;
+
+done:
+    return ret;
+}
+
+static int _td_fcntl(
+    myst_timerfddev_t* timerfddev,
+    myst_timerfd_t* timerfd,
+    int cmd,
+    long arg)
+{
+    int ret = 0;
+    description_t* desc;
+
+    if (!timerfddev || !_valid_timerfd(timerfd))
+        ERAISE(-EINVAL);
+
+    desc = timerfd->desc;
+
+    switch (cmd)
+    {
+        case F_SETFD:
+        {
+            if (arg != FD_CLOEXEC && arg != 0)
+                ERAISE(-EINVAL);
+
+            timerfd->fdflags = arg;
+            goto done;
+        }
+        case F_GETFD:
+        {
+            ret = timerfd->fdflags;
+            goto done;
+        }
+        case F_SETFL:
+        {
+            myst_mutex_lock(&desc->mutex);
+            desc->flags = arg & O_NONBLOCK;
+            myst_mutex_unlock(&desc->mutex);
+            goto done;
+        }
+        case F_GETFL:
+        {
+            ret = O_RDWR | desc->flags;
+            goto done;
+        }
+        default:
+        {
+            ERAISE(-ENOTSUP);
+        }
+    }
+
+done:
+    return ret;
+}
+
+static int _td_ioctl(
+    myst_timerfddev_t* timerfddev,
+    myst_timerfd_t* timerfd,
+    unsigned long request,
+    long arg)
+{
+    int ret = 0;
+
+    (void)request;
+    (void)arg;
+
+    if (!timerfddev || !_valid_timerfd(timerfd))
+        ERAISE(-EBADF);
+
+    ERAISE(-ENOTTY);
+
+done:
+    return ret;
+}
+
+static int _td_dup(
+    myst_timerfddev_t* timerfddev,
+    const myst_timerfd_t* timerfd,
+    myst_timerfd_t** timerfd_out)
+{
+    int ret = 0;
+    myst_timerfd_t* new_timerfd;
+
+    if (timerfd_out)
+        *timerfd_out = NULL;
+
+    if (!timerfddev || !_valid_timerfd(timerfd) || !timerfd_out)
+        ERAISE(-EINVAL);
+
+    if (!(new_timerfd = calloc(1, sizeof(myst_timerfd_t))))
+        ERAISE(-ENOMEM);
+
+    *new_timerfd = *timerfd;
+
+    /* file descriptor flags are not propagated */
+    new_timerfd->fdflags = 0;
+
+    myst_mutex_lock(&timerfd->desc->mutex);
+    timerfd->desc->refs++;
+    myst_mutex_unlock(&timerfd->desc->mutex);
+
+    *timerfd_out = new_timerfd;
+
+done:
+    return ret;
+}
+
+static int _td_close(myst_timerfddev_t* timerfddev, myst_timerfd_t* timerfd)
+{
+    int ret = 0;
+    description_t* desc;
+    bool release;
+
+    if (!timerfddev || !_valid_timerfd(timerfd))
+        ERAISE(-EBADF);
+
+    desc = timerfd->desc;
+
+    myst_mutex_lock(&_realtime_mutex);
+    myst_mutex_lock(&desc->mutex);
+
+    if ((release = --desc->refs == 0) && desc->listed)
+        myst_list_remove(&_realtime, (myst_list_node_t*)desc);
+
+    myst_mutex_unlock(&desc->mutex);
+    myst_mutex_unlock(&_realtime_mutex);
+
+    if (release)
+    {
+        /* the callback does not run again once the timer is cancelled */
+        myst_timer_cancel(&desc->timer);
+        memset(desc, 0, sizeof(description_t));
+        free(desc);
+    }
+
+    memset(timerfd, 0, sizeof(myst_timerfd_t));
+    free(timerfd);
+
+done:
+    return ret;
+}
+
+static int _td_target_fd(myst_timerfddev_t* timerfddev, myst_timerfd_t* timerfd)
+{
+    int ret = 0;
+
+    if (!timerfddev || !_valid_timerfd(timerfd))
+        ERAISE(-EINVAL);
+
+    ret = -ENOTSUP;
+
+done:
+    return ret;
+}
+
+static int _td_get_events(
+    myst_timerfddev_t* timerfddev,
+    myst_timerfd_t* timerfd)
+{
+    int ret = 0;
+    description_t* desc;
+
+    if (!timerfddev || !_valid_timerfd(timerfd))
+        ERAISE(-EINVAL);
+
+    desc = timerfd->desc;
+    myst_mutex_lock(&desc->mutex);
+
+    _update(desc, myst_deadline_now());
+
+    if (desc->ticks || desc->canceled)
+        ret = POLLIN;
+
+    myst_mutex_unlock(&desc->mutex);
+
+done:
+    return ret;
+}
+
+void myst_timerfd_clock_was_set(long delta)
+{
+    const long now = myst_deadline_now();
+
+    myst_mutex_lock(&_realtime_mutex);
+
+    for (description_t* d = (description_t*)_realtime.head; d; d = d->next)
+    {
+        myst_mutex_lock(&d->mutex);
+
+        /* what expired by the old time stays expired */
+        _update(d, now);
+
+        /* the expiry is as far from the clock as it was, so it may now be
+         * in the past (and count the periods that the clock skipped) */
+        if (d->absolute && d->expires)
+        {
+            if (__builtin_ssubl_overflow(d->expires, delta, &d->expires))
+                d->expires = delta < 0 ? LONG_MAX : 1;
+
+            if (d->expires <= 0)
+                d->expires = 1;
+        }
+
+        if (d->cancel_on_set)
+            d->canceled = true;
+
+        _arm(d);
+        myst_cond_broadcast(&d->cond, SIZE_MAX);
+
+        myst_mutex_unlock(&d->mutex);
+    }
+
+    myst_mutex_unlock(&_realtime_mutex);
+
+    /* the cancelled timers are readable */
+    myst_tcall_poll_wake();
+}
+
+/* no requests: each fails with ENOTTY */
+static const myst_ioctl_table_t _ioctls = MYST_IOCTL_TABLE_NONE("timerfd");
+
+extern myst_timerfddev_t* myst_timerfddev_get(void)
+{
+    // clang-format-off
+    static myst_timerfddev_t _timerfddev = {
+        {
+            .fd_read = (void*)_td_read,
+            .fd_write = (void*)_td_write,
+            .fd_readv = (void*)_td_readv,
+            .fd_writev = (void*)_td_writev,
+            .fd_fstat = (void*)_td_fstat,
+            .fd_fcntl = (void*)_td_fcntl,
+            .fd_ioctl = (void*)_td_ioctl,
+            .fd_ioctls = &_ioctls,
+            .fd_dup = (void*)_td_dup,
+            .fd_close = (void*)_td_close,
+            .fd_target_fd = (void*)_td_target_fd,
+            .fd_get_events = (void*)_td_get_events,
+        },
+        .td_create = _td_create,
+        .td_settime = _td_settime,
+        .td_gettime = _td_gettime,
+    };
+    // clang-format-on
+
+    return &_timerfddev;
+}
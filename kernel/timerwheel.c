@@ -0,0 +1,323 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <stdint.h>
+
+#include <myst/cond.h>
+#include <myst/deadline.h>
+#include <myst/mutex.h>
+#include <myst/thread.h>
+#include <myst/timerwheel.h>
+
+/* A hierarchical timing wheel: level 0 has a slot for each of the next 64
+ * ticks, level 1 a slot for each of the next 64 laps of level 0 and so on.
+ * Adding and cancelling a timer are O(1), and at the start of each lap of a
+ * level the timers of the next slot of the level above are spread over it.
+ * The timer threads wait for the earliest occupied slot (rather than for
+ * each timer) and skip over the empty ones. */
+#define LEVEL_BITS 6
+#define LEVEL_SIZE (1 << LEVEL_BITS)
+#define LEVEL_MASK (LEVEL_SIZE - 1)
+#define NUM_LEVELS 6
+
+/* the furthest tick the wheel holds (about two years): later timers wait in
+ * the last slot and are put back when it comes round */
+#define MAX_DELTA ((1UL << (LEVEL_BITS * NUM_LEVELS)) - 1)
+
+typedef struct wheel
+{
+    myst_mutex_t mutex;
+    myst_cond_t cond;
+    bool started;
+
+    /* the last tick that was run */
+    uint64_t tick;
+
+    /* the occupied slots of each level */
+    uint64_t occupied[NUM_LEVELS];
+    myst_timer_t* slots[NUM_LEVELS * LEVEL_SIZE];
+    size_t count;
+
+    /* when the timer threads wake up next */
+    long wait_until;
+} wheel_t;
+
+static wheel_t _wheel;
+
+static void _start(void)
+{
+    if (!_wheel.started)
+    {
+        _wheel.tick = (uint64_t)myst_deadline_now() / MYST_TIMER_TICK;
+        _wheel.wait_until = MYST_DEADLINE_NEVER;
+        _wheel.started = true;
+    }
+}
+
+/* put the timer in the slot for its tick (but not before the min tick) */
+static void _insert(myst_timer_t* timer, uint64_t min)
+{
+    uint64_t expires = timer->expires > 0 ? (uint64_t)timer->expires : 0;
+    uint64_t tick = expires / MYST_TIMER_TICK;
+    uint64_t delta;
+    int level = 0;
+    int slot;
+
+    /* round up so that the timer does not run early */
+    if (expires % MYST_TIMER_TICK)
+        tick++;
+
+    if (tick < min)
+        tick = min;
+
+    if ((delta = tick - _wheel.tick) > MAX_DELTA)
+    {
+        delta = MAX_DELTA;
+        tick = _wheel.tick + delta;
+    }
+
+    while (delta >> (LEVEL_BITS * (level + 1)))
+        level++;
+
+    slot = (tick >> (LEVEL_BITS * level)) & LEVEL_MASK;
+    _wheel.occupied[level] |= 1UL << slot;
+    slot += level * LEVEL_SIZE;
+
+    timer->slot = slot;
+    timer->prev = NULL;
+    timer->next = _wheel.slots[slot];
+
+    if (timer->next)
+        timer->next->prev = timer;
+
+    _wheel.slots[slot] = timer;
+    _wheel.count++;
+}
+
+static void _remove(myst_timer_t* timer)
+{
+    int slot = timer->slot;
+
+    if (timer->prev)
+        timer->prev->next = timer->next;
+    else
+        _wheel.slots[slot] = timer->next;
+
+    if (timer->next)
+        timer->next->prev = timer->prev;
+
+    if (!_wheel.slots[slot])
+        _wheel.occupied[slot / LEVEL_SIZE] &= ~(1UL << (slot % LEVEL_SIZE));
+
+    timer->prev = NULL;
+    timer->next = NULL;
+    timer->slot = -1;
+    _wheel.count--;
+}
+
+/* take all the timers out of a slot (returning them as a list) */
+static myst_timer_t* _detach(int level, int index)
+{
+    int slot = level * LEVEL_SIZE + index;
+    myst_timer_t* list = _wheel.slots[slot];
+
+    _wheel.slots[slot] = NULL;
+    _wheel.occupied[level] &= ~(1UL << index);
+
+    for (myst_timer_t* p = list; p; p = p->next)
+    {
+        p->slot = -1;
+        _wheel.count--;
+    }
+
+    return list;
+}
+
+/* the next tick that has a slot to run or spread out (UINT64_MAX if none) */
+static uint64_t _next_tick(void)
+{
+    uint64_t next = UINT64_MAX;
+
+    for (int level = 0; level < NUM_LEVELS; level++)
+    {
+        int shift = LEVEL_BITS * level;
+        uint64_t lap = _wheel.tick >> shift;
+        uint64_t bits = _wheel.occupied[level];
+        int from = (int)((lap + 1) & LEVEL_MASK);
+        uint64_t tick;
+
+        if (!bits)
+            continue;
+
+        /* rotate the bits so that the slot after the current one is first */
+        if (from)
+            bits = (bits >> from) | (bits << (LEVEL_SIZE - from));
+
+        tick = (lap + 1 + __builtin_ctzl(bits)) << shift;
+
+        if (tick < next)
+            next = tick;
+    }
+
+    return next;
+}
+
+static void _run_tick(long now)
+{
+    uint64_t tick = _wheel.tick;
+    myst_timer_t* list;
+    myst_timer_t* next;
+
+    /* spread out the slots of the levels whose lap starts with this tick
+     * (including the timers that are due now) */
+    for (int level = 1; level < NUM_LEVELS; level++)
+    {
+        int shift = LEVEL_BITS * level;
+
+        if (tick & ((1UL << shift) - 1))
+            break;
+
+        list = _detach(level, (tick >> shift) & LEVEL_MASK);
+
+        for (myst_timer_t* p = list; p; p = next)
+        {
+            next = p->next;
+            _insert(p, tick);
+        }
+    }
+
+    list = _detach(0, tick & LEVEL_MASK);
+
+    for (myst_timer_t* p = list; p; p = next)
+    {
+        long expires;
+
+        next = p->next;
+        p->prev = NULL;
+        p->next = NULL;
+
+        if ((expires = p->callback(p, now)))
+        {
+            p->expires = expires;
+            _insert(p, _wheel.tick + 1);
+        }
+    }
+}
+
+/* run the ticks up to now */
+static void _advance(long now)
+{
+    uint64_t target = (uint64_t)now / MYST_TIMER_TICK;
+    uint64_t tick;
+
+    while (_wheel.count && (tick = _next_tick()) <= target)
+    {
+        _wheel.tick = tick;
+        _run_tick(now);
+    }
+
+    if (target > _wheel.tick)
+        _wheel.tick = target;
+}
+
+void myst_timer_init(
+    myst_timer_t* timer,
+    myst_timer_callback_t callback,
+    void* arg)
+{
+    timer->prev = NULL;
+    timer->next = NULL;
+    timer->expires = 0;
+    timer->slot = -1;
+    timer->callback = callback;
+    timer->arg = arg;
+}
+
+void myst_timer_add(myst_timer_t* timer, long expires)
+{
+    assert(timer->callback);
+
+    myst_mutex_lock(&_wheel.mutex);
+    {
+        _start();
+
+        if (timer->slot >= 0)
+            _remove(timer);
+
+        /* an empty wheel may be far behind (nothing has run it) */
+        if (!_wheel.count)
+        {
+            uint64_t tick = (uint64_t)myst_deadline_now() / MYST_TIMER_TICK;
+
+            if (tick > _wheel.tick)
+                _wheel.tick = tick;
+        }
+
+        timer->expires = expires;
+        _insert(timer, _wheel.tick + 1);
+
+        /* wake the timer threads if they would sleep past this timer */
+        if (expires < _wheel.wait_until)
+        {
+            _wheel.wait_until = expires;
+            myst_cond_broadcast(&_wheel.cond, SIZE_MAX);
+        }
+    }
+    myst_mutex_unlock(&_wheel.mutex);
+}
+
+bool myst_timer_cancel(myst_timer_t* timer)
+{
+    bool armed;
+
+    myst_mutex_lock(&_wheel.mutex);
+    {
+        if ((armed = timer->slot >= 0))
+            _remove(timer);
+    }
+    myst_mutex_unlock(&_wheel.mutex);
+
+    return armed;
+}
+
+long myst_timer_expires(myst_timer_t* timer)
+{
+    long expires;
+
+    myst_mutex_lock(&_wheel.mutex);
+    expires = timer->slot >= 0 ? timer->expires : 0;
+    myst_mutex_unlock(&_wheel.mutex);
+
+    return expires;
+}
+
+long myst_timer_run(void)
+{
+    myst_thread_t* self = myst_thread_self();
+
+    myst_mutex_lock(&_wheel.mutex);
+    _start();
+
+    for (;;)
+    {
+        myst_deadline_t deadline = {MYST_DEADLINE_NEVER};
+        uint64_t tick;
+
+        _advance(myst_deadline_now());
+
+        if ((tick = _next_tick()) != UINT64_MAX)
+            deadline.nsec = (long)(tick * MYST_TIMER_TICK);
+
+        _wheel.wait_until = deadline.nsec;
+
+        /* let the process be killed while the thread waits */
+        self->signal.cond_wait = &_wheel.cond;
+        myst_cond_wait_deadline(&_wheel.cond, &_wheel.mutex, &deadline);
+        self->signal.cond_wait = NULL;
+    }
+
+    /* unreachable */
+    myst_mutex_unlock(&_wheel.mutex);
+    return 0;
+}
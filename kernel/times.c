@@ -5,6 +5,7 @@
 #include <myst/assume.h>
 #include <myst/clock.h>
 #include <myst/eraise.h>
+#include <myst/metrics.h>
 #include <myst/syscall.h>
 #include <myst/thread.h>
 #include <myst/times.h>
@@ -48,6 +49,7 @@ void myst_times_enter_kernel()
         lapsed_nsecs(current->leave_kernel_ts, current->enter_kernel_ts);
     myst_assume(lapsed >= 0);
     __atomic_fetch_add(&process_times.tms_utime, lapsed, __ATOMIC_SEQ_CST);
+    __atomic_fetch_add(&current->rusage.utime, lapsed, __ATOMIC_SEQ_CST);
 }
 
 void myst_times_leave_kernel()
@@ -59,6 +61,14 @@ void myst_times_leave_kernel()
         lapsed_nsecs(current->enter_kernel_ts, current->leave_kernel_ts);
     myst_assume(lapsed > 0);
     __atomic_fetch_add(&process_times.tms_stime, lapsed, __ATOMIC_SEQ_CST);
+    myst_histogram_observe(&myst_metric_syscall_seconds, lapsed);
+
+    /* time spent blocked in the target is not system time of the thread */
+    lapsed -= current->blocked_nsecs;
+    current->blocked_nsecs = 0;
+
+    if (lapsed > 0)
+        __atomic_fetch_add(&current->rusage.stime, lapsed, __ATOMIC_SEQ_CST);
 }
 
 long myst_times_system_time()
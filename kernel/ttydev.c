@@ -10,8 +10,10 @@
 #include <unistd.h>
 
 #include <myst/assume.h>
+#include <myst/console.h>
 #include <myst/eraise.h>
 #include <myst/id.h>
+#include <myst/ioctl.h>
 #include <myst/tcall.h>
 #include <myst/ttydev.h>
 
@@ -101,7 +103,7 @@ static ssize_t _td_write(
     if (count == 0)
         goto done;
 
-    ERAISE(myst_tcall_write_console(tty->fd, buf, count));
+    ERAISE(myst_console_write(tty->fd, buf, count));
 
 done:
     return ret;
@@ -222,31 +224,20 @@ static int _td_ioctl(
     if (!ttydev || !_valid_tty(tty))
         ERAISE(-EBADF);
 
-    if (request == TIOCGWINSZ)
+    switch (request)
     {
-        struct winsize
+        case TIOCGWINSZ:
         {
-            unsigned short int ws_row;
-            unsigned short int ws_col;
-            unsigned short int ws_xpixel;
-            unsigned short int ws_ypixel;
-        };
-        struct winsize* p;
-
-        if (!(p = (struct winsize*)arg))
-            ERAISE(-EINVAL);
-
-        p->ws_row = 24;
-        p->ws_col = 80;
-        p->ws_xpixel = 0;
-        p->ws_ypixel = 0;
-
-        ret = 0;
-        goto done;
-    }
-    else
-    {
-        ERAISE(-ENOTSUP);
+            struct winsize* p = (struct winsize*)arg;
+
+            p->ws_row = 24;
+            p->ws_col = 80;
+            p->ws_xpixel = 0;
+            p->ws_ypixel = 0;
+            break;
+        }
+        default:
+            ERAISE(-ENOTTY);
     }
 
 done:
@@ -329,6 +320,12 @@ done:
     return ret;
 }
 
+static const myst_ioctl_t _ioctl_list[] = {
+    MYST_IOCTL(TIOCGWINSZ, MYST_IOCTL_OUT, struct winsize),
+};
+
+static const myst_ioctl_table_t _ioctls = MYST_IOCTL_TABLE("tty", _ioctl_list);
+
 extern myst_ttydev_t* myst_ttydev_get(void)
 {
     // clang-format-off
@@ -341,6 +338,7 @@ extern myst_ttydev_t* myst_ttydev_get(void)
             .fd_fstat = (void*)_td_fstat,
             .fd_fcntl = (void*)_td_fcntl,
             .fd_ioctl = (void*)_td_ioctl,
+            .fd_ioctls = &_ioctls,
             .fd_dup = (void*)_td_dup,
             .fd_close = (void*)_td_close,
             .fd_target_fd = (void*)_td_target_fd,
@@ -0,0 +1,95 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdint.h>
+#include <string.h>
+
+#include <myst/mmanutils.h>
+#include <myst/uaccess.h>
+
+bool myst_is_user_range(const void* addr, size_t size)
+{
+    return myst_is_mman_range(addr, size);
+}
+
+static bool _check(const void* addr, size_t size, size_t align)
+{
+    if (align > 1 && ((uintptr_t)addr & (align - 1)))
+        return false;
+
+    return myst_is_user_range(addr, size);
+}
+
+static void _poison(void* dst, size_t size)
+{
+#ifdef MYST_DEBUG
+    memset(dst, MYST_UACCESS_POISON, size);
+#else
+    (void)dst;
+    (void)size;
+#endif
+}
+
+long myst_copy_from_user_aligned(
+    void* dst,
+    const void* src,
+    size_t size,
+    size_t align)
+{
+    if (!_check(src, size, align))
+    {
+        _poison(dst, size);
+        return -EFAULT;
+    }
+
+    memcpy(dst, src, size);
+    return 0;
+}
+
+long myst_copy_to_user_aligned(
+    void* dst,
+    const void* src,
+    size_t size,
+    size_t align)
+{
+    if (!_check(dst, size, align))
+        return -EFAULT;
+
+    memcpy(dst, src, size);
+    return 0;
+}
+
+long myst_copy_from_user(void* dst, const void* src, size_t size)
+{
+    return myst_copy_from_user_aligned(dst, src, size, 1);
+}
+
+long myst_copy_to_user(void* dst, const void* src, size_t size)
+{
+    return myst_copy_to_user_aligned(dst, src, size, 1);
+}
+
+long myst_strncpy_from_user(char* dst, const char* src, size_t size)
+{
+    size_t n;
+
+    if (size == 0)
+        return -ENAMETOOLONG;
+
+    /* the string may end well before the end of user memory */
+    for (n = 0; n < size; n++)
+    {
+        if (!myst_is_user_range(src + n, 1))
+        {
+            _poison(dst, size);
+            return -EFAULT;
+        }
+
+        if ((dst[n] = src[n]) == '\0')
+            return (long)n;
+    }
+
+    dst[size - 1] = '\0';
+    return -ENAMETOOLONG;
+}
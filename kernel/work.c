@@ -0,0 +1,278 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <string.h>
+
+#include <myst/cond.h>
+#include <myst/deadline.h>
+#include <myst/mutex.h>
+#include <myst/spinlock.h>
+#include <myst/thread.h>
+#include <myst/time.h>
+#include <myst/work.h>
+
+/*
+**==============================================================================
+**
+** Background work:
+**
+** A queue is a list of work items sorted by when they are due. Whenever a
+** thread enters the kernel it checks the time that the first item of the
+** kernel-entry queue is due (without a lock or a clock read if the queue is
+** empty) and, once that has passed, runs up to MAX_BATCH of the items that
+** are due. One thread runs items at a time: the others go on with their
+** syscalls.
+**
+** As Mystikos cannot preempt a thread in user space, that work only runs
+** when some thread enters the kernel: an application that makes no syscalls
+** for a while delays it.
+**
+** Work that takes too long to run in a syscall of the application (such as
+** generating a quote) goes on the worker queue instead, which only the
+** worker thread runs. The C runtime starts that thread on demand (like the
+** itimer thread) and it takes one of the threads of the enclave; until it
+** starts, the work waits.
+**
+**==============================================================================
+*/
+
+/* the most items that one kernel entry runs */
+#define MAX_BATCH 4
+
+typedef struct queue
+{
+    myst_spinlock_t lock;
+    myst_work_t* head;
+
+    /* when the first item is due (MYST_DEADLINE_NEVER if there is none) */
+    volatile long next;
+
+    /* whether a thread is running items, and which one */
+    volatile int busy;
+    myst_thread_t* runner;
+} queue_t;
+
+/* the items run at kernel entry, and by the worker thread */
+static queue_t _entry = {.next = MYST_DEADLINE_NEVER};
+static queue_t _worker = {.next = MYST_DEADLINE_NEVER};
+
+/* whether there is worker work, and whether the worker thread runs */
+static volatile int _worker_wanted;
+static volatile int _worker_started;
+
+/* the worker thread waits on the condition for the next item */
+static myst_mutex_t _worker_mutex;
+static myst_cond_t _worker_cond;
+
+static queue_t* _queue(myst_work_t* work)
+{
+    return work->worker ? &_worker : &_entry;
+}
+
+/* insert the work in order (with q->lock held) */
+static void _insert(queue_t* q, myst_work_t* work, long when)
+{
+    myst_work_t** p = &q->head;
+
+    while (*p && (*p)->when <= when)
+        p = &(*p)->next;
+
+    work->when = when;
+    work->next = *p;
+    work->queued = true;
+    *p = work;
+
+    __atomic_store_n(&q->next, q->head->when, __ATOMIC_RELEASE);
+}
+
+/* remove the work from the queue (with q->lock held) */
+static void _remove(queue_t* q, myst_work_t* work)
+{
+    for (myst_work_t** p = &q->head; *p; p = &(*p)->next)
+    {
+        if (*p == work)
+        {
+            *p = work->next;
+            break;
+        }
+    }
+
+    work->next = NULL;
+    work->queued = false;
+
+    __atomic_store_n(
+        &q->next,
+        q->head ? q->head->when : MYST_DEADLINE_NEVER,
+        __ATOMIC_RELEASE);
+}
+
+void myst_work_init(
+    myst_work_t* work,
+    const char* name,
+    myst_work_callback_t callback,
+    void* arg)
+{
+    memset(work, 0, sizeof(myst_work_t));
+    work->name = name;
+    work->callback = callback;
+    work->arg = arg;
+}
+
+void myst_work_init_worker(
+    myst_work_t* work,
+    const char* name,
+    myst_work_callback_t callback,
+    void* arg)
+{
+    myst_work_init(work, name, callback, arg);
+    work->worker = true;
+    __atomic_store_n(&_worker_wanted, 1, __ATOMIC_RELEASE);
+}
+
+void myst_work_queue(myst_work_t* work, long when)
+{
+    queue_t* q = _queue(work);
+
+    myst_spin_lock(&q->lock);
+
+    if (work->queued)
+        _remove(q, work);
+
+    work->cancelled = false;
+    _insert(q, work, when);
+
+    myst_spin_unlock(&q->lock);
+
+    /* the worker thread may be waiting for a later item */
+    if (work->worker)
+    {
+        myst_mutex_lock(&_worker_mutex);
+        myst_cond_signal(&_worker_cond);
+        myst_mutex_unlock(&_worker_mutex);
+    }
+}
+
+bool myst_work_cancel(myst_work_t* work)
+{
+    queue_t* q = _queue(work);
+    bool queued;
+
+    myst_spin_lock(&q->lock);
+
+    if ((queued = work->queued))
+        _remove(q, work);
+
+    work->cancelled = true;
+
+    myst_spin_unlock(&q->lock);
+
+    /* wait for the callback (which rarely runs for long) to return */
+    if (q->runner != myst_thread_self())
+    {
+        while (__atomic_load_n(&work->running, __ATOMIC_ACQUIRE))
+            myst_sleep_msec(1);
+    }
+
+    return queued;
+}
+
+/* take the first item if it is due (with q->lock held) */
+static myst_work_t* _take(queue_t* q, long now)
+{
+    myst_work_t* work = q->head;
+
+    if (!work || work->when > now)
+        return NULL;
+
+    _remove(q, work);
+    work->running = true;
+    return work;
+}
+
+/* run up to MAX_BATCH items that are due, unless another thread is */
+static void _run(queue_t* q, long now)
+{
+    if (__atomic_exchange_n(&q->busy, 1, __ATOMIC_ACQUIRE))
+        return;
+
+    q->runner = myst_thread_self();
+
+    for (size_t i = 0; i < MAX_BATCH; i++)
+    {
+        myst_work_t* work;
+        long when;
+
+        myst_spin_lock(&q->lock);
+        work = _take(q, now);
+        myst_spin_unlock(&q->lock);
+
+        if (!work)
+            break;
+
+        when = work->callback(work, now);
+
+        /* the callback may have queued or cancelled the work itself */
+        myst_spin_lock(&q->lock);
+
+        if (when && !work->queued && !work->cancelled)
+            _insert(q, work, when);
+
+        __atomic_store_n(&work->running, false, __ATOMIC_RELEASE);
+        myst_spin_unlock(&q->lock);
+    }
+
+    q->runner = NULL;
+    __atomic_store_n(&q->busy, 0, __ATOMIC_RELEASE);
+}
+
+void myst_work_run(void)
+{
+    long now;
+
+    if (__atomic_load_n(&_entry.next, __ATOMIC_ACQUIRE) == MYST_DEADLINE_NEVER)
+        return;
+
+    if ((now = myst_deadline_now()) < _entry.next)
+        return;
+
+    _run(&_entry, now);
+}
+
+long myst_work_run_worker(void)
+{
+    myst_thread_t* self = myst_thread_self();
+
+    if (!__atomic_load_n(&_worker_wanted, __ATOMIC_ACQUIRE))
+        return -ENOENT;
+
+    if (__atomic_exchange_n(&_worker_started, 1, __ATOMIC_ACQUIRE))
+        return -EEXIST;
+
+    myst_mutex_lock(&_worker_mutex);
+
+    for (;;)
+    {
+        myst_deadline_t deadline;
+        long now = myst_deadline_now();
+
+        deadline.nsec = __atomic_load_n(&_worker.next, __ATOMIC_ACQUIRE);
+
+        if (deadline.nsec <= now)
+        {
+            myst_mutex_unlock(&_worker_mutex);
+            _run(&_worker, now);
+            myst_mutex_lock(&_worker_mutex);
+            continue;
+        }
+
+        /* let the process be killed while the thread waits */
+        self->signal.cond_wait = &_worker_cond;
+        myst_cond_wait_deadline(&_worker_cond, &_worker_mutex, &deadline);
+        self->signal.cond_wait = NULL;
+    }
+
+    /* unreachable */
+    myst_mutex_unlock(&_worker_mutex);
+    return 0;
+}
@@ -0,0 +1,119 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+
+#include <myst/eraise.h>
+#include <myst/printf.h>
+#include <myst/syscall.h>
+#include <myst/thread.h>
+#include <myst/wxpolicy.h>
+
+/*
+**==============================================================================
+**
+** Write XOR execute:
+**
+** With "WriteXorExecute" in config.json, the kernel fails an mmap() or an
+** mprotect() of the application that asks for memory that is both writable
+** and executable with EACCES (as Linux does under SELinux without execmem),
+** and logs it. Code must then be written to memory that is not executable
+** and made executable (but no longer writable) afterward. The programs in
+** "WriteXorExecuteAllow" (by absolute path, as exec'd) are exempt, for JIT
+** compilers that need such mappings.
+**
+** SGX1 cannot change the protection of enclave pages, so this checks what
+** the application asks for rather than what the hardware enforces: it
+** catches code that makes memory writable and executable, not a write to
+** memory that is executable.
+**
+**==============================================================================
+*/
+
+static bool _enabled;
+static char** _allow;
+static size_t _num_allow;
+
+int myst_wx_setup(const myst_wx_config_t* config)
+{
+    int ret = 0;
+
+    if (!config)
+        ERAISE(-EINVAL);
+
+    if (!config->enabled)
+        goto done;
+
+    if (config->num_allow)
+    {
+        if (!(_allow = calloc(config->num_allow, sizeof(char*))))
+            ERAISE(-ENOMEM);
+
+        for (size_t i = 0; i < config->num_allow; i++)
+        {
+            if (!config->allow[i] || config->allow[i][0] != '/')
+            {
+                myst_eprintf(
+                    "kernel: WriteXorExecuteAllow: not an absolute path: %s\n",
+                    config->allow[i] ? config->allow[i] : "");
+                ERAISE(-EINVAL);
+            }
+
+            if (!(_allow[i] = strdup(config->allow[i])))
+                ERAISE(-ENOMEM);
+
+            _num_allow++;
+        }
+    }
+
+    _enabled = true;
+
+done:
+    return ret;
+}
+
+void myst_wx_exec(myst_thread_t* process, const char* path)
+{
+    process->main.wx_exempt = false;
+
+    for (size_t i = 0; i < _num_allow; i++)
+    {
+        if (strcmp(_allow[i], path) == 0)
+        {
+            process->main.wx_exempt = true;
+            break;
+        }
+    }
+}
+
+long myst_wx_check(long n, const void* addr, size_t length, int prot)
+{
+    myst_thread_t* thread;
+    myst_thread_t* process;
+
+    if (!_enabled || (prot & (PROT_WRITE | PROT_EXEC)) !=
+                         (PROT_WRITE | PROT_EXEC))
+    {
+        return 0;
+    }
+
+    thread = myst_thread_self();
+    process = myst_find_process_thread(thread);
+
+    if (process && process->main.wx_exempt)
+        return 0;
+
+    myst_eprintf(
+        "kernel: W^X: denied %s of %zu writable and executable bytes at %p "
+        "in process %d (%s)\n",
+        syscall_str(n),
+        length,
+        addr,
+        thread->pid,
+        thread->name);
+
+    return -EACCES;
+}
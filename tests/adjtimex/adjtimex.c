@@ -0,0 +1,92 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/time.h>
+#include <sys/timex.h>
+#include <time.h>
+
+static void test_state(void)
+{
+    struct timex tx;
+    struct timeval before;
+    struct timeval after;
+    int r;
+
+    memset(&tx, 0, sizeof(tx));
+    assert(gettimeofday(&before, NULL) == 0);
+    r = adjtimex(&tx);
+    assert(gettimeofday(&after, NULL) == 0);
+
+    assert(r == TIME_OK || r == TIME_ERROR);
+    assert((r == TIME_ERROR) == !!(tx.status & STA_UNSYNC));
+
+    /* the errors are in microseconds and at most 16 seconds */
+    assert(tx.maxerror >= 0 && tx.maxerror <= 16000000);
+    assert(tx.esterror >= 0 && tx.esterror <= 16000000);
+
+    /* the time is the realtime clock */
+    assert(timercmp(&before, &tx.time, <=));
+    assert(timercmp(&tx.time, &after, <=));
+
+    /* clock_adjtime() gives the same answer for the realtime clock */
+    memset(&tx, 0, sizeof(tx));
+    r = clock_adjtime(CLOCK_REALTIME, &tx);
+    assert(r == TIME_OK || r == TIME_ERROR);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_tai(void)
+{
+    struct timex tx;
+    struct timespec utc;
+    struct timespec tai;
+    long diff;
+
+    memset(&tx, 0, sizeof(tx));
+    assert(adjtimex(&tx) >= 0);
+    assert(tx.tai >= 0);
+
+    assert(clock_gettime(CLOCK_REALTIME, &utc) == 0);
+    assert(clock_gettime(CLOCK_TAI, &tai) == 0);
+
+    /* CLOCK_TAI is ahead of CLOCK_REALTIME by the TAI offset */
+    diff = (tai.tv_sec - utc.tv_sec) * 1000000000 + tai.tv_nsec - utc.tv_nsec;
+    assert(diff >= tx.tai * 1000000000L);
+    assert(diff < (tx.tai + 1) * 1000000000L);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_errors(void)
+{
+    struct timex tx;
+
+    /* the enclave cannot discipline the host clock */
+    memset(&tx, 0, sizeof(tx));
+    tx.modes = ADJ_MAXERROR;
+    tx.maxerror = 0;
+    assert(adjtimex(&tx) == -1 && errno == EPERM);
+
+    memset(&tx, 0, sizeof(tx));
+    assert(clock_adjtime(CLOCK_MONOTONIC, &tx) == -1 && errno == EOPNOTSUPP);
+    assert(clock_adjtime(1000, &tx) == -1 && errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_state();
+    test_tai();
+    test_errors();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
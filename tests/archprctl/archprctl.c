@@ -0,0 +1,97 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <sys/personality.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#define ARCH_SET_GS 0x1001
+#define ARCH_SET_FS 0x1002
+#define ARCH_GET_FS 0x1003
+#define ARCH_GET_CPUID 0x1011
+#define ARCH_SET_CPUID 0x1012
+
+static long _arch_prctl(int code, unsigned long addr)
+{
+    return syscall(SYS_arch_prctl, code, addr);
+}
+
+static void* _get_fsbase(void)
+{
+    void* p;
+    __asm__ volatile("mov %%fs:0, %0" : "=r"(p));
+    return p;
+}
+
+void test_fs(void)
+{
+    unsigned long fs = 0;
+
+    /* ARCH_GET_FS reports the thread pointer */
+    assert(_arch_prctl(ARCH_GET_FS, (unsigned long)&fs) == 0);
+    assert((void*)fs == _get_fsbase());
+
+    /* setting the same thread pointer again is harmless */
+    assert(_arch_prctl(ARCH_SET_FS, fs) == 0);
+    assert(_get_fsbase() == (void*)fs);
+
+    /* a thread pointer must point to itself */
+    assert(_arch_prctl(ARCH_SET_FS, (unsigned long)&fs) == -1);
+    assert(errno == EINVAL);
+
+    assert(_arch_prctl(ARCH_GET_FS, 0) == -1);
+    assert(errno == EFAULT);
+
+    assert(_arch_prctl(ARCH_SET_GS, 0) == -1);
+    assert(errno == EPERM);
+
+    assert(_arch_prctl(0x9999, 0) == -1);
+    assert(errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+void test_cpuid(void)
+{
+    assert(_arch_prctl(ARCH_GET_CPUID, 0) == 1);
+
+    /* CPUID faulting is unavailable; leaving CPUID enabled succeeds */
+    assert(_arch_prctl(ARCH_SET_CPUID, 0) == -1);
+    assert(errno == ENODEV);
+    assert(_arch_prctl(ARCH_SET_CPUID, 1) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+void test_personality(void)
+{
+    assert(personality(0xffffffff) == PER_LINUX);
+
+    assert(personality(PER_LINUX | ADDR_NO_RANDOMIZE) == PER_LINUX);
+    assert(personality(0xffffffff) == (PER_LINUX | ADDR_NO_RANDOMIZE));
+
+    /* other execution domains are rejected */
+    assert(personality(PER_SVR4) == -1);
+    assert(errno == EINVAL);
+    assert(personality(0xffffffff) == (PER_LINUX | ADDR_NO_RANDOMIZE));
+
+    assert(personality(PER_LINUX) == (PER_LINUX | ADDR_NO_RANDOMIZE));
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_fs();
+    test_cpuid();
+    test_personality();
+
+    printf("=== passed test (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,309 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+/*
+** Micro-benchmarks for the time and syscall hot paths:
+**
+**     clock     - clock_gettime() on the coarse clocks (which return the
+**                 cached tick value) and on the precise clocks
+**     futex     - round-trip latency of FUTEX_WAKE/FUTEX_WAIT between two
+**                 threads
+**     pipe      - throughput of a pipe between two threads
+**
+** Each benchmark prints one "bench:" line per measurement. Options of the
+** form --max-<name>=<value> or --min-<name>=<value> set a regression
+** threshold; the program fails if a measurement crosses its threshold.
+*/
+
+#include <assert.h>
+#include <pthread.h>
+#include <stdatomic.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <time.h>
+#include <unistd.h>
+
+#define CLOCK_ITERATIONS 1000000
+#define FUTEX_ITERATIONS 10000
+#define PIPE_BYTES (256 * 1024 * 1024)
+#define PIPE_CHUNK (64 * 1024)
+
+#define FUTEX_WAIT_PRIVATE 128
+#define FUTEX_WAKE_PRIVATE 129
+
+typedef struct threshold
+{
+    char name[64];
+    bool is_max; /* fail if above (true) or below (false) */
+    double value;
+} threshold_t;
+
+static threshold_t _thresholds[32];
+static size_t _nthresholds;
+static int _failures;
+
+static uint64_t _now_ns(void)
+{
+    struct timespec ts;
+    int r = clock_gettime(CLOCK_MONOTONIC, &ts);
+
+    /* not inside assert(), which -DNDEBUG removes */
+    assert(r == 0);
+    (void)r;
+    return (uint64_t)ts.tv_sec * 1000000000 + (uint64_t)ts.tv_nsec;
+}
+
+static void _report(const char* name, double value, const char* units)
+{
+    printf("bench: %-24s %12.2f %s\n", name, value, units);
+
+    for (size_t i = 0; i < _nthresholds; i++)
+    {
+        const threshold_t* t = &_thresholds[i];
+
+        if (strcmp(t->name, name) != 0)
+            continue;
+
+        if ((t->is_max && value > t->value) || (!t->is_max && value < t->value))
+        {
+            fprintf(
+                stderr,
+                "bench: %s: %.2f %s is %s the threshold of %.2f\n",
+                name,
+                value,
+                units,
+                t->is_max ? "above" : "below",
+                t->value);
+            _failures++;
+        }
+    }
+}
+
+/*
+**==============================================================================
+**
+** clock
+**
+**==============================================================================
+*/
+
+static void _bench_clock(const char* name, clockid_t clk_id)
+{
+    struct timespec ts;
+    uint64_t t0;
+    uint64_t t1;
+
+    t0 = _now_ns();
+
+    for (size_t i = 0; i < CLOCK_ITERATIONS; i++)
+        clock_gettime(clk_id, &ts);
+
+    t1 = _now_ns();
+
+    _report(name, (double)(t1 - t0) / CLOCK_ITERATIONS, "ns/op");
+}
+
+/*
+**==============================================================================
+**
+** futex
+**
+**==============================================================================
+*/
+
+static _Atomic int _futex_word;
+
+static long _futex(_Atomic int* uaddr, int op, int val)
+{
+    return syscall(SYS_futex, uaddr, op, val, NULL, NULL, 0);
+}
+
+/* wait until the futex word becomes the given value */
+static void _futex_wait_for(int value)
+{
+    int cur;
+
+    while ((cur = atomic_load(&_futex_word)) != value)
+        _futex(&_futex_word, FUTEX_WAIT_PRIVATE, cur);
+}
+
+static void _futex_set(int value)
+{
+    atomic_store(&_futex_word, value);
+    _futex(&_futex_word, FUTEX_WAKE_PRIVATE, 1);
+}
+
+static void* _futex_thread(void* arg)
+{
+    (void)arg;
+
+    for (int i = 0; i < FUTEX_ITERATIONS; i++)
+    {
+        _futex_wait_for(2 * i + 1);
+        _futex_set(2 * i + 2);
+    }
+
+    return NULL;
+}
+
+static void _bench_futex(void)
+{
+    pthread_t thread;
+    uint64_t t0;
+    uint64_t t1;
+    int r;
+
+    atomic_store(&_futex_word, 0);
+    r = pthread_create(&thread, NULL, _futex_thread, NULL);
+    assert(r == 0);
+
+    t0 = _now_ns();
+
+    for (int i = 0; i < FUTEX_ITERATIONS; i++)
+    {
+        _futex_set(2 * i + 1);
+        _futex_wait_for(2 * i + 2);
+    }
+
+    t1 = _now_ns();
+
+    r = pthread_join(thread, NULL);
+    assert(r == 0);
+    (void)r;
+
+    /* one round trip is two wakeups */
+    _report(
+        "futex_wake_latency",
+        (double)(t1 - t0) / (2.0 * FUTEX_ITERATIONS) / 1000.0,
+        "us");
+}
+
+/*
+**==============================================================================
+**
+** pipe
+**
+**==============================================================================
+*/
+
+static void* _pipe_reader(void* arg)
+{
+    int fd = *(int*)arg;
+    static char buf[PIPE_CHUNK];
+    size_t total = 0;
+    ssize_t n;
+
+    while ((n = read(fd, buf, sizeof(buf))) > 0)
+        total += (size_t)n;
+
+    assert(n == 0);
+    assert(total == PIPE_BYTES);
+    return NULL;
+}
+
+static void _bench_pipe(void)
+{
+    int fds[2];
+    pthread_t thread;
+    static char buf[PIPE_CHUNK];
+    uint64_t t0;
+    uint64_t t1;
+    int r;
+
+    memset(buf, 0xab, sizeof(buf));
+    r = pipe(fds);
+    assert(r == 0);
+    r = pthread_create(&thread, NULL, _pipe_reader, &fds[0]);
+    assert(r == 0);
+
+    t0 = _now_ns();
+
+    for (size_t total = 0; total < PIPE_BYTES;)
+    {
+        ssize_t n = write(fds[1], buf, sizeof(buf));
+        assert(n > 0);
+        total += (size_t)n;
+    }
+
+    close(fds[1]);
+    r = pthread_join(thread, NULL);
+    assert(r == 0);
+    (void)r;
+
+    t1 = _now_ns();
+
+    close(fds[0]);
+
+    _report(
+        "pipe_throughput",
+        (double)PIPE_BYTES / (1024.0 * 1024.0) / ((double)(t1 - t0) / 1e9),
+        "MB/s");
+}
+
+/*
+**==============================================================================
+**
+** main
+**
+**==============================================================================
+*/
+
+static void _parse_threshold(const char* arg0, const char* arg)
+{
+    threshold_t* t;
+    const char* eq;
+    size_t len;
+
+    if (_nthresholds == sizeof(_thresholds) / sizeof(_thresholds[0]))
+        goto usage;
+
+    t = &_thresholds[_nthresholds];
+
+    if (strncmp(arg, "--max-", 6) == 0)
+        t->is_max = true;
+    else if (strncmp(arg, "--min-", 6) == 0)
+        t->is_max = false;
+    else
+        goto usage;
+
+    if (!(eq = strchr(arg, '=')) || (len = eq - (arg + 6)) == 0 ||
+        len >= sizeof(t->name))
+        goto usage;
+
+    memcpy(t->name, arg + 6, len);
+    t->name[len] = '\0';
+    t->value = strtod(eq + 1, NULL);
+    _nthresholds++;
+    return;
+
+usage:
+    fprintf(stderr, "Usage: %s [--max-<name>=<value>]...\n", arg0);
+    fprintf(stderr, "       %s [--min-<name>=<value>]...\n", arg0);
+    exit(1);
+}
+
+int main(int argc, const char* argv[])
+{
+    for (int i = 1; i < argc; i++)
+        _parse_threshold(argv[0], argv[i]);
+
+    _bench_clock("clock_monotonic_coarse", CLOCK_MONOTONIC_COARSE);
+    _bench_clock("clock_monotonic", CLOCK_MONOTONIC);
+    _bench_clock("clock_realtime_coarse", CLOCK_REALTIME_COARSE);
+    _bench_clock("clock_realtime", CLOCK_REALTIME);
+    _bench_futex();
+    _bench_pipe();
+
+    if (_failures)
+    {
+        fprintf(stderr, "bench: %d threshold(s) exceeded\n", _failures);
+        return 1;
+    }
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
@@ -0,0 +1,158 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+static void _write_file(const char* path, const char* data)
+{
+    int fd;
+
+    assert((fd = open(path, O_CREAT | O_WRONLY | O_TRUNC, 0666)) >= 0);
+    assert(write(fd, data, strlen(data)) == (ssize_t)strlen(data));
+    assert(close(fd) == 0);
+}
+
+static void _check_file(const char* path, const char* data)
+{
+    char buf[64];
+    int fd;
+    ssize_t n;
+
+    assert((fd = open(path, O_RDONLY)) >= 0);
+    assert((n = read(fd, buf, sizeof(buf) - 1)) >= 0);
+    buf[n] = '\0';
+    assert(strcmp(buf, data) == 0);
+    assert(close(fd) == 0);
+}
+
+static void _check_cwd(const char* expect)
+{
+    char cwd[PATH_MAX];
+
+    assert(getcwd(cwd, sizeof(cwd)));
+    assert(strcmp(cwd, expect) == 0);
+}
+
+static void test_fchdir(void)
+{
+    int fd;
+
+    assert((fd = open("/jail/dir", O_RDONLY | O_DIRECTORY)) >= 0);
+    assert(chdir("/") == 0);
+    assert(fchdir(fd) == 0);
+    _check_cwd("/jail/dir");
+    assert(close(fd) == 0);
+
+    /* only directories */
+    assert((fd = open("/outside", O_RDONLY)) >= 0);
+    assert(fchdir(fd) == -1 && errno == ENOTDIR);
+    assert(close(fd) == 0);
+
+    assert(fchdir(-1) == -1 && errno == EBADF);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_chroot(void)
+{
+    struct stat st;
+    char path[PATH_MAX];
+    int outside;
+    int fd;
+
+    assert(chroot("/outside") == -1 && errno == ENOTDIR);
+    assert(chroot("/nonexistent") == -1 && errno == ENOENT);
+
+    assert((outside = open("/", O_RDONLY | O_DIRECTORY)) >= 0);
+
+    /* the working directory is kept, now relative to the new root */
+    assert(chdir("/jail/dir") == 0);
+    assert(chroot("/jail") == 0);
+    _check_cwd("/dir");
+
+    /* absolute and relative paths resolve beneath the root */
+    _check_file("/file", "inside");
+    _check_file("../file", "inside");
+    _check_file("../../../../file", "inside");
+    _check_file("/../../file", "inside");
+    assert(stat("/outside", &st) == -1 && errno == ENOENT);
+    assert(stat("/jail", &st) == -1 && errno == ENOENT);
+
+    /* ".." stops at the root */
+    assert(chdir("../../..") == 0);
+    _check_cwd("/");
+
+    /* relative to a directory descriptor */
+    assert((fd = open("/dir", O_RDONLY | O_DIRECTORY)) >= 0);
+    assert(fstatat(fd, "../file", &st, 0) == 0);
+    assert(fstatat(fd, "../../outside", &st, 0) == -1 && errno == ENOENT);
+    assert(close(fd) == 0);
+
+    /* ".." in a relative symbolic link stops at the root too */
+    assert(symlink("../../..", "/dir/escape") == 0);
+    _check_file("/dir/escape/file", "inside");
+    assert(stat("/dir/escape/outside", &st) == -1 && errno == ENOENT);
+    assert(stat("/dir/escape/jail", &st) == -1 && errno == ENOENT);
+    assert(realpath("/dir/escape/file", path) && strcmp(path, "/file") == 0);
+
+    /* and absolute symbolic links resolve beneath the root */
+    assert(symlink("/file", "/dir/absolute") == 0);
+    _check_file("/dir/absolute", "inside");
+
+    /* also when creating and removing files through them */
+    assert(symlink("/", "/d") == 0);
+    assert(stat("/d/outside", &st) == -1 && errno == ENOENT);
+    _write_file("/d/x", "through");
+    _check_file("/x", "through");
+    assert(unlink("/d/x") == 0);
+    assert(stat("/x", &st) == -1 && errno == ENOENT);
+    assert(mkdir("/d/sub", 0777) == 0);
+    assert(stat("/sub", &st) == 0 && S_ISDIR(st.st_mode));
+    assert(rmdir("/d/sub") == 0);
+    assert(stat("/sub", &st) == -1 && errno == ENOENT);
+    assert(unlink("/d") == 0);
+
+    /* a directory opened outside the new root leads back to the root */
+    assert(fchdir(outside) == 0);
+    _check_cwd("/");
+    assert(access("outside", F_OK) == -1 && errno == ENOENT);
+    _check_file("file", "inside");
+    assert(close(outside) == 0);
+
+    /* new files are created beneath the root */
+    _write_file("/new", "created");
+    _check_file("/new", "created");
+
+    /* chroot nests, relative to the current root */
+    assert(chroot("dir") == 0);
+    _check_cwd("/");
+    assert(access("/file", F_OK) == -1 && errno == ENOENT);
+    _check_file("/nested", "nested");
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    assert(mkdir("/jail", 0777) == 0);
+    assert(mkdir("/jail/dir", 0777) == 0);
+    _write_file("/jail/file", "inside");
+    _write_file("/jail/dir/nested", "nested");
+    _write_file("/outside", "outside");
+
+    test_fchdir();
+    test_chroot();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -2,11 +2,14 @@
 // Licensed under the MIT License.
 
 #include <assert.h>
+#include <errno.h>
 #include <stdio.h>
 #include <stdlib.h>
 #include <string.h>
+#include <sys/syscall.h>
 #include <sys/time.h>
 #include <time.h>
+#include <unistd.h>
 
 #define NANO_IN_SECOND 1000000000
 #define SGX_TARGET "sgx"
@@ -167,6 +170,33 @@ static int test_clock_getres()
     return 0;
 }
 
+/* the kernel copies the arguments in and out and rejects bad pointers */
+static int test_bad_pointers()
+{
+    void* bad = (void*)8;
+    struct timespec ts;
+
+    assert(syscall(SYS_clock_gettime, CLOCK_REALTIME, bad) == -1);
+    assert(errno == EFAULT);
+    assert(syscall(SYS_clock_gettime, CLOCK_REALTIME, NULL) == -1);
+    assert(errno == EFAULT);
+    assert(syscall(SYS_clock_settime, CLOCK_REALTIME, bad) == -1);
+    assert(errno == EFAULT);
+    assert(syscall(SYS_nanosleep, bad, NULL) == -1 && errno == EFAULT);
+    assert(syscall(SYS_gettimeofday, bad, NULL) == -1 && errno == EFAULT);
+
+    /* these pointers may be null */
+    assert(syscall(SYS_clock_getres, CLOCK_REALTIME, NULL) == 0);
+    assert(syscall(SYS_gettimeofday, NULL, NULL) == 0);
+    assert(syscall(SYS_time, NULL) > 0);
+
+    ts.tv_sec = 0;
+    ts.tv_nsec = 1000;
+    assert(syscall(SYS_nanosleep, &ts, NULL) == 0);
+
+    return 0;
+}
+
 int main(int argc, const char* argv[])
 {
     assert(argc == 3);
@@ -184,6 +214,8 @@ int main(int argc, const char* argv[])
 
     assert(test_clock_getres() == 0);
 
+    assert(test_bad_pointers() == 0);
+
     printf("=== passed test (%s)\n", argv[0]);
 
     return 0;
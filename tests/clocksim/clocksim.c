@@ -0,0 +1,859 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+/*
+** Host simulation of the enclave clock. A mock host thread publishes
+** clock_ctrl.now and may advance it, jump it, stall it or move it backwards
+** between enclave reads. Every result is checked against a reference model
+** and against the invariants the enclave clock promises:
+**
+**     - CLOCK_MONOTONIC and CLOCK_BOOTTIME never go backward
+**     - the monotonic clock drifts ahead of the host by at most one
+**       nanosecond per read while the host clock is stalled or regressing
+**     - CLOCK_REALTIME never goes backward, even across clock_settime()
+**     - with the NTP clock source, CLOCK_REALTIME follows the offset the
+**       host publishes by at most 500 ppm, and not while it is unsynced
+**     - adjtimex() reports errors of at most 16 seconds and TIME_ERROR
+**       whenever the clock is not synchronized
+**     - the emulated time-stamp counter (RDTSC) never goes backward
+**     - arithmetic overflow aborts the enclave instead of wrapping
+**
+** Fixed scenarios then run a leap second that the host announces through
+** each of its paths (held, deleted, smeared, announced too late to smear
+** and announced on a day that has none): CLOCK_TAI never jumps, and
+** CLOCK_REALTIME never goes backward and ends up a second off.
+**
+** The enclave also checks the header of clock_ctrl: it refuses a layout
+** it does not know and reads no host status from a version 1 host.
+**
+** Each trial is generated from its own seed and runs in a child process so
+** it starts from a fresh copy of the clock's static state. A failing trial
+** prints its seed; rerun it alone with CLOCKSIM_SEED=<seed>.
+*/
+
+#include "../../tools/myst/enc/clock.c"
+
+#include <limits.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/types.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define DEFAULT_TRIALS 500
+#define STEPS_PER_TRIAL 2000
+#define INTERVAL 1000000 /* the clock tick used by "myst exec" (1ms) */
+
+/*
+**==============================================================================
+**
+** TEE and oe stubs
+**
+**==============================================================================
+*/
+
+/* set when the model predicts that the next clock operation overflows */
+static bool _expect_abort;
+
+static uint64_t _seed;
+
+static bool _is_outside(const void* ptr, size_t size)
+{
+    (void)ptr;
+    (void)size;
+    return true;
+}
+
+const myst_tee_t* myst_tee(void)
+{
+    static const myst_tee_t _tee = {
+        .name = "clocksim",
+        .is_outside = _is_outside,
+    };
+
+    return &_tee;
+}
+
+void oe_abort(void)
+{
+    if (_expect_abort)
+        _exit(0);
+
+    fprintf(stderr, "seed=%lu: unexpected clock abort\n", _seed);
+    _exit(1);
+}
+
+/*
+**==============================================================================
+**
+** random numbers (xorshift64*: reproducible across libc versions)
+**
+**==============================================================================
+*/
+
+static uint64_t _rng_state;
+
+static uint64_t _rand64(void)
+{
+    _rng_state ^= _rng_state >> 12;
+    _rng_state ^= _rng_state << 25;
+    _rng_state ^= _rng_state >> 27;
+    return _rng_state * 0x2545F4914F6CDD1DULL;
+}
+
+/* return a random value in the range [lo, hi] */
+static long _rand_range(long lo, long hi)
+{
+    uint64_t span = (uint64_t)hi - (uint64_t)lo + 1;
+
+    if (span == 0)
+        return (long)_rand64();
+
+    return (long)((uint64_t)lo + _rand64() % span);
+}
+
+/*
+**==============================================================================
+**
+** reference model
+**
+**==============================================================================
+*/
+
+typedef struct model
+{
+    long realtime0;
+    long monotime0;
+    long prev;          /* last monotonic value handed out */
+    long delta;         /* accumulated clock_settime() adjustment */
+    long host_max;      /* largest value the host has published */
+    long lag;           /* reads since the host last moved past prev */
+    long last_realtime; /* last realtime value handed out */
+    uint64_t last_tsc;  /* last emulated TSC value handed out */
+    const struct clock_ctrl* ctrl;
+    long host_offset; /* the host realtime offset applied so far */
+    long slewed_at;   /* monotonic time of the last slew */
+} model_t;
+
+#define CHECK(COND)                                                   \
+    do                                                                \
+    {                                                                 \
+        if (!(COND))                                                  \
+        {                                                             \
+            fprintf(                                                  \
+                stderr,                                               \
+                "seed=%lu step=%zu: %s(%u): check failed: %s\n",      \
+                _seed,                                                \
+                _step,                                                \
+                __FILE__,                                             \
+                __LINE__,                                             \
+                #COND);                                               \
+            _exit(1);                                                 \
+        }                                                             \
+    } while (0)
+
+static size_t _step;
+
+static long _ts_to_ns(const struct timespec* ts)
+{
+    return ts->tv_sec * NANO_IN_SECOND + ts->tv_nsec;
+}
+
+/* predict the next monotonic value; return false if it would overflow */
+static bool _model_monotime(model_t* m, long host_now, long* result)
+{
+    if (host_now > m->prev)
+    {
+        m->prev = host_now;
+        m->lag = 0;
+    }
+    else
+    {
+        if (m->prev == LONG_MAX)
+            return false;
+
+        m->prev++;
+        m->lag++;
+    }
+
+    *result = m->prev;
+    return true;
+}
+
+/* predict the host realtime offset applied at monotonic time mono */
+static long _model_host_offset(model_t* m, long mono)
+{
+    const struct clock_ctrl* ctrl = m->ctrl;
+    long elapsed;
+    long max;
+    long target;
+
+    if (ctrl->source != MYST_CLOCK_SOURCE_NTP)
+        return 0;
+
+    if (m->slewed_at == 0)
+        m->slewed_at = m->monotime0;
+
+    if ((elapsed = mono - m->slewed_at) <= 0)
+        return m->host_offset;
+
+    m->slewed_at = mono;
+
+    /* a status that is being written cannot be read */
+    if ((ctrl->seq & 1) || !ctrl->synced)
+        return m->host_offset;
+
+    max = elapsed / MYST_CLOCK_MAX_SLEW_DIVISOR;
+    target = ctrl->realtime_offset;
+
+    if (target > m->host_offset)
+    {
+        if (target - m->host_offset < max)
+            m->host_offset = target;
+        else
+            m->host_offset += max;
+    }
+    else if (target < m->host_offset)
+    {
+        if (m->host_offset - target < max)
+            m->host_offset = target;
+        else
+            m->host_offset -= max;
+    }
+
+    return m->host_offset;
+}
+
+/* predict the next realtime value; return false if it would overflow */
+static bool _model_realtime(model_t* m, long host_now, long* result)
+{
+    long mono;
+    long ns;
+
+    if (!_model_monotime(m, host_now, &mono))
+        return false;
+
+    ns = mono - m->monotime0;
+
+    if (__builtin_saddl_overflow(ns, m->realtime0, &ns))
+        return false;
+
+    if (__builtin_saddl_overflow(ns, m->delta, &ns))
+        return false;
+
+    if (__builtin_saddl_overflow(ns, _model_host_offset(m, mono), &ns))
+        return false;
+
+    *result = ns;
+    return true;
+}
+
+
+/*
+**==============================================================================
+**
+** simulation
+**
+**==============================================================================
+*/
+
+typedef enum
+{
+    HOST_ADVANCE, /* regular tick */
+    HOST_JUMP,    /* large forward step (e.g., host suspended) */
+    HOST_STALL,   /* host thread not scheduled */
+    HOST_REGRESS, /* malicious host moves the clock backward */
+    HOST_EDGE,    /* host moves the clock close to LONG_MAX */
+    HOST_STATUS,  /* host publishes a new offset and errors (or tears them) */
+    NUM_HOST_ACTIONS,
+} host_action_t;
+
+/* whether this trial drives the clocks toward overflow */
+static bool _edge_trial;
+
+static void _mock_host(struct clock_ctrl* ctrl, model_t* m)
+{
+    long now = ctrl->now;
+
+    switch (_rand64() % NUM_HOST_ACTIONS)
+    {
+        case HOST_ADVANCE:
+        {
+            if (now <= LONG_MAX - INTERVAL)
+                now += _rand_range(1, INTERVAL);
+            break;
+        }
+        case HOST_JUMP:
+        {
+            long step = _rand_range(INTERVAL, 3600L * NANO_IN_SECOND);
+
+            if (now <= LONG_MAX - step)
+                now += step;
+            break;
+        }
+        case HOST_STALL:
+        {
+            break;
+        }
+        case HOST_REGRESS:
+        {
+            now -= _rand_range(0, now <= 0 ? 0 : now);
+            break;
+        }
+        case HOST_EDGE:
+        {
+            if (_edge_trial && _rand64() % 64 == 0)
+                now = LONG_MAX - _rand_range(0, 1024);
+            break;
+        }
+        case HOST_STATUS:
+        {
+            /* mostly a small correction; sometimes an absurd one */
+            long offset = ctrl->realtime_offset;
+            const long limit = 1L << 60;
+
+            if (_rand64() % 16 == 0 || offset < -limit || offset > limit)
+                offset = _rand_range(LONG_MIN / 2, LONG_MAX / 2);
+            else
+                offset += _rand_range(-1000000, 1000000);
+
+            ctrl->realtime_offset = offset;
+
+            ctrl->maxerror = _rand_range(-1, 20L * NANO_IN_SECOND);
+            ctrl->esterror = _rand_range(-1, 20L * NANO_IN_SECOND);
+            ctrl->synced = _rand64() % 4 != 0;
+
+            /* leave the status half written now and then */
+            ctrl->seq += _rand64() % 4 == 0 ? 1 : 2;
+            break;
+        }
+    }
+
+    ctrl->now = now;
+
+    if (now > m->host_max)
+        m->host_max = now;
+}
+
+static void _check_monotonic(model_t* m, clockid_t clk_id, long host_now)
+{
+    struct timespec ts;
+    long prev = m->prev;
+    long expected;
+
+    _expect_abort = !_model_monotime(m, host_now, &expected);
+
+    CHECK(myst_tcall_clock_gettime(clk_id, &ts) == 0);
+    CHECK(!_expect_abort);
+    CHECK(ts.tv_nsec >= 0 && ts.tv_nsec < NANO_IN_SECOND);
+    CHECK(_ts_to_ns(&ts) == expected);
+
+    /* monotonicity */
+    CHECK(expected > prev);
+
+    /* bounded drift */
+    CHECK(expected <= m->host_max || expected - m->host_max <= m->lag);
+}
+
+static void _check_realtime(model_t* m, long host_now)
+{
+    struct timespec ts;
+    long expected;
+
+    _expect_abort = !_model_realtime(m, host_now, &expected);
+
+    CHECK(myst_tcall_clock_gettime(CLOCK_REALTIME, &ts) == 0);
+    CHECK(!_expect_abort);
+    CHECK(ts.tv_nsec >= 0 && ts.tv_nsec < NANO_IN_SECOND);
+    CHECK(_ts_to_ns(&ts) == expected);
+    CHECK(expected > m->last_realtime);
+
+    m->last_realtime = expected;
+}
+
+static void _check_adjtime(model_t* m, long host_now)
+{
+    const struct clock_ctrl* ctrl = m->ctrl;
+    struct timex tx;
+    long expected;
+    long r;
+
+    memset(&tx, 0, sizeof(tx));
+    tx.modes = ADJ_OFFSET;
+    CHECK(myst_tcall_clock_adjtime(CLOCK_REALTIME, &tx) == -EPERM);
+    CHECK(myst_tcall_clock_adjtime(CLOCK_MONOTONIC, &tx) == -EOPNOTSUPP);
+    tx.modes = 0;
+
+    _expect_abort = !_model_realtime(m, host_now, &expected);
+
+    r = myst_tcall_clock_adjtime(CLOCK_REALTIME, &tx);
+    CHECK(!_expect_abort);
+    CHECK(r == TIME_OK || r == TIME_ERROR);
+    CHECK((r == TIME_OK) == !(tx.status & STA_UNSYNC));
+    CHECK(tx.time.tv_sec == expected / NANO_IN_SECOND);
+    CHECK(tx.time.tv_usec == (expected % NANO_IN_SECOND) / 1000);
+    CHECK(tx.maxerror >= 0 && tx.maxerror <= 16 * MICRO_IN_SECOND);
+    CHECK(tx.esterror >= 0 && tx.esterror <= 16 * MICRO_IN_SECOND);
+
+    /* a torn or unsynchronized status is never reported as synchronized */
+    if ((ctrl->seq & 1) || !ctrl->synced)
+        CHECK(r == TIME_ERROR);
+
+    /* the free-running clocks only know the error they started with */
+    if (ctrl->source != MYST_CLOCK_SOURCE_NTP)
+        CHECK(tx.offset == 0);
+    else if (r == TIME_OK)
+        CHECK(tx.offset == (ctrl->realtime_offset - m->host_offset) / 1000);
+
+    CHECK(expected > m->last_realtime);
+    m->last_realtime = expected;
+}
+
+static void _check_tsc(model_t* m, long host_now)
+{
+    long mono;
+    uint64_t tsc;
+
+    _expect_abort = !_model_monotime(m, host_now, &mono);
+
+    tsc = myst_get_tsc();
+    CHECK(!_expect_abort);
+    CHECK(tsc > m->last_tsc);
+
+    m->last_tsc = tsc;
+}
+
+static void _check_settime(model_t* m, long host_now)
+{
+    struct timespec ts;
+    long new_time;
+    long cur_time;
+    long expected_ret = 0;
+
+    /* mostly near the current time; occasionally an absurd value */
+    if (_rand64() % 16 == 0)
+    {
+        ts.tv_sec = _rand_range(0, LONG_MAX);
+    }
+    else
+    {
+        long offset = _rand_range(-3600, 3600);
+        ts.tv_sec = m->last_realtime / NANO_IN_SECOND + offset;
+
+        if (ts.tv_sec < 0)
+            ts.tv_sec = 0;
+    }
+
+    ts.tv_nsec = _rand_range(0, NANO_IN_SECOND - 1);
+
+    if (__builtin_smull_overflow(ts.tv_sec, NANO_IN_SECOND, &new_time) ||
+        __builtin_saddl_overflow(new_time, ts.tv_nsec, &new_time))
+    {
+        /* rejected before the current time is sampled */
+        CHECK(myst_tcall_clock_settime(CLOCK_REALTIME, &ts) == -EINVAL);
+        return;
+    }
+
+    _expect_abort = !_model_realtime(m, host_now, &cur_time);
+
+    if (!_expect_abort && new_time > cur_time)
+    {
+        long delta;
+
+        if (__builtin_saddl_overflow(m->delta, new_time - cur_time, &delta))
+            expected_ret = -EINVAL;
+        else
+            m->delta = delta;
+    }
+
+    CHECK(myst_tcall_clock_settime(CLOCK_REALTIME, &ts) == expected_ret);
+    CHECK(!_expect_abort);
+
+    /* the clock reads at least cur_time from here on */
+    if (cur_time > m->last_realtime)
+        m->last_realtime = cur_time;
+}
+
+/* clear the clock_ctrl and fill in its header as the host does */
+static void _init_ctrl(struct clock_ctrl* ctrl, uint32_t version)
+{
+    memset(ctrl, 0, sizeof(struct clock_ctrl));
+    myst_shm_header_init(
+        &ctrl->header,
+        MYST_CLOCK_CTRL_MAGIC,
+        version,
+        version == 1 ? MYST_CLOCK_CTRL_V1_SIZE : sizeof(struct clock_ctrl));
+}
+
+static void _run_trial(void)
+{
+    struct clock_ctrl ctrl;
+    myst_clock_config_t config;
+    model_t m;
+    struct timespec ts;
+
+    _init_ctrl(&ctrl, MYST_CLOCK_CTRL_VERSION);
+    memset(&config, 0, sizeof(config));
+    memset(&m, 0, sizeof(m));
+
+    /* most trials run to completion; the rest end in an expected abort */
+    _edge_trial = (_rand64() % 8 == 0);
+
+    /* start either at a plausible epoch time or close to the limit */
+    ctrl.monotime0 = _rand_range(1, 1L << 40);
+
+    if (_edge_trial && _rand64() % 2 == 0)
+        ctrl.realtime0 = LONG_MAX - _rand_range(0, 3600L * NANO_IN_SECOND);
+    else
+        ctrl.realtime0 = _rand_range(1, 1L << 62);
+
+    ctrl.now = ctrl.monotime0;
+    ctrl.interval = INTERVAL;
+    ctrl.source = (int)(_rand64() % 3);
+    ctrl.synced = 1;
+
+    m.realtime0 = ctrl.realtime0;
+    m.monotime0 = ctrl.monotime0;
+    m.prev = ctrl.monotime0;
+    m.host_max = ctrl.now;
+    m.ctrl = &ctrl;
+
+    /* the host must feed the clock source that was asked for */
+    config.source = (ctrl.source + 1) % 3;
+    CHECK(myst_setup_clock(&ctrl, &config) != 0);
+    config.source = ctrl.source;
+    CHECK(myst_setup_clock(&ctrl, &config) == 0);
+
+    /* a 2.5GHz counter, or 1GHz when the host does not report one */
+    if (_rand64() % 2)
+        myst_setup_tsc(2500000000);
+
+    CHECK(myst_tcall_clock_getres(CLOCK_MONOTONIC, &ts) == 0);
+    CHECK(_ts_to_ns(&ts) == INTERVAL);
+
+    for (_step = 0; _step < STEPS_PER_TRIAL; _step++)
+    {
+        _mock_host(&ctrl, &m);
+
+        switch (_rand64() % 6)
+        {
+            case 0:
+                _check_monotonic(&m, CLOCK_MONOTONIC, ctrl.now);
+                break;
+            case 1:
+                _check_monotonic(&m, CLOCK_BOOTTIME, ctrl.now);
+                break;
+            case 2:
+                _check_realtime(&m, ctrl.now);
+                break;
+            case 3:
+                _check_settime(&m, ctrl.now);
+                break;
+            case 4:
+                _check_tsc(&m, ctrl.now);
+                break;
+            case 5:
+                _check_adjtime(&m, ctrl.now);
+                break;
+        }
+    }
+
+    /* other clocks are not settable */
+    CHECK(myst_tcall_clock_settime(CLOCK_MONOTONIC, &ts) == -EINVAL);
+}
+
+/*
+**==============================================================================
+**
+** leap seconds
+**
+**==============================================================================
+*/
+
+#define JAN_1_2017 1483228800L /* a leap second came before this midnight */
+#define MAR_1_2017 1488326400L /* and none before this one */
+#define SECOND ((long)NANO_IN_SECOND)
+#define HOUR (3600 * SECOND)
+
+typedef struct leap_scenario
+{
+    long midnight; /* seconds since the epoch */
+    int leap;      /* what the host announces */
+    bool smear;
+    long start; /* nanoseconds before midnight */
+    long stop;  /* nanoseconds after midnight */
+    long step;
+} leap_scenario_t;
+
+static const leap_scenario_t* _scenario;
+
+/* UTC less the scale without leap seconds, once the leap second is over */
+static long _expected_adjustment(const leap_scenario_t* s, long t, int* state)
+{
+    const long midnight = s->midnight * NANO_IN_SECOND;
+    const long smear_start = midnight - 12 * HOUR;
+    const bool smear = s->smear && s->start >= 12 * HOUR;
+    const int leap = s->midnight == MAR_1_2017 ? 0 : s->leap;
+
+    *state = TIME_OK;
+
+    if (leap == 0)
+        return 0;
+
+    if (smear)
+    {
+        if (t >= smear_start + 24 * HOUR)
+            return leap * NANO_IN_SECOND;
+
+        *state = t < midnight ? (leap > 0 ? TIME_INS : TIME_DEL) : TIME_OOP;
+
+        if (t <= smear_start)
+            return 0;
+
+        return leap * ((t - smear_start) / 86400);
+    }
+
+    if (t < midnight)
+    {
+        *state = leap > 0 ? TIME_INS : TIME_DEL;
+        return 0;
+    }
+
+    if (leap > 0 && t < midnight + NANO_IN_SECOND)
+    {
+        *state = TIME_OOP;
+        return t - midnight;
+    }
+
+    return leap * NANO_IN_SECOND;
+}
+
+static void _run_leap_scenario(void)
+{
+    const leap_scenario_t* s = _scenario;
+    const long tai0 = 36;
+    struct clock_ctrl ctrl;
+    myst_clock_config_t config;
+    long realtime0 = s->midnight * NANO_IN_SECOND - s->start;
+    long last_utc = 0;
+
+    _init_ctrl(&ctrl, MYST_CLOCK_CTRL_VERSION);
+    ctrl.monotime0 = NANO_IN_SECOND;
+    ctrl.realtime0 = realtime0;
+    ctrl.now = ctrl.monotime0;
+    ctrl.interval = INTERVAL;
+    ctrl.source = MYST_CLOCK_SOURCE_NTP;
+    ctrl.synced = 1;
+    ctrl.tai_offset = tai0;
+    ctrl.leap = s->leap;
+
+    config.source = MYST_CLOCK_SOURCE_NTP;
+    config.leap_smear = s->smear;
+
+    /* the TAI offset in the configuration must be plausible */
+    config.tai_offset = -1;
+    CHECK(myst_setup_clock(&ctrl, &config) != 0);
+    config.tai_offset = 1001;
+    CHECK(myst_setup_clock(&ctrl, &config) != 0);
+    config.tai_offset = 0;
+    CHECK(myst_setup_clock(&ctrl, &config) == 0);
+
+    for (_step = 0; ctrl.now - ctrl.monotime0 < s->start + s->stop; _step++)
+    {
+        /* one read per step, so the host time is the monotonic time */
+        const long t = realtime0 + (ctrl.now += s->step) - ctrl.monotime0;
+        int state;
+        const long adjustment = _expected_adjustment(s, t, &state);
+        const bool over = state == TIME_OK && adjustment != 0;
+        struct timespec ts;
+        struct timex tx;
+        long r;
+
+        switch (_step % 3)
+        {
+            case 0:
+            {
+                CHECK(myst_tcall_clock_gettime(CLOCK_REALTIME, &ts) == 0);
+                CHECK(_ts_to_ns(&ts) == t - adjustment);
+                CHECK(_ts_to_ns(&ts) >= last_utc);
+                last_utc = _ts_to_ns(&ts);
+                break;
+            }
+            case 1:
+            {
+                /* TAI runs on through the leap second */
+                CHECK(myst_tcall_clock_gettime(CLOCK_TAI, &ts) == 0);
+                CHECK(_ts_to_ns(&ts) == t + tai0 * NANO_IN_SECOND);
+                break;
+            }
+            case 2:
+            {
+                memset(&tx, 0, sizeof(tx));
+                r = myst_tcall_clock_adjtime(CLOCK_REALTIME, &tx);
+                CHECK(r == state);
+                CHECK(tx.tai == tai0 + (over ? s->leap : 0));
+                CHECK(tx.time.tv_sec == (t - adjustment) / NANO_IN_SECOND);
+
+                if (state == TIME_OK)
+                    CHECK(!(tx.status & (STA_INS | STA_DEL)));
+                else
+                    CHECK(tx.status & (s->leap > 0 ? STA_INS : STA_DEL));
+                break;
+            }
+        }
+    }
+}
+
+/* the enclave refuses a clock_ctrl from a host launcher it does not match
+ * and accepts one of the previous version */
+static void _run_abi_check(void)
+{
+    struct clock_ctrl ctrl;
+    myst_clock_config_t config;
+    struct timespec ts;
+    struct timex tx;
+
+    memset(&config, 0, sizeof(config));
+    config.source = MYST_CLOCK_SOURCE_REALTIME;
+
+    _init_ctrl(&ctrl, MYST_CLOCK_CTRL_VERSION);
+    ctrl.realtime0 = 1000L * NANO_IN_SECOND;
+    ctrl.monotime0 = NANO_IN_SECOND;
+    ctrl.now = ctrl.monotime0;
+    ctrl.interval = INTERVAL;
+
+    ctrl.header.magic = ~MYST_CLOCK_CTRL_MAGIC;
+    CHECK(myst_setup_clock(&ctrl, &config) != 0);
+    ctrl.header.magic = MYST_CLOCK_CTRL_MAGIC;
+
+    ctrl.header.version = MYST_CLOCK_CTRL_VERSION + 1;
+    CHECK(myst_setup_clock(&ctrl, &config) != 0);
+    ctrl.header.version = MYST_CLOCK_CTRL_VERSION - 2;
+    CHECK(myst_setup_clock(&ctrl, &config) != 0);
+    ctrl.header.version = MYST_CLOCK_CTRL_VERSION;
+
+    ctrl.header.size = sizeof(ctrl) - 1;
+    CHECK(myst_setup_clock(&ctrl, &config) != 0);
+
+    /* a version 1 host: the status below is not there to be read */
+    _init_ctrl(&ctrl, 1);
+    ctrl.realtime0 = 1000L * NANO_IN_SECOND;
+    ctrl.monotime0 = NANO_IN_SECOND;
+    ctrl.now = ctrl.monotime0;
+    ctrl.interval = INTERVAL;
+    ctrl.synced = 1;
+    ctrl.tai_offset = 37;
+    ctrl.leap = 1;
+
+    ctrl.header.size = MYST_CLOCK_CTRL_V1_SIZE - 1;
+    CHECK(myst_setup_clock(&ctrl, &config) != 0);
+    ctrl.header.size = MYST_CLOCK_CTRL_V1_SIZE;
+
+    /* which cannot follow NTP */
+    ctrl.source = config.source = MYST_CLOCK_SOURCE_NTP;
+    CHECK(myst_setup_clock(&ctrl, &config) != 0);
+    ctrl.source = config.source = MYST_CLOCK_SOURCE_REALTIME;
+    CHECK(myst_setup_clock(&ctrl, &config) == 0);
+
+    ctrl.now += NANO_IN_SECOND;
+    CHECK(myst_tcall_clock_gettime(CLOCK_REALTIME, &ts) == 0);
+    CHECK(_ts_to_ns(&ts) == 1001L * NANO_IN_SECOND);
+
+    memset(&tx, 0, sizeof(tx));
+    CHECK(myst_tcall_clock_adjtime(CLOCK_REALTIME, &tx) == TIME_ERROR);
+    CHECK(tx.status & STA_UNSYNC);
+    CHECK(!(tx.status & STA_INS));
+    CHECK(tx.tai == 0);
+}
+
+static int _spawn(void (*run)(void), uint64_t seed)
+{
+    pid_t pid;
+    int status;
+
+    if ((pid = fork()) < 0)
+    {
+        perror("fork");
+        return -1;
+    }
+
+    if (pid == 0)
+    {
+        _seed = seed;
+        _rng_state = seed * 0x9E3779B97F4A7C15ULL + 1;
+        run();
+        _exit(0);
+    }
+
+    if (waitpid(pid, &status, 0) != pid)
+        return -1;
+
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0)
+    {
+        if (run == _run_trial)
+        {
+            fprintf(
+                stderr, "clocksim: trial failed: CLOCKSIM_SEED=%lu\n", seed);
+        }
+        else if (run == _run_abi_check)
+            fprintf(stderr, "clocksim: the clock_ctrl header check failed\n");
+        else
+            fprintf(stderr, "clocksim: leap scenario %lu failed\n", seed);
+
+        return -1;
+    }
+
+    return 0;
+}
+
+int main(int argc, const char* argv[])
+{
+    const char* env;
+    uint64_t first = 1;
+    uint64_t ntrials = DEFAULT_TRIALS;
+
+    if ((env = getenv("CLOCKSIM_SEED")))
+    {
+        first = strtoul(env, NULL, 10);
+        ntrials = 1;
+    }
+    else if ((env = getenv("CLOCKSIM_TRIALS")))
+    {
+        ntrials = strtoul(env, NULL, 10);
+    }
+
+    for (uint64_t i = 0; i < ntrials; i++)
+    {
+        if (_spawn(_run_trial, first + i) != 0)
+            return 1;
+    }
+
+    static const leap_scenario_t scenarios[] = {
+        /* inserted: held at midnight for a second */
+        {JAN_1_2017, 1, false, 20 * SECOND, 20 * SECOND, 1000003},
+        /* deleted: skips a second at midnight */
+        {JAN_1_2017, -1, false, 20 * SECOND, 20 * SECOND, 1000003},
+        /* smeared across the day around midnight */
+        {JAN_1_2017, 1, true, 13 * HOUR, 13 * HOUR, 59 * SECOND},
+        {JAN_1_2017, -1, true, 13 * HOUR, 13 * HOUR, 59 * SECOND},
+        /* announced after the smear should have started */
+        {JAN_1_2017, 1, true, 20 * SECOND, 20 * SECOND, 1000003},
+        /* the host announces a leap second that cannot be */
+        {MAR_1_2017, 1, false, 20 * SECOND, 20 * SECOND, 1000003},
+    };
+
+    for (size_t i = 0; i < sizeof(scenarios) / sizeof(scenarios[0]); i++)
+    {
+        _scenario = &scenarios[i];
+
+        if (_spawn(_run_leap_scenario, i) != 0)
+            return 1;
+    }
+
+    if (_spawn(_run_abi_check, 0) != 0)
+        return 1;
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
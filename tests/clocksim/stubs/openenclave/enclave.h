@@ -0,0 +1,16 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _CLOCKSIM_OPENENCLAVE_ENCLAVE_H
+#define _CLOCKSIM_OPENENCLAVE_ENCLAVE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+/* Host stand-ins for the OE calls made by tools/myst/enc/clock.c */
+
+bool oe_is_outside_enclave(const void* ptr, size_t size);
+
+void oe_abort(void);
+
+#endif /* _CLOCKSIM_OPENENCLAVE_ENCLAVE_H */
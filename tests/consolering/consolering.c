@@ -0,0 +1,364 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <errno.h>
+#include <pthread.h>
+#include <stdarg.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#include <myst/consolering.h>
+#include <myst/printf.h>
+#include <myst/tcall.h>
+#include <myst/time.h>
+
+#define CAPACITY MYST_CONSOLE_RING_CAPACITY
+
+/* as in kernel/consolering.c */
+#define MAX_RECORD (CAPACITY / 4)
+
+/*
+**==============================================================================
+**
+** what kernel/consolering.c takes from the rest of the kernel
+**
+**==============================================================================
+*/
+
+/* what was written to the console with host calls */
+static char _direct[256];
+static size_t _direct_size;
+
+int myst_eprintf(const char* format, ...)
+{
+    va_list ap;
+    int n;
+
+    va_start(ap, format);
+    n = vfprintf(stderr, format, ap);
+    va_end(ap);
+
+    return n;
+}
+
+void myst_sleep_msec(uint64_t milliseconds)
+{
+    usleep(milliseconds * 1000);
+}
+
+long myst_tcall_write_console(int fd, const void* buf, size_t count)
+{
+    (void)fd;
+    assert(_direct_size + count <= sizeof(_direct));
+    memcpy(_direct + _direct_size, buf, count);
+    _direct_size += count;
+    return (long)count;
+}
+
+/*
+**==============================================================================
+**
+** the host side of the ring (as in tools/myst/host/shm.c)
+**
+**==============================================================================
+*/
+
+static myst_console_ring_t* _ring;
+
+typedef struct output
+{
+    /* the records in the order they were read */
+    uint32_t fds[1024];
+    uint32_t sizes[1024];
+    size_t nrecords;
+
+    /* their bytes, one after the other */
+    uint8_t* data;
+    size_t size;
+} output_t;
+
+static output_t _out;
+
+static void _new_ring(uint64_t pos)
+{
+    free(_ring);
+    assert((_ring = calloc(1, sizeof(myst_console_ring_t))));
+    myst_shm_header_init(
+        &_ring->header,
+        MYST_CONSOLE_RING_MAGIC,
+        MYST_CONSOLE_RING_VERSION,
+        sizeof(myst_console_ring_t));
+
+    /* a ring that the host has already used up to pos */
+    _ring->head = _ring->tail = pos;
+    assert(myst_console_ring_setup(_ring) == 0);
+
+    free(_out.data);
+    memset(&_out, 0, sizeof(_out));
+    assert((_out.data = malloc(4 * 1024 * 1024)));
+}
+
+/* read the records the kernel has published, and return how many */
+static size_t _consume(void)
+{
+    uint64_t head = __atomic_load_n(&_ring->head, __ATOMIC_ACQUIRE);
+    uint64_t tail = _ring->tail;
+    size_t n = 0;
+
+    while (tail != head)
+    {
+        myst_console_record_t record;
+        size_t offset = tail & (CAPACITY - 1);
+        size_t rem = CAPACITY - offset;
+
+        assert(rem >= sizeof(record));
+        memcpy(&record, &_ring->data[offset], sizeof(record));
+        assert(record.fd == STDOUT_FILENO || record.fd == STDERR_FILENO);
+        assert(record.size <= MAX_RECORD);
+
+        offset = (offset + sizeof(record)) & (CAPACITY - 1);
+        rem = CAPACITY - offset;
+
+        if (rem > record.size)
+            rem = record.size;
+
+        memcpy(_out.data + _out.size, &_ring->data[offset], rem);
+        memcpy(_out.data + _out.size + rem, _ring->data, record.size - rem);
+        _out.size += record.size;
+
+        assert(_out.nrecords < sizeof(_out.fds) / sizeof(_out.fds[0]));
+        _out.fds[_out.nrecords] = record.fd;
+        _out.sizes[_out.nrecords] = record.size;
+        _out.nrecords++;
+
+        tail += myst_console_record_size(record.size);
+        n++;
+    }
+
+    __atomic_store_n(&_ring->tail, tail, __ATOMIC_RELEASE);
+
+    return n;
+}
+
+static volatile bool _stop;
+
+static void* _consumer(void* arg)
+{
+    (void)arg;
+
+    while (!__atomic_load_n(&_stop, __ATOMIC_ACQUIRE))
+    {
+        if (_consume() == 0)
+            usleep(100);
+    }
+
+    _consume();
+
+    return NULL;
+}
+
+/* fill buf with bytes that depend on where they are in the output */
+static void _fill(uint8_t* buf, size_t size, size_t pos)
+{
+    for (size_t i = 0; i < size; i++)
+        buf[i] = (uint8_t)((pos + i) * 7 + 3);
+}
+
+static bool _check(const uint8_t* data, size_t size, size_t pos)
+{
+    for (size_t i = 0; i < size; i++)
+    {
+        if (data[i] != (uint8_t)((pos + i) * 7 + 3))
+            return false;
+    }
+
+    return true;
+}
+
+/*
+**==============================================================================
+**
+** tests
+**
+**==============================================================================
+*/
+
+static void test_wraparound(void)
+{
+    uint8_t buf[100];
+    const uint64_t start = CAPACITY - sizeof(myst_console_record_t);
+
+    /* the header takes the last bytes of the data, and the bytes wrap */
+    _new_ring(start);
+    _fill(buf, sizeof(buf), 0);
+    assert(myst_console_ring_write(STDOUT_FILENO, buf, sizeof(buf)) == 100);
+    assert(_ring->head == start + myst_console_record_size(sizeof(buf)));
+    assert(memcmp(_ring->data, buf, sizeof(buf)) == 0);
+
+    assert(_consume() == 1);
+    assert(_out.fds[0] == STDOUT_FILENO && _out.sizes[0] == 100);
+    assert(_check(_out.data, 100, 0));
+
+    /* a record whose bytes end exactly at the end of the data */
+    _new_ring(CAPACITY - 64);
+    _fill(buf, 56, 0);
+    assert(myst_console_ring_write(STDERR_FILENO, buf, 56) == 56);
+    assert((_ring->head & (CAPACITY - 1)) == 0);
+    assert(_consume() == 1 && _check(_out.data, 56, 0));
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_ordering(void)
+{
+    static uint8_t buf[3 * MAX_RECORD];
+    pthread_t thread;
+    size_t pos = 0;
+    size_t nwrites = 0;
+    size_t sizes[] = {1, 7, 8, 100, 4000, MAX_RECORD, MAX_RECORD - 3, 9999};
+    const size_t nsizes = sizeof(sizes) / sizeof(sizes[0]);
+
+    /* many times around the ring while the host reads it */
+    _new_ring(0);
+    _stop = false;
+    assert(pthread_create(&thread, NULL, _consumer, NULL) == 0);
+
+    while (pos < 3 * CAPACITY)
+    {
+        const size_t size = sizes[nwrites % nsizes];
+        const int fd = nwrites % 2 ? STDERR_FILENO : STDOUT_FILENO;
+
+        _fill(buf, size, pos);
+        assert(myst_console_ring_write(fd, buf, size) == (long)size);
+        pos += size;
+        nwrites++;
+    }
+
+    /* a write larger than a record is split in order */
+    _fill(buf, sizeof(buf), pos);
+    assert(myst_console_ring_write(1, buf, sizeof(buf)) == sizeof(buf));
+    pos += sizeof(buf);
+
+    myst_console_ring_flush();
+    assert(_ring->tail == _ring->head);
+
+    __atomic_store_n(&_stop, true, __ATOMIC_RELEASE);
+    assert(pthread_join(thread, NULL) == 0);
+
+    /* the host saw every byte, in the order written */
+    assert(_out.size == pos);
+    assert(_check(_out.data, pos, 0));
+    assert(_out.nrecords == nwrites + 3);
+
+    for (size_t i = 0; i < nwrites; i++)
+    {
+        assert(_out.sizes[i] == sizes[i % nsizes]);
+        assert(_out.fds[i] == (i % 2 ? STDERR_FILENO : STDOUT_FILENO));
+    }
+
+    for (size_t i = nwrites; i < nwrites + 3; i++)
+        assert(_out.sizes[i] == MAX_RECORD && _out.fds[i] == STDOUT_FILENO);
+
+    assert(_direct_size == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static volatile long _write_ret;
+
+static void* _writer(void* arg)
+{
+    uint8_t* buf = arg;
+
+    _write_ret = myst_console_ring_write(STDERR_FILENO, buf, 1000);
+    return NULL;
+}
+
+static void test_full(void)
+{
+    static uint8_t buf[1000];
+    const size_t n = CAPACITY / myst_console_record_size(sizeof(buf));
+    pthread_t thread;
+    uint64_t head;
+
+    _new_ring(CAPACITY / 2);
+
+    /* fill the ring while the host does not read it */
+    for (size_t i = 0; i < n; i++)
+    {
+        _fill(buf, sizeof(buf), i * sizeof(buf));
+        assert(myst_console_ring_write(1, buf, sizeof(buf)) == sizeof(buf));
+    }
+
+    head = _ring->head;
+    assert(head - _ring->tail > CAPACITY - myst_console_record_size(1000));
+
+    /* the next write waits for room rather than overwriting */
+    _fill(buf, sizeof(buf), n * sizeof(buf));
+    _write_ret = 0;
+    assert(pthread_create(&thread, NULL, _writer, buf) == 0);
+    usleep(100000);
+    assert(_write_ret == 0 && _ring->head == head);
+
+    /* and goes on once the host has read */
+    assert(_consume() == n);
+    assert(pthread_join(thread, NULL) == 0);
+    assert(_write_ret == 1000);
+    assert(_consume() == 1);
+
+    assert(_out.nrecords == n + 1);
+    assert(_out.fds[n - 1] == STDOUT_FILENO && _out.fds[n] == STDERR_FILENO);
+    assert(_check(_out.data, (n + 1) * sizeof(buf), 0));
+    assert(_direct_size == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+/* last, as the kernel stops using a ring that stalled */
+static void test_stalled(void)
+{
+    static uint8_t buf[1000];
+    const size_t n = CAPACITY / myst_console_record_size(sizeof(buf));
+
+    _new_ring(0);
+
+    for (size_t i = 0; i < n; i++)
+    {
+        _fill(buf, sizeof(buf), i * sizeof(buf));
+        assert(myst_console_ring_write(1, buf, sizeof(buf)) == sizeof(buf));
+    }
+
+    /* the host stopped reading: the write goes to the host directly (the
+     * ring still has room for a record of up to 8 bytes) */
+    assert(myst_console_ring_write(2, "stalled!!", 9) == 9);
+    assert(_direct_size == 9 && memcmp(_direct, "stalled!!", 9) == 0);
+
+    /* what was in the ring is still all there, ahead of it */
+    assert(_consume() == n);
+    assert(_check(_out.data, n * sizeof(buf), 0));
+
+    /* and later writes do not use the ring */
+    assert(myst_console_ring_write(2, "x", 1) == -ENOTSUP);
+    assert(_ring->head == _ring->tail);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    (void)argc;
+
+    test_wraparound();
+    test_ordering();
+    test_full();
+    test_stalled();
+
+    free(_ring);
+    free(_out.data);
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
@@ -1,9 +1,12 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+#include <assert.h>
 #include <cpuid.h>
 #include <stdint.h>
 #include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
 
 void test_cpuid()
 {
@@ -21,10 +24,57 @@ void test_cpuid()
 #endif
 }
 
+/* features that cannot work in an enclave are hidden on SGX */
+void test_masked_features()
+{
+    uint32_t rax, rbx, rcx, rdx;
+
+    __cpuid_count(1, 0, rax, rbx, rcx, rdx);
+
+    /* MONITOR/MWAIT, VMX, SMX */
+    assert((rcx & ((1 << 3) | (1 << 5) | (1 << 6))) == 0);
+
+    __cpuid_count(7, 0, rax, rbx, rcx, rdx);
+
+    /* SGX, SGX_LC */
+    assert((rbx & (1 << 2)) == 0);
+    assert((rcx & (1 << 30)) == 0);
+}
+
+/* the logical processor count is the same whichever leaf reports it */
+void test_topology()
+{
+    uint32_t rax, rbx, rcx, rdx;
+    uint32_t max_leaf;
+    uint32_t count;
+
+    __cpuid_count(0, 0, max_leaf, rbx, rcx, rdx);
+    __cpuid_count(1, 0, rax, rbx, rcx, rdx);
+    count = (rbx >> 16) & 0xff;
+    assert(count >= 1);
+
+    if (max_leaf >= 0xB)
+    {
+        __cpuid_count(0xB, 1, rax, rbx, rcx, rdx);
+
+        /* leaf 0x1 saturates at 255 processors */
+        if (((rcx >> 8) & 0xff) == 2)
+            assert((rbx & 0xffff) == count || count == 0xff);
+    }
+}
+
 int main(int argc, const char* argv[])
 {
+    const char* target = getenv("MYST_TARGET");
+
     test_cpuid();
 
+    if (target && strcmp(target, "sgx") == 0)
+    {
+        test_masked_features();
+        test_topology();
+    }
+
     printf("=== passed test (%s)\n", argv[0]);
 
     return 0;
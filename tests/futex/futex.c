@@ -2,6 +2,10 @@
 // Licensed under the MIT License.
 
 #include <assert.h>
+#include <errno.h>
+#include <pthread.h>
+#include <stdatomic.h>
+#include <stddef.h>
 #include <stdint.h>
 #include <stdio.h>
 #include <sys/time.h>
@@ -10,6 +14,21 @@
 #include <unistd.h>
 
 #define FUTEX_WAIT 0
+#define FUTEX_WAITERS 0x80000000
+#define FUTEX_OWNER_DIED 0x40000000
+#define FUTEX_TID_MASK 0x3fffffff
+
+struct robust_list
+{
+    struct robust_list* next;
+};
+
+struct robust_list_head
+{
+    struct robust_list list;
+    long futex_offset;
+    struct robust_list* list_op_pending;
+};
 
 /* get the timestamp in nanoseconds */
 uint64_t timestamp_nsec(void)
@@ -52,9 +71,123 @@ void test_double_wait(void)
     printf("=== passed test (%s)\n", __FUNCTION__);
 }
 
+/* a robust futex as laid out by the C library: list entry, then the word */
+struct robust_futex
+{
+    struct robust_list list;
+    _Atomic uint32_t word;
+};
+
+static struct robust_futex _robust_futex;
+static _Atomic int _robust_ready;
+
+static void* _robust_owner(void* arg)
+{
+    struct robust_list_head head;
+    struct robust_list_head* head_ptr;
+    size_t len;
+    const uint32_t tid = (uint32_t)syscall(SYS_gettid);
+
+    (void)arg;
+
+    head.list.next = &_robust_futex.list;
+    head.futex_offset = offsetof(struct robust_futex, word);
+    head.list_op_pending = NULL;
+    _robust_futex.list.next = &head.list;
+
+    assert(syscall(SYS_set_robust_list, &head, sizeof(head) + 1) == -1);
+    assert(errno == EINVAL);
+    assert(syscall(SYS_set_robust_list, &head, sizeof(head)) == 0);
+
+    assert(syscall(SYS_get_robust_list, 0, &head_ptr, &len) == 0);
+    assert(head_ptr == &head);
+    assert(len == sizeof(head));
+
+    /* take the futex and exit without releasing it or running any of the C
+     * library's thread exit code, as if the thread were killed */
+    atomic_store(&_robust_futex.word, tid);
+    atomic_store(&_robust_ready, 1);
+    usleep(100000);
+    syscall(SYS_exit, 0);
+
+    return NULL;
+}
+
+void test_robust_list(void)
+{
+    pthread_t thread;
+    uint32_t word;
+
+    atomic_store(&_robust_ready, 0);
+    assert(pthread_create(&thread, NULL, _robust_owner, NULL) == 0);
+
+    while (!atomic_load(&_robust_ready))
+        usleep(1000);
+
+    /* announce a waiter and wait until the owner's death releases the word */
+    word = atomic_load(&_robust_futex.word);
+    while (!(word & FUTEX_OWNER_DIED))
+    {
+        if (!(word & FUTEX_WAITERS))
+        {
+            atomic_compare_exchange_strong(
+                &_robust_futex.word, &word, word | FUTEX_WAITERS);
+            continue;
+        }
+
+        syscall(SYS_futex, &_robust_futex.word, FUTEX_WAIT, word, NULL);
+        word = atomic_load(&_robust_futex.word);
+    }
+
+    /* the owner's TID was cleared and the waiters bit preserved */
+    assert((word & FUTEX_TID_MASK) == 0);
+    assert(word & FUTEX_WAITERS);
+
+    assert(pthread_join(thread, NULL) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static pthread_mutex_t _robust_mutex;
+
+static void* _robust_mutex_owner(void* arg)
+{
+    (void)arg;
+    assert(pthread_mutex_lock(&_robust_mutex) == 0);
+    return NULL;
+}
+
+void test_robust_mutex(void)
+{
+    pthread_mutexattr_t attr;
+    pthread_t thread;
+
+    assert(pthread_mutexattr_init(&attr) == 0);
+    assert(pthread_mutexattr_setrobust(&attr, PTHREAD_MUTEX_ROBUST) == 0);
+    assert(pthread_mutex_init(&_robust_mutex, &attr) == 0);
+
+    /* the thread exits while holding the mutex */
+    assert(pthread_create(&thread, NULL, _robust_mutex_owner, NULL) == 0);
+    assert(pthread_join(thread, NULL) == 0);
+
+    assert(pthread_mutex_lock(&_robust_mutex) == EOWNERDEAD);
+    assert(pthread_mutex_consistent(&_robust_mutex) == 0);
+    assert(pthread_mutex_unlock(&_robust_mutex) == 0);
+
+    assert(pthread_mutex_lock(&_robust_mutex) == 0);
+    assert(pthread_mutex_unlock(&_robust_mutex) == 0);
+
+    pthread_mutex_destroy(&_robust_mutex);
+    pthread_mutexattr_destroy(&attr);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
 int main(int argc, const char* argv[])
 {
     test_double_wait();
+    test_robust_list();
+    test_robust_mutex();
 
     printf("=== passed test (%s)\n", argv[0]);
 
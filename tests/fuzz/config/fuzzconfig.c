@@ -0,0 +1,22 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <stdint.h>
+#include <string.h>
+
+#include "config.h"
+
+/* Drive the config.json parser used by "myst package" and "myst exec" */
+int LLVMFuzzerTestOneInput(const uint8_t* data, size_t size)
+{
+    config_parsed_data_t parsed_data;
+
+    if (size == 0)
+        return 0;
+
+    memset(&parsed_data, 0, sizeof(parsed_data));
+    parse_config_from_buffer((const char*)data, size, &parsed_data);
+    free_config(&parsed_data);
+
+    return 0;
+}
@@ -0,0 +1,36 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <myst/cpio.h>
+
+/* Walk the archive the same way the kernel unpacks the CPIO rootfs */
+int LLVMFuzzerTestOneInput(const uint8_t* data, size_t size)
+{
+    size_t pos = 0;
+    myst_cpio_entry_t entry;
+    const void* file_data;
+    volatile uint8_t sum = 0;
+
+    if (!myst_is_cpio_archive(data, size))
+        return 0;
+
+    while (myst_cpio_next_entry(data, size, &pos, &entry, &file_data) == 1)
+    {
+        const uint8_t* p = (const uint8_t*)file_data;
+
+        assert(pos <= size);
+        assert(p >= data && p + entry.size <= data + size);
+        assert(strlen(entry.name) < sizeof(entry.name));
+
+        /* touch every byte so the sanitizers check the reported extent */
+        for (size_t i = 0; i < entry.size; i++)
+            sum += p[i];
+    }
+
+    return 0;
+}
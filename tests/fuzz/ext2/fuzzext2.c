@@ -0,0 +1,168 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <myst/blkdev.h>
+#include <myst/ext2.h>
+#include <myst/round.h>
+
+/*
+**==============================================================================
+**
+** memblkdev: a block device backed by a copy of the fuzzer input. Writes go
+** to the copy so that each iteration starts from the original image.
+**
+**==============================================================================
+*/
+
+typedef struct memblkdev
+{
+    myst_blkdev_t base;
+    uint8_t* data;
+    size_t nblks;
+} memblkdev_t;
+
+static int _memblkdev_close(myst_blkdev_t* dev)
+{
+    memblkdev_t* p = (memblkdev_t*)dev;
+
+    free(p->data);
+    free(p);
+    return 0;
+}
+
+static int _memblkdev_get(myst_blkdev_t* dev, uint64_t blkno, void* data)
+{
+    memblkdev_t* p = (memblkdev_t*)dev;
+
+    if (blkno >= p->nblks)
+        return -EIO;
+
+    memcpy(data, p->data + blkno * MYST_BLKSIZE, MYST_BLKSIZE);
+    return 0;
+}
+
+static int _memblkdev_put(myst_blkdev_t* dev, uint64_t blkno, const void* data)
+{
+    memblkdev_t* p = (memblkdev_t*)dev;
+
+    if (blkno >= p->nblks)
+        return -EIO;
+
+    memcpy(p->data + blkno * MYST_BLKSIZE, data, MYST_BLKSIZE);
+    return 0;
+}
+
+static myst_blkdev_t* _memblkdev_open(const uint8_t* data, size_t size)
+{
+    memblkdev_t* p;
+    size_t n;
+
+    if (myst_round_up(size, MYST_BLKSIZE, &n) != 0 || n == 0)
+        return NULL;
+
+    if (!(p = calloc(1, sizeof(memblkdev_t))))
+        return NULL;
+
+    if (!(p->data = calloc(1, n)))
+    {
+        free(p);
+        return NULL;
+    }
+
+    memcpy(p->data, data, size);
+    p->nblks = n / MYST_BLKSIZE;
+    p->base.close = _memblkdev_close;
+    p->base.get = _memblkdev_get;
+    p->base.put = _memblkdev_put;
+
+    return &p->base;
+}
+
+/*
+**==============================================================================
+**
+** harness
+**
+**==============================================================================
+*/
+
+static myst_fs_t* _fs;
+
+static int _resolve(const char* path, char suffix[PATH_MAX], myst_fs_t** fs)
+{
+    if (strlen(path) >= PATH_MAX)
+        return -ENAMETOOLONG;
+
+    strcpy(suffix, path);
+    *fs = _fs;
+    return 0;
+}
+
+/* read back every file and link reachable from the root directory */
+static void _walk(myst_fs_t* fs)
+{
+    myst_strarr_t paths = MYST_STRARR_INITIALIZER;
+    char buf[1024];
+
+    if (ext2_lsr((ext2_t*)fs, "/", &paths) != 0)
+        goto done;
+
+    for (size_t i = 0; i < paths.size; i++)
+    {
+        const char* path = paths.data[i];
+        struct stat st;
+        myst_file_t* file;
+
+        if (ext2_lstat(fs, path, &st) != 0)
+            continue;
+
+        if (S_ISLNK(st.st_mode))
+        {
+            ext2_readlink(fs, path, buf, sizeof(buf));
+            continue;
+        }
+
+        if (!S_ISREG(st.st_mode))
+            continue;
+
+        if (ext2_open(fs, path, O_RDONLY, 0, NULL, &file) != 0)
+            continue;
+
+        while (ext2_read(fs, file, buf, sizeof(buf)) > 0)
+            ;
+
+        ext2_close(fs, file);
+    }
+
+done:
+    myst_strarr_release(&paths);
+}
+
+int LLVMFuzzerTestOneInput(const uint8_t* data, size_t size)
+{
+    myst_blkdev_t* dev;
+
+    if (!(dev = _memblkdev_open(data, size)))
+        return 0;
+
+    if (ext2_create(dev, &_fs, _resolve) != 0)
+    {
+        (*dev->close)(dev);
+        return 0;
+    }
+
+    ext2_check((ext2_t*)_fs);
+    _walk(_fs);
+
+    /* releasing the file system also closes the block device */
+    ext2_release(_fs);
+    _fs = NULL;
+
+    return 0;
+}
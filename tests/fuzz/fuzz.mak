@@ -0,0 +1,75 @@
+##==============================================================================
+##
+## fuzz.mak: shared rules for the libFuzzer harnesses under tests/fuzz
+##
+## Each harness directory defines the following and then includes this file:
+##
+##     FUZZER       - name of the fuzzer executable
+##     FUZZ_SOURCES - the harness plus the sources under test (these are
+##                    rebuilt with coverage instrumentation)
+##     FUZZ_LIBS    - uninstrumented libraries that satisfy the rest
+##     SEEDS        - seed inputs copied into the corpus before fuzzing
+##
+## Targets:
+##
+##     make                - build the fuzzer
+##     make tests          - run a bounded, deterministic fuzzing session
+##     make fuzz           - fuzz until a failure is found (or interrupted)
+##     make repro CRASH=x  - replay a single failing input
+##
+##==============================================================================
+
+FUZZ_CC = clang
+
+FUZZ_CFLAGS = -g -O1 -Wall -Werror -fno-omit-frame-pointer
+FUZZ_CFLAGS += -fsanitize=fuzzer,address,undefined
+FUZZ_CFLAGS += -Wno-conversion
+FUZZ_CFLAGS += -Wno-parentheses
+
+FUZZ_INCLUDES ?= -I$(INCDIR)
+
+# Fixed seed and run count so that "make tests" explores the same inputs on
+# every invocation; override on the command line for longer campaigns.
+FUZZ_SEED ?= 1
+FUZZ_RUNS ?= 100000
+FUZZ_MAX_LEN ?= 4096
+
+__FUZZER = $(SUBBINDIR)/$(FUZZER)
+CORPUS = $(SUBOBJDIR)/corpus
+ARTIFACTS = $(SUBOBJDIR)/artifacts
+
+FUZZ_OPTS =
+FUZZ_OPTS += -seed=$(FUZZ_SEED)
+FUZZ_OPTS += -max_len=$(FUZZ_MAX_LEN)
+FUZZ_OPTS += -artifact_prefix=$(ARTIFACTS)/
+FUZZ_OPTS += -print_final_stats=1
+
+fuzzer: $(__FUZZER)
+
+$(__FUZZER): $(FUZZ_SOURCES) $(FUZZ_LIBS)
+	mkdir -p $(SUBBINDIR)
+	$(FUZZ_CC) $(FUZZ_CFLAGS) $(FUZZ_INCLUDES) -o $@ $(FUZZ_SOURCES) \
+	    $(FUZZ_LIBS)
+	@ echo "Created $(__FUZZER)"
+
+corpus: $(SEEDS)
+	mkdir -p $(CORPUS) $(ARTIFACTS)
+	$(foreach i, $(SEEDS), cp $(i) $(CORPUS) $(NL) )
+
+REDEFINE_TESTS=1
+
+CLEAN = $(__FUZZER) $(CORPUS) $(ARTIFACTS) $(SEEDS_CLEAN)
+
+include $(TOP)/rules.mak
+
+tests: $(__FUZZER) corpus
+	$(RUNTEST) $(__FUZZER) $(FUZZ_OPTS) -runs=$(FUZZ_RUNS) $(CORPUS)
+
+fuzz: $(__FUZZER) corpus
+	$(__FUZZER) $(FUZZ_OPTS) $(CORPUS)
+
+# Failing inputs are written to $(ARTIFACTS) as crash-<sha1>, leak-<sha1> or
+# timeout-<sha1>. Replaying one runs the harness exactly once on that input.
+repro: $(__FUZZER)
+	@ test -n "$(CRASH)" || ( echo "usage: make repro CRASH=<path>"; exit 1 )
+	$(__FUZZER) $(CRASH)
@@ -0,0 +1,186 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <limits.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/uio.h>
+
+#include <myst/defs.h>
+#include <myst/iov.h>
+#include <myst/paths.h>
+#include <myst/strings.h>
+#include <myst/syscall.h>
+#include <myst/timeval.h>
+
+/*
+** The first input byte selects a decoder and the remainder is its argument
+** data. These are the helpers the syscall layer applies to pathnames, iovec
+** arrays and timevals handed in from user space.
+*/
+enum
+{
+    DECODER_PATHS,
+    DECODER_IOV,
+    DECODER_TIMEVAL,
+    NUM_DECODERS,
+};
+
+/* kernel/paths.c calls this for relative paths */
+long myst_syscall_getcwd(char* buf, size_t size)
+{
+    if (myst_strlcpy(buf, "/", size) >= size)
+        return -ERANGE;
+
+    return (long)buf;
+}
+
+/* decode two strings (cwd and path) separated by a zero byte */
+static void _fuzz_paths(const uint8_t* data, size_t size)
+{
+    char* str;
+    const char* cwd;
+    const char* path;
+    char abspath[PATH_MAX];
+    char normpath[PATH_MAX];
+    char dirname[PATH_MAX];
+    char basename[PATH_MAX];
+    char buf[PATH_MAX];
+
+    if (!(str = calloc(1, size + 2)))
+        return;
+
+    memcpy(str, data, size);
+    cwd = str;
+    path = str + strlen(str) + 1;
+
+    if (myst_path_absolute_cwd(cwd, path, abspath, sizeof(abspath)) == 0)
+    {
+        if (myst_normalize(abspath, normpath, sizeof(normpath)) == 0)
+        {
+            myst_split_path(
+                normpath, dirname, sizeof(dirname), basename, sizeof(basename));
+            myst_make_path(buf, sizeof(buf), dirname, basename);
+        }
+    }
+
+    myst_path_absolute(path, buf, sizeof(buf));
+    myst_basename(path);
+
+    free(str);
+}
+
+/* decode a sequence of 16-bit lengths into an iovec array over the input */
+static void _fuzz_iov(const uint8_t* data, size_t size)
+{
+    struct iovec iov[16];
+    int iovcnt = 0;
+    size_t hdr;
+    const uint8_t* p;
+    size_t rem;
+    void* buf = NULL;
+    ssize_t n;
+
+    if (size < 1)
+        return;
+
+    iovcnt = data[0] % (MYST_COUNTOF(iov) + 1);
+    hdr = 1 + (size_t)iovcnt * sizeof(uint16_t);
+
+    if (hdr > size)
+        return;
+
+    p = data + hdr;
+    rem = size - hdr;
+
+    for (int i = 0; i < iovcnt; i++)
+    {
+        uint16_t len;
+
+        memcpy(&len, data + 1 + i * sizeof(uint16_t), sizeof(len));
+
+        /* a zero length yields a null base, which the helpers accept */
+        if (len > rem)
+            len = (uint16_t)rem;
+
+        iov[i].iov_base = len ? (void*)p : NULL;
+        iov[i].iov_len = len;
+        p += len;
+        rem -= len;
+    }
+
+    if ((n = myst_iov_gather(iov, iovcnt, &buf)) > 0)
+    {
+        uint8_t* copy;
+
+        /* scatter into private copies so the input is left untouched */
+        if ((copy = malloc((size_t)n)))
+        {
+            uint8_t* q = copy;
+
+            for (int i = 0; i < iovcnt; i++)
+            {
+                if (iov[i].iov_base)
+                {
+                    iov[i].iov_base = q;
+                    q += iov[i].iov_len;
+                }
+            }
+
+            myst_iov_scatter(iov, iovcnt, buf, (size_t)n);
+
+            if (memcmp(copy, buf, (size_t)n) != 0)
+                abort();
+
+            free(copy);
+        }
+    }
+
+    free(buf);
+}
+
+/* round-trip a timeval through its microsecond representation */
+static void _fuzz_timeval(const uint8_t* data, size_t size)
+{
+    struct timeval tv;
+    struct timeval tv2;
+    uint64_t usec;
+
+    if (size < sizeof(tv))
+        return;
+
+    memcpy(&tv, data, sizeof(tv));
+
+    if (myst_timeval_to_uint64(&tv, &usec) == 0)
+    {
+        if (myst_uint64_to_timeval(usec, &tv2) != 0)
+            abort();
+
+        if (tv.tv_usec >= 0 && tv.tv_usec < 1000000 &&
+            (tv.tv_sec != tv2.tv_sec || tv.tv_usec != tv2.tv_usec))
+            abort();
+    }
+}
+
+int LLVMFuzzerTestOneInput(const uint8_t* data, size_t size)
+{
+    if (size < 1)
+        return 0;
+
+    switch (data[0] % NUM_DECODERS)
+    {
+        case DECODER_PATHS:
+            _fuzz_paths(data + 1, size - 1);
+            break;
+        case DECODER_IOV:
+            _fuzz_iov(data + 1, size - 1);
+            break;
+        case DECODER_TIMEVAL:
+            _fuzz_timeval(data + 1, size - 1);
+            break;
+    }
+
+    return 0;
+}
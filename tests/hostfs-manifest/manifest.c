@@ -0,0 +1,174 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+/* the same host directory is mounted with and without the manifest */
+#define VERIFIED "/mnt/verified"
+#define WRITABLE "/mnt/writable"
+
+static const char _data1[] = "the first data file\n";
+static const char _data2[] = "the second data file\n";
+
+static void _mount(const char* source)
+{
+    const char* args[] = {"manifest", "data.sha256", NULL};
+
+    assert(mkdir("/mnt", 0777) == 0);
+    assert(mkdir(VERIFIED, 0777) == 0);
+    assert(mkdir(WRITABLE, 0777) == 0);
+
+    /* the manifest must be in the signed archive */
+    const char* bad_args[] = {"manifest", "no-such-manifest", NULL};
+    assert(mount(source, VERIFIED, "hostfs", 0, bad_args) == -1);
+    assert(errno == ENOENT);
+
+    assert(mount(source, VERIFIED, "hostfs", 0, args) == 0);
+    assert(mount(source, WRITABLE, "hostfs", 0, NULL) == 0);
+}
+
+static void _check_contents(const char* path, const char* expected)
+{
+    char buf[256];
+    const size_t n = strlen(expected);
+    struct stat st;
+    int fd;
+
+    assert((fd = open(path, O_RDONLY)) >= 0);
+    assert(read(fd, buf, sizeof(buf)) == n);
+    assert(memcmp(buf, expected, n) == 0);
+    assert(read(fd, buf, sizeof(buf)) == 0);
+
+    assert(pread(fd, buf, 3, 4) == 3);
+    assert(memcmp(buf, expected + 4, 3) == 0);
+
+    assert(lseek(fd, -4, SEEK_END) == n - 4);
+    assert(read(fd, buf, sizeof(buf)) == 4);
+    assert(memcmp(buf, expected + n - 4, 4) == 0);
+
+    assert(fstat(fd, &st) == 0);
+    assert(st.st_size == n);
+
+    assert(close(fd) == 0);
+}
+
+static void test_verified_reads(void)
+{
+    _check_contents(VERIFIED "/data1", _data1);
+    _check_contents(VERIFIED "/dir/data2", _data2);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_unlisted(void)
+{
+    DIR* dir;
+
+    /* files that are not in the manifest cannot be opened */
+    assert(open(VERIFIED "/unlisted", O_RDONLY) == -1 && errno == EACCES);
+
+    /* but directories can still be listed */
+    assert((dir = opendir(VERIFIED "/dir")));
+    assert(closedir(dir) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_read_only(void)
+{
+    int fd;
+
+    assert(open(VERIFIED "/data1", O_RDWR) == -1 && errno == EROFS);
+    assert(open(VERIFIED "/data1", O_WRONLY) == -1 && errno == EROFS);
+    assert(open(VERIFIED "/new", O_CREAT | O_RDONLY, 0666) == -1);
+    assert(errno == EROFS);
+    assert(creat(VERIFIED "/new", 0666) == -1 && errno == EROFS);
+    assert(unlink(VERIFIED "/data1") == -1 && errno == EROFS);
+    assert(rename(VERIFIED "/data1", VERIFIED "/x") == -1 && errno == EROFS);
+    assert(mkdir(VERIFIED "/newdir", 0777) == -1 && errno == EROFS);
+    assert(truncate(VERIFIED "/data1", 0) == -1 && errno == EROFS);
+
+    assert((fd = open(VERIFIED "/data1", O_RDONLY)) >= 0);
+    assert(write(fd, "x", 1) == -1 && errno == EBADF);
+    assert(close(fd) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_tampered(void)
+{
+    const char tampered[] = "the first data file!\n";
+    int fd;
+    int dfd;
+
+    /* a file that is open keeps the verified contents */
+    assert((fd = open(VERIFIED "/data1", O_RDONLY)) >= 0);
+
+    /* the host replaces the file */
+    {
+        int wfd;
+
+        assert((wfd = open(WRITABLE "/data1", O_WRONLY | O_TRUNC)) >= 0);
+        assert(write(wfd, tampered, strlen(tampered)) == strlen(tampered));
+        assert(close(wfd) == 0);
+    }
+
+    {
+        char buf[256];
+        const size_t n = strlen(_data1);
+
+        assert((dfd = dup(fd)) >= 0);
+        assert(read(dfd, buf, sizeof(buf)) == n);
+        assert(memcmp(buf, _data1, n) == 0);
+        assert(close(dfd) == 0);
+        assert(close(fd) == 0);
+    }
+
+    /* the next open sees the mismatch */
+    assert(open(VERIFIED "/data1", O_RDONLY) == -1 && errno == EIO);
+
+    /* the file is accepted again once restored */
+    {
+        int wfd;
+
+        assert((wfd = open(WRITABLE "/data1", O_WRONLY | O_TRUNC)) >= 0);
+        assert(write(wfd, _data1, strlen(_data1)) == strlen(_data1));
+        assert(close(wfd) == 0);
+    }
+
+    _check_contents(VERIFIED "/data1", _data1);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    if (argc != 2)
+    {
+        fprintf(stderr, "Usage: %s host-directory\n", argv[0]);
+        exit(1);
+    }
+
+    _mount(argv[1]);
+
+    test_verified_reads();
+    test_unlisted();
+    test_read_only();
+    test_tampered();
+
+    assert(umount(VERIFIED) == 0);
+    assert(umount(WRITABLE) == 0);
+
+    printf("=== passed test (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,295 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#include <myst/shm.h>
+
+#include "hostpoll.h"
+#include "poller.h"
+#include "tee.h"
+
+/*
+**==============================================================================
+**
+** what tools/myst/enc/hostpoll.c takes from the enclave
+**
+**==============================================================================
+*/
+
+static bool _is_outside(const void* ptr, size_t size)
+{
+    (void)ptr;
+    (void)size;
+    return true;
+}
+
+static const myst_tee_t _tee = {.name = "test", .is_outside = _is_outside};
+
+const myst_tee_t* myst_tee(void)
+{
+    return &_tee;
+}
+
+/*
+**==============================================================================
+**
+** tests: the poller runs on the host as it does under the enclave, and the
+** enclave side reads its table
+**
+**==============================================================================
+*/
+
+/* more than the poller takes from epoll_wait() at once */
+#define NPIPES 256
+
+static int _pipes[NPIPES][2];
+
+static void _make_pipes(void)
+{
+    for (size_t i = 0; i < NPIPES; i++)
+        assert(pipe2(_pipes[i], O_NONBLOCK) == 0);
+}
+
+/* add the read ends to the poller, as the enclave's first poll does */
+static void _watch_pipes(void)
+{
+    struct pollfd fds[NPIPES];
+
+    for (size_t i = 0; i < NPIPES; i++)
+    {
+        fds[i].fd = _pipes[i][0];
+        fds[i].events = POLLIN;
+    }
+
+    assert(poller_poll(fds, NPIPES, 0) == 0);
+}
+
+/* wait for the enclave side to see count of the pipes ready */
+static long _wait_ready(struct pollfd* fds, nfds_t nfds, long count)
+{
+    long n = 0;
+
+    for (size_t i = 0; i < 5000; i++)
+    {
+        if ((n = myst_hostpoll_ready(fds, nfds)) >= count)
+            break;
+
+        usleep(1000);
+    }
+
+    return n;
+}
+
+static void _read_byte(int fd)
+{
+    char c;
+
+    assert(read(fd, &c, 1) == 1);
+    myst_hostpoll_consume(SYS_read, fd);
+}
+
+static void test_many_descriptors(void)
+{
+    struct pollfd fds[NPIPES];
+    long count = 0;
+
+    for (size_t i = 0; i < NPIPES; i++)
+    {
+        fds[i].fd = _pipes[i][0];
+        fds[i].events = POLLIN;
+    }
+
+    assert(myst_hostpoll_ready(fds, NPIPES) == 0);
+
+    /* every other pipe becomes ready at once */
+    for (size_t i = 0; i < NPIPES; i += 2)
+    {
+        assert(write(_pipes[i][1], "x", 1) == 1);
+        count++;
+    }
+
+    assert(_wait_ready(fds, NPIPES, count) == count);
+
+    for (size_t i = 0; i < NPIPES; i++)
+        assert(fds[i].revents == (i % 2 ? 0 : POLLIN));
+
+    /* only the events asked for are reported */
+    for (size_t i = 0; i < NPIPES; i++)
+        fds[i].events = POLLOUT;
+
+    assert(myst_hostpoll_ready(fds, NPIPES) == 0);
+
+    for (size_t i = 0; i < NPIPES; i++)
+        fds[i].events = POLLIN;
+
+    /* a read forgets what the poller saw, so the pipes are not ready */
+    for (size_t i = 0; i < NPIPES; i += 2)
+        _read_byte(_pipes[i][0]);
+
+    assert(myst_hostpoll_ready(fds, NPIPES) == 0);
+    usleep(10000);
+    assert(myst_hostpoll_ready(fds, NPIPES) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static volatile bool _stop;
+
+/* keeps the poller busy with the pipes that the test does not look at */
+static void* _writer(void* arg)
+{
+    char buf[64];
+
+    (void)arg;
+
+    while (!_stop)
+    {
+        for (size_t i = 1; i < NPIPES; i++)
+        {
+            if (write(_pipes[i][1], "x", 1) == 1 &&
+                read(_pipes[i][0], buf, sizeof(buf)) > 0)
+            {
+                myst_hostpoll_consume(SYS_read, _pipes[i][0]);
+            }
+        }
+    }
+
+    return NULL;
+}
+
+static void test_stale_results(void)
+{
+    pthread_t thread;
+    struct pollfd fd = {.fd = _pipes[0][0], .events = POLLIN};
+
+    _stop = false;
+    assert(pthread_create(&thread, NULL, _writer, NULL) == 0);
+
+    /* a result that the poller took before a read never outlives it */
+    for (size_t i = 0; i < 2000; i++)
+    {
+        assert(write(_pipes[0][1], "x", 1) == 1);
+        assert(_wait_ready(&fd, 1, 1) == 1);
+        assert(fd.revents == POLLIN);
+
+        _read_byte(_pipes[0][0]);
+
+        for (size_t j = 0; j < 100; j++)
+            assert(myst_hostpoll_ready(&fd, 1) == 0);
+    }
+
+    _stop = true;
+    assert(pthread_join(thread, NULL) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static volatile long _poll_ret = 1;
+
+static void* _blocked_poll(void* arg)
+{
+    struct pollfd fd = {.fd = _pipes[0][0], .events = POLLIN};
+
+    (void)arg;
+    _poll_ret = poller_poll(&fd, 1, -1);
+
+    return NULL;
+}
+
+static void test_wakeup(void)
+{
+    struct pollfd fd = {.fd = _pipes[0][0], .events = POLLIN};
+    pthread_t thread;
+
+    /* a wakeup makes a blocked poll return with nothing ready */
+    assert(pthread_create(&thread, NULL, _blocked_poll, NULL) == 0);
+    usleep(20000);
+    assert(_poll_ret == 1);
+    assert(poller_wake() == 0);
+    assert(pthread_join(thread, NULL) == 0);
+    assert(_poll_ret == 0);
+
+    /* it was consumed: the next poll waits for its timeout */
+    assert(poller_poll(&fd, 1, 10) == 0);
+
+    /* a wakeup before the poll is not lost */
+    assert(poller_wake() == 0);
+    assert(poller_poll(&fd, 1, -1) == 0);
+
+    /* and readiness is still reported alongside a wakeup */
+    assert(write(_pipes[0][1], "x", 1) == 1);
+    assert(poller_wake() == 0);
+    assert(poller_poll(&fd, 1, -1) == 1 && fd.revents == POLLIN);
+    _read_byte(_pipes[0][0]);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_close_and_reuse(void)
+{
+    for (size_t i = 0; i < 500; i++)
+    {
+        int p[2];
+        const int fd = _pipes[0][0];
+        struct pollfd pfd = {.fd = fd, .events = POLLIN};
+
+        /* the pipe is ready, and the poller has seen it */
+        assert(write(_pipes[0][1], "x", 1) == 1);
+        assert(_wait_ready(&pfd, 1, 1) == 1);
+
+        /* the enclave closes it and the number is taken by an empty pipe */
+        poller_forget(fd);
+        assert(close(fd) == 0);
+        assert(close(_pipes[0][1]) == 0);
+        assert(pipe2(p, O_NONBLOCK) == 0);
+        assert(p[0] == fd);
+
+        /* readiness of the old pipe is not reported for the new one */
+        assert(myst_hostpoll_ready(&pfd, 1) == 0);
+
+        /* nor after the new one is polled, until it is ready itself */
+        assert(poller_poll(&pfd, 1, 0) == 0);
+        usleep(100);
+        assert(myst_hostpoll_ready(&pfd, 1) == 0);
+
+        assert(write(p[1], "x", 1) == 1);
+        assert(_wait_ready(&pfd, 1, 1) == 1);
+        _read_byte(p[0]);
+
+        _pipes[0][0] = p[0];
+        _pipes[0][1] = p[1];
+    }
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    struct myst_shm shm;
+
+    (void)argc;
+
+    memset(&shm, 0, sizeof(shm));
+    assert(poller_start(&shm) == 0);
+    assert(myst_setup_hostpoll(shm.poll_events, shm.poll_nfds) == 0);
+
+    _make_pipes();
+    _watch_pipes();
+
+    test_many_descriptors();
+    test_stale_results();
+    test_wakeup();
+    test_close_and_reuse();
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
@@ -0,0 +1,752 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/socket.h>
+#include <sys/syscall.h>
+#include <sys/uio.h>
+#include <unistd.h>
+
+/* the io_uring ABI (no liburing or kernel headers in the test image) */
+
+#ifndef SYS_io_uring_setup
+#define SYS_io_uring_setup 425
+#define SYS_io_uring_enter 426
+#define SYS_io_uring_register 427
+#endif
+
+#define IORING_OFF_SQ_RING 0ULL
+#define IORING_OFF_CQ_RING 0x8000000ULL
+#define IORING_OFF_SQES 0x10000000ULL
+
+#define IORING_SETUP_SQPOLL (1U << 1)
+#define IORING_SETUP_CQSIZE (1U << 3)
+
+#define IORING_FEAT_SINGLE_MMAP (1U << 0)
+
+#define IORING_ENTER_GETEVENTS (1U << 0)
+
+#define IOSQE_IO_DRAIN (1U << 1)
+#define IOSQE_IO_LINK (1U << 2)
+
+#define IORING_OP_NOP 0
+#define IORING_OP_READV 1
+#define IORING_OP_WRITEV 2
+#define IORING_OP_FSYNC 3
+#define IORING_OP_ACCEPT 13
+#define IORING_OP_READ 22
+#define IORING_OP_WRITE 23
+
+#define IORING_REGISTER_PROBE 8
+#define IO_URING_OP_SUPPORTED (1U << 0)
+
+struct io_sqring_offsets
+{
+    uint32_t head, tail, ring_mask, ring_entries, flags, dropped, array;
+    uint32_t resv1;
+    uint64_t resv2;
+};
+
+struct io_cqring_offsets
+{
+    uint32_t head, tail, ring_mask, ring_entries, overflow, cqes, flags;
+    uint32_t resv1;
+    uint64_t resv2;
+};
+
+struct io_uring_params
+{
+    uint32_t sq_entries;
+    uint32_t cq_entries;
+    uint32_t flags;
+    uint32_t sq_thread_cpu;
+    uint32_t sq_thread_idle;
+    uint32_t features;
+    uint32_t wq_fd;
+    uint32_t resv[3];
+    struct io_sqring_offsets sq_off;
+    struct io_cqring_offsets cq_off;
+};
+
+struct io_uring_sqe
+{
+    uint8_t opcode;
+    uint8_t flags;
+    uint16_t ioprio;
+    int32_t fd;
+    uint64_t off;
+    uint64_t addr;
+    uint32_t len;
+    uint32_t op_flags;
+    uint64_t user_data;
+    uint64_t pad[3];
+};
+
+struct io_uring_cqe
+{
+    uint64_t user_data;
+    int32_t res;
+    uint32_t flags;
+};
+
+struct io_uring_probe_op
+{
+    uint8_t op;
+    uint8_t resv;
+    uint16_t flags;
+    uint32_t resv2;
+};
+
+struct io_uring_probe
+{
+    uint8_t last_op;
+    uint8_t ops_len;
+    uint16_t resv;
+    uint32_t resv2[3];
+    struct io_uring_probe_op ops[];
+};
+
+typedef struct ring
+{
+    int fd;
+    struct io_uring_params params;
+    uint8_t* sq_ptr;
+    size_t sq_size;
+    uint8_t* cq_ptr;
+    size_t cq_size;
+    struct io_uring_sqe* sqes;
+    size_t sqes_size;
+} ring_t;
+
+static int _setup(uint32_t entries, struct io_uring_params* params)
+{
+    return (int)syscall(SYS_io_uring_setup, entries, params);
+}
+
+static int _enter(ring_t* r, uint32_t submit, uint32_t wait, uint32_t flags)
+{
+    return (int)syscall(
+        SYS_io_uring_enter, r->fd, submit, wait, flags, NULL, 0);
+}
+
+static uint32_t* _sq(ring_t* r, uint32_t off)
+{
+    return (uint32_t*)(r->sq_ptr + off);
+}
+
+static uint32_t* _cq(ring_t* r, uint32_t off)
+{
+    return (uint32_t*)(r->cq_ptr + off);
+}
+
+static void _init(ring_t* r, uint32_t entries)
+{
+    struct io_uring_params* p = &r->params;
+
+    memset(r, 0, sizeof(ring_t));
+    assert((r->fd = _setup(entries, p)) >= 0);
+
+    r->sq_size = p->sq_off.array + p->sq_entries * sizeof(uint32_t);
+    r->cq_size = p->cq_off.cqes + p->cq_entries * sizeof(struct io_uring_cqe);
+
+    if ((p->features & IORING_FEAT_SINGLE_MMAP))
+    {
+        if (r->cq_size > r->sq_size)
+            r->sq_size = r->cq_size;
+        r->cq_size = r->sq_size;
+    }
+
+    r->sq_ptr = mmap(
+        NULL,
+        r->sq_size,
+        PROT_READ | PROT_WRITE,
+        MAP_SHARED | MAP_POPULATE,
+        r->fd,
+        IORING_OFF_SQ_RING);
+    assert(r->sq_ptr != MAP_FAILED);
+
+    if ((p->features & IORING_FEAT_SINGLE_MMAP))
+    {
+        r->cq_ptr = r->sq_ptr;
+    }
+    else
+    {
+        r->cq_ptr = mmap(
+            NULL,
+            r->cq_size,
+            PROT_READ | PROT_WRITE,
+            MAP_SHARED | MAP_POPULATE,
+            r->fd,
+            IORING_OFF_CQ_RING);
+        assert(r->cq_ptr != MAP_FAILED);
+    }
+
+    r->sqes_size = p->sq_entries * sizeof(struct io_uring_sqe);
+    r->sqes = mmap(
+        NULL,
+        r->sqes_size,
+        PROT_READ | PROT_WRITE,
+        MAP_SHARED | MAP_POPULATE,
+        r->fd,
+        IORING_OFF_SQES);
+    assert(r->sqes != MAP_FAILED);
+}
+
+static void _fini(ring_t* r)
+{
+    munmap(r->sqes, r->sqes_size);
+
+    if (r->cq_ptr != r->sq_ptr)
+        munmap(r->cq_ptr, r->cq_size);
+
+    munmap(r->sq_ptr, r->sq_size);
+    close(r->fd);
+}
+
+/* queue an SQE (the caller submits with _enter()) */
+static struct io_uring_sqe* _get_sqe(ring_t* r)
+{
+    struct io_uring_params* p = &r->params;
+    const uint32_t mask = *_sq(r, p->sq_off.ring_mask);
+    const uint32_t head = __atomic_load_n(_sq(r, p->sq_off.head), __ATOMIC_ACQUIRE);
+    const uint32_t tail = *_sq(r, p->sq_off.tail);
+    const uint32_t index = tail & mask;
+    struct io_uring_sqe* sqe;
+
+    assert(tail - head < p->sq_entries);
+
+    sqe = &r->sqes[index];
+    memset(sqe, 0, sizeof(*sqe));
+    _sq(r, p->sq_off.array)[index] = index;
+    __atomic_store_n(_sq(r, p->sq_off.tail), tail + 1, __ATOMIC_RELEASE);
+
+    return sqe;
+}
+
+static void _prep(
+    struct io_uring_sqe* sqe,
+    uint8_t opcode,
+    int fd,
+    const void* addr,
+    uint32_t len,
+    uint64_t off,
+    uint64_t user_data)
+{
+    sqe->opcode = opcode;
+    sqe->fd = fd;
+    sqe->addr = (uint64_t)addr;
+    sqe->len = len;
+    sqe->off = off;
+    sqe->user_data = user_data;
+}
+
+/* the number of CQEs waiting to be consumed */
+static uint32_t _cq_ready(ring_t* r)
+{
+    struct io_uring_params* p = &r->params;
+    const uint32_t tail = __atomic_load_n(_cq(r, p->cq_off.tail), __ATOMIC_ACQUIRE);
+    return tail - *_cq(r, p->cq_off.head);
+}
+
+/* consume the next CQE */
+static struct io_uring_cqe _get_cqe(ring_t* r)
+{
+    struct io_uring_params* p = &r->params;
+    const uint32_t mask = *_cq(r, p->cq_off.ring_mask);
+    const uint32_t head = *_cq(r, p->cq_off.head);
+    struct io_uring_cqe* cqes = (void*)(r->cq_ptr + p->cq_off.cqes);
+    struct io_uring_cqe cqe;
+
+    assert(_cq_ready(r) > 0);
+    cqe = cqes[head & mask];
+    __atomic_store_n(_cq(r, p->cq_off.head), head + 1, __ATOMIC_RELEASE);
+
+    return cqe;
+}
+
+static void _expect_cqe(ring_t* r, uint64_t user_data, int32_t res)
+{
+    struct io_uring_cqe cqe = _get_cqe(r);
+
+    if (cqe.user_data != user_data || cqe.res != res)
+    {
+        fprintf(
+            stderr,
+            "unexpected cqe: user_data=%lu res=%d (expected %lu %d)\n",
+            (unsigned long)cqe.user_data,
+            cqe.res,
+            (unsigned long)user_data,
+            res);
+        assert(0);
+    }
+}
+
+/* consume n CQEs (in any order) and store their results by user_data */
+static void _get_results(ring_t* r, size_t n, int32_t* results, size_t size)
+{
+    for (size_t i = 0; i < n; i++)
+    {
+        struct io_uring_cqe cqe = _get_cqe(r);
+        assert(cqe.user_data < size);
+        results[cqe.user_data] = cqe.res;
+    }
+}
+
+void test_setup(void)
+{
+    struct io_uring_params p;
+    int fd;
+
+    /* entries are rounded up to a power of two; the CQ is twice the SQ */
+    memset(&p, 0, sizeof(p));
+    assert((fd = _setup(5, &p)) >= 0);
+    assert(p.sq_entries == 8);
+    assert(p.cq_entries == 16);
+    assert(p.features & IORING_FEAT_SINGLE_MMAP);
+    assert(fcntl(fd, F_GETFD) == FD_CLOEXEC);
+    close(fd);
+
+    memset(&p, 0, sizeof(p));
+    p.flags = IORING_SETUP_CQSIZE;
+    p.cq_entries = 100;
+    assert((fd = _setup(4, &p)) >= 0);
+    assert(p.sq_entries == 4);
+    assert(p.cq_entries == 128);
+    close(fd);
+
+    memset(&p, 0, sizeof(p));
+    assert(_setup(0, &p) == -1 && errno == EINVAL);
+
+    memset(&p, 0, sizeof(p));
+    p.resv[0] = 1;
+    assert(_setup(4, &p) == -1 && errno == EINVAL);
+
+    /* no kernel submission thread */
+    memset(&p, 0, sizeof(p));
+    p.flags = IORING_SETUP_SQPOLL;
+    assert(_setup(4, &p) == -1 && errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+void test_probe(void)
+{
+    ring_t r;
+    const size_t n = 64;
+    struct io_uring_probe* probe;
+
+    _init(&r, 4);
+
+    probe = calloc(1, sizeof(*probe) + n * sizeof(probe->ops[0]));
+    assert(probe);
+
+    assert(syscall(SYS_io_uring_register, r.fd, IORING_REGISTER_PROBE, probe, n) == 0);
+    assert(probe->last_op >= IORING_OP_WRITE);
+    assert(probe->ops_len > IORING_OP_WRITE);
+    assert(probe->ops[IORING_OP_NOP].flags & IO_URING_OP_SUPPORTED);
+    assert(probe->ops[IORING_OP_READ].flags & IO_URING_OP_SUPPORTED);
+    assert(probe->ops[IORING_OP_WRITE].flags & IO_URING_OP_SUPPORTED);
+    assert(probe->ops[IORING_OP_FSYNC].flags & IO_URING_OP_SUPPORTED);
+    assert(probe->ops[IORING_OP_ACCEPT].flags & IO_URING_OP_SUPPORTED);
+
+    free(probe);
+    _fini(&r);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+void test_file_io(void)
+{
+    ring_t r;
+    const char path[] = "/tmp/io_uring_file";
+    const char data[] = "hello io_uring";
+    char buf[sizeof(data)];
+    char a[6], b[9];
+    struct iovec iov[2] = {{a, sizeof(a)}, {b, sizeof(b)}};
+    struct io_uring_sqe* sqe;
+    int fd;
+
+    _init(&r, 8);
+    assert((fd = open(path, O_CREAT | O_TRUNC | O_RDWR, 0666)) >= 0);
+
+    /* write at an offset, then fsync, as one linked batch */
+    sqe = _get_sqe(&r);
+    _prep(sqe, IORING_OP_WRITE, fd, data, sizeof(data), 0, 1);
+    sqe->flags = IOSQE_IO_LINK;
+    sqe = _get_sqe(&r);
+    _prep(sqe, IORING_OP_FSYNC, fd, NULL, 0, 0, 2);
+    sqe->flags = IOSQE_IO_LINK;
+    _prep(_get_sqe(&r), IORING_OP_NOP, -1, NULL, 0, 0, 3);
+    assert(_enter(&r, 3, 3, IORING_ENTER_GETEVENTS) == 3);
+    _expect_cqe(&r, 1, sizeof(data));
+    _expect_cqe(&r, 2, 0);
+    _expect_cqe(&r, 3, 0);
+
+    /* read it back with a positioned read */
+    memset(buf, 0, sizeof(buf));
+    _prep(_get_sqe(&r), IORING_OP_READ, fd, buf, sizeof(buf), 0, 4);
+    assert(_enter(&r, 1, 1, IORING_ENTER_GETEVENTS) == 1);
+    _expect_cqe(&r, 4, sizeof(data));
+    assert(memcmp(buf, data, sizeof(data)) == 0);
+
+    /* scatter read from offset 0 */
+    _prep(_get_sqe(&r), IORING_OP_READV, fd, iov, 2, 0, 5);
+    assert(_enter(&r, 1, 1, IORING_ENTER_GETEVENTS) == 1);
+    _expect_cqe(&r, 5, sizeof(a) + sizeof(b));
+    assert(memcmp(a, "hello ", sizeof(a)) == 0);
+    assert(memcmp(b, "io_uring", 8) == 0);
+
+    /* an offset of -1 uses (and advances) the file position */
+    assert(lseek(fd, 6, SEEK_SET) == 6);
+    memset(buf, 0, sizeof(buf));
+    _prep(_get_sqe(&r), IORING_OP_READ, fd, buf, 8, (uint64_t)-1, 6);
+    assert(_enter(&r, 1, 1, IORING_ENTER_GETEVENTS) == 1);
+    _expect_cqe(&r, 6, 8);
+    assert(memcmp(buf, "io_uring", 8) == 0);
+    assert(lseek(fd, 0, SEEK_CUR) == 14);
+
+    /* gather write */
+    _prep(_get_sqe(&r), IORING_OP_WRITEV, fd, iov, 2, 100, 7);
+    assert(_enter(&r, 1, 1, IORING_ENTER_GETEVENTS) == 1);
+    _expect_cqe(&r, 7, sizeof(a) + sizeof(b));
+    assert(pread(fd, buf, sizeof(a), 100) == sizeof(a));
+    assert(memcmp(buf, "hello ", sizeof(a)) == 0);
+
+    close(fd);
+    unlink(path);
+    _fini(&r);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+void test_errors(void)
+{
+    ring_t r;
+    char buf[8];
+    struct io_uring_sqe* sqe;
+    int32_t results[4];
+
+    _init(&r, 4);
+
+    /* a failed link cancels the rest of the chain */
+    sqe = _get_sqe(&r);
+    _prep(sqe, IORING_OP_READ, 999, buf, sizeof(buf), 0, 1);
+    sqe->flags = IOSQE_IO_LINK;
+    _prep(_get_sqe(&r), IORING_OP_NOP, -1, NULL, 0, 0, 2);
+    _prep(_get_sqe(&r), IORING_OP_NOP, -1, NULL, 0, 0, 3);
+    assert(_enter(&r, 3, 3, IORING_ENTER_GETEVENTS) == 3);
+    _get_results(&r, 3, results, 4);
+    assert(results[1] == -EBADF);
+    assert(results[2] == -ECANCELED);
+    assert(results[3] == 0);
+
+    _fini(&r);
+
+    /* bad ring descriptor */
+    assert(syscall(SYS_io_uring_enter, 0, 0, 0, 0, NULL, 0) == -1);
+    assert(errno == EBADF || errno == EOPNOTSUPP);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+void test_lifetime(void)
+{
+    ring_t r;
+
+    _init(&r, 4);
+
+    /* the rings stay mapped after the descriptor is closed */
+    close(r.fd);
+    assert(*_sq(&r, r.params.sq_off.ring_entries) == r.params.sq_entries);
+    assert(*_cq(&r, r.params.cq_off.ring_entries) == r.params.cq_entries);
+    r.sqes[0].user_data = 1;
+
+    assert(munmap(r.sqes, r.sqes_size) == 0);
+
+    if (r.cq_ptr != r.sq_ptr)
+        assert(munmap(r.cq_ptr, r.cq_size) == 0);
+
+    assert(munmap(r.sq_ptr, r.sq_size) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static volatile int _handled;
+static volatile int _handler_blocked_usr2;
+
+static void _sigusr1_handler(int sig)
+{
+    sigset_t set;
+
+    (void)sig;
+    assert(sigprocmask(SIG_BLOCK, NULL, &set) == 0);
+    _handler_blocked_usr2 = sigismember(&set, SIGUSR2);
+    _handled = 1;
+}
+
+typedef struct signaller
+{
+    pthread_t thread;
+    int fd;
+} signaller_t;
+
+static void* _signaller(void* arg)
+{
+    signaller_t* s = arg;
+
+    usleep(100000);
+    assert(pthread_kill(s->thread, SIGUSR1) == 0);
+    assert(write(s->fd, "ping", 4) == 4);
+    return NULL;
+}
+
+void test_sigmask(void)
+{
+    ring_t r;
+    sigset_t set;
+    sigset_t old;
+    uint64_t mask = 0;
+    int32_t results[2];
+    struct sigaction sa;
+    struct sigaction old_sa;
+    signaller_t s;
+    pthread_t thread;
+    int fds[2];
+    char buf[4];
+
+    _init(&r, 4);
+
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    assert(sigprocmask(SIG_BLOCK, &set, &old) == 0);
+
+    /* the mask applies while waiting, and the old one is restored */
+    _prep(_get_sqe(&r), IORING_OP_NOP, -1, NULL, 0, 0, 1);
+    assert(
+        syscall(
+            SYS_io_uring_enter,
+            r.fd,
+            1,
+            1,
+            IORING_ENTER_GETEVENTS,
+            &mask,
+            sizeof(mask)) == 1);
+    _get_results(&r, 1, results, 2);
+    assert(results[1] == 0);
+    assert(sigprocmask(SIG_BLOCK, NULL, &set) == 0);
+    assert(sigismember(&set, SIGUSR1));
+
+    /* the mask is a kernel sigset_t (64 bits) */
+    assert(
+        syscall(
+            SYS_io_uring_enter,
+            r.fd,
+            0,
+            1,
+            IORING_ENTER_GETEVENTS,
+            &mask,
+            sizeof(int)) == -1);
+    assert(errno == EINVAL);
+
+    /* a signal let in while waiting is handled with the mask of the wait,
+     * then the original mask is restored */
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_handler = _sigusr1_handler;
+    assert(sigaction(SIGUSR1, &sa, &old_sa) == 0);
+    sigaddset(&set, SIGUSR2);
+    assert(sigprocmask(SIG_BLOCK, &set, NULL) == 0);
+    assert(pipe(fds) == 0);
+
+    _prep(_get_sqe(&r), IORING_OP_READ, fds[0], buf, sizeof(buf), 0, 2);
+    assert(_enter(&r, 1, 0, 0) == 1);
+    s.thread = pthread_self();
+    s.fd = fds[1];
+    assert(pthread_create(&thread, NULL, _signaller, &s) == 0);
+    syscall(
+        SYS_io_uring_enter,
+        r.fd,
+        0,
+        1,
+        IORING_ENTER_GETEVENTS,
+        &mask,
+        sizeof(mask));
+    assert(pthread_join(thread, NULL) == 0);
+    assert(_handled);
+    assert(!_handler_blocked_usr2);
+    assert(sigprocmask(SIG_BLOCK, NULL, &set) == 0);
+    assert(sigismember(&set, SIGUSR1));
+    assert(sigismember(&set, SIGUSR2));
+
+    /* the read completes either way */
+    if (_cq_ready(&r) == 0)
+        assert(_enter(&r, 0, 1, IORING_ENTER_GETEVENTS) == 0);
+    _expect_cqe(&r, 2, 4);
+
+    close(fds[0]);
+    close(fds[1]);
+    assert(sigaction(SIGUSR1, &old_sa, NULL) == 0);
+    assert(sigprocmask(SIG_SETMASK, &old, NULL) == 0);
+    _fini(&r);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void* _writer(void* arg)
+{
+    int fd = *(int*)arg;
+
+    usleep(100000);
+    assert(write(fd, "ping", 4) == 4);
+    return NULL;
+}
+
+void test_pipe(void)
+{
+    ring_t r;
+    int fds[2];
+    char buf[16];
+    pthread_t thread;
+    struct io_uring_sqe* sqe;
+
+    _init(&r, 4);
+    assert(pipe(fds) == 0);
+
+    /* a read on an empty pipe completes once data arrives */
+    memset(buf, 0, sizeof(buf));
+    _prep(_get_sqe(&r), IORING_OP_READ, fds[0], buf, sizeof(buf), 0, 1);
+    assert(_enter(&r, 1, 0, 0) == 1);
+    assert(_cq_ready(&r) == 0);
+
+    assert(pthread_create(&thread, NULL, _writer, &fds[1]) == 0);
+    assert(_enter(&r, 0, 1, IORING_ENTER_GETEVENTS) == 0);
+    _expect_cqe(&r, 1, 4);
+    assert(memcmp(buf, "ping", 4) == 0);
+    assert(pthread_join(thread, NULL) == 0);
+
+    /* write and read in one batch */
+    _prep(_get_sqe(&r), IORING_OP_WRITE, fds[1], "pong", 4, 0, 2);
+    _prep(_get_sqe(&r), IORING_OP_READ, fds[0], buf, sizeof(buf), 0, 3);
+    assert(_enter(&r, 2, 2, IORING_ENTER_GETEVENTS) == 2);
+    _expect_cqe(&r, 2, 4);
+    _expect_cqe(&r, 3, 4);
+    assert(memcmp(buf, "pong", 4) == 0);
+
+    /* a link that would block is deferred as a whole, in order */
+    sqe = _get_sqe(&r);
+    _prep(sqe, IORING_OP_READ, fds[0], buf, sizeof(buf), 0, 4);
+    sqe->flags = IOSQE_IO_LINK;
+    _prep(_get_sqe(&r), IORING_OP_NOP, -1, NULL, 0, 0, 5);
+    assert(_enter(&r, 2, 0, 0) == 2);
+    assert(_cq_ready(&r) == 0);
+    assert(write(fds[1], "link", 4) == 4);
+    assert(_enter(&r, 0, 2, IORING_ENTER_GETEVENTS) == 0);
+    _expect_cqe(&r, 4, 4);
+    _expect_cqe(&r, 5, 0);
+
+    /* a drained operation waits for the ones submitted before it */
+    _prep(_get_sqe(&r), IORING_OP_READ, fds[0], buf, sizeof(buf), 0, 6);
+    sqe = _get_sqe(&r);
+    _prep(sqe, IORING_OP_NOP, -1, NULL, 0, 0, 7);
+    sqe->flags = IOSQE_IO_DRAIN;
+    _prep(_get_sqe(&r), IORING_OP_NOP, -1, NULL, 0, 0, 8);
+    assert(_enter(&r, 3, 0, 0) == 3);
+    assert(_cq_ready(&r) == 0);
+    assert(write(fds[1], "done", 4) == 4);
+    assert(_enter(&r, 0, 3, IORING_ENTER_GETEVENTS) == 0);
+    _expect_cqe(&r, 6, 4);
+    _expect_cqe(&r, 7, 0);
+    _expect_cqe(&r, 8, 0);
+
+    close(fds[0]);
+    close(fds[1]);
+    _fini(&r);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void* _connector(void* arg)
+{
+    struct sockaddr_in* addr = arg;
+    int sock;
+
+    assert((sock = socket(AF_INET, SOCK_STREAM, 0)) >= 0);
+    assert(connect(sock, (struct sockaddr*)addr, sizeof(*addr)) == 0);
+    assert(write(sock, "x", 1) == 1);
+    close(sock);
+
+    return NULL;
+}
+
+void test_accept(void)
+{
+    ring_t r;
+    int lsock;
+    struct sockaddr_in addr;
+    socklen_t addrlen = sizeof(addr);
+    struct sockaddr_in peer;
+    socklen_t peerlen = sizeof(peer);
+    struct io_uring_sqe* sqe;
+    struct io_uring_cqe cqe;
+    pthread_t thread;
+    char c;
+
+    _init(&r, 4);
+
+    assert((lsock = socket(AF_INET, SOCK_STREAM, 0)) >= 0);
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    addr.sin_port = 0;
+    assert(bind(lsock, (struct sockaddr*)&addr, sizeof(addr)) == 0);
+    assert(listen(lsock, 1) == 0);
+    assert(getsockname(lsock, (struct sockaddr*)&addr, &addrlen) == 0);
+
+    /* the accept completes once the client connects */
+    sqe = _get_sqe(&r);
+    _prep(sqe, IORING_OP_ACCEPT, lsock, &peer, 0, (uint64_t)&peerlen, 1);
+    assert(_enter(&r, 1, 0, 0) == 1);
+
+    assert(pthread_create(&thread, NULL, _connector, &addr) == 0);
+    assert(_enter(&r, 0, 1, IORING_ENTER_GETEVENTS) == 0);
+
+    cqe = _get_cqe(&r);
+    assert(cqe.user_data == 1);
+    assert(cqe.res >= 0);
+    assert(peer.sin_family == AF_INET);
+
+    assert(read(cqe.res, &c, 1) == 1 && c == 'x');
+    assert(pthread_join(thread, NULL) == 0);
+
+    close(cqe.res);
+    close(lsock);
+    _fini(&r);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_setup();
+    test_probe();
+    test_file_io();
+    test_errors();
+    test_lifetime();
+    test_sigmask();
+    test_pipe();
+    test_accept();
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
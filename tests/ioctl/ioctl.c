@@ -0,0 +1,128 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/epoll.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+#include <termios.h>
+#include <unistd.h>
+
+/* a request that none of the devices below support */
+static void _assert_enotty(int fd)
+{
+    struct termios t;
+
+    assert(ioctl(fd, TCGETS, &t) == -1);
+    assert(errno == ENOTTY);
+    assert(!isatty(fd));
+}
+
+static void test_unknown(void)
+{
+    int fd;
+    int pipefd[2];
+    int sv[2];
+
+    assert((fd = open("/ioctl.txt", O_CREAT | O_RDWR, 0644)) >= 0);
+    _assert_enotty(fd);
+    close(fd);
+    unlink("/ioctl.txt");
+
+    assert(pipe(pipefd) == 0);
+    _assert_enotty(pipefd[0]);
+    _assert_enotty(pipefd[1]);
+    close(pipefd[0]);
+    close(pipefd[1]);
+
+    assert((fd = epoll_create1(0)) >= 0);
+    _assert_enotty(fd);
+    close(fd);
+
+    assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+    _assert_enotty(sv[0]);
+    close(sv[0]);
+    close(sv[1]);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_cloexec(void)
+{
+    int pipefd[2];
+
+    /* these apply to any file descriptor */
+    assert(pipe(pipefd) == 0);
+    assert(ioctl(pipefd[0], FIOCLEX) == 0);
+    assert(fcntl(pipefd[0], F_GETFD) & FD_CLOEXEC);
+    assert(ioctl(pipefd[0], FIONCLEX) == 0);
+    assert(!(fcntl(pipefd[0], F_GETFD) & FD_CLOEXEC));
+    close(pipefd[0]);
+    close(pipefd[1]);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_nonblock(void)
+{
+    int sv[2];
+    int master;
+    int on = 1;
+    int off = 0;
+
+    /* these set the flags of the file descriptor, as fcntl() does */
+    assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+    assert(ioctl(sv[0], FIONBIO, &on) == 0);
+    assert(fcntl(sv[0], F_GETFL) & O_NONBLOCK);
+    assert(ioctl(sv[0], FIONBIO, &off) == 0);
+    assert(!(fcntl(sv[0], F_GETFL) & O_NONBLOCK));
+    assert(ioctl(sv[0], FIONBIO, NULL) == -1 && errno == EFAULT);
+    close(sv[0]);
+    close(sv[1]);
+
+    assert((master = posix_openpt(O_RDWR | O_NOCTTY)) >= 0);
+    assert(ioctl(master, FIONBIO, &on) == 0);
+    assert(fcntl(master, F_GETFL) & O_NONBLOCK);
+    assert(ioctl(master, FIONBIO, &off) == 0);
+    assert(!(fcntl(master, F_GETFL) & O_NONBLOCK));
+    close(master);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_fault(void)
+{
+    int master;
+    int n;
+
+    assert((master = posix_openpt(O_RDWR | O_NOCTTY)) >= 0);
+
+    /* a supported request with an argument that is not user memory */
+    assert(ioctl(master, TCGETS, (void*)8) == -1);
+    assert(errno == EFAULT);
+    assert(ioctl(master, FIONREAD, NULL) == -1);
+    assert(errno == EFAULT);
+
+    assert(ioctl(master, FIONREAD, &n) == 0);
+    assert(n == 0);
+    close(master);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_unknown();
+    test_cloexec();
+    test_nonblock();
+    test_fault();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
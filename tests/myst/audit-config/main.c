@@ -0,0 +1,117 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <arpa/inet.h>
+#include <assert.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+static char _log[65536];
+
+static void _read_audit_log(void)
+{
+    int fd;
+    ssize_t n;
+    size_t len = 0;
+
+    assert((fd = open("/proc/myst/audit", O_RDONLY)) >= 0);
+
+    while ((n = read(fd, _log + len, sizeof(_log) - len - 1)) > 0)
+        len += (size_t)n;
+
+    assert(n == 0);
+    _log[len] = '\0';
+    close(fd);
+}
+
+/* find the record line that contains all the given strings */
+static char* _find_record(const char* type, const char* detail)
+{
+    for (char* line = _log; *line;)
+    {
+        char* nl = strchr(line, '\n');
+        char* found = NULL;
+
+        assert(nl);
+        *nl = '\0';
+
+        if (strstr(line, type) && strstr(line, detail))
+            found = strdup(line);
+
+        *nl = '\n';
+
+        if (found)
+            return found;
+
+        line = nl + 1;
+    }
+
+    return NULL;
+}
+
+int main(int argc, const char* argv[])
+{
+    size_t records = 0;
+    char last_hash[65] = "";
+    char* line;
+    char* rec;
+    unsigned long seq;
+    char hash[65];
+
+    /* connect to a closed port (the attempt is recorded either way) */
+    {
+        struct sockaddr_in addr;
+        int sd;
+
+        assert((sd = socket(AF_INET, SOCK_STREAM, 0)) >= 0);
+        memset(&addr, 0, sizeof(addr));
+        addr.sin_family = AF_INET;
+        addr.sin_port = htons(9);
+        addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+        connect(sd, (struct sockaddr*)&addr, sizeof(addr));
+        close(sd);
+    }
+
+    /* a failing mount whose target would forge a record if not escaped */
+    assert(mount("none", "/mnt\n0 0 EXEC", "bogusfs", 0, NULL) != 0);
+
+    _read_audit_log();
+
+    /* the in-memory log starts at the beginning of the chain */
+    assert(strncmp(_log, "start 0 ", 8) == 0);
+
+    assert((rec = _find_record(" EXEC pid=", "path=/bin/test argc=1")));
+    assert(strstr(rec, " 1 ") == rec + 64);
+    free(rec);
+
+    assert((rec = _find_record(" CONNECT pid=", "addr=127.0.0.1:9")));
+    free(rec);
+
+    assert((rec = _find_record(" MOUNT pid=", "type=bogusfs")));
+    assert(strstr(rec, "target=/mnt\\x0a0 0 EXEC"));
+    assert(strstr(rec, "ret=-"));
+    free(rec);
+
+    /* the head names the last record and its hash */
+    for (line = strchr(_log, '\n') + 1; *line; line = strchr(line, '\n') + 1)
+    {
+        if (strncmp(line, "head ", 5) == 0)
+            break;
+
+        memcpy(last_hash, line, 64);
+        records++;
+    }
+
+    assert(sscanf(line, "head %lu %64s", &seq, hash) == 2);
+    assert(seq == records);
+    assert(strcmp(hash, last_hash) == 0);
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
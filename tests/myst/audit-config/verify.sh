@@ -0,0 +1,42 @@
+#!/bin/bash
+#
+# Check the hash chain of an exported audit log (see doc/audit-log.md).
+#
+
+set -e
+
+log=$1
+prev=
+seq=
+
+while IFS= read -r line; do
+    case "$line" in
+        start\ *)
+            read -r _ seq prev <<< "$line"
+            ;;
+        head\ *)
+            read -r _ head_seq head_hash report <<< "$line"
+            test "$head_seq" = "$seq"
+            test "$head_hash" = "$prev"
+            test -n "$report"
+            echo "verified $seq records"
+            exit 0
+            ;;
+        *)
+            hash=${line%% *}
+            text=${line#* }
+            seq=$((seq + 1))
+            test "${text%% *}" = "$seq"
+            expected=$({ echo -n "$prev" | xxd -r -p; echo -n "$text"; } |
+                sha256sum | cut -d" " -f1)
+            if [ "$hash" != "$expected" ]; then
+                echo "$log: record $seq: hash mismatch" >&2
+                exit 1
+            fi
+            prev=$hash
+            ;;
+    esac
+done < "$log"
+
+echo "$log: no head record" >&2
+exit 1
@@ -0,0 +1,108 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <errno.h>
+#include <limits.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+static volatile int _sigxcpus;
+
+static void _handler(int sig)
+{
+    assert(sig == SIGXCPU);
+    _sigxcpus++;
+}
+
+static long _now_sec(void)
+{
+    struct timespec ts;
+
+    assert(clock_gettime(CLOCK_MONOTONIC, &ts) == 0);
+    return ts.tv_sec;
+}
+
+/* use CPU time until the signals arrive, entering the kernel so that the
+ * budget is checked */
+static void _spin(long seconds, int sigxcpus)
+{
+    long start = _now_sec();
+
+    while (_now_sec() - start < seconds && _sigxcpus < sigxcpus)
+        getppid();
+}
+
+/* run until killed at the hard limit */
+static int _child(void)
+{
+    struct rlimit rlim = {1, 2};
+
+    assert(signal(SIGXCPU, SIG_IGN) != SIG_ERR);
+    assert(setrlimit(RLIMIT_CPU, &rlim) == 0);
+    _spin(60, INT_MAX);
+
+    return 1;
+}
+
+static void test_limits(void)
+{
+    struct rlimit rlim;
+
+    /* from "CpuTimeLimit" and "CpuTimeHardLimit" */
+    assert(getrlimit(RLIMIT_CPU, &rlim) == 0);
+    assert(rlim.rlim_cur == 1);
+    assert(rlim.rlim_max == 30);
+
+    /* the hard limit may not be raised */
+    rlim.rlim_max = 60;
+    assert(setrlimit(RLIMIT_CPU, &rlim) == -1 && errno == EPERM);
+
+    rlim.rlim_cur = 40;
+    rlim.rlim_max = 30;
+    assert(setrlimit(RLIMIT_CPU, &rlim) == -1 && errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_sigxcpu(void)
+{
+    assert(signal(SIGXCPU, _handler) != SIG_ERR);
+
+    /* one signal at the soft limit and another one a second later */
+    _spin(10, 2);
+    assert(_sigxcpus == 2);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_sigkill(const char* path)
+{
+    char* const argv[] = {(char*)path, "child", NULL};
+    pid_t pid;
+    int wstatus;
+
+    assert(posix_spawn(&pid, path, NULL, NULL, argv, NULL) == 0);
+    assert(waitpid(pid, &wstatus, 0) == pid);
+    assert(WIFSIGNALED(wstatus) && WTERMSIG(wstatus) == SIGKILL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    if (argc == 2 && strcmp(argv[1], "child") == 0)
+        return _child();
+
+    test_limits();
+    test_sigxcpu();
+    test_sigkill(argv[0]);
+
+    return 0;
+}
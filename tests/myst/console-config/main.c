@@ -0,0 +1,30 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+
+int main(int argc, const char* argv[])
+{
+    const char partial[] = "partial stderr line";
+
+    /* enough output to rotate a 1k log more than twice */
+    for (int i = 0; i < 100; i++)
+        printf("stdout line %d\n", i);
+
+    fflush(stdout);
+
+    /* one write() holding several lines */
+    fprintf(stderr, "stderr line 1\nstderr line 2\n");
+
+    /* a line split across writes */
+    write(STDERR_FILENO, "stderr ", 7);
+    write(STDERR_FILENO, "line 3\n", 7);
+
+    /* a line without a newline is flushed at exit */
+    write(STDERR_FILENO, partial, strlen(partial));
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
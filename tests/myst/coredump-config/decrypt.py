@@ -0,0 +1,35 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+# decrypt an encrypted core file (see doc/coredump.md)
+#
+# usage: decrypt.py CORE PRIVATE-KEY OUTPUT
+
+import struct
+import sys
+
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import padding
+from cryptography.hazmat.primitives.ciphers.aead import AESGCM
+
+data = open(sys.argv[1], "rb").read()
+key = serialization.load_pem_private_key(open(sys.argv[2], "rb").read(), None)
+assert data[:8] == b"MYSTCORE"
+version, n = struct.unpack_from("<II", data, 8)
+assert version == 1
+oaep = padding.OAEP(
+    mgf=padding.MGF1(hashes.SHA256()), algorithm=hashes.SHA256(), label=None
+)
+aes = AESGCM(key.decrypt(data[16 : 16 + n], oaep))
+off, i, out = 16 + n, 0, open(sys.argv[3], "wb")
+
+while True:
+    (size,) = struct.unpack_from("<I", data, off)
+    nonce = struct.pack("<Q", i) + b"\0" * 4
+    out.write(aes.decrypt(nonce, data[off + 4 : off + 4 + size + 16], None))
+    off, i = off + 4 + size + 16, i + 1
+    if size == 0:
+        break
+
+# nothing follows the last record
+assert off == len(data)
@@ -0,0 +1,36 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+
+int main(int argc, const char* argv[])
+{
+    volatile char stack[64];
+    char* heap;
+
+    /* markers that the Makefile looks for in the core file */
+    heap = mmap(
+        NULL, 4096, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    assert(heap != MAP_FAILED);
+    strcpy(heap, "coredump-config: heap marker");
+    strcpy((char*)stack, "coredump-config: stack marker");
+
+    /* a page fault that nothing handles reaches the target's exception
+     * handler, which writes the core file before the process dies */
+    if (argc == 2 && strcmp(argv[1], "fault") == 0)
+    {
+        printf("=== %s: faulting\n", argv[0]);
+        fflush(stdout);
+        *(volatile int*)8 = 1;
+    }
+
+    printf("=== %s: aborting\n", argv[0]);
+    fflush(stdout);
+
+    /* SIGABRT dumps core */
+    abort();
+}
@@ -0,0 +1,119 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define NUM_FILES 64
+#define NUM_THREADS 3
+
+extern char** environ;
+
+/* create, write, read and remove files to churn kernel allocations */
+static void _churn_files(const char* dir)
+{
+    char path[256];
+    char buf[1024];
+
+    assert(mkdir(dir, 0777) == 0);
+
+    for (size_t i = 0; i < NUM_FILES; i++)
+    {
+        int fd;
+
+        snprintf(path, sizeof(path), "%s/file%zu", dir, i);
+        assert((fd = open(path, O_CREAT | O_RDWR | O_TRUNC, 0666)) >= 0);
+        memset(buf, (int)i, sizeof(buf));
+        assert(write(fd, buf, sizeof(buf)) == sizeof(buf));
+        assert(lseek(fd, 0, SEEK_SET) == 0);
+        assert(read(fd, buf, sizeof(buf)) == sizeof(buf));
+        assert(close(fd) == 0);
+    }
+
+    for (size_t i = 0; i < NUM_FILES; i++)
+    {
+        snprintf(path, sizeof(path), "%s/file%zu", dir, i);
+        assert(unlink(path) == 0);
+    }
+
+    assert(rmdir(dir) == 0);
+}
+
+static void* _thread(void* arg)
+{
+    char dir[64];
+
+    snprintf(dir, sizeof(dir), "/tmp/dir%zu", (size_t)arg);
+    _churn_files(dir);
+
+    return NULL;
+}
+
+static void test_threads(void)
+{
+    pthread_t threads[NUM_THREADS];
+
+    for (size_t i = 0; i < NUM_THREADS; i++)
+        assert(pthread_create(&threads[i], NULL, _thread, (void*)i) == 0);
+
+    for (size_t i = 0; i < NUM_THREADS; i++)
+        assert(pthread_join(threads[i], NULL) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_pipes(void)
+{
+    for (size_t i = 0; i < NUM_FILES; i++)
+    {
+        int fds[2];
+        char c = 'x';
+
+        assert(pipe(fds) == 0);
+        assert(write(fds[1], &c, 1) == 1);
+        assert(read(fds[0], &c, 1) == 1);
+        assert(close(fds[0]) == 0);
+        assert(close(fds[1]) == 0);
+    }
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_spawn(const char* program)
+{
+    char* argv[] = {(char*)program, "child", NULL};
+    pid_t pid;
+    int wstatus;
+
+    assert(posix_spawn(&pid, program, NULL, NULL, argv, environ) == 0);
+    assert(waitpid(pid, &wstatus, 0) == pid);
+    assert(WIFEXITED(wstatus) && WEXITSTATUS(wstatus) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    if (argc == 2 && strcmp(argv[1], "child") == 0)
+    {
+        _churn_files("/tmp/child");
+        return 0;
+    }
+
+    assert(mkdir("/tmp", 0777) == 0 || access("/tmp", F_OK) == 0);
+
+    test_threads();
+    test_pipes();
+    test_spawn(argv[0]);
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
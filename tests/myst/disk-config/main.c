@@ -0,0 +1,186 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/ioctl.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define DATA_SIZE (1024 * 1024)
+
+/* written by the first run and read by the second, past the cache */
+#define MARKER "written before the last boot"
+#define MARKER_OFFSET (512 * 1024 + 7)
+
+static void test_stat(void)
+{
+    struct stat st;
+    uint64_t size;
+    int ssz;
+    int fd;
+
+    assert(stat("/dev/vda", &st) == 0);
+    assert(S_ISBLK(st.st_mode));
+
+    assert((fd = open("/dev/vda", O_RDONLY)) >= 0);
+    assert(fstat(fd, &st) == 0);
+    assert(S_ISBLK(st.st_mode));
+    assert(ioctl(fd, BLKGETSIZE64, &size) == 0);
+    assert(size == DATA_SIZE);
+    assert(ioctl(fd, BLKSSZGET, &ssz) == 0);
+    assert(ssz == 512);
+    assert(lseek(fd, 0, SEEK_END) == DATA_SIZE);
+    close(fd);
+
+    /* only the disks of config.json */
+    assert(open("/dev/vdd", O_RDONLY) == -1 && errno == ENOENT);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_read_write(void)
+{
+    char buf[3000];
+    char block[512];
+    int fd;
+
+    for (size_t i = 0; i < sizeof(buf); i++)
+        buf[i] = (char)i;
+
+    assert((fd = open("/dev/vda", O_RDWR)) >= 0);
+
+    /* not aligned to blocks */
+    assert(pwrite(fd, buf, sizeof(buf), 1000) == sizeof(buf));
+    memset(block, 0, sizeof(block));
+    assert(pread(fd, block, 100, 1000 + 2900) == 100);
+    assert(memcmp(block, buf + 2900, 100) == 0);
+
+    /* more blocks than the cache holds */
+    for (int i = 0; i < 256; i++)
+    {
+        memset(block, i, sizeof(block));
+        assert(write(fd, block, sizeof(block)) == sizeof(block));
+    }
+
+    assert(lseek(fd, 0, SEEK_SET) == 0);
+
+    for (int i = 0; i < 256; i++)
+    {
+        assert(read(fd, block, sizeof(block)) == sizeof(block));
+        assert(block[0] == (char)i && block[511] == (char)i);
+    }
+
+    /* the end of the disk */
+    assert(pwrite(fd, buf, 10, DATA_SIZE - 4) == 4);
+    assert(pwrite(fd, buf, 1, DATA_SIZE) == -1 && errno == ENOSPC);
+    assert(pread(fd, buf, 1, DATA_SIZE) == 0);
+
+    assert(fsync(fd) == 0);
+    assert(ioctl(fd, BLKFLSBUF, 0) == 0);
+    close(fd);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_read_only(void)
+{
+    char buf[16];
+    int ro;
+    int fd;
+
+    assert(open("/dev/vdb", O_RDWR) == -1 && errno == EROFS);
+
+    assert((fd = open("/dev/vdb", O_RDONLY)) >= 0);
+    assert(read(fd, buf, 10) == 10);
+    assert(memcmp(buf, "hello disk", 10) == 0);
+    assert(ioctl(fd, BLKROGET, &ro) == 0);
+    assert(ro == 1);
+    assert(write(fd, buf, 1) == -1 && errno == EBADF);
+    close(fd);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_ext2(void)
+{
+    char buf[64];
+    ssize_t n;
+    int fd;
+
+    assert(mkdir("/mnt", 0777) == 0 || errno == EEXIST);
+    assert(mount("/dev/vdc", "/mnt", "ext2", 0, NULL) == 0);
+
+    assert((fd = open("/mnt/hello.txt", O_RDONLY)) >= 0);
+    assert((n = read(fd, buf, sizeof(buf) - 1)) == 11);
+    buf[n] = '\0';
+    assert(strcmp(buf, "hello ext2\n") == 0);
+    close(fd);
+
+    fd = open("/mnt/new.txt", O_CREAT | O_TRUNC | O_WRONLY, 0644);
+    assert(fd >= 0);
+    assert(write(fd, "new", 3) == 3);
+    close(fd);
+
+    assert(umount("/mnt") == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_write_marker(void)
+{
+    int fd;
+
+    assert((fd = open("/dev/vda", O_WRONLY)) >= 0);
+    assert(
+        pwrite(fd, MARKER, sizeof(MARKER), MARKER_OFFSET) == sizeof(MARKER));
+    assert(fsync(fd) == 0);
+    close(fd);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_read_marker(void)
+{
+    char buf[sizeof(MARKER)];
+    char block[512];
+    int fd;
+
+    assert((fd = open("/dev/vda", O_RDONLY)) >= 0);
+    assert(pread(fd, buf, sizeof(buf), MARKER_OFFSET) == sizeof(buf));
+    assert(memcmp(buf, MARKER, sizeof(MARKER)) == 0);
+
+    /* the blocks of the first run reached the image */
+    assert(pread(fd, block, sizeof(block), 100 * 512) == 512);
+    assert(block[0] == (char)100);
+    close(fd);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    assert(argc == 2);
+
+    if (strcmp(argv[1], "write") == 0)
+    {
+        test_stat();
+        test_read_write();
+        test_read_only();
+        test_ext2();
+        test_write_marker();
+    }
+    else
+    {
+        test_read_marker();
+    }
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
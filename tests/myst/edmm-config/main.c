@@ -0,0 +1,78 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+/* more than CommittedMemorySize, so most pages are committed on demand */
+#define MAP_SIZE (256 * 1024 * 1024)
+#define PAGE_SIZE 4096
+
+/* the pages that the TEE has committed on first access */
+static uint64_t _page_faults(void)
+{
+    FILE* stream;
+    char line[256];
+    unsigned long value = 0;
+
+    assert((stream = fopen("/proc/myst/metrics", "r")));
+
+    while (fgets(line, sizeof(line), stream))
+    {
+        if (sscanf(line, "myst_page_faults_total %lu", &value) == 1)
+            break;
+    }
+
+    fclose(stream);
+    return value;
+}
+
+static void _check_zero(const uint8_t* p, size_t size)
+{
+    for (size_t i = 0; i < size; i += PAGE_SIZE)
+        assert(p[i] == 0 && p[i + PAGE_SIZE - 1] == 0);
+}
+
+static void _touch(uint8_t* p, size_t size)
+{
+    for (size_t i = 0; i < size; i += PAGE_SIZE)
+        p[i] = (uint8_t)(i / PAGE_SIZE);
+
+    for (size_t i = 0; i < size; i += PAGE_SIZE)
+        assert(p[i] == (uint8_t)(i / PAGE_SIZE));
+}
+
+int main(int argc, const char* argv[])
+{
+    const int prot = PROT_READ | PROT_WRITE;
+    const int flags = MAP_PRIVATE | MAP_ANONYMOUS;
+    uint8_t* p;
+
+    /* new memory reads as zero and can be written */
+    p = mmap(NULL, MAP_SIZE, prot, flags, -1, 0);
+    assert(p != MAP_FAILED);
+    _check_zero(p, MAP_SIZE);
+    _touch(p, MAP_SIZE);
+    assert(munmap(p, MAP_SIZE) == 0);
+
+    /* the pages beyond CommittedMemorySize were committed on demand */
+    assert(_page_faults() > 0);
+
+    /* memory that was used before is zero-filled when mapped again */
+    p = mmap(NULL, MAP_SIZE, prot, flags, -1, 0);
+    assert(p != MAP_FAILED);
+    _check_zero(p, MAP_SIZE);
+    assert(munmap(p, MAP_SIZE) == 0);
+
+    /* the break memory starts at the bottom of the region */
+    p = sbrk(0);
+    assert(sbrk(16 * PAGE_SIZE) != (void*)-1);
+    _touch(p, 16 * PAGE_SIZE);
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
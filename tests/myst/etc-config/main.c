@@ -0,0 +1,85 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <grp.h>
+#include <limits.h>
+#include <pwd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+static void _read_file(const char* path, char* buf, size_t size)
+{
+    FILE* is;
+    size_t n;
+
+    assert((is = fopen(path, "r")));
+    n = fread(buf, 1, size - 1, is);
+    buf[n] = '\0';
+    fclose(is);
+}
+
+/*
+    argv[1] = expected hostname
+    argv[2..] = expected name servers (none means no /etc/resolv.conf)
+*/
+int main(int argc, const char* argv[])
+{
+    char buf[1024];
+    char expect[256];
+    struct stat st;
+    struct passwd* pw;
+    struct group* gr;
+
+    assert(argc >= 2);
+
+    /* /etc/hostname matches the configured hostname */
+    _read_file("/etc/hostname", buf, sizeof(buf));
+    snprintf(expect, sizeof(expect), "%s\n", argv[1]);
+    assert(strcmp(buf, expect) == 0);
+
+    /* /etc/hosts resolves localhost and the hostname */
+    _read_file("/etc/hosts", buf, sizeof(buf));
+    assert(strstr(buf, "127.0.0.1\tlocalhost\n"));
+    snprintf(expect, sizeof(expect), "127.0.1.1\t%s\n", argv[1]);
+    assert(strstr(buf, expect));
+
+    /* /etc/resolv.conf lists the configured name servers in order */
+    if (argc > 2)
+    {
+        char* p = buf;
+
+        _read_file("/etc/resolv.conf", buf, sizeof(buf));
+
+        for (int i = 2; i < argc; i++)
+        {
+            snprintf(expect, sizeof(expect), "nameserver %s\n", argv[i]);
+            assert(strncmp(p, expect, strlen(expect)) == 0);
+            p += strlen(expect);
+        }
+
+        assert(*p == '\0');
+    }
+    else
+    {
+        assert(stat("/etc/resolv.conf", &st) != 0);
+    }
+
+    /* the passwd and group databases know the user we run as */
+    assert((pw = getpwuid(getuid())));
+    assert(strcmp(pw->pw_name, "root") == 0);
+    assert(pw->pw_gid == getgid());
+    assert(strcmp(pw->pw_dir, "/root") == 0);
+
+    assert((gr = getgrgid(getgid())));
+    assert(strcmp(gr->gr_name, "root") == 0);
+
+    assert(stat("/etc/nsswitch.conf", &st) == 0);
+
+    printf("=== passed test (%s-etc-config)\n", argv[0]);
+
+    return 0;
+}
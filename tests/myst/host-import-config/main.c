@@ -0,0 +1,65 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+static void _check_file(const char* path, const char* expected)
+{
+    char buf[64];
+    int fd;
+    ssize_t n;
+
+    assert((fd = open(path, O_RDONLY)) >= 0);
+    assert((n = read(fd, buf, sizeof(buf) - 1)) >= 0);
+    buf[n] = '\0';
+    assert(strcmp(buf, expected) == 0);
+    assert(close(fd) == 0);
+}
+
+static void test_import(void)
+{
+    struct stat st;
+    char target[64];
+    ssize_t n;
+
+    _check_file("/imported/hello.txt", "hello\n");
+    _check_file("/imported/sub/nested.txt", "nested\n");
+
+    assert(stat("/imported/sub", &st) == 0 && S_ISDIR(st.st_mode));
+    assert(stat("/imported/sub/nested.txt", &st) == 0);
+    assert((st.st_mode & 0777) == 0600);
+
+    assert((n = readlink("/imported/link", target, sizeof(target) - 1)) > 0);
+    target[n] = '\0';
+    assert(strcmp(target, "hello.txt") == 0);
+    _check_file("/imported/link", "hello\n");
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_host_file(void)
+{
+    struct stat st;
+
+    _check_file("/etc/instance/id.txt", "instance-1\n");
+
+    assert(stat("/etc/instance/id.txt", &st) == 0);
+    assert(S_ISREG(st.st_mode) && (st.st_mode & 0777) == 0444);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_import();
+    test_host_file();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
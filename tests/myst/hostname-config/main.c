@@ -1,7 +1,9 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+#define _GNU_SOURCE
 #include <assert.h>
+#include <errno.h>
 #include <limits.h>
 #include <signal.h>
 #include <spawn.h>
@@ -9,21 +11,23 @@
 #include <stdio.h>
 #include <stdlib.h>
 #include <string.h>
+#include <sys/utsname.h>
 #include <sys/wait.h>
 #include <time.h>
 #include <unistd.h>
 
 /*
-    argv[1] = test name
-    argv[2] = expected hostname
+    argv[1] = expected hostname
+    argv[2] = expected uname release, version and machine (optional)
 */
 int test_hostname(int argc, const char* argv[])
 {
     int r;
     char hostname[HOST_NAME_MAX];
     char new_hostname[] = "changed";
+    char long_hostname[HOST_NAME_MAX + 2];
 
-    assert(argc == 2);
+    assert(argc == 2 || argc == 5);
 
     // Validate application hostname is correct to start with
     assert(gethostname(hostname, sizeof(hostname)) == 0);
@@ -36,14 +40,56 @@ int test_hostname(int argc, const char* argv[])
     assert(gethostname(hostname, sizeof(hostname)) == 0);
     assert(strcmp(hostname, new_hostname) == 0);
 
+    // Names that do not fit in the utsname field are rejected
+    memset(long_hostname, 'x', sizeof(long_hostname));
+    assert(sethostname(long_hostname, sizeof(long_hostname)) == -1);
+    assert(errno == EINVAL);
+    assert(gethostname(hostname, sizeof(hostname)) == 0);
+    assert(strcmp(hostname, new_hostname) == 0);
+
+    // The name need not be null-terminated
+    assert(sethostname("abcdef", 3) == 0);
+    assert(gethostname(hostname, sizeof(hostname)) == 0);
+    assert(strcmp(hostname, "abc") == 0);
+
     printf("=== passed test (%s-hostname-config)\n", argv[0]);
 
     return 0;
 }
 
+int test_uname(int argc, const char* argv[])
+{
+    struct utsname buf;
+    const char* release = "5.4.0";
+    const char* version = "Mystikos 1.0.0";
+    const char* machine = "x86_64";
+
+    if (argc == 5)
+    {
+        release = argv[2];
+        version = argv[3];
+        machine = argv[4];
+    }
+
+    assert(uname(&buf) == 0);
+    assert(strcmp(buf.sysname, "Linux") == 0);
+    assert(strcmp(buf.release, release) == 0);
+    assert(strcmp(buf.version, version) == 0);
+    assert(strcmp(buf.machine, machine) == 0);
+
+    assert(setdomainname("example.com", 11) == 0);
+    assert(uname(&buf) == 0);
+    assert(strcmp(buf.domainname, "example.com") == 0);
+
+    printf("=== passed test (%s-uname-config)\n", argv[0]);
+
+    return 0;
+}
+
 int main(int argc, const char* argv[])
 {
     assert(test_hostname(argc, argv) == 0);
+    assert(test_uname(argc, argv) == 0);
 
     return 0;
 }
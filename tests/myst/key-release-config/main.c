@@ -0,0 +1,70 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+/* the keys that mock.py releases */
+static void _expected(uint8_t* key, size_t size, uint8_t first)
+{
+    for (size_t i = 0; i < size; i++)
+        key[i] = (uint8_t)(first + i);
+}
+
+static void test_environment(void)
+{
+    uint8_t key[32];
+    char hex[sizeof(key) * 2 + 1];
+    const char* value;
+
+    _expected(key, sizeof(key), 0);
+
+    for (size_t i = 0; i < sizeof(key); i++)
+        snprintf(hex + i * 2, 3, "%02x", key[i]);
+
+    assert((value = getenv("DB_KEY")));
+    assert(strcmp(value, hex) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_file(void)
+{
+    uint8_t key[64];
+    uint8_t buf[sizeof(key) + 1];
+    struct stat st;
+    int fd;
+
+    _expected(key, sizeof(key), 100);
+
+    assert(stat("/run/keys/disk.key", &st) == 0);
+    assert((st.st_mode & 0777) == 0400);
+    assert(st.st_size == sizeof(key));
+
+    assert((fd = open("/run/keys/disk.key", O_RDONLY)) >= 0);
+    assert(read(fd, buf, sizeof(buf)) == sizeof(key));
+    assert(memcmp(buf, key, sizeof(key)) == 0);
+    assert(close(fd) == 0);
+
+    assert(stat("/run/keys", &st) == 0);
+    assert((st.st_mode & 0777) == 0700);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    (void)argc;
+
+    test_environment();
+    test_file();
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
@@ -0,0 +1,151 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+# A mock of the services that the key-release provider talks to: the
+# attestation service, the managed identity endpoint and the key vault. It
+# does not check the quote, but it checks that the key vault request carries
+# the token from the identity endpoint and the attestation token, and it
+# wraps the keys to the transfer key in that token (CKM_RSA_AES_KEY_WRAP), as
+# the key vault does.
+#
+# usage: mock.py PORT CERT KEY READY
+#
+# Needs the "cryptography" package.
+
+import base64
+import http.server
+import json
+import os
+import ssl
+import sys
+from urllib.parse import parse_qs, urlparse
+
+from cryptography.hazmat.primitives import hashes
+from cryptography.hazmat.primitives.asymmetric import padding, rsa
+from cryptography.hazmat.primitives.keywrap import aes_key_wrap_with_padding
+
+ACCESS_TOKEN = "mock-access-token"
+
+# the keys that may be released, by name (as in main.c)
+KEYS = {
+    "dbkey": bytes(range(32)),
+    "diskkey": bytes(range(100, 164)),
+}
+
+
+def b64url_encode(data):
+    return base64.urlsafe_b64encode(data).rstrip(b"=").decode()
+
+
+def b64url_decode(text):
+    return base64.urlsafe_b64decode(text + "=" * (-len(text) % 4))
+
+
+def b64url_int(text):
+    return int.from_bytes(b64url_decode(text), "big")
+
+
+def jws(payload):
+    header = b64url_encode(json.dumps({"alg": "none"}).encode())
+    body = b64url_encode(json.dumps(payload).encode())
+    return header + "." + body + ".c2lnbmF0dXJl"
+
+
+def wrap(jwk, key):
+    public = rsa.RSAPublicNumbers(
+        b64url_int(jwk["e"]), b64url_int(jwk["n"])
+    ).public_key()
+    aes_key = os.urandom(32)
+    oaep = padding.OAEP(
+        mgf=padding.MGF1(algorithm=hashes.SHA1()),
+        algorithm=hashes.SHA1(),
+        label=None,
+    )
+    ciphertext = public.encrypt(aes_key, oaep)
+    ciphertext += aes_key_wrap_with_padding(aes_key, key)
+    hsm = {"ciphertext": b64url_encode(ciphertext)}
+    return b64url_encode(json.dumps(hsm).encode())
+
+
+class Handler(http.server.BaseHTTPRequestHandler):
+    def reply(self, status, body):
+        data = json.dumps(body).encode()
+        self.send_response(status)
+        self.send_header("Content-Type", "application/json")
+        self.send_header("Content-Length", str(len(data)))
+        self.end_headers()
+        self.wfile.write(data)
+
+    def body(self):
+        size = int(self.headers.get("Content-Length", "0"))
+        return json.loads(self.rfile.read(size))
+
+    def do_GET(self):
+        url = urlparse(self.path)
+        query = parse_qs(url.query)
+
+        if url.path != "/metadata/identity/oauth2/token":
+            return self.reply(404, {})
+
+        if self.headers.get("Metadata") != "true":
+            return self.reply(400, {"error": "no Metadata header"})
+
+        if query.get("resource") != ["https://vault.azure.net"]:
+            return self.reply(400, {"error": "bad resource"})
+
+        self.reply(200, {"access_token": ACCESS_TOKEN})
+
+    def do_POST(self):
+        url = urlparse(self.path)
+        parts = url.path.split("/")
+
+        # POST /attest/SgxEnclave: the token holds the runtime data
+        if len(parts) == 3 and parts[1] == "attest":
+            body = self.body()
+
+            if not body.get("quote"):
+                return self.reply(400, {"error": "no quote"})
+
+            runtime = json.loads(b64url_decode(body["runtimeData"]["data"]))
+            token = jws({"x-ms-runtime": runtime})
+            return self.reply(200, {"token": token})
+
+        # POST /keys/<name>[/<version>]/release
+        if parts[1:2] == ["keys"] and len(parts) in (4, 5) and \
+                parts[-1] == "release":
+            name = parts[2]
+
+            if self.headers.get("Authorization") != "Bearer " + ACCESS_TOKEN:
+                return self.reply(401, {"error": "bad access token"})
+
+            if name not in KEYS:
+                return self.reply(403, {"error": "release policy"})
+
+            body = self.body()
+
+            if body.get("enc") != "CKM_RSA_AES_KEY_WRAP":
+                return self.reply(400, {"error": "bad enc"})
+
+            token = json.loads(b64url_decode(body["target"].split(".")[1]))
+            jwk = token["x-ms-runtime"]["keys"][0]
+            key_hsm = wrap(jwk, KEYS[name])
+            payload = {"response": {"key": {"key": {"key_hsm": key_hsm}}}}
+            return self.reply(200, {"value": jws(payload)})
+
+        self.reply(404, {})
+
+    def log_message(self, format, *args):
+        sys.stderr.write("mock: " + (format % args) + "\n")
+
+
+def main():
+    port, cert, key, ready = sys.argv[1:5]
+    server = http.server.ThreadingHTTPServer(("127.0.0.1", int(port)), Handler)
+    context = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+    context.load_cert_chain(cert, key)
+    server.socket = context.wrap_socket(server.socket, server_side=True)
+    open(ready, "w").close()
+    server.serve_forever()
+
+
+main()
@@ -0,0 +1,34 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+/*
+    argv[1] = "signed" (from the configuration)
+    argv[2..] = "--verbose" and "--level=<n>" (from the host)
+    TUNE_EXPECTED_MODE = the expected value of MODE
+*/
+int main(int argc, const char* argv[])
+{
+    const char* expected_mode = getenv("TUNE_EXPECTED_MODE");
+    const char* mode = getenv("MODE");
+
+    /* the signed parameters come first */
+    assert(argc == 4);
+    assert(strcmp(argv[1], "signed") == 0);
+    assert(strcmp(argv[2], "--verbose") == 0);
+    assert(strncmp(argv[3], "--level=", 8) == 0);
+
+    /* MODE is overridable, FIXED is not, OTHER is not imported at all */
+    assert(expected_mode);
+    assert(mode && strcmp(mode, expected_mode) == 0);
+    assert(strcmp(getenv("FIXED"), "signed") == 0);
+    assert(getenv("OTHER") == NULL);
+
+    printf("=== passed test (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,58 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+static void _check_file(const char* path, const char* expected)
+{
+    char buf[64];
+    int fd;
+    ssize_t n;
+
+    assert((fd = open(path, O_RDONLY)) >= 0);
+    assert((n = read(fd, buf, sizeof(buf) - 1)) >= 0);
+    buf[n] = '\0';
+    assert(strcmp(buf, expected) == 0);
+    assert(close(fd) == 0);
+}
+
+static void test_layer(void)
+{
+    struct stat st;
+    char target[64];
+    ssize_t n;
+
+    /* changed and added by the layer */
+    _check_file("/data/changed.txt", "layer\n");
+    _check_file("/data/added/added.txt", "added\n");
+
+    /* left alone */
+    _check_file("/data/same/same.txt", "same\n");
+
+    /* removed by whiteouts, which are not themselves in the tree */
+    assert(stat("/data/removed.txt", &st) == -1);
+    assert(stat("/data/gone", &st) == -1);
+    assert(stat("/data/gone/x.txt", &st) == -1);
+    assert(stat("/data/.wh.gone", &st) == -1);
+
+    /* a symbolic link that was replaced */
+    assert((n = readlink("/data/link", target, sizeof(target) - 1)) > 0);
+    target[n] = '\0';
+    assert(strcmp(target, "same/same.txt") == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_layer();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
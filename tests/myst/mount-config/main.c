@@ -0,0 +1,97 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+static void test_tmpfs(void)
+{
+    struct stat st;
+    int fd;
+    const unsigned long flags = MS_NOSUID | MS_NODEV;
+
+    assert(mount("tmpfs", "/run", "tmpfs", flags, "mode=755") == 0);
+
+    assert((fd = open("/run/pid", O_CREAT | O_WRONLY, 0644)) >= 0);
+    assert(write(fd, "1\n", 2) == 2);
+    close(fd);
+    assert(stat("/run/pid", &st) == 0 && st.st_size == 2);
+
+    /* the mount point is taken */
+    assert(mount("tmpfs", "/run", "tmpfs", 0, NULL) == -1 && errno == EEXIST);
+
+    assert(umount2("/run", MNT_DETACH) == 0);
+    assert(stat("/run/pid", &st) == -1 && errno == ENOENT);
+
+    /* the mount point must exist */
+    assert(mount("tmpfs", "/nodir", "tmpfs", 0, NULL) == -1);
+    assert(errno == ENOENT);
+
+    assert(mount("tmpfs", "/run", "tmpfs", MS_RDONLY, NULL) == -1);
+    assert(errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_proc(void)
+{
+    assert(mount("proc", "/proc", "proc", 0, NULL) == 0);
+    assert(access("/proc/self/status", R_OK) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_denied(void)
+{
+    /* not a source of config.json */
+    assert(mount("none", "/proc", "proc", 0, NULL) == -1 && errno == EPERM);
+
+    /* not a type of config.json */
+    assert(mount("/", "/run", "ramfs", 0, NULL) == -1 && errno == EPERM);
+
+    /* not a target of config.json */
+    assert(mount("proc", "/run", "proc", 0, NULL) == -1 && errno == EPERM);
+    assert(mount("proc", "/proc/..", "proc", 0, NULL) == -1 && errno == EPERM);
+
+    /* not mounted by the application */
+    assert(umount2("/proc", 0) == -1 && errno == EPERM);
+    assert(umount2("/", 0) == -1 && errno == EPERM);
+
+    assert(umount2("/run", MNT_EXPIRE) == -1 && errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+/* a configuration without AllowedMounts allows no mounts */
+static void test_none(void)
+{
+    assert(mount("tmpfs", "/run", "tmpfs", 0, NULL) == -1 && errno == EPERM);
+    assert(mount("proc", "/proc", "proc", 0, NULL) == -1 && errno == EPERM);
+    assert(umount2("/proc", 0) == -1 && errno == EPERM);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    if (argc == 2 && strcmp(argv[1], "none") == 0)
+    {
+        test_none();
+        printf("=== passed all tests (%s)\n", argv[0]);
+        return 0;
+    }
+
+    test_tmpfs();
+    test_proc();
+    test_denied();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
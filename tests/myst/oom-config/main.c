@@ -0,0 +1,124 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHUNK_SIZE (1024 * 1024)
+#define MAX_CHUNKS 1024
+
+extern char** environ;
+
+static void* _chunks[MAX_CHUNKS];
+
+/* map chunks until the enclave memory runs out */
+static size_t _hog(void)
+{
+    const int prot = PROT_READ | PROT_WRITE;
+    const int flags = MAP_PRIVATE | MAP_ANONYMOUS;
+    size_t n = 0;
+
+    while (n < MAX_CHUNKS)
+    {
+        void* p = mmap(NULL, CHUNK_SIZE, prot, flags, -1, 0);
+
+        if (p == MAP_FAILED)
+        {
+            assert(errno == ENOMEM);
+            break;
+        }
+
+        memset(p, 0xff, CHUNK_SIZE);
+        _chunks[n++] = p;
+    }
+
+    assert(n < MAX_CHUNKS);
+    return n;
+}
+
+static unsigned long _meminfo(const char* name)
+{
+    char buf[1024];
+    ssize_t n;
+    int fd;
+    const char* p;
+    unsigned long value;
+
+    assert((fd = open("/proc/meminfo", O_RDONLY)) >= 0);
+    assert((n = read(fd, buf, sizeof(buf) - 1)) > 0);
+    buf[n] = '\0';
+    close(fd);
+
+    assert((p = strstr(buf, name)));
+    assert(sscanf(p + strlen(name), ": %lu", &value) == 1);
+
+    return value;
+}
+
+static void test_fail(void)
+{
+    size_t n;
+    void* p;
+
+    assert(_meminfo("MemLowWatermark") == 4 * 1024 * 1024);
+
+    /* the allocation fails instead of taking down the enclave */
+    n = _hog();
+    assert(n > 0);
+
+    for (size_t i = 0; i < n; i++)
+        assert(munmap(_chunks[i], CHUNK_SIZE) == 0);
+
+    assert((p = malloc(CHUNK_SIZE)));
+    free(p);
+
+    assert(_meminfo("OomKills") == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_kill(const char* program)
+{
+    char* argv[] = {(char*)program, "hog", NULL};
+    pid_t pid;
+    int wstatus;
+
+    /* the child becomes the largest process and is killed */
+    assert(posix_spawn(&pid, program, NULL, NULL, argv, environ) == 0);
+    assert(waitpid(pid, &wstatus, 0) == pid);
+    assert(!(WIFEXITED(wstatus) && WEXITSTATUS(wstatus) == 0));
+
+    assert(_meminfo("OomKills") == 1);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    assert(argc == 2);
+
+    if (strcmp(argv[1], "hog") == 0)
+    {
+        _hog();
+        printf("hog: mmap failed\n");
+        return 0;
+    }
+
+    if (strcmp(argv[1], "fail") == 0)
+        test_fail();
+    else if (strcmp(argv[1], "kill") == 0)
+        test_kill(argv[0]);
+    else
+        assert(0);
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
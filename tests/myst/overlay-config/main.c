@@ -0,0 +1,198 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdbool.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+static void _read_file(const char* path, char* buf, size_t size)
+{
+    int fd;
+    ssize_t n;
+
+    assert((fd = open(path, O_RDONLY)) >= 0);
+    assert((n = read(fd, buf, size - 1)) >= 0);
+    buf[n] = '\0';
+    assert(close(fd) == 0);
+}
+
+static void _write_file(const char* path, int flags, const char* data)
+{
+    int fd;
+    const size_t len = strlen(data);
+
+    assert((fd = open(path, O_WRONLY | flags, 0666)) >= 0);
+    assert(write(fd, data, len) == (ssize_t)len);
+    assert(close(fd) == 0);
+}
+
+static bool _exists(const char* path)
+{
+    struct stat st;
+
+    if (lstat(path, &st) == 0)
+        return true;
+
+    assert(errno == ENOENT);
+    return false;
+}
+
+/* the names in a directory (without "." and "..") separated by spaces */
+static void _list_dir(const char* path, char* buf, size_t size)
+{
+    DIR* dir;
+    struct dirent* ent;
+    const char* names[16];
+    size_t n = 0;
+
+    assert((dir = opendir(path)));
+
+    while ((ent = readdir(dir)))
+    {
+        if (strcmp(ent->d_name, ".") == 0 || strcmp(ent->d_name, "..") == 0)
+            continue;
+
+        assert(strncmp(ent->d_name, ".wh.", 4) != 0);
+        assert(n < sizeof(names) / sizeof(names[0]));
+        names[n++] = strdup(ent->d_name);
+    }
+
+    assert(closedir(dir) == 0);
+
+    /* sort so the order of the layers does not matter */
+    for (size_t i = 0; i < n; i++)
+    {
+        for (size_t j = i + 1; j < n; j++)
+        {
+            if (strcmp(names[j], names[i]) < 0)
+            {
+                const char* tmp = names[i];
+                names[i] = names[j];
+                names[j] = tmp;
+            }
+        }
+    }
+
+    *buf = '\0';
+
+    for (size_t i = 0; i < n; i++)
+    {
+        if (i)
+            strncat(buf, " ", size - strlen(buf) - 1);
+
+        strncat(buf, names[i], size - strlen(buf) - 1);
+    }
+}
+
+static void test_copy_up(void)
+{
+    char buf[64];
+
+    _read_file("/data/lower.txt", buf, sizeof(buf));
+    assert(strcmp(buf, "lower\n") == 0);
+
+    /* the first write copies the file up with its contents */
+    _write_file("/data/lower.txt", O_APPEND, "upper\n");
+    _read_file("/data/lower.txt", buf, sizeof(buf));
+    assert(strcmp(buf, "lower\nupper\n") == 0);
+
+    /* symbolic links of the lower layer still resolve */
+    _read_file("/data/link", buf, sizeof(buf));
+    assert(strcmp(buf, "lower\nupper\n") == 0);
+
+    /* truncation copies up too */
+    assert(truncate("/data/dir/b.txt", 0) == 0);
+    _read_file("/data/dir/b.txt", buf, sizeof(buf));
+    assert(strcmp(buf, "") == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_whiteout(void)
+{
+    char buf[64];
+
+    _list_dir("/data/dir", buf, sizeof(buf));
+    assert(strcmp(buf, "a.txt b.txt") == 0);
+
+    assert(unlink("/data/dir/a.txt") == 0);
+    assert(!_exists("/data/dir/a.txt"));
+    assert(unlink("/data/dir/a.txt") == -1 && errno == ENOENT);
+
+    _list_dir("/data/dir", buf, sizeof(buf));
+    assert(strcmp(buf, "b.txt") == 0);
+
+    /* the name can be used again */
+    _write_file("/data/dir/a.txt", O_CREAT | O_EXCL, "new\n");
+    _read_file("/data/dir/a.txt", buf, sizeof(buf));
+    assert(strcmp(buf, "new\n") == 0);
+
+    _list_dir("/data/dir", buf, sizeof(buf));
+    assert(strcmp(buf, "a.txt b.txt") == 0);
+
+    /* the reserved names cannot be reached */
+    assert(!_exists("/data/dir/.wh.a.txt"));
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_opaque(void)
+{
+    char buf[64];
+
+    assert(rmdir("/data/gone") == -1 && errno == ENOTEMPTY);
+    assert(unlink("/data/gone/x.txt") == 0);
+    assert(rmdir("/data/gone") == 0);
+    assert(!_exists("/data/gone"));
+
+    /* a directory made in its place does not show the old contents */
+    assert(mkdir("/data/gone", 0777) == 0);
+    _list_dir("/data/gone", buf, sizeof(buf));
+    assert(strcmp(buf, "") == 0);
+    assert(!_exists("/data/gone/x.txt"));
+
+    _list_dir("/data", buf, sizeof(buf));
+    assert(strcmp(buf, "dir gone link lower.txt") == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_rename(void)
+{
+    char buf[64];
+
+    assert(rename("/data/dir/b.txt", "/data/moved.txt") == 0);
+    assert(!_exists("/data/dir/b.txt"));
+    assert(_exists("/data/moved.txt"));
+
+    _list_dir("/data/dir", buf, sizeof(buf));
+    assert(strcmp(buf, "a.txt") == 0);
+
+    /* directories of the lower layer cannot move */
+    assert(rename("/data/dir", "/data/dir2") == -1 && errno == EXDEV);
+
+    /* new ones can */
+    assert(mkdir("/data/new", 0777) == 0);
+    assert(rename("/data/new", "/data/new2") == 0);
+    assert(_exists("/data/new2"));
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_copy_up();
+    test_whiteout();
+    test_opaque();
+    test_rename();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,121 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#define SYS_myst_gen_creds 1009
+#define SYS_myst_free_creds 1010
+
+typedef struct creds
+{
+    uint8_t* cert;
+    size_t cert_size;
+    uint8_t* pkey;
+    size_t pkey_size;
+} creds_t;
+
+static void _gen_creds(creds_t* creds)
+{
+    memset(creds, 0, sizeof(creds_t));
+    assert(
+        syscall(
+            SYS_myst_gen_creds,
+            &creds->cert,
+            &creds->cert_size,
+            &creds->pkey,
+            &creds->pkey_size) == 0);
+    assert(creds->cert && creds->cert_size);
+    assert(creds->pkey && creds->pkey_size);
+}
+
+static void _free_creds(creds_t* creds)
+{
+    assert(
+        syscall(
+            SYS_myst_free_creds,
+            creds->cert,
+            creds->cert_size,
+            creds->pkey,
+            creds->pkey_size) == 0);
+}
+
+/* the value of a counter in /proc/myst/metrics */
+static unsigned long _metric(const char* name)
+{
+    FILE* is;
+    char line[256];
+    size_t len = strlen(name);
+    unsigned long value = 0;
+
+    assert((is = fopen("/proc/myst/metrics", "r")));
+
+    while (fgets(line, sizeof(line), is))
+    {
+        if (strncmp(line, name, len) == 0 && line[len] == ' ')
+            value = strtoul(line + len + 1, NULL, 10);
+    }
+
+    fclose(is);
+    return value;
+}
+
+static void test_cached(void)
+{
+    creds_t c1;
+    creds_t c2;
+    unsigned long hits = _metric("myst_quote_cache_hits_total");
+    unsigned long misses = _metric("myst_quote_cache_misses_total");
+
+    _gen_creds(&c1);
+    _gen_creds(&c2);
+
+    /* the same credentials, in copies of their own */
+    assert(c1.cert != c2.cert);
+    assert(c1.cert_size == c2.cert_size);
+    assert(memcmp(c1.cert, c2.cert, c1.cert_size) == 0);
+    assert(c1.pkey_size == c2.pkey_size);
+    assert(memcmp(c1.pkey, c2.pkey, c1.pkey_size) == 0);
+
+    /* the first request may have generated them */
+    assert(_metric("myst_quote_cache_hits_total") >= hits + 1);
+    assert(_metric("myst_quote_cache_misses_total") <= misses + 1);
+
+    _free_creds(&c1);
+    _free_creds(&c2);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_fault(void)
+{
+    size_t cert_size;
+    uint8_t* pkey;
+    size_t pkey_size;
+
+    /* not user memory */
+    assert(
+        syscall(
+            SYS_myst_gen_creds,
+            (uint8_t**)8,
+            &cert_size,
+            &pkey,
+            &pkey_size) == -1);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_cached();
+    test_fault();
+
+    printf("=== passed test (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,13 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <stdio.h>
+
+/* never runs: each configuration has a secret that cannot be fetched */
+int main(int argc, const char* argv[])
+{
+    (void)argc;
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
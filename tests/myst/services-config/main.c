@@ -0,0 +1,77 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define RUNS "/tmp/sidecar.runs"
+#define READY "/tmp/sidecar.ready"
+
+/* the sidecar fails on its first run and is restarted by the supervisor */
+static int _sidecar(void)
+{
+    int fd;
+    struct stat st;
+
+    assert(strcmp(getenv("ROLE"), "sidecar") == 0);
+
+    assert((fd = open(RUNS, O_WRONLY | O_CREAT | O_APPEND, 0666)) >= 0);
+    assert(write(fd, "x", 1) == 1);
+    close(fd);
+
+    assert((fd = open(READY, O_WRONLY | O_CREAT, 0666)) >= 0);
+    close(fd);
+
+    assert(stat(RUNS, &st) == 0);
+
+    if (st.st_size == 1)
+        return 1;
+
+    /* run until the supervisor kills it */
+    for (;;)
+        usleep(10000);
+}
+
+static int _app(void)
+{
+    struct stat st;
+
+    assert(strcmp(getenv("ROLE"), "app") == 0);
+
+    /* the sidecar was ready before the app was started */
+    assert(access(READY, F_OK) == 0);
+    assert(getppid() == 1);
+
+    /* wait for the sidecar to be restarted */
+    for (size_t i = 0; i < 1000; i++)
+    {
+        if (stat(RUNS, &st) == 0 && st.st_size == 2)
+        {
+            printf("=== passed test (services-config)\n");
+            return 0;
+        }
+
+        usleep(10000);
+    }
+
+    fprintf(stderr, "the sidecar was not restarted\n");
+    return 1;
+}
+
+int main(int argc, const char* argv[])
+{
+    assert(argc == 2);
+
+    if (strcmp(argv[1], "sidecar") == 0)
+        return _sidecar();
+
+    if (strcmp(argv[1], "app") == 0)
+        return _app();
+
+    return 1;
+}
@@ -0,0 +1,81 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <linux/futex.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+static void _handler(int signum)
+{
+    (void)signum;
+    printf("shutdown-config: child got SIGTERM\n");
+    fflush(stdout);
+    _exit(0);
+}
+
+/* the main process exits while its children are still running */
+static int _test_orphans(void)
+{
+    pid_t pid;
+
+    /* this child exits on SIGTERM */
+    if ((pid = fork()) == 0)
+    {
+        signal(SIGTERM, _handler);
+
+        for (;;)
+            usleep(10000);
+    }
+
+    assert(pid > 0);
+
+    /* this child ignores SIGTERM, so it is killed after the grace period */
+    if ((pid = fork()) == 0)
+    {
+        signal(SIGTERM, SIG_IGN);
+
+        for (;;)
+            usleep(10000);
+    }
+
+    assert(pid > 0);
+
+    /* let the children install their handlers */
+    sleep(1);
+
+    printf("=== passed test (shutdown-config: orphans)\n");
+    return 3;
+}
+
+/* wait on a futex that is never woken until the host sends SIGTERM, which
+ * the default action handles */
+static int _test_sigterm(void)
+{
+    static int word;
+
+    printf("shutdown-config: waiting for SIGTERM\n");
+    fflush(stdout);
+
+    for (;;)
+        syscall(SYS_futex, &word, FUTEX_WAIT, 0, NULL, NULL, 0);
+
+    return 1;
+}
+
+int main(int argc, const char* argv[])
+{
+    assert(argc == 2);
+
+    if (strcmp(argv[1], "orphans") == 0)
+        return _test_orphans();
+
+    if (strcmp(argv[1], "sigterm") == 0)
+        return _test_sigterm();
+
+    return 1;
+}
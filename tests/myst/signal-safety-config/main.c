@@ -0,0 +1,87 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/time.h>
+#include <time.h>
+#include <unistd.h>
+
+#define NUM_THREADS 3
+
+static int _pipe[2];
+static volatile int _signals;
+
+/* a handler that makes syscalls, which is what the audit guards */
+static void _handler(int sig)
+{
+    struct timespec ts;
+    char c = 0;
+
+    assert(sig == SIGALRM);
+    assert(getpid() > 0);
+    assert(clock_gettime(CLOCK_MONOTONIC, &ts) == 0);
+
+    /* the pipe may be full */
+    (void)write(_pipe[1], &c, 1);
+    _signals++;
+}
+
+/* wait, read and write so that signals arrive in many kernel paths */
+static void* _thread(void* arg)
+{
+    char path[64];
+    char buf[256];
+
+    snprintf(path, sizeof(path), "/tmp/file%ld", (long)arg);
+
+    for (size_t i = 0; i < 100; i++)
+    {
+        struct pollfd fds = {.fd = _pipe[0], .events = POLLIN};
+        int fd;
+
+        if (poll(&fds, 1, 1) == 1)
+            (void)read(_pipe[0], buf, sizeof(buf));
+
+        assert((fd = open(path, O_CREAT | O_RDWR | O_TRUNC, 0666)) >= 0);
+        memset(buf, (int)i, sizeof(buf));
+        assert(write(fd, buf, sizeof(buf)) == sizeof(buf));
+        assert(close(fd) == 0);
+        assert(unlink(path) == 0);
+    }
+
+    return NULL;
+}
+
+int main(int argc, const char* argv[])
+{
+    pthread_t threads[NUM_THREADS];
+    struct itimerval value = {{0, 1000}, {0, 1000}};
+
+    assert(pipe(_pipe) == 0);
+    assert(fcntl(_pipe[1], F_SETFL, O_NONBLOCK) == 0);
+    assert(mkdir("/tmp", 0777) == 0 || access("/tmp", F_OK) == 0);
+
+    assert(signal(SIGALRM, _handler) != SIG_ERR);
+    assert(setitimer(ITIMER_REAL, &value, NULL) == 0);
+
+    for (long i = 0; i < NUM_THREADS; i++)
+        assert(pthread_create(&threads[i], NULL, _thread, (void*)i) == 0);
+
+    for (size_t i = 0; i < NUM_THREADS; i++)
+        assert(pthread_join(threads[i], NULL) == 0);
+
+    memset(&value, 0, sizeof(value));
+    assert(setitimer(ITIMER_REAL, &value, NULL) == 0);
+    assert(_signals > 0);
+
+    printf("=== passed test (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,219 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <arpa/inet.h>
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <pthread.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/ptrace.h>
+#include <sys/socket.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <sys/un.h>
+#include <unistd.h>
+
+#ifndef SYS_openat2
+#define SYS_openat2 437
+#endif
+
+static int _connect(const char* ip, int port)
+{
+    struct sockaddr_in addr;
+    int sd;
+    int r;
+
+    assert((sd = socket(AF_INET, SOCK_STREAM, 0)) >= 0);
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_port = htons(port);
+    addr.sin_addr.s_addr = inet_addr(ip);
+    r = connect(sd, (struct sockaddr*)&addr, sizeof(addr));
+    close(sd);
+
+    return r == 0 ? 0 : errno;
+}
+
+static int _connect_unix(const char* path)
+{
+    struct sockaddr_un addr;
+    int sd;
+    int r;
+
+    assert((sd = socket(AF_UNIX, SOCK_STREAM, 0)) >= 0);
+    memset(&addr, 0, sizeof(addr));
+    addr.sun_family = AF_UNIX;
+    strcpy(addr.sun_path, path);
+    r = connect(sd, (struct sockaddr*)&addr, sizeof(addr));
+    close(sd);
+
+    return r == 0 ? 0 : errno;
+}
+
+static void test_syscall_rules(void)
+{
+    /* "mkdir" always fails with EACCES */
+    assert(mkdir("/tmp-policy", 0777) == -1 && errno == EACCES);
+
+    /* "getpid" is not named by any rule */
+    assert(getpid() > 0);
+}
+
+static void test_path_rules(void)
+{
+    int fd;
+    int dirfd;
+
+    assert((fd = open("/secret/key", O_RDONLY)) == -1 && errno == EPERM);
+    assert((fd = open("/secret/../secret/key", O_RDONLY)) == -1);
+    assert(errno == EPERM);
+
+    /* relative paths are resolved before they are matched */
+    assert(chdir("/secret") == 0);
+    assert((fd = open("key", O_RDONLY)) == -1 && errno == EPERM);
+    assert(chdir("/") == 0);
+
+    assert((dirfd = open("/secret", O_RDONLY | O_DIRECTORY)) >= 0);
+    assert(openat(dirfd, "key", O_RDONLY) == -1 && errno == EPERM);
+    close(dirfd);
+
+    /* openat2() is matched as openat() is */
+    {
+        struct
+        {
+            uint64_t flags;
+            uint64_t mode;
+            uint64_t resolve;
+        } how = {O_RDONLY, 0, 0};
+
+        fd = syscall(SYS_openat2, AT_FDCWD, "/secret/key", &how, sizeof(how));
+        assert(fd == -1 && errno == EPERM);
+    }
+
+    /* symbolic links are followed before the path is matched */
+    assert(symlink("/secret/key", "/public/key") == 0);
+    assert(symlink("../secret", "/public/secret") == 0);
+    assert((fd = open("/public/key", O_RDONLY)) == -1 && errno == EPERM);
+    assert((fd = open("/public/secret/key", O_RDONLY)) == -1);
+    assert(errno == EPERM);
+    assert(unlink("/public/key") == 0);
+    assert(unlink("/public/secret") == 0);
+
+    /* paths that do not match the pattern are allowed */
+    assert((fd = open("/public/file", O_RDONLY)) >= 0);
+    close(fd);
+}
+
+static char _racy_path[] = "/public/file";
+static volatile int _stop;
+
+/* swaps the path being opened, and a symbolic link in it, for a secret */
+static void* _swapper(void* arg)
+{
+    (void)arg;
+
+    while (!_stop)
+    {
+        memcpy(_racy_path, "/secret//key", 12);
+        assert(symlink("/secret/key", "/public/tmp") == 0);
+        assert(rename("/public/tmp", "/public/link") == 0);
+
+        memcpy(_racy_path, "/public/file", 12);
+        assert(symlink("/public/file", "/public/tmp") == 0);
+        assert(rename("/public/tmp", "/public/link") == 0);
+    }
+
+    return NULL;
+}
+
+/* read what the open gave (if it was allowed) */
+static void _read_opened(int fd, char buf[8])
+{
+    memset(buf, 0, 8);
+
+    if (fd >= 0)
+    {
+        assert(read(fd, buf, 7) >= 0);
+        close(fd);
+    }
+}
+
+static void test_swapped_paths(void)
+{
+    pthread_t thread;
+    char buf[8];
+
+    assert(symlink("/public/file", "/public/link") == 0);
+    assert(pthread_create(&thread, NULL, _swapper, NULL) == 0);
+
+    /* the file opened is always the one that the rules were checked for */
+    for (size_t i = 0; i < 2000; i++)
+    {
+        int fd;
+
+        /* a path copied halfway through a swap names no file */
+        fd = open(_racy_path, O_RDONLY);
+        assert(fd >= 0 || errno == EPERM || errno == ENOENT);
+        _read_opened(fd, buf);
+        assert(strcmp(buf, "key\n") != 0);
+
+        fd = open("/public/link", O_RDONLY);
+        assert(fd >= 0 || errno == EPERM);
+        _read_opened(fd, buf);
+        assert(strcmp(buf, "key\n") != 0);
+    }
+
+    _stop = 1;
+    assert(pthread_join(thread, NULL) == 0);
+    assert(unlink("/public/link") == 0);
+}
+
+static void test_connect_rules(void)
+{
+    /* anything in 10.0.0.0/8 */
+    assert(_connect("10.1.2.3", 80) == ENETUNREACH);
+    assert(_connect("10.255.0.1", 8080) == ENETUNREACH);
+
+    /* only port 7 of 127.0.0.1 */
+    assert(_connect("127.0.0.1", 7) == EHOSTUNREACH);
+    assert(_connect("127.0.0.1", 9) != EHOSTUNREACH);
+
+    /* unix sockets matching the pattern */
+    assert(_connect_unix("/run/denied.sock") == EACCES);
+    assert(_connect_unix("/run/other") != EACCES);
+
+    /* an address that is not the application's is not read */
+    {
+        const struct sockaddr* bad = (const struct sockaddr*)8;
+        int sd;
+
+        assert((sd = socket(AF_INET, SOCK_STREAM, 0)) >= 0);
+        assert(connect(sd, bad, sizeof(struct sockaddr_in)) == -1);
+        assert(errno == EFAULT);
+        close(sd);
+    }
+}
+
+int main(int argc, const char* argv[])
+{
+    if (argc == 2 && strcmp(argv[1], "kill") == 0)
+    {
+        printf("before ptrace\n");
+        fflush(stdout);
+        ptrace(PTRACE_TRACEME, 0, NULL, NULL);
+        printf("after ptrace\n");
+        return 0;
+    }
+
+    test_syscall_rules();
+    test_path_rules();
+    test_swapped_paths();
+    test_connect_rules();
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
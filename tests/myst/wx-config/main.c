@@ -0,0 +1,95 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <errno.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define RW (PROT_READ | PROT_WRITE)
+#define RX (PROT_READ | PROT_EXEC)
+#define RWX (PROT_READ | PROT_WRITE | PROT_EXEC)
+
+static const int _flags = MAP_PRIVATE | MAP_ANONYMOUS;
+
+/* what a JIT compiler does: /bin/jit is in WriteXorExecuteAllow */
+static int _jit(void)
+{
+    void* p;
+
+    assert((p = mmap(NULL, 4096, RWX, _flags, -1, 0)) != MAP_FAILED);
+    assert(mprotect(p, 4096, RWX) == 0);
+    assert(munmap(p, 4096) == 0);
+
+    return 0;
+}
+
+static void test_denied(void)
+{
+    void* p;
+
+    assert(mmap(NULL, 4096, RWX, _flags, -1, 0) == MAP_FAILED);
+    assert(errno == EACCES);
+
+    /* write the code, then make it executable */
+    assert((p = mmap(NULL, 4096, RW, _flags, -1, 0)) != MAP_FAILED);
+    memset(p, 0xc3, 4096);
+    assert(mprotect(p, 4096, RX) == 0);
+
+    assert(mprotect(p, 4096, RWX) == -1);
+    assert(errno == EACCES);
+
+    assert(munmap(p, 4096) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_fork(void)
+{
+    pid_t pid;
+    int wstatus;
+
+    /* a child runs the same program, so it is not exempt either */
+    if ((pid = fork()) == 0)
+    {
+        void* p = mmap(NULL, 4096, RWX, _flags, -1, 0);
+        _exit(p == MAP_FAILED && errno == EACCES ? 0 : 1);
+    }
+
+    assert(pid > 0);
+    assert(waitpid(pid, &wstatus, 0) == pid);
+    assert(WIFEXITED(wstatus) && WEXITSTATUS(wstatus) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_allowed(void)
+{
+    char* argv[] = {"/bin/jit", NULL};
+    pid_t pid;
+    int wstatus;
+
+    assert(posix_spawn(&pid, argv[0], NULL, NULL, argv, NULL) == 0);
+    assert(waitpid(pid, &wstatus, 0) == pid);
+    assert(WIFEXITED(wstatus) && WEXITSTATUS(wstatus) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    if (strcmp(argv[0], "/bin/jit") == 0)
+        return _jit();
+
+    test_denied();
+    test_fork();
+    test_allowed();
+
+    printf("=== passed test (%s)\n", argv[0]);
+
+    return 0;
+}
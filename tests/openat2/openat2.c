@@ -0,0 +1,265 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+/* the openat2 ABI (no wrapper or kernel headers in the test image) */
+
+#ifndef SYS_openat2
+#define SYS_openat2 437
+#endif
+
+#ifndef SYS_faccessat2
+#define SYS_faccessat2 439
+#endif
+
+#define RESOLVE_NO_XDEV_ 0x01
+#define RESOLVE_NO_MAGICLINKS_ 0x02
+#define RESOLVE_NO_SYMLINKS_ 0x04
+#define RESOLVE_BENEATH_ 0x08
+#define RESOLVE_IN_ROOT_ 0x10
+
+struct open_how_
+{
+    uint64_t flags;
+    uint64_t mode;
+    uint64_t resolve;
+};
+
+#define DIR "/tmp/openat2"
+
+static int _openat2(int dirfd, const char* path, int flags, uint64_t resolve)
+{
+    struct open_how_ how = {.flags = flags, .resolve = resolve};
+    return syscall(SYS_openat2, dirfd, path, &how, sizeof(how));
+}
+
+static void _write_file(const char* path, const char* data)
+{
+    int fd;
+
+    assert((fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, 0666)) >= 0);
+    assert(write(fd, data, strlen(data)) == (ssize_t)strlen(data));
+    assert(close(fd) == 0);
+}
+
+/* read the file at fd, which must hold data */
+static void _check_file(int fd, const char* data)
+{
+    char buf[64];
+    ssize_t n;
+
+    assert(fd >= 0);
+    assert((n = read(fd, buf, sizeof(buf) - 1)) >= 0);
+    buf[n] = '\0';
+    assert(strcmp(buf, data) == 0);
+    assert(close(fd) == 0);
+}
+
+static void _setup(void)
+{
+    mkdir("/tmp", 0777);
+    assert(mkdir(DIR, 0777) == 0);
+    assert(mkdir(DIR "/root", 0777) == 0);
+    assert(mkdir(DIR "/root/sub", 0777) == 0);
+    _write_file(DIR "/outside.txt", "outside\n");
+    _write_file(DIR "/root/inside.txt", "inside\n");
+    _write_file(DIR "/root/sub/file.txt", "sub\n");
+    assert(symlink("inside.txt", DIR "/root/rel") == 0);
+    assert(symlink("/inside.txt", DIR "/root/abs") == 0);
+    assert(symlink("../outside.txt", DIR "/root/up") == 0);
+    assert(symlink("sub", DIR "/root/sublink") == 0);
+}
+
+static void test_openat2(void)
+{
+    struct open_how_ how = {.flags = O_RDONLY};
+    int dirfd;
+    int fd;
+
+    assert((dirfd = open(DIR "/root", O_RDONLY | O_DIRECTORY)) >= 0);
+
+    _check_file(_openat2(dirfd, "inside.txt", O_RDONLY, 0), "inside\n");
+    _check_file(_openat2(dirfd, "sublink/file.txt", O_RDONLY, 0), "sub\n");
+    _check_file(_openat2(dirfd, "up", O_RDONLY, 0), "outside\n");
+
+    /* a larger struct is fine when the extra bytes are zero */
+    {
+        struct
+        {
+            struct open_how_ how;
+            uint64_t extra;
+        } big = {.how = {.flags = O_RDONLY}};
+        const char* path = "inside.txt";
+
+        fd = syscall(SYS_openat2, dirfd, path, &big, sizeof(big));
+        _check_file(fd, "inside\n");
+
+        big.extra = 1;
+        fd = syscall(SYS_openat2, dirfd, path, &big, sizeof(big));
+        assert(fd == -1 && errno == E2BIG);
+    }
+
+    /* what openat() ignores is an error */
+    fd = syscall(SYS_openat2, dirfd, "inside.txt", &how, sizeof(how) - 8);
+    assert(fd == -1 && errno == EINVAL);
+    how.mode = 0644;
+    fd = syscall(SYS_openat2, dirfd, "inside.txt", &how, sizeof(how));
+    assert(fd == -1 && errno == EINVAL);
+    how.mode = 0;
+    how.resolve = 0x1000;
+    fd = syscall(SYS_openat2, dirfd, "inside.txt", &how, sizeof(how));
+    assert(fd == -1 && errno == EINVAL);
+    fd = _openat2(dirfd, "x", O_RDONLY, RESOLVE_BENEATH_ | RESOLVE_IN_ROOT_);
+    assert(fd == -1 && errno == EINVAL);
+
+    assert(close(dirfd) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_no_symlinks(void)
+{
+    int dirfd;
+    int fd;
+
+    assert((dirfd = open(DIR "/root", O_RDONLY | O_DIRECTORY)) >= 0);
+
+    fd = _openat2(dirfd, "rel", O_RDONLY, RESOLVE_NO_SYMLINKS_);
+    assert(fd == -1 && errno == ELOOP);
+
+    /* links are refused in the middle of the path too */
+    fd = _openat2(dirfd, "sublink/file.txt", O_RDONLY, RESOLVE_NO_SYMLINKS_);
+    assert(fd == -1 && errno == ELOOP);
+
+    fd = _openat2(dirfd, "sub/file.txt", O_RDONLY, RESOLVE_NO_SYMLINKS_);
+    _check_file(fd, "sub\n");
+
+    /* a final link that is not followed is fine */
+    fd = _openat2(dirfd, "rel", O_PATH | O_NOFOLLOW, RESOLVE_NO_SYMLINKS_);
+    assert(fd >= 0);
+    assert(close(fd) == 0);
+
+    fd = _openat2(dirfd, "rel", O_RDONLY | O_NOFOLLOW, 0);
+    assert(fd == -1 && errno == ELOOP);
+
+    /* the magic links of /proc are links too */
+    fd = _openat2(dirfd, "/proc/self/fd/0", O_RDONLY, RESOLVE_NO_MAGICLINKS_);
+    assert(fd == -1 && errno == ELOOP);
+
+    assert(close(dirfd) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_beneath(void)
+{
+    const uint64_t r = RESOLVE_BENEATH_;
+    int dirfd;
+    int fd;
+
+    assert((dirfd = open(DIR "/root", O_RDONLY | O_DIRECTORY)) >= 0);
+
+    _check_file(_openat2(dirfd, "sub/../inside.txt", O_RDONLY, r), "inside\n");
+    _check_file(_openat2(dirfd, "rel", O_RDONLY, r), "inside\n");
+
+    fd = _openat2(dirfd, "../outside.txt", O_RDONLY, r);
+    assert(fd == -1 && errno == EXDEV);
+
+    fd = _openat2(dirfd, "up", O_RDONLY, r);
+    assert(fd == -1 && errno == EXDEV);
+
+    fd = _openat2(dirfd, "abs", O_RDONLY, r);
+    assert(fd == -1 && errno == EXDEV);
+
+    fd = _openat2(dirfd, DIR "/root/inside.txt", O_RDONLY, r);
+    assert(fd == -1 && errno == EXDEV);
+
+    /* new files can be created beneath the directory */
+    fd = _openat2(dirfd, "sub/new.txt", O_WRONLY | O_CREAT | O_EXCL, r);
+    assert(fd >= 0);
+    assert(close(fd) == 0);
+    assert(access(DIR "/root/sub/new.txt", F_OK) == 0);
+
+    assert(close(dirfd) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_in_root(void)
+{
+    const uint64_t r = RESOLVE_IN_ROOT_;
+    int dirfd;
+
+    assert((dirfd = open(DIR "/root", O_RDONLY | O_DIRECTORY)) >= 0);
+
+    /* the directory acts as the root */
+    _check_file(_openat2(dirfd, "abs", O_RDONLY, r), "inside\n");
+    _check_file(_openat2(dirfd, "/inside.txt", O_RDONLY, r), "inside\n");
+    _check_file(_openat2(dirfd, "../../inside.txt", O_RDONLY, r), "inside\n");
+
+    /* so "../outside.txt" is "/outside.txt", which is not there */
+    assert(_openat2(dirfd, "up", O_RDONLY, r) == -1 && errno == ENOENT);
+
+    assert(close(dirfd) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_faccessat2(void)
+{
+    int dirfd;
+    long r;
+
+    assert((dirfd = open(DIR "/root", O_RDONLY | O_DIRECTORY)) >= 0);
+
+    assert(syscall(SYS_faccessat, dirfd, "inside.txt", R_OK) == 0);
+    assert(syscall(SYS_faccessat2, dirfd, "inside.txt", R_OK, 0) == 0);
+    assert(syscall(SYS_faccessat2, dirfd, "rel", F_OK, AT_EACCESS) == 0);
+
+    /* a link is checked itself with AT_SYMLINK_NOFOLLOW */
+    assert(unlink(DIR "/root/inside.txt") == 0);
+    r = syscall(SYS_faccessat2, dirfd, "rel", F_OK, 0);
+    assert(r == -1 && errno == ENOENT);
+    r = syscall(SYS_faccessat2, dirfd, "rel", F_OK, AT_SYMLINK_NOFOLLOW);
+    assert(r == 0);
+
+    /* the directory itself */
+    r = syscall(SYS_faccessat2, dirfd, "", X_OK, AT_EMPTY_PATH);
+    assert(r == 0);
+    r = syscall(SYS_faccessat2, dirfd, "", X_OK, 0);
+    assert(r == -1 && errno == ENOENT);
+
+    r = syscall(SYS_faccessat2, dirfd, "sub", F_OK, 0x1);
+    assert(r == -1 && errno == EINVAL);
+    r = syscall(SYS_faccessat2, dirfd, "sub", 0x10, 0);
+    assert(r == -1 && errno == EINVAL);
+
+    assert(close(dirfd) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    _setup();
+
+    test_openat2();
+    test_no_symlinks();
+    test_beneath();
+    test_in_root();
+    test_faccessat2();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
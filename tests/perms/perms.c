@@ -0,0 +1,167 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+static mode_t _mode(const char* path)
+{
+    struct stat st;
+
+    assert(lstat(path, &st) == 0);
+    return st.st_mode & 07777;
+}
+
+static void test_umask(void)
+{
+    int fd;
+
+    umask(022);
+    assert(umask(027) == 022);
+
+    assert((fd = open("/perms.txt", O_CREAT | O_EXCL | O_WRONLY, 0666)) >= 0);
+    close(fd);
+    assert(_mode("/perms.txt") == 0640);
+    assert(unlink("/perms.txt") == 0);
+
+    assert(mkdir("/perms.dir", 0777) == 0);
+    assert(_mode("/perms.dir") == 0750);
+    assert(rmdir("/perms.dir") == 0);
+
+    /* without a umask, a new file has the mode asked for */
+    assert(umask(0) == 027);
+    assert((fd = creat("/perms.txt", 0666)) >= 0);
+    close(fd);
+    assert(_mode("/perms.txt") == 0666);
+    assert(unlink("/perms.txt") == 0);
+
+    umask(022);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_chmod(void)
+{
+    int fd;
+    struct stat st;
+
+    assert((fd = open("/perms.txt", O_CREAT | O_RDWR, 0644)) >= 0);
+
+    assert(chmod("/perms.txt", 0600) == 0);
+    assert(_mode("/perms.txt") == 0600);
+
+    assert(fchmod(fd, 04755) == 0);
+    assert(_mode("/perms.txt") == 04755);
+
+    assert(chdir("/") == 0);
+    assert(fchmodat(AT_FDCWD, "perms.txt", 0640, 0) == 0);
+    assert(_mode("/perms.txt") == 0640);
+
+    /* the file type stays */
+    assert(chmod("/perms.txt", S_IFDIR | 0644) == 0);
+    assert(stat("/perms.txt", &st) == 0);
+    assert(S_ISREG(st.st_mode) && (st.st_mode & 07777) == 0644);
+
+    close(fd);
+    assert(unlink("/perms.txt") == 0);
+
+    assert(chmod("/perms.none", 0644) == -1 && errno == ENOENT);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_chown(void)
+{
+    int fd;
+    struct stat st;
+
+    assert((fd = open("/perms.txt", O_CREAT | O_RDWR, 04755)) >= 0);
+    assert(fstat(fd, &st) == 0);
+    assert(st.st_uid == 0 && st.st_gid == 0);
+
+    /* root gives the file away, which drops the set-user-ID bit */
+    assert(chown("/perms.txt", 1000, 1001) == 0);
+    assert(stat("/perms.txt", &st) == 0);
+    assert(st.st_uid == 1000 && st.st_gid == 1001);
+    assert((st.st_mode & 07777) == 0755);
+
+    /* -1 leaves the owner as it is */
+    assert(fchown(fd, -1, 50) == 0);
+    assert(fstat(fd, &st) == 0);
+    assert(st.st_uid == 1000 && st.st_gid == 50);
+
+    assert(fchownat(fd, "", 7, -1, AT_EMPTY_PATH) == 0);
+    assert(fstat(fd, &st) == 0);
+    assert(st.st_uid == 7 && st.st_gid == 50);
+
+    /* lchown() changes the link rather than the file */
+    assert(symlink("/perms.txt", "/perms.lnk") == 0);
+    assert(lchown("/perms.lnk", 8, 9) == 0);
+    assert(lstat("/perms.lnk", &st) == 0);
+    assert(st.st_uid == 8 && st.st_gid == 9);
+    assert(stat("/perms.lnk", &st) == 0);
+    assert(st.st_uid == 7 && st.st_gid == 50);
+
+    assert(fchownat(AT_FDCWD, "/perms.lnk", 0, 0, AT_SYMLINK_NOFOLLOW) == 0);
+    assert(lstat("/perms.lnk", &st) == 0);
+    assert(st.st_uid == 0 && st.st_gid == 0);
+
+    close(fd);
+    assert(unlink("/perms.lnk") == 0);
+    assert(unlink("/perms.txt") == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_root(void)
+{
+    int fd;
+
+    assert((fd = open("/perms.txt", O_CREAT | O_RDWR, 0)) >= 0);
+    close(fd);
+
+    /* root reads and writes any file */
+    assert((fd = open("/perms.txt", O_RDWR | O_TRUNC)) >= 0);
+    close(fd);
+    assert(access("/perms.txt", R_OK | W_OK) == 0);
+
+    /* but only executes what has an execute bit */
+    assert(access("/perms.txt", X_OK) == -1 && errno == EACCES);
+    assert(chmod("/perms.txt", 0010) == 0);
+    assert(access("/perms.txt", X_OK) == 0);
+    assert(unlink("/perms.txt") == 0);
+
+    /* and searches any directory */
+    assert(mkdir("/perms.dir", 0) == 0);
+    assert(access("/perms.dir", X_OK) == 0);
+    assert(rmdir("/perms.dir") == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_proc(void)
+{
+    /* the kernel decides the modes of the files that describe it */
+    assert(chmod("/proc/meminfo", 0666) == -1 && errno == EPERM);
+    assert(chown("/proc/meminfo", 1, 1) == -1 && errno == EPERM);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_umask();
+    test_chmod();
+    test_chown();
+    test_root();
+    test_proc();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
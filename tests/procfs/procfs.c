@@ -66,6 +66,35 @@ int test_self_links(const char* pn)
     test_self_fd();
 }
 
+int test_metrics()
+{
+    int fd;
+    static char buf[16384];
+    ssize_t n;
+    size_t len = 0;
+
+    /* make a few syscalls so that the histogram has samples */
+    for (size_t i = 0; i < 10; i++)
+        getppid();
+
+    fd = open("/proc/myst/metrics", O_RDONLY);
+    assert(fd > 0);
+
+    while ((n = read(fd, buf + len, sizeof(buf) - len - 1)) > 0)
+        len += n;
+
+    assert(n == 0);
+    buf[len] = '\0';
+    close(fd);
+
+    assert(strstr(buf, "# TYPE myst_tcalls_total counter\n"));
+    assert(strstr(buf, "# TYPE myst_syscall_duration_seconds histogram\n"));
+    assert(strstr(buf, "myst_syscall_duration_seconds_bucket{le=\"+Inf\"} "));
+    assert(strstr(buf, "myst_futex_wait_duration_seconds_count "));
+    assert(strstr(buf, "myst_page_faults_total "));
+    assert(!strstr(buf, "myst_syscall_duration_seconds_count 0\n"));
+}
+
 int test_readonly()
 {
     int fd;
@@ -78,6 +107,7 @@ int main(int argc, const char* argv[])
 {
     test_meminfo();
     test_self_links(argv[0]);
+    test_metrics();
     test_readonly();
 
     printf("\n=== passed test (%s)\n", argv[0]);
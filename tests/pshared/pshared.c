@@ -0,0 +1,123 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define PATH "/tmp/pshared"
+#define NUM_CHILDREN 3
+#define NUM_INCREMENTS 100
+
+/* the state that the processes share through a file mapping */
+struct shared
+{
+    pthread_mutex_t mutex;
+    pthread_cond_t cond;
+    int count;
+    char message[64];
+};
+
+static struct shared* _map(void)
+{
+    struct shared* s;
+    int fd;
+
+    assert((fd = open(PATH, O_RDWR)) >= 0);
+    s = mmap(NULL, sizeof(*s), PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    assert(s != MAP_FAILED);
+    assert(close(fd) == 0);
+
+    return s;
+}
+
+static int _child(void)
+{
+    struct shared* s = _map();
+
+    for (int i = 0; i < NUM_INCREMENTS; i++)
+    {
+        assert(pthread_mutex_lock(&s->mutex) == 0);
+        s->count++;
+        assert(pthread_cond_signal(&s->cond) == 0);
+        assert(pthread_mutex_unlock(&s->mutex) == 0);
+    }
+
+    assert(strcmp(s->message, "hello") == 0);
+    assert(munmap(s, sizeof(*s)) == 0);
+
+    return 0;
+}
+
+static void test_mutex_and_cond(const char* path)
+{
+    struct shared* s;
+    pthread_mutexattr_t mattr;
+    pthread_condattr_t cattr;
+    pid_t pids[NUM_CHILDREN];
+    char* const argv[] = {(char*)path, "child", NULL};
+    int fd;
+
+    assert(mkdir("/tmp", 0777) == 0 || access("/tmp", F_OK) == 0);
+    assert((fd = open(PATH, O_CREAT | O_RDWR | O_TRUNC, 0666)) >= 0);
+    assert(ftruncate(fd, sizeof(struct shared)) == 0);
+    assert(close(fd) == 0);
+
+    s = _map();
+
+    assert(pthread_mutexattr_init(&mattr) == 0);
+    assert(pthread_mutexattr_setpshared(&mattr, PTHREAD_PROCESS_SHARED) == 0);
+    assert(pthread_mutex_init(&s->mutex, &mattr) == 0);
+    assert(pthread_condattr_init(&cattr) == 0);
+    assert(pthread_condattr_setpshared(&cattr, PTHREAD_PROCESS_SHARED) == 0);
+    assert(pthread_cond_init(&s->cond, &cattr) == 0);
+    strcpy(s->message, "hello");
+
+    for (int i = 0; i < NUM_CHILDREN; i++)
+        assert(posix_spawn(&pids[i], path, NULL, NULL, argv, NULL) == 0);
+
+    /* the children wake the parent once they have all counted */
+    assert(pthread_mutex_lock(&s->mutex) == 0);
+
+    while (s->count < NUM_CHILDREN * NUM_INCREMENTS)
+        assert(pthread_cond_wait(&s->cond, &s->mutex) == 0);
+
+    assert(pthread_mutex_unlock(&s->mutex) == 0);
+
+    for (int i = 0; i < NUM_CHILDREN; i++)
+    {
+        int wstatus;
+
+        assert(waitpid(pids[i], &wstatus, 0) == pids[i]);
+        assert(WIFEXITED(wstatus) && WEXITSTATUS(wstatus) == 0);
+    }
+
+    /* the memory outlives the children's mappings */
+    assert(s->count == NUM_CHILDREN * NUM_INCREMENTS);
+
+    assert(pthread_cond_destroy(&s->cond) == 0);
+    assert(pthread_mutex_destroy(&s->mutex) == 0);
+    assert(munmap(s, sizeof(*s)) == 0);
+    assert(unlink(PATH) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    if (argc == 2 && strcmp(argv[1], "child") == 0)
+        return _child();
+
+    test_mutex_and_cond(argv[0]);
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,185 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <pty.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/ioctl.h>
+#include <termios.h>
+#include <unistd.h>
+
+static volatile sig_atomic_t _sigwinch;
+static volatile sig_atomic_t _sigint;
+
+static void _handler(int sig)
+{
+    if (sig == SIGWINCH)
+        _sigwinch = 1;
+    else if (sig == SIGINT)
+        _sigint = 1;
+}
+
+/* read what is there without blocking */
+static ssize_t _drain(int fd, char* buf, size_t size)
+{
+    struct pollfd pfd = {.fd = fd, .events = POLLIN};
+    ssize_t n;
+
+    if (poll(&pfd, 1, 1000) != 1)
+        return 0;
+
+    assert((n = read(fd, buf, size - 1)) >= 0);
+    buf[n] = '\0';
+    return n;
+}
+
+static void test_open(void)
+{
+    int master;
+    char path[64];
+    int fd;
+
+    assert((master = posix_openpt(O_RDWR | O_NOCTTY)) >= 0);
+    assert(grantpt(master) == 0);
+    assert(ptsname_r(master, path, sizeof(path)) == 0);
+    assert(strncmp(path, "/dev/pts/", 9) == 0);
+
+    /* the slave is locked until unlockpt() */
+    assert(open(path, O_RDWR | O_NOCTTY) == -1);
+    assert(errno == EIO);
+    assert(unlockpt(master) == 0);
+    assert((fd = open(path, O_RDWR | O_NOCTTY)) >= 0);
+
+    assert(isatty(master));
+    assert(isatty(fd));
+
+    assert(close(fd) == 0);
+    assert(close(master) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_canonical(int master, int slave)
+{
+    char buf[64];
+
+    /* input is read a line at a time, with erase applied */
+    assert(write(master, "abx\177c\rdef\n", 10) == 10);
+    assert(read(slave, buf, sizeof(buf)) == 4);
+    assert(memcmp(buf, "abc\n", 4) == 0);
+    assert(read(slave, buf, sizeof(buf)) == 4);
+    assert(memcmp(buf, "def\n", 4) == 0);
+
+    /* the master sees the echo */
+    assert(_drain(master, buf, sizeof(buf)) > 0);
+    assert(strcmp(buf, "abx\b \bc\r\ndef\r\n") == 0);
+
+    /* output processing maps NL to CR-NL */
+    assert(write(slave, "out\n", 4) == 4);
+    assert(_drain(master, buf, sizeof(buf)) == 5);
+    assert(strcmp(buf, "out\r\n") == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_raw(int master, int slave)
+{
+    struct termios saved;
+    struct termios t;
+    char buf[64];
+
+    assert(tcgetattr(slave, &saved) == 0);
+    t = saved;
+    cfmakeraw(&t);
+    assert(tcsetattr(slave, TCSANOW, &t) == 0);
+
+    assert(tcgetattr(slave, &t) == 0);
+    assert(!(t.c_lflag & (ICANON | ECHO)));
+
+    /* bytes are readable at once, without echo */
+    assert(write(master, "a\rb", 3) == 3);
+    assert(read(slave, buf, sizeof(buf)) == 3);
+    assert(memcmp(buf, "a\rb", 3) == 0);
+    assert(_drain(master, buf, sizeof(buf)) == 0);
+
+    assert(tcsetattr(slave, TCSANOW, &saved) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_signals(int master, int slave)
+{
+    struct winsize ws = {.ws_row = 50, .ws_col = 132};
+    struct winsize got;
+
+    /* only a controlling terminal gets terminal signals */
+    if (ioctl(slave, TIOCSCTTY, 0) != 0)
+    {
+        printf("=== skipped test (%s)\n", __FUNCTION__);
+        return;
+    }
+
+    signal(SIGWINCH, _handler);
+    signal(SIGINT, _handler);
+
+    assert(ioctl(master, TIOCSWINSZ, &ws) == 0);
+    assert(ioctl(slave, TIOCGWINSZ, &got) == 0);
+    assert(got.ws_row == 50 && got.ws_col == 132);
+
+    for (int i = 0; i < 100 && !_sigwinch; i++)
+        usleep(10000);
+
+    assert(_sigwinch);
+
+    /* ^C interrupts */
+    assert(write(master, "\003", 1) == 1);
+
+    for (int i = 0; i < 100 && !_sigint; i++)
+        usleep(10000);
+
+    assert(_sigint);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_hangup(int master, int slave)
+{
+    char buf[64];
+
+    _drain(master, buf, sizeof(buf));
+
+    /* the master reads EIO once the slave is closed */
+    assert(close(slave) == 0);
+    assert(read(master, buf, sizeof(buf)) == -1);
+    assert(errno == EIO);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    int master;
+    int slave;
+
+    test_open();
+
+    assert(openpty(&master, &slave, NULL, NULL, NULL) == 0);
+
+    test_canonical(master, slave);
+    test_raw(master, slave);
+    test_signals(master, slave);
+    test_hangup(master, slave);
+
+    assert(close(master) == 0);
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -4,6 +4,7 @@
 #include <assert.h>
 #include <stdint.h>
 #include <stdio.h>
+#include <time.h>
 
 uint64_t rdtsc(void)
 {
@@ -14,6 +15,22 @@ uint64_t rdtsc(void)
     return ((uint64_t)hi << 32 | (uint64_t)lo);
 }
 
+uint64_t rdtscp(uint32_t* aux)
+{
+    uint32_t hi;
+    uint32_t lo;
+
+    __asm__ __volatile__("rdtscp" : "=a"(lo), "=d"(hi), "=c"(*aux));
+    return ((uint64_t)hi << 32 | (uint64_t)lo);
+}
+
+static uint64_t _now_ns(void)
+{
+    struct timespec ts;
+    assert(clock_gettime(CLOCK_MONOTONIC, &ts) == 0);
+    return (uint64_t)ts.tv_sec * 1000000000 + (uint64_t)ts.tv_nsec;
+}
+
 int main(int argc, const char* argv[])
 {
     uint64_t x1 = rdtsc();
@@ -24,6 +41,34 @@ int main(int argc, const char* argv[])
 
     assert(x2 >= x1);
 
+    /* the counter never goes backward, whether emulated or not */
+    {
+        uint32_t aux;
+        uint64_t prev = rdtsc();
+
+        for (size_t i = 0; i < 100000; i++)
+        {
+            uint64_t x = (i % 2) ? rdtscp(&aux) : rdtsc();
+            assert(x >= prev);
+            prev = x;
+        }
+    }
+
+    /* the counter advances with wall time (used for latency measurement) */
+    {
+        const uint64_t t0 = _now_ns();
+        const uint64_t c0 = rdtsc();
+        struct timespec req = {0, 50000000};
+
+        nanosleep(&req, NULL);
+
+        const uint64_t t1 = _now_ns();
+        const uint64_t c1 = rdtsc();
+
+        assert(t1 > t0);
+        assert(c1 > c0);
+    }
+
     printf("=== passed test (%s)\n", argv[0]);
 
     return 0;
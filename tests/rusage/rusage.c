@@ -0,0 +1,216 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <fcntl.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+extern char** environ;
+
+static long _usecs(const struct timeval* tv)
+{
+    return tv->tv_sec * 1000000 + tv->tv_usec;
+}
+
+/* spin in user space for the given number of milliseconds */
+static void _burn(long msecs)
+{
+    struct timespec t0;
+    struct timespec t1;
+    volatile unsigned long x = 0;
+
+    clock_gettime(CLOCK_MONOTONIC, &t0);
+
+    do
+    {
+        for (size_t i = 0; i < 100000; i++)
+            x += i;
+
+        clock_gettime(CLOCK_MONOTONIC, &t1);
+    } while ((t1.tv_sec - t0.tv_sec) * 1000 +
+                 (t1.tv_nsec - t0.tv_nsec) / 1000000 <
+             msecs);
+}
+
+static void _read_file(const char* path, char* buf, size_t size)
+{
+    int fd;
+    ssize_t n;
+
+    assert((fd = open(path, O_RDONLY)) >= 0);
+    assert((n = read(fd, buf, size - 1)) > 0);
+    buf[n] = '\0';
+    close(fd);
+}
+
+static void test_self(void)
+{
+    struct rusage before;
+    struct rusage after;
+
+    assert(getrusage(RUSAGE_SELF, &before) == 0);
+    _burn(200);
+    assert(getrusage(RUSAGE_SELF, &after) == 0);
+
+    assert(_usecs(&after.ru_utime) > _usecs(&before.ru_utime));
+    assert(after.ru_utime.tv_usec < 1000000);
+    assert(after.ru_stime.tv_usec < 1000000);
+    assert(after.ru_maxrss > 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_maxrss(void)
+{
+    const size_t length = 8 * 1024 * 1024;
+    const int prot = PROT_READ | PROT_WRITE;
+    struct rusage before;
+    struct rusage after;
+    void* p;
+
+    assert(getrusage(RUSAGE_SELF, &before) == 0);
+
+    p = mmap(NULL, length, prot, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    assert(p != MAP_FAILED);
+    memset(p, 0xff, length);
+    assert(munmap(p, length) == 0);
+
+    assert(getrusage(RUSAGE_SELF, &after) == 0);
+
+    /* the peak may have been above the current size before the mapping */
+    assert(after.ru_maxrss - before.ru_maxrss >= (long)(length / 2048));
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_sleep(void)
+{
+    struct rusage before;
+    struct rusage after;
+    const struct timespec req = {0, 200 * 1000000};
+
+    assert(getrusage(RUSAGE_THREAD, &before) == 0);
+    assert(nanosleep(&req, NULL) == 0);
+    assert(getrusage(RUSAGE_THREAD, &after) == 0);
+
+    /* sleeping is a voluntary context switch and not system time */
+    assert(after.ru_nvcsw > before.ru_nvcsw);
+    assert(_usecs(&after.ru_stime) - _usecs(&before.ru_stime) < 100000);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_children(const char* program)
+{
+    char* argv[] = {(char*)program, "child", NULL};
+    struct rusage usage;
+    struct rusage children;
+    pid_t pid;
+    int wstatus;
+
+    assert(getrusage(RUSAGE_CHILDREN, &children) == 0);
+    assert(_usecs(&children.ru_utime) == 0);
+
+    assert(posix_spawn(&pid, program, NULL, NULL, argv, environ) == 0);
+    assert(wait4(pid, &wstatus, 0, &usage) == pid);
+    assert(WIFEXITED(wstatus) && WEXITSTATUS(wstatus) == 0);
+
+    /* the child spins for 200 milliseconds */
+    assert(_usecs(&usage.ru_utime) >= 100000);
+    assert(usage.ru_maxrss > 0);
+
+    assert(getrusage(RUSAGE_CHILDREN, &children) == 0);
+    assert(_usecs(&children.ru_utime) == _usecs(&usage.ru_utime));
+    assert(children.ru_maxrss == usage.ru_maxrss);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_proc_stat(void)
+{
+    char buf[4096];
+    char comm[64];
+    char state;
+    int pid;
+    int ppid;
+    unsigned long utime;
+    long num_threads;
+    unsigned long rss;
+    char* p;
+
+    _read_file("/proc/self/stat", buf, sizeof(buf));
+    printf("%s", buf);
+
+    assert(sscanf(buf, "%d (%63[^)]) %c %d", &pid, comm, &state, &ppid) == 4);
+    assert(pid == getpid());
+    assert(strcmp(comm, "rusage") == 0);
+    assert(state == 'R');
+
+    /* skip to field 14 (utime) after the comm field */
+    assert((p = strrchr(buf, ')')));
+    assert(
+        sscanf(
+            p + 2,
+            "%*c %*d %*d %*d %*d %*d %*u %*u %*u %*u %*u %lu %*u %*d %*d %*d "
+            "%*d %ld %*d %*u %*u %lu",
+            &utime,
+            &num_threads,
+            &rss) == 3);
+
+    /* this process has spun for at least 200 milliseconds */
+    assert(utime >= 10);
+    assert(num_threads == 1);
+    assert(rss > 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_proc_status(void)
+{
+    char buf[4096];
+    char* p;
+    size_t fds;
+
+    _read_file("/proc/self/status", buf, sizeof(buf));
+    printf("%s", buf);
+
+    assert(strstr(buf, "Name:\trusage\n"));
+    assert(strstr(buf, "Threads:\t1\n"));
+    assert(strstr(buf, "VmHWM:"));
+    assert(strstr(buf, "voluntary_ctxt_switches:"));
+
+    /* stdin, stdout and stderr */
+    assert((p = strstr(buf, "FDCount:\t")));
+    assert(sscanf(p, "FDCount:\t%zu", &fds) == 1);
+    assert(fds >= 3);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    if (argc == 2 && strcmp(argv[1], "child") == 0)
+    {
+        _burn(200);
+        return 0;
+    }
+
+    test_self();
+    test_maxrss();
+    test_sleep();
+    test_children(argv[0]);
+    test_proc_stat();
+    test_proc_status();
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
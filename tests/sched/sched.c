@@ -0,0 +1,249 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <pthread.h>
+#include <sched.h>
+#include <signal.h>
+#include <stdatomic.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <sys/resource.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#define NUM_YIELDERS 4
+
+static pid_t _gettid(void)
+{
+    return (pid_t)syscall(SYS_gettid);
+}
+
+void test_priority_range(void)
+{
+    assert(sched_get_priority_min(SCHED_FIFO) == 1);
+    assert(sched_get_priority_max(SCHED_FIFO) == 99);
+    assert(sched_get_priority_min(SCHED_RR) == 1);
+    assert(sched_get_priority_max(SCHED_RR) == 99);
+    assert(sched_get_priority_min(SCHED_OTHER) == 0);
+    assert(sched_get_priority_max(SCHED_OTHER) == 0);
+    assert(sched_get_priority_max(SCHED_IDLE) == 0);
+
+    assert(sched_get_priority_max(12345) == -1);
+    assert(errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void* _get_nice(void* arg)
+{
+    (void)arg;
+    errno = 0;
+    return (void*)(intptr_t)getpriority(PRIO_PROCESS, 0);
+}
+
+void test_nice(void)
+{
+    pthread_t thread;
+    void* child_nice;
+
+    errno = 0;
+    assert(getpriority(PRIO_PROCESS, 0) == 0);
+    assert(errno == 0);
+
+    assert(setpriority(PRIO_PROCESS, 0, 5) == 0);
+    assert(getpriority(PRIO_PROCESS, 0) == 5);
+    assert(getpriority(PRIO_PROCESS, _gettid()) == 5);
+
+    /* out-of-range values are clamped */
+    assert(setpriority(PRIO_PROCESS, 0, 100) == 0);
+    assert(getpriority(PRIO_PROCESS, 0) == 19);
+
+    /* new threads inherit the nice value of their creator */
+    assert(setpriority(PRIO_PROCESS, 0, 7) == 0);
+    assert(pthread_create(&thread, NULL, _get_nice, NULL) == 0);
+    assert(pthread_join(thread, &child_nice) == 0);
+    assert((intptr_t)child_nice == 7);
+
+    assert(setpriority(PRIO_PROCESS, 99999, 0) == -1);
+    assert(errno == ESRCH);
+
+    assert(setpriority(PRIO_PROCESS, 0, 0) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+void test_scheduler(void)
+{
+    struct sched_param param = {.sched_priority = 10};
+
+    assert(sched_getscheduler(0) == SCHED_OTHER);
+
+    assert(sched_setscheduler(0, SCHED_RR, &param) == 0);
+    assert(sched_getscheduler(0) == SCHED_RR);
+
+    param.sched_priority = 0;
+    assert(sched_getparam(0, &param) == 0);
+    assert(param.sched_priority == 10);
+
+    param.sched_priority = 20;
+    assert(sched_setparam(0, &param) == 0);
+    param.sched_priority = 0;
+    assert(sched_getparam(0, &param) == 0);
+    assert(param.sched_priority == 20);
+
+    /* the priority must be within the range of the policy */
+    param.sched_priority = 100;
+    assert(sched_setparam(0, &param) == -1);
+    assert(errno == EINVAL);
+    param.sched_priority = 1;
+    assert(sched_setscheduler(0, SCHED_OTHER, &param) == -1);
+    assert(errno == EINVAL);
+
+    param.sched_priority = 0;
+    assert(sched_setscheduler(0, SCHED_OTHER, &param) == 0);
+    assert(sched_getscheduler(0) == SCHED_OTHER);
+
+    assert(sched_getscheduler(-1) == -1);
+    assert(errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static _Atomic int _stop;
+static _Atomic uint64_t _progress[NUM_YIELDERS];
+
+static void* _yielder(void* arg)
+{
+    const size_t index = (size_t)(uintptr_t)arg;
+
+    /* mix nice values to check that no thread is starved */
+    setpriority(PRIO_PROCESS, 0, (int)index * 5);
+
+    while (!atomic_load(&_stop))
+    {
+        _progress[index]++;
+        sched_yield();
+    }
+
+    return NULL;
+}
+
+void test_yield(void)
+{
+    pthread_t threads[NUM_YIELDERS];
+
+    for (size_t i = 0; i < NUM_YIELDERS; i++)
+    {
+        void* arg = (void*)(uintptr_t)i;
+        assert(pthread_create(&threads[i], NULL, _yielder, arg) == 0);
+    }
+
+    sleep(1);
+    atomic_store(&_stop, 1);
+
+    for (size_t i = 0; i < NUM_YIELDERS; i++)
+    {
+        assert(pthread_join(threads[i], NULL) == 0);
+        printf("thread %zu: %lu yields\n", i, _progress[i]);
+        assert(_progress[i] > 0);
+    }
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void* _get_cpu(void* arg)
+{
+    unsigned int* cpu = (unsigned int*)arg;
+    unsigned int node;
+
+    assert(syscall(SYS_getcpu, cpu, &node, NULL) == 0);
+    assert(node == 0);
+
+    return NULL;
+}
+
+void test_cpu(void)
+{
+    cpu_set_t set;
+    unsigned int cpu;
+    unsigned int again;
+    int ncpus;
+
+    assert(sched_getaffinity(0, sizeof(set), &set) == 0);
+    ncpus = CPU_COUNT(&set);
+    assert(ncpus >= 1);
+    assert(sysconf(_SC_NPROCESSORS_ONLN) == ncpus);
+
+    /* a thread keeps its virtual CPU */
+    _get_cpu(&cpu);
+    _get_cpu(&again);
+    assert(cpu == again);
+    assert((int)cpu < ncpus);
+    assert(CPU_ISSET(cpu, &set));
+
+    /* each thread reports a CPU in the affinity mask */
+    {
+        pthread_t thread;
+        unsigned int other = (unsigned int)-1;
+
+        assert(pthread_create(&thread, NULL, _get_cpu, &other) == 0);
+        assert(pthread_join(thread, NULL) == 0);
+        assert((int)other < ncpus);
+    }
+
+    /* a mask without any of the CPUs is rejected */
+    CPU_ZERO(&set);
+    assert(sched_setaffinity(0, sizeof(set), &set) == -1);
+    assert(errno == EINVAL);
+
+    CPU_SET(0, &set);
+    assert(sched_setaffinity(0, sizeof(set), &set) == 0);
+
+    assert(sched_getaffinity(999999, sizeof(set), &set) == -1);
+    assert(errno == ESRCH);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+void test_tgkill(void)
+{
+    const pid_t pid = getpid();
+
+    /* the main thread's tid is the pid */
+    assert(_gettid() == pid);
+
+    /* signal 0 checks that the thread exists */
+    assert(syscall(SYS_tgkill, pid, _gettid(), 0) == 0);
+    assert(syscall(SYS_tkill, _gettid(), 0) == 0);
+
+    assert(syscall(SYS_tgkill, pid, 999999, 0) == -1);
+    assert(errno == ESRCH);
+
+    assert(syscall(SYS_tgkill, pid, _gettid(), -1) == -1);
+    assert(errno == EINVAL);
+
+    assert(syscall(SYS_tgkill, 0, _gettid(), 0) == -1);
+    assert(errno == EINVAL);
+
+    assert(syscall(SYS_tkill, 0, 0) == -1);
+    assert(errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_priority_range();
+    test_nice();
+    test_scheduler();
+    test_yield();
+    test_cpu();
+    test_tgkill();
+
+    printf("=== passed test (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,199 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+/* send fds[] with one byte of data */
+static void _send_fds(int sock, const int* fds, size_t nfds)
+{
+    char data = 'x';
+    struct iovec iov = {.iov_base = &data, .iov_len = 1};
+    char control[CMSG_SPACE(sizeof(int) * 4)];
+    struct msghdr msg;
+    struct cmsghdr* cmsg;
+
+    assert(nfds <= 4);
+
+    memset(&msg, 0, sizeof(msg));
+    memset(control, 0, sizeof(control));
+    msg.msg_iov = &iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control;
+    msg.msg_controllen = CMSG_SPACE(sizeof(int) * nfds);
+
+    cmsg = CMSG_FIRSTHDR(&msg);
+    cmsg->cmsg_level = SOL_SOCKET;
+    cmsg->cmsg_type = SCM_RIGHTS;
+    cmsg->cmsg_len = CMSG_LEN(sizeof(int) * nfds);
+    memcpy(CMSG_DATA(cmsg), fds, sizeof(int) * nfds);
+
+    assert(sendmsg(sock, &msg, 0) == 1);
+}
+
+/* receive up to max descriptors; returns how many there were */
+static size_t _recv_fds(int sock, int* fds, size_t max, int flags, int* mflags)
+{
+    char data;
+    struct iovec iov = {.iov_base = &data, .iov_len = 1};
+    char control[CMSG_SPACE(sizeof(int) * 4)];
+    struct msghdr msg;
+    struct cmsghdr* cmsg;
+    size_t n = 0;
+
+    assert(max <= 4);
+
+    memset(&msg, 0, sizeof(msg));
+    msg.msg_iov = &iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control;
+    msg.msg_controllen = CMSG_LEN(sizeof(int) * max);
+
+    assert(recvmsg(sock, &msg, flags) == 1);
+    assert(data == 'x');
+
+    for (cmsg = CMSG_FIRSTHDR(&msg); cmsg; cmsg = CMSG_NXTHDR(&msg, cmsg))
+    {
+        if (cmsg->cmsg_level == SOL_SOCKET && cmsg->cmsg_type == SCM_RIGHTS)
+        {
+            n = (cmsg->cmsg_len - CMSG_LEN(0)) / sizeof(int);
+            assert(n <= max);
+            memcpy(fds, CMSG_DATA(cmsg), sizeof(int) * n);
+        }
+    }
+
+    *mflags = msg.msg_flags;
+    return n;
+}
+
+static void test_pipe(int sv[2])
+{
+    int pipefd[2];
+    int fd;
+    int mflags;
+    char buf[8];
+
+    assert(pipe(pipefd) == 0);
+    _send_fds(sv[0], &pipefd[1], 1);
+
+    /* the received descriptor writes into the same pipe */
+    assert(_recv_fds(sv[1], &fd, 1, 0, &mflags) == 1);
+    assert(!(mflags & MSG_CTRUNC));
+    assert(fd != pipefd[1]);
+    assert(fcntl(fd, F_GETFD) == 0);
+
+    /* it stays open after the sender closes its own */
+    assert(close(pipefd[1]) == 0);
+    assert(write(fd, "abc", 3) == 3);
+    assert(close(fd) == 0);
+    assert(read(pipefd[0], buf, sizeof(buf)) == 3);
+    assert(memcmp(buf, "abc", 3) == 0);
+
+    /* end of file once every reference to the write end is closed */
+    assert(read(pipefd[0], buf, sizeof(buf)) == 0);
+    assert(close(pipefd[0]) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_cloexec(int sv[2])
+{
+    int fd;
+    int received;
+    int mflags;
+
+    assert((fd = open("/scmrights", O_CREAT | O_RDWR | O_CLOEXEC, 0666)) >= 0);
+    _send_fds(sv[0], &fd, 1);
+
+    assert(_recv_fds(sv[1], &received, 1, MSG_CMSG_CLOEXEC, &mflags) == 1);
+    assert(fcntl(received, F_GETFD) == FD_CLOEXEC);
+    assert(write(received, "abc", 3) == 3);
+
+    assert(close(received) == 0);
+    assert(close(fd) == 0);
+    assert(unlink("/scmrights") == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_truncated(int sv[2])
+{
+    int pipefd[2];
+    int fd;
+    int mflags;
+
+    assert(pipe(pipefd) == 0);
+    _send_fds(sv[0], pipefd, 2);
+
+    /* only the first one fits: the other is closed */
+    assert(_recv_fds(sv[1], &fd, 1, 0, &mflags) == 1);
+    assert(mflags & MSG_CTRUNC);
+    assert(close(fd) == 0);
+
+    assert(close(pipefd[0]) == 0);
+    assert(close(pipefd[1]) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_discarded(void)
+{
+    int sv[2];
+    int pipefd[2];
+    char buf[8];
+
+    assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+    assert(pipe(pipefd) == 0);
+    _send_fds(sv[0], &pipefd[1], 1);
+
+    /* the message is never received */
+    assert(close(sv[1]) == 0);
+    assert(close(sv[0]) == 0);
+    assert(close(pipefd[1]) == 0);
+
+    /* the next message in flight releases the dropped descriptor */
+    {
+        int sv2[2];
+        int dummy[2];
+        int fd;
+        int mflags;
+
+        assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv2) == 0);
+        assert(pipe(dummy) == 0);
+        _send_fds(sv2[0], &dummy[0], 1);
+        assert(_recv_fds(sv2[1], &fd, 1, 0, &mflags) == 1);
+        assert(close(fd) == 0);
+        assert(close(dummy[0]) == 0);
+        assert(close(dummy[1]) == 0);
+        assert(close(sv2[0]) == 0);
+        assert(close(sv2[1]) == 0);
+    }
+
+    assert(read(pipefd[0], buf, sizeof(buf)) == 0);
+    assert(close(pipefd[0]) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    int sv[2];
+
+    assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+
+    test_pipe(sv);
+    test_cloexec(sv);
+    test_truncated(sv);
+    test_discarded();
+
+    assert(close(sv[0]) == 0);
+    assert(close(sv[1]) == 0);
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,381 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdarg.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include <myst/atexit.h>
+#include <myst/audit.h>
+#include <myst/file.h>
+#include <myst/printf.h>
+#include <myst/secret.h>
+#include <myst/tcall.h>
+
+/*
+**==============================================================================
+**
+** what kernel/secret.c and the providers take from the rest of the kernel
+**
+**==============================================================================
+*/
+
+/* the last attestation event */
+static const char* _audit_request;
+static long _audit_ret = 1;
+
+/* blobs "sealed" by this test start with this prefix */
+#define SEALED "sealed:"
+
+int myst_eprintf(const char* format, ...)
+{
+    va_list ap;
+    int n;
+
+    va_start(ap, format);
+    n = vfprintf(stderr, format, ap);
+    va_end(ap);
+
+    return n;
+}
+
+int myst_atexit(void (*function)(void*), void* arg)
+{
+    (void)function;
+    (void)arg;
+    return 0;
+}
+
+void myst_audit_attestation(const char* request, long ret)
+{
+    _audit_request = request;
+    _audit_ret = ret;
+}
+
+/* stands in for oe_unseal(): only blobs with the prefix unseal */
+long myst_tcall_unseal_secret(
+    const void* blob,
+    size_t blob_size,
+    void* buf,
+    size_t size,
+    size_t* size_out)
+{
+    const size_t n = sizeof(SEALED) - 1;
+
+    if (blob_size < n || memcmp(blob, SEALED, n) != 0)
+        return -EACCES;
+
+    if (blob_size - n > size)
+        return -ERANGE;
+
+    memcpy(buf, (const char*)blob + n, blob_size - n);
+    *size_out = blob_size - n;
+
+    return 0;
+}
+
+long myst_tcall_key_release(
+    const myst_key_release_request_t* request,
+    void* key,
+    size_t key_size,
+    size_t* key_size_out)
+{
+    (void)request;
+    (void)key;
+    (void)key_size;
+    (void)key_size_out;
+    return -ENOTSUP;
+}
+
+long myst_tcall_attested_https_get(
+    const myst_attested_https_request_t* request,
+    void* buf,
+    size_t size,
+    size_t* size_out)
+{
+    (void)request;
+    (void)buf;
+    (void)size;
+    (void)size_out;
+    return -ENOTSUP;
+}
+
+/*
+**==============================================================================
+**
+** a custom provider, which returns the id as the secret
+**
+**==============================================================================
+*/
+
+static int _echo_get(
+    myst_secret_provider_t* provider,
+    const myst_secret_t* secret,
+    void* buf,
+    size_t size,
+    size_t* size_out)
+{
+    const size_t len = strlen(secret->id);
+
+    (void)provider;
+
+    if (len > size)
+        return -ERANGE;
+
+    memcpy(buf, secret->id, len);
+    *size_out = len;
+
+    return 0;
+}
+
+static myst_secret_provider_t _echo_provider = {
+    .name = "echo",
+    .get = _echo_get,
+};
+
+/* a provider that takes the name of a built-in one */
+static myst_secret_provider_t _sealed_file_provider = {
+    .name = "sealed-file",
+    .get = _echo_get,
+};
+
+static myst_secret_provider_t _no_get_provider = {.name = "no-get"};
+
+static int _register_echo_ret = 1;
+static int _register_taken_ret = 1;
+static int _register_no_get_ret = 1;
+
+/* overrides the weak definition in kernel/secret.c */
+void myst_register_custom_secret_providers(void)
+{
+    _register_echo_ret = myst_register_secret_provider(&_echo_provider);
+    _register_taken_ret =
+        myst_register_secret_provider(&_sealed_file_provider);
+    _register_no_get_ret = myst_register_secret_provider(&_no_get_provider);
+}
+
+/*
+**==============================================================================
+**
+** tests
+**
+**==============================================================================
+*/
+
+static char _tmp[] = "/tmp/mystsecretsXXXXXX";
+static char _path[PATH_MAX];
+
+static const char* _path_of(const char* name)
+{
+    snprintf(_path, sizeof(_path), "%s/%s", _tmp, name);
+    return _path;
+}
+
+static void _write(const char* path, const char* data)
+{
+    int fd;
+
+    assert((fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, 0600)) >= 0);
+    assert(write(fd, data, strlen(data)) == (ssize_t)strlen(data));
+    assert(close(fd) == 0);
+}
+
+static int _setup(
+    myst_secret_t* secrets,
+    size_t num_secrets,
+    size_t* envc,
+    const char*** envp)
+{
+    myst_secrets_config_t config = {NULL, secrets, num_secrets};
+    myst_key_release_config_t key_release = {0};
+
+    return myst_secrets_setup(&config, &key_release, envc, envp);
+}
+
+/* the registry is global, so each test runs in a process of its own */
+static void _run(void (*test)(void))
+{
+    pid_t pid;
+    int status;
+
+    assert((pid = fork()) >= 0);
+
+    if (pid == 0)
+    {
+        (*test)();
+        exit(0);
+    }
+
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+static void test_custom_provider(void)
+{
+    const char* env[] = {"HOME=/", "ECHO=old", NULL};
+    const char** envp = env;
+    size_t envc = 2;
+    char file[PATH_MAX + 8];
+    char dir[PATH_MAX];
+    struct stat st;
+    void* data;
+    size_t size;
+    myst_secret_t secrets[] = {
+        {"echo", "hello", "ECHO", file},
+    };
+
+    snprintf(dir, sizeof(dir), "%s/custom/run", _tmp);
+    snprintf(file, sizeof(file), "%s/secret", dir);
+
+    assert(_setup(secrets, 1, &envc, &envp) == 0);
+
+    /* the custom provider was registered with the built-in ones */
+    assert(_register_echo_ret == 0);
+    assert(myst_find_secret_provider("echo") == &_echo_provider);
+    assert(
+        myst_find_secret_provider("sealed-file") != &_sealed_file_provider);
+    assert(myst_find_secret_provider("key-release"));
+    assert(myst_find_secret_provider("attested-https"));
+
+    /* names are unique and a provider needs a get function */
+    assert(_register_taken_ret == -EEXIST);
+    assert(_register_no_get_ret == -EINVAL);
+    assert(!myst_find_secret_provider("no-get"));
+
+    /* the secret replaces the earlier definition, as hex */
+    assert(envp != env && envc == 2);
+    assert(strcmp(envp[0], "HOME=/") == 0);
+    assert(strcmp(envp[1], "ECHO=68656c6c6f") == 0);
+    assert(envp[2] == NULL);
+
+    /* and is written to the file, which only the owner can read */
+    assert(myst_load_file(file, &data, &size) == 0);
+    assert(size == 5 && memcmp(data, "hello", 5) == 0);
+    free(data);
+
+    assert(stat(file, &st) == 0 && (st.st_mode & 0777) == 0400);
+    assert(stat(dir, &st) == 0 && (st.st_mode & 0777) == 0700);
+
+    assert(strcmp(_audit_request, "echo") == 0 && _audit_ret == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_sealed_file(void)
+{
+    const char* env[] = {NULL};
+    const char** envp = env;
+    size_t envc = 0;
+    char blob[PATH_MAX];
+    myst_secret_t secrets[] = {
+        {"sealed-file", blob, "DB_PASSWORD", NULL},
+        {"echo", "x", "ECHO", NULL},
+    };
+
+    snprintf(blob, sizeof(blob), "%s", _path_of("db-password.sealed"));
+    _write(blob, SEALED "pw");
+
+    /* the file is unsealed by the target and the secrets are in order */
+    assert(_setup(secrets, 2, &envc, &envp) == 0);
+    assert(envc == 2);
+    assert(strcmp(envp[0], "DB_PASSWORD=7077") == 0);
+    assert(strcmp(envp[1], "ECHO=78") == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_sealed_file_not_unsealed(void)
+{
+    const char* env[] = {"HOME=/", NULL};
+    const char** envp = env;
+    size_t envc = 1;
+    char blob[PATH_MAX];
+    myst_secret_t secrets[] = {
+        {"sealed-file", blob, "DB_PASSWORD", NULL},
+    };
+
+    snprintf(blob, sizeof(blob), "%s", _path_of("other.sealed"));
+    _write(blob, "sealed by another signer");
+
+    /* the setup fails, and the environment is left alone */
+    assert(_setup(secrets, 1, &envc, &envp) == -EACCES);
+    assert(envp == env && envc == 1);
+    assert(strcmp(_audit_request, "sealed-file") == 0);
+    assert(_audit_ret == -EACCES);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_sealed_file_missing(void)
+{
+    const char* env[] = {NULL};
+    const char** envp = env;
+    size_t envc = 0;
+    myst_secret_t secrets[] = {
+        {"sealed-file", "/no/such/file.sealed", "DB_PASSWORD", NULL},
+    };
+
+    assert(_setup(secrets, 1, &envc, &envp) == -ENOENT);
+    assert(envp == env && envc == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static myst_secret_t _bad_secrets[] = {
+    {"no-such-provider", "x", "X", NULL},
+    {"echo", "x", NULL, NULL},
+    {"echo", "x", "A=B", NULL},
+    {"echo", NULL, "X", NULL},
+};
+
+static size_t _bad_secret;
+
+static void _setup_bad_secret(void)
+{
+    const char* env[] = {NULL};
+    const char** envp = env;
+    size_t envc = 0;
+
+    /* these are refused before any provider is asked */
+    assert(_setup(&_bad_secrets[_bad_secret], 1, &envc, &envp) == -EINVAL);
+    assert(envp == env && envc == 0);
+    assert(!_audit_request);
+}
+
+static void test_bad_secrets(void)
+{
+    const size_t n = sizeof(_bad_secrets) / sizeof(_bad_secrets[0]);
+
+    for (_bad_secret = 0; _bad_secret < n; _bad_secret++)
+        _run(_setup_bad_secret);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    char cmd[PATH_MAX];
+
+    (void)argc;
+
+    assert(mkdtemp(_tmp));
+
+    _run(test_custom_provider);
+    _run(test_sealed_file);
+    _run(test_sealed_file_not_unsealed);
+    _run(test_sealed_file_missing);
+    test_bad_secrets();
+
+    snprintf(cmd, sizeof(cmd), "rm -rf %s", _tmp);
+    assert(system(cmd) == 0);
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
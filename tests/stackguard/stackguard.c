@@ -0,0 +1,248 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <pthread.h>
+#include <setjmp.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define STACK_SIZE (128 * 1024)
+#define GUARD_SIZE (16 * 1024)
+#define ALTSTACK_SIZE (64 * 1024)
+
+extern char** environ;
+
+static char _altstack[ALTSTACK_SIZE];
+
+static _Atomic int _handled;
+static volatile void* _handler_local;
+static volatile int _handler_ss_flags;
+static volatile int _handler_ss_errno;
+
+static bool _on_altstack(const volatile void* p)
+{
+    const char* q = (const char*)p;
+    return q >= _altstack && q < _altstack + sizeof(_altstack);
+}
+
+static void _usr1_handler(int signum)
+{
+    int local;
+    stack_t ss;
+    stack_t new_ss = {.ss_sp = _altstack, .ss_size = sizeof(_altstack)};
+
+    assert(signum == SIGUSR1);
+    _handler_local = &local;
+
+    /* the stack reports SS_ONSTACK and cannot be changed from the handler */
+    assert(sigaltstack(NULL, &ss) == 0);
+    _handler_ss_flags = ss.ss_flags;
+
+    if (sigaltstack(&new_ss, NULL) != 0)
+        _handler_ss_errno = errno;
+
+    _handled++;
+}
+
+static void test_sigaltstack(void)
+{
+    stack_t ss;
+    stack_t old;
+    struct sigaction act = {.sa_handler = _usr1_handler};
+
+    /* there is no alternate stack initially */
+    assert(sigaltstack(NULL, &old) == 0);
+    assert(old.ss_flags == SS_DISABLE);
+
+    /* too small */
+    ss.ss_sp = _altstack;
+    ss.ss_size = 1024;
+    ss.ss_flags = 0;
+    assert(sigaltstack(&ss, NULL) == -1 && errno == ENOMEM);
+
+    /* bad flags */
+    ss.ss_size = sizeof(_altstack);
+    ss.ss_flags = 0x1234;
+    assert(sigaltstack(&ss, NULL) == -1 && errno == EINVAL);
+
+    ss.ss_flags = 0;
+    assert(sigaltstack(&ss, NULL) == 0);
+    assert(sigaltstack(NULL, &old) == 0);
+    assert(old.ss_sp == _altstack);
+    assert(old.ss_size == sizeof(_altstack));
+    assert(old.ss_flags == 0);
+
+    /* SA_ONSTACK runs the handler on the alternate stack */
+    act.sa_flags = SA_ONSTACK;
+    assert(sigaction(SIGUSR1, &act, NULL) == 0);
+    _handled = 0;
+    assert(raise(SIGUSR1) == 0);
+    assert(_handled == 1);
+    assert(_on_altstack(_handler_local));
+    assert(_handler_ss_flags == SS_ONSTACK);
+    assert(_handler_ss_errno == EPERM);
+
+    /* without SA_ONSTACK the handler runs on the thread stack */
+    act.sa_flags = 0;
+    assert(sigaction(SIGUSR1, &act, NULL) == 0);
+    assert(raise(SIGUSR1) == 0);
+    assert(_handled == 2);
+    assert(!_on_altstack(_handler_local));
+
+    /* disable the alternate stack */
+    ss.ss_flags = SS_DISABLE;
+    assert(sigaltstack(&ss, &old) == 0);
+    assert(old.ss_sp == _altstack);
+    assert(sigaltstack(NULL, &old) == 0);
+    assert(old.ss_flags == SS_DISABLE);
+
+    signal(SIGUSR1, SIG_DFL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static sigjmp_buf _jmpbuf;
+static void* _fault_addr;
+static int _fault_code;
+static volatile void* _segv_local;
+
+static void _segv_handler(int signum, siginfo_t* si, void* context)
+{
+    int local;
+
+    (void)context;
+    assert(signum == SIGSEGV);
+
+    _segv_local = &local;
+    _fault_addr = si->si_addr;
+    _fault_code = si->si_code;
+
+    siglongjmp(_jmpbuf, 1);
+}
+
+static volatile int _max_depth = 1024 * 1024;
+
+/* recurse until the stack overflows, entering the kernel on every call */
+static int _recurse(int depth)
+{
+    volatile char buf[512];
+
+    memset((char*)buf, depth, sizeof(buf));
+    getpid();
+
+    if (depth == _max_depth)
+        return 0;
+
+    return _recurse(depth + 1) + buf[0];
+}
+
+static void _get_stack(void** addr, size_t* size)
+{
+    pthread_attr_t attr;
+
+    assert(pthread_getattr_np(pthread_self(), &attr) == 0);
+    assert(pthread_attr_getstack(&attr, addr, size) == 0);
+    pthread_attr_destroy(&attr);
+}
+
+static void* _overflow_thread(void* arg)
+{
+    stack_t ss = {.ss_sp = _altstack, .ss_size = sizeof(_altstack)};
+    struct sigaction act = {.sa_sigaction = _segv_handler};
+    void* stack;
+    size_t size;
+
+    (void)arg;
+
+    /* the alternate stack belongs to the thread */
+    assert(sigaltstack(&ss, NULL) == 0);
+
+    act.sa_flags = SA_SIGINFO | SA_ONSTACK;
+    assert(sigaction(SIGSEGV, &act, NULL) == 0);
+
+    _get_stack(&stack, &size);
+
+    if (sigsetjmp(_jmpbuf, 1) == 0)
+    {
+        _recurse(0);
+        assert(0);
+    }
+
+    /* the fault is in the guard just below the stack */
+    assert(_fault_code == SEGV_ACCERR);
+    assert((uint8_t*)_fault_addr < (uint8_t*)stack);
+    assert((uint8_t*)_fault_addr >= (uint8_t*)stack - GUARD_SIZE);
+    assert(_on_altstack(_segv_local));
+
+    signal(SIGSEGV, SIG_DFL);
+
+    return NULL;
+}
+
+static void _run_overflow_thread(void* (*start)(void*))
+{
+    pthread_attr_t attr;
+    pthread_t thread;
+
+    assert(pthread_attr_init(&attr) == 0);
+    assert(pthread_attr_setstacksize(&attr, STACK_SIZE) == 0);
+    assert(pthread_attr_setguardsize(&attr, GUARD_SIZE) == 0);
+    assert(pthread_create(&thread, &attr, start, NULL) == 0);
+    assert(pthread_join(thread, NULL) == 0);
+    pthread_attr_destroy(&attr);
+}
+
+static void test_overflow(void)
+{
+    _run_overflow_thread(_overflow_thread);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void* _crash_thread(void* arg)
+{
+    (void)arg;
+    _recurse(0);
+    return NULL;
+}
+
+static void test_crash(const char* program)
+{
+    char* argv[] = {(char*)program, "crash", NULL};
+    pid_t pid;
+    int wstatus;
+
+    /* without a handler the overflow takes down the process */
+    assert(posix_spawn(&pid, program, NULL, NULL, argv, environ) == 0);
+    assert(waitpid(pid, &wstatus, 0) == pid);
+    assert(!(WIFEXITED(wstatus) && WEXITSTATUS(wstatus) == 0));
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    if (argc == 2 && strcmp(argv[1], "crash") == 0)
+    {
+        _run_overflow_thread(_crash_thread);
+        printf("crash: the thread survived the overflow\n");
+        return 0;
+    }
+
+    test_sigaltstack();
+    test_overflow();
+    test_crash(argv[0]);
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
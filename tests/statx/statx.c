@@ -0,0 +1,169 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+/* the statx ABI (no statx() wrapper or kernel headers in the test image) */
+
+#ifndef SYS_statx
+#define SYS_statx 332
+#endif
+
+#define STATX_TYPE_ 0x00000001U
+#define STATX_MODE_ 0x00000002U
+#define STATX_SIZE_ 0x00000200U
+#define STATX_BASIC_STATS_ 0x000007ffU
+#define STATX_BTIME_ 0x00000800U
+#define STATX__RESERVED_ 0x80000000U
+
+struct statx_timestamp_
+{
+    int64_t tv_sec;
+    uint32_t tv_nsec;
+    int32_t __reserved;
+};
+
+struct statx_
+{
+    uint32_t stx_mask;
+    uint32_t stx_blksize;
+    uint64_t stx_attributes;
+    uint32_t stx_nlink;
+    uint32_t stx_uid;
+    uint32_t stx_gid;
+    uint16_t stx_mode;
+    uint16_t __spare0[1];
+    uint64_t stx_ino;
+    uint64_t stx_size;
+    uint64_t stx_blocks;
+    uint64_t stx_attributes_mask;
+    struct statx_timestamp_ stx_atime;
+    struct statx_timestamp_ stx_btime;
+    struct statx_timestamp_ stx_ctime;
+    struct statx_timestamp_ stx_mtime;
+    uint32_t stx_rdev_major;
+    uint32_t stx_rdev_minor;
+    uint32_t stx_dev_major;
+    uint32_t stx_dev_minor;
+    uint64_t stx_mnt_id;
+    uint64_t __spare2;
+    uint64_t __spare3[12];
+};
+
+static int _statx(
+    int dirfd,
+    const char* path,
+    int flags,
+    unsigned int mask,
+    struct statx_* buf)
+{
+    return (int)syscall(SYS_statx, dirfd, path, flags, mask, buf);
+}
+
+static void test_basic(void)
+{
+    struct statx_ stx;
+    struct stat st;
+    int fd;
+
+    assert((fd = open("/statx.txt", O_CREAT | O_TRUNC | O_WRONLY, 0640)) >= 0);
+    assert(write(fd, "hello", 5) == 5);
+    assert(close(fd) == 0);
+
+    /* the basic fields are returned even if only some are asked for */
+    assert(_statx(AT_FDCWD, "/statx.txt", 0, STATX_SIZE_, &stx) == 0);
+    assert((stx.stx_mask & STATX_BASIC_STATS_) == STATX_BASIC_STATS_);
+    assert(stat("/statx.txt", &st) == 0);
+    assert(stx.stx_size == 5);
+    assert(stx.stx_mode == st.st_mode);
+    assert(S_ISREG(stx.stx_mode) && (stx.stx_mode & 0777) == 0640);
+    assert(stx.stx_ino == st.st_ino);
+    assert(stx.stx_nlink == st.st_nlink);
+    assert(stx.stx_uid == st.st_uid && stx.stx_gid == st.st_gid);
+    assert(stx.stx_mtime.tv_sec == st.st_mtim.tv_sec);
+    assert(stx.stx_mtime.tv_nsec == st.st_mtim.tv_nsec);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_btime(void)
+{
+    struct statx_ stx;
+
+    /* the creation time is only returned when asked for */
+    assert(_statx(AT_FDCWD, "/statx.txt", 0, STATX_BTIME_, &stx) == 0);
+
+    if (!(stx.stx_mask & STATX_BTIME_))
+    {
+        printf("=== skipped test (%s)\n", __FUNCTION__);
+        return;
+    }
+
+    assert(stx.stx_btime.tv_sec != 0);
+    assert(stx.stx_btime.tv_sec <= stx.stx_mtime.tv_sec);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_flags(void)
+{
+    struct statx_ stx;
+    int flags;
+    int fd;
+
+    assert(symlink("/statx.txt", "/statx.lnk") == 0);
+
+    /* follow the link unless AT_SYMLINK_NOFOLLOW */
+    assert(_statx(AT_FDCWD, "/statx.lnk", 0, STATX_TYPE_, &stx) == 0);
+    assert(S_ISREG(stx.stx_mode));
+    flags = AT_SYMLINK_NOFOLLOW;
+    assert(_statx(AT_FDCWD, "/statx.lnk", flags, STATX_TYPE_, &stx) == 0);
+    assert(S_ISLNK(stx.stx_mode));
+
+    /* AT_EMPTY_PATH gets the file of the descriptor */
+    assert((fd = open("/statx.txt", O_RDONLY)) >= 0);
+    assert(_statx(fd, "", AT_EMPTY_PATH, STATX_SIZE_, &stx) == 0);
+    assert(stx.stx_size == 5);
+    assert(_statx(fd, "", 0, STATX_SIZE_, &stx) == -1 && errno == ENOENT);
+    assert(close(fd) == 0);
+
+    /* relative to a directory */
+    assert((fd = open("/", O_RDONLY | O_DIRECTORY)) >= 0);
+    assert(_statx(fd, "statx.txt", 0, STATX_SIZE_, &stx) == 0);
+    assert(stx.stx_size == 5);
+    assert(close(fd) == 0);
+
+    assert(_statx(AT_FDCWD, "/none", 0, STATX_SIZE_, &stx) == -1);
+    assert(errno == ENOENT);
+
+    /* unknown flags and the reserved mask bit */
+    assert(_statx(AT_FDCWD, "/statx.txt", 0x1, STATX_SIZE_, &stx) == -1);
+    assert(errno == EINVAL);
+    assert(_statx(AT_FDCWD, "/statx.txt", 0, STATX__RESERVED_, &stx) == -1);
+    assert(errno == EINVAL);
+
+    assert(unlink("/statx.lnk") == 0);
+    assert(unlink("/statx.txt") == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_basic();
+    test_btime();
+    test_flags();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -163,6 +163,48 @@ void test_thread_name()
     }
 }
 
+static void _read_comm(const char* path, char* buf, size_t size)
+{
+    FILE* is;
+
+    assert((is = fopen(path, "r")));
+    assert(fgets(buf, size, is));
+    fclose(is);
+}
+
+void test_comm(const char* argv0)
+{
+    char buf[BUFSIZ];
+    char path[64];
+    char expect[32];
+    const char* slash = strrchr(argv0, '/');
+
+    // The process is named after the basename of the executable
+    snprintf(expect, sizeof(expect), "%.15s\n", slash ? slash + 1 : argv0);
+    _read_comm("/proc/self/comm", buf, sizeof(buf));
+    assert(strcmp(buf, expect) == 0);
+
+    // /proc/[pid]/comm follows PR_SET_NAME
+    assert(prctl(PR_SET_NAME, NEWNAME) == 0);
+    snprintf(path, sizeof(path), "/proc/%d/comm", getpid());
+    _read_comm(path, buf, sizeof(buf));
+    assert(strcmp(buf, NEWNAME "\n") == 0);
+}
+
+void test_dumpable()
+{
+    assert(prctl(PR_GET_DUMPABLE) == 1);
+
+    assert(prctl(PR_SET_DUMPABLE, 0) == 0);
+    assert(prctl(PR_GET_DUMPABLE) == 0);
+
+    assert(prctl(PR_SET_DUMPABLE, 2) == -1);
+    assert(prctl(PR_GET_DUMPABLE) == 0);
+
+    assert(prctl(PR_SET_DUMPABLE, 1) == 0);
+    assert(prctl(PR_GET_DUMPABLE) == 1);
+}
+
 int main(int argc, const char* argv[])
 {
     assert(test_uptime());
@@ -171,7 +213,9 @@ int main(int argc, const char* argv[])
 
     test_getrusage();
     test_prlimit();
+    test_comm(argv[0]);
     test_thread_name();
+    test_dumpable();
 
     printf("\n=== passed test (%s)\n", argv[0]);
 
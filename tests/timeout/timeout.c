@@ -0,0 +1,159 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <linux/futex.h>
+#include <poll.h>
+#include <pthread.h>
+#include <stdbool.h>
+#include <stdio.h>
+#include <sys/epoll.h>
+#include <sys/select.h>
+#include <sys/syscall.h>
+#include <time.h>
+#include <unistd.h>
+
+/* the waits below may end this much early (the host rounds timeouts) */
+#define SLACK_MSEC 10
+
+static long _now_msec(void)
+{
+    struct timespec ts;
+
+    assert(clock_gettime(CLOCK_MONOTONIC, &ts) == 0);
+    return ts.tv_sec * 1000 + ts.tv_nsec / 1000000;
+}
+
+/* Write to a pipe that nobody waits for every few milliseconds, which
+ * wakes every thread that polls (in Mystikos) */
+static int _noise[2];
+static volatile bool _stop;
+
+static void* _noise_thread(void* arg)
+{
+    char c = 0;
+
+    while (!_stop)
+    {
+        struct timespec ts = {0, 5 * 1000000};
+
+        assert(write(_noise[1], &c, 1) == 1);
+        assert(read(_noise[0], &c, 1) == 1);
+        nanosleep(&ts, NULL);
+    }
+
+    return arg;
+}
+
+static void test_poll(int idle)
+{
+    struct pollfd fds[] = {{.fd = idle, .events = POLLIN}};
+    long start = _now_msec();
+
+    assert(poll(fds, 1, 300) == 0);
+    assert(_now_msec() - start >= 300 - SLACK_MSEC);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_epoll(int idle)
+{
+    struct epoll_event ev = {.events = EPOLLIN};
+    int epfd;
+    long start;
+
+    assert((epfd = epoll_create1(0)) >= 0);
+    assert(epoll_ctl(epfd, EPOLL_CTL_ADD, idle, &ev) == 0);
+
+    start = _now_msec();
+    assert(epoll_wait(epfd, &ev, 1, 300) == 0);
+    assert(_now_msec() - start >= 300 - SLACK_MSEC);
+
+    assert(close(epfd) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_select(int idle, int ready)
+{
+    fd_set rfds;
+    struct timeval tv = {0, 300 * 1000};
+    long start = _now_msec();
+
+    FD_ZERO(&rfds);
+    FD_SET(idle, &rfds);
+    assert(select(idle + 1, &rfds, NULL, NULL, &tv) == 0);
+    assert(_now_msec() - start >= 300 - SLACK_MSEC);
+
+    /* the timeout is updated with the time that was not slept */
+    assert(tv.tv_sec == 0 && tv.tv_usec == 0);
+
+    tv.tv_sec = 10;
+    tv.tv_usec = 0;
+    FD_ZERO(&rfds);
+    FD_SET(ready, &rfds);
+    assert(select(ready + 1, &rfds, NULL, NULL, &tv) == 1);
+    assert(FD_ISSET(ready, &rfds));
+    assert(tv.tv_sec >= 9 && tv.tv_sec <= 10);
+
+    /* no descriptors: a sleep */
+    tv.tv_sec = 0;
+    tv.tv_usec = 100 * 1000;
+    start = _now_msec();
+    assert(select(0, NULL, NULL, NULL, &tv) == 0);
+    assert(_now_msec() - start >= 100 - SLACK_MSEC);
+
+    /* a malformed timeout */
+    tv.tv_sec = 0;
+    tv.tv_usec = -1;
+    assert(select(0, NULL, NULL, NULL, &tv) == -1 && errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_futex(void)
+{
+    static int word;
+    struct timespec ts = {0, 300 * 1000000};
+    long start = _now_msec();
+    long r;
+
+    r = syscall(SYS_futex, &word, FUTEX_WAIT_PRIVATE, 0, &ts, NULL, 0);
+    assert(r == -1 && errno == ETIMEDOUT);
+    assert(_now_msec() - start >= 300 - SLACK_MSEC);
+
+    ts.tv_nsec = 1000000000;
+    r = syscall(SYS_futex, &word, FUTEX_WAIT_PRIVATE, 0, &ts, NULL, 0);
+    assert(r == -1 && errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    int idle[2];
+    int ready[2];
+    pthread_t thread;
+    char c = 0;
+
+    assert(pipe(idle) == 0);
+    assert(pipe(ready) == 0);
+    assert(pipe(_noise) == 0);
+    assert(write(ready[1], &c, 1) == 1);
+
+    assert(pthread_create(&thread, NULL, _noise_thread, NULL) == 0);
+
+    test_poll(idle[0]);
+    test_epoll(idle[0]);
+    test_select(idle[0], ready[0]);
+    test_futex();
+
+    _stop = true;
+    assert(pthread_join(thread, NULL) == 0);
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
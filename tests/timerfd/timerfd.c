@@ -0,0 +1,206 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/timerfd.h>
+#include <time.h>
+#include <unistd.h>
+
+#define MSEC 1000000
+
+static long _now_msec(clockid_t clockid)
+{
+    struct timespec ts;
+
+    assert(clock_gettime(clockid, &ts) == 0);
+    return ts.tv_sec * 1000 + ts.tv_nsec / MSEC;
+}
+
+static void _set_realtime(long delta_sec)
+{
+    struct timespec ts;
+
+    assert(clock_gettime(CLOCK_REALTIME, &ts) == 0);
+    ts.tv_sec += delta_sec;
+    assert(clock_settime(CLOCK_REALTIME, &ts) == 0);
+}
+
+static void test_relative(void)
+{
+    struct itimerspec value = {{0, 0}, {0, 50 * MSEC}};
+    struct itimerspec curr;
+    uint64_t ticks;
+    long start;
+    int fd;
+
+    assert((fd = timerfd_create(CLOCK_MONOTONIC, 0)) >= 0);
+
+    /* disarmed */
+    assert(timerfd_gettime(fd, &curr) == 0);
+    assert(curr.it_value.tv_sec == 0 && curr.it_value.tv_nsec == 0);
+
+    start = _now_msec(CLOCK_MONOTONIC);
+    assert(timerfd_settime(fd, 0, &value, NULL) == 0);
+    assert(timerfd_gettime(fd, &curr) == 0);
+    assert(curr.it_value.tv_sec == 0 && curr.it_value.tv_nsec > 0);
+
+    /* the read blocks until the expiry */
+    assert(read(fd, &ticks, sizeof(ticks)) == sizeof(ticks));
+    assert(ticks == 1);
+    assert(_now_msec(CLOCK_MONOTONIC) - start >= 50);
+
+    /* a one-shot timer is disarmed after its expiry */
+    assert(timerfd_gettime(fd, &curr) == 0);
+    assert(curr.it_value.tv_sec == 0 && curr.it_value.tv_nsec == 0);
+
+    assert(read(fd, &ticks, 4) == -1 && errno == EINVAL);
+
+    close(fd);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_periodic(void)
+{
+    struct itimerspec value = {{0, 10 * MSEC}, {0, 10 * MSEC}};
+    struct itimerspec old;
+    struct pollfd pfd;
+    uint64_t ticks;
+    int fd;
+
+    assert((fd = timerfd_create(CLOCK_BOOTTIME, TFD_NONBLOCK)) >= 0);
+    assert(read(fd, &ticks, sizeof(ticks)) == -1 && errno == EAGAIN);
+
+    assert(timerfd_settime(fd, 0, &value, NULL) == 0);
+
+    pfd.fd = fd;
+    pfd.events = POLLIN;
+    assert(poll(&pfd, 1, 1000) == 1 && (pfd.revents & POLLIN));
+
+    /* the expirations that were missed are counted */
+    usleep(50000);
+    assert(read(fd, &ticks, sizeof(ticks)) == sizeof(ticks));
+    assert(ticks >= 5);
+    assert(read(fd, &ticks, sizeof(ticks)) == -1 && errno == EAGAIN);
+
+    memset(&value, 0, sizeof(value));
+    assert(timerfd_settime(fd, 0, &value, &old) == 0);
+    assert(old.it_interval.tv_nsec == 10 * MSEC);
+
+    pfd.revents = 0;
+    assert(poll(&pfd, 1, 30) == 0);
+
+    close(fd);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_absolute(void)
+{
+    struct itimerspec value;
+    uint64_t ticks;
+    long start;
+    int fd;
+
+    /* an absolute timer moves with the clock */
+    assert((fd = timerfd_create(CLOCK_REALTIME, TFD_NONBLOCK)) >= 0);
+    memset(&value, 0, sizeof(value));
+    assert(clock_gettime(CLOCK_REALTIME, &value.it_value) == 0);
+    value.it_value.tv_sec += 10;
+    assert(timerfd_settime(fd, TFD_TIMER_ABSTIME, &value, NULL) == 0);
+
+    start = _now_msec(CLOCK_MONOTONIC);
+    _set_realtime(20);
+    assert(read(fd, &ticks, sizeof(ticks)) == sizeof(ticks));
+    assert(ticks == 1);
+    assert(_now_msec(CLOCK_MONOTONIC) - start < 1000);
+    _set_realtime(-20);
+
+    close(fd);
+
+    /* the alarm clocks are their plain clocks */
+    assert((fd = timerfd_create(CLOCK_BOOTTIME_ALARM, 0)) >= 0);
+    close(fd);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_cancel_on_set(void)
+{
+    const int flags = TFD_TIMER_ABSTIME | TFD_TIMER_CANCEL_ON_SET;
+    struct itimerspec value;
+    struct pollfd pfd;
+    uint64_t ticks;
+    int fd;
+
+    assert((fd = timerfd_create(CLOCK_REALTIME, TFD_CLOEXEC)) >= 0);
+    assert(fcntl(fd, F_GETFD) == FD_CLOEXEC);
+
+    memset(&value, 0, sizeof(value));
+    assert(clock_gettime(CLOCK_REALTIME, &value.it_value) == 0);
+    value.it_value.tv_sec += 3600;
+    assert(timerfd_settime(fd, flags, &value, NULL) == 0);
+
+    pfd.fd = fd;
+    pfd.events = POLLIN;
+    assert(poll(&pfd, 1, 0) == 0);
+
+    _set_realtime(1);
+    _set_realtime(-1);
+
+    assert(poll(&pfd, 1, 1000) == 1 && (pfd.revents & POLLIN));
+    assert(read(fd, &ticks, sizeof(ticks)) == -1 && errno == ECANCELED);
+
+    /* only once */
+    assert(fcntl(fd, F_SETFL, O_NONBLOCK) == 0);
+    assert(read(fd, &ticks, sizeof(ticks)) == -1 && errno == EAGAIN);
+
+    close(fd);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_errors(void)
+{
+    struct itimerspec value = {{0, 0}, {0, 1000000000}};
+    int fd;
+
+    assert(timerfd_create(CLOCK_PROCESS_CPUTIME_ID, 0) == -1);
+    assert(errno == EINVAL);
+    assert(timerfd_create(CLOCK_MONOTONIC, 0x4) == -1 && errno == EINVAL);
+
+    assert((fd = timerfd_create(CLOCK_MONOTONIC, 0)) >= 0);
+    assert(timerfd_settime(fd, 0, &value, NULL) == -1 && errno == EINVAL);
+    value.it_value.tv_nsec = 0;
+    assert(timerfd_settime(fd, 0x4, &value, NULL) == -1 && errno == EINVAL);
+    assert(write(fd, "12345678", 8) == -1 && errno == EINVAL);
+    close(fd);
+
+    /* not a timer file descriptor */
+    assert(timerfd_gettime(STDOUT_FILENO, &value) == -1 && errno == EINVAL);
+    assert(timerfd_gettime(fd, &value) == -1 && errno == EBADF);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    (void)argc;
+
+    test_relative();
+    test_periodic();
+    test_absolute();
+    test_cancel_on_set();
+    test_errors();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,327 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <signal.h>
+#include <stdbool.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <sys/time.h>
+#include <time.h>
+#include <unistd.h>
+
+#define NUM_IDLE_TIMERS 20000
+#define NUM_SIGNAL_TIMERS 5
+
+/* the sigevent that the timer_create syscall takes */
+struct ksigevent
+{
+    union sigval sigev_value;
+    int sigev_signo;
+    int sigev_notify;
+    int sigev_tid;
+};
+
+static long _now_msec(void)
+{
+    struct timespec ts;
+
+    assert(clock_gettime(CLOCK_MONOTONIC, &ts) == 0);
+    return ts.tv_sec * 1000 + ts.tv_nsec / 1000000;
+}
+
+static void _sleep_msec(long msec)
+{
+    struct timespec ts = {msec / 1000, (msec % 1000) * 1000000};
+
+    while (nanosleep(&ts, &ts) != 0)
+        assert(errno == EINTR);
+}
+
+static long _timer_create(clockid_t clockid, struct ksigevent* sev, int* id)
+{
+    return syscall(SYS_timer_create, clockid, sev, id);
+}
+
+static long _timer_settime(
+    int id,
+    int flags,
+    const struct itimerspec* value,
+    struct itimerspec* old)
+{
+    return syscall(SYS_timer_settime, id, flags, value, old);
+}
+
+static long _timer_gettime(int id, struct itimerspec* value)
+{
+    return syscall(SYS_timer_gettime, id, value);
+}
+
+static long _timer_delete(int id)
+{
+    return syscall(SYS_timer_delete, id);
+}
+
+static volatile int _alarms;
+static volatile int _signals;
+static volatile int _values[NUM_SIGNAL_TIMERS];
+static volatile int _overruns;
+
+static void _alarm_handler(int sig)
+{
+    assert(sig == SIGALRM);
+    _alarms++;
+}
+
+static void _timer_handler(int sig, siginfo_t* si, void* context)
+{
+    (void)context;
+
+    assert(sig == SIGUSR1);
+    assert(si->si_code == SI_TIMER);
+
+    if (si->si_value.sival_int >= 0 &&
+        si->si_value.sival_int < NUM_SIGNAL_TIMERS)
+    {
+        _values[si->si_value.sival_int]++;
+    }
+
+    _overruns += si->si_overrun;
+    _signals++;
+}
+
+static void test_itimer(void)
+{
+    struct itimerval value = {{0, 20 * 1000}, {0, 20 * 1000}};
+    struct itimerval curr;
+    long start = _now_msec();
+
+    assert(signal(SIGALRM, _alarm_handler) != SIG_ERR);
+    assert(setitimer(ITIMER_REAL, &value, NULL) == 0);
+
+    while (_alarms < 5)
+        assert(_now_msec() - start < 5000);
+
+    /* 5 periods of 20 milliseconds */
+    assert(_now_msec() - start >= 100 - 10);
+
+    assert(getitimer(ITIMER_REAL, &curr) == 0);
+    assert(curr.it_interval.tv_usec == 20 * 1000);
+    assert(curr.it_value.tv_sec == 0 && curr.it_value.tv_usec <= 20 * 1000);
+
+    memset(&value, 0, sizeof(value));
+    assert(setitimer(ITIMER_REAL, &value, &curr) == 0);
+    assert(curr.it_interval.tv_usec == 20 * 1000);
+
+    assert(getitimer(ITIMER_REAL, &curr) == 0);
+    assert(curr.it_value.tv_sec == 0 && curr.it_value.tv_usec == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+/* many timers without a signal, as an event loop would keep */
+static void test_idle_timers(void)
+{
+    static int ids[NUM_IDLE_TIMERS];
+    struct ksigevent sev = {.sigev_notify = SIGEV_NONE};
+    struct itimerspec value;
+    struct itimerspec curr;
+
+    for (int i = 0; i < NUM_IDLE_TIMERS; i++)
+    {
+        assert(_timer_create(CLOCK_MONOTONIC, &sev, &ids[i]) == 0);
+
+        /* every tenth timer expires within 100 milliseconds */
+        memset(&value, 0, sizeof(value));
+
+        if (i % 10 == 0)
+            value.it_value.tv_nsec = (1 + i % 100) * 1000000;
+        else
+            value.it_value.tv_sec = 60 + i;
+
+        assert(_timer_settime(ids[i], 0, &value, NULL) == 0);
+    }
+
+    /* the ids are distinct */
+    for (int i = 1; i < NUM_IDLE_TIMERS; i++)
+        assert(ids[i] != ids[i - 1]);
+
+    _sleep_msec(200);
+
+    for (int i = 0; i < NUM_IDLE_TIMERS; i++)
+    {
+        assert(_timer_gettime(ids[i], &curr) == 0);
+
+        if (i % 10 == 0)
+        {
+            assert(curr.it_value.tv_sec == 0 && curr.it_value.tv_nsec == 0);
+        }
+        else
+        {
+            assert(curr.it_value.tv_sec < 60 + i);
+            assert(curr.it_value.tv_sec >= 60 + i - 2);
+        }
+    }
+
+    for (int i = 0; i < NUM_IDLE_TIMERS; i++)
+        assert(_timer_delete(ids[i]) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_signal_timers(void)
+{
+    int ids[NUM_SIGNAL_TIMERS];
+    struct sigaction sa = {.sa_sigaction = _timer_handler};
+    long start = _now_msec();
+
+    sa.sa_flags = SA_SIGINFO;
+    assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+
+    /* the timers expire 20 milliseconds apart (on both clocks) */
+    for (int i = 0; i < NUM_SIGNAL_TIMERS; i++)
+    {
+        struct ksigevent sev = {
+            .sigev_value.sival_int = i,
+            .sigev_signo = SIGUSR1,
+            .sigev_notify = SIGEV_SIGNAL,
+        };
+        struct itimerspec value = {{0, 0}, {0, (i + 1) * 20 * 1000000}};
+        clockid_t clockid = i % 2 ? CLOCK_REALTIME : CLOCK_MONOTONIC;
+
+        assert(_timer_create(clockid, &sev, &ids[i]) == 0);
+        assert(_timer_settime(ids[i], 0, &value, NULL) == 0);
+    }
+
+    while (_signals < NUM_SIGNAL_TIMERS)
+        assert(_now_msec() - start < 5000);
+
+    assert(_now_msec() - start >= NUM_SIGNAL_TIMERS * 20 - 10);
+
+    for (int i = 0; i < NUM_SIGNAL_TIMERS; i++)
+    {
+        assert(_values[i] == 1);
+        assert(_timer_delete(ids[i]) == 0);
+    }
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_thread_and_periodic_timer(void)
+{
+    struct ksigevent sev = {
+        .sigev_value.sival_int = 0,
+        .sigev_signo = SIGUSR1,
+        .sigev_notify = SIGEV_THREAD_ID,
+        .sigev_tid = (int)syscall(SYS_gettid),
+    };
+    struct itimerspec value = {{0, 10 * 1000000}, {0, 10 * 1000000}};
+    struct itimerspec curr;
+    int id;
+    int expirations;
+
+    _signals = 0;
+    _overruns = 0;
+
+    assert(_timer_create(CLOCK_MONOTONIC, &sev, &id) == 0);
+    assert(_timer_settime(id, 0, &value, NULL) == 0);
+
+    _sleep_msec(200);
+
+    /* 20 periods went by: each one was signalled or was an overrun */
+    expirations = _signals + _overruns;
+    assert(expirations >= 15 && expirations <= 25);
+    assert(syscall(SYS_timer_getoverrun, id) >= 0);
+
+    assert(_timer_gettime(id, &curr) == 0);
+    assert(curr.it_interval.tv_nsec == 10 * 1000000);
+    assert(curr.it_value.tv_sec == 0 && curr.it_value.tv_nsec > 0);
+
+    assert(_timer_delete(id) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_absolute_timer(void)
+{
+    struct ksigevent sev = {.sigev_notify = SIGEV_NONE};
+    struct itimerspec value = {{0, 0}, {0, 0}};
+    struct itimerspec curr;
+    int id;
+
+    assert(_timer_create(CLOCK_REALTIME, &sev, &id) == 0);
+
+    /* 500 milliseconds from now on the realtime clock */
+    assert(clock_gettime(CLOCK_REALTIME, &value.it_value) == 0);
+    value.it_value.tv_nsec += 500 * 1000000;
+
+    if (value.it_value.tv_nsec >= 1000000000)
+    {
+        value.it_value.tv_sec++;
+        value.it_value.tv_nsec -= 1000000000;
+    }
+
+    assert(_timer_settime(id, TIMER_ABSTIME, &value, NULL) == 0);
+    assert(_timer_gettime(id, &curr) == 0);
+    assert(curr.it_value.tv_sec == 0);
+    assert(curr.it_value.tv_nsec > 400 * 1000000);
+
+    /* a time in the past expires at once */
+    value.it_value.tv_sec -= 10;
+    assert(_timer_settime(id, TIMER_ABSTIME, &value, &curr) == 0);
+    assert(curr.it_value.tv_nsec > 0);
+
+    _sleep_msec(20);
+    assert(_timer_gettime(id, &curr) == 0);
+    assert(curr.it_value.tv_sec == 0 && curr.it_value.tv_nsec == 0);
+
+    assert(_timer_delete(id) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_errors(void)
+{
+    struct ksigevent sev = {.sigev_notify = SIGEV_NONE};
+    struct itimerspec value = {{0, 0}, {0, 1000000000}};
+    int id;
+
+    assert(_timer_create(12345, &sev, &id) == -1 && errno == EINVAL);
+
+    sev.sigev_notify = SIGEV_SIGNAL;
+    sev.sigev_signo = -1;
+    assert(_timer_create(CLOCK_MONOTONIC, &sev, &id) == -1);
+    assert(errno == EINVAL);
+
+    sev.sigev_notify = SIGEV_NONE;
+    assert(_timer_create(CLOCK_MONOTONIC, &sev, &id) == 0);
+
+    assert(_timer_settime(id, 0, &value, NULL) == -1 && errno == EINVAL);
+    value.it_value.tv_nsec = 0;
+    value.it_interval.tv_sec = -1;
+    assert(_timer_settime(id, 0, &value, NULL) == -1 && errno == EINVAL);
+
+    assert(_timer_delete(id) == 0);
+    assert(_timer_delete(id) == -1 && errno == EINVAL);
+    assert(_timer_gettime(id, &value) == -1 && errno == EINVAL);
+    assert(syscall(SYS_timer_getoverrun, id) == -1 && errno == EINVAL);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    test_itimer();
+    test_idle_timers();
+    test_signal_timers();
+    test_thread_and_periodic_timer();
+    test_absolute_timer();
+    test_errors();
+
+    printf("=== passed all tests (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,133 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <dlfcn.h>
+#include <pthread.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+#define NUM_THREADS 8
+
+/* .tdata: copied from the PT_TLS image into every new thread */
+static __thread uint64_t _initialized = 0x0123456789abcdef;
+static __thread char _string[] = "thread-local string";
+
+/* .tbss: zero-filled after the image copy */
+static __thread uint64_t _zeroed[64];
+
+/* raise the alignment of the PT_TLS segment above the default */
+static __thread uint64_t _aligned __attribute__((aligned(64))) = 42;
+
+static int* (*_lib_initialized_addr)(void);
+static char* (*_lib_zeroed_addr)(void);
+
+static void* _get_fsbase(void)
+{
+    void* p;
+    asm volatile("mov %%fs:0, %0" : "=r"(p));
+    return p;
+}
+
+static uint64_t _get_canary(void)
+{
+    uint64_t canary;
+    asm volatile("mov %%fs:0x28, %0" : "=r"(canary));
+    return canary;
+}
+
+static uint64_t _main_canary;
+
+/* check that this thread has a fresh copy of the TLS image, then modify it
+ * so that other threads can check that they do not share it */
+static void _check_tls(uint64_t id)
+{
+    /* the thread pointer points to itself (musl and glibc layouts) */
+    assert(_get_fsbase() == (void*)pthread_self());
+
+    /* the stack canary is inherited by every thread */
+    assert(_get_canary() == _main_canary);
+
+    assert(_initialized == 0x0123456789abcdef);
+    assert(strcmp(_string, "thread-local string") == 0);
+    assert(_aligned == 42);
+    assert(((uintptr_t)&_aligned % 64) == 0);
+
+    for (size_t i = 0; i < sizeof(_zeroed) / sizeof(_zeroed[0]); i++)
+        assert(_zeroed[i] == 0);
+
+    _initialized = id;
+    _string[0] = 'T';
+    _aligned = id;
+    memset(_zeroed, 0xff, sizeof(_zeroed));
+
+    /* dynamic TLS of the dlopen()'d library */
+    {
+        int* initialized = _lib_initialized_addr();
+        char* zeroed = _lib_zeroed_addr();
+
+        assert(*initialized == 0x5a5a5a5a);
+
+        for (size_t i = 0; i < 4096; i++)
+            assert(zeroed[i] == 0);
+
+        *initialized = (int)id;
+        memset(zeroed, 0xff, 4096);
+        assert(_lib_initialized_addr() == initialized);
+    }
+
+    assert(_initialized == id);
+    assert(_aligned == id);
+}
+
+static void* _thread(void* arg)
+{
+    _check_tls((uint64_t)(uintptr_t)arg);
+
+    /* return the address of the thread's block to check it is distinct */
+    return &_initialized;
+}
+
+int main(int argc, const char* argv[])
+{
+    void* handle;
+    pthread_t threads[NUM_THREADS];
+    void* addrs[NUM_THREADS];
+
+    (void)argc;
+
+    assert((handle = dlopen("/lib/libtlslib.so", RTLD_NOW)));
+    assert((_lib_initialized_addr = dlsym(handle, "lib_initialized_addr")));
+    assert((_lib_zeroed_addr = dlsym(handle, "lib_zeroed_addr")));
+
+    _main_canary = _get_canary();
+    _check_tls(1000);
+
+    for (size_t i = 0; i < NUM_THREADS; i++)
+    {
+        void* arg = (void*)(uintptr_t)(i + 1);
+        assert(pthread_create(&threads[i], NULL, _thread, arg) == 0);
+    }
+
+    for (size_t i = 0; i < NUM_THREADS; i++)
+        assert(pthread_join(threads[i], &addrs[i]) == 0);
+
+    for (size_t i = 0; i < NUM_THREADS; i++)
+    {
+        assert(addrs[i] != &_initialized);
+
+        for (size_t j = i + 1; j < NUM_THREADS; j++)
+            assert(addrs[i] != addrs[j]);
+    }
+
+    /* the threads did not modify the main thread's copy */
+    assert(_initialized == 1000);
+    assert(*_lib_initialized_addr() == 1000);
+
+    dlclose(handle);
+
+    printf("=== passed test (%s)\n", argv[0]);
+
+    return 0;
+}
@@ -0,0 +1,18 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+/* TLS in a library loaded with dlopen() lives in a dynamically allocated
+ * block that is reached through the DTV rather than the static TLS area */
+
+__thread int lib_initialized = 0x5a5a5a5a;
+__thread char lib_zeroed[4096];
+
+int* lib_initialized_addr(void)
+{
+    return &lib_initialized;
+}
+
+char* lib_zeroed_addr(void)
+{
+    return lib_zeroed;
+}
@@ -0,0 +1,373 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <assert.h>
+#include <errno.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <time.h>
+#include <unistd.h>
+
+#include <myst/cond.h>
+#include <myst/deadline.h>
+#include <myst/mutex.h>
+#include <myst/thread.h>
+#include <myst/time.h>
+#include <myst/work.h>
+
+/*
+**==============================================================================
+**
+** what kernel/work.c takes from the rest of the kernel
+**
+**==============================================================================
+*/
+
+/* the clock that the tests move by hand */
+static volatile long _now = 1000;
+
+static __thread myst_thread_t _self;
+
+/* work.c has a single mutex and condition (for the worker thread) */
+static pthread_mutex_t _mutex = PTHREAD_MUTEX_INITIALIZER;
+static pthread_cond_t _cond = PTHREAD_COND_INITIALIZER;
+
+long myst_deadline_now(void)
+{
+    return __atomic_load_n(&_now, __ATOMIC_ACQUIRE);
+}
+
+myst_thread_t* myst_thread_self(void)
+{
+    return (myst_thread_t*)&_self;
+}
+
+void myst_sleep_msec(uint64_t milliseconds)
+{
+    usleep(milliseconds * 1000);
+}
+
+int myst_mutex_lock(myst_mutex_t* mutex)
+{
+    (void)mutex;
+    return pthread_mutex_lock(&_mutex);
+}
+
+int myst_mutex_unlock(myst_mutex_t* mutex)
+{
+    (void)mutex;
+    return pthread_mutex_unlock(&_mutex);
+}
+
+int myst_cond_signal(myst_cond_t* c)
+{
+    (void)c;
+    return pthread_cond_signal(&_cond);
+}
+
+/* the clock is moved by hand, so wake up now and then to look at it */
+int myst_cond_wait_deadline(
+    myst_cond_t* c,
+    myst_mutex_t* mutex,
+    const myst_deadline_t* deadline)
+{
+    struct timespec ts;
+
+    (void)c;
+    (void)mutex;
+    (void)deadline;
+
+    clock_gettime(CLOCK_REALTIME, &ts);
+    ts.tv_nsec += 10000000;
+
+    if (ts.tv_nsec >= 1000000000)
+    {
+        ts.tv_sec++;
+        ts.tv_nsec -= 1000000000;
+    }
+
+    return pthread_cond_timedwait(&_cond, &_mutex, &ts);
+}
+
+/*
+**==============================================================================
+**
+** tests
+**
+**==============================================================================
+*/
+
+/* as in kernel/work.c */
+#define MAX_BATCH 4
+
+static int _order[16];
+static size_t _count;
+
+static long _record(myst_work_t* work, long now)
+{
+    (void)now;
+    _order[_count++] = (int)(long)work->arg;
+    return 0;
+}
+
+static void test_order(void)
+{
+    myst_work_t w[3];
+
+    _count = 0;
+    myst_work_init(&w[0], "third", _record, (void*)3);
+    myst_work_init(&w[1], "first", _record, (void*)1);
+    myst_work_init(&w[2], "second", _record, (void*)2);
+
+    myst_work_queue(&w[0], _now + 30);
+    myst_work_queue(&w[1], _now + 10);
+    myst_work_queue(&w[2], _now + 20);
+
+    /* nothing is due yet */
+    myst_work_run();
+    assert(_count == 0);
+
+    /* only what is due runs, earliest first */
+    _now += 20;
+    myst_work_run();
+    assert(_count == 2);
+    assert(_order[0] == 1 && _order[1] == 2);
+    assert(!w[1].queued && !w[2].queued && w[0].queued);
+
+    _now += 10;
+    myst_work_run();
+    assert(_count == 3 && _order[2] == 3);
+
+    /* requeueing moves the work rather than adding it twice */
+    _count = 0;
+    myst_work_queue(&w[0], _now + 50);
+    myst_work_queue(&w[0], _now + 10);
+    _now += 100;
+    myst_work_run();
+    assert(_count == 1 && _order[0] == 3);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static long _periodic(myst_work_t* work, long now)
+{
+    _record(work, now);
+    return now + 10;
+}
+
+static void test_requeue(void)
+{
+    myst_work_t w;
+
+    _count = 0;
+    myst_work_init(&w, "periodic", _periodic, (void*)7);
+    myst_work_queue(&w, 0);
+
+    /* the callback queues the work again at the time it returns */
+    myst_work_run();
+    assert(_count == 1 && w.queued && w.when == _now + 10);
+
+    myst_work_run();
+    assert(_count == 1);
+
+    _now += 10;
+    myst_work_run();
+    assert(_count == 2);
+
+    /* cancelling queued work keeps it from running */
+    assert(myst_work_cancel(&w));
+    assert(!w.queued);
+    _now += 10;
+    myst_work_run();
+    assert(_count == 2);
+    assert(!myst_work_cancel(&w));
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static void test_batch(void)
+{
+    myst_work_t w[MAX_BATCH + 2];
+
+    _count = 0;
+
+    for (size_t i = 0; i < MAX_BATCH + 2; i++)
+    {
+        myst_work_init(&w[i], "batch", _record, (void*)i);
+        myst_work_queue(&w[i], _now);
+    }
+
+    /* one kernel entry runs at most MAX_BATCH items */
+    myst_work_run();
+    assert(_count == MAX_BATCH);
+
+    myst_work_run();
+    assert(_count == MAX_BATCH + 2);
+
+    for (size_t i = 0; i < MAX_BATCH + 2; i++)
+        assert(_order[i] == (int)i);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static volatile int _in_callback;
+static volatile int _release;
+static volatile int _callback_done;
+static volatile int _cancel_done;
+static volatile int _nested_run;
+
+static long _slow(myst_work_t* work, long now)
+{
+    (void)work;
+
+    __atomic_store_n(&_in_callback, 1, __ATOMIC_RELEASE);
+
+    while (!__atomic_load_n(&_release, __ATOMIC_ACQUIRE))
+        usleep(1000);
+
+    __atomic_store_n(&_callback_done, 1, __ATOMIC_RELEASE);
+
+    /* ask to run again, which the cancel overrides */
+    return now + 10;
+}
+
+static void* _runner(void* arg)
+{
+    (void)arg;
+    myst_work_run();
+    return NULL;
+}
+
+static void* _canceller(void* arg)
+{
+    myst_work_t* w = arg;
+
+    /* the work was taken from the queue to run */
+    assert(!myst_work_cancel(w));
+    __atomic_store_n(&_cancel_done, 1, __ATOMIC_RELEASE);
+
+    return NULL;
+}
+
+static long _other(myst_work_t* work, long now)
+{
+    (void)work;
+    (void)now;
+    _nested_run = 1;
+    return 0;
+}
+
+static void test_cancel_while_running(void)
+{
+    myst_work_t w;
+    myst_work_t other;
+    pthread_t runner;
+    pthread_t canceller;
+
+    myst_work_init(&w, "slow", _slow, NULL);
+    myst_work_init(&other, "other", _other, NULL);
+    myst_work_queue(&w, _now);
+
+    assert(pthread_create(&runner, NULL, _runner, NULL) == 0);
+
+    while (!__atomic_load_n(&_in_callback, __ATOMIC_ACQUIRE))
+        usleep(1000);
+
+    /* one thread runs items at a time: the others go on */
+    myst_work_queue(&other, _now);
+    myst_work_run();
+    assert(!_nested_run);
+    assert(myst_work_cancel(&other));
+
+    /* the cancel waits for the callback to return */
+    assert(pthread_create(&canceller, NULL, _canceller, &w) == 0);
+    usleep(50000);
+    assert(!__atomic_load_n(&_cancel_done, __ATOMIC_ACQUIRE));
+
+    __atomic_store_n(&_release, 1, __ATOMIC_RELEASE);
+    assert(pthread_join(canceller, NULL) == 0);
+    assert(_callback_done);
+    assert(pthread_join(runner, NULL) == 0);
+
+    /* and the work is not queued again */
+    assert(!w.queued && !w.running);
+    assert(!_nested_run);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+static volatile myst_thread_t* _ran_on;
+
+static long _slow_work(myst_work_t* work, long now)
+{
+    (void)work;
+    (void)now;
+    _ran_on = myst_thread_self();
+    return 0;
+}
+
+static void* _worker(void* arg)
+{
+    (void)arg;
+    myst_work_run_worker();
+
+    /* unreachable */
+    assert(0);
+    return NULL;
+}
+
+static void test_worker(void)
+{
+    myst_work_t w;
+    pthread_t worker;
+
+    /* no thread is needed yet */
+    assert(myst_work_run_worker() == -ENOENT);
+
+    myst_work_init_worker(&w, "worker", _slow_work, NULL);
+    myst_work_queue(&w, _now);
+
+    /* the threads that enter the kernel leave it to the worker */
+    myst_work_run();
+    assert(!_ran_on && w.queued);
+
+    assert(pthread_create(&worker, NULL, _worker, NULL) == 0);
+
+    while (!_ran_on)
+        usleep(1000);
+
+    assert(_ran_on != myst_thread_self());
+    assert(!w.queued);
+
+    /* and there is only one worker */
+    assert(myst_work_run_worker() == -EEXIST);
+
+    /* work queued for later waits until it is due */
+    _ran_on = NULL;
+    myst_work_queue(&w, _now + 10);
+    usleep(50000);
+    assert(!_ran_on && w.queued);
+
+    _now += 10;
+
+    while (!_ran_on)
+        usleep(1000);
+
+    assert(pthread_detach(worker) == 0);
+
+    printf("=== passed test (%s)\n", __FUNCTION__);
+}
+
+int main(int argc, const char* argv[])
+{
+    (void)argc;
+
+    test_order();
+    test_requeue();
+    test_batch();
+    test_cancel_while_running();
+    test_worker();
+
+    printf("=== passed test (%s)\n", argv[0]);
+    return 0;
+}
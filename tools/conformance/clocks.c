@@ -0,0 +1,195 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <sys/syscall.h>
+#include <sys/time.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "conformance.h"
+
+#define NANO_IN_SECOND 1000000000L
+
+static long _nsec(clockid_t clockid)
+{
+    struct timespec ts;
+
+    if (clock_gettime(clockid, &ts) != 0)
+        return -1;
+
+    return ts.tv_sec * NANO_IN_SECOND + ts.tv_nsec;
+}
+
+static conformance_result_t _monotonic(conformance_context_t* context)
+{
+    long prev = _nsec(CLOCK_MONOTONIC);
+
+    REQUIRE(prev > 0);
+
+    for (int i = 0; i < 100000; i++)
+    {
+        long now = _nsec(CLOCK_MONOTONIC);
+
+        REQUIRE(now >= prev);
+        prev = now;
+    }
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _boottime(conformance_context_t* context)
+{
+    long monotonic = _nsec(CLOCK_MONOTONIC);
+    long boottime = _nsec(CLOCK_BOOTTIME);
+
+    REQUIRE(monotonic > 0);
+    REQUIRE(boottime >= monotonic);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _realtime(conformance_context_t* context)
+{
+    struct timeval tv;
+    long realtime = _nsec(CLOCK_REALTIME);
+    long diff;
+
+    REQUIRE(gettimeofday(&tv, NULL) == 0);
+    diff = tv.tv_sec * NANO_IN_SECOND + tv.tv_usec * 1000 - realtime;
+
+    /* after 2020 */
+    REQUIRE(realtime > 1577836800 * NANO_IN_SECOND);
+    REQUIRE(diff > -NANO_IN_SECOND && diff < NANO_IN_SECOND);
+    REQUIRE(time(NULL) == tv.tv_sec || time(NULL) == tv.tv_sec + 1);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _resolution(conformance_context_t* context)
+{
+    const clockid_t clocks[] = {CLOCK_REALTIME, CLOCK_MONOTONIC};
+
+    for (size_t i = 0; i < CONFORMANCE_COUNTOF(clocks); i++)
+    {
+        struct timespec res;
+
+        REQUIRE(clock_getres(clocks[i], &res) == 0);
+        REQUIRE(res.tv_sec == 0 && res.tv_nsec > 0);
+        REQUIRE(res.tv_nsec <= 10000000);
+    }
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _invalid_clock(conformance_context_t* context)
+{
+    struct timespec ts;
+
+    errno = 0;
+    REQUIRE(clock_gettime((clockid_t)12345, &ts) == -1 && errno == EINVAL);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _nanosleep(conformance_context_t* context)
+{
+    const struct timespec req = {0, 20000000};
+    long start = _nsec(CLOCK_MONOTONIC);
+    long elapsed;
+
+    REQUIRE(nanosleep(&req, NULL) == 0);
+    elapsed = _nsec(CLOCK_MONOTONIC) - start;
+
+    REQUIRE(elapsed >= req.tv_nsec);
+    REQUIRE(elapsed < NANO_IN_SECOND);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _nanosleep_invalid(conformance_context_t* context)
+{
+    const struct timespec req = {0, NANO_IN_SECOND};
+
+    errno = 0;
+    REQUIRE(nanosleep(&req, NULL) == -1 && errno == EINVAL);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _clock_nanosleep(conformance_context_t* context)
+{
+    const struct timespec req = {0, 10000000};
+    long start = _nsec(CLOCK_MONOTONIC);
+
+    /* not through libc, which may fall back to nanosleep() */
+    REQUIRE(syscall(SYS_clock_nanosleep, CLOCK_MONOTONIC, 0, &req, NULL) == 0);
+    REQUIRE(_nsec(CLOCK_MONOTONIC) - start >= req.tv_nsec);
+
+    return CONFORMANCE_PASS;
+}
+
+static const conformance_check_t _checks[] = {
+    {
+        "monotonic",
+        "CLOCK_MONOTONIC never goes backward",
+        NULL,
+        _monotonic,
+        false,
+    },
+    {
+        "boottime",
+        "CLOCK_BOOTTIME is not behind CLOCK_MONOTONIC",
+        NULL,
+        _boottime,
+        false,
+    },
+    {
+        "realtime",
+        "CLOCK_REALTIME, gettimeofday() and time() tell the same time",
+        NULL,
+        _realtime,
+        false,
+    },
+    {
+        "resolution",
+        "clock_getres() reports a resolution of 10ms at most",
+        NULL,
+        _resolution,
+        false,
+    },
+    {
+        "invalid-clock",
+        "clock_gettime() of an unknown clock fails with EINVAL",
+        NULL,
+        _invalid_clock,
+        false,
+    },
+    {
+        "nanosleep",
+        "nanosleep() sleeps for the given time at least",
+        NULL,
+        _nanosleep,
+        false,
+    },
+    {
+        "nanosleep-invalid",
+        "nanosleep() of 1000000000 nanoseconds fails with EINVAL",
+        NULL,
+        _nanosleep_invalid,
+        false,
+    },
+    {
+        "clock-nanosleep",
+        "clock_nanosleep() sleeps on the given clock",
+        "doc/syscall-limitations.md: SYS_clock_nanosleep is unsupported",
+        _clock_nanosleep,
+        true,
+    },
+};
+
+const conformance_suite_t conformance_clocks = {
+    "clocks",
+    _checks,
+    CONFORMANCE_COUNTOF(_checks),
+};
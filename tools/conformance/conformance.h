@@ -0,0 +1,99 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_CONFORMANCE_H
+#define _MYST_CONFORMANCE_H
+
+#include <errno.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <string.h>
+
+typedef enum conformance_result
+{
+    CONFORMANCE_PASS,
+    CONFORMANCE_FAIL,
+    CONFORMANCE_SKIP,
+} conformance_result_t;
+
+/* where a check writes why it failed or was skipped */
+typedef struct conformance_context
+{
+    char detail[256];
+} conformance_context_t;
+
+typedef struct conformance_check
+{
+    const char* name;
+
+    /* what Linux does (and what the check verifies) */
+    const char* expect;
+
+    /* NULL, or where the documentation says that Mystikos differs: a check
+     * that fails is then reported as a limitation rather than a failure */
+    const char* limitation;
+
+    conformance_result_t (*run)(conformance_context_t* context);
+
+    /* the documentation says that a system call of the check is unsupported,
+     * which stops Mystikos: the check only runs on Linux */
+    bool unsupported;
+} conformance_check_t;
+
+typedef struct conformance_suite
+{
+    const char* name;
+    const conformance_check_t* checks;
+    size_t num_checks;
+} conformance_suite_t;
+
+#define CONFORMANCE_COUNTOF(ARR) (sizeof(ARR) / sizeof(ARR[0]))
+
+/* fail the check (with the expression, the line and errno) unless COND holds;
+ * the function must have a conformance_context_t* named context */
+#define REQUIRE(COND)                                  \
+    do                                                 \
+    {                                                  \
+        if (!(COND))                                   \
+        {                                              \
+            snprintf(                                  \
+                context->detail,                       \
+                sizeof(context->detail),               \
+                "line %d: %s (errno %d: %s)",          \
+                __LINE__,                              \
+                #COND,                                 \
+                errno,                                 \
+                strerror(errno));                      \
+            return CONFORMANCE_FAIL;                   \
+        }                                              \
+    } while (0)
+
+/* skip the check with the given reason */
+#define SKIP(REASON)                                   \
+    do                                                 \
+    {                                                  \
+        snprintf(                                      \
+            context->detail,                           \
+            sizeof(context->detail),                   \
+            "%s",                                      \
+            REASON);                                   \
+        return CONFORMANCE_SKIP;                       \
+    } while (0)
+
+/* the current CLOCK_MONOTONIC time in milliseconds */
+long conformance_now_msec(void);
+
+/* whether the program runs on Mystikos rather than on Linux */
+bool conformance_on_mystikos(void);
+
+/* the directory (under /tmp) where the checks may create files */
+const char* conformance_tmpdir(void);
+
+extern const conformance_suite_t conformance_clocks;
+extern const conformance_suite_t conformance_futexes;
+extern const conformance_suite_t conformance_fs;
+extern const conformance_suite_t conformance_signals;
+extern const conformance_suite_t conformance_sockets;
+
+#endif /* _MYST_CONFORMANCE_H */
@@ -0,0 +1,370 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <dirent.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include "conformance.h"
+
+static const char* _path(char buf[PATH_MAX], const char* name)
+{
+    snprintf(buf, PATH_MAX, "%s/%s", conformance_tmpdir(), name);
+    return buf;
+}
+
+static int _write_file(const char* path, const char* data)
+{
+    int fd;
+    ssize_t n;
+
+    if ((fd = open(path, O_CREAT | O_TRUNC | O_WRONLY, 0644)) < 0)
+        return -1;
+
+    n = write(fd, data, strlen(data));
+    close(fd);
+
+    return n == (ssize_t)strlen(data) ? 0 : -1;
+}
+
+/* whether the file holds exactly the data */
+static bool _has(const char* path, const char* data)
+{
+    char buf[256];
+    ssize_t n;
+    int fd;
+
+    if ((fd = open(path, O_RDONLY)) < 0)
+        return false;
+
+    n = read(fd, buf, sizeof(buf));
+    close(fd);
+
+    return n == (ssize_t)strlen(data) && memcmp(buf, data, n) == 0;
+}
+
+static conformance_result_t _exclusive(conformance_context_t* context)
+{
+    char path[PATH_MAX];
+    const int flags = O_CREAT | O_EXCL | O_WRONLY;
+    int fd;
+
+    REQUIRE((fd = open(_path(path, "excl"), flags, 0644)) >= 0);
+    close(fd);
+
+    errno = 0;
+    REQUIRE(open(path, flags, 0644) == -1 && errno == EEXIST);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _read_write(conformance_context_t* context)
+{
+    char path[PATH_MAX];
+    char buf[8192];
+    char data[8192];
+    int fd;
+
+    for (size_t i = 0; i < sizeof(data); i++)
+        data[i] = (char)(i * 7);
+
+    REQUIRE((fd = open(_path(path, "rw"), O_CREAT | O_RDWR, 0644)) >= 0);
+    REQUIRE(write(fd, data, sizeof(data)) == sizeof(data));
+    REQUIRE(lseek(fd, 0, SEEK_CUR) == sizeof(data));
+    REQUIRE(lseek(fd, 0, SEEK_END) == sizeof(data));
+
+    /* not aligned to blocks */
+    REQUIRE(pread(fd, buf, 1000, 4000) == 1000);
+    REQUIRE(memcmp(buf, data + 4000, 1000) == 0);
+
+    /* at the end */
+    REQUIRE(read(fd, buf, sizeof(buf)) == 0);
+    REQUIRE(fsync(fd) == 0 && fdatasync(fd) == 0);
+    close(fd);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _append(conformance_context_t* context)
+{
+    char path[PATH_MAX];
+    int fd;
+
+    REQUIRE(_write_file(_path(path, "append"), "one") == 0);
+    REQUIRE((fd = open(path, O_WRONLY | O_APPEND)) >= 0);
+    REQUIRE(lseek(fd, 0, SEEK_SET) == 0);
+    REQUIRE(write(fd, "two", 3) == 3);
+    close(fd);
+
+    REQUIRE(_has(path, "onetwo"));
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _truncate(conformance_context_t* context)
+{
+    char path[PATH_MAX];
+    char buf[16];
+    struct stat st;
+    int fd;
+
+    REQUIRE(_write_file(_path(path, "trunc"), "abcdef") == 0);
+    REQUIRE((fd = open(path, O_RDWR)) >= 0);
+
+    REQUIRE(ftruncate(fd, 3) == 0);
+    REQUIRE(fstat(fd, &st) == 0 && st.st_size == 3);
+
+    /* the extension reads as zeros */
+    REQUIRE(ftruncate(fd, 8) == 0);
+    REQUIRE(pread(fd, buf, sizeof(buf), 0) == 8);
+    REQUIRE(memcmp(buf, "abc\0\0\0\0\0", 8) == 0);
+    close(fd);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _rename(conformance_context_t* context)
+{
+    char from[PATH_MAX];
+    char to[PATH_MAX];
+
+    REQUIRE(_write_file(_path(from, "from"), "new") == 0);
+    REQUIRE(_write_file(_path(to, "to"), "old") == 0);
+
+    REQUIRE(rename(from, to) == 0);
+    REQUIRE(_has(to, "new"));
+
+    errno = 0;
+    REQUIRE(access(from, F_OK) == -1 && errno == ENOENT);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _unlink_open(conformance_context_t* context)
+{
+    char path[PATH_MAX];
+    char buf[16];
+    int fd;
+
+    REQUIRE(_write_file(_path(path, "unlinked"), "still here") == 0);
+    REQUIRE((fd = open(path, O_RDONLY)) >= 0);
+    REQUIRE(unlink(path) == 0);
+
+    errno = 0;
+    REQUIRE(access(path, F_OK) == -1 && errno == ENOENT);
+    REQUIRE(read(fd, buf, sizeof(buf)) == 10);
+    REQUIRE(memcmp(buf, "still here", 10) == 0);
+    close(fd);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _hard_link(conformance_context_t* context)
+{
+    char path[PATH_MAX];
+    char link_path[PATH_MAX];
+    struct stat st1;
+    struct stat st2;
+
+    REQUIRE(_write_file(_path(path, "target"), "data") == 0);
+    REQUIRE(link(path, _path(link_path, "link")) == 0);
+
+    REQUIRE(stat(path, &st1) == 0 && stat(link_path, &st2) == 0);
+    REQUIRE(st1.st_ino == st2.st_ino && st1.st_dev == st2.st_dev);
+    REQUIRE(st1.st_nlink == 2);
+
+    REQUIRE(unlink(path) == 0);
+    REQUIRE(stat(link_path, &st2) == 0 && st2.st_nlink == 1);
+    REQUIRE(_has(link_path, "data"));
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _symlink(conformance_context_t* context)
+{
+    char path[PATH_MAX];
+    char link_path[PATH_MAX];
+    char buf[PATH_MAX];
+    struct stat st;
+    ssize_t n;
+
+    REQUIRE(_write_file(_path(path, "target"), "data") == 0);
+    REQUIRE(symlink("target", _path(link_path, "symlink")) == 0);
+
+    REQUIRE((n = readlink(link_path, buf, sizeof(buf))) == 6);
+    REQUIRE(memcmp(buf, "target", 6) == 0);
+    REQUIRE(lstat(link_path, &st) == 0 && S_ISLNK(st.st_mode));
+    REQUIRE(stat(link_path, &st) == 0 && S_ISREG(st.st_mode));
+    REQUIRE(_has(link_path, "data"));
+
+    /* a dangling link */
+    REQUIRE(unlink(path) == 0);
+    errno = 0;
+    REQUIRE(stat(link_path, &st) == -1 && errno == ENOENT);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _directory(conformance_context_t* context)
+{
+    char dir[PATH_MAX];
+    char path[PATH_MAX];
+    struct dirent* ent;
+    size_t count = 0;
+    DIR* d;
+
+    REQUIRE(mkdir(_path(dir, "dir"), 0755) == 0);
+    errno = 0;
+    REQUIRE(mkdir(dir, 0755) == -1 && errno == EEXIST);
+
+    REQUIRE(_write_file(_path(path, "dir/a"), "a") == 0);
+    REQUIRE(_write_file(_path(path, "dir/b"), "b") == 0);
+
+    errno = 0;
+    REQUIRE(rmdir(dir) == -1 && errno == ENOTEMPTY);
+
+    REQUIRE((d = opendir(dir)));
+
+    while ((ent = readdir(d)))
+    {
+        if (strcmp(ent->d_name, ".") != 0 && strcmp(ent->d_name, "..") != 0)
+            count++;
+    }
+
+    closedir(d);
+    REQUIRE(count == 2);
+
+    errno = 0;
+    REQUIRE(open(dir, O_WRONLY) == -1 && errno == EISDIR);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _dup_offset(conformance_context_t* context)
+{
+    char path[PATH_MAX];
+    char c;
+    int fd1;
+    int fd2;
+
+    REQUIRE(_write_file(_path(path, "dup"), "xy") == 0);
+    REQUIRE((fd1 = open(path, O_RDONLY)) >= 0);
+    REQUIRE((fd2 = dup(fd1)) >= 0);
+
+    /* the descriptors share the offset */
+    REQUIRE(read(fd1, &c, 1) == 1 && c == 'x');
+    REQUIRE(read(fd2, &c, 1) == 1 && c == 'y');
+    close(fd1);
+    close(fd2);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _openat(conformance_context_t* context)
+{
+    char dir[PATH_MAX];
+    char path[PATH_MAX];
+    int dirfd;
+    int fd;
+
+    REQUIRE(mkdir(_path(dir, "at"), 0755) == 0);
+    REQUIRE((dirfd = open(dir, O_RDONLY | O_DIRECTORY)) >= 0);
+
+    fd = openat(dirfd, "file", O_CREAT | O_WRONLY, 0644);
+    close(dirfd);
+    REQUIRE(fd >= 0);
+    close(fd);
+
+    REQUIRE(access(_path(path, "at/file"), F_OK) == 0);
+
+    return CONFORMANCE_PASS;
+}
+
+static const conformance_check_t _checks[] = {
+    {
+        "exclusive",
+        "open() with O_CREAT and O_EXCL fails with EEXIST on a file",
+        NULL,
+        _exclusive,
+        false,
+    },
+    {
+        "read-write",
+        "a file reads back what was written, at any offset",
+        NULL,
+        _read_write,
+        false,
+    },
+    {
+        "append",
+        "the writes of O_APPEND go to the end of the file",
+        NULL,
+        _append,
+        false,
+    },
+    {
+        "truncate",
+        "ftruncate() shrinks a file, or extends it with zeros",
+        NULL,
+        _truncate,
+        false,
+    },
+    {
+        "rename",
+        "rename() replaces the file at the new name",
+        NULL,
+        _rename,
+        false,
+    },
+    {
+        "unlink-open",
+        "an unlinked file remains readable through its open descriptors",
+        NULL,
+        _unlink_open,
+        false,
+    },
+    {
+        "hard-link",
+        "link() makes another name for the same inode",
+        NULL,
+        _hard_link,
+        false,
+    },
+    {
+        "symlink",
+        "symbolic links are read with readlink() and followed by stat()",
+        NULL,
+        _symlink,
+        false,
+    },
+    {
+        "directory",
+        "directories list their entries and are only removed when empty",
+        NULL,
+        _directory,
+        false,
+    },
+    {
+        "dup-offset",
+        "the descriptors made by dup() share the file offset",
+        NULL,
+        _dup_offset,
+        false,
+    },
+    {
+        "openat",
+        "openat() opens a path relative to a directory descriptor",
+        "doc/syscall-limitations.md: SYS_openat is unsupported",
+        _openat,
+        false,
+    },
+};
+
+const conformance_suite_t conformance_fs = {
+    "fs",
+    _checks,
+    CONFORMANCE_COUNTOF(_checks),
+};
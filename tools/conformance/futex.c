@@ -0,0 +1,261 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <limits.h>
+#include <linux/futex.h>
+#include <pthread.h>
+#include <sys/syscall.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "conformance.h"
+
+/* how long a waiter waits before it gives up */
+#define WAIT_MSEC 5000
+
+typedef struct waiter
+{
+    volatile int word;
+    volatile int ready;
+    volatile int done;
+} waiter_t;
+
+static long _futex(
+    volatile int* uaddr,
+    int op,
+    int val,
+    const void* arg,
+    volatile int* uaddr2,
+    int val3)
+{
+    return syscall(SYS_futex, uaddr, op, val, arg, uaddr2, val3);
+}
+
+static void* _wait_thread(void* arg)
+{
+    waiter_t* waiter = arg;
+    const struct timespec timeout = {WAIT_MSEC / 1000, 0};
+
+    __atomic_store_n(&waiter->ready, 1, __ATOMIC_SEQ_CST);
+
+    /* returns when woken or when the word changed */
+    while (__atomic_load_n(&waiter->word, __ATOMIC_SEQ_CST) == 0)
+    {
+        if (_futex(&waiter->word, FUTEX_WAIT_PRIVATE, 0, &timeout, NULL, 0) &&
+            errno == ETIMEDOUT)
+        {
+            break;
+        }
+    }
+
+    __atomic_store_n(&waiter->done, 1, __ATOMIC_SEQ_CST);
+    return NULL;
+}
+
+/* start a thread that waits on waiter->word and let it block */
+static int _start_waiter(waiter_t* waiter, pthread_t* thread)
+{
+    memset((void*)waiter, 0, sizeof(*waiter));
+
+    if (pthread_create(thread, NULL, _wait_thread, waiter) != 0)
+        return -1;
+
+    while (!__atomic_load_n(&waiter->ready, __ATOMIC_SEQ_CST))
+        usleep(1000);
+
+    /* there is no telling when the thread blocks in the kernel */
+    usleep(50000);
+    return 0;
+}
+
+static void _stop_waiter(
+    waiter_t* waiter,
+    volatile int* uaddr,
+    pthread_t thread)
+{
+    __atomic_store_n(&waiter->word, 1, __ATOMIC_SEQ_CST);
+    _futex(uaddr, FUTEX_WAKE_PRIVATE, INT_MAX, NULL, NULL, 0);
+    _futex(&waiter->word, FUTEX_WAKE_PRIVATE, INT_MAX, NULL, NULL, 0);
+    pthread_join(thread, NULL);
+}
+
+static conformance_result_t _wait_value(conformance_context_t* context)
+{
+    volatile int word = 1;
+
+    errno = 0;
+    REQUIRE(_futex(&word, FUTEX_WAIT_PRIVATE, 0, NULL, NULL, 0) == -1);
+    REQUIRE(errno == EAGAIN);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _wait_timeout(conformance_context_t* context)
+{
+    volatile int word = 0;
+    const struct timespec timeout = {0, 20000000};
+    long start = conformance_now_msec();
+
+    errno = 0;
+    REQUIRE(_futex(&word, FUTEX_WAIT_PRIVATE, 0, &timeout, NULL, 0) == -1);
+    REQUIRE(errno == ETIMEDOUT);
+    REQUIRE(conformance_now_msec() - start >= 20);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _wake_none(conformance_context_t* context)
+{
+    volatile int word = 0;
+
+    REQUIRE(_futex(&word, FUTEX_WAKE_PRIVATE, INT_MAX, NULL, NULL, 0) == 0);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _wake(conformance_context_t* context)
+{
+    waiter_t waiter;
+    pthread_t thread;
+    long start;
+    long woken;
+
+    REQUIRE(_start_waiter(&waiter, &thread) == 0);
+
+    start = conformance_now_msec();
+    __atomic_store_n(&waiter.word, 1, __ATOMIC_SEQ_CST);
+    woken = _futex(&waiter.word, FUTEX_WAKE_PRIVATE, 1, NULL, NULL, 0);
+    pthread_join(thread, NULL);
+
+    REQUIRE(woken == 1);
+    REQUIRE(waiter.done);
+    REQUIRE(conformance_now_msec() - start < WAIT_MSEC);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _requeue(conformance_context_t* context)
+{
+    volatile int target = 0;
+    const int op = FUTEX_REQUEUE_PRIVATE;
+    waiter_t waiter;
+    pthread_t thread;
+    long requeued;
+    long from;
+    long to;
+
+    REQUIRE(_start_waiter(&waiter, &thread) == 0);
+
+    /* wake none and move the waiter to the target */
+    requeued = _futex(&waiter.word, op, 0, (void*)1, &target, 0);
+    from = _futex(&waiter.word, FUTEX_WAKE_PRIVATE, 1, NULL, NULL, 0);
+    to = _futex(&target, FUTEX_WAKE_PRIVATE, 1, NULL, NULL, 0);
+    _stop_waiter(&waiter, &target, thread);
+
+    REQUIRE(requeued >= 0);
+    REQUIRE(from == 0);
+    REQUIRE(to == 1);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _cmp_requeue(conformance_context_t* context)
+{
+    volatile int word = 7;
+    volatile int target = 0;
+    const int op = FUTEX_CMP_REQUEUE_PRIVATE;
+
+    /* the value of the word is 7 */
+    REQUIRE(_futex(&word, op, 0, (void*)1, &target, 7) == 0);
+
+    errno = 0;
+    REQUIRE(_futex(&word, op, 0, (void*)1, &target, 8) == -1);
+    REQUIRE(errno == EAGAIN);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _wait_bitset(conformance_context_t* context)
+{
+    volatile int word = 0;
+    const int op = FUTEX_WAIT_BITSET_PRIVATE;
+    struct timespec deadline;
+    long start = conformance_now_msec();
+
+    /* the timeout of FUTEX_WAIT_BITSET is an absolute time */
+    REQUIRE(clock_gettime(CLOCK_MONOTONIC, &deadline) == 0);
+    deadline.tv_nsec += 20000000;
+
+    if (deadline.tv_nsec >= 1000000000)
+    {
+        deadline.tv_sec++;
+        deadline.tv_nsec -= 1000000000;
+    }
+
+    errno = 0;
+    REQUIRE(
+        _futex(&word, op, 0, &deadline, NULL, FUTEX_BITSET_MATCH_ANY) == -1);
+    REQUIRE(errno == ETIMEDOUT);
+    REQUIRE(conformance_now_msec() - start >= 20);
+
+    return CONFORMANCE_PASS;
+}
+
+static const conformance_check_t _checks[] = {
+    {
+        "wait-value",
+        "FUTEX_WAIT fails with EAGAIN when the word has another value",
+        NULL,
+        _wait_value,
+        false,
+    },
+    {
+        "wait-timeout",
+        "FUTEX_WAIT fails with ETIMEDOUT after its timeout",
+        NULL,
+        _wait_timeout,
+        false,
+    },
+    {
+        "wake-none",
+        "FUTEX_WAKE without waiters wakes none",
+        NULL,
+        _wake_none,
+        false,
+    },
+    {
+        "wake",
+        "FUTEX_WAKE wakes a thread blocked in FUTEX_WAIT",
+        NULL,
+        _wake,
+        false,
+    },
+    {
+        "requeue",
+        "FUTEX_REQUEUE moves a waiter to another futex",
+        NULL,
+        _requeue,
+        false,
+    },
+    {
+        "cmp-requeue",
+        "FUTEX_CMP_REQUEUE fails with EAGAIN when the word has another value",
+        "doc/syscall-limitations.md: SYS_futex is partial",
+        _cmp_requeue,
+        false,
+    },
+    {
+        "wait-bitset",
+        "FUTEX_WAIT_BITSET fails with ETIMEDOUT at its deadline",
+        "doc/syscall-limitations.md: SYS_futex is partial",
+        _wait_bitset,
+        false,
+    },
+};
+
+const conformance_suite_t conformance_futexes = {
+    "futexes",
+    _checks,
+    CONFORMANCE_COUNTOF(_checks),
+};
@@ -0,0 +1,324 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+/*
+**==============================================================================
+**
+** myst-conformance:
+**
+** Runs checks of the clocks, futexes, file systems, signals and sockets inside
+** the guest and writes a JSON report (see doc/conformance.md) to standard
+** output, or to the file of --output. The progress goes to standard error.
+** The exit status is 0 when no check failed, 1 when one did, and 2 on a bad
+** argument.
+**
+**==============================================================================
+*/
+
+#define _GNU_SOURCE
+#include <ftw.h>
+#include <stdlib.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <sys/utsname.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "conformance.h"
+
+/* the version of the report format */
+#define REPORT_FORMAT 1
+
+/* a system call of Mystikos (see include/myst/syscallext.h) */
+#define SYS_myst_max_threads 1014
+
+static const conformance_suite_t* _suites[] = {
+    &conformance_clocks,
+    &conformance_futexes,
+    &conformance_fs,
+    &conformance_signals,
+    &conformance_sockets,
+};
+
+static char _tmpdir[] = "/tmp/myst-conformance-XXXXXX";
+
+long conformance_now_msec(void)
+{
+    struct timespec ts;
+
+    if (clock_gettime(CLOCK_MONOTONIC, &ts) != 0)
+        return 0;
+
+    return ts.tv_sec * 1000 + ts.tv_nsec / 1000000;
+}
+
+bool conformance_on_mystikos(void)
+{
+    /* Linux fails with ENOSYS */
+    return syscall(SYS_myst_max_threads) > 0;
+}
+
+const char* conformance_tmpdir(void)
+{
+    return _tmpdir;
+}
+
+static void _put_string(FILE* os, const char* s)
+{
+    fputc('"', os);
+
+    for (; s && *s; s++)
+    {
+        unsigned char c = (unsigned char)*s;
+
+        if (c == '"' || c == '\\')
+            fprintf(os, "\\%c", c);
+        else if (c < 0x20)
+            fprintf(os, "\\u%04x", c);
+        else
+            fputc(c, os);
+    }
+
+    fputc('"', os);
+}
+
+static void _put_field(FILE* os, const char* name, const char* value, bool more)
+{
+    _put_string(os, name);
+    fputs(": ", os);
+
+    if (value)
+        _put_string(os, value);
+    else
+        fputs("null", os);
+
+    fputs(more ? ", " : "", os);
+}
+
+static bool _selected(const char* name, char** only, size_t num_only)
+{
+    if (num_only == 0)
+        return true;
+
+    for (size_t i = 0; i < num_only; i++)
+    {
+        if (strcmp(only[i], name) == 0)
+            return true;
+    }
+
+    return false;
+}
+
+static int _remove(
+    const char* path,
+    const struct stat* st,
+    int type,
+    struct FTW* ftw)
+{
+    (void)st;
+    (void)type;
+
+    /* keep the directory itself */
+    if (ftw->level > 0)
+        remove(path);
+
+    return 0;
+}
+
+static void _usage(const char* arg0)
+{
+    fprintf(
+        stderr,
+        "Usage: %s [--output FILE] [--suite NAME]... [--list]\n"
+        "\n"
+        "Checks the behavior of the clocks, futexes, file systems, signals\n"
+        "and sockets, and writes a JSON report (to standard output by\n"
+        "default). The exit status is 1 when a check failed.\n",
+        arg0);
+}
+
+int main(int argc, char* argv[])
+{
+    const char* output = NULL;
+    char* only[CONFORMANCE_COUNTOF(_suites)];
+    size_t num_only = 0;
+    size_t npass = 0;
+    size_t nfail = 0;
+    size_t nlimitation = 0;
+    size_t nskip = 0;
+    const char* sep = "";
+    bool mystikos = conformance_on_mystikos();
+    struct utsname uts;
+    FILE* os = stdout;
+
+    for (int i = 1; i < argc; i++)
+    {
+        if (strcmp(argv[i], "--output") == 0 && i + 1 < argc)
+        {
+            output = argv[++i];
+        }
+        else if (strcmp(argv[i], "--suite") == 0 && i + 1 < argc)
+        {
+            const char* name = argv[++i];
+            bool found = false;
+
+            for (size_t j = 0; j < CONFORMANCE_COUNTOF(_suites); j++)
+                found = found || strcmp(_suites[j]->name, name) == 0;
+
+            if (!found || num_only == CONFORMANCE_COUNTOF(only))
+            {
+                fprintf(stderr, "%s: unknown suite: %s\n", argv[0], name);
+                return 2;
+            }
+
+            only[num_only++] = argv[i];
+        }
+        else if (strcmp(argv[i], "--list") == 0)
+        {
+            for (size_t j = 0; j < CONFORMANCE_COUNTOF(_suites); j++)
+            {
+                const conformance_suite_t* suite = _suites[j];
+
+                for (size_t k = 0; k < suite->num_checks; k++)
+                    printf("%s.%s\n", suite->name, suite->checks[k].name);
+            }
+
+            return 0;
+        }
+        else
+        {
+            _usage(argv[0]);
+            return 2;
+        }
+    }
+
+    if (output && !(os = fopen(output, "w")))
+    {
+        fprintf(stderr, "%s: cannot open %s\n", argv[0], output);
+        return 2;
+    }
+
+    if (!mkdtemp(_tmpdir))
+    {
+        fprintf(stderr, "%s: cannot create %s\n", argv[0], _tmpdir);
+        return 2;
+    }
+
+    /* the build and the host that the report is about */
+    memset(&uts, 0, sizeof(uts));
+    uname(&uts);
+
+    fprintf(os, "{\n  \"format\": %d,\n", REPORT_FORMAT);
+    fprintf(os, "  \"mystikos\": %s,\n", mystikos ? "true" : "false");
+    fputs("  \"uname\": {", os);
+    _put_field(os, "sysname", uts.sysname, true);
+    _put_field(os, "release", uts.release, true);
+    _put_field(os, "version", uts.version, true);
+    _put_field(os, "machine", uts.machine, false);
+    fputs("},\n  \"checks\": [", os);
+
+    for (size_t i = 0; i < CONFORMANCE_COUNTOF(_suites); i++)
+    {
+        const conformance_suite_t* suite = _suites[i];
+
+        if (!_selected(suite->name, only, num_only))
+            continue;
+
+        for (size_t j = 0; j < suite->num_checks; j++)
+        {
+            const conformance_check_t* check = &suite->checks[j];
+            conformance_context_t context;
+            conformance_result_t r;
+            const char* result;
+            const char* detail;
+            long start;
+            long msec;
+
+            memset(&context, 0, sizeof(context));
+            errno = 0;
+            start = conformance_now_msec();
+
+            if (check->unsupported && mystikos)
+            {
+                snprintf(
+                    context.detail,
+                    sizeof(context.detail),
+                    "not run: Mystikos stops on an unsupported system call");
+                r = CONFORMANCE_FAIL;
+            }
+            else
+            {
+                r = check->run(&context);
+            }
+
+            msec = conformance_now_msec() - start;
+            detail = *context.detail ? context.detail : NULL;
+
+            /* remove the files that the check left */
+            nftw(_tmpdir, _remove, 16, FTW_DEPTH | FTW_PHYS);
+
+            if (r == CONFORMANCE_PASS)
+            {
+                result = "pass";
+                npass++;
+            }
+            else if (r == CONFORMANCE_SKIP)
+            {
+                result = "skip";
+                nskip++;
+            }
+            else if (check->limitation)
+            {
+                result = "limitation";
+                nlimitation++;
+            }
+            else
+            {
+                result = "fail";
+                nfail++;
+            }
+
+            fprintf(
+                stderr,
+                "myst-conformance: %s.%s: %s\n",
+                suite->name,
+                check->name,
+                result);
+
+            fprintf(os, "%s\n    {", sep);
+            _put_field(os, "suite", suite->name, true);
+            _put_field(os, "name", check->name, true);
+            _put_field(os, "expect", check->expect, true);
+            _put_field(os, "limitation", check->limitation, true);
+            _put_field(os, "result", result, true);
+            _put_field(os, "detail", detail, true);
+            fprintf(os, "\"msec\": %ld}", msec);
+            sep = ",";
+        }
+    }
+
+    fprintf(
+        os,
+        "\n  ],\n  \"summary\": {\"pass\": %zu, \"fail\": %zu, "
+        "\"limitation\": %zu, \"skip\": %zu}\n}\n",
+        npass,
+        nfail,
+        nlimitation,
+        nskip);
+
+    rmdir(_tmpdir);
+
+    if (os != stdout)
+        fclose(os);
+
+    fprintf(
+        stderr,
+        "myst-conformance: %zu passed, %zu failed, %zu limitations, "
+        "%zu skipped\n",
+        npass,
+        nfail,
+        nlimitation,
+        nskip);
+
+    return nfail ? 1 : 0;
+}
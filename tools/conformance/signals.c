@@ -0,0 +1,341 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <pthread.h>
+#include <signal.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <sys/time.h>
+#include <unistd.h>
+
+#include "conformance.h"
+
+static volatile sig_atomic_t _count;
+static volatile sig_atomic_t _signo;
+static volatile sig_atomic_t _masked;
+static volatile uintptr_t _stack_addr;
+static volatile pthread_t _thread;
+
+static void _handler(int signo, siginfo_t* info, void* ucontext)
+{
+    sigset_t mask;
+    int local;
+
+    (void)ucontext;
+
+    _count++;
+    _signo = info->si_signo == signo ? signo : -1;
+    _thread = pthread_self();
+    _stack_addr = (uintptr_t)&local;
+
+    if (pthread_sigmask(SIG_SETMASK, NULL, &mask) == 0)
+        _masked = sigismember(&mask, signo);
+}
+
+static int _catch(int signo, int flags)
+{
+    struct sigaction sa;
+
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_sigaction = _handler;
+    sa.sa_flags = SA_SIGINFO | flags;
+    sigemptyset(&sa.sa_mask);
+
+    _count = 0;
+    _signo = 0;
+    _masked = 0;
+    return sigaction(signo, &sa, NULL);
+}
+
+/* ignore the signal (which discards it if pending), then unblock it */
+static void _reset(int signo)
+{
+    sigset_t set;
+
+    signal(signo, SIG_IGN);
+    sigemptyset(&set);
+    sigaddset(&set, signo);
+    pthread_sigmask(SIG_UNBLOCK, &set, NULL);
+    signal(signo, SIG_DFL);
+}
+
+static int _block(int signo)
+{
+    sigset_t set;
+
+    sigemptyset(&set);
+    sigaddset(&set, signo);
+    return pthread_sigmask(SIG_BLOCK, &set, NULL);
+}
+
+static conformance_result_t _handler_runs(conformance_context_t* context)
+{
+    REQUIRE(_catch(SIGUSR1, 0) == 0);
+    REQUIRE(raise(SIGUSR1) == 0);
+    _reset(SIGUSR1);
+
+    REQUIRE(_count == 1);
+    REQUIRE(_signo == SIGUSR1);
+
+    /* without SA_NODEFER */
+    REQUIRE(_masked == 1);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _pending(conformance_context_t* context)
+{
+    sigset_t set;
+
+    REQUIRE(_catch(SIGUSR2, 0) == 0);
+    REQUIRE(_block(SIGUSR2) == 0);
+    REQUIRE(raise(SIGUSR2) == 0);
+    REQUIRE(raise(SIGUSR2) == 0);
+
+    REQUIRE(_count == 0);
+    REQUIRE(sigpending(&set) == 0 && sigismember(&set, SIGUSR2));
+
+    /* a standard signal is pending once */
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR2);
+    REQUIRE(pthread_sigmask(SIG_UNBLOCK, &set, NULL) == 0);
+    _reset(SIGUSR2);
+
+    REQUIRE(_count == 1);
+    REQUIRE(sigpending(&set) == 0 && !sigismember(&set, SIGUSR2));
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _ignored(conformance_context_t* context)
+{
+    sigset_t set;
+
+    REQUIRE(signal(SIGUSR1, SIG_IGN) != SIG_ERR);
+    REQUIRE(raise(SIGUSR1) == 0);
+    REQUIRE(sigpending(&set) == 0 && !sigismember(&set, SIGUSR1));
+    signal(SIGUSR1, SIG_DFL);
+
+    return CONFORMANCE_PASS;
+}
+
+static void* _idle_thread(void* arg)
+{
+    volatile int* stop = arg;
+
+    while (!*stop)
+        usleep(1000);
+
+    return NULL;
+}
+
+static conformance_result_t _thread_kill(conformance_context_t* context)
+{
+    volatile int stop = 0;
+    pthread_t thread;
+    long start;
+
+    REQUIRE(_catch(SIGUSR1, 0) == 0);
+    REQUIRE(pthread_create(&thread, NULL, _idle_thread, (void*)&stop) == 0);
+    REQUIRE(pthread_kill(thread, SIGUSR1) == 0);
+
+    for (start = conformance_now_msec(); !_count;)
+    {
+        if (conformance_now_msec() - start > 5000)
+            break;
+
+        usleep(1000);
+    }
+
+    stop = 1;
+    pthread_join(thread, NULL);
+    _reset(SIGUSR1);
+
+    REQUIRE(_count == 1);
+    REQUIRE(pthread_equal(_thread, thread));
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _altstack(conformance_context_t* context)
+{
+    const size_t size = 64 * 1024;
+    stack_t ss;
+    stack_t old;
+    uintptr_t base;
+
+    REQUIRE((ss.ss_sp = malloc(size)));
+    ss.ss_size = size;
+    ss.ss_flags = 0;
+    base = (uintptr_t)ss.ss_sp;
+
+    REQUIRE(sigaltstack(&ss, &old) == 0);
+    REQUIRE(_catch(SIGUSR1, SA_ONSTACK) == 0);
+    raise(SIGUSR1);
+    _reset(SIGUSR1);
+    sigaltstack(&old, NULL);
+    free(ss.ss_sp);
+
+    REQUIRE(_count == 1);
+    REQUIRE(_stack_addr >= base && _stack_addr < base + size);
+
+    return CONFORMANCE_PASS;
+}
+
+typedef struct writer
+{
+    int fd;
+    volatile int stop;
+} writer_t;
+
+/* end the read if no signal interrupted it within 2 seconds */
+static void* _write_later(void* arg)
+{
+    writer_t* writer = arg;
+
+    for (int i = 0; i < 2000 && !writer->stop; i++)
+        usleep(1000);
+
+    if (!writer->stop && write(writer->fd, "x", 1) != 1)
+        return arg;
+
+    return NULL;
+}
+
+static conformance_result_t _eintr(conformance_context_t* context)
+{
+    struct itimerval it = {{0, 0}, {0, 20000}};
+    writer_t writer;
+    pthread_t thread;
+    int fds[2];
+    ssize_t n;
+    char c;
+
+    REQUIRE(pipe(fds) == 0);
+    writer.fd = fds[1];
+    writer.stop = 0;
+
+    REQUIRE(_catch(SIGALRM, 0) == 0);
+    REQUIRE(pthread_create(&thread, NULL, _write_later, &writer) == 0);
+    REQUIRE(setitimer(ITIMER_REAL, &it, NULL) == 0);
+
+    errno = 0;
+    n = read(fds[0], &c, 1);
+    writer.stop = 1;
+    pthread_join(thread, NULL);
+    _reset(SIGALRM);
+    close(fds[0]);
+    close(fds[1]);
+
+    /* without SA_RESTART */
+    REQUIRE(n == -1 && errno == EINTR);
+    REQUIRE(_count == 1);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _sigtimedwait(conformance_context_t* context)
+{
+    const struct timespec timeout = {1, 0};
+    sigset_t set;
+    int signo;
+
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    REQUIRE(_block(SIGUSR1) == 0);
+    REQUIRE(raise(SIGUSR1) == 0);
+
+    signo = sigtimedwait(&set, NULL, &timeout);
+    _reset(SIGUSR1);
+
+    REQUIRE(signo == SIGUSR1);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _sigsuspend(conformance_context_t* context)
+{
+    sigset_t empty;
+    int r;
+
+    sigemptyset(&empty);
+    REQUIRE(_catch(SIGUSR1, 0) == 0);
+    REQUIRE(_block(SIGUSR1) == 0);
+    REQUIRE(raise(SIGUSR1) == 0);
+
+    /* the pending signal interrupts the wait at once */
+    errno = 0;
+    r = sigsuspend(&empty);
+    _reset(SIGUSR1);
+
+    REQUIRE(r == -1 && errno == EINTR);
+    REQUIRE(_count == 1);
+
+    return CONFORMANCE_PASS;
+}
+
+static const conformance_check_t _checks[] = {
+    {
+        "handler",
+        "raise() runs the handler, with the signal blocked meanwhile",
+        NULL,
+        _handler_runs,
+        false,
+    },
+    {
+        "pending",
+        "a blocked signal is pending once and delivered when unblocked",
+        NULL,
+        _pending,
+        false,
+    },
+    {
+        "ignored",
+        "an ignored signal is discarded",
+        NULL,
+        _ignored,
+        false,
+    },
+    {
+        "thread-kill",
+        "pthread_kill() runs the handler on the given thread",
+        NULL,
+        _thread_kill,
+        false,
+    },
+    {
+        "altstack",
+        "the handler of SA_ONSTACK runs on the sigaltstack() stack",
+        NULL,
+        _altstack,
+        false,
+    },
+    {
+        "eintr",
+        "a signal without SA_RESTART ends a blocked read() with EINTR",
+        NULL,
+        _eintr,
+        false,
+    },
+    {
+        "sigtimedwait",
+        "sigtimedwait() takes a pending signal",
+        "doc/syscall-limitations.md: SYS_rt_sigtimedwait is unsupported",
+        _sigtimedwait,
+        true,
+    },
+    {
+        "sigsuspend",
+        "sigsuspend() returns EINTR after the handler of a pending signal",
+        "doc/syscall-limitations.md: SYS_rt_sigsuspend is unsupported",
+        _sigsuspend,
+        true,
+    },
+};
+
+const conformance_suite_t conformance_signals = {
+    "signals",
+    _checks,
+    CONFORMANCE_COUNTOF(_checks),
+};
@@ -0,0 +1,396 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <poll.h>
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <unistd.h>
+
+#include "conformance.h"
+
+/* bind a socket of the given type to a free port of 127.0.0.1 */
+static int _loopback(int type, struct sockaddr_in* addr)
+{
+    socklen_t len = sizeof(*addr);
+    int sd;
+
+    if ((sd = socket(AF_INET, type, 0)) < 0)
+        return -1;
+
+    memset(addr, 0, sizeof(*addr));
+    addr->sin_family = AF_INET;
+    addr->sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+    if (bind(sd, (struct sockaddr*)addr, sizeof(*addr)) != 0 ||
+        getsockname(sd, (struct sockaddr*)addr, &len) != 0)
+    {
+        close(sd);
+        return -1;
+    }
+
+    return sd;
+}
+
+static conformance_result_t _stream_pair(conformance_context_t* context)
+{
+    char buf[16];
+    int sv[2];
+
+    REQUIRE(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+
+    REQUIRE(send(sv[0], "ping", 4, 0) == 4);
+    REQUIRE(recv(sv[1], buf, sizeof(buf), 0) == 4);
+    REQUIRE(memcmp(buf, "ping", 4) == 0);
+    REQUIRE(send(sv[1], "pong", 4, 0) == 4);
+    REQUIRE(recv(sv[0], buf, sizeof(buf), 0) == 4);
+
+    /* the peer reads the end of the stream */
+    REQUIRE(shutdown(sv[0], SHUT_WR) == 0);
+    REQUIRE(recv(sv[1], buf, sizeof(buf), 0) == 0);
+
+    close(sv[0]);
+    close(sv[1]);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _dgram_pair(conformance_context_t* context)
+{
+    char buf[16];
+    int sv[2];
+
+    REQUIRE(socketpair(AF_UNIX, SOCK_DGRAM, 0, sv) == 0);
+
+    REQUIRE(send(sv[0], "one", 3, 0) == 3);
+    REQUIRE(send(sv[0], "two", 3, 0) == 3);
+
+    /* the messages keep their boundaries */
+    REQUIRE(recv(sv[1], buf, sizeof(buf), 0) == 3);
+    REQUIRE(memcmp(buf, "one", 3) == 0);
+    REQUIRE(recv(sv[1], buf, sizeof(buf), 0) == 3);
+    REQUIRE(memcmp(buf, "two", 3) == 0);
+
+    close(sv[0]);
+    close(sv[1]);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _unix_listen(conformance_context_t* context)
+{
+    struct sockaddr_un addr;
+    struct sockaddr_un name;
+    socklen_t len = sizeof(name);
+    char buf[16];
+    int lsd;
+    int csd;
+    int asd;
+
+    memset(&addr, 0, sizeof(addr));
+    addr.sun_family = AF_UNIX;
+    snprintf(
+        addr.sun_path,
+        sizeof(addr.sun_path),
+        "%s/socket",
+        conformance_tmpdir());
+
+    REQUIRE((lsd = socket(AF_UNIX, SOCK_STREAM, 0)) >= 0);
+    REQUIRE(bind(lsd, (struct sockaddr*)&addr, sizeof(addr)) == 0);
+    REQUIRE(listen(lsd, 1) == 0);
+
+    REQUIRE(getsockname(lsd, (struct sockaddr*)&name, &len) == 0);
+    REQUIRE(strcmp(name.sun_path, addr.sun_path) == 0);
+
+    REQUIRE((csd = socket(AF_UNIX, SOCK_STREAM, 0)) >= 0);
+    REQUIRE(connect(csd, (struct sockaddr*)&addr, sizeof(addr)) == 0);
+    REQUIRE((asd = accept(lsd, NULL, NULL)) >= 0);
+
+    REQUIRE(write(csd, "hello", 5) == 5);
+    REQUIRE(read(asd, buf, sizeof(buf)) == 5);
+
+    close(asd);
+    close(csd);
+    close(lsd);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _tcp(conformance_context_t* context)
+{
+    struct sockaddr_in addr;
+    char data[4096];
+    char buf[4096];
+    size_t n = 0;
+    ssize_t r;
+    int lsd;
+    int csd;
+    int asd;
+
+    for (size_t i = 0; i < sizeof(data); i++)
+        data[i] = (char)i;
+
+    REQUIRE((lsd = _loopback(SOCK_STREAM, &addr)) >= 0);
+    REQUIRE(addr.sin_port != 0);
+    REQUIRE(listen(lsd, 1) == 0);
+
+    REQUIRE((csd = socket(AF_INET, SOCK_STREAM, 0)) >= 0);
+    REQUIRE(connect(csd, (struct sockaddr*)&addr, sizeof(addr)) == 0);
+    REQUIRE((asd = accept(lsd, NULL, NULL)) >= 0);
+
+    REQUIRE(send(csd, data, sizeof(data), 0) == sizeof(data));
+
+    /* a stream may arrive in pieces */
+    while (n < sizeof(buf) && (r = recv(asd, buf + n, sizeof(buf) - n, 0)) > 0)
+        n += (size_t)r;
+
+    REQUIRE(n == sizeof(data) && memcmp(buf, data, n) == 0);
+
+    close(csd);
+    REQUIRE(recv(asd, buf, sizeof(buf), 0) == 0);
+
+    close(asd);
+    close(lsd);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _udp(conformance_context_t* context)
+{
+    struct sockaddr_in addr;
+    struct sockaddr_in from;
+    socklen_t len = sizeof(from);
+    struct sockaddr* to = (struct sockaddr*)&addr;
+    struct sockaddr* sender = (struct sockaddr*)&from;
+    char buf[16];
+    int rsd;
+    int ssd;
+
+    REQUIRE((rsd = _loopback(SOCK_DGRAM, &addr)) >= 0);
+    REQUIRE((ssd = socket(AF_INET, SOCK_DGRAM, 0)) >= 0);
+
+    REQUIRE(sendto(ssd, "datagram", 8, 0, to, sizeof(addr)) == 8);
+    REQUIRE(recvfrom(rsd, buf, sizeof(buf), 0, sender, &len) == 8);
+    REQUIRE(memcmp(buf, "datagram", 8) == 0);
+    REQUIRE(from.sin_addr.s_addr == htonl(INADDR_LOOPBACK));
+
+    close(ssd);
+    close(rsd);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _nonblock(conformance_context_t* context)
+{
+    struct sockaddr_in addr;
+    char c;
+    int lsd;
+    int sv[2];
+
+    REQUIRE((lsd = _loopback(SOCK_STREAM, &addr)) >= 0);
+    REQUIRE(listen(lsd, 1) == 0);
+    REQUIRE(fcntl(lsd, F_SETFL, O_NONBLOCK) == 0);
+
+    errno = 0;
+    REQUIRE(accept(lsd, NULL, NULL) == -1);
+    REQUIRE(errno == EAGAIN || errno == EWOULDBLOCK);
+    close(lsd);
+
+    REQUIRE(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+    errno = 0;
+    REQUIRE(recv(sv[0], &c, 1, MSG_DONTWAIT) == -1);
+    REQUIRE(errno == EAGAIN || errno == EWOULDBLOCK);
+    close(sv[0]);
+    close(sv[1]);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _poll(conformance_context_t* context)
+{
+    struct pollfd pfd;
+    int sv[2];
+
+    REQUIRE(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+
+    pfd.fd = sv[0];
+    pfd.events = POLLIN | POLLOUT;
+    pfd.revents = 0;
+    REQUIRE(poll(&pfd, 1, 0) == 1 && pfd.revents == POLLOUT);
+
+    REQUIRE(write(sv[1], "x", 1) == 1);
+    pfd.events = POLLIN;
+    REQUIRE(poll(&pfd, 1, 1000) == 1 && (pfd.revents & POLLIN));
+
+    /* the peer hung up */
+    close(sv[1]);
+    REQUIRE(poll(&pfd, 1, 1000) == 1);
+    REQUIRE(pfd.revents & (POLLIN | POLLHUP));
+    close(sv[0]);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _refused(conformance_context_t* context)
+{
+    struct sockaddr_in addr;
+    int sd;
+
+    /* a port that nothing listens on */
+    REQUIRE((sd = _loopback(SOCK_STREAM, &addr)) >= 0);
+    close(sd);
+
+    REQUIRE((sd = socket(AF_INET, SOCK_STREAM, 0)) >= 0);
+    errno = 0;
+    REQUIRE(connect(sd, (struct sockaddr*)&addr, sizeof(addr)) == -1);
+    REQUIRE(errno == ECONNREFUSED);
+    close(sd);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _epipe(conformance_context_t* context)
+{
+    int sv[2];
+
+    REQUIRE(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+    close(sv[1]);
+
+    errno = 0;
+    REQUIRE(send(sv[0], "x", 1, MSG_NOSIGNAL) == -1 && errno == EPIPE);
+    close(sv[0]);
+
+    return CONFORMANCE_PASS;
+}
+
+static conformance_result_t _scm_rights(conformance_context_t* context)
+{
+    char control[CMSG_SPACE(sizeof(int))];
+    struct msghdr msg;
+    struct cmsghdr* cmsg;
+    struct iovec iov;
+    char c = 'm';
+    char buf[8];
+    int pipefd[2];
+    int sv[2];
+    int fd;
+
+    REQUIRE(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+    REQUIRE(pipe(pipefd) == 0);
+
+    memset(&msg, 0, sizeof(msg));
+    memset(control, 0, sizeof(control));
+    iov.iov_base = &c;
+    iov.iov_len = 1;
+    msg.msg_iov = &iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control;
+    msg.msg_controllen = sizeof(control);
+    cmsg = CMSG_FIRSTHDR(&msg);
+    cmsg->cmsg_level = SOL_SOCKET;
+    cmsg->cmsg_type = SCM_RIGHTS;
+    cmsg->cmsg_len = CMSG_LEN(sizeof(int));
+    memcpy(CMSG_DATA(cmsg), &pipefd[0], sizeof(int));
+
+    REQUIRE(sendmsg(sv[0], &msg, 0) == 1);
+    close(pipefd[0]);
+
+    memset(control, 0, sizeof(control));
+    msg.msg_controllen = sizeof(control);
+    REQUIRE(recvmsg(sv[1], &msg, 0) == 1);
+    REQUIRE((cmsg = CMSG_FIRSTHDR(&msg)) && cmsg->cmsg_type == SCM_RIGHTS);
+    memcpy(&fd, CMSG_DATA(cmsg), sizeof(int));
+
+    /* the received descriptor reads the pipe */
+    REQUIRE(write(pipefd[1], "fd", 2) == 2);
+    REQUIRE(read(fd, buf, sizeof(buf)) == 2);
+
+    close(fd);
+    close(pipefd[1]);
+    close(sv[0]);
+    close(sv[1]);
+
+    return CONFORMANCE_PASS;
+}
+
+static const conformance_check_t _checks[] = {
+    {
+        "stream-pair",
+        "an AF_UNIX stream pair carries data both ways, then the end",
+        NULL,
+        _stream_pair,
+        false,
+    },
+    {
+        "dgram-pair",
+        "an AF_UNIX datagram pair keeps the message boundaries",
+        NULL,
+        _dgram_pair,
+        false,
+    },
+    {
+        "unix-listen",
+        "an AF_UNIX socket bound to a path accepts connections",
+        NULL,
+        _unix_listen,
+        false,
+    },
+    {
+        "tcp",
+        "a TCP connection over 127.0.0.1 carries data, then the end",
+        NULL,
+        _tcp,
+        false,
+    },
+    {
+        "udp",
+        "a UDP datagram over 127.0.0.1 arrives with its source address",
+        NULL,
+        _udp,
+        false,
+    },
+    {
+        "nonblock",
+        "accept() and recv() fail with EAGAIN rather than block",
+        NULL,
+        _nonblock,
+        false,
+    },
+    {
+        "poll",
+        "poll() reports sockets that are writable, readable or hung up",
+        NULL,
+        _poll,
+        false,
+    },
+    {
+        "refused",
+        "connect() to a port without a listener fails with ECONNREFUSED",
+        NULL,
+        _refused,
+        false,
+    },
+    {
+        "epipe",
+        "send() with MSG_NOSIGNAL to a closed peer fails with EPIPE",
+        NULL,
+        _epipe,
+        false,
+    },
+    {
+        "scm-rights",
+        "sendmsg() passes a file descriptor with SCM_RIGHTS",
+        NULL,
+        _scm_rights,
+        false,
+    },
+};
+
+const conformance_suite_t conformance_sockets = {
+    "sockets",
+    _checks,
+    CONFORMANCE_COUNTOF(_checks),
+};
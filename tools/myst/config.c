@@ -28,26 +28,25 @@
         }                                                   \
     } while (0)
 
-static json_result_t _extract_mem_size(
+static json_result_t _extract_size(
     json_type_t type,
     const json_union_t* un,
-    uint64_t* num_pages)
+    uint64_t* size)
 {
     json_result_t ret = JSON_FAILED;
-    uint64_t value;
 
-    if (num_pages == NULL)
+    if (size == NULL)
         CONFIG_RAISE(JSON_FAILED);
 
     if (type == JSON_TYPE_INTEGER)
-        value = (uint64_t)un->integer; // number in bytes
+        *size = (uint64_t)un->integer; // number in bytes
     else if (type == JSON_TYPE_STRING)
     {
-        if (myst_expand_size_string_to_ulong(un->string, &value) != 0)
+        if (myst_expand_size_string_to_ulong(un->string, size) != 0)
         {
             fprintf(
                 stderr,
-                "ERROR: Configuration: MemSize values can be in megabyte (m), "
+                "ERROR: Configuration: size values can be in megabyte (m), "
                 "kilobytes (k) or bytes\n");
             fprintf(stderr, "for example \"10m\", \"100k\" or \"100000000\"\n");
             CONFIG_RAISE(JSON_OUT_OF_BOUNDS);
@@ -56,6 +55,26 @@ static json_result_t _extract_mem_size(
     else
         CONFIG_RAISE(JSON_TYPE_MISMATCH);
 
+    ret = JSON_OK;
+done:
+    return ret;
+}
+
+static json_result_t _extract_mem_size(
+    json_type_t type,
+    const json_union_t* un,
+    uint64_t* num_pages)
+{
+    json_result_t ret = JSON_FAILED;
+    uint64_t value;
+
+    if (num_pages == NULL)
+        CONFIG_RAISE(JSON_FAILED);
+
+    ret = _extract_size(type, un, &value);
+    if (ret != JSON_OK)
+        CONFIG_RAISE(ret);
+
     if (myst_round_up(value, PAGE_SIZE, &value) != 0)
         CONFIG_RAISE(JSON_FAILED);
 
@@ -117,6 +136,468 @@ done:
     return ret;
 }
 
+/* is this a value inside an element of the "SyscallPolicy.Rules" array? */
+static bool _is_syscall_rule(json_parser_t* parser)
+{
+    return parser->depth == 3 &&
+           strcmp(parser->path[0].name, "SyscallPolicy") == 0 &&
+           strcmp(parser->path[1].name, "Rules") == 0;
+}
+
+static json_result_t _extract_syscall_rule(
+    json_parser_t* parser,
+    json_type_t type,
+    const json_union_t* un,
+    myst_syscall_policy_t* policy)
+{
+    json_result_t ret = JSON_FAILED;
+    const size_t index = parser->path[1].index;
+    myst_syscall_rule_t* rule;
+    const char** field = NULL;
+
+    /* grow the rules array to hold this element */
+    if (index >= policy->num_rules)
+    {
+        myst_syscall_rule_t* tmp;
+        const size_t count = index + 1;
+
+        if (!(tmp = realloc(policy->rules, count * sizeof(*tmp))))
+            CONFIG_RAISE(JSON_OUT_OF_MEMORY);
+
+        memset(
+            tmp + policy->num_rules,
+            0,
+            (count - policy->num_rules) * sizeof(*tmp));
+        policy->rules = tmp;
+        policy->num_rules = count;
+    }
+
+    rule = &policy->rules[index];
+
+    if (json_match(parser, "SyscallPolicy.Rules.Syscall") == JSON_OK)
+        field = &rule->syscall;
+    else if (json_match(parser, "SyscallPolicy.Rules.Action") == JSON_OK)
+        field = &rule->action;
+    else if (json_match(parser, "SyscallPolicy.Rules.Errno") == JSON_OK)
+        field = &rule->error;
+    else if (json_match(parser, "SyscallPolicy.Rules.Path") == JSON_OK)
+        field = &rule->path;
+    else if (json_match(parser, "SyscallPolicy.Rules.Address") == JSON_OK)
+        field = &rule->address;
+    else if (json_match(parser, "SyscallPolicy.Rules.Port") == JSON_OK)
+    {
+        if (type != JSON_TYPE_INTEGER)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        rule->port = (long)un->integer;
+    }
+
+    if (field)
+    {
+        if (type != JSON_TYPE_STRING)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        *field = un->string;
+    }
+
+    ret = JSON_OK;
+
+done:
+    return ret;
+}
+
+/* is this a value inside an element of the "Secrets.Items" array? */
+static bool _is_secret(json_parser_t* parser)
+{
+    return parser->depth == 3 &&
+           strcmp(parser->path[0].name, "Secrets") == 0 &&
+           strcmp(parser->path[1].name, "Items") == 0;
+}
+
+static json_result_t _extract_secret(
+    json_parser_t* parser,
+    json_type_t type,
+    const json_union_t* un,
+    myst_secrets_config_t* config)
+{
+    json_result_t ret = JSON_FAILED;
+    const size_t index = parser->path[1].index;
+    myst_secret_t* secret;
+    const char** field = NULL;
+
+    /* grow the secrets array to hold this element */
+    if (index >= config->num_secrets)
+    {
+        myst_secret_t* tmp;
+        const size_t count = index + 1;
+
+        if (!(tmp = realloc(config->secrets, count * sizeof(*tmp))))
+            CONFIG_RAISE(JSON_OUT_OF_MEMORY);
+
+        memset(
+            tmp + config->num_secrets,
+            0,
+            (count - config->num_secrets) * sizeof(*tmp));
+        config->secrets = tmp;
+        config->num_secrets = count;
+    }
+
+    secret = &config->secrets[index];
+
+    if (json_match(parser, "Secrets.Items.Provider") == JSON_OK)
+        field = &secret->provider;
+    else if (json_match(parser, "Secrets.Items.Id") == JSON_OK)
+        field = &secret->id;
+    else if (json_match(parser, "Secrets.Items.Environment") == JSON_OK)
+        field = &secret->environment;
+    else if (json_match(parser, "Secrets.Items.File") == JSON_OK)
+        field = &secret->file;
+
+    if (field)
+    {
+        if (type != JSON_TYPE_STRING)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        *field = un->string;
+    }
+
+    ret = JSON_OK;
+
+done:
+    return ret;
+}
+
+static bool _is_service(json_parser_t* parser)
+{
+    return parser->depth == 2 && strcmp(parser->path[0].name, "Services") == 0;
+}
+
+static json_result_t _extract_service(
+    json_parser_t* parser,
+    json_type_t type,
+    const json_union_t* un,
+    myst_services_config_t* config)
+{
+    json_result_t ret = JSON_FAILED;
+    const size_t index = parser->path[0].index;
+    myst_service_t* service;
+    const char** field = NULL;
+
+    /* grow the services array to hold this element */
+    if (index >= config->num_services)
+    {
+        myst_service_t* tmp;
+        const size_t count = index + 1;
+
+        if (!(tmp = realloc(config->services, count * sizeof(*tmp))))
+            CONFIG_RAISE(JSON_OUT_OF_MEMORY);
+
+        memset(
+            tmp + config->num_services,
+            0,
+            (count - config->num_services) * sizeof(*tmp));
+        config->services = tmp;
+        config->num_services = count;
+    }
+
+    service = &config->services[index];
+
+    if (json_match(parser, "Services.Name") == JSON_OK)
+        field = &service->name;
+    else if (json_match(parser, "Services.ApplicationPath") == JSON_OK)
+        field = &service->path;
+    else if (json_match(parser, "Services.Restart") == JSON_OK)
+        field = &service->restart;
+    else if (json_match(parser, "Services.ReadyPath") == JSON_OK)
+        field = &service->ready_path;
+    else if (json_match(parser, "Services.ApplicationParameters") == JSON_OK)
+    {
+        ret = _config_extract_array(
+            type, un, &service->argv, &service->argc);
+        if (ret != JSON_OK)
+            CONFIG_RAISE(ret);
+    }
+    else if (json_match(parser, "Services.EnvironmentVariables") == JSON_OK)
+    {
+        ret = _config_extract_array(
+            type, un, &service->envp, &service->envc);
+        if (ret != JSON_OK)
+            CONFIG_RAISE(ret);
+    }
+    else if (json_match(parser, "Services.MaxRestarts") == JSON_OK)
+    {
+        if (type != JSON_TYPE_INTEGER || un->integer < 0)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        service->max_restarts = (size_t)un->integer;
+    }
+    else if (json_match(parser, "Services.ReadyTimeout") == JSON_OK)
+    {
+        if (type != JSON_TYPE_INTEGER || un->integer < 0)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        service->ready_timeout = (uint64_t)un->integer;
+    }
+
+    if (field)
+    {
+        if (type != JSON_TYPE_STRING)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        *field = un->string;
+    }
+
+    ret = JSON_OK;
+
+done:
+    return ret;
+}
+
+static bool _is_host_import(json_parser_t* parser)
+{
+    return parser->depth == 2 &&
+           strcmp(parser->path[0].name, "HostDirectoryImports") == 0;
+}
+
+static json_result_t _extract_host_import(
+    json_parser_t* parser,
+    json_type_t type,
+    const json_union_t* un,
+    myst_host_imports_config_t* config)
+{
+    json_result_t ret = JSON_FAILED;
+    const size_t index = parser->path[0].index;
+    myst_host_import_t* import;
+    const char** field = NULL;
+
+    /* grow the imports array to hold this element */
+    if (index >= config->num_imports)
+    {
+        myst_host_import_t* tmp;
+        const size_t count = index + 1;
+
+        if (!(tmp = realloc(config->imports, count * sizeof(*tmp))))
+            CONFIG_RAISE(JSON_OUT_OF_MEMORY);
+
+        memset(
+            tmp + config->num_imports,
+            0,
+            (count - config->num_imports) * sizeof(*tmp));
+        config->imports = tmp;
+        config->num_imports = count;
+    }
+
+    import = &config->imports[index];
+
+    if (json_match(parser, "HostDirectoryImports.HostPath") == JSON_OK)
+        field = &import->host_path;
+    else if (json_match(parser, "HostDirectoryImports.TargetPath") == JSON_OK)
+        field = &import->target_path;
+    else if (json_match(parser, "HostDirectoryImports.Sha256") == JSON_OK)
+        field = &import->sha256;
+
+    if (field)
+    {
+        if (type != JSON_TYPE_STRING)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        *field = un->string;
+    }
+
+    ret = JSON_OK;
+
+done:
+    return ret;
+}
+
+static bool _is_host_file(json_parser_t* parser)
+{
+    return parser->depth == 2 && strcmp(parser->path[0].name, "HostFiles") == 0;
+}
+
+static json_result_t _extract_host_file(
+    json_parser_t* parser,
+    json_type_t type,
+    const json_union_t* un,
+    myst_host_imports_config_t* config)
+{
+    json_result_t ret = JSON_FAILED;
+    const size_t index = parser->path[0].index;
+    myst_host_file_t* file;
+    const char** field = NULL;
+
+    /* grow the files array to hold this element */
+    if (index >= config->num_files)
+    {
+        myst_host_file_t* tmp;
+        const size_t count = index + 1;
+
+        if (!(tmp = realloc(config->files, count * sizeof(*tmp))))
+            CONFIG_RAISE(JSON_OUT_OF_MEMORY);
+
+        memset(
+            tmp + config->num_files,
+            0,
+            (count - config->num_files) * sizeof(*tmp));
+        config->files = tmp;
+        config->num_files = count;
+    }
+
+    file = &config->files[index];
+
+    if (json_match(parser, "HostFiles.HostPath") == JSON_OK)
+        field = &file->host_path;
+    else if (json_match(parser, "HostFiles.TargetPath") == JSON_OK)
+        field = &file->target_path;
+
+    if (field)
+    {
+        if (type != JSON_TYPE_STRING)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        *field = un->string;
+    }
+
+    ret = JSON_OK;
+
+done:
+    return ret;
+}
+
+static bool _is_disk(json_parser_t* parser)
+{
+    return parser->depth == 2 && strcmp(parser->path[0].name, "Disks") == 0;
+}
+
+static json_result_t _extract_disk(
+    json_parser_t* parser,
+    json_type_t type,
+    const json_union_t* un,
+    myst_disks_config_t* config)
+{
+    json_result_t ret = JSON_FAILED;
+    const size_t index = parser->path[0].index;
+    myst_disk_config_t* disk;
+
+    /* grow the disks array to hold this element */
+    if (index >= config->num_disks)
+    {
+        myst_disk_config_t* tmp;
+        const size_t count = index + 1;
+
+        if (!(tmp = realloc(config->disks, count * sizeof(*tmp))))
+            CONFIG_RAISE(JSON_OUT_OF_MEMORY);
+
+        memset(
+            tmp + config->num_disks,
+            0,
+            (count - config->num_disks) * sizeof(*tmp));
+        config->disks = tmp;
+        config->num_disks = count;
+    }
+
+    disk = &config->disks[index];
+
+    if (json_match(parser, "Disks.HostPath") == JSON_OK)
+    {
+        if (type != JSON_TYPE_STRING)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        disk->host_path = un->string;
+    }
+    else if (json_match(parser, "Disks.Journal") == JSON_OK)
+    {
+        if (type != JSON_TYPE_STRING)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        disk->journal_path = un->string;
+    }
+    else if (json_match(parser, "Disks.CacheSize") == JSON_OK)
+    {
+        uint64_t size;
+
+        if ((ret = _extract_size(type, un, &size)) != JSON_OK)
+            CONFIG_RAISE(ret);
+
+        disk->cache_size = size;
+    }
+    else if (json_match(parser, "Disks.ReadOnly") == JSON_OK)
+    {
+        if (type != JSON_TYPE_BOOLEAN)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        disk->read_only = un->boolean;
+    }
+
+    ret = JSON_OK;
+
+done:
+    return ret;
+}
+
+static bool _is_allowed_mount(json_parser_t* parser)
+{
+    return parser->depth == 2 &&
+           strcmp(parser->path[0].name, "AllowedMounts") == 0;
+}
+
+static json_result_t _extract_allowed_mount(
+    json_parser_t* parser,
+    json_type_t type,
+    const json_union_t* un,
+    myst_mount_policy_t* policy)
+{
+    json_result_t ret = JSON_FAILED;
+    const size_t index = parser->path[0].index;
+    myst_mount_rule_t* rule;
+
+    /* grow the rules array to hold this element */
+    if (index >= policy->num_rules)
+    {
+        myst_mount_rule_t* tmp;
+        const size_t count = index + 1;
+
+        if (!(tmp = realloc(policy->rules, count * sizeof(*tmp))))
+            CONFIG_RAISE(JSON_OUT_OF_MEMORY);
+
+        memset(
+            tmp + policy->num_rules,
+            0,
+            (count - policy->num_rules) * sizeof(*tmp));
+        policy->rules = tmp;
+        policy->num_rules = count;
+    }
+
+    rule = &policy->rules[index];
+
+    if (json_match(parser, "AllowedMounts.Type") == JSON_OK)
+    {
+        if (type != JSON_TYPE_STRING)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        rule->type = un->string;
+    }
+    else if (json_match(parser, "AllowedMounts.Source") == JSON_OK)
+    {
+        if (type != JSON_TYPE_STRING)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        rule->source = un->string;
+    }
+    else if (json_match(parser, "AllowedMounts.Target") == JSON_OK)
+    {
+        if (type != JSON_TYPE_STRING)
+            CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+        rule->target = un->string;
+    }
+
+    ret = JSON_OK;
+
+done:
+    return ret;
+}
+
 static json_result_t _json_read_callback(
     json_parser_t* parser,
     json_reason_t reason,
@@ -212,6 +693,13 @@ static json_result_t _json_read_callback(
                 if (ret != JSON_OK)
                     CONFIG_RAISE(ret);
             }
+            else if (json_match(parser, "CommittedMemorySize") == JSON_OK)
+            {
+                ret = _extract_mem_size(
+                    type, un, &parsed_data->committed_pages);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
             else if (json_match(parser, "ApplicationPath") == JSON_OK)
             {
                 if (type == JSON_TYPE_STRING)
@@ -259,6 +747,30 @@ static json_result_t _json_read_callback(
                 if (ret != JSON_OK)
                     CONFIG_RAISE(ret);
             }
+            else if (
+                json_match(parser, "LaunchOverrides.EnvironmentVariables") ==
+                JSON_OK)
+            {
+                ret = _config_extract_array(
+                    type,
+                    un,
+                    &parsed_data->overridable_environment_variables,
+                    &parsed_data->overridable_environment_variables_count);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (
+                json_match(parser, "LaunchOverrides.ApplicationParameters") ==
+                JSON_OK)
+            {
+                ret = _config_extract_array(
+                    type,
+                    un,
+                    &parsed_data->overridable_application_parameters,
+                    &parsed_data->overridable_application_parameters_count);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
             else if (json_match(parser, "CurrentWorkingDirectory") == JSON_OK)
             {
                 if (type == JSON_TYPE_STRING)
@@ -273,6 +785,368 @@ static json_result_t _json_read_callback(
                 else
                     CONFIG_RAISE(JSON_TYPE_MISMATCH);
             }
+            else if (json_match(parser, "UnameRelease") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->uname_release = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "UnameVersion") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->uname_version = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "UnameMachine") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->uname_machine = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "DisabledCpuFeatures") == JSON_OK)
+            {
+                ret = _config_extract_array(
+                    type,
+                    un,
+                    &parsed_data->disabled_cpu_features,
+                    &parsed_data->disabled_cpu_features_count);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (json_match(parser, "DnsServers") == JSON_OK)
+            {
+                ret = _config_extract_array(
+                    type,
+                    un,
+                    &parsed_data->dns_servers,
+                    &parsed_data->dns_servers_count);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (json_match(parser, "ConsoleLogStdout") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->console_log_stdout = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "ConsoleLogStderr") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->console_log_stderr = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "ConsoleLogTimestamps") == JSON_OK)
+            {
+                if (type == JSON_TYPE_BOOLEAN)
+                    parsed_data->console_log_timestamps = un->boolean;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "ConsoleLogMaxSize") == JSON_OK)
+            {
+                ret = _extract_size(
+                    type, un, &parsed_data->console_log_max_size);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (json_match(parser, "ConsoleLogMaxFiles") == JSON_OK)
+            {
+                if (type == JSON_TYPE_INTEGER && un->integer >= 0)
+                    parsed_data->console_log_max_files =
+                        (uint64_t)un->integer;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "ConsoleLogOnly") == JSON_OK)
+            {
+                if (type == JSON_TYPE_BOOLEAN)
+                    parsed_data->console_log_only = un->boolean;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "AuditLog") == JSON_OK)
+            {
+                if (type == JSON_TYPE_BOOLEAN)
+                    parsed_data->audit_log = un->boolean;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "AuditLogPath") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->audit_log_path = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "HostClockSource") == JSON_OK)
+            {
+                if (type != JSON_TYPE_STRING)
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+                if (strcmp(un->string, "realtime") == 0)
+                    parsed_data->host_clock_source = MYST_CLOCK_SOURCE_REALTIME;
+                else if (strcmp(un->string, "tai") == 0)
+                    parsed_data->host_clock_source = MYST_CLOCK_SOURCE_TAI;
+                else if (strcmp(un->string, "ntp") == 0)
+                    parsed_data->host_clock_source = MYST_CLOCK_SOURCE_NTP;
+                else
+                    CONFIG_RAISE(JSON_UNSUPPORTED);
+            }
+            else if (json_match(parser, "TaiOffset") == JSON_OK)
+            {
+                if (type != JSON_TYPE_INTEGER || un->integer < 0)
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+
+                parsed_data->tai_offset = (long)un->integer;
+            }
+            else if (json_match(parser, "LeapSecondSmear") == JSON_OK)
+            {
+                if (type == JSON_TYPE_BOOLEAN)
+                    parsed_data->leap_second_smear = un->boolean;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "RootfsOverlay") == JSON_OK)
+            {
+                if (type == JSON_TYPE_BOOLEAN)
+                    parsed_data->rootfs_overlay = un->boolean;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "RootfsLayers") == JSON_OK)
+            {
+                ret = _config_extract_array(
+                    type,
+                    un,
+                    &parsed_data->rootfs_layers,
+                    &parsed_data->rootfs_layers_count);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (json_match(parser, "RootfsLayerStore") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->rootfs_layer_store = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (
+                json_match(parser, "SyscallPolicy.DefaultAction") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->syscall_policy.default_action = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (
+                json_match(parser, "SyscallPolicy.DefaultErrno") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->syscall_policy.default_error = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "WriteXorExecute") == JSON_OK)
+            {
+                if (type == JSON_TYPE_BOOLEAN)
+                    parsed_data->write_xor_execute = un->boolean;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "WriteXorExecuteAllow") == JSON_OK)
+            {
+                ret = _config_extract_array(
+                    type,
+                    un,
+                    &parsed_data->write_xor_execute_allow,
+                    &parsed_data->write_xor_execute_allow_count);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (json_match(parser, "OomPolicy") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->oom_policy = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "MemLowWatermark") == JSON_OK)
+            {
+                ret = _extract_size(type, un, &parsed_data->mem_low_watermark);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (json_match(parser, "ShutdownGracePeriod") == JSON_OK)
+            {
+                if (type == JSON_TYPE_INTEGER && un->integer >= 0)
+                    parsed_data->shutdown_grace_period = (uint64_t)un->integer;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "QuoteCacheLifetime") == JSON_OK)
+            {
+                if (type == JSON_TYPE_INTEGER && un->integer >= 0)
+                    parsed_data->quote_cache_lifetime = (uint64_t)un->integer;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "CpuTimeLimit") == JSON_OK)
+            {
+                if (type == JSON_TYPE_INTEGER && un->integer >= 0)
+                    parsed_data->cpu_time_limit = (uint64_t)un->integer;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "CpuTimeHardLimit") == JSON_OK)
+            {
+                if (type == JSON_TYPE_INTEGER && un->integer >= 0)
+                    parsed_data->cpu_time_hard_limit = (uint64_t)un->integer;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "WallTimeLimit") == JSON_OK)
+            {
+                if (type == JSON_TYPE_INTEGER && un->integer >= 0)
+                    parsed_data->wall_time_limit = (uint64_t)un->integer;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "DebugMalloc") == JSON_OK)
+            {
+                if (type == JSON_TYPE_BOOLEAN)
+                    parsed_data->debug_malloc = un->boolean;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "DebugMallocQuarantine") == JSON_OK)
+            {
+                ret = _extract_size(
+                    type, un, &parsed_data->debug_malloc_quarantine);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (json_match(parser, "DebugSignalSafety") == JSON_OK)
+            {
+                if (type == JSON_TYPE_BOOLEAN)
+                    parsed_data->debug_signal_safety = un->boolean;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "KeyRelease.AttestationUrl") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->key_release.attestation_url = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "KeyRelease.CaCertificates") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->key_release.ca_certificates = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (
+                json_match(parser, "KeyRelease.IdentityEndpoint") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->key_release.identity_endpoint = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "KeyRelease.ClientId") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->key_release.client_id = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "KeyRelease.Resource") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->key_release.resource = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "Secrets.CaCertificates") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->secrets.ca_certificates = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "CoreDump.Path") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->coredump.path = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (json_match(parser, "CoreDump.MaxSize") == JSON_OK)
+            {
+                ret = _extract_size(type, un, &parsed_data->coredump.max_size);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (json_match(parser, "CoreDump.EncryptionKey") == JSON_OK)
+            {
+                if (type == JSON_TYPE_STRING)
+                    parsed_data->coredump.encryption_key = un->string;
+                else
+                    CONFIG_RAISE(JSON_TYPE_MISMATCH);
+            }
+            else if (_is_secret(parser))
+            {
+                ret = _extract_secret(parser, type, un, &parsed_data->secrets);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (_is_service(parser))
+            {
+                ret = _extract_service(
+                    parser, type, un, &parsed_data->services);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (_is_host_import(parser))
+            {
+                ret = _extract_host_import(
+                    parser, type, un, &parsed_data->host_imports);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (_is_host_file(parser))
+            {
+                ret = _extract_host_file(
+                    parser, type, un, &parsed_data->host_imports);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (_is_disk(parser))
+            {
+                ret = _extract_disk(parser, type, un, &parsed_data->disks);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (_is_allowed_mount(parser))
+            {
+                ret = _extract_allowed_mount(
+                    parser, type, un, &parsed_data->mount_policy);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
+            else if (_is_syscall_rule(parser))
+            {
+                ret = _extract_syscall_rule(
+                    parser, type, un, &parsed_data->syscall_policy);
+                if (ret != JSON_OK)
+                    CONFIG_RAISE(ret);
+            }
             else
             {
                 // Ignore everything we dont understand
@@ -322,6 +1196,9 @@ int _parse_config(config_parsed_data_t* parsed_data)
         CONFIG_RAISE(JSON_UNEXPECTED);
     }
 
+    /* a configuration allows only the mounts that it lists */
+    parsed_data->mount_policy.enforced = true;
+
     ret = 0;
 
 done:
@@ -361,6 +1238,40 @@ int free_config(config_parsed_data_t* parsed_data)
         free(parsed_data->host_environment_variables);
     if (parsed_data->application_parameters)
         free(parsed_data->application_parameters);
+    if (parsed_data->overridable_environment_variables)
+        free(parsed_data->overridable_environment_variables);
+    if (parsed_data->overridable_application_parameters)
+        free(parsed_data->overridable_application_parameters);
+    if (parsed_data->disabled_cpu_features)
+        free(parsed_data->disabled_cpu_features);
+    if (parsed_data->dns_servers)
+        free(parsed_data->dns_servers);
+    if (parsed_data->syscall_policy.rules)
+        free(parsed_data->syscall_policy.rules);
+    if (parsed_data->write_xor_execute_allow)
+        free(parsed_data->write_xor_execute_allow);
+    if (parsed_data->rootfs_layers)
+        free(parsed_data->rootfs_layers);
+
+    if (parsed_data->secrets.secrets)
+        free(parsed_data->secrets.secrets);
+
+    for (size_t i = 0; i < parsed_data->services.num_services; i++)
+    {
+        free(parsed_data->services.services[i].argv);
+        free(parsed_data->services.services[i].envp);
+    }
+
+    if (parsed_data->services.services)
+        free(parsed_data->services.services);
+    if (parsed_data->host_imports.imports)
+        free(parsed_data->host_imports.imports);
+    if (parsed_data->host_imports.files)
+        free(parsed_data->host_imports.files);
+    if (parsed_data->disks.disks)
+        free(parsed_data->disks.disks);
+    if (parsed_data->mount_policy.rules)
+        free(parsed_data->mount_policy.rules);
     if (parsed_data->buffer)
         free(parsed_data->buffer);
     memset(parsed_data, 0, sizeof(*parsed_data));
@@ -392,6 +1303,10 @@ int write_oe_config_fd(int fd, config_parsed_data_t* parsed_data)
 
     fprintf(out_file, "SecurityVersion=%d\n", parsed_data->oe_security_version);
 
+    /* on-demand commits need the faulting address of page faults (SGX2) */
+    if (parsed_data->committed_pages)
+        fprintf(out_file, "CapturePFGPExceptions=1\n");
+
     ret = 0;
 
 done:
@@ -1,7 +1,16 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+#include <myst/clock.h>
+#include <myst/coredump.h>
+#include <myst/disk.h>
+#include <myst/hostimport.h>
 #include <myst/json.h>
+#include <myst/keyrelease.h>
+#include <myst/mount.h>
+#include <myst/secret.h>
+#include <myst/services.h>
+#include <myst/syscallpolicy.h>
 #include <stdio.h>
 #include <sys/types.h>
 
@@ -21,6 +30,7 @@ typedef struct _config_parsed_data_t
 
     // Mystikos config values
     uint64_t heap_pages; // heap_pages*4096=value-in-config
+    uint64_t committed_pages; // committed when the enclave starts (SGX2)
     char* application_path;
     unsigned char allow_host_parameters;
     char** application_parameters;
@@ -29,8 +39,55 @@ typedef struct _config_parsed_data_t
     size_t enclave_environment_variables_count;
     char** host_environment_variables;
     size_t host_environment_variables_count;
+    char** overridable_environment_variables;
+    size_t overridable_environment_variables_count;
+    char** overridable_application_parameters;
+    size_t overridable_application_parameters_count;
     char* cwd;
     char* hostname;
+    char* uname_release;
+    char* uname_version;
+    char* uname_machine;
+    char** disabled_cpu_features;
+    size_t disabled_cpu_features_count;
+    char** dns_servers;
+    size_t dns_servers_count;
+    char* console_log_stdout;
+    char* console_log_stderr;
+    unsigned char console_log_timestamps;
+    uint64_t console_log_max_size;
+    uint64_t console_log_max_files;
+    unsigned char console_log_only;
+    unsigned char audit_log;
+    char* audit_log_path;
+    unsigned char rootfs_overlay;
+    char** rootfs_layers;
+    size_t rootfs_layers_count;
+    char* rootfs_layer_store;
+    int host_clock_source; // myst_clock_source_t
+    long tai_offset;
+    unsigned char leap_second_smear;
+    myst_syscall_policy_t syscall_policy;
+    unsigned char write_xor_execute;
+    char** write_xor_execute_allow;
+    size_t write_xor_execute_allow_count;
+    uint64_t quote_cache_lifetime;
+    char* oom_policy;
+    uint64_t mem_low_watermark;
+    unsigned char debug_malloc;
+    uint64_t debug_malloc_quarantine;
+    unsigned char debug_signal_safety;
+    myst_key_release_config_t key_release;
+    myst_secrets_config_t secrets;
+    myst_coredump_config_t coredump;
+    myst_services_config_t services;
+    myst_host_imports_config_t host_imports;
+    myst_disks_config_t disks;
+    myst_mount_policy_t mount_policy;
+    uint64_t shutdown_grace_period;
+    uint64_t cpu_time_limit;
+    uint64_t cpu_time_hard_limit;
+    uint64_t wall_time_limit;
 
     // Internal data
     void* buffer;
@@ -0,0 +1,123 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <string.h>
+
+#include <mbedtls/gcm.h>
+#include <mbedtls/pk.h>
+#include <mbedtls/rsa.h>
+
+#include "crypto.h"
+
+static int _rng(void* arg, unsigned char* data, size_t size)
+{
+    (void)arg;
+    return myst_crypto_random(data, size) == 0 ? 0 : -1;
+}
+
+long myst_rsa_oaep_encrypt(
+    const char* pem_public_key,
+    const void* in,
+    size_t in_size,
+    void* out,
+    size_t out_size,
+    size_t* out_size_out)
+{
+    long ret = 0;
+    mbedtls_pk_context pk;
+    mbedtls_rsa_context* rsa;
+    size_t size;
+
+    mbedtls_pk_init(&pk);
+
+    if (!pem_public_key || !in || !out || !out_size_out)
+    {
+        ret = -EINVAL;
+        goto done;
+    }
+
+    *out_size_out = 0;
+
+    if (mbedtls_pk_parse_public_key(
+            &pk,
+            (const unsigned char*)pem_public_key,
+            strlen(pem_public_key) + 1) != 0 ||
+        mbedtls_pk_get_type(&pk) != MBEDTLS_PK_RSA)
+    {
+        ret = -EINVAL;
+        goto done;
+    }
+
+    rsa = mbedtls_pk_rsa(pk);
+    mbedtls_rsa_set_padding(rsa, MBEDTLS_RSA_PKCS_V21, MBEDTLS_MD_SHA256);
+
+    if ((size = mbedtls_rsa_get_len(rsa)) > out_size)
+    {
+        ret = -ERANGE;
+        goto done;
+    }
+
+    if (mbedtls_rsa_rsaes_oaep_encrypt(
+            rsa,
+            _rng,
+            NULL,
+            MBEDTLS_RSA_PUBLIC,
+            NULL,
+            0,
+            in_size,
+            in,
+            out) != 0)
+    {
+        ret = -EINVAL;
+        goto done;
+    }
+
+    *out_size_out = size;
+
+done:
+    mbedtls_pk_free(&pk);
+    return ret;
+}
+
+long myst_aes_gcm_encrypt(
+    const uint8_t key[32],
+    const uint8_t nonce[12],
+    const void* in,
+    size_t size,
+    void* out,
+    uint8_t tag[16])
+{
+    long ret = 0;
+    mbedtls_gcm_context gcm;
+
+    mbedtls_gcm_init(&gcm);
+
+    if (!key || !nonce || (size && (!in || !out)) || !tag)
+    {
+        ret = -EINVAL;
+        goto done;
+    }
+
+    if (mbedtls_gcm_setkey(&gcm, MBEDTLS_CIPHER_ID_AES, key, 256) != 0 ||
+        mbedtls_gcm_crypt_and_tag(
+            &gcm,
+            MBEDTLS_GCM_ENCRYPT,
+            size,
+            nonce,
+            12,
+            NULL,
+            0,
+            in,
+            out,
+            16,
+            tag) != 0)
+    {
+        ret = -EIO;
+        goto done;
+    }
+
+done:
+    mbedtls_gcm_free(&gcm);
+    return ret;
+}
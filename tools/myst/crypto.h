@@ -0,0 +1,33 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_MYST_CRYPTO_H
+#define _MYST_MYST_CRYPTO_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* The encryption that core dumps use, for both the SGX and Linux targets */
+
+/* fill data with random bytes; defined by each target */
+int myst_crypto_random(void* data, size_t size);
+
+/* wrap a core dump key to the operator's RSA public key */
+long myst_rsa_oaep_encrypt(
+    const char* pem_public_key,
+    const void* in,
+    size_t in_size,
+    void* out,
+    size_t out_size,
+    size_t* out_size_out);
+
+/* encrypt one record of a core dump */
+long myst_aes_gcm_encrypt(
+    const uint8_t key[32],
+    const uint8_t nonce[12],
+    const void* in,
+    size_t size,
+    void* out,
+    uint8_t tag[16]);
+
+#endif /* _MYST_MYST_CRYPTO_H */
@@ -4,25 +4,170 @@
 #include <openenclave/enclave.h>
 
 #include <errno.h>
+#include <limits.h>
 #include <myst/clock.h>
 #include <myst/syscall.h>
 #include <myst/syscallext.h>
 #include <stdio.h>
+#include <string.h>
+#include <sys/timex.h>
 
+#include "tee.h"
+
+/* what the host says about its clock when it does not say (16 seconds,
+ * the largest error NTP reports) */
+#define MAX_ERROR (16L * NANO_IN_SECOND)
+
+/* give up reading the host status after this many torn reads */
+#define MAX_STATUS_READS 16
+
+/* the largest TAI offset believed from the host (37 seconds in 2017) */
+#define MAX_TAI_OFFSET 1000
+
+#define DAY (86400L * NANO_IN_SECOND)
+
+/* leap seconds are smeared over the 24 hours around them (noon to noon) */
+#define SMEAR_WINDOW DAY
+
+typedef struct clock_status
+{
+    long realtime_offset;
+    long maxerror;
+    long esterror;
+    bool synced;
+    int leap;
+} clock_status_t;
+
+static struct clock_ctrl* _ctrl = NULL;
+static uint32_t _ctrl_version = 0;
+static int _source = MYST_CLOCK_SOURCE_REALTIME;
+static long _host_offset = 0;
+static long _slewed_at = 0;
+
+/* The realtime clock below is kept on a scale without leap seconds: UTC is
+ * that less the leap seconds since the start, and TAI is UTC plus the TAI
+ * offset. A leap second announced by the host is applied at the end of the
+ * UTC day (nanoseconds below are on the scale without leap seconds). */
+static long _tai_offset = 0;
+static bool _leap_smear = false;
+static bool _leap_smearing = false;
+static int _leap = 0;
+static long _leap_at = 0;
+static long _leap_total = 0;
+static long _leap_polled_at = 0;
 static long _realtime0 = 0;
 static long _monotime0 = 0;
 static volatile long* _monotime_now = 0;
 static long _realtime_delta = 0;
 static long enc_clock_res = 0;
+static uint64_t _tsc_hz = NANO_IN_SECOND;
+
+/* Check the header of the clock_ctrl that the host made, so that a host
+ * launcher that does not match the enclave image fails here rather than
+ * feeding the clock from the wrong fields */
+static int _check_header(const struct clock_ctrl* ctrl, uint32_t* version)
+{
+    const myst_shm_header_t* header = &ctrl->header;
+    uint32_t magic;
+    uint64_t size;
+    size_t min;
+
+    if (!myst_tee()->is_outside(header, sizeof(myst_shm_header_t)))
+        return -1;
+
+    // Read the header once, as the host may change it under us.
+    magic = header->magic;
+    *version = header->version;
+    size = header->size;
+
+    if (magic != MYST_CLOCK_CTRL_MAGIC)
+    {
+        fprintf(
+            stderr,
+            "clock_ctrl from the host has a bad magic number (0x%x): the "
+            "host launcher does not match the enclave image\n",
+            magic);
+        return -1;
+    }
+
+    // Accept the current layout and the one before it.
+    if (*version == MYST_CLOCK_CTRL_VERSION)
+        min = sizeof(struct clock_ctrl);
+    else if (*version == MYST_CLOCK_CTRL_VERSION - 1)
+        min = MYST_CLOCK_CTRL_V1_SIZE;
+    else
+    {
+        fprintf(
+            stderr,
+            "clock_ctrl from the host is version %u but the enclave supports "
+            "versions %u and %u: the host launcher does not match the "
+            "enclave image\n",
+            *version,
+            MYST_CLOCK_CTRL_VERSION - 1,
+            MYST_CLOCK_CTRL_VERSION);
+        return -1;
+    }
 
-int myst_setup_clock(struct clock_ctrl* ctrl)
+    if (size < min)
+    {
+        fprintf(
+            stderr,
+            "clock_ctrl from the host is too small for version %u "
+            "(%lu bytes)\n",
+            *version,
+            size);
+        return -1;
+    }
+
+    if (!myst_tee()->is_outside(ctrl, min))
+        return -1;
+
+    return 0;
+}
+
+int myst_setup_clock(
+    struct clock_ctrl* ctrl,
+    const myst_clock_config_t* config)
 {
     int ret = -1;
-    if (ctrl != NULL)
+    uint32_t version;
+
+    if (ctrl != NULL && config != NULL)
     {
-        if (!oe_is_outside_enclave(ctrl, sizeof(struct clock_ctrl)))
+        if (_check_header(ctrl, &version) != 0)
             return ret;
 
+        // The host must feed the clock that the configuration asked for.
+        if (ctrl->source != config->source)
+            goto done;
+
+        // Only version 2 publishes the offset that NTP follows.
+        if (version < 2 && config->source == MYST_CLOCK_SOURCE_NTP)
+        {
+            fprintf(
+                stderr,
+                "clock_ctrl from the host is version %u, which does not "
+                "support the NTP clock source\n",
+                version);
+            goto done;
+        }
+
+        _ctrl = ctrl;
+        _source = config->source;
+        _leap_smear = config->leap_smear;
+
+        // The configured TAI offset wins over whatever the host says.
+        if (config->tai_offset < 0 || config->tai_offset > MAX_TAI_OFFSET)
+            goto done;
+        else if (config->tai_offset)
+            _tai_offset = config->tai_offset;
+        else if (
+            version >= 2 && ctrl->tai_offset > 0 &&
+            ctrl->tai_offset <= MAX_TAI_OFFSET)
+        {
+            _tai_offset = ctrl->tai_offset;
+        }
+
         // Copy the starting values into enclave to isolate them
         // From attacks. Note the starting clocks don't account for
         // the time spent in entering the enclave.
@@ -41,6 +186,7 @@ int myst_setup_clock(struct clock_ctrl* ctrl)
         _monotime_now = &ctrl->now;
 
         enc_clock_res = (long)ctrl->interval;
+        _ctrl_version = version;
 
         ret = 0;
     }
@@ -73,7 +219,8 @@ static long _get_monotime()
     {
         // maintain monotonicity.
         // TODO: issue a warning. Host might be playing tricks.
-        return ++prev;
+        _check(__builtin_saddl_overflow(prev, 1, &prev));
+        return prev;
     }
 }
 
@@ -83,18 +230,251 @@ static long _get_boottime()
     return _get_monotime();
 }
 
-/* Return realtime clock in nanoseconds since the epoch */
-static long _get_realtime()
+static long _clamp_error(long error)
+{
+    return (error < 0 || error > MAX_ERROR) ? MAX_ERROR : error;
+}
+
+/* Read the status the host publishes, or fail if the host keeps changing
+ * it (as a malicious host might) */
+static bool _read_status(clock_status_t* status)
+{
+    // A version 1 host publishes no status.
+    if (_ctrl_version < 2)
+        return false;
+
+    for (size_t i = 0; i < MAX_STATUS_READS; i++)
+    {
+        unsigned long seq = __atomic_load_n(&_ctrl->seq, __ATOMIC_ACQUIRE);
+
+        if (seq & 1)
+            continue;
+
+        status->realtime_offset = _ctrl->realtime_offset;
+        status->maxerror = _clamp_error(_ctrl->maxerror);
+        status->esterror = _clamp_error(_ctrl->esterror);
+        status->synced = _ctrl->synced != 0;
+        status->leap = _ctrl->leap;
+
+        __atomic_thread_fence(__ATOMIC_ACQUIRE);
+
+        if (__atomic_load_n(&_ctrl->seq, __ATOMIC_RELAXED) == seq)
+            return true;
+    }
+
+    return false;
+}
+
+/* Return the part of the host realtime offset applied so far. With
+ * MYST_CLOCK_SOURCE_NTP this moves toward the offset the host publishes by
+ * at most 500 ppm of the monotonic time since the last move, so the host can
+ * neither step the realtime clock nor move it backward. */
+static long _get_host_offset(long monotime)
+{
+    clock_status_t status;
+    long elapsed;
+    long max;
+    long diff;
+
+    if (_source != MYST_CLOCK_SOURCE_NTP)
+        return 0;
+
+    if (_slewed_at == 0)
+        _slewed_at = _monotime0;
+
+    if ((elapsed = monotime - _slewed_at) <= 0)
+        return _host_offset;
+
+    _slewed_at = monotime;
+
+    // Hold the offset while the host clock is not synchronized.
+    if (!_read_status(&status) || !status.synced)
+        return _host_offset;
+
+    max = elapsed / MYST_CLOCK_MAX_SLEW_DIVISOR;
+
+    if (__builtin_ssubl_overflow(status.realtime_offset, _host_offset, &diff))
+        diff = status.realtime_offset > _host_offset ? LONG_MAX : LONG_MIN;
+
+    if (diff > max)
+        diff = max;
+    else if (diff < -max)
+        diff = -max;
+
+    _host_offset += diff;
+    return _host_offset;
+}
+
+/* Return the realtime clock at the given monotonic time */
+static long _realtime_at(long monotime)
 {
-    // Derive the realtime clock from the monotonic clock.
-    // Any adjustment to the system clock is invisible to the
-    // enclave application once it is launched.
-    long ret = _get_monotime() - _monotime0;
+    // Derive the realtime clock from the monotonic clock. Adjustments to
+    // the host clock are invisible to the enclave application once it is
+    // launched, except as slewed in by MYST_CLOCK_SOURCE_NTP.
+    long ret = monotime - _monotime0;
     _check(__builtin_saddl_overflow(ret, _realtime0, &ret));
     _check(__builtin_saddl_overflow(ret, _realtime_delta, &ret));
+    _check(__builtin_saddl_overflow(ret, _get_host_offset(monotime), &ret));
     return ret;
 }
 
+/* Whether the given UTC day (since the epoch) is January 1st or July 1st,
+ * the days that leap seconds may precede. This is civil_from_days() from
+ * http://howardhinnant.github.io/date_algorithms.html */
+static bool _is_leap_day(long days)
+{
+    const long z = days + 719468;
+    const long era = (z >= 0 ? z : z - 146096) / 146097;
+    const long doe = z - era * 146097;
+    const long yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    const long doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    const long mp = (5 * doy + 2) / 153;
+    const long day = doy - (153 * mp + 2) / 5 + 1;
+    const long month = mp < 10 ? mp + 3 : mp - 9;
+
+    return day == 1 && (month == 1 || month == 7);
+}
+
+/* Take note of a leap second that the host announces (once a second) */
+static void _poll_leap(long monotime, long realtime)
+{
+    clock_status_t status;
+    long utc;
+    long midnight;
+
+    if (_leap || !_ctrl)
+        return;
+
+    if (_leap_polled_at && monotime - _leap_polled_at < NANO_IN_SECOND)
+        return;
+
+    _leap_polled_at = monotime;
+
+    if (!_read_status(&status) || (status.leap != 1 && status.leap != -1))
+        return;
+
+    // The leap second is at the end of the current UTC day, which only
+    // ends a leap second early in June or December.
+    utc = realtime - _leap_total;
+
+    if (utc < 0 || !_is_leap_day(utc / DAY + 1))
+        return;
+
+    midnight = (utc / DAY + 1) * DAY;
+    _check(__builtin_saddl_overflow(midnight, _leap_total, &_leap_at));
+    _leap = status.leap;
+
+    // Too late to smear all of it: step (or hold) the clock instead
+    _leap_smearing = _leap_smear && realtime <= _leap_at - SMEAR_WINDOW / 2;
+}
+
+/* Return how far UTC is behind the given realtime because of the pending
+ * leap second (once it has passed it counts in _leap_total instead) */
+static long _leap_adjustment(long realtime)
+{
+    long elapsed;
+
+    if (!_leap)
+        return 0;
+
+    if (_leap_smearing)
+    {
+        // Slow down (or speed up) UTC by one second a day
+        if (realtime <= _leap_at - SMEAR_WINDOW / 2)
+            return 0;
+
+        elapsed = realtime - (_leap_at - SMEAR_WINDOW / 2);
+
+        if (elapsed < SMEAR_WINDOW)
+            return _leap * (elapsed / (SMEAR_WINDOW / NANO_IN_SECOND));
+    }
+    else
+    {
+        if (realtime < _leap_at)
+            return 0;
+
+        // Hold UTC for the inserted second (it never goes backward)
+        elapsed = realtime - _leap_at;
+
+        if (_leap > 0 && elapsed < NANO_IN_SECOND)
+            return elapsed;
+    }
+
+    // The leap second is over
+    _leap_total += _leap * NANO_IN_SECOND;
+    _tai_offset += _leap;
+    _leap = 0;
+    return 0;
+}
+
+/* Return the offset of UTC from the realtime scale without leap seconds */
+static long _utc_offset(long monotime, long realtime)
+{
+    long adjustment;
+
+    _poll_leap(monotime, realtime);
+    adjustment = _leap_adjustment(realtime);
+
+    return _leap_total + adjustment;
+}
+
+/* Return realtime clock in nanoseconds since the epoch */
+static long _get_realtime()
+{
+    long monotime = _get_monotime();
+    long ret = _realtime_at(monotime);
+    _check(__builtin_ssubl_overflow(ret, _utc_offset(monotime, ret), &ret));
+    return ret;
+}
+
+/* Return the TAI clock in nanoseconds since the epoch */
+static long _get_taitime()
+{
+    long monotime = _get_monotime();
+    long ret = _realtime_at(monotime);
+    long tai;
+
+    // TAI runs on without leap seconds, whatever UTC does around them
+    // (and a leap second moves _leap_total and _tai_offset together).
+    _utc_offset(monotime, ret);
+    _check(__builtin_ssubl_overflow(ret, _leap_total, &ret));
+    _check(__builtin_smull_overflow(_tai_offset, NANO_IN_SECOND, &tai));
+    _check(__builtin_saddl_overflow(ret, tai, &ret));
+    return ret;
+}
+
+/* Set the frequency of the emulated time-stamp counter */
+void myst_setup_tsc(uint64_t tsc_hz)
+{
+    /* the conversion below requires (NANO_IN_SECOND * tsc_hz) to fit */
+    if (tsc_hz > 0 && tsc_hz <= LONG_MAX / NANO_IN_SECOND)
+        _tsc_hz = tsc_hz;
+}
+
+/* Return an emulated time-stamp counter derived from the monotonic clock */
+uint64_t myst_get_tsc(void)
+{
+    static uint64_t prev = 0;
+    const uint64_t ns = (uint64_t)_get_monotime();
+    uint64_t scaled;
+    uint64_t old = __atomic_load_n(&prev, __ATOMIC_ACQUIRE);
+    uint64_t tsc;
+
+    scaled = (ns / NANO_IN_SECOND) * _tsc_hz;
+    scaled += (ns % NANO_IN_SECOND) * _tsc_hz / NANO_IN_SECOND;
+
+    // The monotonic clock is strictly increasing but scaling it down to a
+    // slower counter may not be. Every thread raises prev past the value it
+    // returns, so that no two calls return the same value or go backward.
+    do
+    {
+        tsc = scaled > old ? scaled : old + 1;
+    } while (!__atomic_compare_exchange_n(
+        &prev, &old, tsc, false, __ATOMIC_ACQ_REL, __ATOMIC_ACQUIRE));
+
+    return tsc;
+}
+
 long myst_tcall_clock_getres(clockid_t clk_id, struct timespec* res)
 {
     (void)clk_id;
@@ -126,6 +506,11 @@ long myst_tcall_clock_gettime(clockid_t clk_id, struct timespec* tp)
             nanoseconds = _get_boottime();
             break;
         }
+        case CLOCK_TAI:
+        {
+            nanoseconds = _get_taitime();
+            break;
+        }
         default:
             return -EINVAL;
     }
@@ -141,19 +526,109 @@ long myst_tcall_clock_settime(clockid_t clk_id, struct timespec* tp)
 {
     if (clk_id == CLOCK_REALTIME)
     {
-        long new_time = tp->tv_sec * NANO_IN_SECOND + tp->tv_nsec;
+        long new_time;
+        long new_delta;
+
+        if (__builtin_smull_overflow(tp->tv_sec, NANO_IN_SECOND, &new_time) ||
+            __builtin_saddl_overflow(new_time, tp->tv_nsec, &new_time))
+            return -EINVAL;
+
         long cur_time = (long)_get_realtime();
 
         if (new_time <= cur_time)
             return 0; // trying to set clock backward, make it no-op
 
-        if (_realtime_delta > _realtime_delta + (new_time - cur_time))
+        if (__builtin_saddl_overflow(
+                _realtime_delta, new_time - cur_time, &new_delta))
             return -EINVAL; // possible overflow, make it no-op
 
-        _realtime_delta += (new_time - cur_time);
+        _realtime_delta = new_delta;
         return 0;
     }
 
     // Clocks other than CLOCK_REALTIME are not settable
     return -EINVAL;
 }
+
+/* This overrides the weak version in libmystkernel.a */
+long myst_tcall_clock_adjtime(clockid_t clk_id, struct timex* tx)
+{
+    clock_status_t status;
+    long monotime;
+    long realtime;
+    long utc;
+    long pending = 0;
+    int state;
+
+    if (clk_id != CLOCK_REALTIME)
+        return -EOPNOTSUPP;
+
+    // The enclave clock cannot be disciplined from inside
+    if (tx->modes)
+        return -EPERM;
+
+    monotime = _get_monotime();
+    realtime = _realtime_at(monotime);
+    _check(__builtin_ssubl_overflow(
+        realtime, _utc_offset(monotime, realtime), &utc));
+
+    if (!_ctrl || !_read_status(&status))
+    {
+        status.realtime_offset = _host_offset;
+        status.maxerror = MAX_ERROR;
+        status.esterror = MAX_ERROR;
+        status.synced = false;
+    }
+
+    if (_source == MYST_CLOCK_SOURCE_NTP)
+    {
+        // The offset that is yet to be slewed in is an error too
+        if (__builtin_ssubl_overflow(
+                status.realtime_offset, _host_offset, &pending) ||
+            pending > MAX_ERROR || pending < -MAX_ERROR)
+        {
+            pending = pending < 0 ? -MAX_ERROR : MAX_ERROR;
+        }
+
+        status.maxerror += pending < 0 ? -pending : pending;
+        status.esterror += pending < 0 ? -pending : pending;
+    }
+    else
+    {
+        // The clock runs free from the start, drifting by up to 500 ppm
+        long drift = monotime - _monotime0;
+        status.maxerror += drift / MYST_CLOCK_MAX_SLEW_DIVISOR;
+    }
+
+    if (status.maxerror > MAX_ERROR)
+    {
+        status.maxerror = MAX_ERROR;
+        status.synced = false;
+    }
+
+    if (status.esterror > MAX_ERROR)
+        status.esterror = MAX_ERROR;
+
+    // Like Linux, report the leap second until it has been applied
+    if (!_leap)
+        state = TIME_OK;
+    else if (realtime < _leap_at)
+        state = _leap > 0 ? TIME_INS : TIME_DEL;
+    else
+        state = TIME_OOP;
+
+    memset(tx, 0, sizeof(struct timex));
+    tx->offset = pending / 1000;
+    tx->maxerror = status.maxerror / 1000;
+    tx->esterror = status.esterror / 1000;
+    tx->status = status.synced ? 0 : STA_UNSYNC;
+    tx->status |= _leap > 0 ? STA_INS : _leap < 0 ? STA_DEL : 0;
+    tx->precision = enc_clock_res > 1000 ? enc_clock_res / 1000 : 1;
+    tx->tolerance = 500L << 16; /* 500 ppm (scaled by 2^16) */
+    tx->time.tv_sec = utc / NANO_IN_SECOND;
+    tx->time.tv_usec = (utc % NANO_IN_SECOND) / 1000;
+    tx->tick = MICRO_IN_SECOND / 100; /* USER_HZ */
+    tx->tai = (int)_tai_offset;
+
+    return status.synced ? state : TIME_ERROR;
+}
@@ -0,0 +1,100 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <signal.h>
+#include <string.h>
+
+#include <openenclave/enclave.h>
+
+#include <myst/coredump.h>
+#include "../crypto.h"
+#include "coredump.h"
+#include "tee.h"
+
+/* set by the kernel when core dumps are enabled (see kernel/coredump.c) */
+static myst_crash_handler_t _crash_handler;
+
+long myst_set_crash_handler(myst_crash_handler_t handler)
+{
+    _crash_handler = handler;
+    return 0;
+}
+
+/* set by the kernel to raise SIGSEGV for faults in the stack guards */
+static myst_fault_handler_t _fault_handler;
+
+long myst_set_fault_handler(myst_fault_handler_t handler)
+{
+    _fault_handler = handler;
+    return 0;
+}
+
+bool myst_report_fault(oe_exception_record_t* er)
+{
+    /* the faulting address is only known where PF/GP capture is supported */
+    if (!_fault_handler || er->code != OE_EXCEPTION_PAGE_FAULT)
+        return false;
+
+    return (*_fault_handler)(
+        er->faulting_address, &er->context->rip, &er->context->rsp);
+}
+
+static int _exception_signum(uint32_t code)
+{
+    switch (code)
+    {
+        case OE_EXCEPTION_DIVIDE_BY_ZERO:
+        case OE_EXCEPTION_X87_FLOAT_POINT:
+        case OE_EXCEPTION_SIMD_FLOAT_POINT:
+            return SIGFPE;
+        case OE_EXCEPTION_BOUND_OUT_OF_RANGE:
+        case OE_EXCEPTION_PAGE_FAULT:
+        case OE_EXCEPTION_ACCESS_VIOLATION:
+            return SIGSEGV;
+        case OE_EXCEPTION_ILLEGAL_INSTRUCTION:
+            return SIGILL;
+        case OE_EXCEPTION_MISALIGNMENT:
+            return SIGBUS;
+        default:
+            /* breakpoints belong to the debugger */
+            return 0;
+    }
+}
+
+void myst_report_crash(const oe_exception_record_t* er)
+{
+    const oe_context_t* c = er->context;
+    myst_coredump_regs_t regs;
+    int signum;
+
+    if (!_crash_handler || !(signum = _exception_signum(er->code)))
+        return;
+
+    memset(&regs, 0, sizeof(regs));
+    regs.r15 = c->r15;
+    regs.r14 = c->r14;
+    regs.r13 = c->r13;
+    regs.r12 = c->r12;
+    regs.rbp = c->rbp;
+    regs.rbx = c->rbx;
+    regs.r11 = c->r11;
+    regs.r10 = c->r10;
+    regs.r9 = c->r9;
+    regs.r8 = c->r8;
+    regs.rax = c->rax;
+    regs.rcx = c->rcx;
+    regs.rdx = c->rdx;
+    regs.rsi = c->rsi;
+    regs.rdi = c->rdi;
+    regs.orig_rax = (uint64_t)-1;
+    regs.rip = c->rip;
+    regs.eflags = c->flags;
+    regs.rsp = c->rsp;
+
+    (*_crash_handler)(signum, &regs);
+}
+
+int myst_crypto_random(void* data, size_t size)
+{
+    return myst_tee()->random(data, size);
+}
@@ -0,0 +1,22 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_ENC_COREDUMP_H
+#define _MYST_ENC_COREDUMP_H
+
+#include <openenclave/enclave.h>
+
+#include <myst/coredump.h>
+#include <myst/stackguard.h>
+
+long myst_set_crash_handler(myst_crash_handler_t handler);
+
+long myst_set_fault_handler(myst_fault_handler_t handler);
+
+/* let the kernel handle a page fault in a stack guard (see stackguard.c) */
+bool myst_report_fault(oe_exception_record_t* er);
+
+/* pass an exception that nothing handles to the kernel's crash handler */
+void myst_report_crash(const oe_exception_record_t* er);
+
+#endif /* _MYST_ENC_COREDUMP_H */
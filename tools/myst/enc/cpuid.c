@@ -0,0 +1,277 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+#include <myst/defs.h>
+
+/*
+**==============================================================================
+**
+** CPUID virtualization:
+**
+** SGX enclaves cannot execute CPUID, so the exception handler asks the host
+** to execute it and then passes the result through myst_emulate_cpuid(). The
+** tables below describe how the host's answer is rewritten before the
+** application sees it:
+**
+**     _masks[]     - feature bits cleared unconditionally (features that
+**                    cannot work inside an enclave) or when the feature
+**                    group is listed under "DisabledCpuFeatures" in the
+**                    enclave configuration.
+**
+**     topology     - the processor count reported by leaves 0x1, 0x4, 0xB,
+**                    0x1F and 0x80000008 is rewritten to match the number of
+**                    threads the enclave was configured with.
+**
+**==============================================================================
+*/
+
+#define ANY_SUBLEAF 0xffffffff
+
+#define BIT(N) (1U << (N))
+
+enum
+{
+    EAX,
+    EBX,
+    ECX,
+    EDX
+};
+
+typedef struct cpuid_mask
+{
+    const char* feature; /* null for features that are always hidden */
+    uint32_t leaf;
+    uint32_t subleaf;
+    uint32_t reg;
+    uint32_t bits;
+} cpuid_mask_t;
+
+static const cpuid_mask_t _masks[] = {
+    /* MONITOR/MWAIT, VMX and SMX fault inside an enclave */
+    {NULL, 0x1, ANY_SUBLEAF, ECX, BIT(3) | BIT(5) | BIT(6)},
+
+    /* SGX and SGX launch control are not available to enclave code */
+    {NULL, 0x7, 0, EBX, BIT(2)},
+    {NULL, 0x7, 0, ECX, BIT(30)},
+    {NULL, 0x12, ANY_SUBLEAF, EAX, 0xffffffff},
+    {NULL, 0x12, ANY_SUBLEAF, EBX, 0xffffffff},
+    {NULL, 0x12, ANY_SUBLEAF, ECX, 0xffffffff},
+    {NULL, 0x12, ANY_SUBLEAF, EDX, 0xffffffff},
+
+    /* AVX-512 (F, DQ, IFMA, PF, ER, CD, BW, VL, VBMI, VBMI2, VNNI, BITALG,
+     * VPOPCNTDQ, 4VNNIW, 4FMAPS, VP2INTERSECT, FP16, BF16) and the opmask and
+     * ZMM state components */
+    {"avx512",
+     0x7,
+     0,
+     EBX,
+     BIT(16) | BIT(17) | BIT(21) | BIT(26) | BIT(27) | BIT(28) | BIT(30) |
+         BIT(31)},
+    {"avx512", 0x7, 0, ECX, BIT(1) | BIT(6) | BIT(11) | BIT(12) | BIT(14)},
+    {"avx512", 0x7, 0, EDX, BIT(2) | BIT(3) | BIT(8) | BIT(23)},
+    {"avx512", 0x7, 1, EAX, BIT(5)},
+    {"avx512", 0xD, 0, EAX, BIT(5) | BIT(6) | BIT(7)},
+
+    /* AVX, FMA, F16C and AVX2 (hiding AVX implies hiding AVX-512) */
+    {"avx", 0x1, ANY_SUBLEAF, ECX, BIT(12) | BIT(28) | BIT(29)},
+    {"avx", 0x7, 0, EBX, BIT(5)},
+    {"avx", 0xD, 0, EAX, BIT(2)},
+
+    /* TSX (HLE and RTM) */
+    {"tsx", 0x7, 0, EBX, BIT(4) | BIT(11)},
+
+    /* AMX (BF16, TILE, INT8) and the tile state components */
+    {"amx", 0x7, 0, EDX, BIT(22) | BIT(24) | BIT(25)},
+    {"amx", 0xD, 0, EAX, BIT(17) | BIT(18)},
+};
+
+/* features hidden along with another feature */
+static const struct
+{
+    const char* feature;
+    const char* implies;
+} _implied[] = {
+    {"avx", "avx512"},
+};
+
+/* one bit per _masks[] entry that is in effect */
+static uint64_t _active_masks;
+
+static size_t _num_cpus = 1;
+
+static bool _known_feature(const char* feature)
+{
+    for (size_t i = 0; i < MYST_COUNTOF(_masks); i++)
+    {
+        if (_masks[i].feature && strcmp(_masks[i].feature, feature) == 0)
+            return true;
+    }
+
+    return false;
+}
+
+static void _disable_feature(const char* feature)
+{
+    for (size_t i = 0; i < MYST_COUNTOF(_masks); i++)
+    {
+        if (_masks[i].feature && strcmp(_masks[i].feature, feature) == 0)
+            _active_masks |= (1ULL << i);
+    }
+
+    for (size_t i = 0; i < MYST_COUNTOF(_implied); i++)
+    {
+        if (strcmp(_implied[i].feature, feature) == 0)
+            _disable_feature(_implied[i].implies);
+    }
+}
+
+int myst_setup_cpuid(
+    const char* const* disabled_features,
+    size_t num_disabled_features,
+    size_t num_cpus)
+{
+    MYST_STATIC_ASSERT(MYST_COUNTOF(_masks) <= 64);
+
+    _active_masks = 0;
+
+    for (size_t i = 0; i < MYST_COUNTOF(_masks); i++)
+    {
+        if (!_masks[i].feature)
+            _active_masks |= (1ULL << i);
+    }
+
+    for (size_t i = 0; i < num_disabled_features; i++)
+    {
+        if (!_known_feature(disabled_features[i]))
+        {
+            fprintf(
+                stderr,
+                "unknown feature in DisabledCpuFeatures: %s\n",
+                disabled_features[i]);
+            return -1;
+        }
+
+        _disable_feature(disabled_features[i]);
+    }
+
+    _num_cpus = num_cpus ? num_cpus : 1;
+
+    return 0;
+}
+
+/* return the number of bits needed to represent x distinct IDs */
+static uint32_t _id_width(size_t x)
+{
+    uint32_t n = 0;
+
+    while ((1UL << n) < x)
+        n++;
+
+    return n;
+}
+
+static void _fix_topology(uint32_t leaf, uint32_t subleaf, uint32_t regs[4])
+{
+    const uint32_t n = (uint32_t)_num_cpus;
+
+    switch (leaf)
+    {
+        case 0x1:
+        {
+            /* logical processor count in EBX[23:16]; APIC ID 0 in EBX[31:24]
+             * (the APIC ID is not the vCPU that getcpu() reports) */
+            regs[EBX] &= 0x0000ffff;
+            regs[EBX] |= (n > 0xff ? 0xff : n) << 16;
+
+            /* HTT: the count in EBX[23:16] is valid */
+            if (n > 1)
+                regs[EDX] |= BIT(28);
+            else
+                regs[EDX] &= ~BIT(28);
+            break;
+        }
+        case 0x4:
+        {
+            /* cache type 0 means no more caches */
+            if ((regs[EAX] & 0x1f) == 0)
+                break;
+
+            /* one core per logical processor; only L3 and beyond shared */
+            const uint32_t cores = (n > 64 ? 64 : n) - 1;
+            const uint32_t level = (regs[EAX] >> 5) & 0x7;
+            uint32_t sharing = 0;
+
+            if (level >= 3)
+                sharing = (n > 4096 ? 4096 : n) - 1;
+
+            regs[EAX] &= 0x00003fff;
+            regs[EAX] |= (cores << 26) | (sharing << 14);
+            break;
+        }
+        case 0xB:
+        case 0x1F:
+        {
+            /* leave the leaf alone if the host does not implement it */
+            if (subleaf < 2 && ((regs[ECX] >> 8) & 0xff) == 0)
+                break;
+
+            /* level 0 is SMT (one thread per core), level 1 is core */
+            if (subleaf == 0)
+            {
+                regs[EAX] = 0;
+                regs[EBX] = 1;
+                regs[ECX] = (1 << 8) | subleaf;
+            }
+            else if (subleaf == 1)
+            {
+                regs[EAX] = _id_width(n);
+                regs[EBX] = n & 0xffff;
+                regs[ECX] = (2 << 8) | subleaf;
+            }
+            else
+            {
+                regs[EAX] = 0;
+                regs[EBX] = 0;
+                regs[ECX] = subleaf & 0xff;
+            }
+
+            /* x2APIC ID */
+            regs[EDX] = 0;
+            break;
+        }
+        case 0x80000008:
+        {
+            /* AMD: number of physical cores - 1 in ECX[7:0] */
+            regs[ECX] &= 0xffffff00;
+            regs[ECX] |= (n > 0x100 ? 0x100 : n) - 1;
+            break;
+        }
+    }
+}
+
+/* rewrite the result of CPUID(leaf, subleaf) as executed by the host */
+void myst_emulate_cpuid(uint32_t leaf, uint32_t subleaf, uint32_t regs[4])
+{
+    for (size_t i = 0; i < MYST_COUNTOF(_masks); i++)
+    {
+        const cpuid_mask_t* m = &_masks[i];
+
+        if (!(_active_masks & (1ULL << i)))
+            continue;
+
+        if (m->leaf != leaf)
+            continue;
+
+        if (m->subleaf != ANY_SUBLEAF && m->subleaf != subleaf)
+            continue;
+
+        regs[m->reg] &= ~m->bits;
+    }
+
+    _fix_topology(leaf, subleaf, regs);
+}
@@ -0,0 +1,141 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdint.h>
+#include <stdio.h>
+
+#include "edmm.h"
+#include "myst_t.h"
+
+/*
+**==============================================================================
+**
+** SGX2 dynamic memory (EDMM):
+**
+** With "CommittedMemorySize", the host leaves the lower pages of the mman
+** region out of the enclave image (see tools/myst/host/regions.c). The first
+** access to one of them faults: the SGX driver adds a zeroed page (EAUG) in
+** the PENDING state and the access faults again, this time into the enclave.
+** The exception handler then accepts the page (EACCEPT) and extends it to
+** read-write-execute like the rest of the region (EMODPE), and the access is
+** retried. Which pages are in the image is part of MRENCLAVE, so the host
+** cannot pass off pages of its own as already committed.
+**
+** The kernel also makes the guard pages below the thread stacks
+** inaccessible in the host page tables (see kernel/stackguard.c), which is
+** only worth doing here since SGX1 page faults do not report the address.
+** The host may ignore this, which only loses the guard: the enclave still
+** treats a fault that the EPCM did not raise as a crash.
+**
+** Pages are never given back to the host: that takes the driver's
+** SGX_IOC_ENCLAVE_REMOVE_PAGES and SGX_IOC_ENCLAVE_RESTRICT_PERMISSIONS
+** ioctls on the enclave file descriptor, which Open Enclave does not expose.
+**
+**==============================================================================
+*/
+
+/* ENCLU leaf functions */
+#define ENCLU_EACCEPT 5
+#define ENCLU_EMODPE 6
+
+/* SECINFO flags */
+#define SECINFO_R 0x01
+#define SECINFO_W 0x02
+#define SECINFO_X 0x04
+#define SECINFO_PENDING 0x08
+#define SECINFO_PT_REG (2 << 8)
+
+/* the page fault error code bit for an access that the EPCM denied */
+#define PF_SGX 0x8000
+
+/* EACCEPT fails with this when another thread accepted the page first */
+#define SGX_PAGE_ATTRIBUTES_MISMATCH 19
+
+typedef struct secinfo
+{
+    uint64_t flags;
+    uint64_t reserved[7];
+} __attribute__((aligned(64))) secinfo_t;
+
+static uintptr_t _start;
+static uintptr_t _end;
+
+volatile uint64_t myst_edmm_page_faults;
+
+static uint64_t _enclu(uint64_t leaf, const secinfo_t* secinfo, uintptr_t addr)
+{
+    uint64_t rax = leaf;
+
+    __asm__ volatile("enclu"
+                     : "+a"(rax)
+                     : "b"(secinfo), "c"(addr)
+                     : "memory", "cc");
+
+    return rax;
+}
+
+int myst_edmm_setup(void* addr, size_t size)
+{
+    long retval;
+
+    if (!addr || !size || ((uintptr_t)addr | size) & (OE_PAGE_SIZE - 1))
+        return -EINVAL;
+
+    /* the driver only adds pages where the host mapping allows access */
+    if (myst_edmm_map_ocall(&retval, (uint64_t)addr, size) != OE_OK ||
+        retval != 0)
+    {
+        fprintf(stderr, "myst_edmm_map_ocall() failed\n");
+        return -EINVAL;
+    }
+
+    _start = (uintptr_t)addr;
+    _end = (uintptr_t)addr + size;
+
+    return 0;
+}
+
+bool myst_edmm_handle_fault(const oe_exception_record_t* er)
+{
+    const uintptr_t mask = OE_PAGE_SIZE - 1;
+    const uintptr_t page = er->faulting_address & ~mask;
+    const secinfo_t pending = {SECINFO_R | SECINFO_W | SECINFO_PENDING |
+                               SECINFO_PT_REG};
+    const secinfo_t rwx = {SECINFO_R | SECINFO_W | SECINFO_X};
+    uint64_t r;
+
+    if (er->code != OE_EXCEPTION_PAGE_FAULT || page < _start || page >= _end)
+        return false;
+
+    /* the host page tables denied the access (as for a stack guard) */
+    if (!(er->error_code & PF_SGX))
+        return false;
+
+    if ((r = _enclu(ENCLU_EACCEPT, &pending, page)) != 0)
+        return r == SGX_PAGE_ATTRIBUTES_MISMATCH;
+
+    /* EAUG adds read-write pages; the region is mapped executable too */
+    _enclu(ENCLU_EMODPE, &rwx, page);
+
+    __atomic_fetch_add(&myst_edmm_page_faults, 1, __ATOMIC_RELAXED);
+
+    return true;
+}
+
+long myst_edmm_protect(void* addr, size_t length, int prot)
+{
+    long retval;
+
+    if (_start == _end)
+        return -ENOTSUP;
+
+    if (((uintptr_t)addr | length) & (OE_PAGE_SIZE - 1) ||
+        !oe_is_within_enclave(addr, length))
+        return -EINVAL;
+
+    if (myst_edmm_protect_ocall(&retval, (uint64_t)addr, length, prot) != OE_OK)
+        return -EINVAL;
+
+    return retval;
+}
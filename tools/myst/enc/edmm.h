@@ -0,0 +1,28 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_ENC_EDMM_H
+#define _MYST_ENC_EDMM_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#include <openenclave/enclave.h>
+
+/* commit the pages in [addr, addr + size) on first access (SGX2) */
+int myst_edmm_setup(void* addr, size_t size);
+
+/* accept a page that the driver added on a page fault; returns true if the
+ * faulting instruction can be retried */
+bool myst_edmm_handle_fault(const oe_exception_record_t* er);
+
+/* set the host protection of pages of the enclave (such as the guards below
+ * the thread stacks); fails with -ENOTSUP unless the enclave uses EDMM,
+ * whose page faults report the faulting address */
+long myst_edmm_protect(void* addr, size_t length, int prot);
+
+/* the number of pages committed on a page fault */
+extern volatile uint64_t myst_edmm_page_faults;
+
+#endif /* _MYST_ENC_EDMM_H */
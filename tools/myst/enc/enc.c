@@ -30,6 +30,10 @@
 
 #include "../config.h"
 #include "../shared.h"
+#include "coredump.h"
+#include "edmm.h"
+#include "hostpoll.h"
+#include "tee.h"
 #include "myst_t.h"
 
 #define IRETFRAME_Rip 0
@@ -44,31 +48,77 @@ static size_t _get_num_tcs(void)
     return oe_enclave_properties_sgx.header.size_settings.num_tcs;
 }
 
-int myst_setup_clock(struct clock_ctrl*);
+int myst_setup_clock(struct clock_ctrl*, int source);
+
+void myst_setup_tsc(uint64_t tsc_hz);
+
+uint64_t myst_get_tsc(void);
+
+int myst_setup_cpuid(
+    const char* const* disabled_features,
+    size_t num_disabled_features,
+    size_t num_cpus);
+
+void myst_emulate_cpuid(uint32_t leaf, uint32_t subleaf, uint32_t regs[4]);
+
+long myst_handle_tcall(long n, long params[6]);
+
+/* Get the host's TSC frequency from CPUID (or 0 if not reported) */
+static uint64_t _get_tsc_hz(void)
+{
+    uint32_t rax = 0;
+    uint32_t rbx = 0;
+    uint32_t rcx = 0;
+    uint32_t rdx = 0;
+    uint32_t max_leaf;
+
+    myst_cpuid_ocall(0, 0, &rax, &rbx, &rcx, &rdx);
+    max_leaf = rax;
+
+    /* TSC/crystal ratio (EBX/EAX) times the crystal frequency (ECX) */
+    if (max_leaf >= 0x15)
+    {
+        myst_cpuid_ocall(0x15, 0, &rax, &rbx, &rcx, &rdx);
+
+        if (rax && rbx && rcx)
+            return (uint64_t)rcx * rbx / rax;
+    }
+
+    /* processor base frequency in MHz */
+    if (max_leaf >= 0x16)
+    {
+        myst_cpuid_ocall(0x16, 0, &rax, &rbx, &rcx, &rdx);
+
+        if (rax & 0xffff)
+            return (uint64_t)(rax & 0xffff) * 1000000;
+    }
+
+    return 0;
+}
 
 /* Handle illegal SGX instructions */
 static uint64_t _vectored_handler(oe_exception_record_t* er)
 {
     const uint16_t RDTSC_OPCODE = 0x310F;
+    const uint16_t RDTSCP_OPCODE = 0x010F; /* followed by 0xF9 */
     const uint16_t CPUID_OPCODE = 0xA20F;
     const uint16_t IRETQ_OPCODE = 0xCF48;
     const uint16_t opcode = *((uint16_t*)er->context->rip);
 
+    /* a page of the mman region that is committed on first access */
+    if (myst_edmm_handle_fault(er))
+        return OE_EXCEPTION_CONTINUE_EXECUTION;
+
+    /* an overflow into an inaccessible stack guard raises SIGSEGV */
+    if (myst_report_fault(er))
+        return OE_EXCEPTION_CONTINUE_EXECUTION;
+
     if (er->code == OE_EXCEPTION_ILLEGAL_INSTRUCTION && opcode == RDTSC_OPCODE)
     {
-        uint32_t rax = 0;
-        uint32_t rdx = 0;
-
-        /* Ask host to execute RDTSC instruction */
-        if (myst_rdtsc_ocall(&rax, &rdx) != OE_OK)
-        {
-            fprintf(stderr, "myst_rdtsc_ocall() failed\n");
-            assert(false);
-            return OE_EXCEPTION_CONTINUE_SEARCH;
-        }
+        const uint64_t tsc = myst_get_tsc();
 
-        er->context->rax = rax;
-        er->context->rdx = rdx;
+        er->context->rax = (uint32_t)tsc;
+        er->context->rdx = (uint32_t)(tsc >> 32);
 
         /* Skip over the illegal instruction. */
         er->context->rip += 2;
@@ -76,28 +126,42 @@ static uint64_t _vectored_handler(oe_exception_record_t* er)
         return OE_EXCEPTION_CONTINUE_EXECUTION;
     }
 
+    if (er->code == OE_EXCEPTION_ILLEGAL_INSTRUCTION &&
+        opcode == RDTSCP_OPCODE && ((uint8_t*)er->context->rip)[2] == 0xF9)
+    {
+        const uint64_t tsc = myst_get_tsc();
+
+        er->context->rax = (uint32_t)tsc;
+        er->context->rdx = (uint32_t)(tsc >> 32);
+
+        /* IA32_TSC_AUX holds the CPU number, which is always 0 */
+        er->context->rcx = 0;
+
+        /* Skip over the illegal instruction. */
+        er->context->rip += 3;
+
+        return OE_EXCEPTION_CONTINUE_EXECUTION;
+    }
+
     if (er->code == OE_EXCEPTION_ILLEGAL_INSTRUCTION && opcode == CPUID_OPCODE)
     {
-        uint32_t rax = 0xaa;
-        uint32_t rbx = 0xbb;
-        uint32_t rcx = 0xcc;
-        uint32_t rdx = 0xdd;
+        const uint32_t leaf = (uint32_t)er->context->rax;
+        const uint32_t subleaf = (uint32_t)er->context->rcx;
+        uint32_t regs[4] = {0xaa, 0xbb, 0xcc, 0xdd};
 
-        if (er->context->rax != 0xff)
+        if (leaf != 0xff)
         {
             myst_cpuid_ocall(
-                (uint32_t)er->context->rax, /* leaf */
-                (uint32_t)er->context->rcx, /* subleaf */
-                &rax,
-                &rbx,
-                &rcx,
-                &rdx);
+                leaf, subleaf, &regs[0], &regs[1], &regs[2], &regs[3]);
+
+            /* mask features and topology the enclave cannot deliver */
+            myst_emulate_cpuid(leaf, subleaf, regs);
         }
 
-        er->context->rax = rax;
-        er->context->rbx = rbx;
-        er->context->rcx = rcx;
-        er->context->rdx = rdx;
+        er->context->rax = regs[0];
+        er->context->rbx = regs[1];
+        er->context->rcx = regs[2];
+        er->context->rdx = regs[3];
 
         return OE_EXCEPTION_CONTINUE_EXECUTION;
     }
@@ -113,6 +177,9 @@ static uint64_t _vectored_handler(oe_exception_record_t* er)
         return OE_EXCEPTION_CONTINUE_EXECUTION;
     }
 
+    /* the exception kills the enclave: write a core dump if configured */
+    myst_report_crash(er);
+
     return OE_EXCEPTION_CONTINUE_SEARCH;
 }
 
@@ -132,38 +199,151 @@ static bool _is_allowed_env_variable(
     return false;
 }
 
-const void* __oe_get_enclave_base(void);
-size_t __oe_get_enclave_size(void);
+/* match a string against LaunchOverrides patterns (a trailing '*' in a
+ * pattern matches any suffix); len is the number of characters to match */
+static bool _is_overridable(
+    char** patterns,
+    size_t count,
+    const char* str,
+    size_t len)
+{
+    for (size_t i = 0; i < count; i++)
+    {
+        const char* pattern = patterns[i];
+        size_t n = strlen(pattern);
 
-volatile int myst_enter_ecall_lock = 0;
+        if (n > 0 && pattern[n - 1] == '*')
+        {
+            if (len >= n - 1 && strncmp(str, pattern, n - 1) == 0)
+                return true;
+        }
+        else if (len == n && strncmp(str, pattern, n) == 0)
+        {
+            return true;
+        }
+    }
+
+    return false;
+}
 
-/* return 0 if OE is in SGX debug mode (else return -1) */
-static int _test_oe_debug_mode(void)
+/* append the host arguments after argv[0], which must all be allowed by
+ * LaunchOverrides.ApplicationParameters */
+static int _append_host_parameters(
+    const config_parsed_data_t* config,
+    myst_args_t* args,
+    const void* argv_data,
+    size_t argv_size)
 {
     int ret = -1;
-    uint8_t* buf = NULL;
-    size_t buf_size;
-    oe_report_t report;
+    myst_args_t tmp = {0};
 
-    if (oe_get_report_v2(0, NULL, 0, NULL, 0, &buf, &buf_size) != OE_OK)
+    if (myst_args_unpack(&tmp, argv_data, argv_size) != 0)
         goto done;
 
-    if (oe_parse_report(buf, buf_size, &report) != OE_OK)
-        goto done;
+    for (size_t i = 1; i < tmp.size; i++)
+    {
+        const char* arg = tmp.data[i];
 
-    if (!(report.identity.attributes & OE_REPORT_ATTRIBUTES_DEBUG))
-        goto done;
+        if (!_is_overridable(
+                config->overridable_application_parameters,
+                config->overridable_application_parameters_count,
+                arg,
+                strlen(arg)))
+        {
+            fprintf(
+                stderr,
+                "argument \"%s\" is not allowed by "
+                "LaunchOverrides.ApplicationParameters\n",
+                arg);
+            goto done;
+        }
+
+        if (myst_args_append1(args, arg) != 0)
+            goto done;
+    }
 
     ret = 0;
 
 done:
 
-    if (buf)
-        oe_free_report(buf);
+    if (tmp.data)
+        free(tmp.data);
 
     return ret;
 }
 
+/* set a host environment variable allowed by LaunchOverrides, replacing the
+ * enclave definition of the same name (if any); returns 1 if not allowed */
+static int _override_env_variable(
+    const config_parsed_data_t* config,
+    myst_args_t* env,
+    const char* var)
+{
+    const char* eq = strchr(var, '=');
+    size_t len;
+
+    if (!eq)
+        return 1;
+
+    len = (size_t)(eq - var);
+
+    if (!_is_overridable(
+            config->overridable_environment_variables,
+            config->overridable_environment_variables_count,
+            var,
+            len))
+    {
+        return 1;
+    }
+
+    for (size_t i = 0; i < env->size; i++)
+    {
+        if (strncmp(env->data[i], var, len + 1) == 0)
+        {
+            env->data[i] = var;
+            return 0;
+        }
+    }
+
+    return myst_args_append1(env, var);
+}
+
+const void* __oe_get_enclave_base(void);
+size_t __oe_get_enclave_size(void);
+
+volatile int myst_enter_ecall_lock = 0;
+
+/* The kernel's tcalls: those the enclave implements itself (see
+ * myst_handle_tcall) are handled here and the rest go to the SGX target.
+ */
+static long _tcall(long n, long params[6])
+{
+    switch (n)
+    {
+        case MYST_TCALL_EXPORT_COVERAGE:
+        case SYS_setpriority:
+        case MYST_TCALL_WRITE_LOG:
+        case MYST_TCALL_KEY_RELEASE:
+        case MYST_TCALL_UNSEAL_SECRET:
+        case MYST_TCALL_ATTESTED_HTTPS_GET:
+        case MYST_TCALL_SET_CRASH_HANDLER:
+        case MYST_TCALL_RSA_OAEP_ENCRYPT:
+        case MYST_TCALL_AES_GCM_ENCRYPT:
+        case MYST_TCALL_CLOCK_ADJTIME:
+        case MYST_TCALL_GET_BLOCK_DEVICE_SIZE:
+        case MYST_TCALL_SYNC_BLOCK_DEVICE:
+        case MYST_TCALL_MPROTECT:
+        case MYST_TCALL_SET_FAULT_HANDLER:
+        {
+            return myst_handle_tcall(n, params);
+        }
+        default:
+        {
+            return myst_tcall(n, params);
+        }
+    }
+}
+
 int myst_enter_ecall(
     struct myst_options* options,
     struct myst_shm* shared_memory,
@@ -196,9 +376,11 @@ int myst_enter_ecall(
     size_t archive_size;
     const void* config_data;
     size_t config_size;
+    myst_clock_config_t clock_config;
     bool trace_errors = false;
     bool trace_syscalls = false;
     bool export_ramfs = false;
+    bool export_coverage = false;
     const char* rootfs = NULL;
     config_parsed_data_t parsed_config = {0};
     unsigned char have_config = 0;
@@ -265,6 +447,14 @@ int myst_enter_ecall(
         {
             goto done;
         }
+
+        /* the signed parameters may be followed by allowed host arguments */
+        if (parsed_config.overridable_application_parameters_count &&
+            _append_host_parameters(
+                &parsed_config, &args, argv_data, argv_size) != 0)
+        {
+            goto done;
+        }
     }
     else
     {
@@ -287,20 +477,30 @@ int myst_enter_ecall(
             goto done;
         }
 
-        // now include host-side environment variables that are allowed
-        if (parsed_config.host_environment_variables &&
-            parsed_config.host_environment_variables_count)
+        // now include host-side environment variables that are allowed,
+        // letting those in LaunchOverrides replace the enclave definitions
+        if (parsed_config.host_environment_variables_count ||
+            parsed_config.overridable_environment_variables_count)
         {
-            myst_args_t tmp;
+            myst_args_t tmp = {0};
 
             if (myst_args_unpack(&tmp, envp_data, envp_size) != 0)
                 goto done;
 
             for (size_t i = 0; i < tmp.size; i++)
             {
-                if (_is_allowed_env_variable(&parsed_config, tmp.data[i]))
+                const char* var = tmp.data[i];
+                int r = _override_env_variable(&parsed_config, &env, var);
+
+                if (r < 0)
+                {
+                    free(tmp.data);
+                    goto done;
+                }
+
+                if (r == 1 && _is_allowed_env_variable(&parsed_config, var))
                 {
-                    if (myst_args_append1(&env, tmp.data[i]) != 0)
+                    if (myst_args_append1(&env, var) != 0)
                     {
                         free(tmp.data);
                         goto done;
@@ -350,6 +550,7 @@ int myst_enter_ecall(
         trace_errors = options->trace_errors;
         trace_syscalls = options->trace_syscalls;
         export_ramfs = options->export_ramfs;
+        export_coverage = options->export_coverage;
 
         if (strlen(options->rootfs) >= PATH_MAX)
         {
@@ -360,6 +561,16 @@ int myst_enter_ecall(
         rootfs = options->rootfs;
     }
 
+    /* Setup CPUID emulation before any CPUID can be trapped */
+    if (myst_setup_cpuid(
+            (const char* const*)parsed_config.disabled_cpu_features,
+            parsed_config.disabled_cpu_features_count,
+            _get_num_tcs()) != 0)
+    {
+        fprintf(stderr, "myst_setup_cpuid() failed\n");
+        goto done;
+    }
+
     /* Setup the vectored exception handler */
     if (oe_add_vectored_exception_handler(true, _vectored_handler) != OE_OK)
     {
@@ -367,12 +578,26 @@ int myst_enter_ecall(
         assert(0);
     }
 
-    if (myst_setup_clock(shared_memory->clock))
+    clock_config.source = parsed_config.host_clock_source;
+    clock_config.tai_offset = parsed_config.tai_offset;
+    clock_config.leap_smear = parsed_config.leap_second_smear;
+
+    if (myst_setup_clock(shared_memory->clock, &clock_config))
     {
         fprintf(stderr, "myst_setup_clock() failed\n");
         assert(0);
     }
 
+    if (myst_setup_hostpoll(
+            shared_memory->poll_events, shared_memory->poll_nfds) != 0)
+    {
+        fprintf(stderr, "myst_setup_hostpoll() failed\n");
+        assert(0);
+    }
+
+    /* RDTSC is emulated from the monotonic clock at the host TSC rate */
+    myst_setup_tsc(_get_tsc_hz());
+
     /* Get the mman region */
     void* mman_data;
     size_t mman_size;
@@ -389,6 +614,27 @@ int myst_enter_ecall(
         mman_size = region.size;
     }
 
+    /* Commit the pages left out of the enclave image on first access */
+    size_t mman_pending_size = 0;
+    {
+        /* the region starts and ends with a guard page */
+        const size_t pages = mman_size / OE_PAGE_SIZE - 2;
+        const size_t committed = parsed_config.committed_pages;
+
+        if (committed && committed < pages)
+        {
+            mman_pending_size = (pages - committed) * OE_PAGE_SIZE;
+
+            void* addr = (uint8_t*)mman_data + OE_PAGE_SIZE;
+
+            if (myst_edmm_setup(addr, mman_pending_size) != 0)
+            {
+                fprintf(stderr, "myst_edmm_setup() failed\n");
+                assert(0);
+            }
+        }
+    }
+
     /* Get the rootfs region */
     {
         oe_region_t region;
@@ -568,8 +814,64 @@ int myst_enter_ecall(
         kargs.envp = env.data;
         kargs.cwd = cwd;
         kargs.hostname = hostname;
+        kargs.uname_release = parsed_config.uname_release;
+        kargs.uname_version = parsed_config.uname_version;
+        kargs.uname_machine = parsed_config.uname_machine;
+        kargs.dns_servers = (const char**)parsed_config.dns_servers;
+        kargs.num_dns_servers = parsed_config.dns_servers_count;
+        kargs.console_log_stdout = parsed_config.console_log_stdout;
+        kargs.console_log_stderr = parsed_config.console_log_stderr;
+        kargs.console_log_timestamps = parsed_config.console_log_timestamps;
+        kargs.console_log_max_size = parsed_config.console_log_max_size;
+        kargs.console_log_max_files = parsed_config.console_log_max_files;
+        kargs.console_log_only = parsed_config.console_log_only;
+        kargs.audit_log = parsed_config.audit_log;
+        kargs.audit_log_path = parsed_config.audit_log_path;
+        kargs.rootfs_overlay = parsed_config.rootfs_overlay;
+        kargs.rootfs_layers.store = parsed_config.rootfs_layer_store;
+        kargs.rootfs_layers.layers = (const char**)parsed_config.rootfs_layers;
+        kargs.rootfs_layers.num_layers = parsed_config.rootfs_layers_count;
+        kargs.syscall_policy = parsed_config.syscall_policy;
+        kargs.oom_policy = parsed_config.oom_policy;
+        kargs.mem_low_watermark = parsed_config.mem_low_watermark;
+        kargs.debug_malloc = parsed_config.debug_malloc;
+        kargs.debug_malloc_quarantine = parsed_config.debug_malloc_quarantine;
+        kargs.debug_signal_safety = parsed_config.debug_signal_safety;
+        kargs.wx.enabled = parsed_config.write_xor_execute;
+        kargs.wx.allow = (const char**)parsed_config.write_xor_execute_allow;
+        kargs.wx.num_allow = parsed_config.write_xor_execute_allow_count;
+        kargs.quote_cache_lifetime = parsed_config.quote_cache_lifetime;
+        kargs.cpu_time_limit = parsed_config.cpu_time_limit;
+        kargs.cpu_time_hard_limit = parsed_config.cpu_time_hard_limit;
+        kargs.wall_time_limit = parsed_config.wall_time_limit;
+        kargs.key_release = parsed_config.key_release;
+        kargs.secrets = parsed_config.secrets;
+        kargs.coredump = parsed_config.coredump;
+        kargs.services = parsed_config.services;
+        kargs.host_imports = parsed_config.host_imports;
+        kargs.disks = parsed_config.disks;
+        kargs.mount_policy = parsed_config.mount_policy;
+        kargs.shutdown.grace_period = parsed_config.shutdown_grace_period;
+
+        /* the host sets this flag when it receives SIGTERM */
+        if (shared_memory->shutdown &&
+            myst_tee()->is_outside(
+                (const void*)shared_memory->shutdown, sizeof(int)))
+        {
+            kargs.shutdown.request = shared_memory->shutdown;
+        }
+
+        /* the kernel checks the header of the ring */
+        if (shared_memory->console &&
+            myst_tee()->is_outside(
+                shared_memory->console, sizeof(myst_console_ring_t)))
+        {
+            kargs.console_ring = shared_memory->console;
+        }
         kargs.mman_data = mman_data;
         kargs.mman_size = mman_size;
+        kargs.mman_pending_size = mman_pending_size;
+        kargs.page_faults = &myst_edmm_page_faults;
         kargs.rootfs_data = (void*)rootfs_data;
         kargs.rootfs_size = rootfs_size;
         kargs.archive_data = (void*)archive_data;
@@ -577,17 +879,16 @@ int myst_enter_ecall(
         kargs.crt_data = (void*)crt_data;
         kargs.crt_size = crt_size;
         kargs.max_threads = _get_num_tcs();
+        kargs.num_cpus = _get_num_tcs();
         kargs.trace_errors = trace_errors;
         kargs.trace_syscalls = trace_syscalls;
         kargs.export_ramfs = export_ramfs;
-        kargs.tcall = myst_tcall;
+        kargs.export_coverage = export_coverage;
+        kargs.tcall = _tcall;
         kargs.event = event;
 
-        /* determine whether in SGX debug mode */
-        if (_test_oe_debug_mode() == 0)
-            kargs.tee_debug_mode = true;
-        else
-            kargs.tee_debug_mode = false;
+        /* determine whether in TEE debug mode */
+        kargs.tee_debug_mode = myst_tee()->debug_mode();
 
         if (rootfs)
             myst_strlcpy(kargs.rootfs, rootfs, sizeof(kargs.rootfs));
@@ -749,6 +1050,35 @@ long myst_tcall_export_file(const char* path, const void* data, size_t size)
     return retval;
 }
 
+long myst_tcall_export_coverage(
+    const char* path,
+    const void* data,
+    size_t size)
+{
+    long retval = -1;
+
+    if (myst_export_coverage_ocall(&retval, path, data, size) != OE_OK)
+        return -EINVAL;
+
+    return retval;
+}
+
+long myst_tcall_write_log(
+    const char* path,
+    const void* buf,
+    size_t count,
+    size_t max_size,
+    size_t max_files)
+{
+    long retval = -1;
+
+    if (myst_write_log_ocall(
+            &retval, path, buf, count, max_size, max_files) != OE_OK)
+        return -EINVAL;
+
+    return retval;
+}
+
 long myst_tcall_poll_wake(void)
 {
     long r;
@@ -813,6 +1143,26 @@ int myst_tcall_write_block_device(
     return retval;
 }
 
+int myst_tcall_get_block_device_size(int blkdev, uint64_t* size)
+{
+    int retval;
+
+    if (myst_get_block_device_size_ocall(&retval, blkdev, size) != OE_OK)
+        return -EINVAL;
+
+    return retval;
+}
+
+int myst_tcall_sync_block_device(int blkdev)
+{
+    int retval;
+
+    if (myst_sync_block_device_ocall(&retval, blkdev) != OE_OK)
+        return -EINVAL;
+
+    return retval;
+}
+
 int myst_load_fssig(const char* path, myst_fssig_t* fssig)
 {
     int retval;
@@ -832,10 +1182,18 @@ int myst_load_fssig(const char* path, myst_fssig_t* fssig)
     return retval;
 }
 
-OE_SET_ENCLAVE_SGX(
-    1,    /* ProductID */
-    1,    /* SecurityVersion */
-    true, /* Debug */
-    32,   /* NumHeapPages */
-    32,   /* NumStackPages */
-    16);  /* NumTCS */
+/* EDMM (see edmm.c) needs the faulting address of page faults, which SGX2
+ * CPUs only report when the enclave captures #PF and #GP exceptions */
+OE_SET_ENCLAVE_SGX2(
+    1,     /* ProductID */
+    1,     /* SecurityVersion */
+    {0},   /* ExtendedProductID */
+    {0},   /* FamilyID */
+    true,  /* Debug */
+    true,  /* CapturePFGPExceptions */
+    false, /* RequireKSS */
+    false, /* CreateZeroBaseEnclave */
+    0,     /* StartAddress */
+    32,    /* NumHeapPages */
+    32,    /* NumStackPages */
+    16);   /* NumTCS */
@@ -0,0 +1,102 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdbool.h>
+#include <syscall.h>
+
+#include "hostpoll.h"
+#include "tee.h"
+
+#define EVENTS_MASK 0x0000ffff
+#define SEQUENCE_ONE 0x00010000
+
+/* in host memory: only ever a hint, see myst_hostpoll_ready() */
+static volatile uint32_t* _events;
+static size_t _nfds;
+
+int myst_setup_hostpoll(volatile uint32_t* events, size_t nfds)
+{
+    size_t size;
+
+    if (!events || !nfds)
+        return 0;
+
+    if (__builtin_mul_overflow(nfds, sizeof(uint32_t), &size) ||
+        !myst_tee()->is_outside((const void*)events, size))
+    {
+        return -EINVAL;
+    }
+
+    _events = events;
+    _nfds = nfds;
+    return 0;
+}
+
+long myst_hostpoll_ready(struct pollfd* fds, nfds_t nfds)
+{
+    long count = 0;
+
+    if (!_events)
+        return 0;
+
+    for (nfds_t i = 0; i < nfds; i++)
+    {
+        const int fd = fds[i].fd;
+        uint32_t mask = (uint16_t)fds[i].events | POLLERR | POLLHUP;
+
+        fds[i].revents = 0;
+
+        if (fd < 0 || (size_t)fd >= _nfds)
+            continue;
+
+        /* the host can only make poll() return early or late, and poll()
+         * results are anyway stale by the time they are used */
+        if ((fds[i].revents = (short)(_events[fd] & EVENTS_MASK & mask)))
+            count++;
+    }
+
+    return count;
+}
+
+void myst_hostpoll_consume(long n, int fd)
+{
+    uint32_t clear;
+
+    switch (n)
+    {
+        case SYS_read:
+        case SYS_recvfrom:
+        case SYS_recvmsg:
+        case SYS_accept:
+        case SYS_accept4:
+            clear = POLLIN;
+            break;
+        case SYS_write:
+        case SYS_sendto:
+        case SYS_sendmsg:
+            clear = POLLOUT;
+            break;
+        default:
+            return;
+    }
+
+    if (!_events || fd < 0 || (size_t)fd >= _nfds)
+        return;
+
+    /* the new sequence number makes the poller drop (and take again) a
+     * result it took before this call */
+    for (;;)
+    {
+        uint32_t e = __atomic_load_n(&_events[fd], __ATOMIC_ACQUIRE);
+        uint32_t v = (e & ~EVENTS_MASK) + SEQUENCE_ONE;
+
+        v |= e & EVENTS_MASK & ~clear;
+
+        if (__atomic_compare_exchange_n(
+                &_events[fd], &e, v, false, __ATOMIC_RELEASE, __ATOMIC_RELAXED))
+        {
+            break;
+        }
+    }
+}
@@ -0,0 +1,22 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_ENC_HOSTPOLL_H
+#define _MYST_ENC_HOSTPOLL_H
+
+#include <poll.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/* use the readiness table of the host poller (see tools/myst/host/poller.c) */
+int myst_setup_hostpoll(volatile uint32_t* events, size_t nfds);
+
+/* fill in the revents of the descriptors that the host has seen ready and
+ * return how many there are (zero if the host must be asked) */
+long myst_hostpoll_ready(struct pollfd* fds, nfds_t nfds);
+
+/* called after each tcall: forget what the host has seen on a descriptor
+ * that a read or write may have changed */
+void myst_hostpoll_consume(long n, int fd);
+
+#endif /* _MYST_ENC_HOSTPOLL_H */
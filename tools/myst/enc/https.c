@@ -0,0 +1,865 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+
+#include <mbedtls/base64.h>
+#include <mbedtls/ssl.h>
+
+#include <myst/json.h>
+#include "https.h"
+#include "myst_t.h"
+
+#define MAX_RESPONSE_SIZE (1024 * 1024)
+#define MAX_HOST_SIZE 256
+
+/*
+**==============================================================================
+**
+** strings:
+**
+**==============================================================================
+*/
+
+int myst_strbuf_append(myst_strbuf_t* buf, const void* data, size_t size)
+{
+    if (buf->size + size + 1 > buf->cap)
+    {
+        size_t cap = buf->cap ? buf->cap : 1024;
+        char* p;
+
+        while (cap < buf->size + size + 1)
+            cap *= 2;
+
+        if (!(p = realloc(buf->data, cap)))
+            return -ENOMEM;
+
+        buf->data = p;
+        buf->cap = cap;
+    }
+
+    memcpy(buf->data + buf->size, data, size);
+    buf->size += size;
+    buf->data[buf->size] = '\0';
+
+    return 0;
+}
+
+int myst_strbuf_puts(myst_strbuf_t* buf, const char* str)
+{
+    return myst_strbuf_append(buf, str, strlen(str));
+}
+
+void myst_strbuf_release(myst_strbuf_t* buf)
+{
+    if (buf->data)
+    {
+        memset(buf->data, 0, buf->cap);
+        free(buf->data);
+    }
+
+    memset(buf, 0, sizeof(myst_strbuf_t));
+}
+
+int myst_strbuf_put_query(myst_strbuf_t* buf, const char* str)
+{
+    static const char digits[] = "0123456789ABCDEF";
+
+    for (const char* p = str; *p; p++)
+    {
+        const unsigned char c = (unsigned char)*p;
+        char tmp[3];
+
+        if ((c >= 'A' && c <= 'Z') || (c >= 'a' && c <= 'z') ||
+            (c >= '0' && c <= '9') || c == '-' || c == '_' || c == '.' ||
+            c == '~')
+        {
+            tmp[0] = (char)c;
+
+            if (myst_strbuf_append(buf, tmp, 1) != 0)
+                return -ENOMEM;
+        }
+        else
+        {
+            tmp[0] = '%';
+            tmp[1] = digits[c >> 4];
+            tmp[2] = digits[c & 0xf];
+
+            if (myst_strbuf_append(buf, tmp, 3) != 0)
+                return -ENOMEM;
+        }
+    }
+
+    return 0;
+}
+
+static int _strncasecmp(const char* s1, const char* s2, size_t n)
+{
+    for (size_t i = 0; i < n; i++)
+    {
+        int c1 = (unsigned char)s1[i];
+        int c2 = (unsigned char)s2[i];
+
+        if (c1 >= 'A' && c1 <= 'Z')
+            c1 += 'a' - 'A';
+
+        if (c2 >= 'A' && c2 <= 'Z')
+            c2 += 'a' - 'A';
+
+        if (c1 != c2 || !c1)
+            return c1 - c2;
+    }
+
+    return 0;
+}
+
+/*
+**==============================================================================
+**
+** base64url (RFC 4648, section 5) without padding:
+**
+**==============================================================================
+*/
+
+int myst_base64url_encode(const uint8_t* data, size_t size, char** str_out)
+{
+    size_t len = 0;
+    char* str;
+
+    *str_out = NULL;
+
+    mbedtls_base64_encode(NULL, 0, &len, data, size);
+
+    if (!(str = malloc(len + 1)))
+        return -ENOMEM;
+
+    if (mbedtls_base64_encode((uint8_t*)str, len + 1, &len, data, size) != 0)
+    {
+        free(str);
+        return -EINVAL;
+    }
+
+    while (len && str[len - 1] == '=')
+        len--;
+
+    str[len] = '\0';
+
+    for (char* p = str; *p; p++)
+    {
+        if (*p == '+')
+            *p = '-';
+        else if (*p == '/')
+            *p = '_';
+    }
+
+    *str_out = str;
+    return 0;
+}
+
+int myst_base64url_decode(
+    const char* str,
+    uint8_t** data_out,
+    size_t* size_out)
+{
+    int ret = 0;
+    const size_t len = strlen(str);
+    char* tmp = NULL;
+    size_t tmp_len = 0;
+    uint8_t* data = NULL;
+    size_t size = 0;
+
+    *data_out = NULL;
+    *size_out = 0;
+
+    /* restore the standard alphabet and the padding */
+    if (!(tmp = malloc(len + 4)))
+    {
+        ret = -ENOMEM;
+        goto done;
+    }
+
+    for (size_t i = 0; i < len; i++)
+    {
+        if (str[i] == '-')
+            tmp[tmp_len++] = '+';
+        else if (str[i] == '_')
+            tmp[tmp_len++] = '/';
+        else
+            tmp[tmp_len++] = str[i];
+    }
+
+    while (tmp_len % 4)
+        tmp[tmp_len++] = '=';
+
+    mbedtls_base64_decode(NULL, 0, &size, (uint8_t*)tmp, tmp_len);
+
+    if (!(data = malloc(size + 1)))
+    {
+        ret = -ENOMEM;
+        goto done;
+    }
+
+    if (mbedtls_base64_decode(data, size, &size, (uint8_t*)tmp, tmp_len) != 0)
+    {
+        ret = -EINVAL;
+        goto done;
+    }
+
+    /* decoded JSON must be zero-terminated */
+    data[size] = '\0';
+
+    *data_out = data;
+    *size_out = size;
+    data = NULL;
+
+done:
+
+    if (tmp)
+        free(tmp);
+
+    if (data)
+        free(data);
+
+    return ret;
+}
+
+/*
+**==============================================================================
+**
+** JSON:
+**
+**==============================================================================
+*/
+
+typedef struct json_get
+{
+    const char* pattern;
+    char* value;
+} json_get_t;
+
+static json_result_t _json_get_callback(
+    json_parser_t* parser,
+    json_reason_t reason,
+    json_type_t type,
+    const json_union_t* un,
+    void* callback_data)
+{
+    json_get_t* get = (json_get_t*)callback_data;
+
+    if (reason == JSON_REASON_VALUE && type == JSON_TYPE_STRING &&
+        !get->value && json_match(parser, get->pattern) == JSON_OK)
+    {
+        if (!(get->value = strdup(un->string)))
+            return JSON_OUT_OF_MEMORY;
+    }
+
+    return JSON_OK;
+}
+
+int myst_json_get_string(
+    const char* json,
+    const char* pattern,
+    char** value_out)
+{
+    int ret = 0;
+    static json_allocator_t allocator = {
+        malloc,
+        free,
+    };
+    const json_parser_options_t options = {1};
+    json_parser_t parser;
+    json_get_t get = {pattern, NULL};
+    char* text;
+
+    *value_out = NULL;
+
+    /* the parser modifies its input */
+    if (!(text = strdup(json)))
+        return -ENOMEM;
+
+    if (json_parser_init(
+            &parser,
+            text,
+            strlen(text),
+            _json_get_callback,
+            &get,
+            &allocator,
+            &options) != JSON_OK ||
+        json_parser_parse(&parser) != JSON_OK)
+    {
+        ret = -EPROTO;
+        goto done;
+    }
+
+    if (!get.value)
+    {
+        ret = -EPROTO;
+        goto done;
+    }
+
+    *value_out = get.value;
+    get.value = NULL;
+
+done:
+
+    free(text);
+
+    if (get.value)
+        free(get.value);
+
+    return ret;
+}
+
+/*
+**==============================================================================
+**
+** HTTP and HTTPS:
+**
+**==============================================================================
+*/
+
+typedef struct url
+{
+    bool https;
+    char host[MAX_HOST_SIZE];
+    uint16_t port;
+    const char* path;
+} url_t;
+
+static int _parse_url(const char* str, url_t* url)
+{
+    const char* p;
+    const char* end;
+    size_t len;
+
+    memset(url, 0, sizeof(url_t));
+
+    if (strncmp(str, "https://", 8) == 0)
+    {
+        url->https = true;
+        url->port = 443;
+        p = str + 8;
+    }
+    else if (strncmp(str, "http://", 7) == 0)
+    {
+        url->port = 80;
+        p = str + 7;
+    }
+    else
+    {
+        return -EINVAL;
+    }
+
+    for (end = p; *end && *end != '/' && *end != ':'; end++)
+        ;
+
+    if ((len = (size_t)(end - p)) == 0 || len >= sizeof(url->host))
+        return -EINVAL;
+
+    memcpy(url->host, p, len);
+    url->host[len] = '\0';
+
+    if (*end == ':')
+    {
+        unsigned long port = 0;
+
+        for (end++; *end >= '0' && *end <= '9'; end++)
+        {
+            if ((port = port * 10 + (unsigned long)(*end - '0')) > 65535)
+                return -EINVAL;
+        }
+
+        if (port == 0)
+            return -EINVAL;
+
+        url->port = (uint16_t)port;
+    }
+
+    url->path = *end ? end : "/";
+
+    if (*url->path != '/')
+        return -EINVAL;
+
+    return 0;
+}
+
+typedef struct conn
+{
+    int sock;
+    bool tls;
+    mbedtls_ssl_context ssl;
+    mbedtls_ssl_config conf;
+} conn_t;
+
+static int _sock_send(void* ctx, const unsigned char* buf, size_t len)
+{
+    conn_t* conn = (conn_t*)ctx;
+    long ret;
+
+    if (myst_sendto_ocall(&ret, conn->sock, buf, len, 0, NULL, 0) != OE_OK)
+        return MBEDTLS_ERR_SSL_INTERNAL_ERROR;
+
+    if (ret == -EAGAIN || ret == -EINTR)
+        return MBEDTLS_ERR_SSL_WANT_WRITE;
+
+    return ret < 0 ? MBEDTLS_ERR_SSL_INTERNAL_ERROR : (int)ret;
+}
+
+static int _sock_recv(void* ctx, unsigned char* buf, size_t len)
+{
+    conn_t* conn = (conn_t*)ctx;
+    socklen_t addrlen = 0;
+    long ret;
+
+    if (myst_recvfrom_ocall(&ret, conn->sock, buf, len, 0, NULL, &addrlen, 0) !=
+        OE_OK)
+    {
+        return MBEDTLS_ERR_SSL_INTERNAL_ERROR;
+    }
+
+    if (ret == -EAGAIN || ret == -EINTR)
+        return MBEDTLS_ERR_SSL_WANT_READ;
+
+    return ret < 0 ? MBEDTLS_ERR_SSL_INTERNAL_ERROR : (int)ret;
+}
+
+static void _close(conn_t* conn)
+{
+    long ret;
+
+    if (conn->tls)
+    {
+        mbedtls_ssl_close_notify(&conn->ssl);
+        mbedtls_ssl_free(&conn->ssl);
+        mbedtls_ssl_config_free(&conn->conf);
+        conn->tls = false;
+    }
+
+    if (conn->sock >= 0)
+    {
+        myst_close_ocall(&ret, conn->sock);
+        conn->sock = -1;
+    }
+}
+
+static int _connect(
+    myst_https_client_t* client,
+    const url_t* url,
+    conn_t* conn)
+{
+    int ret = 0;
+    struct sockaddr_in addr;
+    uint32_t ip = 0;
+    long r;
+
+    memset(conn, 0, sizeof(conn_t));
+    conn->sock = -1;
+
+    if (myst_resolve_host_ocall(&r, url->host, &ip) != OE_OK || r != 0)
+    {
+        ret = -EHOSTUNREACH;
+        goto done;
+    }
+
+    if (myst_socket_ocall(&r, AF_INET, SOCK_STREAM, 0) != OE_OK || r < 0)
+    {
+        ret = -ENETDOWN;
+        goto done;
+    }
+
+    conn->sock = (int)r;
+
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_port = htons(url->port);
+    addr.sin_addr.s_addr = ip;
+
+    if (myst_connect_ocall(
+            &r, conn->sock, (struct sockaddr*)&addr, sizeof(addr)) != OE_OK ||
+        r != 0)
+    {
+        ret = -ECONNREFUSED;
+        goto done;
+    }
+
+    if (!url->https)
+        goto done;
+
+    mbedtls_ssl_init(&conn->ssl);
+    mbedtls_ssl_config_init(&conn->conf);
+    conn->tls = true;
+
+    if (mbedtls_ssl_config_defaults(
+            &conn->conf,
+            MBEDTLS_SSL_IS_CLIENT,
+            MBEDTLS_SSL_TRANSPORT_STREAM,
+            MBEDTLS_SSL_PRESET_DEFAULT) != 0)
+    {
+        ret = -EPROTO;
+        goto done;
+    }
+
+    mbedtls_ssl_conf_authmode(&conn->conf, MBEDTLS_SSL_VERIFY_REQUIRED);
+    mbedtls_ssl_conf_ca_chain(&conn->conf, &client->ca, NULL);
+    mbedtls_ssl_conf_rng(&conn->conf, mbedtls_ctr_drbg_random, &client->drbg);
+
+    if (client->have_cert &&
+        mbedtls_ssl_conf_own_cert(&conn->conf, &client->cert, &client->key) !=
+            0)
+    {
+        ret = -EPROTO;
+        goto done;
+    }
+
+    if (mbedtls_ssl_setup(&conn->ssl, &conn->conf) != 0 ||
+        mbedtls_ssl_set_hostname(&conn->ssl, url->host) != 0)
+    {
+        ret = -EPROTO;
+        goto done;
+    }
+
+    mbedtls_ssl_set_bio(&conn->ssl, conn, _sock_send, _sock_recv, NULL);
+
+    while ((r = mbedtls_ssl_handshake(&conn->ssl)) != 0)
+    {
+        if (r != MBEDTLS_ERR_SSL_WANT_READ && r != MBEDTLS_ERR_SSL_WANT_WRITE)
+        {
+            /* includes certificate verification failures */
+            ret = -EPERM;
+            goto done;
+        }
+    }
+
+done:
+
+    if (ret != 0)
+        _close(conn);
+
+    return ret;
+}
+
+static int _write(conn_t* conn, const char* data, size_t size)
+{
+    while (size)
+    {
+        int n;
+
+        if (conn->tls)
+            n = mbedtls_ssl_write(&conn->ssl, (const uint8_t*)data, size);
+        else
+            n = _sock_send(conn, (const uint8_t*)data, size);
+
+        if (n == MBEDTLS_ERR_SSL_WANT_READ || n == MBEDTLS_ERR_SSL_WANT_WRITE)
+            continue;
+
+        if (n <= 0)
+            return -EIO;
+
+        data += n;
+        size -= (size_t)n;
+    }
+
+    return 0;
+}
+
+/* read until the peer closes the connection (requests use "close") */
+static int _read_all(conn_t* conn, myst_strbuf_t* buf)
+{
+    char tmp[4096];
+
+    for (;;)
+    {
+        int n;
+
+        if (conn->tls)
+            n = mbedtls_ssl_read(&conn->ssl, (uint8_t*)tmp, sizeof(tmp));
+        else
+            n = _sock_recv(conn, (uint8_t*)tmp, sizeof(tmp));
+
+        if (n == MBEDTLS_ERR_SSL_WANT_READ || n == MBEDTLS_ERR_SSL_WANT_WRITE)
+            continue;
+
+        if (n == 0 || n == MBEDTLS_ERR_SSL_PEER_CLOSE_NOTIFY)
+            break;
+
+        if (n < 0)
+            return -EIO;
+
+        if (buf->size + (size_t)n > MAX_RESPONSE_SIZE)
+            return -EMSGSIZE;
+
+        if (myst_strbuf_append(buf, tmp, (size_t)n) != 0)
+            return -ENOMEM;
+    }
+
+    return 0;
+}
+
+static const char* _find_header(const char* headers, const char* name)
+{
+    const size_t len = strlen(name);
+
+    for (const char* p = headers; p && *p;)
+    {
+        if (_strncasecmp(p, name, len) == 0 && p[len] == ':')
+        {
+            p += len + 1;
+
+            while (*p == ' ' || *p == '\t')
+                p++;
+
+            return p;
+        }
+
+        if ((p = strstr(p, "\r\n")))
+            p += 2;
+    }
+
+    return NULL;
+}
+
+/* remove the chunked transfer encoding in place */
+static int _dechunk(char* body, size_t* size)
+{
+    const char* p = body;
+    const char* end = body + *size;
+    size_t n = 0;
+
+    for (;;)
+    {
+        size_t chunk = 0;
+        const char* eol;
+
+        for (; p < end && *p != '\r' && *p != ';'; p++)
+        {
+            int x;
+
+            if (*p >= '0' && *p <= '9')
+                x = *p - '0';
+            else if (*p >= 'a' && *p <= 'f')
+                x = *p - 'a' + 10;
+            else if (*p >= 'A' && *p <= 'F')
+                x = *p - 'A' + 10;
+            else
+                return -EPROTO;
+
+            if (chunk > MAX_RESPONSE_SIZE)
+                return -EPROTO;
+
+            chunk = chunk * 16 + (size_t)x;
+        }
+
+        if (!(eol = strstr(p, "\r\n")))
+            return -EPROTO;
+
+        p = eol + 2;
+
+        if (chunk == 0)
+            break;
+
+        if (chunk > (size_t)(end - p))
+            return -EPROTO;
+
+        memmove(body + n, p, chunk);
+        n += chunk;
+        p += chunk;
+
+        if (end - p < 2 || p[0] != '\r' || p[1] != '\n')
+            return -EPROTO;
+
+        p += 2;
+    }
+
+    body[n] = '\0';
+    *size = n;
+
+    return 0;
+}
+
+int myst_https_request(
+    myst_https_client_t* client,
+    const char* method,
+    const char* url_str,
+    const char* headers,
+    const char* body,
+    char** data_out,
+    size_t* size_out)
+{
+    int ret = 0;
+    url_t url;
+    conn_t conn = {.sock = -1};
+    myst_strbuf_t req = {0};
+    myst_strbuf_t rsp = {0};
+    char* hdrs;
+    char* content;
+    size_t content_size;
+    const char* value;
+    char line[64];
+
+    *data_out = NULL;
+    *size_out = 0;
+
+    if ((ret = _parse_url(url_str, &url)) != 0)
+        goto done;
+
+    snprintf(line, sizeof(line), "Content-Length: %zu\r\n", strlen(body));
+
+    if (myst_strbuf_puts(&req, method) != 0 || myst_strbuf_puts(&req, " ") != 0 ||
+        myst_strbuf_puts(&req, url.path) != 0 ||
+        myst_strbuf_puts(&req, " HTTP/1.1\r\nHost: ") != 0 ||
+        myst_strbuf_puts(&req, url.host) != 0 ||
+        myst_strbuf_puts(&req, "\r\nConnection: close\r\n") != 0 ||
+        myst_strbuf_puts(&req, headers) != 0 || myst_strbuf_puts(&req, line) != 0 ||
+        myst_strbuf_puts(&req, "\r\n") != 0 || myst_strbuf_puts(&req, body) != 0)
+    {
+        ret = -ENOMEM;
+        goto done;
+    }
+
+    if ((ret = _connect(client, &url, &conn)) != 0)
+        goto done;
+
+    if ((ret = _write(&conn, req.data, req.size)) != 0)
+        goto done;
+
+    if ((ret = _read_all(&conn, &rsp)) != 0)
+        goto done;
+
+    if (!rsp.data || strncmp(rsp.data, "HTTP/1.", 7) != 0 ||
+        !(content = strstr(rsp.data, "\r\n\r\n")))
+    {
+        ret = -EPROTO;
+        goto done;
+    }
+
+    *content = '\0';
+    content += 4;
+    content_size = rsp.size - (size_t)(content - rsp.data);
+
+    /* "HTTP/1.1 200 OK" */
+    if (strlen(rsp.data) < 12 || strncmp(rsp.data + 8, " 200", 4) != 0)
+    {
+        const char* status = rsp.data + 8;
+
+        /* distinguish a policy refusal from other failures */
+        if (strncmp(status, " 401", 4) == 0 || strncmp(status, " 403", 4) == 0)
+            ret = -EACCES;
+        else if (strncmp(status, " 404", 4) == 0)
+            ret = -ENOENT;
+        else
+            ret = -EPROTO;
+
+        goto done;
+    }
+
+    hdrs = strstr(rsp.data, "\r\n");
+    hdrs = hdrs ? hdrs + 2 : NULL;
+
+    if ((value = _find_header(hdrs, "Transfer-Encoding")) &&
+        _strncasecmp(value, "chunked", 7) == 0)
+    {
+        if ((ret = _dechunk(content, &content_size)) != 0)
+            goto done;
+    }
+
+    if (!(*data_out = malloc(content_size + 1)))
+    {
+        ret = -ENOMEM;
+        goto done;
+    }
+
+    memcpy(*data_out, content, content_size);
+    (*data_out)[content_size] = '\0';
+    *size_out = content_size;
+
+done:
+
+    _close(&conn);
+    myst_strbuf_release(&req);
+    myst_strbuf_release(&rsp);
+
+    return ret;
+}
+
+/*
+**==============================================================================
+**
+** client credentials:
+**
+**==============================================================================
+*/
+
+int myst_https_client_init(myst_https_client_t* client, const char* ca_pem)
+{
+    static const char pers[] = "myst-https";
+
+    if (!client)
+        return -EINVAL;
+
+    /* initialize everything so that myst_https_client_free() always works */
+    memset(client, 0, sizeof(myst_https_client_t));
+    mbedtls_entropy_init(&client->entropy);
+    mbedtls_ctr_drbg_init(&client->drbg);
+    mbedtls_x509_crt_init(&client->ca);
+    mbedtls_x509_crt_init(&client->cert);
+    mbedtls_pk_init(&client->key);
+
+    if (!ca_pem)
+        return -EINVAL;
+
+    if (mbedtls_ctr_drbg_seed(
+            &client->drbg,
+            mbedtls_entropy_func,
+            &client->entropy,
+            (const uint8_t*)pers,
+            sizeof(pers) - 1) != 0)
+    {
+        return -EIO;
+    }
+
+    /* the PEM parser needs the zero terminator */
+    if (mbedtls_x509_crt_parse(
+            &client->ca, (const uint8_t*)ca_pem, strlen(ca_pem) + 1) != 0)
+    {
+        return -EINVAL;
+    }
+
+    return 0;
+}
+
+int myst_https_client_set_cert(
+    myst_https_client_t* client,
+    const uint8_t* cert,
+    size_t cert_size,
+    const uint8_t* key,
+    size_t key_size)
+{
+    if (!client || !cert || !key)
+        return -EINVAL;
+
+    if (mbedtls_x509_crt_parse_der(&client->cert, cert, cert_size) != 0 ||
+        mbedtls_pk_parse_key(&client->key, key, key_size, NULL, 0) != 0)
+    {
+        return -EINVAL;
+    }
+
+    client->have_cert = true;
+    return 0;
+}
+
+void myst_https_client_free(myst_https_client_t* client)
+{
+    if (client)
+    {
+        mbedtls_pk_free(&client->key);
+        mbedtls_x509_crt_free(&client->cert);
+        mbedtls_x509_crt_free(&client->ca);
+        mbedtls_ctr_drbg_free(&client->drbg);
+        mbedtls_entropy_free(&client->entropy);
+    }
+}
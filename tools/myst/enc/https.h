@@ -0,0 +1,82 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_ENC_HTTPS_H
+#define _MYST_ENC_HTTPS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#include <mbedtls/ctr_drbg.h>
+#include <mbedtls/entropy.h>
+#include <mbedtls/pk.h>
+#include <mbedtls/x509_crt.h>
+
+/* a growing zero-terminated string */
+typedef struct myst_strbuf
+{
+    char* data;
+    size_t size;
+    size_t cap;
+} myst_strbuf_t;
+
+int myst_strbuf_append(myst_strbuf_t* buf, const void* data, size_t size);
+
+int myst_strbuf_puts(myst_strbuf_t* buf, const char* str);
+
+/* append a percent-encoded URL query value */
+int myst_strbuf_put_query(myst_strbuf_t* buf, const char* str);
+
+/* clear and free the buffer (it may have held secrets) */
+void myst_strbuf_release(myst_strbuf_t* buf);
+
+/* base64url (RFC 4648, section 5) without padding */
+int myst_base64url_encode(const uint8_t* data, size_t size, char** str);
+
+/* the decoded data is zero-terminated */
+int myst_base64url_decode(const char* str, uint8_t** data, size_t* size);
+
+/* get the string at a path such as "response.key.key.key_hsm" */
+int myst_json_get_string(const char* json, const char* pattern, char** value);
+
+/* an HTTP/1.1 client that runs TLS inside the enclave over host sockets */
+typedef struct myst_https_client
+{
+    mbedtls_entropy_context entropy;
+    mbedtls_ctr_drbg_context drbg;
+
+    /* servers are authenticated against these certificates */
+    mbedtls_x509_crt ca;
+
+    /* the optional client certificate */
+    mbedtls_x509_crt cert;
+    mbedtls_pk_context key;
+    bool have_cert;
+} myst_https_client_t;
+
+/* ca_pem holds zero-terminated PEM certificates */
+int myst_https_client_init(myst_https_client_t* client, const char* ca_pem);
+
+/* present a certificate (DER) and private key (PEM or DER) to servers */
+int myst_https_client_set_cert(
+    myst_https_client_t* client,
+    const uint8_t* cert,
+    size_t cert_size,
+    const uint8_t* key,
+    size_t key_size);
+
+void myst_https_client_free(myst_https_client_t* client);
+
+/* send a request and return the body of a 200 response (zero-terminated);
+ * 401 and 403 responses fail with -EACCES and 404 with -ENOENT */
+int myst_https_request(
+    myst_https_client_t* client,
+    const char* method,
+    const char* url,
+    const char* headers,
+    const char* body,
+    char** data,
+    size_t* size);
+
+#endif /* _MYST_ENC_HTTPS_H */
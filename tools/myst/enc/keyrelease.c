@@ -0,0 +1,618 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <mbedtls/aes.h>
+#include <mbedtls/rsa.h>
+#include <mbedtls/sha256.h>
+#include <openenclave/enclave.h>
+
+#include <myst/keyrelease.h>
+#include "https.h"
+#include "keyrelease.h"
+#include "tee.h"
+
+/*
+**==============================================================================
+**
+** Secure key release from Azure Key Vault (or Managed HSM):
+**
+**     (1) Generate an RSA transfer key pair inside the enclave.
+**     (2) Get an SGX quote whose report data binds the public transfer key
+**         and exchange it for a token from Microsoft Azure Attestation.
+**     (3) Get an access token for the key vault from the identity endpoint.
+**     (4) Ask the key vault to release the key to the attestation token; the
+**         key vault checks the token against the key's release policy and
+**         returns the key wrapped with the public transfer key.
+**     (5) Unwrap the key with the private transfer key.
+**
+** All HTTPS peers are authenticated against the CA certificates from the
+** signed image. Only the identity endpoint (which is local to the VM) may use
+** plain HTTP; the access token it returns is useless without the attestation
+** token that only this enclave can obtain.
+**
+**==============================================================================
+*/
+
+#define TRANSFER_KEY_BITS 2048
+#define TRANSFER_KEY_ID "myst-transfer-key"
+
+#define DEFAULT_IDENTITY_ENDPOINT \
+    "http://169.254.169.254/metadata/identity/oauth2/token"
+#define DEFAULT_RESOURCE "https://vault.azure.net"
+
+/* the initial value of RFC 5649 key wrap with padding */
+static const uint8_t _kwp_aiv[] = {0xa6, 0x59, 0x59, 0xa6};
+
+typedef struct crypto
+{
+    myst_https_client_t https;
+    mbedtls_rsa_context rsa;
+} crypto_t;
+
+/*
+**==============================================================================
+**
+** key release steps:
+**
+**==============================================================================
+*/
+
+static int _mpi_to_base64url(const mbedtls_mpi* mpi, char** str)
+{
+    int ret = 0;
+    const size_t size = mbedtls_mpi_size(mpi);
+    uint8_t* data;
+
+    if (!(data = malloc(size)))
+        return -ENOMEM;
+
+    if (mbedtls_mpi_write_binary(mpi, data, size) != 0)
+        ret = -EINVAL;
+    else
+        ret = myst_base64url_encode(data, size, str);
+
+    free(data);
+    return ret;
+}
+
+/* the public transfer key as a JSON web key set */
+static int _format_jwks(crypto_t* crypto, char** jwks_out)
+{
+    int ret = 0;
+    char* n = NULL;
+    char* e = NULL;
+    myst_strbuf_t buf = {0};
+
+    *jwks_out = NULL;
+
+    if ((ret = _mpi_to_base64url(&crypto->rsa.N, &n)) != 0 ||
+        (ret = _mpi_to_base64url(&crypto->rsa.E, &e)) != 0)
+    {
+        goto done;
+    }
+
+    if (myst_strbuf_puts(&buf, "{\"keys\":[{\"kty\":\"RSA\",\"kid\":\"") != 0 ||
+        myst_strbuf_puts(&buf, TRANSFER_KEY_ID) != 0 ||
+        myst_strbuf_puts(&buf, "\",\"key_ops\":[\"encrypt\"],\"e\":\"") != 0 ||
+        myst_strbuf_puts(&buf, e) != 0 || myst_strbuf_puts(&buf, "\",\"n\":\"") != 0 ||
+        myst_strbuf_puts(&buf, n) != 0 || myst_strbuf_puts(&buf, "\"}]}") != 0)
+    {
+        ret = -ENOMEM;
+        goto done;
+    }
+
+    *jwks_out = buf.data;
+    buf.data = NULL;
+
+done:
+
+    free(n);
+    free(e);
+    myst_strbuf_release(&buf);
+
+    return ret;
+}
+
+/* get an attestation token whose runtime data holds the transfer key */
+static int _attest(
+    crypto_t* crypto,
+    const char* attestation_url,
+    char** token_out)
+{
+    int ret = 0;
+    const myst_tee_t* tee = myst_tee();
+    char* jwks = NULL;
+    uint8_t* evidence = NULL;
+    size_t evidence_size = 0;
+    char* quote = NULL;
+    char* runtime_data = NULL;
+    myst_strbuf_t url = {0};
+    myst_strbuf_t body = {0};
+    char* response = NULL;
+    size_t response_size = 0;
+    uint8_t report_data[32];
+
+    *token_out = NULL;
+
+    if ((ret = _format_jwks(crypto, &jwks)) != 0)
+        goto done;
+
+    /* the attestation service checks the report data against this hash */
+    if (mbedtls_sha256_ret(
+            (const uint8_t*)jwks, strlen(jwks), report_data, 0) != 0)
+    {
+        ret = -EIO;
+        goto done;
+    }
+
+    if ((ret = tee->get_evidence(
+             report_data, sizeof(report_data), &evidence, &evidence_size)) != 0)
+    {
+        goto done;
+    }
+
+    if ((ret = myst_base64url_encode(evidence, evidence_size, &quote)) != 0 ||
+        (ret = myst_base64url_encode(
+             (const uint8_t*)jwks, strlen(jwks), &runtime_data)) != 0)
+    {
+        goto done;
+    }
+
+    if (myst_strbuf_puts(&url, attestation_url) != 0 ||
+        myst_strbuf_puts(&url, "/attest/") != 0 ||
+        myst_strbuf_puts(&url, tee->name) != 0 ||
+        myst_strbuf_puts(&url, "?api-version=2022-08-01") != 0 ||
+        myst_strbuf_puts(&body, "{\"quote\":\"") != 0 ||
+        myst_strbuf_puts(&body, quote) != 0 ||
+        myst_strbuf_puts(&body, "\",\"runtimeData\":{\"data\":\"") != 0 ||
+        myst_strbuf_puts(&body, runtime_data) != 0 ||
+        myst_strbuf_puts(&body, "\",\"dataType\":\"JSON\"}}") != 0)
+    {
+        ret = -ENOMEM;
+        goto done;
+    }
+
+    if ((ret = myst_https_request(
+             &crypto->https,
+             "POST",
+             url.data,
+             "Content-Type: application/json\r\n",
+             body.data,
+             &response,
+             &response_size)) != 0)
+    {
+        goto done;
+    }
+
+    ret = myst_json_get_string(response, "token", token_out);
+
+done:
+
+    if (evidence)
+        tee->free_evidence(evidence);
+
+    free(jwks);
+    free(quote);
+    free(runtime_data);
+    free(response);
+    myst_strbuf_release(&url);
+    myst_strbuf_release(&body);
+
+    return ret;
+}
+
+/* get an access token for the key vault from the managed identity */
+static int _get_access_token(
+    crypto_t* crypto,
+    const myst_key_release_request_t* request,
+    char** token_out)
+{
+    int ret = 0;
+    const char* endpoint = request->identity_endpoint;
+    const char* resource = request->resource;
+    myst_strbuf_t url = {0};
+    char* response = NULL;
+    size_t response_size = 0;
+
+    *token_out = NULL;
+
+    if (!endpoint)
+        endpoint = DEFAULT_IDENTITY_ENDPOINT;
+
+    if (!resource)
+        resource = DEFAULT_RESOURCE;
+
+    if (myst_strbuf_puts(&url, endpoint) != 0 ||
+        myst_strbuf_puts(&url, "?api-version=2018-02-01&resource=") != 0 ||
+        myst_strbuf_put_query(&url, resource) != 0)
+    {
+        ret = -ENOMEM;
+        goto done;
+    }
+
+    if (request->client_id)
+    {
+        if (myst_strbuf_puts(&url, "&client_id=") != 0 ||
+            myst_strbuf_put_query(&url, request->client_id) != 0)
+        {
+            ret = -ENOMEM;
+            goto done;
+        }
+    }
+
+    if ((ret = myst_https_request(
+             &crypto->https,
+             "GET",
+             url.data,
+             "Metadata: true\r\n",
+             "",
+             &response,
+             &response_size)) != 0)
+    {
+        goto done;
+    }
+
+    ret = myst_json_get_string(response, "access_token", token_out);
+
+done:
+
+    myst_strbuf_release(&url);
+
+    if (response)
+    {
+        memset(response, 0, response_size);
+        free(response);
+    }
+
+    return ret;
+}
+
+/* RFC 5649 key unwrap with padding */
+static int _aes_kwp_unwrap(
+    const uint8_t* kek,
+    size_t kek_size,
+    const uint8_t* in,
+    size_t in_size,
+    uint8_t* out,
+    size_t* out_size)
+{
+    int ret = 0;
+    mbedtls_aes_context aes;
+    uint8_t a[8];
+    uint8_t b[16];
+    size_t n;
+    size_t mli;
+
+    mbedtls_aes_init(&aes);
+
+    if (in_size < 16 || in_size % 8)
+    {
+        ret = -EINVAL;
+        goto done;
+    }
+
+    if (mbedtls_aes_setkey_dec(&aes, kek, (unsigned int)(kek_size * 8)) != 0)
+    {
+        ret = -EINVAL;
+        goto done;
+    }
+
+    n = in_size / 8 - 1;
+
+    if (n == 1)
+    {
+        if (mbedtls_aes_crypt_ecb(&aes, MBEDTLS_AES_DECRYPT, in, b) != 0)
+        {
+            ret = -EINVAL;
+            goto done;
+        }
+
+        memcpy(a, b, 8);
+        memcpy(out, b + 8, 8);
+    }
+    else
+    {
+        memcpy(a, in, 8);
+        memmove(out, in + 8, n * 8);
+
+        for (size_t j = 6; j-- > 0;)
+        {
+            for (size_t i = n; i > 0; i--)
+            {
+                const uint64_t t = (uint64_t)(n * j + i);
+
+                memcpy(b, a, 8);
+
+                for (size_t k = 0; k < 8; k++)
+                    b[7 - k] ^= (uint8_t)(t >> (8 * k));
+
+                memcpy(b + 8, out + (i - 1) * 8, 8);
+
+                if (mbedtls_aes_crypt_ecb(&aes, MBEDTLS_AES_DECRYPT, b, b) != 0)
+                {
+                    ret = -EINVAL;
+                    goto done;
+                }
+
+                memcpy(a, b, 8);
+                memcpy(out + (i - 1) * 8, b + 8, 8);
+            }
+        }
+    }
+
+    /* check the integrity value and the message length indicator */
+    mli = ((size_t)a[4] << 24) | ((size_t)a[5] << 16) | ((size_t)a[6] << 8) |
+          (size_t)a[7];
+
+    if (memcmp(a, _kwp_aiv, sizeof(_kwp_aiv)) != 0 || mli > n * 8 ||
+        mli <= (n - 1) * 8)
+    {
+        ret = -EBADMSG;
+        goto done;
+    }
+
+    for (size_t i = mli; i < n * 8; i++)
+    {
+        if (out[i] != 0)
+        {
+            ret = -EBADMSG;
+            goto done;
+        }
+    }
+
+    *out_size = mli;
+
+done:
+
+    mbedtls_aes_free(&aes);
+    memset(a, 0, sizeof(a));
+    memset(b, 0, sizeof(b));
+
+    return ret;
+}
+
+/* unwrap "key_hsm" (CKM_RSA_AES_KEY_WRAP) with the private transfer key */
+static int _unwrap(
+    crypto_t* crypto,
+    const char* key_hsm,
+    void* key,
+    size_t key_size,
+    size_t* key_size_out)
+{
+    int ret = 0;
+    uint8_t* hsm = NULL;
+    size_t hsm_size;
+    char* ciphertext = NULL;
+    uint8_t* wrapped = NULL;
+    size_t wrapped_size;
+    uint8_t* unwrapped = NULL;
+    size_t unwrapped_size = 0;
+    uint8_t aes_key[32];
+    size_t aes_key_size = 0;
+    const size_t rsa_size = mbedtls_rsa_get_len(&crypto->rsa);
+
+    if ((ret = myst_base64url_decode(key_hsm, &hsm, &hsm_size)) != 0)
+        goto done;
+
+    if ((ret = myst_json_get_string((const char*)hsm, "ciphertext", &ciphertext)) != 0)
+        goto done;
+
+    if ((ret = myst_base64url_decode(ciphertext, &wrapped, &wrapped_size)) != 0)
+        goto done;
+
+    /* the RSA-OAEP wrapped AES key followed by the AES-KWP wrapped key */
+    if (wrapped_size <= rsa_size)
+    {
+        ret = -EBADMSG;
+        goto done;
+    }
+
+    if (mbedtls_rsa_rsaes_oaep_decrypt(
+            &crypto->rsa,
+            mbedtls_ctr_drbg_random,
+            &crypto->https.drbg,
+            MBEDTLS_RSA_PRIVATE,
+            NULL,
+            0,
+            &aes_key_size,
+            wrapped,
+            aes_key,
+            sizeof(aes_key)) != 0)
+    {
+        ret = -EBADMSG;
+        goto done;
+    }
+
+    if (!(unwrapped = malloc(wrapped_size)))
+    {
+        ret = -ENOMEM;
+        goto done;
+    }
+
+    if ((ret = _aes_kwp_unwrap(
+             aes_key,
+             aes_key_size,
+             wrapped + rsa_size,
+             wrapped_size - rsa_size,
+             unwrapped,
+             &unwrapped_size)) != 0)
+    {
+        goto done;
+    }
+
+    if (unwrapped_size > key_size)
+    {
+        ret = -ERANGE;
+        goto done;
+    }
+
+    memcpy(key, unwrapped, unwrapped_size);
+    *key_size_out = unwrapped_size;
+
+done:
+
+    memset(aes_key, 0, sizeof(aes_key));
+    free(hsm);
+    free(ciphertext);
+    free(wrapped);
+
+    if (unwrapped)
+    {
+        memset(unwrapped, 0, wrapped_size);
+        free(unwrapped);
+    }
+
+    return ret;
+}
+
+static int _release(
+    crypto_t* crypto,
+    const myst_key_release_request_t* request,
+    const char* attestation_token,
+    const char* access_token,
+    void* key,
+    size_t key_size,
+    size_t* key_size_out)
+{
+    int ret = 0;
+    myst_strbuf_t url = {0};
+    myst_strbuf_t headers = {0};
+    myst_strbuf_t body = {0};
+    char* response = NULL;
+    size_t response_size = 0;
+    char* jws = NULL;
+    char* dot;
+    uint8_t* payload = NULL;
+    size_t payload_size;
+    char* key_hsm = NULL;
+
+    if (myst_strbuf_puts(&url, request->key_url) != 0 ||
+        myst_strbuf_puts(&url, "/release?api-version=7.3") != 0 ||
+        myst_strbuf_puts(&headers, "Content-Type: application/json\r\n") != 0 ||
+        myst_strbuf_puts(&headers, "Authorization: Bearer ") != 0 ||
+        myst_strbuf_puts(&headers, access_token) != 0 ||
+        myst_strbuf_puts(&headers, "\r\n") != 0 ||
+        myst_strbuf_puts(&body, "{\"target\":\"") != 0 ||
+        myst_strbuf_puts(&body, attestation_token) != 0 ||
+        myst_strbuf_puts(&body, "\",\"enc\":\"CKM_RSA_AES_KEY_WRAP\"}") != 0)
+    {
+        ret = -ENOMEM;
+        goto done;
+    }
+
+    if ((ret = myst_https_request(
+             &crypto->https,
+             "POST",
+             url.data,
+             headers.data,
+             body.data,
+             &response,
+             &response_size)) != 0)
+    {
+        goto done;
+    }
+
+    /* the response is a JWS: header.payload.signature */
+    if ((ret = myst_json_get_string(response, "value", &jws)) != 0)
+        goto done;
+
+    if (!(dot = strchr(jws, '.')) || !strchr(dot + 1, '.'))
+    {
+        ret = -EPROTO;
+        goto done;
+    }
+
+    *strchr(dot + 1, '.') = '\0';
+
+    if ((ret = myst_base64url_decode(dot + 1, &payload, &payload_size)) != 0)
+        goto done;
+
+    if ((ret = myst_json_get_string(
+             (const char*)payload, "response.key.key.key_hsm", &key_hsm)) != 0)
+    {
+        goto done;
+    }
+
+    ret = _unwrap(crypto, key_hsm, key, key_size, key_size_out);
+
+done:
+
+    myst_strbuf_release(&url);
+    myst_strbuf_release(&headers);
+    myst_strbuf_release(&body);
+    free(response);
+    free(jws);
+    free(payload);
+    free(key_hsm);
+
+    return ret;
+}
+
+long myst_key_release(
+    const myst_key_release_request_t* request,
+    void* key,
+    size_t key_size,
+    size_t* key_size_out)
+{
+    long ret = 0;
+    crypto_t crypto;
+    char* attestation_token = NULL;
+    char* access_token = NULL;
+
+    if (!request || !request->attestation_url || !request->key_url ||
+        !request->ca_data || !key || !key_size_out)
+    {
+        return -EINVAL;
+    }
+
+    *key_size_out = 0;
+
+    mbedtls_rsa_init(&crypto.rsa, MBEDTLS_RSA_PKCS_V21, MBEDTLS_MD_SHA1);
+
+    if ((ret = myst_https_client_init(&crypto.https, request->ca_data)) != 0)
+        goto done;
+
+    if (mbedtls_rsa_gen_key(
+            &crypto.rsa,
+            mbedtls_ctr_drbg_random,
+            &crypto.https.drbg,
+            TRANSFER_KEY_BITS,
+            65537) != 0)
+    {
+        ret = -EIO;
+        goto done;
+    }
+
+    if ((ret = _attest(&crypto, request->attestation_url, &attestation_token)))
+        goto done;
+
+    if ((ret = _get_access_token(&crypto, request, &access_token)))
+        goto done;
+
+    ret = _release(
+        &crypto,
+        request,
+        attestation_token,
+        access_token,
+        key,
+        key_size,
+        key_size_out);
+
+done:
+
+    mbedtls_rsa_free(&crypto.rsa);
+    myst_https_client_free(&crypto.https);
+    free(attestation_token);
+
+    if (access_token)
+    {
+        memset(access_token, 0, strlen(access_token));
+        free(access_token);
+    }
+
+    return ret;
+}
@@ -0,0 +1,16 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_ENC_KEYRELEASE_H
+#define _MYST_ENC_KEYRELEASE_H
+
+#include <myst/keyrelease.h>
+
+/* attest the enclave and release one key from the key vault */
+long myst_key_release(
+    const myst_key_release_request_t* request,
+    void* key,
+    size_t key_size,
+    size_t* key_size_out);
+
+#endif /* _MYST_ENC_KEYRELEASE_H */
@@ -0,0 +1,122 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <myst/secret.h>
+#include "https.h"
+#include "secret.h"
+#include "tee.h"
+
+long myst_unseal_secret(
+    const void* blob,
+    size_t blob_size,
+    void* buf,
+    size_t size,
+    size_t* size_out)
+{
+    long ret = 0;
+    uint8_t* data = NULL;
+    size_t data_size = 0;
+
+    if (!blob || !buf || !size_out)
+        return -EINVAL;
+
+    *size_out = 0;
+
+    if (myst_tee()->unseal(blob, blob_size, &data, &data_size) != 0)
+    {
+        /* corrupt, or sealed by an enclave that this one cannot act for */
+        ret = -EACCES;
+        goto done;
+    }
+
+    if (data_size > size)
+    {
+        ret = -ERANGE;
+        goto done;
+    }
+
+    memcpy(buf, data, data_size);
+    *size_out = data_size;
+
+done:
+
+    if (data)
+    {
+        memset(data, 0, data_size);
+        myst_tee()->free_unsealed(data);
+    }
+
+    return ret;
+}
+
+long myst_attested_https_get(
+    const myst_attested_https_request_t* request,
+    void* buf,
+    size_t size,
+    size_t* size_out)
+{
+    long ret = 0;
+    myst_https_client_t client;
+    uint8_t* cert = NULL;
+    size_t cert_size = 0;
+    uint8_t* key = NULL;
+    size_t key_size = 0;
+    char* data = NULL;
+    size_t data_size = 0;
+
+    if (!request || !request->url || !request->ca_data || !buf || !size_out)
+        return -EINVAL;
+
+    *size_out = 0;
+
+    if ((ret = myst_https_client_init(&client, request->ca_data)) != 0)
+        goto done;
+
+    /* a certificate for a fresh key pair, with the key bound to a quote */
+    if (myst_tee()->gen_creds(&cert, &cert_size, &key, &key_size) != 0)
+    {
+        ret = -EIO;
+        goto done;
+    }
+
+    if ((ret = myst_https_client_set_cert(
+             &client, cert, cert_size, key, key_size)) != 0)
+    {
+        goto done;
+    }
+
+    if ((ret = myst_https_request(
+             &client, "GET", request->url, "", "", &data, &data_size)) != 0)
+    {
+        goto done;
+    }
+
+    if (data_size > size)
+    {
+        ret = -ERANGE;
+        goto done;
+    }
+
+    memcpy(buf, data, data_size);
+    *size_out = data_size;
+
+done:
+
+    /* the client is always initialized, even if initialization failed */
+    myst_https_client_free(&client);
+
+    if (cert || key)
+        myst_tee()->free_creds(cert, cert_size, key, key_size);
+
+    if (data)
+    {
+        memset(data, 0, data_size);
+        free(data);
+    }
+
+    return ret;
+}
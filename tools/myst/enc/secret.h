@@ -0,0 +1,24 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_ENC_SECRET_H
+#define _MYST_ENC_SECRET_H
+
+#include <myst/secret.h>
+
+/* unseal a blob made by oe_seal() for the "sealed-file" provider */
+long myst_unseal_secret(
+    const void* blob,
+    size_t blob_size,
+    void* buf,
+    size_t size,
+    size_t* size_out);
+
+/* fetch a secret for the "attested-https" provider */
+long myst_attested_https_get(
+    const myst_attested_https_request_t* request,
+    void* buf,
+    size_t size,
+    size_t* size_out);
+
+#endif /* _MYST_ENC_SECRET_H */
@@ -12,7 +12,13 @@
 
 #include <myst/iov.h>
 #include <myst/tcall.h>
+#include "../crypto.h"
+#include "coredump.h"
+#include "edmm.h"
+#include "hostpoll.h"
+#include "keyrelease.h"
 #include "myst_t.h"
+#include "secret.h"
 
 #define RETURN(EXPR) return ((EXPR) == OE_OK ? ret : -EINVAL)
 
@@ -711,7 +717,7 @@ static long _ioctl(int fd, unsigned long request, void* argp)
         default:
         {
             /* unsupported ioctl */
-            return -ENOTSUP;
+            return -ENOTTY;
         }
     }
 
@@ -758,6 +764,23 @@ done:
     return ret;
 }
 
+static long _setpriority(int which, id_t who, int prio)
+{
+    long ret = 0;
+    long retval;
+
+    if (myst_setpriority_ocall(&retval, which, who, prio) != OE_OK)
+    {
+        ret = -EINVAL;
+        goto done;
+    }
+
+    ret = retval;
+
+done:
+    return ret;
+}
+
 static long _fchmod(int fd, mode_t mode)
 {
     long ret = 0;
@@ -821,6 +844,10 @@ static long _poll(struct pollfd* fds, nfds_t nfds, int timeout)
         copy = NULL;
     }
 
+    /* return at once if the host poller has seen some of them ready */
+    if (fds && (ret = myst_hostpoll_ready(fds, nfds)) > 0)
+        goto done;
+
     if (myst_poll_ocall(&retval, copy, nfds, timeout) != OE_OK)
     {
         ret = -EINVAL;
@@ -1379,7 +1406,7 @@ done:
 }
 #endif
 
-long myst_handle_tcall(long n, long params[6])
+static long _handle_tcall(long n, long params[6])
 {
     const long a = params[0];
     const long b = params[1];
@@ -1499,6 +1526,10 @@ long myst_handle_tcall(long n, long params[6])
         {
             return _sched_yield();
         }
+        case SYS_setpriority:
+        {
+            return _setpriority((int)a, (id_t)b, (int)c);
+        }
         case SYS_fchmod:
         {
             return _fchmod((int)a, (mode_t)b);
@@ -1597,9 +1628,98 @@ long myst_handle_tcall(long n, long params[6])
                 (int)a, (const char*)b, (const struct timespec*)c, (int)d);
         }
 #endif
+        case MYST_TCALL_EXPORT_COVERAGE:
+        {
+            return myst_tcall_export_coverage(
+                (const char*)a, (const void*)b, (size_t)c);
+        }
+        case MYST_TCALL_WRITE_LOG:
+        {
+            return myst_tcall_write_log(
+                (const char*)a,
+                (const void*)b,
+                (size_t)c,
+                (size_t)d,
+                (size_t)e);
+        }
+        case MYST_TCALL_KEY_RELEASE:
+        {
+            return myst_key_release(
+                (const myst_key_release_request_t*)a,
+                (void*)b,
+                (size_t)c,
+                (size_t*)d);
+        }
+        case MYST_TCALL_UNSEAL_SECRET:
+        {
+            return myst_unseal_secret(
+                (const void*)a, (size_t)b, (void*)c, (size_t)d, (size_t*)e);
+        }
+        case MYST_TCALL_ATTESTED_HTTPS_GET:
+        {
+            return myst_attested_https_get(
+                (const myst_attested_https_request_t*)a,
+                (void*)b,
+                (size_t)c,
+                (size_t*)d);
+        }
+        case MYST_TCALL_SET_CRASH_HANDLER:
+        {
+            return myst_set_crash_handler((myst_crash_handler_t)a);
+        }
+        case MYST_TCALL_RSA_OAEP_ENCRYPT:
+        {
+            return myst_rsa_oaep_encrypt(
+                (const char*)a,
+                (const void*)b,
+                (size_t)c,
+                (void*)d,
+                (size_t)e,
+                (size_t*)f);
+        }
+        case MYST_TCALL_AES_GCM_ENCRYPT:
+        {
+            return myst_aes_gcm_encrypt(
+                (const uint8_t*)a,
+                (const uint8_t*)b,
+                (const void*)c,
+                (size_t)d,
+                (void*)e,
+                (uint8_t*)f);
+        }
+        case MYST_TCALL_CLOCK_ADJTIME:
+        {
+            return myst_tcall_clock_adjtime((clockid_t)a, (struct timex*)b);
+        }
+        case MYST_TCALL_GET_BLOCK_DEVICE_SIZE:
+        {
+            return myst_tcall_get_block_device_size((int)a, (uint64_t*)b);
+        }
+        case MYST_TCALL_SYNC_BLOCK_DEVICE:
+        {
+            return myst_tcall_sync_block_device((int)a);
+        }
+        case MYST_TCALL_MPROTECT:
+        {
+            return myst_edmm_protect((void*)a, (size_t)b, (int)c);
+        }
+        case MYST_TCALL_SET_FAULT_HANDLER:
+        {
+            return myst_set_fault_handler((myst_fault_handler_t)a);
+        }
         default:
         {
             return -ENOTSUP;
         }
     }
 }
+
+long myst_handle_tcall(long n, long params[6])
+{
+    long ret = _handle_tcall(n, params);
+
+    /* after the call: a poller result taken before it is then discarded */
+    myst_hostpoll_consume(n, (int)params[0]);
+
+    return ret;
+}
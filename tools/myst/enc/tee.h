@@ -0,0 +1,73 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_ENC_TEE_H
+#define _MYST_ENC_TEE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/*
+**==============================================================================
+**
+** The trusted execution environment behind the enclave code in this
+** directory. Attestation, sealing, entropy and the checks on host memory go
+** through these functions rather than calling Open Enclave directly, so that
+** another TEE (such as TDX or SEV-SNP) only needs a new backend. Calls to the
+** host are the myst_*_ocall() functions declared by myst.edl: for SGX they
+** are generated by oeedger8r, and another backend provides its own.
+**
+**==============================================================================
+*/
+
+typedef struct myst_tee
+{
+    /* the name attestation services know the TEE by (e.g. "SgxEnclave") */
+    const char* name;
+
+    /* fill data with random bytes from the TEE */
+    int (*random)(void* data, size_t size);
+
+    /* get evidence (a quote) whose report data is the given bytes */
+    int (*get_evidence)(
+        const uint8_t* report_data,
+        size_t report_data_size,
+        uint8_t** evidence,
+        size_t* evidence_size);
+
+    void (*free_evidence)(uint8_t* evidence);
+
+    /* a self-signed certificate that carries evidence (for attested TLS) */
+    int (*gen_creds)(
+        uint8_t** cert,
+        size_t* cert_size,
+        uint8_t** private_key,
+        size_t* private_key_size);
+
+    void (*free_creds)(
+        uint8_t* cert,
+        size_t cert_size,
+        uint8_t* private_key,
+        size_t private_key_size);
+
+    /* unseal a blob that was sealed for this enclave */
+    int (*unseal)(
+        const void* blob,
+        size_t blob_size,
+        uint8_t** data,
+        size_t* data_size);
+
+    void (*free_unsealed)(uint8_t* data);
+
+    /* whether [ptr, ptr + size) is entirely host memory */
+    bool (*is_outside)(const void* ptr, size_t size);
+
+    /* whether the enclave can be debugged (so its memory is not private) */
+    bool (*debug_mode)(void);
+} myst_tee_t;
+
+/* the TEE this enclave runs in */
+const myst_tee_t* myst_tee(void);
+
+#endif /* _MYST_ENC_TEE_H */
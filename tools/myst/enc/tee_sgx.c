@@ -0,0 +1,129 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+
+#include <openenclave/attestation/attester.h>
+#include <openenclave/attestation/sgx/evidence.h>
+#include <openenclave/enclave.h>
+#include <openenclave/seal.h>
+
+#include "tee.h"
+
+/* defined by the SGX target (target/sgx/enclave/gencreds.c) */
+int myst_gen_creds(
+    uint8_t** cert_out,
+    size_t* cert_size_out,
+    uint8_t** private_key_out,
+    size_t* private_key_size_out);
+
+void myst_free_creds(
+    uint8_t* cert,
+    size_t cert_size,
+    uint8_t* private_key,
+    size_t private_key_size);
+
+static int _random(void* data, size_t size)
+{
+    return oe_random(data, size) == OE_OK ? 0 : -EIO;
+}
+
+static int _get_evidence(
+    const uint8_t* report_data,
+    size_t report_data_size,
+    uint8_t** evidence,
+    size_t* evidence_size)
+{
+    int ret = 0;
+    static const oe_uuid_t format = {OE_FORMAT_UUID_RAW_SGX_QUOTE_ECDSA};
+
+    if (oe_attester_initialize() != OE_OK)
+        return -ENOTSUP;
+
+    /* a raw quote carries the custom claims as its report data */
+    if (oe_get_evidence(
+            &format,
+            0,
+            report_data,
+            report_data_size,
+            NULL,
+            0,
+            evidence,
+            evidence_size,
+            NULL,
+            NULL) != OE_OK)
+    {
+        ret = -ENOTSUP;
+    }
+
+    oe_attester_shutdown();
+    return ret;
+}
+
+static void _free_evidence(uint8_t* evidence)
+{
+    oe_free_evidence(evidence);
+}
+
+static int _unseal(
+    const void* blob,
+    size_t blob_size,
+    uint8_t** data,
+    size_t* data_size)
+{
+    if (oe_unseal(blob, blob_size, NULL, 0, data, data_size) != OE_OK)
+        return -EACCES;
+
+    return 0;
+}
+
+static void _free_unsealed(uint8_t* data)
+{
+    oe_free(data);
+}
+
+static bool _is_outside(const void* ptr, size_t size)
+{
+    return oe_is_outside_enclave(ptr, size);
+}
+
+static bool _debug_mode(void)
+{
+    bool ret = false;
+    uint8_t* buf = NULL;
+    size_t buf_size;
+    oe_report_t report;
+
+    if (oe_get_report_v2(0, NULL, 0, NULL, 0, &buf, &buf_size) != OE_OK)
+        goto done;
+
+    if (oe_parse_report(buf, buf_size, &report) != OE_OK)
+        goto done;
+
+    ret = (report.identity.attributes & OE_REPORT_ATTRIBUTES_DEBUG) != 0;
+
+done:
+
+    if (buf)
+        oe_free_report(buf);
+
+    return ret;
+}
+
+static const myst_tee_t _sgx = {
+    .name = "SgxEnclave",
+    .random = _random,
+    .get_evidence = _get_evidence,
+    .free_evidence = _free_evidence,
+    .gen_creds = myst_gen_creds,
+    .free_creds = myst_free_creds,
+    .unseal = _unseal,
+    .free_unsealed = _free_unsealed,
+    .is_outside = _is_outside,
+    .debug_mode = _debug_mode,
+};
+
+const myst_tee_t* myst_tee(void)
+{
+    return &_sgx;
+}
@@ -37,12 +37,17 @@ void get_archive_options(
     size_t* num_pubkeys_out,
     const char* roothashes[],
     size_t max_roothashes,
-    size_t* num_roothashes_out)
+    size_t* num_roothashes_out,
+    const char* manifests[],
+    size_t max_manifests,
+    size_t* num_manifests_out)
 {
     const char* pubkey;
     const char* roothash;
+    const char* manifest;
     size_t num_pubkeys = 0;
     size_t num_roothashes = 0;
+    size_t num_manifests = 0;
 
     while (_getopt(argc, argv, "--pubkey", &pubkey) == 0)
     {
@@ -70,8 +75,22 @@ void get_archive_options(
         roothashes[num_roothashes++] = roothash;
     }
 
+    while (_getopt(argc, argv, "--manifest", &manifest) == 0)
+    {
+        struct stat statbuf;
+
+        if (num_manifests == max_manifests)
+            _err("too many --manifest options (> %u)", max_manifests);
+
+        if (stat(manifest, &statbuf) != 0)
+            _err("no such file for --manifest options: %s", manifest);
+
+        manifests[num_manifests++] = manifest;
+    }
+
     *num_pubkeys_out = num_pubkeys;
     *num_roothashes_out = num_roothashes;
+    *num_manifests_out = num_manifests;
 }
 
 /* create the CPIO archive */
@@ -80,6 +99,8 @@ void create_archive(
     size_t num_pubkeys,
     const char* roothashes[],
     size_t num_roothashes,
+    const char* manifests[],
+    size_t num_manifests,
     char archive_path[PATH_MAX])
 {
     char dir_template[] = "/tmp/mystXXXXXX";
@@ -145,6 +166,33 @@ void create_archive(
         }
     }
 
+    /* create the manifests directory */
+    {
+        char path[PATH_MAX];
+        const int n = sizeof(path);
+
+        if (snprintf(path, n, "%s/manifests", dirname) >= n)
+            _err("path too long");
+
+        if (mkdir(path, 0700) != 0)
+            _err("failed to create directory: %s", path);
+
+        /* create <dirname>/manifests/<manifest> files */
+        for (size_t i = 0; i < num_manifests; i++)
+        {
+            const char* manifest = manifests[i];
+            const char* basename = myst_basename(manifest);
+
+            if (snprintf(path, n, "%s/manifests/%s", dirname, basename) >= n)
+            {
+                _err("path too long");
+            }
+
+            if (myst_copy_file(manifest, path) != 0)
+                _err("failed to copy %s to %s", manifest, path);
+        }
+    }
+
     /* pack the directory into a CPIO archive */
     if (myst_cpio_pack(dirname, filename) != 0)
         _err("failed to CPIO archive from %s", dirname);
@@ -9,6 +9,8 @@ void create_archive(
     size_t num_pubkeys,
     const char* roothashes[],
     size_t num_roothashes,
+    const char* manifests[],
+    size_t num_manifests,
     char archive_path[PATH_MAX]);
 
 void get_archive_options(
@@ -19,6 +21,9 @@ void get_archive_options(
     size_t* num_pubkeys,
     const char* roothashes[],
     size_t max_roothashes,
-    size_t* num_roothashes);
+    size_t* num_roothashes,
+    const char* manifests[],
+    size_t max_manifests,
+    size_t* num_manifests);
 
 #endif /* MYST_HOST_ARCHIVE_H */
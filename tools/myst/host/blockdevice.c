@@ -28,3 +28,13 @@ int myst_read_block_device_ocall(
 {
     return myst_read_block_device(blkdev, blkno, blocks, num_blocks);
 }
+
+int myst_get_block_device_size_ocall(int blkdev, uint64_t* size)
+{
+    return myst_get_block_device_size(blkdev, size);
+}
+
+int myst_sync_block_device_ocall(int blkdev)
+{
+    return myst_sync_block_device(blkdev);
+}
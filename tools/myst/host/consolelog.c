@@ -0,0 +1,150 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include <myst/tcall.h>
+
+/* host files that receive console output (one per stream) */
+#define MAX_LOGS 8
+
+typedef struct log_file
+{
+    char path[PATH_MAX];
+    int fd;
+    size_t size;
+} log_file_t;
+
+static log_file_t _logs[MAX_LOGS];
+static size_t _num_logs;
+static pthread_mutex_t _lock = PTHREAD_MUTEX_INITIALIZER;
+
+static int _open(log_file_t* log, int flags)
+{
+    struct stat st;
+
+    if ((log->fd = open(log->path, O_WRONLY | O_CREAT | flags, 0644)) < 0)
+        return -errno;
+
+    if (fstat(log->fd, &st) != 0)
+    {
+        int err = -errno;
+        close(log->fd);
+        log->fd = -1;
+        return err;
+    }
+
+    log->size = (size_t)st.st_size;
+    return 0;
+}
+
+static log_file_t* _find(const char* path)
+{
+    log_file_t* log;
+
+    for (size_t i = 0; i < _num_logs; i++)
+    {
+        if (strcmp(_logs[i].path, path) == 0)
+            return &_logs[i];
+    }
+
+    if (_num_logs == MAX_LOGS || strlen(path) >= sizeof(log->path))
+        return NULL;
+
+    log = &_logs[_num_logs];
+    strcpy(log->path, path);
+
+    if (_open(log, O_APPEND) != 0)
+        return NULL;
+
+    _num_logs++;
+    return log;
+}
+
+/* rename path.N-1 -> path.N ... path -> path.1 and start a new file */
+static int _rotate(log_file_t* log, size_t max_files)
+{
+    char from[PATH_MAX + 8];
+    char to[PATH_MAX + 8];
+
+    close(log->fd);
+    log->fd = -1;
+
+    for (size_t i = max_files; i > 1; i--)
+    {
+        snprintf(from, sizeof(from), "%s.%zu", log->path, i - 1);
+        snprintf(to, sizeof(to), "%s.%zu", log->path, i);
+        rename(from, to);
+    }
+
+    snprintf(to, sizeof(to), "%s.1", log->path);
+    rename(log->path, to);
+
+    return _open(log, O_TRUNC);
+}
+
+long myst_tcall_write_log(
+    const char* path,
+    const void* buf,
+    size_t count,
+    size_t max_size,
+    size_t max_files)
+{
+    long ret = 0;
+    log_file_t* log;
+    const char* p = buf;
+    size_t n = count;
+
+    if (!path || (!buf && count))
+        return -EINVAL;
+
+    pthread_mutex_lock(&_lock);
+
+    if (!(log = _find(path)))
+    {
+        ret = -ENOENT;
+        goto done;
+    }
+
+    /* reopen the file if an earlier rotation failed */
+    if (log->fd < 0 && (ret = _open(log, O_APPEND)) != 0)
+        goto done;
+
+    /* rotate before the log grows beyond the maximum size */
+    if (max_size && log->size && log->size + count > max_size)
+    {
+        if ((ret = _rotate(log, max_files)) != 0)
+            goto done;
+    }
+
+    while (n)
+    {
+        ssize_t r = write(log->fd, p, n);
+
+        if (r < 0)
+        {
+            if (errno == EINTR)
+                continue;
+
+            ret = -errno;
+            goto done;
+        }
+
+        p += r;
+        n -= (size_t)r;
+        log->size += (size_t)r;
+    }
+
+    ret = (long)count;
+
+done:
+    pthread_mutex_unlock(&_lock);
+    return ret;
+}
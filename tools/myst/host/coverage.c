@@ -0,0 +1,68 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <limits.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include <myst/eraise.h>
+#include <myst/file.h>
+#include <myst/strings.h>
+#include <myst/tcall.h>
+
+/* writes coverage data (.gcda, .profraw) under $MYST_EXPORT_COVERAGE/coverage
+ * (or the current directory) at the path it had in the root file system */
+long myst_tcall_export_coverage(
+    const char* path,
+    const void* data,
+    size_t size)
+{
+    long ret = 0;
+    const char* env;
+    char root[PATH_MAX];
+    char file[PATH_MAX];
+    char dir[PATH_MAX];
+    char* p;
+
+    if (!path || (!data && size))
+        ERAISE(-EINVAL);
+
+    if ((env = getenv("MYST_EXPORT_COVERAGE")))
+    {
+        struct stat buf;
+
+        if (stat(env, &buf) != 0 || !S_ISDIR(buf.st_mode))
+            ERAISE(-ENOTDIR);
+
+        if (myst_strlcpy(root, env, sizeof(root)) >= sizeof(root))
+            ERAISE(-ENAMETOOLONG);
+    }
+    else
+    {
+        if (!(getcwd(root, sizeof(root))))
+            ERAISE(-errno);
+    }
+
+    if (snprintf(file, sizeof(file), "%s/coverage/%s", root, path) >=
+        (int)sizeof(file))
+        ERAISE(-ENAMETOOLONG);
+
+    if (myst_strlcpy(dir, file, sizeof(dir)) >= sizeof(dir))
+        ERAISE(-ENAMETOOLONG);
+
+    /* Chop off the final component */
+    if ((p = strrchr(dir, '/')))
+        *p = '\0';
+    else
+        ERAISE(-EINVAL);
+
+    ECHECK(myst_mkdirhier(dir, 0777));
+    ECHECK(myst_write_file(file, data, size));
+
+done:
+    return ret;
+}
@@ -2,6 +2,7 @@
 // Licensed under the MIT License.
 
 #include <assert.h>
+#include <cpuid.h>
 #include <errno.h>
 #include <libgen.h>
 #include <limits.h>
@@ -12,6 +13,8 @@
 #include <poll.h>
 #include <pthread.h>
 #include <stdlib.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
 #include <sys/stat.h>
 #include <sys/types.h>
 #include <sys/user.h>
@@ -35,6 +38,7 @@
 #include "archive.h"
 #include "exec.h"
 #include "myst_u.h"
+#include "poller.h"
 #include "regions.h"
 #include "utils.h"
 
@@ -127,6 +131,53 @@ long myst_export_file_ocall(const char* path, const void* data, size_t size)
     return myst_tcall_export_file(path, data, size);
 }
 
+long myst_export_coverage_ocall(
+    const char* path,
+    const void* data,
+    size_t size)
+{
+    return myst_tcall_export_coverage(path, data, size);
+}
+
+long myst_write_log_ocall(
+    const char* path,
+    const void* buf,
+    size_t count,
+    size_t max_size,
+    size_t max_files)
+{
+    return myst_tcall_write_log(path, buf, count, max_size, max_files);
+}
+
+/* let the driver add (EAUG) the pages of the mman region that were left out
+ * of the enclave image: it only does so where the host mapping allows it */
+long myst_edmm_map_ocall(uint64_t addr, size_t length)
+{
+    if (mprotect((void*)addr, length, PROT_READ | PROT_WRITE | PROT_EXEC) != 0)
+        return -errno;
+
+    return 0;
+}
+
+long myst_edmm_protect_ocall(uint64_t addr, size_t length, int prot)
+{
+    if (mprotect((void*)addr, length, prot) != 0)
+        return -errno;
+
+    return 0;
+}
+
+/* whether the CPU supports SGX2 (CPUID.(EAX=12H,ECX=0):EAX[1]) */
+static bool _have_sgx2(void)
+{
+    unsigned int eax, ebx, ecx, edx;
+
+    if (!__get_cpuid_count(0x12, 0, &eax, &ebx, &ecx, &edx))
+        return false;
+
+    return (eax & 0x2) != 0;
+}
+
 int exec_launch_enclave(
     const char* enc_path,
     oe_enclave_type_t type,
@@ -141,6 +192,9 @@ int exec_launch_enclave(
     myst_buf_t argv_buf = MYST_BUF_INITIALIZER;
     myst_buf_t envp_buf = MYST_BUF_INITIALIZER;
 
+    if (get_region_details()->mman_committed_size && !_have_sgx2())
+        _err("CommittedMemorySize requires a CPU that supports SGX2");
+
     /* Load the enclave: calls oe_region_add_regions() */
     r = oe_create_myst_enclave(enc_path, type, flags, NULL, 0, &_enclave);
 
@@ -156,7 +210,22 @@ int exec_launch_enclave(
         _err("failed to serialize envp stings");
 
     /* Get clock times right before entering the enclave */
-    shm_create_clock(&shared_memory, CLOCK_TICK);
+    {
+        const int source = get_region_details()->host_clock_source;
+
+        if (shm_create_clock(&shared_memory, CLOCK_TICK, source) != 0)
+            _err("failed to start the host clock");
+    }
+
+    /* Let the kernel stop the application gracefully on SIGTERM */
+    shared_memory.shutdown = shm_create_shutdown((uint64_t)&_event);
+
+    /* Let the kernel write to the console without leaving the enclave */
+    shared_memory.console = shm_create_console();
+
+    /* Let the enclave see which host descriptors are ready */
+    if (poller_start(&shared_memory) != 0)
+        _err("failed to start the host poller");
 
     /* Enter the enclave and run the program */
     r = myst_enter_ecall(
@@ -178,6 +247,7 @@ int exec_launch_enclave(
         _err("failed to terminate enclave: result=%s", oe_result_str(r));
 
     shm_free_clock(&shared_memory);
+    shm_free_console(shared_memory.console);
 
     free(argv_buf.data);
     free(envp_buf.data);
@@ -208,6 +278,9 @@ Options:\n\
     --app-config-path <json> -- specifies the configuration json file for\n\
                                 running an unsigned binary. The file can be\n\
                                 the same one used for the signing process.\n\
+    --env <name=value>   -- set an environment variable for the application\n\
+                            (may be repeated); a signed configuration must\n\
+                            allow the name (see LaunchOverrides)\n\
 \n"
 
 int exec_action(int argc, const char* argv[], const char* envp[])
@@ -221,12 +294,16 @@ int exec_action(int argc, const char* argv[], const char* envp[])
     static const size_t max_roothashes = 128;
     const char* roothashes[max_roothashes];
     size_t num_roothashes = 0;
+    static const size_t max_manifests = 128;
+    const char* manifests[max_manifests];
+    size_t num_manifests = 0;
     const region_details* details;
     int return_status;
     char archive_path[PATH_MAX];
     char rootfs_path[] = "/tmp/mystXXXXXX";
     uint64_t heap_size = 0;
     const char* commandline_config = NULL;
+    const char** env;
 
     assert(strcmp(argv[1], "exec") == 0 || strcmp(argv[1], "exec-sgx") == 0);
 
@@ -252,6 +329,10 @@ int exec_action(int argc, const char* argv[], const char* envp[])
         if (cli_getopt(&argc, argv, "--export-ramfs", NULL) == 0)
             options.export_ramfs = true;
 
+        /* Get --export-coverage option */
+        if (cli_getopt(&argc, argv, "--export-coverage", NULL) == 0)
+            options.export_coverage = true;
+
         /* Get --memory-size or --user-mem-size option */
         {
             const char* opt;
@@ -282,6 +363,9 @@ int exec_action(int argc, const char* argv[], const char* envp[])
          */
         cli_getopt(&argc, argv, "--app-config-path", &commandline_config);
 
+        /* Get --env options */
+        env = cli_getopt_env(&argc, argv, envp);
+
         /* Get --help option */
         if ((cli_getopt(&argc, argv, "--help", NULL) == 0) ||
             (cli_getopt(&argc, argv, "-h", NULL) == 0))
@@ -290,15 +374,18 @@ int exec_action(int argc, const char* argv[], const char* envp[])
             return 1;
         }
 
-        /* Set export_ramfs option based on MYST_ENABLE_GCOV env variable */
+        /* Set export options based on MYST_ENABLE_GCOV env variable */
         {
             const char* val;
 
             if ((val = getenv("MYST_ENABLE_GCOV")) && strcmp(val, "1") == 0)
+            {
                 options.export_ramfs = true;
+                options.export_coverage = true;
+            }
         }
 
-        /* Get --pubkey=filename and --roothash=filename options */
+        /* Get --pubkey, --roothash and --manifest options */
         get_archive_options(
             &argc,
             argv,
@@ -307,7 +394,10 @@ int exec_action(int argc, const char* argv[], const char* envp[])
             &num_pubkeys,
             roothashes,
             max_roothashes,
-            &num_roothashes);
+            &num_roothashes,
+            manifests,
+            max_manifests,
+            &num_manifests);
     }
 
     if (argc < 4)
@@ -319,7 +409,13 @@ int exec_action(int argc, const char* argv[], const char* envp[])
     const char* rootfs = argv[2];
     const char* program = argv[3];
     create_archive(
-        pubkeys, num_pubkeys, roothashes, num_roothashes, archive_path);
+        pubkeys,
+        num_pubkeys,
+        roothashes,
+        num_roothashes,
+        manifests,
+        num_manifests,
+        archive_path);
 
     /* copy the rootfs path to the options */
     if (myst_strlcpy(options.rootfs, rootfs, sizeof(options.rootfs)) >=
@@ -359,9 +455,10 @@ int exec_action(int argc, const char* argv[], const char* envp[])
     unlink(archive_path);
 
     return_status = exec_launch_enclave(
-        details->enc.path, type, flags, argv + 3, envp, &options);
+        details->enc.path, type, flags, argv + 3, env, &options);
 
     free_region_details();
+    free(env);
 
     if (rootfs == rootfs_path)
         unlink(rootfs_path);
@@ -401,6 +498,11 @@ long myst_sched_yield_ocall(void)
     return (sched_yield() == 0) ? 0 : -errno;
 }
 
+long myst_setpriority_ocall(int which, uint32_t who, int prio)
+{
+    return (setpriority(which, who, prio) == 0) ? 0 : -errno;
+}
+
 long myst_fchmod_ocall(int fd, uint32_t mode)
 {
     if (fchmod(fd, mode) != 0)
@@ -411,17 +513,12 @@ long myst_fchmod_ocall(int fd, uint32_t mode)
 
 long myst_poll_wake_ocall(void)
 {
-    extern long myst_tcall_poll_wake();
-
-    return myst_tcall_poll_wake();
+    return poller_wake();
 }
 
 long myst_poll_ocall(struct pollfd* fds, unsigned long nfds, int timeout)
 {
-    extern long myst_tcall_poll(
-        struct pollfd * lfds, unsigned long nfds, int timeout);
-
-    return myst_tcall_poll(fds, nfds, timeout);
+    return poller_poll(fds, nfds, timeout);
 }
 
 int myst_load_fssig_ocall(const char* path, myst_fssig_t* fssig)
@@ -11,6 +11,7 @@
 #include <sys/mman.h>
 #include <syscall.h>
 #include <time.h>
+#include <unistd.h>
 
 #include <myst/args.h>
 #include <myst/cpio.h>
@@ -20,6 +21,7 @@
 #include <myst/kernel.h>
 #include <myst/reloc.h>
 #include <myst/round.h>
+#include <myst/shm.h>
 #include <myst/strings.h>
 #include <myst/tcall.h>
 #include <myst/thread.h>
@@ -28,6 +30,7 @@
 #include "../shared.h"
 #include "archive.h"
 #include "exec_linux.h"
+#include "tee_linux.h"
 #include "utils.h"
 
 #define USAGE_FORMAT \
@@ -53,6 +56,9 @@ Options:\n\
     --app-config-path <json> -- specifies the configuration json file for\n\
                                 running an unsigned binary. The file can be\n\
                                 the same one used for the signing process.\n\
+    --env <name=value>   -- set an environment variable for the application\n\
+                            (may be repeated); a signed configuration must\n\
+                            allow the name (see LaunchOverrides)\n\
 \n\
 "
 
@@ -61,6 +67,7 @@ struct options
     bool trace_errors;
     bool trace_syscalls;
     bool export_ramfs;
+    bool export_coverage;
     char rootfs[PATH_MAX];
 };
 
@@ -103,12 +110,19 @@ static void _get_options(
     if (cli_getopt(argc, argv, "--export-ramfs", NULL) == 0)
         options->export_ramfs = true;
 
-    /* Set export_ramfs option based on MYST_ENABLE_GCOV env variable */
+    /* Get --export-coverage option */
+    if (cli_getopt(argc, argv, "--export-coverage", NULL) == 0)
+        options->export_coverage = true;
+
+    /* Set export options based on MYST_ENABLE_GCOV env variable */
     {
         const char* val;
 
         if ((val = getenv("MYST_ENABLE_GCOV")) && strcmp(val, "1") == 0)
+        {
             options->export_ramfs = true;
+            options->export_coverage = true;
+        }
     }
 
     /* Get --memory-size or --memory-size option */
@@ -272,6 +286,13 @@ static void _release_regions(struct regions* r)
 }
 
 /* the address of this is eventually passed to futex (uaddr argument) */
+/* the application sees the same CPUs as the host */
+static size_t _get_num_cpus(void)
+{
+    const long n = sysconf(_SC_NPROCESSORS_ONLN);
+    return n > 0 ? (size_t)n : 1;
+}
+
 static __thread int _thread_event;
 
 static int _enter_kernel(
@@ -288,6 +309,7 @@ static int _enter_kernel(
 {
     int ret = 0;
     myst_kernel_args_t args;
+    myst_console_ring_t* console = NULL;
     const elf_ehdr_t* ehdr = regions->libmystkernel.image_data;
     myst_kernel_entry_t entry;
     myst_args_t env;
@@ -385,6 +407,46 @@ static int _enter_kernel(
     args.envp = env.data;
     args.cwd = cwd;
     args.hostname = hostname;
+    args.uname_release = parsed_data.uname_release;
+    args.uname_version = parsed_data.uname_version;
+    args.uname_machine = parsed_data.uname_machine;
+    args.dns_servers = (const char**)parsed_data.dns_servers;
+    args.num_dns_servers = parsed_data.dns_servers_count;
+    args.console_log_stdout = parsed_data.console_log_stdout;
+    args.console_log_stderr = parsed_data.console_log_stderr;
+    args.console_log_timestamps = parsed_data.console_log_timestamps;
+    args.console_log_max_size = parsed_data.console_log_max_size;
+    args.console_log_max_files = parsed_data.console_log_max_files;
+    args.console_log_only = parsed_data.console_log_only;
+    args.audit_log = parsed_data.audit_log;
+    args.audit_log_path = parsed_data.audit_log_path;
+    args.rootfs_overlay = parsed_data.rootfs_overlay;
+    args.rootfs_layers.store = parsed_data.rootfs_layer_store;
+    args.rootfs_layers.layers = (const char**)parsed_data.rootfs_layers;
+    args.rootfs_layers.num_layers = parsed_data.rootfs_layers_count;
+    args.syscall_policy = parsed_data.syscall_policy;
+    args.oom_policy = parsed_data.oom_policy;
+    args.mem_low_watermark = parsed_data.mem_low_watermark;
+    args.debug_malloc = parsed_data.debug_malloc;
+    args.debug_malloc_quarantine = parsed_data.debug_malloc_quarantine;
+    args.debug_signal_safety = parsed_data.debug_signal_safety;
+    args.wx.enabled = parsed_data.write_xor_execute;
+    args.wx.allow = (const char**)parsed_data.write_xor_execute_allow;
+    args.wx.num_allow = parsed_data.write_xor_execute_allow_count;
+    args.quote_cache_lifetime = parsed_data.quote_cache_lifetime;
+    args.cpu_time_limit = parsed_data.cpu_time_limit;
+    args.cpu_time_hard_limit = parsed_data.cpu_time_hard_limit;
+    args.wall_time_limit = parsed_data.wall_time_limit;
+    args.key_release = parsed_data.key_release;
+    args.secrets = parsed_data.secrets;
+    args.coredump = parsed_data.coredump;
+    args.services = parsed_data.services;
+    args.host_imports = parsed_data.host_imports;
+    args.disks = parsed_data.disks;
+    args.mount_policy = parsed_data.mount_policy;
+    args.shutdown.grace_period = parsed_data.shutdown_grace_period;
+    args.shutdown.request = shm_create_shutdown((uint64_t)&_thread_event);
+    args.console_ring = console = shm_create_console();
     args.mman_data = regions->mman_data;
     args.mman_size = regions->mman_size;
     args.rootfs_data = regions->rootfs_data;
@@ -394,10 +456,12 @@ static int _enter_kernel(
     args.crt_data = regions->libmystcrt.image_data;
     args.crt_size = regions->libmystcrt.image_size;
     args.max_threads = LONG_MAX;
+    args.num_cpus = _get_num_cpus();
     args.trace_errors = options->trace_errors;
     args.trace_syscalls = options->trace_syscalls;
     args.have_syscall_instruction = true;
     args.export_ramfs = options->export_ramfs;
+    args.export_coverage = options->export_coverage;
     args.event = (uint64_t)&_thread_event;
     args.tee_debug_mode = true;
     args.tcall = tcall;
@@ -422,9 +486,18 @@ static int _enter_kernel(
         ERAISE(-EINVAL);
     }
 
+    /* Let the kernel take the faults of stacks that overflow */
+    if (tee_linux_setup_thread() != 0)
+    {
+        snprintf(err, err_size, "failed to set the signal stack");
+        ERAISE(-EINVAL);
+    }
+
     *return_status = (*entry)(&args);
 
 done:
+    shm_free_console(console);
+
     if (regions->app_config && regions->app_config_size)
         free_config(&parsed_data);
 
@@ -436,7 +509,7 @@ done:
 
 static long _tcall(long n, long params[6])
 {
-    return myst_tcall(n, params);
+    return tee_linux_tcall(n, params);
 }
 
 int exec_linux_action(int argc, const char* argv[], const char* envp[])
@@ -452,16 +525,23 @@ int exec_linux_action(int argc, const char* argv[], const char* envp[])
     static const size_t max_roothashes = 128;
     const char* roothashes[max_roothashes];
     size_t num_roothashes = 0;
+    static const size_t max_manifests = 128;
+    const char* manifests[max_manifests];
+    size_t num_manifests = 0;
     char archive_path[PATH_MAX];
     char rootfs_path[] = "/tmp/mystXXXXXX";
     size_t heap_size = 0;
     const char* app_config_path = NULL;
+    const char** env;
 
     (void)program_arg;
 
     /* Get the command-line options */
     _get_options(&argc, argv, &options, &heap_size, &app_config_path);
 
+    /* Get --env options */
+    env = cli_getopt_env(&argc, argv, envp);
+
     /* Get --pubkey=filename options */
     get_archive_options(
         &argc,
@@ -471,7 +551,10 @@ int exec_linux_action(int argc, const char* argv[], const char* envp[])
         &num_pubkeys,
         roothashes,
         max_roothashes,
-        &num_roothashes);
+        &num_roothashes,
+        manifests,
+        max_manifests,
+        &num_manifests);
 
     /* Check usage */
     if (argc < 4)
@@ -483,7 +566,13 @@ int exec_linux_action(int argc, const char* argv[], const char* envp[])
     rootfs_arg = argv[2];
     program_arg = argv[3];
     create_archive(
-        pubkeys, num_pubkeys, roothashes, num_roothashes, archive_path);
+        pubkeys,
+        num_pubkeys,
+        roothashes,
+        num_roothashes,
+        manifests,
+        num_manifests,
+        archive_path);
 
     /* copy the rootfs path to the options */
     if (myst_strlcpy(options.rootfs, rootfs_arg, sizeof(options.rootfs)) >=
@@ -517,7 +606,7 @@ int exec_linux_action(int argc, const char* argv[], const char* envp[])
     unlink(archive_path);
 
     int envc = 0;
-    while (envp[envc] != NULL)
+    while (env[envc] != NULL)
     {
         envc++;
     }
@@ -532,7 +621,7 @@ int exec_linux_action(int argc, const char* argv[], const char* envp[])
             argc,
             argv,
             envc,
-            envp,
+            env,
             &options,
             &regions,
             _tcall,
@@ -545,6 +634,7 @@ int exec_linux_action(int argc, const char* argv[], const char* envp[])
 
     /* release the regions memory */
     _release_regions(&regions);
+    free(env);
 
 #if 0
     if (rootfs_arg == rootfs_path)
@@ -567,6 +657,12 @@ static void* _thread_func(void* arg)
     uint64_t cookie = (uint64_t)arg;
     uint64_t event = (uint64_t)&_thread_event;
 
+    if (tee_linux_setup_thread() != 0)
+    {
+        fprintf(stderr, "tee_linux_setup_thread() failed\n");
+        exit(1);
+    }
+
     if (myst_run_thread(cookie, event) != 0)
     {
         fprintf(stderr, "myst_run_thread() failed\n");
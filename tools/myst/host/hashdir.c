@@ -0,0 +1,214 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include <myst/dirhash.h>
+#include <myst/hex.h>
+#include <myst/sha256.h>
+
+#define USAGE_HASHDIR \
+    "\n\
+Usage: %s hashdir <directory>\n\
+\n\
+Synopsis:\n\
+    Print the hash of a directory tree, as checked by the kernel when the\n\
+    directory is imported with \"HostDirectoryImports\" in config.json.\n\
+    The hash covers the names, contents and permissions of the directories,\n\
+    regular files and symbolic links below the directory.\n\
+\n"
+
+static int _cmp(const void* a, const void* b)
+{
+    return strcmp(*(char* const*)a, *(char* const*)b);
+}
+
+static int _hash_file(myst_sha256_ctx_t* ctx, const char* path, size_t size)
+{
+    int ret = -1;
+    int fd;
+    char buf[16 * 1024];
+    size_t rem = size;
+
+    if ((fd = open(path, O_RDONLY | O_NOFOLLOW)) < 0)
+        goto done;
+
+    while (rem > 0)
+    {
+        ssize_t n = read(fd, buf, rem < sizeof(buf) ? rem : sizeof(buf));
+
+        if (n <= 0)
+            goto done;
+
+        if (myst_sha256_update(ctx, buf, (size_t)n) != 0)
+            goto done;
+
+        rem -= (size_t)n;
+    }
+
+    ret = 0;
+
+done:
+
+    if (fd >= 0)
+        close(fd);
+
+    return ret;
+}
+
+static int _hash_dir(myst_sha256_ctx_t* ctx, const char* dir, const char* rel)
+{
+    int ret = -1;
+    DIR* d;
+    struct dirent* ent;
+    char** names = NULL;
+    size_t count = 0;
+
+    if (!(d = opendir(dir)))
+    {
+        fprintf(stderr, "cannot open directory: %s\n", dir);
+        goto done;
+    }
+
+    while ((ent = readdir(d)))
+    {
+        char** tmp;
+
+        if (strcmp(ent->d_name, ".") == 0 || strcmp(ent->d_name, "..") == 0)
+            continue;
+
+        if (!(tmp = realloc(names, (count + 1) * sizeof(char*))))
+        {
+            fprintf(stderr, "out of memory\n");
+            goto done;
+        }
+
+        names = tmp;
+
+        if (!(names[count] = strdup(ent->d_name)))
+        {
+            fprintf(stderr, "out of memory\n");
+            goto done;
+        }
+
+        count++;
+    }
+
+    /* the same order as myst_dirhash_sort() */
+    qsort(names, count, sizeof(char*), _cmp);
+
+    for (size_t i = 0; i < count; i++)
+    {
+        char path[PATH_MAX];
+        char relpath[PATH_MAX];
+        struct stat st;
+
+        snprintf(path, sizeof(path), "%s/%s", dir, names[i]);
+
+        if (*rel)
+            snprintf(relpath, sizeof(relpath), "%s/%s", rel, names[i]);
+        else
+            snprintf(relpath, sizeof(relpath), "%s", names[i]);
+
+        if (lstat(path, &st) != 0)
+        {
+            fprintf(stderr, "cannot stat: %s\n", path);
+            goto done;
+        }
+
+        if (S_ISDIR(st.st_mode))
+        {
+            if (myst_dirhash_dir(ctx, relpath, st.st_mode) != 0 ||
+                _hash_dir(ctx, path, relpath) != 0)
+            {
+                goto done;
+            }
+        }
+        else if (S_ISREG(st.st_mode))
+        {
+            if (myst_dirhash_file(ctx, relpath, st.st_mode, st.st_size) != 0 ||
+                _hash_file(ctx, path, st.st_size) != 0)
+            {
+                fprintf(stderr, "cannot read: %s\n", path);
+                goto done;
+            }
+        }
+        else if (S_ISLNK(st.st_mode))
+        {
+            char target[PATH_MAX];
+            ssize_t n;
+
+            if ((n = readlink(path, target, sizeof(target) - 1)) < 0)
+            {
+                fprintf(stderr, "cannot read link: %s\n", path);
+                goto done;
+            }
+
+            target[n] = '\0';
+
+            if (myst_dirhash_symlink(ctx, relpath, target) != 0)
+                goto done;
+        }
+        else
+        {
+            fprintf(
+                stderr,
+                "not a directory, regular file or symbolic link: %s\n",
+                path);
+            goto done;
+        }
+    }
+
+    ret = 0;
+
+done:
+
+    if (d)
+        closedir(d);
+
+    for (size_t i = 0; names && i < count; i++)
+        free(names[i]);
+
+    free(names);
+
+    return ret;
+}
+
+int hashdir_action(int argc, const char* argv[])
+{
+    myst_sha256_ctx_t ctx;
+    myst_sha256_t sha256;
+    struct stat st;
+
+    if (argc != 3)
+    {
+        fprintf(stderr, USAGE_HASHDIR, argv[0]);
+        return 1;
+    }
+
+    if (stat(argv[2], &st) != 0 || !S_ISDIR(st.st_mode))
+    {
+        fprintf(stderr, "%s: not a directory: %s\n", argv[0], argv[2]);
+        return 1;
+    }
+
+    if (myst_sha256_start(&ctx) != 0 || _hash_dir(&ctx, argv[2], "") != 0 ||
+        myst_sha256_finish(&ctx, &sha256) != 0)
+    {
+        fprintf(stderr, "%s: failed to hash %s\n", argv[0], argv[2]);
+        return 1;
+    }
+
+    myst_hexdump(NULL, sha256.data, sizeof(sha256.data));
+
+    return 0;
+}
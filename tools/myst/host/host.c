@@ -60,17 +60,6 @@ long myst_syscall_isatty_ocall(int fd)
     return 1;
 }
 
-void myst_rdtsc_ocall(uint32_t* rax, uint32_t* rdx)
-{
-    uint32_t hi;
-    uint32_t lo;
-
-    __asm__ __volatile__("rdtsc" : "=a"(lo), "=d"(hi));
-
-    *rax = lo;
-    *rdx = hi;
-}
-
 void myst_cpuid_ocall(
     uint32_t leaf,
     uint32_t subleaf,
@@ -287,6 +276,8 @@ Where <action> is one of:\n\
                      pieces during in the process\n\
     dump-sgx      -- dump the SGX enclave configuration along with the\n\
                      packaging configuration from an SGX packaged executable\n\
+    hashdir       -- print the hash of a directory for HostDirectoryImports\n\
+    mklayer       -- write a root file system layer for RootfsLayers\n\
 \n\
 "
 
@@ -363,6 +354,16 @@ int main(int argc, const char* argv[], const char* envp[])
         extern int fssig_action(int argc, const char* argv[]);
         return fssig_action(argc, argv);
     }
+    else if (strcmp(argv[1], "hashdir") == 0)
+    {
+        extern int hashdir_action(int argc, const char* argv[]);
+        return hashdir_action(argc, argv);
+    }
+    else if (strcmp(argv[1], "mklayer") == 0)
+    {
+        extern int mklayer_action(int argc, const char* argv[]);
+        return mklayer_action(argc, argv);
+    }
     else
     {
         fprintf(stderr, USAGE, argv[0]);
@@ -0,0 +1,132 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include <myst/cpio.h>
+#include <myst/sha256.h>
+
+#define USAGE_MKLAYER \
+    "\n\
+Usage: %s mklayer <base-directory> <directory> <layer-store>\n\
+\n\
+Synopsis:\n\
+    Write the root file system layer that turns the base directory into\n\
+    the other one: the files, directories and symbolic links that were\n\
+    added or changed, and a whiteout for each that was removed. The layer\n\
+    is a CPIO archive, written to the layer store directory as\n\
+    <sha256>.cpio, and its hash is printed for \"RootfsLayers\" in\n\
+    config.json.\n\
+\n"
+
+static int _hash_file(const char* path, myst_sha256_t* sha256)
+{
+    int ret = -1;
+    int fd;
+    char buf[16 * 1024];
+    myst_sha256_ctx_t ctx;
+    ssize_t n;
+
+    if ((fd = open(path, O_RDONLY)) < 0)
+        goto done;
+
+    if (myst_sha256_start(&ctx) != 0)
+        goto done;
+
+    while ((n = read(fd, buf, sizeof(buf))) > 0)
+    {
+        if (myst_sha256_update(&ctx, buf, (size_t)n) != 0)
+            goto done;
+    }
+
+    if (n < 0 || myst_sha256_finish(&ctx, sha256) != 0)
+        goto done;
+
+    ret = 0;
+
+done:
+
+    if (fd >= 0)
+        close(fd);
+
+    return ret;
+}
+
+int mklayer_action(int argc, const char* argv[])
+{
+    const char* base;
+    const char* update;
+    const char* store;
+    char tmp[PATH_MAX];
+    char path[PATH_MAX];
+    char hex[2 * MYST_SHA256_SIZE + 1];
+    myst_sha256_t sha256;
+    struct stat st;
+
+    if (argc != 5)
+    {
+        fprintf(stderr, USAGE_MKLAYER, argv[0]);
+        return 1;
+    }
+
+    base = argv[2];
+    update = argv[3];
+    store = argv[4];
+
+    if (stat(base, &st) != 0 || !S_ISDIR(st.st_mode))
+    {
+        fprintf(stderr, "%s: not a directory: %s\n", argv[0], base);
+        return 1;
+    }
+
+    if (stat(update, &st) != 0 || !S_ISDIR(st.st_mode))
+    {
+        fprintf(stderr, "%s: not a directory: %s\n", argv[0], update);
+        return 1;
+    }
+
+    if (mkdir(store, 0755) != 0 && errno != EEXIST)
+    {
+        fprintf(stderr, "%s: cannot create %s\n", argv[0], store);
+        return 1;
+    }
+
+    snprintf(tmp, sizeof(tmp), "%s/.layer.%d", store, (int)getpid());
+
+    if (myst_cpio_make_layer(base, update, tmp) != 0)
+    {
+        fprintf(stderr, "%s: failed to write the layer\n", argv[0]);
+        unlink(tmp);
+        return 1;
+    }
+
+    if (_hash_file(tmp, &sha256) != 0)
+    {
+        fprintf(stderr, "%s: failed to hash %s\n", argv[0], tmp);
+        unlink(tmp);
+        return 1;
+    }
+
+    for (size_t i = 0; i < MYST_SHA256_SIZE; i++)
+        snprintf(hex + 2 * i, 3, "%02x", sha256.data[i]);
+
+    snprintf(path, sizeof(path), "%s/%s.cpio", store, hex);
+
+    if (rename(tmp, path) != 0)
+    {
+        fprintf(stderr, "%s: cannot create %s\n", argv[0], path);
+        unlink(tmp);
+        return 1;
+    }
+
+    printf("%s\n", hex);
+
+    return 0;
+}
@@ -51,6 +51,8 @@ and <options> are one of:\n\
     --help                -- this message\n\
     --pubkey=pem_file     -- trust disks signed by this key (repeatable)\n\
     --roothash=ascii_file -- trust disks with this roothash (repeatable)\n\
+    --manifest=file       -- verify hostfs files against this manifest\n\
+                             (repeatable)\n\
 \n\
 "
 
@@ -108,8 +110,11 @@ int _package(int argc, const char* argv[])
     static const size_t max_roothashes = 128;
     const char* roothashes[max_roothashes];
     size_t num_roothashes = 0;
+    static const size_t max_manifests = 128;
+    const char* manifests[max_manifests];
+    size_t num_manifests = 0;
 
-    /* Get --pubkey=filename and --roothash=filename options */
+    /* Get --pubkey, --roothash and --manifest options */
     get_archive_options(
         &argc,
         argv,
@@ -118,7 +123,10 @@ int _package(int argc, const char* argv[])
         &num_pubkeys,
         roothashes,
         max_roothashes,
-        &num_roothashes);
+        &num_roothashes,
+        manifests,
+        max_manifests,
+        &num_manifests);
 
     if ((argc < 4) || (cli_getopt(&argc, argv, "--help", NULL) == 0) ||
         (cli_getopt(&argc, argv, "-h", NULL) == 0))
@@ -146,7 +154,13 @@ int _package(int argc, const char* argv[])
     }
 
     create_archive(
-        pubkeys, num_pubkeys, roothashes, num_roothashes, archive_file);
+        pubkeys,
+        num_pubkeys,
+        roothashes,
+        num_roothashes,
+        manifests,
+        num_manifests,
+        archive_file);
 
     tmp_dir = mkdtemp(dir_template);
     if (tmp_dir == NULL)
@@ -470,6 +484,7 @@ int _exec_package(
     char* unpack_dir = NULL;
     int ret = -1;
     const char** exec_args = NULL;
+    const char** env = NULL;
 
     /* Get options */
     {
@@ -488,6 +503,9 @@ int _exec_package(
         options.trace_errors = true;
     }
 
+    /* Get --env options */
+    env = cli_getopt_env(&argc, argv, envp);
+
     if (!realpath(argv[0], full_app_path))
     {
         fprintf(stderr, "Invalid path %s\n", argv[0]);
@@ -610,7 +628,9 @@ int _exec_package(
     {
         fprintf(stderr, "Failed to process configuration\n");
     }
-    if ((parsed_data.allow_host_parameters == 0) && (argc > 1))
+    if ((parsed_data.allow_host_parameters == 0) &&
+        (parsed_data.overridable_application_parameters_count == 0) &&
+        (argc > 1))
     {
         printf(
             "Command line arguments will be ignored due to configuration.\n");
@@ -664,7 +684,9 @@ int _exec_package(
     // then append them also
     int num_args = 1; // argv[0];
 
-    if (parsed_data.allow_host_parameters)
+    // the enclave checks these against LaunchOverrides.ApplicationParameters
+    if (parsed_data.allow_host_parameters ||
+        parsed_data.overridable_application_parameters_count)
     {
         num_args = argc;
     }
@@ -696,7 +718,7 @@ int _exec_package(
     }
 
     ret = exec_launch_enclave(
-        scratch_path, type, flags, exec_args, envp, &options);
+        scratch_path, type, flags, exec_args, env, &options);
     if (ret != 0)
     {
         fprintf(stderr, "Enclave %s returned %d\n", scratch_path, ret);
@@ -716,5 +738,8 @@ done:
     if (exec_args)
         free(exec_args);
 
+    if (env)
+        free(env);
+
     return ret;
 }
@@ -0,0 +1,250 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <pthread.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/epoll.h>
+#include <sys/eventfd.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <unistd.h>
+
+#include "poller.h"
+
+/*
+**==============================================================================
+**
+** Host poller:
+**
+** One host thread waits on a single epoll instance that holds every host
+** descriptor the enclave has polled. When a descriptor changes, the thread
+** records the events it has now in a table in host memory, which the enclave
+** reads before it calls out to poll() (see tools/myst/enc/poll.c): if a
+** descriptor it polls is already known to be ready, the poll returns without
+** leaving the enclave. Each entry holds the POLL* bits in its low half and a
+** sequence number in its high half. The enclave clears the bits that I/O may
+** have made stale and bumps the sequence number after each read or write;
+** the poller only stores a result if the sequence number has not changed
+** since it took it, so a stale result never reaches the table. Blocking
+** polls still go to the host, where an eventfd wakes them.
+**
+**==============================================================================
+*/
+
+/* the largest descriptor table to share with the enclave */
+#define MAX_FDS 65536
+
+#define EVENTS_MASK 0x0000ffff
+#define SEQUENCE_ONE 0x00010000
+
+#define MAX_EPOLL_EVENTS 64
+
+static int _epfd = -1;
+static int _wakefd = -1;
+static volatile uint32_t* _events;
+static uint8_t* _watched;
+static size_t _nfds;
+
+/* store the events that fd has now, unless the enclave used it meanwhile */
+static void _update(int fd)
+{
+    for (;;)
+    {
+        uint32_t e = __atomic_load_n(&_events[fd], __ATOMIC_ACQUIRE);
+        struct pollfd p = {.fd = fd, .events = POLLIN | POLLOUT | POLLRDHUP};
+        uint32_t n;
+
+        /* POLLNVAL: closed, so poller_forget() has reset the entry */
+        if (poll(&p, 1, 0) < 0 || (p.revents & POLLNVAL))
+            return;
+
+        n = (e & ~EVENTS_MASK) | ((uint32_t)p.revents & EVENTS_MASK);
+
+        if (__atomic_compare_exchange_n(
+                &_events[fd], &e, n, false, __ATOMIC_RELEASE, __ATOMIC_RELAXED))
+        {
+            return;
+        }
+    }
+}
+
+static void* _poller_thread(void* arg)
+{
+    struct epoll_event events[MAX_EPOLL_EVENTS];
+
+    (void)arg;
+
+    for (;;)
+    {
+        int n = epoll_wait(_epfd, events, MAX_EPOLL_EVENTS, -1);
+
+        if (n < 0)
+        {
+            if (errno == EINTR)
+                continue;
+
+            fprintf(
+                stderr, "poller: epoll_wait() failed: %s\n", strerror(errno));
+            return NULL;
+        }
+
+        for (int i = 0; i < n; i++)
+            _update(events[i].data.fd);
+    }
+
+    return NULL;
+}
+
+static void _watch(int fd)
+{
+    struct epoll_event ev;
+
+    if (fd < 0 || (size_t)fd >= _nfds)
+        return;
+
+    if (__atomic_exchange_n(&_watched[fd], 1, __ATOMIC_ACQ_REL))
+        return;
+
+    /* edge-triggered: the thread rechecks the descriptor on each change */
+    memset(&ev, 0, sizeof(ev));
+    ev.events = EPOLLIN | EPOLLOUT | EPOLLRDHUP | EPOLLET;
+    ev.data.fd = fd;
+
+    if (epoll_ctl(_epfd, EPOLL_CTL_ADD, fd, &ev) != 0 && errno != EEXIST)
+    {
+        /* for example, a regular file, which epoll does not support */
+        return;
+    }
+
+    _update(fd);
+}
+
+int poller_start(struct myst_shm* shm)
+{
+    struct rlimit rlim;
+    pthread_t thread;
+    void* p;
+
+    _nfds = MAX_FDS;
+
+    if (getrlimit(RLIMIT_NOFILE, &rlim) == 0 && rlim.rlim_cur < _nfds)
+        _nfds = rlim.rlim_cur;
+
+    if ((p = mmap(
+             NULL,
+             _nfds * sizeof(uint32_t),
+             PROT_READ | PROT_WRITE,
+             MAP_PRIVATE | MAP_ANONYMOUS,
+             -1,
+             0)) == MAP_FAILED)
+    {
+        return -ENOMEM;
+    }
+
+    _events = p;
+
+    if (!(_watched = calloc(_nfds, 1)))
+        return -ENOMEM;
+
+    if ((_epfd = epoll_create1(EPOLL_CLOEXEC)) < 0)
+        return -errno;
+
+    if ((_wakefd = eventfd(0, EFD_NONBLOCK | EFD_CLOEXEC)) < 0)
+        return -errno;
+
+    if (pthread_create(&thread, NULL, _poller_thread, NULL) != 0)
+        return -EAGAIN;
+
+    pthread_detach(thread);
+
+    shm->poll_events = _events;
+    shm->poll_nfds = _nfds;
+
+    return 0;
+}
+
+long poller_poll(struct pollfd* fds, nfds_t nfds, int timeout)
+{
+    long ret = 0;
+    long r;
+    struct pollfd* copy;
+
+    if (!(copy = calloc(nfds + 1, sizeof(struct pollfd))))
+        return -ENOMEM;
+
+    for (nfds_t i = 0; i < nfds; i++)
+    {
+        copy[i] = fds[i];
+        _watch(fds[i].fd);
+    }
+
+    /* the last entry is the wake descriptor */
+    copy[nfds].fd = _wakefd;
+    copy[nfds].events = POLLIN;
+
+    if ((r = poll(copy, nfds + 1, timeout)) < 0)
+    {
+        ret = -errno;
+        goto done;
+    }
+
+    if (copy[nfds].revents & POLLIN)
+    {
+        uint64_t x;
+
+        /* consume the wakeups so that the next poll blocks */
+        if (read(_wakefd, &x, sizeof(x)) < 0 && errno != EAGAIN)
+        {
+            ret = -errno;
+            goto done;
+        }
+
+        r--;
+    }
+
+    for (nfds_t i = 0; i < nfds; i++)
+        fds[i].revents = copy[i].revents;
+
+    ret = r;
+
+done:
+    free(copy);
+    return ret;
+}
+
+long poller_wake(void)
+{
+    uint64_t x = 1;
+
+    if (write(_wakefd, &x, sizeof(x)) != sizeof(x))
+        return -errno;
+
+    return 0;
+}
+
+void poller_forget(int fd)
+{
+    if (fd < 0 || (size_t)fd >= _nfds || !_watched)
+        return;
+
+    /* the descriptor number may be reused for another file */
+    if (__atomic_exchange_n(&_watched[fd], 0, __ATOMIC_ACQ_REL))
+        epoll_ctl(_epfd, EPOLL_CTL_DEL, fd, NULL);
+
+    for (;;)
+    {
+        uint32_t e = __atomic_load_n(&_events[fd], __ATOMIC_ACQUIRE);
+        uint32_t n = (e & ~EVENTS_MASK) + SEQUENCE_ONE;
+
+        if (__atomic_compare_exchange_n(
+                &_events[fd], &e, n, false, __ATOMIC_RELEASE, __ATOMIC_RELAXED))
+        {
+            return;
+        }
+    }
+}
@@ -0,0 +1,24 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_HOST_POLLER_H
+#define _MYST_HOST_POLLER_H
+
+#include <poll.h>
+
+#include <myst/shm.h>
+
+/* start the poller thread and share its readiness table with the enclave */
+int poller_start(struct myst_shm* shm);
+
+/* poll() that can be interrupted by poller_wake(); the descriptors are also
+ * added to the poller, so that later polls may not need to leave the enclave */
+long poller_poll(struct pollfd* fds, nfds_t nfds, int timeout);
+
+/* make the threads blocked in poller_poll() return */
+long poller_wake(void);
+
+/* called before the enclave closes a host descriptor */
+void poller_forget(int fd);
+
+#endif /* _MYST_HOST_POLLER_H */
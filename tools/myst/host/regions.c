@@ -90,20 +90,21 @@ const region_details* create_region_details_from_package(
             (unsigned char**)&_details.config.buffer,
             &_details.config.buffer_size) == 0)
     {
-        if (heap_pages == 0)
+        config_parsed_data_t parsed_data = {0};
+        if (parse_config_from_buffer(
+                _details.config.buffer,
+                _details.config.buffer_size,
+                &parsed_data) == 0)
         {
-            config_parsed_data_t parsed_data = {0};
-            if (parse_config_from_buffer(
-                    _details.config.buffer,
-                    _details.config.buffer_size,
-                    &parsed_data) == 0)
-            {
+            if (heap_pages == 0)
                 heap_pages = parsed_data.heap_pages;
-                free_config(&parsed_data);
-            }
-            else
-                _err("Failed to parse config we extracted from enclave");
+            _details.mman_committed_size =
+                parsed_data.committed_pages * PAGE_SIZE;
+            _details.host_clock_source = parsed_data.host_clock_source;
+            free_config(&parsed_data);
         }
+        else
+            _err("Failed to parse config we extracted from enclave");
     }
     else
         _err("Failed to extract config data from %s.", get_program_file());
@@ -218,6 +219,9 @@ const region_details* create_region_details_from_files(
                 _details.config.buffer, temp_size, &parsed_data) == 0)
         {
             ram = parsed_data.heap_pages * PAGE_SIZE;
+            _details.mman_committed_size =
+                parsed_data.committed_pages * PAGE_SIZE;
+            _details.host_clock_source = parsed_data.host_clock_source;
             free_config(&parsed_data);
         }
         else
@@ -244,6 +248,9 @@ const region_details* create_region_details_from_files(
                     &parsed_data) == 0)
             {
                 ram = parsed_data.heap_pages * PAGE_SIZE;
+                _details.mman_committed_size =
+                    parsed_data.committed_pages * PAGE_SIZE;
+                _details.host_clock_source = parsed_data.host_clock_source;
                 free_config(&parsed_data);
             }
             else
@@ -909,6 +916,8 @@ static int _add_mman_region(oe_region_context_t* context, uint64_t* vaddr)
     int ret = 0;
     __attribute__((__aligned__(PAGE_SIZE))) uint8_t page[PAGE_SIZE];
     const size_t mman_pages = _details.mman_size / PAGE_SIZE;
+    const size_t committed_pages = _details.mman_committed_size / PAGE_SIZE;
+    size_t pending_pages = 0;
     const uint64_t id = MYST_MMAN_REGION_ID;
 
     if (!context || !vaddr)
@@ -932,7 +941,16 @@ static int _add_mman_region(oe_region_context_t* context, uint64_t* vaddr)
         *vaddr += sizeof(page);
     }
 
-    for (size_t i = 0; i < mman_pages; i++)
+    /* With "CommittedMemorySize", only the top of the region is added: the
+     * mapped memory partition grows down from there. The pages below it are
+     * left out of the enclave image and committed on first access (see
+     * tools/myst/enc/edmm.c). */
+    if (committed_pages && committed_pages < mman_pages)
+        pending_pages = mman_pages - committed_pages;
+
+    *vaddr += pending_pages * PAGE_SIZE;
+
+    for (size_t i = pending_pages; i < mman_pages; i++)
     {
         const bool extend = false;
 
@@ -21,6 +21,10 @@ typedef struct _region_details_item
 typedef struct _region_details
 {
     size_t mman_size;
+    /* if non-zero, the rest of mman_size is committed on demand (SGX2) */
+    size_t mman_committed_size;
+    /* the host clock that feeds the enclave clock (myst_clock_source_t) */
+    int host_clock_source;
     region_details_item enc;
     region_details_item crt;
     region_details_item kernel;
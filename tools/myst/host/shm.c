@@ -1,17 +1,72 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+#include <errno.h>
+#include <linux/futex.h>
 #include <myst/shm.h>
 #include <pthread.h>
+#include <signal.h>
 #include <stdio.h>
 #include <stdlib.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <sys/timex.h>
+#include <time.h>
+#include <unistd.h>
 
 static pthread_t _clock_thread;
 
+/* the TAI offset of the host kernel when the enclave started */
+static long _tai_offset0;
+
+static long _clock_ns(clockid_t clk_id)
+{
+    struct timespec tp;
+
+    clock_gettime(clk_id, &tp);
+    return tp.tv_sec * NANO_IN_SECOND + tp.tv_nsec;
+}
+
+/* publish what adjtimex() reports about the host clock */
+static void _update_clock_status(struct clock_ctrl* ctrl)
+{
+    struct timex tx;
+    int state;
+    long offset = 0;
+    unsigned long seq = ctrl->seq;
+
+    memset(&tx, 0, sizeof(tx));
+    state = adjtimex(&tx);
+
+    // How far NTP has moved the host realtime clock since the start. The
+    // enclave applies leap seconds itself, so count them back in.
+    if (ctrl->source == MYST_CLOCK_SOURCE_NTP)
+    {
+        long realtime = _clock_ns(CLOCK_REALTIME) - ctrl->realtime0;
+        realtime += (tx.tai - _tai_offset0) * NANO_IN_SECOND;
+        offset = realtime - (_clock_ns(CLOCK_MONOTONIC) - ctrl->monotime0);
+    }
+
+    // The enclave retries its read while seq is odd or has changed.
+    __atomic_store_n(&ctrl->seq, seq + 1, __ATOMIC_RELAXED);
+    __atomic_thread_fence(__ATOMIC_RELEASE);
+
+    ctrl->realtime_offset = offset;
+    ctrl->maxerror = tx.maxerror * 1000;
+    ctrl->esterror = tx.esterror * 1000;
+    ctrl->synced =
+        state >= 0 && state != TIME_ERROR && !(tx.status & STA_UNSYNC);
+    ctrl->tai_offset = tx.tai;
+    ctrl->leap = (tx.status & STA_INS) ? 1 : (tx.status & STA_DEL) ? -1 : 0;
+
+    __atomic_store_n(&ctrl->seq, seq + 2, __ATOMIC_RELEASE);
+}
+
 static void* _host_clock_task(void* args)
 {
-    struct timespec tp, sleep_tp;
+    struct timespec sleep_tp;
     struct clock_ctrl* ctrl = (struct clock_ctrl*)args;
+    long updated = ctrl->now;
 
     // Set up sleep interval
     sleep_tp.tv_sec = ctrl->interval / NANO_IN_SECOND;
@@ -22,16 +77,26 @@ static void* _host_clock_task(void* args)
     while (__atomic_load_n(&ctrl->done, __ATOMIC_ACQUIRE) == 0)
     {
         nanosleep(&sleep_tp, NULL);
-        clock_gettime(CLOCK_MONOTONIC, &tp);
-        ctrl->now = tp.tv_sec * NANO_IN_SECOND + tp.tv_nsec;
+        ctrl->now = _clock_ns(CLOCK_MONOTONIC);
+
+        // Leap seconds are announced (and NTP corrects the clock) while
+        // the enclave runs.
+        if (ctrl->now - updated >= NANO_IN_SECOND)
+        {
+            _update_clock_status(ctrl);
+            updated = ctrl->now;
+        }
     }
     return NULL;
 }
 
-int shm_create_clock(struct myst_shm* shm, unsigned long clock_tick)
+int shm_create_clock(
+    struct myst_shm* shm,
+    unsigned long clock_tick,
+    int source)
 {
-    struct timespec tp;
     int res = -1;
+    struct timex tx;
     shm->clock = calloc(1, sizeof(struct clock_ctrl));
     if (shm->clock == NULL)
     {
@@ -39,18 +104,54 @@ int shm_create_clock(struct myst_shm* shm, unsigned long clock_tick)
         return res;
     }
 
+    // The enclave checks that it knows this layout.
+    myst_shm_header_init(
+        &shm->clock->header,
+        MYST_CLOCK_CTRL_MAGIC,
+        MYST_CLOCK_CTRL_VERSION,
+        sizeof(struct clock_ctrl));
+
     // How many nanoseconds between 2 clock ticks.
     shm->clock->interval = clock_tick;
+    shm->clock->source = source;
+
+    // Remember the TAI offset, which later leap seconds change.
+    memset(&tx, 0, sizeof(tx));
+
+    if (adjtimex(&tx) >= 0)
+        _tai_offset0 = tx.tai;
 
     // Remeber the base real time.
-    clock_gettime(CLOCK_REALTIME, &tp);
-    shm->clock->realtime0 = tp.tv_sec * NANO_IN_SECOND + tp.tv_nsec;
+    if (source == MYST_CLOCK_SOURCE_TAI)
+    {
+        if (_tai_offset0 <= 0)
+        {
+            fprintf(stderr, "The host kernel does not know the TAI offset\n");
+            free(shm->clock);
+            return res;
+        }
+
+        shm->clock->realtime0 =
+            _clock_ns(CLOCK_TAI) - _tai_offset0 * NANO_IN_SECOND;
+    }
+    else if (source == MYST_CLOCK_SOURCE_REALTIME ||
+             source == MYST_CLOCK_SOURCE_NTP)
+    {
+        shm->clock->realtime0 = _clock_ns(CLOCK_REALTIME);
+    }
+    else
+    {
+        fprintf(stderr, "Unknown host clock source: %d\n", source);
+        free(shm->clock);
+        return res;
+    }
 
     // Remeber the base monotonic time.
-    clock_gettime(CLOCK_MONOTONIC, &tp);
-    shm->clock->monotime0 = tp.tv_sec * NANO_IN_SECOND + tp.tv_nsec;
+    shm->clock->monotime0 = _clock_ns(CLOCK_MONOTONIC);
     shm->clock->now = shm->clock->monotime0;
 
+    _update_clock_status(shm->clock);
+
     if (pthread_create(&_clock_thread, 0, _host_clock_task, (void*)shm->clock))
     {
         fprintf(stderr, "Failed to create host clock thread\n");
@@ -68,3 +169,160 @@ void shm_free_clock(struct myst_shm* shm)
 
     free(shm->clock);
 }
+
+static pthread_t _console_thread;
+
+/* write all of the bytes, as the enclave would have */
+static void _write_all(int fd, const uint8_t* p, size_t n)
+{
+    while (n)
+    {
+        ssize_t r = write(fd, p, n);
+
+        if (r < 0 && errno == EINTR)
+            continue;
+
+        if (r <= 0)
+            return;
+
+        p += r;
+        n -= (size_t)r;
+    }
+}
+
+/* write out the bytes in the ring at the given position (which wraps) */
+static void _write_ring(
+    myst_console_ring_t* ring,
+    int fd,
+    uint64_t pos,
+    size_t size)
+{
+    size_t offset = pos & (MYST_CONSOLE_RING_CAPACITY - 1);
+    size_t n = MYST_CONSOLE_RING_CAPACITY - offset;
+
+    if (n > size)
+        n = size;
+
+    _write_all(fd, &ring->data[offset], n);
+    _write_all(fd, &ring->data[0], size - n);
+}
+
+static void* _host_console_task(void* args)
+{
+    myst_console_ring_t* ring = (myst_console_ring_t*)args;
+    const struct timespec idle = {0, 1000000};
+    uint64_t tail = ring->tail;
+
+    for (;;)
+    {
+        // Read done first: once it is set, head no longer moves.
+        int done = __atomic_load_n(&ring->done, __ATOMIC_ACQUIRE);
+        uint64_t head = __atomic_load_n(&ring->head, __ATOMIC_ACQUIRE);
+
+        if (tail == head)
+        {
+            if (done)
+                break;
+
+            nanosleep(&idle, NULL);
+            continue;
+        }
+
+        while (tail != head)
+        {
+            myst_console_record_t record;
+            size_t offset = tail & (MYST_CONSOLE_RING_CAPACITY - 1);
+
+            // A record header never wraps, as records are 8-byte aligned.
+            memcpy(&record, &ring->data[offset], sizeof(record));
+
+            if ((record.fd != STDOUT_FILENO && record.fd != STDERR_FILENO) ||
+                record.size > MYST_CONSOLE_RING_CAPACITY / 2)
+            {
+                fprintf(stderr, "The console ring is corrupt\n");
+                return NULL;
+            }
+
+            _write_ring(ring, record.fd, tail + sizeof(record), record.size);
+            tail += myst_console_record_size(record.size);
+        }
+
+        __atomic_store_n(&ring->tail, tail, __ATOMIC_RELEASE);
+    }
+
+    return NULL;
+}
+
+myst_console_ring_t* shm_create_console(void)
+{
+    myst_console_ring_t* ring;
+
+    if (!(ring = calloc(1, sizeof(myst_console_ring_t))))
+    {
+        fprintf(stderr, "Out of memory\n");
+        return NULL;
+    }
+
+    myst_shm_header_init(
+        &ring->header,
+        MYST_CONSOLE_RING_MAGIC,
+        MYST_CONSOLE_RING_VERSION,
+        sizeof(myst_console_ring_t));
+
+    if (pthread_create(&_console_thread, 0, _host_console_task, ring))
+    {
+        fprintf(stderr, "Failed to create host console thread\n");
+        free(ring);
+        return NULL;
+    }
+
+    return ring;
+}
+
+void shm_free_console(myst_console_ring_t* ring)
+{
+    if (!ring)
+        return;
+
+    // The thread writes out what is left in the ring before it exits.
+    __atomic_store_n(&ring->done, 1, __ATOMIC_RELEASE);
+    pthread_join(_console_thread, 0);
+
+    free(ring);
+}
+
+static volatile int _shutdown;
+static volatile int* _shutdown_event;
+
+static void _shutdown_handler(int signum)
+{
+    /* the kernel did not act on the first request (a hung application) */
+    if (_shutdown)
+        _exit(128 + signum);
+
+    _shutdown = 1;
+
+    /* wake the main thread if it waits in the kernel (see myst_tcall_wake) */
+    if (__sync_fetch_and_add(_shutdown_event, 1) != 0)
+    {
+        syscall(
+            SYS_futex, _shutdown_event, FUTEX_WAKE_PRIVATE, 1, NULL, NULL, 0);
+    }
+}
+
+volatile int* shm_create_shutdown(uint64_t event)
+{
+    struct sigaction sa = {0};
+
+    _shutdown_event = (volatile int*)event;
+    sa.sa_handler = _shutdown_handler;
+    sigemptyset(&sa.sa_mask);
+
+    if (sigaction(SIGTERM, &sa, NULL) != 0)
+    {
+        fprintf(stderr, "Failed to install the SIGTERM handler\n");
+        return NULL;
+    }
+
+    return &_shutdown;
+}
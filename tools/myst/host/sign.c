@@ -260,6 +260,9 @@ int _sign(int argc, const char* argv[])
     static const size_t max_roothashes = 128;
     const char* roothashes[max_roothashes];
     size_t num_roothashes = 0;
+    static const size_t max_manifests = 128;
+    const char* manifests[max_manifests];
+    size_t num_manifests = 0;
 
     // We are in the right operation, right?
     assert(
@@ -275,7 +278,10 @@ int _sign(int argc, const char* argv[])
             &num_pubkeys,
             roothashes,
             max_roothashes,
-            &num_roothashes);
+            &num_roothashes,
+            manifests,
+            max_manifests,
+            &num_manifests);
     }
 
     // validate parameters and parse the extra options and validate they exist
@@ -375,7 +381,13 @@ int _sign(int argc, const char* argv[])
     if (!archive)
     {
         create_archive(
-            pubkeys, num_pubkeys, roothashes, num_roothashes, archive_buf);
+            pubkeys,
+            num_pubkeys,
+            roothashes,
+            num_roothashes,
+            manifests,
+            num_manifests,
+            archive_buf);
         archive = archive_buf;
     }
 
@@ -1,6 +1,9 @@
 #define _GNU_SOURCE
 #include <errno.h>
 #include <fcntl.h>
+#include <netdb.h>
+#include <netinet/in.h>
+#include <string.h>
 #include <sys/ioctl.h>
 #include <sys/stat.h>
 #include <sys/statfs.h>
@@ -8,6 +11,7 @@
 #include <unistd.h>
 
 #include "myst_u.h"
+#include "poller.h"
 
 #define RETURN(EXPR)                     \
     do                                   \
@@ -28,6 +32,7 @@ long myst_write_ocall(int fd, const void* buf, size_t count)
 
 long myst_close_ocall(int fd)
 {
+    poller_forget(fd);
     RETURN(close(fd));
 }
 
@@ -87,6 +92,24 @@ long myst_socket_ocall(int domain, int type, int protocol)
     RETURN(socket(domain, type, protocol));
 }
 
+long myst_resolve_host_ocall(const char* host, uint32_t* addr)
+{
+    struct addrinfo hints;
+    struct addrinfo* res = NULL;
+
+    memset(&hints, 0, sizeof(hints));
+    hints.ai_family = AF_INET;
+    hints.ai_socktype = SOCK_STREAM;
+
+    if (getaddrinfo(host, NULL, &hints, &res) != 0 || !res)
+        return -ENOENT;
+
+    *addr = ((struct sockaddr_in*)res->ai_addr)->sin_addr.s_addr;
+    freeaddrinfo(res);
+
+    return 0;
+}
+
 long myst_accept4_ocall(
     int sockfd,
     struct sockaddr* addr,
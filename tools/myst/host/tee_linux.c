@@ -0,0 +1,257 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/random.h>
+#include <sys/resource.h>
+#include <sys/syscall.h>
+#include <sys/timex.h>
+#include <ucontext.h>
+
+#include <myst/blockdevice.h>
+#include <myst/coredump.h>
+#include <myst/tcall.h>
+
+#include "../crypto.h"
+#include "tee_linux.h"
+
+/*
+**==============================================================================
+**
+** The Linux target runs the kernel in the myst process, with no TEE under
+** it. This gives it what the SGX enclave provides on top of the target
+** (tools/myst/enc), so that the same kernel features work when iterating
+** without SGX hardware: hardware exceptions reach the core dump handler (and
+** faults in the stack guards raise SIGSEGV), and core dumps can be
+** encrypted. There is nothing to attest or unseal for, so key release,
+** secrets and attested HTTPS fail with ENOTSUP.
+**
+**==============================================================================
+*/
+
+/* the signals that SGX reports as exceptions (see tools/myst/enc/coredump.c) */
+static const int _crash_signals[] = {SIGSEGV, SIGBUS, SIGILL, SIGFPE};
+
+#define NUM_CRASH_SIGNALS (sizeof(_crash_signals) / sizeof(_crash_signals[0]))
+
+static myst_crash_handler_t _crash_handler;
+static myst_fault_handler_t _fault_handler;
+
+int myst_crypto_random(void* data, size_t size)
+{
+    uint8_t* p = data;
+
+    while (size)
+    {
+        ssize_t n = getrandom(p, size, 0);
+
+        if (n < 0)
+        {
+            if (errno == EINTR)
+                continue;
+
+            return -errno;
+        }
+
+        p += n;
+        size -= (size_t)n;
+    }
+
+    return 0;
+}
+
+static void _crash_action(int signum, siginfo_t* info, void* context)
+{
+    greg_t* gregs = ((ucontext_t*)context)->uc_mcontext.gregs;
+    myst_coredump_regs_t regs;
+
+    /* an overflow into an inaccessible stack guard raises SIGSEGV */
+    if (signum == SIGSEGV && _fault_handler)
+    {
+        uint64_t rip = (uint64_t)gregs[REG_RIP];
+        uint64_t rsp = (uint64_t)gregs[REG_RSP];
+
+        if ((*_fault_handler)((uint64_t)info->si_addr, &rip, &rsp))
+        {
+            gregs[REG_RIP] = (greg_t)rip;
+            gregs[REG_RSP] = (greg_t)rsp;
+            return;
+        }
+    }
+
+    /* restore the default action, so the exception recurs on return and
+     * kills the process, as it aborts the enclave on SGX */
+    signal(signum, SIG_DFL);
+
+    if (!_crash_handler)
+        return;
+
+    memset(&regs, 0, sizeof(regs));
+    regs.r15 = (uint64_t)gregs[REG_R15];
+    regs.r14 = (uint64_t)gregs[REG_R14];
+    regs.r13 = (uint64_t)gregs[REG_R13];
+    regs.r12 = (uint64_t)gregs[REG_R12];
+    regs.rbp = (uint64_t)gregs[REG_RBP];
+    regs.rbx = (uint64_t)gregs[REG_RBX];
+    regs.r11 = (uint64_t)gregs[REG_R11];
+    regs.r10 = (uint64_t)gregs[REG_R10];
+    regs.r9 = (uint64_t)gregs[REG_R9];
+    regs.r8 = (uint64_t)gregs[REG_R8];
+    regs.rax = (uint64_t)gregs[REG_RAX];
+    regs.rcx = (uint64_t)gregs[REG_RCX];
+    regs.rdx = (uint64_t)gregs[REG_RDX];
+    regs.rsi = (uint64_t)gregs[REG_RSI];
+    regs.rdi = (uint64_t)gregs[REG_RDI];
+    regs.orig_rax = (uint64_t)-1;
+    regs.rip = (uint64_t)gregs[REG_RIP];
+    regs.eflags = (uint64_t)gregs[REG_EFL];
+    regs.rsp = (uint64_t)gregs[REG_RSP];
+
+    (*_crash_handler)(signum, &regs);
+}
+
+/* handle the crash signals while the kernel has a handler for them */
+static long _install_crash_action(void)
+{
+    struct sigaction sa;
+    const bool install = _crash_handler || _fault_handler;
+
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_sigaction = _crash_action;
+    sa.sa_flags = SA_SIGINFO | SA_ONSTACK;
+    sigemptyset(&sa.sa_mask);
+
+    for (size_t i = 0; i < NUM_CRASH_SIGNALS; i++)
+    {
+        if (sigaction(_crash_signals[i], install ? &sa : NULL, NULL) != 0)
+            return -errno;
+    }
+
+    return 0;
+}
+
+int tee_linux_setup_thread(void)
+{
+    /* the stack of the application thread may be the one that overflowed */
+    static __thread uint8_t _altstack[64 * 1024];
+    stack_t ss;
+
+    memset(&ss, 0, sizeof(ss));
+    ss.ss_sp = _altstack;
+    ss.ss_size = sizeof(_altstack);
+
+    if (sigaltstack(&ss, NULL) != 0)
+        return -errno;
+
+    return 0;
+}
+
+long tee_linux_tcall(long n, long params[6])
+{
+    const long a = params[0];
+    const long b = params[1];
+    const long c = params[2];
+    const long d = params[3];
+    const long e = params[4];
+    const long f = params[5];
+    long ret;
+
+    switch (n)
+    {
+        case MYST_TCALL_EXPORT_COVERAGE:
+        {
+            return myst_tcall_export_coverage(
+                (const char*)a, (const void*)b, (size_t)c);
+        }
+        case MYST_TCALL_WRITE_LOG:
+        {
+            return myst_tcall_write_log(
+                (const char*)a,
+                (const void*)b,
+                (size_t)c,
+                (size_t)d,
+                (size_t)e);
+        }
+        case SYS_setpriority:
+        {
+            /* the nice value of the calling host thread */
+            if (setpriority((int)a, (id_t)b, (int)c) != 0)
+                return -errno;
+
+            return 0;
+        }
+        case MYST_TCALL_SET_CRASH_HANDLER:
+        {
+            _crash_handler = (myst_crash_handler_t)a;
+            return _install_crash_action();
+        }
+        case MYST_TCALL_SET_FAULT_HANDLER:
+        {
+            _fault_handler = (myst_fault_handler_t)a;
+            return _install_crash_action();
+        }
+        case MYST_TCALL_RSA_OAEP_ENCRYPT:
+        {
+            return myst_rsa_oaep_encrypt(
+                (const char*)a,
+                (const void*)b,
+                (size_t)c,
+                (void*)d,
+                (size_t)e,
+                (size_t*)f);
+        }
+        case MYST_TCALL_AES_GCM_ENCRYPT:
+        {
+            return myst_aes_gcm_encrypt(
+                (const uint8_t*)a,
+                (const uint8_t*)b,
+                (const void*)c,
+                (size_t)d,
+                (void*)e,
+                (uint8_t*)f);
+        }
+        case MYST_TCALL_CLOCK_ADJTIME:
+        {
+            struct timex* tx = (struct timex*)b;
+
+            /* report the state of the host clock, but never change it */
+            if (tx->modes)
+                return -EPERM;
+
+            if ((ret = clock_adjtime((clockid_t)a, tx)) < 0)
+                return -errno;
+
+            return ret;
+        }
+        case MYST_TCALL_GET_BLOCK_DEVICE_SIZE:
+        {
+            return myst_get_block_device_size((int)a, (uint64_t*)b);
+        }
+        case MYST_TCALL_SYNC_BLOCK_DEVICE:
+        {
+            return myst_sync_block_device((int)a);
+        }
+        case MYST_TCALL_MPROTECT:
+        {
+            if (mprotect((void*)a, (size_t)b, (int)c) != 0)
+                return -errno;
+
+            return 0;
+        }
+        case MYST_TCALL_KEY_RELEASE:
+        case MYST_TCALL_UNSEAL_SECRET:
+        case MYST_TCALL_ATTESTED_HTTPS_GET:
+        {
+            /* there is no enclave to attest or unseal for */
+            return -ENOTSUP;
+        }
+        default:
+        {
+            return myst_tcall(n, params);
+        }
+    }
+}
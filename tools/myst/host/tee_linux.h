@@ -0,0 +1,14 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#ifndef _MYST_HOST_TEE_LINUX_H
+#define _MYST_HOST_TEE_LINUX_H
+
+/* handle the tcalls that the SGX target implements in the enclave (see
+ * tools/myst/enc/syscall.c) and pass the rest to the Linux target */
+long tee_linux_tcall(long n, long params[6]);
+
+/* give the calling thread a host stack on which to handle its exceptions */
+int tee_linux_setup_thread(void);
+
+#endif /* _MYST_HOST_TEE_LINUX_H */
@@ -205,3 +205,44 @@ int cli_getopt(
 
     return ret;
 }
+
+const char** cli_getopt_env(int* argc, const char* argv[], const char* envp[])
+{
+    const char** env;
+    size_t envc = 0;
+    const char* arg;
+
+    while (envp[envc])
+        envc++;
+
+    /* each --env option adds at most one variable */
+    if (!(env = calloc(envc + (size_t)*argc + 1, sizeof(char*))))
+        _err("out of memory");
+
+    memcpy(env, envp, envc * sizeof(char*));
+
+    while (cli_getopt(argc, argv, "--env", &arg) == 0)
+    {
+        const char* eq = strchr(arg, '=');
+        size_t len;
+        size_t i;
+
+        if (!eq || eq == arg)
+            _err("--env %s -- expected NAME=VALUE", arg);
+
+        len = (size_t)(eq - arg) + 1;
+
+        for (i = 0; i < envc; i++)
+        {
+            if (strncmp(env[i], arg, len) == 0)
+                break;
+        }
+
+        env[i] = arg;
+
+        if (i == envc)
+            envc++;
+    }
+
+    return env;
+}
@@ -35,4 +35,8 @@ int cli_getopt(
     const char* opt,
     const char** optarg);
 
+// remove every "--env NAME=VALUE" option from argv[] and return a copy of
+// envp[] with those variables set (the caller frees the array)
+const char** cli_getopt_env(int* argc, const char* argv[], const char* envp[]);
+
 #endif /* _HOST_MYST_UTILS_H */
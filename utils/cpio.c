@@ -17,6 +17,8 @@
 
 #include <myst/cpio.h>
 #include <myst/eraise.h>
+#include <myst/lsr.h>
+#include <myst/paths.h>
 #include <myst/round.h>
 #include <myst/strarr.h>
 #include <myst/strings.h>
@@ -1114,6 +1116,463 @@ done:
     return ret;
 }
 
+/*
+**==============================================================================
+**
+** layers:
+**
+** A layer is a CPIO archive that changes a tree: each of its directories,
+** files and symbolic links is added to the tree (replacing what has that
+** name), and each whiteout (an empty file named MYST_CPIO_WHITEOUT_PREFIX
+** followed by a name, as in OCI images) removes that name from the tree.
+**
+**==============================================================================
+*/
+
+/* whether the regular files have the same contents */
+static bool _same_contents(const char* path1, const char* path2, size_t size)
+{
+    bool ret = false;
+    int fd1 = -1;
+    int fd2 = -1;
+    char buf1[4096];
+    char buf2[4096];
+
+    if ((fd1 = open(path1, O_RDONLY)) < 0 || (fd2 = open(path2, O_RDONLY)) < 0)
+        GOTO(done);
+
+    while (size > 0)
+    {
+        size_t count = size < sizeof(buf1) ? size : sizeof(buf1);
+
+        if (read(fd1, buf1, count) != (ssize_t)count ||
+            read(fd2, buf2, count) != (ssize_t)count)
+        {
+            GOTO(done);
+        }
+
+        if (memcmp(buf1, buf2, count) != 0)
+            GOTO(done);
+
+        size -= count;
+    }
+
+    ret = true;
+
+done:
+
+    if (fd1 >= 0)
+        close(fd1);
+
+    if (fd2 >= 0)
+        close(fd2);
+
+    return ret;
+}
+
+/* whether the entry in the update differs from the one in the base */
+static bool _changed(
+    const char* base,
+    const struct stat* base_st,
+    const char* update,
+    const struct stat* st)
+{
+    if ((st->st_mode & S_IFMT) != (base_st->st_mode & S_IFMT))
+        return true;
+
+    if (S_ISDIR(st->st_mode))
+        return false;
+
+    if (st->st_mode != base_st->st_mode)
+        return true;
+
+    if (S_ISREG(st->st_mode))
+    {
+        return st->st_size != base_st->st_size ||
+               !_same_contents(base, update, (size_t)st->st_size);
+    }
+
+    if (S_ISLNK(st->st_mode))
+    {
+        char target1[PATH_MAX];
+        char target2[PATH_MAX];
+        ssize_t n1 = readlink(base, target1, sizeof(target1));
+        ssize_t n2 = readlink(update, target2, sizeof(target2));
+
+        return n1 < 0 || n1 != n2 || memcmp(target1, target2, (size_t)n1);
+    }
+
+    return true;
+}
+
+static int _append_whiteout(
+    myst_cpio_t* cpio,
+    const char* rel,
+    const char* name)
+{
+    int ret = -1;
+    myst_cpio_entry_t ent;
+
+    memset(&ent, 0, sizeof(ent));
+    ent.mode = MYST_CPIO_MODE_IFREG;
+
+    if (*rel)
+    {
+        MYST_STRLCPY(ent.name, rel);
+        MYST_STRLCAT(ent.name, "/");
+    }
+
+    MYST_STRLCAT(ent.name, MYST_CPIO_WHITEOUT_PREFIX);
+
+    if (MYST_STRLCAT(ent.name, name) >= sizeof(ent.name))
+        GOTO(done);
+
+    if (myst_cpio_write_entry(cpio, &ent) != 0 ||
+        myst_cpio_write_data(cpio, NULL, 0) != 0)
+    {
+        GOTO(done);
+    }
+
+    ret = 0;
+
+done:
+    return ret;
+}
+
+/* append what changed from the base directory to the update directory
+ * (rel is the path of both below the top directories) */
+static int _make_layer(
+    myst_cpio_t* cpio,
+    const char* base,
+    const char* update,
+    const char* rel)
+{
+    int ret = -1;
+    DIR* dir = NULL;
+    struct dirent* ent;
+    char path[MYST_CPIO_PATH_MAX];
+    char base_path[MYST_CPIO_PATH_MAX];
+    char name[MYST_CPIO_PATH_MAX];
+    struct stat st;
+    struct stat base_st;
+
+    /* add what was added or changed */
+    if (!(dir = opendir(update)))
+        GOTO(done);
+
+    while ((ent = readdir(dir)))
+    {
+        bool in_base;
+
+        if (strcmp(ent->d_name, ".") == 0 || strcmp(ent->d_name, "..") == 0)
+            continue;
+
+        if (strncmp(
+                ent->d_name,
+                MYST_CPIO_WHITEOUT_PREFIX,
+                sizeof(MYST_CPIO_WHITEOUT_PREFIX) - 1) == 0)
+        {
+            PRINTF("*** cpio: a layer cannot contain %s\n", ent->d_name);
+            GOTO(done);
+        }
+
+        MYST_STRLCPY(path, update);
+        MYST_STRLCAT(path, "/");
+        MYST_STRLCAT(path, ent->d_name);
+        MYST_STRLCPY(base_path, base);
+        MYST_STRLCAT(base_path, "/");
+        MYST_STRLCAT(base_path, ent->d_name);
+        *name = '\0';
+
+        if (*rel)
+        {
+            MYST_STRLCAT(name, rel);
+            MYST_STRLCAT(name, "/");
+        }
+
+        if (MYST_STRLCAT(name, ent->d_name) >= sizeof(name))
+            GOTO(done);
+
+        if (lstat(path, &st) != 0)
+            GOTO(done);
+
+        in_base = lstat(base_path, &base_st) == 0;
+
+        if (!in_base || _changed(base_path, &base_st, path, &st))
+        {
+            if (_append_file(cpio, path, name) != 0)
+                GOTO(done);
+        }
+
+        /* the children of a new directory are all new (as base_path then
+         * does not name a directory) */
+        if (S_ISDIR(st.st_mode) &&
+            _make_layer(cpio, base_path, path, name) != 0)
+        {
+            GOTO(done);
+        }
+    }
+
+    closedir(dir);
+    dir = NULL;
+
+    /* add a whiteout for what was removed */
+    if (lstat(base, &base_st) != 0 || !S_ISDIR(base_st.st_mode))
+    {
+        ret = 0;
+        goto done;
+    }
+
+    if (!(dir = opendir(base)))
+        GOTO(done);
+
+    while ((ent = readdir(dir)))
+    {
+        if (strcmp(ent->d_name, ".") == 0 || strcmp(ent->d_name, "..") == 0)
+            continue;
+
+        MYST_STRLCPY(path, update);
+        MYST_STRLCAT(path, "/");
+        MYST_STRLCAT(path, ent->d_name);
+
+        if (lstat(path, &st) != 0 && _append_whiteout(cpio, rel, ent->d_name))
+            GOTO(done);
+    }
+
+    ret = 0;
+
+done:
+
+    if (dir)
+        closedir(dir);
+
+    return ret;
+}
+
+int myst_cpio_make_layer(
+    const char* base,
+    const char* update,
+    const char* target)
+{
+    int ret = -1;
+    myst_cpio_t* cpio = NULL;
+
+    if (!base || !update || !target)
+        GOTO(done);
+
+    if (!(cpio = myst_cpio_open(target, MYST_CPIO_FLAG_CREATE)))
+        GOTO(done);
+
+    if (_make_layer(cpio, base, update, "") != 0)
+        GOTO(done);
+
+    ret = 0;
+
+done:
+
+    if (cpio)
+        myst_cpio_close(cpio);
+
+    return ret;
+}
+
+/* whether the name stays below the directory (with no ".." in it) */
+static bool _is_relative_name(const char* name)
+{
+    const char* p = name;
+
+    if (*name == '/')
+        return false;
+
+    while (*p)
+    {
+        const char* end = strchr(p, '/');
+        size_t len = end ? (size_t)(end - p) : strlen(p);
+
+        if (len == 2 && p[0] == '.' && p[1] == '.')
+            return false;
+
+        p += len;
+
+        if (*p == '/')
+            p++;
+    }
+
+    return true;
+}
+
+/* remove the file, symbolic link or directory tree (if there is one) */
+static int _remove(const char* path)
+{
+    int ret = -1;
+    struct stat st;
+    myst_strarr_t paths = MYST_STRARR_INITIALIZER;
+
+    if (lstat(path, &st) != 0)
+    {
+        ret = 0;
+        goto done;
+    }
+
+    if (!S_ISDIR(st.st_mode))
+    {
+        if (unlink(path) != 0)
+            GOTO(done);
+
+        ret = 0;
+        goto done;
+    }
+
+    /* a directory comes before what is in it, so remove them in reverse */
+    if (myst_lsr(path, &paths, true) != 0)
+        GOTO(done);
+
+    for (size_t i = paths.size; i > 0; i--)
+    {
+        const char* p = paths.data[i - 1];
+
+        if (lstat(p, &st) != 0)
+            GOTO(done);
+
+        if ((S_ISDIR(st.st_mode) ? rmdir(p) : unlink(p)) != 0)
+            GOTO(done);
+    }
+
+    if (rmdir(path) != 0)
+        GOTO(done);
+
+    ret = 0;
+
+done:
+
+    myst_strarr_release(&paths);
+
+    return ret;
+}
+
+int myst_cpio_mem_apply_layer(
+    const void* cpio_data,
+    size_t cpio_size,
+    const char* target)
+{
+    int ret = -1;
+    char path[MYST_CPIO_PATH_MAX];
+    size_t pos = 0;
+    int fd = -1;
+
+    if (!cpio_data || !target)
+        GOTO(done);
+
+    for (;;)
+    {
+        myst_cpio_entry_t ent;
+        const void* file_data;
+        const char* base;
+        struct stat st;
+        int r;
+
+        if ((r = myst_cpio_next_entry(
+                 cpio_data, cpio_size, &pos, &ent, &file_data)) == 0)
+        {
+            break;
+        }
+
+        if (r < 0)
+            GOTO(done);
+
+        if (strcmp(ent.name, ".") == 0)
+            continue;
+
+        if (!_is_relative_name(ent.name))
+        {
+            PRINTF("*** cpio: bad name in layer: %s\n", ent.name);
+            GOTO(done);
+        }
+
+        MYST_STRLCPY(path, target);
+
+        if (path[strlen(path) - 1] != '/')
+            MYST_STRLCAT(path, "/");
+
+        if (MYST_STRLCAT(path, ent.name) >= sizeof(path))
+            GOTO(done);
+
+        base = myst_basename(path);
+
+        /* a whiteout: remove the name that follows the prefix */
+        if (strncmp(
+                base,
+                MYST_CPIO_WHITEOUT_PREFIX,
+                sizeof(MYST_CPIO_WHITEOUT_PREFIX) - 1) == 0)
+        {
+            const char* name = base + sizeof(MYST_CPIO_WHITEOUT_PREFIX) - 1;
+            char victim[MYST_CPIO_PATH_MAX];
+
+            MYST_STRLCPY(victim, path);
+            victim[base - path] = '\0';
+            MYST_STRLCAT(victim, name);
+
+            if (!*name || _remove(victim) != 0)
+                GOTO(done);
+
+            continue;
+        }
+
+        /* replace what has the name unless both are directories */
+        if (lstat(path, &st) == 0 &&
+            !(S_ISDIR(st.st_mode) && S_ISDIR(ent.mode)) && _remove(path) != 0)
+        {
+            GOTO(done);
+        }
+
+        if (S_ISDIR(ent.mode))
+        {
+            if (lstat(path, &st) != 0 && mkdir(path, ent.mode & 07777) != 0)
+                GOTO(done);
+        }
+        else if (S_ISREG(ent.mode))
+        {
+            const int flags = O_WRONLY | O_CREAT | O_TRUNC;
+            ssize_t n = (ssize_t)ent.size;
+
+            if ((fd = open(path, flags, ent.mode & 07777)) < 0)
+                GOTO(done);
+
+            if (n && write(fd, file_data, (size_t)n) != n)
+                GOTO(done);
+
+            close(fd);
+            fd = -1;
+        }
+        else if (S_ISLNK(ent.mode))
+        {
+            char link[PATH_MAX];
+
+            if (ent.size < 1 || ent.size >= sizeof(link))
+                GOTO(done);
+
+            memcpy(link, file_data, ent.size);
+            link[ent.size] = '\0';
+
+            if (symlink(link, path) != 0)
+                GOTO(done);
+        }
+        else
+        {
+            GOTO(done);
+        }
+    }
+
+    ret = 0;
+
+done:
+
+    if (fd >= 0)
+        close(fd);
+
+    return ret;
+}
+
 int myst_cpio_test(const char* path)
 {
     int ret = 0;
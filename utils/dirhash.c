@@ -0,0 +1,95 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include <errno.h>
+#include <stdint.h>
+#include <string.h>
+#include <sys/stat.h>
+
+#include <myst/dirhash.h>
+#include <myst/eraise.h>
+
+/* a record is the type, the permission bits (32-bit little endian) and the
+ * path with its terminator; then the size (64-bit little endian) of a file or
+ * the target of a symbolic link with its terminator */
+static int _add_header(
+    myst_sha256_ctx_t* ctx,
+    char type,
+    const char* path,
+    mode_t mode)
+{
+    int ret = 0;
+    uint8_t buf[5];
+    const uint32_t perms = mode & 07777;
+
+    if (!ctx || !path)
+        ERAISE(-EINVAL);
+
+    buf[0] = (uint8_t)type;
+
+    for (size_t i = 0; i < 4; i++)
+        buf[1 + i] = (uint8_t)(perms >> (8 * i));
+
+    ECHECK(myst_sha256_update(ctx, buf, sizeof(buf)));
+    ECHECK(myst_sha256_update(ctx, path, strlen(path) + 1));
+
+done:
+    return ret;
+}
+
+int myst_dirhash_dir(myst_sha256_ctx_t* ctx, const char* path, mode_t mode)
+{
+    return _add_header(ctx, 'd', path, mode);
+}
+
+int myst_dirhash_file(
+    myst_sha256_ctx_t* ctx,
+    const char* path,
+    mode_t mode,
+    size_t size)
+{
+    int ret = 0;
+    uint8_t buf[8];
+
+    ECHECK(_add_header(ctx, 'f', path, mode));
+
+    for (size_t i = 0; i < sizeof(buf); i++)
+        buf[i] = (uint8_t)((uint64_t)size >> (8 * i));
+
+    ECHECK(myst_sha256_update(ctx, buf, sizeof(buf)));
+
+done:
+    return ret;
+}
+
+int myst_dirhash_symlink(
+    myst_sha256_ctx_t* ctx,
+    const char* path,
+    const char* target)
+{
+    int ret = 0;
+
+    if (!target)
+        ERAISE(-EINVAL);
+
+    ECHECK(_add_header(ctx, 'l', path, 0777));
+    ECHECK(myst_sha256_update(ctx, target, strlen(target) + 1));
+
+done:
+    return ret;
+}
+
+void myst_dirhash_sort(char** names, size_t count)
+{
+    /* directories are small enough for an insertion sort */
+    for (size_t i = 1; i < count; i++)
+    {
+        char* name = names[i];
+        size_t j = i;
+
+        for (; j > 0 && strcmp(names[j - 1], name) > 0; j--)
+            names[j] = names[j - 1];
+
+        names[j] = name;
+    }
+}
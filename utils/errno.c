@@ -4,6 +4,7 @@
 #include <myst/errno.h>
 #include <stddef.h>
 #include <stdio.h>
+#include <string.h>
 
 typedef struct pair
 {
@@ -160,3 +161,14 @@ const char* myst_error_name(long errnum)
 
     return NULL;
 }
+
+long myst_error_num(const char* name)
+{
+    for (size_t i = 0; name && i < _npairs; i++)
+    {
+        if (strcmp(_pairs[i].errstr, name) == 0)
+            return _pairs[i].errnum;
+    }
+
+    return 0;
+}